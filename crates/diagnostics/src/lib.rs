@@ -3,6 +3,22 @@ use std::{
     ops::Range,
 };
 
+use serde::Serialize;
+
+mod plain;
+mod position;
+mod sarif;
+#[cfg(test)]
+mod tests;
+
+pub use plain::reports_to_plain;
+pub use position::{
+    ColumnUnit,
+    Position,
+    SourceMap,
+};
+pub use sarif::reports_to_sarif;
+
 pub type Span = Range<usize>;
 
 pub use yansi::{
@@ -14,7 +30,8 @@ pub fn disable_pretty_print() {
     yansi::disable();
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ErrorType {
     Lexer,
     Parser,
@@ -22,6 +39,9 @@ pub enum ErrorType {
     Type,
     Verification,
     Emit,
+    Runtime,
+    /// Invariant of the compiler has been violated.
+    Internal,
 }
 
 impl Display for ErrorType {
@@ -34,11 +54,15 @@ impl Display for ErrorType {
             ErrorType::Type => word("Type error"),
             ErrorType::Verification => word("Verification error"),
             ErrorType::Emit => word("Emitter error"),
+            ErrorType::Runtime => word("Runtime error"),
+            ErrorType::Internal => word("Internal error"),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Severity of the report, ordered from the least to the most severe.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Level {
     Info,
     Warning,
@@ -56,7 +80,7 @@ impl<'a> From<Level> for ariadne::ReportKind<'a> {
 }
 
 /// Error report.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Report {
     /// Location of an error
     pub loc: Span,
@@ -70,9 +94,21 @@ pub struct Report {
     pub additional_info: Vec<Report>,
     /// Helping note for the message.
     pub note: String,
+    /// Path to the file the location is relative to.
+    ///
+    /// `None` if the location is in the combined source of the contract.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
 }
 
 impl Report {
+    /// Serialize the report into a JSON object.
+    ///
+    /// Spans are represented as `{ "start": usize, "end": usize }` byte offsets.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("report is always serializable")
+    }
+
     /// Build a report from the lexer error.
     pub fn lexer_error(loc: Span, message: String) -> Self {
         Self {
@@ -82,6 +118,7 @@ impl Report {
             message,
             additional_info: vec![],
             note: String::from("Consider changing structure to adhere to language grammar."),
+            file: None,
         }
     }
 
@@ -94,6 +131,7 @@ impl Report {
             message,
             additional_info: vec![],
             note: String::from("Consider changing structure to adhere to language grammar."),
+            file: None,
         }
     }
 
@@ -106,6 +144,7 @@ impl Report {
             message,
             additional_info: vec![],
             note: String::from("Consider changing structure to adhere to language grammar."),
+            file: None,
         }
     }
 
@@ -118,6 +157,7 @@ impl Report {
             message,
             additional_info: vec![],
             note: String::from("Consider rewriting the code block to reduce syntactical overhead."),
+            file: None,
         }
     }
 
@@ -130,6 +170,7 @@ impl Report {
             message,
             additional_info: vec![],
             note: String::from("Consider rewriting the expression to match the types."),
+            file: None,
         }
     }
 
@@ -142,6 +183,20 @@ impl Report {
             message,
             additional_info: vec![],
             note: String::from("Consider reviewing syntax usage."),
+            file: None,
+        }
+    }
+
+    /// Build a report from the verification warning.
+    pub fn ver_warning(loc: Span, message: String) -> Self {
+        Self {
+            loc,
+            error_type: ErrorType::Verification,
+            level: Level::Warning,
+            message,
+            additional_info: vec![],
+            note: String::from("Consider reviewing the constraints of the contract."),
+            file: None,
         }
     }
 
@@ -159,6 +214,7 @@ impl Report {
             message,
             additional_info: errs,
             note,
+            file: None,
         }
     }
 
@@ -171,6 +227,53 @@ impl Report {
             message,
             additional_info: vec![],
             note: String::from("Consider semantically checking the code first."),
+            file: None,
+        }
+    }
+
+    /// Build a report from the emitter warning.
+    pub fn emit_warning(loc: Span, message: String) -> Self {
+        Self {
+            loc,
+            error_type: ErrorType::Emit,
+            level: Level::Warning,
+            message,
+            additional_info: vec![],
+            note: String::from("Consider reducing the storage used by the contract."),
+            file: None,
+        }
+    }
+
+    /// Build a report from the error of the interpreted code.
+    pub fn runtime_error(loc: Span, message: String) -> Self {
+        Self {
+            loc,
+            error_type: ErrorType::Runtime,
+            level: Level::Error,
+            message,
+            additional_info: vec![],
+            note: String::from("Consider checking the values the expression is evaluated with."),
+            file: None,
         }
     }
+
+    /// Build a report from the violated invariant of the compiler.
+    pub fn internal_error(loc: Span, message: String) -> Self {
+        Self {
+            loc,
+            error_type: ErrorType::Internal,
+            level: Level::Error,
+            message,
+            additional_info: vec![],
+            note: String::from(
+                "This is a bug in the compiler, consider reporting it at https://github.com/SkymanOne/folidity/issues.",
+            ),
+            file: None,
+        }
+    }
+}
+
+/// Serialize the list of reports into a JSON array.
+pub fn reports_to_json(reports: &[Report]) -> String {
+    serde_json::to_string(reports).expect("report is always serializable")
 }
@@ -0,0 +1,65 @@
+//! Serialization of the reports into the plain single-line messages.
+//!
+//! Every report is printed as `file:line:col: level[type]: message`, followed by
+//! `file:line:col: note: message` lines of its notes. The output is stable,
+//! so that it can be matched by `grep` and the problem matchers of the CI.
+
+use crate::{
+    sarif::rule_id,
+    ColumnUnit,
+    Level,
+    Paint,
+    Report,
+    SourceMap,
+};
+
+/// Serialize the list of reports into the plain messages, one per line.
+///
+/// `sources` are the paths and contents of the files. The locations of the reports
+/// without [`Report::file`] are in `file_name`.
+pub fn reports_to_plain(reports: &[Report], file_name: &str, sources: &[(String, &str)]) -> String {
+    let maps: Vec<SourceMap> = sources.iter().map(|(_, c)| SourceMap::new(c)).collect();
+    let position = |r: &Report| {
+        let file = r.file.as_deref().unwrap_or(file_name);
+        let pos = sources
+            .iter()
+            .position(|(name, _)| name == file)
+            .map(|i| maps[i].position(sources[i].1, r.loc.start, ColumnUnit::Char))
+            .unwrap_or_default();
+        format!("{}:{}:{}", file, pos.line + 1, pos.column + 1)
+    };
+
+    let mut lines = vec![];
+    for r in reports {
+        let level = match r.level {
+            Level::Info => "info".blue().bold(),
+            Level::Warning => "warning".yellow().bold(),
+            Level::Error => "error".red().bold(),
+        };
+        lines.push(format!(
+            "{}: {}[{}]: {}",
+            position(r),
+            level,
+            rule_id(r),
+            single_line(&r.message)
+        ));
+        for note in &r.additional_info {
+            lines.push(format!(
+                "{}: {}: {}",
+                position(note),
+                "note".cyan().bold(),
+                single_line(&note.message)
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Join the lines of the message.
+fn single_line(message: &str) -> String {
+    message
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
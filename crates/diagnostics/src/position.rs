@@ -0,0 +1,109 @@
+//! Mapping between the byte offsets of the spans and the line-column positions.
+
+use crate::Span;
+
+/// Unit the columns of the positions are counted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnUnit {
+    /// Bytes of the UTF-8 encoding.
+    Byte,
+    /// Unicode scalar values.
+    Char,
+    /// Code units of the UTF-16 encoding, as used by LSP and SARIF.
+    Utf16,
+}
+
+impl ColumnUnit {
+    /// Width of the character in the unit.
+    fn width(&self, c: char) -> usize {
+        match self {
+            ColumnUnit::Byte => c.len_utf8(),
+            ColumnUnit::Char => 1,
+            ColumnUnit::Utf16 => c.len_utf16(),
+        }
+    }
+}
+
+/// Position in the source, both line and column are counted from zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Line starts of the source, precomputed to map the spans onto the positions.
+///
+/// The source itself is not kept, so the map can be stored next to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMap {
+    /// Byte offsets of the line starts.
+    line_starts: Vec<usize>,
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        Self {
+            line_starts: vec![0],
+        }
+    }
+}
+
+impl SourceMap {
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        Self { line_starts }
+    }
+
+    /// Number of the lines in the source.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Convert the byte offset into the position in the text.
+    ///
+    /// Offsets past the end of the text are clamped to it.
+    pub fn position(&self, text: &str, offset: usize, unit: ColumnUnit) -> Position {
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|s| *s <= offset) - 1;
+        let start = self.line_starts[line];
+        let column = text
+            .get(start..offset)
+            .map_or(0, |s| s.chars().map(|c| unit.width(c)).sum());
+        Position { line, column }
+    }
+
+    /// Convert the span into the positions of its start and end.
+    pub fn span_to_line_col(
+        &self,
+        text: &str,
+        span: &Span,
+        unit: ColumnUnit,
+    ) -> (Position, Position) {
+        (
+            self.position(text, span.start, unit),
+            self.position(text, span.end, unit),
+        )
+    }
+
+    /// Convert the position in the text into the byte offset.
+    ///
+    /// Columns past the end of the line are clamped to it,
+    /// and lines past the end of the text to the end of the text.
+    pub fn offset(&self, text: &str, position: Position, unit: ColumnUnit) -> usize {
+        let Some(start) = self.line_starts.get(position.line) else {
+            return text.len();
+        };
+        let mut column = 0;
+        for (i, c) in text[*start..].char_indices() {
+            if column >= position.column || c == '\n' {
+                return start + i;
+            }
+            column += unit.width(c);
+        }
+        text.len()
+    }
+}
@@ -0,0 +1,119 @@
+//! Serialization of the reports into the [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) log.
+
+use serde_json::{
+    json,
+    Value,
+};
+
+use crate::{
+    ColumnUnit,
+    Level,
+    Report,
+    SourceMap,
+    Span,
+};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Serialize the list of reports into the SARIF log with a single run.
+///
+/// `sources` are the paths and contents of the files. The locations of the reports
+/// without [`Report::file`] are in `file_name`.
+///
+/// Regions contain both line-column positions and byte offsets of the spans.
+pub fn reports_to_sarif(reports: &[Report], file_name: &str, sources: &[(String, &str)]) -> String {
+    let maps: Vec<SourceMap> = sources.iter().map(|(_, c)| SourceMap::new(c)).collect();
+    let location = |r: &Report| {
+        let file = r.file.as_deref().unwrap_or(file_name);
+        match sources.iter().position(|(name, _)| name == file) {
+            Some(i) => location(&r.loc, file, sources[i].1, &maps[i]),
+            None => location(&r.loc, file, "", &SourceMap::default()),
+        }
+    };
+
+    let mut rules: Vec<String> = reports.iter().map(rule_id).collect();
+    rules.sort();
+    rules.dedup();
+
+    let results: Vec<Value> = reports
+        .iter()
+        .map(|r| {
+            let related: Vec<Value> = r
+                .additional_info
+                .iter()
+                .enumerate()
+                .map(|(i, ra)| {
+                    let mut location = location(ra);
+                    location["id"] = json!(i);
+                    location["message"] = json!({ "text": ra.message });
+                    location
+                })
+                .collect();
+
+            let text = if r.note.is_empty() {
+                r.message.clone()
+            } else {
+                format!("{}\nNote: {}", r.message, r.note)
+            };
+
+            json!({
+                "ruleId": rule_id(r),
+                "level": level(&r.level),
+                "message": { "text": text },
+                "locations": [location(r)],
+                "relatedLocations": related,
+            })
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "folidity",
+                    "informationUri": "https://github.com/SkymanOne/folidity",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<Value>>(),
+                }
+            },
+            "results": results,
+        }]
+    });
+
+    serde_json::to_string_pretty(&log).expect("log is always serializable")
+}
+
+pub(crate) fn rule_id(report: &Report) -> String {
+    serde_json::to_value(&report.error_type)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+fn level(level: &Level) -> &'static str {
+    match level {
+        Level::Info => "note",
+        Level::Warning => "warning",
+        Level::Error => "error",
+    }
+}
+
+fn location(loc: &Span, file_name: &str, content: &str, map: &SourceMap) -> Value {
+    // columns are counted in UTF-16 code units as SARIF requires by default.
+    let (start, end) = map.span_to_line_col(content, loc, ColumnUnit::Utf16);
+    json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": file_name },
+            "region": {
+                "startLine": start.line + 1,
+                "startColumn": start.column + 1,
+                "endLine": end.line + 1,
+                "endColumn": end.column + 1,
+                "byteOffset": loc.start,
+                "byteLength": loc.end.saturating_sub(loc.start),
+            }
+        }
+    })
+}
@@ -0,0 +1,247 @@
+use serde_json::{
+    json,
+    Value,
+};
+
+use crate::{
+    disable_pretty_print,
+    reports_to_json,
+    reports_to_plain,
+    reports_to_sarif,
+    ColumnUnit,
+    Position,
+    Report,
+    SourceMap,
+};
+
+/// `é` takes 2 bytes and 1 UTF-16 unit, `𝄞` takes 4 bytes and 2 UTF-16 units.
+const SOURCE: &str = "let é = \"𝄞x\";\r\nmax\n";
+
+fn pos(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+#[test]
+fn test_position_columns() {
+    let map = SourceMap::new(SOURCE);
+    assert_eq!(map.line_count(), 3);
+
+    // `x` follows 10 characters of the line.
+    assert_eq!(map.position(SOURCE, 14, ColumnUnit::Byte), pos(0, 14));
+    assert_eq!(map.position(SOURCE, 14, ColumnUnit::Char), pos(0, 10));
+    assert_eq!(map.position(SOURCE, 14, ColumnUnit::Utf16), pos(0, 11));
+
+    // the offset inside of the character is moved to its start.
+    assert_eq!(map.position(SOURCE, 11, ColumnUnit::Utf16), pos(0, 9));
+    assert_eq!(map.position(SOURCE, 13, ColumnUnit::Char), pos(0, 9));
+}
+
+#[test]
+fn test_position_lines() {
+    let map = SourceMap::new(SOURCE);
+
+    // `\r` is the last column of its line.
+    assert_eq!(map.position(SOURCE, 17, ColumnUnit::Utf16), pos(0, 14));
+    assert_eq!(map.position(SOURCE, 18, ColumnUnit::Utf16), pos(0, 15));
+    assert_eq!(map.position(SOURCE, 19, ColumnUnit::Utf16), pos(1, 0));
+
+    // the span of `x";\r\nma` covers two lines.
+    let (start, end) = map.span_to_line_col(SOURCE, &(14..21), ColumnUnit::Utf16);
+    assert_eq!((start, end), (pos(0, 11), pos(1, 2)));
+
+    // the offsets past the end of the text are clamped to it.
+    assert_eq!(
+        map.position(SOURCE, SOURCE.len(), ColumnUnit::Char),
+        pos(2, 0)
+    );
+    assert_eq!(map.position(SOURCE, 100, ColumnUnit::Char), pos(2, 0));
+    assert_eq!(map.position("", 5, ColumnUnit::Char), pos(0, 0));
+}
+
+#[test]
+fn test_position_offset() {
+    let map = SourceMap::new(SOURCE);
+
+    // every character boundary is mapped back onto itself.
+    for unit in [ColumnUnit::Byte, ColumnUnit::Char, ColumnUnit::Utf16] {
+        for (offset, _) in SOURCE.char_indices().chain([(SOURCE.len(), ' ')]) {
+            let position = map.position(SOURCE, offset, unit);
+            assert_eq!(map.offset(SOURCE, position, unit), offset, "{:?}", unit);
+        }
+    }
+
+    // columns past the end of the line are clamped to the line break,
+    // and lines past the end of the text to its end.
+    assert_eq!(map.offset(SOURCE, pos(0, 40), ColumnUnit::Utf16), 18);
+    assert_eq!(map.offset(SOURCE, pos(1, 40), ColumnUnit::Utf16), 22);
+    assert_eq!(
+        map.offset(SOURCE, pos(7, 0), ColumnUnit::Utf16),
+        SOURCE.len()
+    );
+}
+
+const CONTRACT: &str = "fn () init() {\n    let é = 5;\n}\n";
+const MODULE: &str = "struct Pair {\n    a: int,\n}\n";
+
+fn sources() -> Vec<(String, &'static str)> {
+    vec![
+        (String::from("contract.fol"), CONTRACT),
+        (String::from("lib/pair.fol"), MODULE),
+    ]
+}
+
+#[test]
+fn test_plain_report() {
+    disable_pretty_print();
+    let report = Report::semantic_warning(23..25, String::from("Variable `é` is never used."));
+
+    let plain = reports_to_plain(&[report], "contract.fol", &sources());
+    assert_eq!(
+        plain,
+        "contract.fol:2:9: warning[semantics]: Variable `é` is never used."
+    );
+}
+
+#[test]
+fn test_plain_related_locations() {
+    disable_pretty_print();
+    let mut note = Report::type_error(18..19, String::from("Field `a` is declared here."));
+    note.file = Some(String::from("lib/pair.fol"));
+    let report = Report::ver_error_with_extra(
+        28..29,
+        String::from("Constraint does not hold\n  for some inputs."),
+        vec![note, Report::ver_error(4..8, String::from("Called here."))],
+        String::new(),
+    );
+    let other = Report::parser_error(0, 2, String::from("Unexpected token."));
+
+    let plain = reports_to_plain(&[report, other], "contract.fol", &sources());
+    assert_eq!(
+        plain,
+        [
+            "contract.fol:2:13: error[verification]: Constraint does not hold for some inputs.",
+            "lib/pair.fol:2:5: note: Field `a` is declared here.",
+            "contract.fol:1:5: note: Called here.",
+            "contract.fol:1:1: error[parser]: Unexpected token.",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_sarif_log() {
+    disable_pretty_print();
+    let sources = vec![
+        (String::from("main.fol"), SOURCE),
+        (String::from("lib/pair.fol"), MODULE),
+    ];
+    let mut note = Report::type_error(18..19, String::from("Field `a` is declared here."));
+    note.file = Some(String::from("lib/pair.fol"));
+    let reports = [
+        Report::ver_error_with_extra(
+            14..21,
+            String::from("Constraint does not hold."),
+            vec![note],
+            String::from("Consider constraining the inputs."),
+        ),
+        Report::semantic_warning(4..6, String::from("Variable `é` is never used.")),
+        Report::ver_warning(19..22, String::from("State is unreachable.")),
+    ];
+
+    let sarif = reports_to_sarif(&reports, "main.fol", &sources);
+    let log: Value = serde_json::from_str(&sarif).unwrap();
+    assert_eq!(
+        log["$schema"],
+        "https://json.schemastore.org/sarif-2.1.0.json"
+    );
+    assert_eq!(log["version"], "2.1.0");
+
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "folidity");
+    // the rules are the sorted distinct types of the reports.
+    assert_eq!(
+        run["tool"]["driver"]["rules"],
+        json!([{ "id": "semantics" }, { "id": "verification" }])
+    );
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["ruleId"], "verification");
+    assert_eq!(results[0]["level"], "error");
+    assert_eq!(
+        results[0]["message"]["text"],
+        "Constraint does not hold.\nNote: Consider constraining the inputs."
+    );
+    assert_eq!(results[1]["level"], "warning");
+
+    // the columns are counted in UTF-16 units, `𝄞` takes two of them.
+    assert_eq!(
+        results[0]["locations"][0]["physicalLocation"],
+        json!({
+            "artifactLocation": { "uri": "main.fol" },
+            "region": {
+                "startLine": 1,
+                "startColumn": 12,
+                "endLine": 2,
+                "endColumn": 3,
+                "byteOffset": 14,
+                "byteLength": 7,
+            }
+        })
+    );
+
+    // the notes are the related locations in their own files.
+    assert_eq!(
+        results[0]["relatedLocations"],
+        json!([{
+            "id": 0,
+            "message": { "text": "Field `a` is declared here." },
+            "physicalLocation": {
+                "artifactLocation": { "uri": "lib/pair.fol" },
+                "region": {
+                    "startLine": 2,
+                    "startColumn": 5,
+                    "endLine": 2,
+                    "endColumn": 6,
+                    "byteOffset": 18,
+                    "byteLength": 1,
+                }
+            }
+        }])
+    );
+    assert_eq!(results[1]["relatedLocations"], json!([]));
+}
+
+#[test]
+fn test_json_reports() {
+    let mut runtime = Report::runtime_error(3..5, String::from("Division by zero."));
+    runtime.file = Some(String::from("lib/math.fol"));
+    let missing = Report::ver_error_with_extra(
+        0..1,
+        String::from("Declaration is missing."),
+        vec![Report::internal_error(2..4, String::from("Declared here."))],
+        String::from("Consider reporting it."),
+    );
+
+    assert_eq!(
+        runtime.to_json(),
+        concat!(
+            r#"{"loc":{"start":3,"end":5},"error_type":"runtime","level":"error","#,
+            r#""message":"Division by zero.","additional_info":[],"#,
+            r#""note":"Consider checking the values the expression is evaluated with.","#,
+            r#""file":"lib/math.fol"}"#
+        )
+    );
+    // the reports without the file omit it.
+    assert_eq!(
+        reports_to_json(&[missing]),
+        concat!(
+            r#"[{"loc":{"start":0,"end":1},"error_type":"verification","level":"error","#,
+            r#""message":"Declaration is missing.","additional_info":[{"loc":{"start":2,"end":4},"#,
+            r#""error_type":"internal","level":"error","message":"Declared here.","additional_info":[],"#,
+            r#""note":"This is a bug in the compiler, consider reporting it at https://github.com/SkymanOne/folidity/issues."}],"#,
+            r#""note":"Consider reporting it."}]"#
+        )
+    );
+    assert_eq!(reports_to_json(&[]), "[]");
+}
@@ -0,0 +1,288 @@
+//! ARC-32 application specification of the compiled contract.
+//!
+//! The specification bundles approval and clear programs together with the
+//! description of callable functions, so the contract can be deployed and called
+//! by the standard Algorand tooling.
+//!
+//! The ARC-4 interface of the contract is also available on its own in [`ContractSpec`],
+//! so the SDKs can call the contract without the full specification.
+//!
+//! # Note
+//! Functions are dispatched by their name passed as the first application argument
+//! rather than by the ARC-4 method selector.
+
+use std::collections::BTreeMap;
+
+use base64::{
+    engine::general_purpose::STANDARD,
+    Engine,
+};
+use folidity_semantics::{
+    ast::{
+        Function,
+        FunctionVisibility,
+        TypeVariant,
+    },
+    ContractDefinition,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::teal::{
+    TealArtifacts,
+    STATE_KEY,
+};
+
+/// ARC-32 application specification.
+#[derive(Debug, Clone, Serialize)]
+pub struct AppSpec {
+    /// Call configurations of the methods indexed by their signatures.
+    pub hints: BTreeMap<String, MethodHints>,
+    /// Base64 encoded TEAL programs.
+    pub source: ProgramSource,
+    /// Number of the storage slots used by the application.
+    pub state: StateSchema,
+    /// Declared application state.
+    pub schema: Schema,
+    /// ARC-4 style description of the contract interface.
+    pub contract: ContractSpec,
+    /// Call configuration for the calls without the method.
+    pub bare_call_config: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodHints {
+    pub call_config: BTreeMap<String, String>,
+    /// The method does not modify the state, and can be called via simulation.
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgramSource {
+    pub approval: String,
+    pub clear: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateSchema {
+    pub global: SchemaSize,
+    pub local: SchemaSize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaSize {
+    pub num_byte_slices: u64,
+    pub num_uints: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Schema {
+    pub global: DeclaredSchema,
+    pub local: DeclaredSchema,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeclaredSchema {
+    pub declared: BTreeMap<String, DeclaredValue>,
+    pub reserved: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeclaredValue {
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub key: String,
+    pub descr: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractSpec {
+    pub name: String,
+    pub methods: Vec<MethodSpec>,
+    /// Events logged by the contract as of ARC-28.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<EventSpec>,
+    pub networks: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MethodSpec {
+    pub name: String,
+    pub args: Vec<ArgSpec>,
+    pub returns: ReturnSpec,
+    /// View functions do not modify the state, so they can be called via the read-only
+    /// simulation as of ARC-22.
+    pub readonly: bool,
+    /// Documentation of the function.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventSpec {
+    pub name: String,
+    pub args: Vec<ArgSpec>,
+    /// Documentation of the event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArgSpec {
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReturnSpec {
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+impl AppSpec {
+    /// Build the specification from the compiled contract.
+    pub fn new(name: &str, definition: &ContractDefinition, artifacts: &TealArtifacts) -> Self {
+        let mut hints = BTreeMap::new();
+
+        for (f, method) in definition.functions.iter().zip(&artifacts.methods) {
+            let call = if f.is_init { "CREATE" } else { "CALL" };
+            hints.insert(
+                method_signature(method),
+                MethodHints {
+                    call_config: BTreeMap::from([("no_op".to_string(), call.to_string())]),
+                    read_only: method.readonly,
+                },
+            );
+        }
+
+        // the index of the current state is the only global value.
+        let mut declared = BTreeMap::new();
+        if artifacts.init.schema.global.num_uints > 0 {
+            declared.insert(
+                STATE_KEY.to_string(),
+                DeclaredValue {
+                    ty: "uint64".to_string(),
+                    key: STATE_KEY.to_string(),
+                    descr: "Current state of the contract.".to_string(),
+                },
+            );
+        }
+
+        Self {
+            hints,
+            source: ProgramSource {
+                approval: STANDARD.encode(&artifacts.approval_bytes),
+                clear: STANDARD.encode(&artifacts.clear_bytes),
+            },
+            state: artifacts.init.schema.clone(),
+            schema: Schema {
+                global: DeclaredSchema {
+                    declared,
+                    reserved: BTreeMap::new(),
+                },
+                local: DeclaredSchema::default(),
+            },
+            contract: ContractSpec::new(name, artifacts),
+            bare_call_config: BTreeMap::new(),
+        }
+    }
+
+    /// Serialize the specification into the pretty JSON string.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("spec is always serializable")
+    }
+}
+
+impl ContractSpec {
+    /// Build the ARC-4 interface from the compiled contract.
+    pub fn new(name: &str, artifacts: &TealArtifacts) -> Self {
+        Self {
+            name: name.to_string(),
+            methods: artifacts.methods.clone(),
+            events: artifacts.events.clone(),
+            networks: BTreeMap::new(),
+        }
+    }
+
+    /// Serialize the interface into the pretty JSON string.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("interface is always serializable")
+    }
+}
+
+/// ARC-4 descriptions of the functions of the contract.
+pub fn abi_methods(definition: &ContractDefinition) -> Vec<MethodSpec> {
+    definition.functions.iter().map(method_spec).collect()
+}
+
+/// ARC-28 descriptions of the events of the contract.
+pub fn abi_events(definition: &ContractDefinition) -> Vec<EventSpec> {
+    definition
+        .events
+        .iter()
+        .map(|e| {
+            EventSpec {
+                name: e.name.name.clone(),
+                args: e
+                    .fields
+                    .iter()
+                    .map(|f| {
+                        ArgSpec {
+                            ty: abi_type(&f.ty.ty),
+                            name: f.name.name.clone(),
+                        }
+                    })
+                    .collect(),
+                desc: e.doc.clone(),
+            }
+        })
+        .collect()
+}
+
+fn method_spec(f: &Function) -> MethodSpec {
+    MethodSpec {
+        name: f.name.name.clone(),
+        args: f
+            .params
+            .iter()
+            .map(|(name, p)| {
+                ArgSpec {
+                    ty: abi_type(&p.ty.ty),
+                    name: name.clone(),
+                }
+            })
+            .collect(),
+        returns: ReturnSpec {
+            ty: abi_type(f.return_ty.ty()),
+        },
+        readonly: matches!(f.vis, FunctionVisibility::View(_)),
+        desc: f.doc.clone(),
+    }
+}
+
+/// ARC-4 signature of the method, e.g. `incr_by(byte[16])void`.
+fn method_signature(m: &MethodSpec) -> String {
+    let args: Vec<&str> = m.args.iter().map(|a| a.ty.as_str()).collect();
+    format!("{}({}){}", m.name, args.join(","), m.returns.ty)
+}
+
+/// Map the type to the closest ARC-4 type of its encoding in the emitted code.
+pub(crate) fn abi_type(ty: &TypeVariant) -> String {
+    match ty {
+        TypeVariant::Uint | TypeVariant::U64 | TypeVariant::Float | TypeVariant::Char => {
+            "uint64".to_string()
+        }
+        TypeVariant::U8 => "uint8".to_string(),
+        TypeVariant::U256 => "uint256".to_string(),
+        // signed integers are encoded as a sign block followed by the absolute value.
+        TypeVariant::Int | TypeVariant::I64 => "byte[16]".to_string(),
+        TypeVariant::Bool => "bool".to_string(),
+        TypeVariant::Address => "address".to_string(),
+        TypeVariant::String => "string".to_string(),
+        TypeVariant::Unit => "void".to_string(),
+        _ => "byte[]".to_string(),
+    }
+}
@@ -4,9 +4,12 @@ use derive_more::Display;
 use folidity_semantics::{
     ast::{
         Param,
+        Type,
         TypeVariant,
     },
     ContractDefinition,
+    Identifier,
+    Span,
 };
 
 /// Represents a constant literal in teal bytecode.
@@ -26,7 +29,22 @@ impl Display for Constant {
                 let hex_str = hex::encode(b);
                 write!(f, "0x{}", hex_str)
             }
-            Constant::String(s) => write!(f, "\"{}\"", s),
+            Constant::String(s) => {
+                // the escape sequences of the string are written back in the TEAL syntax.
+                write!(f, "\"")?;
+                for b in s.bytes() {
+                    match b {
+                        b'"' => write!(f, "\\\"")?,
+                        b'\\' => write!(f, "\\\\")?,
+                        b'\n' => write!(f, "\\n")?,
+                        b'\r' => write!(f, "\\r")?,
+                        b'\t' => write!(f, "\\t")?,
+                        0x20..=0x7e => write!(f, "{}", b as char)?,
+                        _ => write!(f, "\\x{:02x}", b)?,
+                    }
+                }
+                write!(f, "\"")
+            }
             Constant::StringLit(s) => write!(f, "{}", s),
         }
     }
@@ -37,6 +55,8 @@ impl Display for Constant {
 pub struct Chunk {
     pub op: Instruction,
     pub constants: Vec<Constant>,
+    /// Location of the source code the chunk is emitted for.
+    pub loc: Option<Span>,
 }
 
 impl Display for Chunk {
@@ -56,17 +76,41 @@ impl Chunk {
         Self {
             op,
             constants: vec![],
+            loc: None,
         }
     }
     pub fn new_single(op: Instruction, c: Constant) -> Self {
         Self {
             op,
             constants: vec![c],
+            loc: None,
         }
     }
 
     pub fn new_multiple(op: Instruction, cs: Vec<Constant>) -> Self {
-        Self { op, constants: cs }
+        Self {
+            op,
+            constants: cs,
+            loc: None,
+        }
+    }
+
+    /// Set the location of the chunk.
+    pub fn with_loc(mut self, loc: Option<Span>) -> Self {
+        self.loc = loc;
+        self
+    }
+}
+
+/// Attribute the chunks without a location to the code at `loc`, except for the padding.
+///
+/// The chunks of the nested code are located first, so they keep the narrower location.
+pub fn locate(chunks: &mut [Chunk], loc: &Span) {
+    for c in chunks
+        .iter_mut()
+        .filter(|c| c.loc.is_none() && c.op != Instruction::Empty)
+    {
+        c.loc = Some(loc.clone());
     }
 }
 
@@ -80,6 +124,8 @@ pub enum Instruction {
     Plus,
     #[display(fmt = "b+")]
     BPlus,
+    #[display(fmt = "addw")]
+    AddW,
     #[display(fmt = "-")]
     Minus,
     #[display(fmt = "b-")]
@@ -88,6 +134,8 @@ pub enum Instruction {
     Mul,
     #[display(fmt = "b*")]
     BMul,
+    #[display(fmt = "mulw")]
+    MulW,
     #[display(fmt = "/")]
     Div,
     #[display(fmt = "b/")]
@@ -124,12 +172,28 @@ pub enum Instruction {
     Not,
     #[display(fmt = "len")]
     Len,
-    #[display(fmt = "&")]
+    #[display(fmt = "%")]
     Mod,
-    #[display(fmt = "b&")]
+    #[display(fmt = "b%")]
     BMod,
+    #[display(fmt = "expw")]
+    ExpW,
+    #[display(fmt = "sqrt")]
+    Sqrt,
+    #[display(fmt = "bsqrt")]
+    BSqrt,
+    #[display(fmt = "b|")]
+    BOr,
     #[display(fmt = "concat")]
     Concat,
+    #[display(fmt = "sha512_256")]
+    Sha512_256,
+    #[display(fmt = "sha256")]
+    Sha256,
+    #[display(fmt = "keccak256")]
+    Keccak256,
+    #[display(fmt = "ed25519verify_bare")]
+    Ed25519VerifyBare,
 
     #[display(fmt = "pushint")]
     PushInt,
@@ -152,6 +216,10 @@ pub enum Instruction {
     Extract3,
     #[display(fmt = "extract_uint64")]
     ExtractUint,
+    #[display(fmt = "getbyte")]
+    GetByte,
+    #[display(fmt = "substring3")]
+    Substring,
 
     #[display(fmt = "callsub")]
     CallSub,
@@ -162,8 +230,14 @@ pub enum Instruction {
     Error,
     #[display(fmt = "itob")]
     Itob,
+    #[display(fmt = "btoi")]
+    Btoi,
     #[display(fmt = "dup")]
     Dup,
+    #[display(fmt = "pop")]
+    Pop,
+    #[display(fmt = "swap")]
+    Swap,
     #[display(fmt = "{}:", _0)]
     Label(String),
     #[display(fmt = "retsub")]
@@ -175,11 +249,31 @@ pub enum Instruction {
     Txna,
     #[display(fmt = "global")]
     Global,
+    #[display(fmt = "balance")]
+    Balance,
+    #[display(fmt = "app_global_get")]
+    AppGlobalGet,
+    #[display(fmt = "app_global_put")]
+    AppGlobalPut,
+    #[display(fmt = "itxn_begin")]
+    ItxnBegin,
+    #[display(fmt = "itxn_field")]
+    ItxnField,
+    #[display(fmt = "itxn_submit")]
+    ItxnSubmit,
+    #[display(fmt = "itxn")]
+    Itxn,
+    #[display(fmt = "method")]
+    Method,
 
     #[display(fmt = "box_get")]
     BoxGet,
     #[display(fmt = "box_put")]
     BoxPut,
+    #[display(fmt = "box_del")]
+    BoxDel,
+    #[display(fmt = "box_len")]
+    BoxLen,
 
     #[display(fmt = "b")]
     Branch,
@@ -204,17 +298,37 @@ pub trait TypeSizeHint {
 impl TypeSizeHint for TypeVariant {
     fn size_hint(&self, contract: &ContractDefinition) -> u64 {
         match self {
-            TypeVariant::Char | TypeVariant::Bool | TypeVariant::Uint | TypeVariant::Float => 8,
-            TypeVariant::Int => 16,
+            TypeVariant::U8 => 1,
+            TypeVariant::Char
+            | TypeVariant::Bool
+            | TypeVariant::Uint
+            | TypeVariant::U64
+            | TypeVariant::Float => 8,
+            TypeVariant::Int | TypeVariant::I64 => 16,
+            TypeVariant::U256 => 32,
             TypeVariant::Address => 32,
             TypeVariant::Unit => 0,
-            TypeVariant::Enum(_) => 16,
+            TypeVariant::Enum(sym) => {
+                let enum_decl = &contract.enums[sym.i];
+                ENUM_HEADER_SIZE
+                    + enum_decl
+                        .variants
+                        .values()
+                        .map(|v| struct_size(&v.fields, contract))
+                        .max()
+                        .unwrap_or(0)
+            }
+            TypeVariant::Option(ty) => OPTION_HEADER_SIZE + ty.size_hint(contract),
+            TypeVariant::Tuple(tys) => struct_size(&tuple_fields(tys), contract),
             TypeVariant::Function(f) => f.returns.size_hint(contract),
-            TypeVariant::Set(_)
-            | TypeVariant::List(_)
-            | TypeVariant::Mapping(_)
-            | TypeVariant::String
-            | TypeVariant::Hex => 512,
+            // mappings are stored in separate boxes.
+            TypeVariant::Mapping(_) => 0,
+            TypeVariant::String | TypeVariant::Hex => RESIZABLE_CAPACITY,
+            // nested elements may not fit into the default capacity.
+            TypeVariant::Set(item) | TypeVariant::List(item) => {
+                let stride = item.size_hint(contract) + if has_size_block(item) { 8 } else { 0 };
+                RESIZABLE_CAPACITY.max(MIN_COLLECTION_ITEMS * stride)
+            }
             TypeVariant::Struct(sym) => {
                 let struct_decl = &contract.structs[sym.i];
                 struct_size(&struct_decl.fields, contract)
@@ -232,13 +346,26 @@ impl TypeSizeHint for TypeVariant {
     }
 }
 
+/// Number of bytes reserved for the resizable values in the fixed layouts, e.g. in the
+/// fields of structs.
+pub const RESIZABLE_CAPACITY: u64 = 512;
+
+/// Number of elements the lists and sets reserve the space for at least.
+pub const MIN_COLLECTION_ITEMS: u64 = 4;
+
+/// Size of the enum index and of the variant tag preceding the payload of the enum.
+pub const ENUM_HEADER_SIZE: u64 = 16;
+
+/// Size of the presence byte preceding the value of the option.
+pub const OPTION_HEADER_SIZE: u64 = 1;
+
 pub fn struct_size(fields: &[Param], contract: &ContractDefinition) -> u64 {
     // construct array
     let mut array_size: u64 = 0;
     for f in fields {
         array_size += f.ty.ty.size_hint(contract);
 
-        if f.ty.ty.is_resizable() {
+        if has_size_block(&f.ty.ty) {
             array_size += 8; // reserve one more uint64 block for actual size of
                              // resizeable struct.
         }
@@ -246,3 +373,36 @@ pub fn struct_size(fields: &[Param], contract: &ContractDefinition) -> u64 {
 
     array_size
 }
+
+/// Elements of the tuple as the fields of the struct, they are laid out in the same way.
+pub fn tuple_fields(tys: &[TypeVariant]) -> Vec<Param> {
+    tys.iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            Param {
+                loc: Default::default(),
+                ty: Type {
+                    loc: Default::default(),
+                    ty: ty.clone(),
+                },
+                name: Identifier {
+                    loc: Default::default(),
+                    name: i.to_string(),
+                },
+                is_mut: false,
+                recursive: false,
+            }
+        })
+        .collect()
+}
+
+/// Is the type stored with the preceding size block in the array.
+///
+/// Options of the resizable values are resizable too, as `some` is not padded.
+pub fn has_size_block(ty: &TypeVariant) -> bool {
+    match ty {
+        TypeVariant::Mapping(_) => false,
+        TypeVariant::Option(inner) => has_size_block(inner),
+        _ => ty.is_resizable(),
+    }
+}
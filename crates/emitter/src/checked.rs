@@ -0,0 +1,266 @@
+//! Checked arithmetic of the `or` fallback.
+//!
+//! `value :> or(fallback)` evaluates the arithmetic of `value` with the operands
+//! stored in the scratch space, and tests them before every operation.
+//! Instead of failing the program on the overflow, the underflow or the division by zero,
+//! it branches to the evaluation of `fallback`.
+//!
+//! Only the arithmetic operations of `value` are checked,
+//! the rest of the expressions, e.g. function calls, are emitted as usual.
+//!
+//! For the options, the presence byte of `value` is tested instead.
+
+use folidity_semantics::ast::{
+    BinaryExpression,
+    BuiltinCall,
+    Expression,
+    TypeVariant,
+};
+
+use crate::{
+    ast::{
+        Chunk,
+        Constant,
+        Instruction,
+        TypeSizeHint,
+    },
+    expression::{
+        arithmetic_op,
+        emit_expression,
+        option_value,
+        variant_tag,
+        FLOAT_SCALE,
+    },
+    integer::U256_WIDTH,
+    teal::EmitArgs,
+};
+
+type EmitResult = Result<u64, ()>;
+
+/// Emit `or(value, fallback)`.
+pub fn emit_or(f: &BuiltinCall, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let index = args.emitter.cond_index_incr()?;
+    let fallback_label = format!("{}_or_fallback", index);
+    let end_label = format!("{}_or_end", index);
+
+    let mut local_chunks = vec![];
+    let size = if let TypeVariant::Option(ty) = f.args[0].ty() {
+        let value_index = args.emitter.scratch_index_incr()?;
+        emit_expression(&f.args[0], &mut local_chunks, args)?;
+        local_chunks.extend_from_slice(&[
+            Chunk::new_single(Instruction::Store, Constant::Uint(value_index)),
+            Chunk::new_single(Instruction::Load, Constant::Uint(value_index)),
+        ]);
+        local_chunks.extend(variant_tag(f.args[0].ty()));
+        local_chunks.extend_from_slice(&[
+            Chunk::new_single(
+                Instruction::BranchZero,
+                Constant::StringLit(fallback_label.clone()),
+            ),
+            Chunk::new_single(Instruction::Load, Constant::Uint(value_index)),
+            option_value(),
+        ]);
+        ty.size_hint(args.emitter.definition)
+    } else {
+        checked(&f.args[0], &fallback_label, &mut local_chunks, args)?
+    };
+    local_chunks.push(Chunk::new_single(
+        Instruction::Branch,
+        Constant::StringLit(end_label.clone()),
+    ));
+
+    local_chunks.push(Chunk::new_empty(Instruction::Label(fallback_label)));
+    emit_expression(&f.args[1], &mut local_chunks, args)?;
+    local_chunks.push(Chunk::new_empty(Instruction::Label(end_label)));
+
+    chunks.extend(local_chunks);
+
+    Ok(size)
+}
+
+/// Emit the expression branching to the `fail` label if any of its operations fails.
+fn checked(e: &Expression, fail: &str, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let b = match e {
+        Expression::Add(b) if b.ty == TypeVariant::String => {
+            return emit_expression(e, chunks, args)
+        }
+        Expression::Add(b)
+        | Expression::Subtract(b)
+        | Expression::Multiply(b)
+        | Expression::Divide(b)
+        | Expression::Modulo(b) => b,
+        _ => return emit_expression(e, chunks, args),
+    };
+
+    let left = args.emitter.scratch_index_incr()?;
+    let right = args.emitter.scratch_index_incr()?;
+
+    let mut local_chunks = vec![];
+    checked(&b.left, fail, &mut local_chunks, args)?;
+    local_chunks.push(Chunk::new_single(Instruction::Store, Constant::Uint(left)));
+    checked(&b.right, fail, &mut local_chunks, args)?;
+    local_chunks.push(Chunk::new_single(Instruction::Store, Constant::Uint(right)));
+
+    let load = |slot: u64| Chunk::new_single(Instruction::Load, Constant::Uint(slot));
+    let branch_fail = Chunk::new_single(
+        Instruction::BranchNotZero,
+        Constant::StringLit(fail.to_string()),
+    );
+
+    if let Some(guard) = guard(e, b, left, right) {
+        local_chunks.extend(guard);
+        local_chunks.push(branch_fail.clone());
+    }
+
+    local_chunks.extend([load(left), load(right)]);
+    local_chunks.push(arithmetic_op(e, b, args)?);
+
+    if let Some(check) = range_check(&b.ty) {
+        let result = args.emitter.scratch_index_incr()?;
+        local_chunks.push(Chunk::new_single(
+            Instruction::Store,
+            Constant::Uint(result),
+        ));
+        local_chunks.push(load(result));
+        local_chunks.extend(check);
+        local_chunks.push(branch_fail);
+        local_chunks.push(load(result));
+        if b.ty == TypeVariant::U256 {
+            // the byte math trims the leading zeros.
+            local_chunks.extend([
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(U256_WIDTH)),
+                Chunk::new_empty(Instruction::ArrayInit),
+                Chunk::new_empty(Instruction::BOr),
+            ]);
+        }
+    }
+
+    chunks.extend(local_chunks);
+
+    Ok(b.ty.size_hint(args.emitter.definition))
+}
+
+/// Test of the operands pushing non-zero value if the operation fails.
+///
+/// The magnitudes of the signed operands are tested regardless of their signs,
+/// so the test is conservative for the operands of different signs.
+fn guard(e: &Expression, b: &BinaryExpression, left: u64, right: u64) -> Option<Vec<Chunk>> {
+    let load = |slot: u64| Chunk::new_single(Instruction::Load, Constant::Uint(slot));
+    let push = |n: u64| Chunk::new_single(Instruction::PushInt, Constant::Uint(n));
+    let op = Chunk::new_empty;
+    // the absolute value follows the sign block.
+    let magnitude = |slot: u64| [load(slot), push(8), op(Instruction::ExtractUint)];
+
+    let chunks = match (e, b.left.ty()) {
+        // the high word of the sum or the product is not zero.
+        (Expression::Add(_), TypeVariant::Uint | TypeVariant::U64 | TypeVariant::Float) => {
+            vec![
+                load(left),
+                load(right),
+                op(Instruction::AddW),
+                op(Instruction::Pop),
+            ]
+        }
+        (Expression::Multiply(_), TypeVariant::Uint | TypeVariant::U64) => {
+            vec![
+                load(left),
+                load(right),
+                op(Instruction::MulW),
+                op(Instruction::Pop),
+            ]
+        }
+        (Expression::Add(_) | Expression::Subtract(_), TypeVariant::Int | TypeVariant::I64) => {
+            let mut chunks = magnitude(left).to_vec();
+            chunks.extend(magnitude(right));
+            chunks.extend([op(Instruction::AddW), op(Instruction::Pop)]);
+            chunks
+        }
+        (Expression::Multiply(_), TypeVariant::Int | TypeVariant::I64) => {
+            let mut chunks = magnitude(left).to_vec();
+            chunks.extend(magnitude(right));
+            chunks.extend([op(Instruction::MulW), op(Instruction::Pop)]);
+            chunks
+        }
+        // the product is scaled back with `divw` that fails on the overflowing quotient.
+        (Expression::Multiply(_), TypeVariant::Float) => {
+            vec![
+                load(left),
+                load(right),
+                op(Instruction::MulW),
+                op(Instruction::Pop),
+                push(FLOAT_SCALE),
+                op(Instruction::GreaterEq),
+            ]
+        }
+
+        (
+            Expression::Subtract(_),
+            TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64 | TypeVariant::Float,
+        ) => vec![load(left), load(right), op(Instruction::Less)],
+        (Expression::Subtract(_), TypeVariant::U256) => {
+            vec![load(left), load(right), op(Instruction::BLess)]
+        }
+
+        (
+            Expression::Divide(_) | Expression::Modulo(_),
+            TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64,
+        ) => vec![load(right), op(Instruction::Not)],
+        (Expression::Divide(_) | Expression::Modulo(_), TypeVariant::Int | TypeVariant::I64) => {
+            let mut chunks = magnitude(right).to_vec();
+            chunks.push(op(Instruction::Not));
+            chunks
+        }
+        (Expression::Divide(_) | Expression::Modulo(_), TypeVariant::U256) => {
+            vec![
+                load(right),
+                push(0),
+                op(Instruction::ArrayInit),
+                op(Instruction::BEq),
+            ]
+        }
+        // the scaled dividend is divided with `divw`, it also fails on the zero divisor.
+        (Expression::Divide(_), TypeVariant::Float) => {
+            vec![
+                load(left),
+                push(FLOAT_SCALE),
+                op(Instruction::MulW),
+                op(Instruction::Pop),
+                load(right),
+                op(Instruction::GreaterEq),
+            ]
+        }
+        // `u8` and `u256` sums and products are checked after the operation.
+        _ => return None,
+    };
+
+    Some(chunks)
+}
+
+/// Test of the result of the operation on top of the stack
+/// pushing non-zero value if it does not fit in the range of the type.
+fn range_check(ty: &TypeVariant) -> Option<Vec<Chunk>> {
+    let push = |n: u64| Chunk::new_single(Instruction::PushInt, Constant::Uint(n));
+    let op = Chunk::new_empty;
+
+    let chunks = match ty {
+        TypeVariant::U8 => vec![push(u8::MAX.into()), op(Instruction::Greater)],
+        TypeVariant::I64 => {
+            vec![
+                push(8),
+                op(Instruction::ExtractUint),
+                push(i64::MAX as u64),
+                op(Instruction::Greater),
+            ]
+        }
+        TypeVariant::U256 => {
+            vec![
+                op(Instruction::Len),
+                push(U256_WIDTH),
+                op(Instruction::Greater),
+            ]
+        }
+        _ => return None,
+    };
+
+    Some(chunks)
+}
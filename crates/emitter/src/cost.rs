@@ -0,0 +1,196 @@
+//! Opcode cost of the emitted functions.
+//!
+//! Every application call has a budget of the opcode cost. Most of the opcodes cost 1,
+//! while the cryptographic ones are much more expensive. The cost of the function
+//! is estimated as the cost of the most expensive path through its subroutine, including
+//! the subroutines it calls. Loops and recursive calls are only counted once, so the cost
+//! of such functions is a lower bound.
+
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use folidity_diagnostics::Report;
+use folidity_semantics::ContractDefinition;
+
+use crate::{
+    ast::{
+        Chunk,
+        Instruction,
+    },
+    scratch_table::{
+        successors,
+        target,
+    },
+};
+
+/// Opcode budget of a single application call.
+pub const APP_CALL_BUDGET: u64 = 700;
+
+/// Estimated opcode cost of the function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCost {
+    /// Name of the function.
+    pub name: String,
+    /// Cost of the most expensive path through the function.
+    pub cost: u64,
+    /// Whether the function has loops or recursive calls,
+    /// so that the cost is a lower bound.
+    pub unbounded: bool,
+}
+
+/// Cost of the opcode on the AVM.
+fn opcode_cost(op: &Instruction) -> u64 {
+    match op {
+        Instruction::Label(_) | Instruction::Empty => 0,
+        Instruction::Sqrt => 4,
+        Instruction::ExpW => 10,
+        Instruction::BSqrt => 40,
+        Instruction::Sha256 => 35,
+        Instruction::Sha512_256 => 45,
+        Instruction::Keccak256 => 130,
+        Instruction::Ed25519VerifyBare => 1900,
+        _ => 1,
+    }
+}
+
+/// Estimate the costs of the functions of the program,
+/// warning about the ones exceeding the budget of the application call.
+pub fn function_costs(
+    chunks: &[Chunk],
+    definition: &ContractDefinition,
+    warnings: &mut Vec<Report>,
+) -> Vec<FunctionCost> {
+    let labels: HashMap<String, usize> = chunks
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| {
+            match &c.op {
+                Instruction::Label(l) => Some((l.clone(), i)),
+                _ => None,
+            }
+        })
+        .collect();
+    let mut estimator = CostEstimator {
+        chunks,
+        labels,
+        costs: HashMap::new(),
+        visiting: HashSet::new(),
+    };
+
+    definition
+        .functions
+        .iter()
+        .map(|f| {
+            let (cost, unbounded) = estimator.subroutine(&format!("__{}", f.name.name));
+            if cost > APP_CALL_BUDGET {
+                let mut report = Report::emit_warning(
+                    f.name.loc.clone(),
+                    format!(
+                        "Function `{}` costs {}{} opcodes, which exceeds the budget of {} of the application call.",
+                        f.name.name,
+                        if unbounded { "at least " } else { "" },
+                        cost,
+                        APP_CALL_BUDGET
+                    ),
+                );
+                report.note = String::from(
+                    "The budget can be pooled by grouping the call with other application calls.",
+                );
+                warnings.push(report);
+            }
+            FunctionCost {
+                name: f.name.name.clone(),
+                cost,
+                unbounded,
+            }
+        })
+        .collect()
+}
+
+struct CostEstimator<'a> {
+    chunks: &'a [Chunk],
+    /// Indices of the labelled chunks.
+    labels: HashMap<String, usize>,
+    /// Costs of the estimated subroutines.
+    costs: HashMap<String, (u64, bool)>,
+    /// Subroutines being estimated, calling them again is a recursion.
+    visiting: HashSet<String>,
+}
+
+impl<'a> CostEstimator<'a> {
+    /// Cost of the subroutine and whether it is a lower bound.
+    fn subroutine(&mut self, label: &str) -> (u64, bool) {
+        if let Some(cost) = self.costs.get(label) {
+            return *cost;
+        }
+        if !self.visiting.insert(label.to_string()) {
+            return (0, true);
+        }
+        let cost = match self.labels.get(label) {
+            Some(start) => self.longest_path(*start),
+            // the helper subroutines are appended to the program as they are.
+            None => (0, true),
+        };
+        self.visiting.remove(label);
+        self.costs.insert(label.to_string(), cost);
+        cost
+    }
+
+    /// Cost of the most expensive path from the chunk to the return of the subroutine.
+    ///
+    /// The edges closing the loops are not followed.
+    fn longest_path(&mut self, start: usize) -> (u64, bool) {
+        let chunks = self.chunks;
+
+        // chunks are finished in the depth-first order,
+        // the edges to the chunks still on the stack close the loops.
+        let mut finished: HashMap<usize, bool> = HashMap::from([(start, false)]);
+        let mut order = vec![];
+        let mut back_edges = HashSet::new();
+        let mut stack = vec![(start, successors(chunks, start, &self.labels), 0)];
+        while let Some((n, next, i)) = stack.last_mut() {
+            let n = *n;
+            let Some(s) = next.get(*i).copied() else {
+                finished.insert(n, true);
+                order.push(n);
+                stack.pop();
+                continue;
+            };
+            *i += 1;
+            match finished.get(&s) {
+                None => {
+                    finished.insert(s, false);
+                    stack.push((s, successors(chunks, s, &self.labels), 0));
+                }
+                Some(false) => {
+                    back_edges.insert((n, s));
+                }
+                Some(true) => {}
+            }
+        }
+
+        let mut unbounded = !back_edges.is_empty();
+        let mut costs: HashMap<usize, u64> = HashMap::new();
+        for n in order {
+            let mut cost = opcode_cost(&chunks[n].op);
+            if chunks[n].op == Instruction::CallSub {
+                if let Some(l) = target(&chunks[n]) {
+                    let (called, lower) = self.subroutine(l);
+                    cost += called;
+                    unbounded |= lower;
+                }
+            }
+            let rest = successors(chunks, n, &self.labels)
+                .into_iter()
+                .filter(|s| !back_edges.contains(&(n, *s)))
+                .filter_map(|s| costs.get(&s))
+                .max()
+                .copied()
+                .unwrap_or(0);
+            costs.insert(n, cost + rest);
+        }
+        (costs[&start], unbounded)
+    }
+}
@@ -0,0 +1,57 @@
+//! Hashes and signatures.
+//!
+//! The hashed values are encoded with ARC-4 and concatenated,
+//! so the hash of the same values can be computed off the chain.
+
+use folidity_semantics::{
+    ast::BuiltinCall,
+    builtin::BuiltinFunction,
+};
+
+use crate::{
+    ast::{
+        Chunk,
+        Instruction,
+        TypeSizeHint,
+    },
+    expression::emit_expression,
+    interface::encode,
+    teal::EmitArgs,
+};
+
+type EmitResult = Result<u64, ()>;
+
+/// Emit the hash of the values or the verification of the signature.
+pub fn emit_crypto_call(
+    f: &BuiltinCall,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
+    let mut local_chunks = vec![];
+    let mut error = false;
+    for (i, a) in f.args.iter().enumerate() {
+        error |= emit_expression(a, &mut local_chunks, args).is_err();
+        if !f.func.is_hash() {
+            continue;
+        }
+        encode(a.ty(), &mut local_chunks);
+        if i > 0 {
+            local_chunks.push(Chunk::new_empty(Instruction::Concat));
+        }
+    }
+    if error {
+        return Err(());
+    }
+
+    let op = match f.func {
+        BuiltinFunction::Hash | BuiltinFunction::Sha256 => Instruction::Sha256,
+        BuiltinFunction::Keccak256 => Instruction::Keccak256,
+        // the data is signed as it is, without the prefix of the program.
+        BuiltinFunction::Ed25519Verify => Instruction::Ed25519VerifyBare,
+        _ => unreachable!("only hashes and signatures are emitted"),
+    };
+    local_chunks.push(Chunk::new_empty(op));
+
+    chunks.extend(local_chunks);
+    Ok(f.returns.size_hint(args.emitter.definition))
+}
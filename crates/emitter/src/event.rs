@@ -0,0 +1,164 @@
+//! Events logged by the contract.
+//!
+//! Events are logged as of ARC-28: the selector of the event signature is followed by
+//! the fields encoded as the ARC-4 tuple. Consecutive booleans are packed into bytes,
+//! and the strings and byte arrays are referenced by their offsets from the head
+//! of the tuple.
+
+use folidity_semantics::ast::{
+    Emit,
+    EventDeclaration,
+    TypeVariant,
+};
+
+use crate::{
+    app_spec::abi_type,
+    ast::{
+        Chunk,
+        Constant,
+        Instruction,
+    },
+    expression::emit_expression,
+    integer::U256_WIDTH,
+    interface::encode,
+    teal::EmitArgs,
+};
+
+type EmitResult = Result<(), ()>;
+
+/// ARC-28 signature of the event, e.g. `Transfer(address,uint64)`.
+pub fn event_signature(event: &EventDeclaration) -> String {
+    let args: Vec<String> = event.fields.iter().map(|f| abi_type(&f.ty.ty)).collect();
+    format!("{}({})", event.name.name, args.join(","))
+}
+
+/// Emit the log of the event.
+pub fn emit_event(e: &Emit, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let signature = event_signature(&args.emitter.definition.events[e.sym.i]);
+
+    // the fields are evaluated first, as the dynamic ones are referenced twice.
+    let mut local_chunks = vec![];
+    let mut error = false;
+    let mut slots = vec![];
+    for a in &e.args {
+        error |= emit_expression(a, &mut local_chunks, args).is_err();
+        // the booleans are packed when the head is assembled.
+        if a.ty() != &TypeVariant::Bool {
+            encode(a.ty(), &mut local_chunks);
+        }
+        let slot = args.emitter.scratch_index_incr()?;
+        local_chunks.push(Chunk::new_single(Instruction::Store, Constant::Uint(slot)));
+        slots.push((slot, a.ty()));
+    }
+    if error {
+        return Err(());
+    }
+
+    // the selector is the prefix of the hash of the signature.
+    local_chunks.extend([
+        Chunk::new_single(Instruction::PushBytes, Constant::String(signature)),
+        Chunk::new_empty(Instruction::Sha512_256),
+        Chunk::new_multiple(
+            Instruction::Extract,
+            vec![Constant::Uint(0), Constant::Uint(4)],
+        ),
+    ]);
+
+    let head_size = head_size(slots.iter().map(|(_, ty)| *ty));
+    let mut dynamic: Vec<u64> = vec![];
+    let mut i = 0;
+    while i < slots.len() {
+        let (slot, ty) = slots[i];
+        match ty {
+            TypeVariant::Bool => {
+                let group: Vec<u64> = slots[i..]
+                    .iter()
+                    .take_while(|(_, ty)| *ty == &TypeVariant::Bool)
+                    .take(8)
+                    .map(|(slot, _)| *slot)
+                    .collect();
+                local_chunks.push(Chunk::new_single(Instruction::PushInt, Constant::Uint(0)));
+                for (bit, slot) in group.iter().enumerate() {
+                    local_chunks.extend([
+                        Chunk::new_single(Instruction::Load, Constant::Uint(*slot)),
+                        Chunk::new_single(Instruction::PushInt, Constant::Uint(1 << (7 - bit))),
+                        Chunk::new_empty(Instruction::Mul),
+                        Chunk::new_empty(Instruction::Plus),
+                    ]);
+                }
+                local_chunks.extend([
+                    Chunk::new_empty(Instruction::Itob),
+                    Chunk::new_multiple(
+                        Instruction::Extract,
+                        vec![Constant::Uint(7), Constant::Uint(1)],
+                    ),
+                ]);
+                i += group.len();
+            }
+            TypeVariant::String | TypeVariant::Hex => {
+                local_chunks.push(Chunk::new_single(
+                    Instruction::PushInt,
+                    Constant::Uint(head_size),
+                ));
+                for d in &dynamic {
+                    local_chunks.extend([
+                        Chunk::new_single(Instruction::Load, Constant::Uint(*d)),
+                        Chunk::new_empty(Instruction::Len),
+                        Chunk::new_empty(Instruction::Plus),
+                    ]);
+                }
+                local_chunks.extend([
+                    Chunk::new_empty(Instruction::Itob),
+                    Chunk::new_multiple(
+                        Instruction::Extract,
+                        vec![Constant::Uint(6), Constant::Uint(2)],
+                    ),
+                ]);
+                dynamic.push(slot);
+                i += 1;
+            }
+            _ => {
+                local_chunks.push(Chunk::new_single(Instruction::Load, Constant::Uint(slot)));
+                i += 1;
+            }
+        }
+        local_chunks.push(Chunk::new_empty(Instruction::Concat));
+    }
+    for d in dynamic {
+        local_chunks.extend([
+            Chunk::new_single(Instruction::Load, Constant::Uint(d)),
+            Chunk::new_empty(Instruction::Concat),
+        ]);
+    }
+    local_chunks.push(Chunk::new_empty(Instruction::Log));
+
+    chunks.extend(local_chunks);
+    Ok(())
+}
+
+/// Size of the head of the ARC-4 tuple in bytes.
+fn head_size<'a>(tys: impl Iterator<Item = &'a TypeVariant>) -> u64 {
+    let mut size = 0;
+    let mut bools = 0;
+    for ty in tys {
+        if ty == &TypeVariant::Bool {
+            // up to 8 consecutive booleans share a byte.
+            if bools % 8 == 0 {
+                size += 1;
+            }
+            bools += 1;
+            continue;
+        }
+        bools = 0;
+        size += match ty {
+            TypeVariant::U8 => 1,
+            // offset of the value in the tail.
+            TypeVariant::String | TypeVariant::Hex => 2,
+            TypeVariant::Int | TypeVariant::I64 => 16,
+            TypeVariant::U256 => U256_WIDTH,
+            TypeVariant::Address => 32,
+            _ => 8,
+        };
+    }
+    size
+}
@@ -0,0 +1,96 @@
+//! Ethereum contract ABI of the functions.
+
+use folidity_semantics::{
+    ast::{
+        Function,
+        FunctionVisibility,
+        TypeVariant,
+    },
+    ContractDefinition,
+};
+use serde_json::{
+    json,
+    Value,
+};
+use tiny_keccak::{
+    Hasher,
+    Keccak,
+};
+
+/// ABI type of the value represented by a single word.
+pub fn abi_type(ty: &TypeVariant) -> Option<&'static str> {
+    let t = match ty {
+        TypeVariant::Int => "int256",
+        TypeVariant::Uint | TypeVariant::U256 => "uint256",
+        TypeVariant::U8 => "uint8",
+        TypeVariant::U64 => "uint64",
+        TypeVariant::I64 => "int64",
+        TypeVariant::Bool => "bool",
+        TypeVariant::Address => "address",
+        TypeVariant::Char => "uint32",
+        TypeVariant::Enum(_) => "uint8",
+        _ => return None,
+    };
+    Some(t)
+}
+
+/// Function selector as a hex literal, i.e. the first 4 bytes of the signature hash.
+pub fn selector(func: &Function) -> String {
+    let params: Vec<&str> = func
+        .params
+        .values()
+        .map(|p| abi_type(&p.ty.ty).unwrap_or_default())
+        .collect();
+    let signature = format!("{}({})", func.name.name, params.join(","));
+
+    let mut hash = [0u8; 32];
+    let mut keccak = Keccak::v256();
+    keccak.update(signature.as_bytes());
+    keccak.finalize(&mut hash);
+
+    format!("0x{}", hex::encode(&hash[..4]))
+}
+
+/// ABI specification of the contract.
+///
+/// The init function is the constructor, private functions are omitted.
+pub fn abi_json(contract: &ContractDefinition) -> String {
+    let entries: Vec<Value> = contract
+        .functions
+        .iter()
+        .filter(|f| f.is_init || !matches!(f.vis, FunctionVisibility::Priv))
+        .map(|f| {
+            let inputs: Vec<Value> = f
+                .params
+                .values()
+                .map(|p| json!({ "name": p.name.name, "type": abi_type(&p.ty.ty) }))
+                .collect();
+            if f.is_init {
+                return json!({
+                    "type": "constructor",
+                    "inputs": inputs,
+                    "stateMutability": "nonpayable",
+                });
+            }
+
+            let outputs: Vec<Value> = abi_type(f.return_ty.ty())
+                .map(|t| json!({ "name": "", "type": t }))
+                .into_iter()
+                .collect();
+            let mutability = if matches!(f.vis, FunctionVisibility::View(_)) {
+                "view"
+            } else {
+                "nonpayable"
+            };
+            json!({
+                "type": "function",
+                "name": f.name.name,
+                "inputs": inputs,
+                "outputs": outputs,
+                "stateMutability": mutability,
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&entries).expect("abi is always serializable")
+}
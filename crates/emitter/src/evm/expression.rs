@@ -0,0 +1,250 @@
+use folidity_diagnostics::{
+    Report,
+    Span,
+};
+use folidity_semantics::{
+    ast::{
+        BinaryExpression,
+        BuiltinCall,
+        Conditional,
+        Expression,
+        FunctionCall,
+        MemberAccess,
+        TypeVariant,
+        UnaryExpression,
+    },
+    builtin::BuiltinFunction,
+};
+use num_bigint::{
+    BigInt,
+    Sign,
+};
+use num_traits::Signed;
+
+use super::{
+    function::{
+        ConcreteVar,
+        EvmArgs,
+    },
+    indent,
+};
+
+type EmitResult = Result<String, ()>;
+
+/// Max number of bits in the word.
+const WORD_BITS: u64 = 256;
+
+/// Emit the Yul expression.
+///
+/// Statements the expression depends on are pushed to the `lines`.
+pub fn emit_expression(
+    expr: &Expression,
+    lines: &mut Vec<String>,
+    args: &mut EvmArgs,
+) -> EmitResult {
+    match expr {
+        Expression::Variable(u) => var(u, args),
+
+        // literals
+        Expression::Int(u) => int(&u.element, &u.loc, args),
+        Expression::UInt(u) => int(&BigInt::from(u.element.clone()), &u.loc, args),
+        Expression::Boolean(u) => Ok(if u.element { "1" } else { "0" }.to_string()),
+        Expression::Char(u) => Ok((u.element as u32).to_string()),
+        Expression::Enum(u) => Ok(u.element.to_string()),
+
+        // operations
+        Expression::Add(b) => arithmetic("add", b, lines, args),
+        Expression::Subtract(b) => arithmetic("sub", b, lines, args),
+        Expression::Multiply(b) => arithmetic("mul", b, lines, args),
+        Expression::Divide(b) => arithmetic("div", b, lines, args),
+        Expression::Modulo(b) => arithmetic("mod", b, lines, args),
+        Expression::Equal(b) => binary("eq", b, lines, args),
+        Expression::NotEqual(b) => Ok(format!("iszero({})", binary("eq", b, lines, args)?)),
+        Expression::Greater(b) => compare("gt", b, lines, args),
+        Expression::Less(b) => compare("lt", b, lines, args),
+        Expression::GreaterEq(b) => Ok(format!("iszero({})", compare("lt", b, lines, args)?)),
+        Expression::LessEq(b) => Ok(format!("iszero({})", compare("gt", b, lines, args)?)),
+        Expression::Not(u) => {
+            Ok(format!(
+                "iszero({})",
+                emit_expression(&u.element, lines, args)?
+            ))
+        }
+        Expression::Or(b) => binary("or", b, lines, args),
+        Expression::And(b) => binary("and", b, lines, args),
+
+        // complex
+        Expression::FunctionCall(f) => func_call(f, lines, args),
+        Expression::BuiltinCall(f) => builtin_call(f, lines, args),
+        Expression::MemberAccess(m) => member_access(m, args),
+        Expression::Conditional(c) => conditional(c, lines, args),
+
+        Expression::Float(_) => unsupported(expr.loc(), "Floats", args),
+        Expression::String(_) => unsupported(expr.loc(), "Strings", args),
+        Expression::Hex(_) => unsupported(expr.loc(), "Hex literals", args),
+        Expression::Address(_) => unsupported(expr.loc(), "Address literals", args),
+        Expression::In(_) => unsupported(expr.loc(), "Inclusion checks", args),
+        Expression::StructInit(_) => unsupported(expr.loc(), "Struct literals", args),
+        Expression::VariantInit(_) => unsupported(expr.loc(), "Enum payloads", args),
+        Expression::List(_) => unsupported(expr.loc(), "Lists", args),
+        Expression::Index(_) | Expression::Slice(_) => {
+            unsupported(expr.loc(), "Indexing of lists", args)
+        }
+        Expression::Optional(_) => unsupported(expr.loc(), "Options", args),
+        Expression::Tuple(_) => unsupported(expr.loc(), "Tuples", args),
+        Expression::Lambda(_) => unsupported(expr.loc(), "Lambdas", args),
+        Expression::InterfaceCall(_) => unsupported(expr.loc(), "Interface calls", args),
+    }
+}
+
+fn unsupported(loc: &Span, what: &str, args: &mut EvmArgs) -> EmitResult {
+    args.diagnostics.push(Report::emit_error(
+        loc.clone(),
+        format!("{} are not supported by the EVM target.", what),
+    ));
+    Err(())
+}
+
+fn var(u: &UnaryExpression<usize>, args: &mut EvmArgs) -> EmitResult {
+    match args.concrete_vars.get(&u.element) {
+        Some(ConcreteVar::Value(v)) => Ok(v.clone()),
+        _ => {
+            args.diagnostics.push(Report::emit_error(
+                u.loc.clone(),
+                "Variable can not be loaded in this context.".to_string(),
+            ));
+            Err(())
+        }
+    }
+}
+
+/// Emit the integer literal in the two's complement representation.
+fn int(n: &BigInt, loc: &Span, args: &mut EvmArgs) -> EmitResult {
+    // the sign takes one bit for negative numbers.
+    let bits = if n.sign() == Sign::Minus {
+        (n.abs() - 1u8).bits() + 1
+    } else {
+        n.bits()
+    };
+    if bits > WORD_BITS {
+        args.diagnostics.push(Report::emit_error(
+            loc.clone(),
+            format!("Literal does not fit into {} bits.", WORD_BITS),
+        ));
+        return Err(());
+    }
+
+    if n.sign() == Sign::Minus {
+        Ok(format!("sub(0, {})", n.abs()))
+    } else {
+        Ok(n.to_string())
+    }
+}
+
+fn binary(
+    op: &str,
+    b: &BinaryExpression,
+    lines: &mut Vec<String>,
+    args: &mut EvmArgs,
+) -> EmitResult {
+    let left = emit_expression(&b.left, lines, args)?;
+    let right = emit_expression(&b.right, lines, args)?;
+    Ok(format!("{}({}, {})", op, left, right))
+}
+
+/// Arithmetic operations revert on overflow, underflow and division by zero.
+///
+/// Sized integers are operated on as words, so the result is checked to fit into
+/// the width of the type.
+fn arithmetic(
+    op: &str,
+    b: &BinaryExpression,
+    lines: &mut Vec<String>,
+    args: &mut EvmArgs,
+) -> EmitResult {
+    let signed = if b.ty.is_signed() { "s" } else { "" };
+    let value = binary(&format!("checked_{}{}", signed, op), b, lines, args)?;
+    match b.ty {
+        TypeVariant::U8 => Ok(format!("checked_u8({})", value)),
+        TypeVariant::U64 => Ok(format!("checked_u64({})", value)),
+        TypeVariant::I64 => Ok(format!("checked_i64({})", value)),
+        _ => Ok(value),
+    }
+}
+
+/// Comparisons of the signed integers use the signed instructions.
+fn compare(
+    op: &str,
+    b: &BinaryExpression,
+    lines: &mut Vec<String>,
+    args: &mut EvmArgs,
+) -> EmitResult {
+    let signed = if b.left.ty().is_signed() { "s" } else { "" };
+    binary(&format!("{}{}", signed, op), b, lines, args)
+}
+
+/// The value of the selected branch is assigned to the temporary variable,
+/// so only the selected branch is evaluated.
+fn conditional(c: &Conditional, lines: &mut Vec<String>, args: &mut EvmArgs) -> EmitResult {
+    let condition = emit_expression(&c.condition, lines, args)?;
+    let temp = args.emitter.temp_var();
+
+    let mut then_lines = vec![];
+    let then_value = emit_expression(&c.then_expr, &mut then_lines, args)?;
+    then_lines.push(format!("{} := {}", temp, then_value));
+    let mut else_lines = vec![];
+    let else_value = emit_expression(&c.else_expr, &mut else_lines, args)?;
+    else_lines.push(format!("{} := {}", temp, else_value));
+
+    lines.extend([
+        format!("let {}", temp),
+        format!("switch {}", condition),
+        "case 0 {".to_string(),
+        indent(&else_lines.join("\n"), 1),
+        "}".to_string(),
+        "default {".to_string(),
+        indent(&then_lines.join("\n"), 1),
+        "}".to_string(),
+    ]);
+
+    Ok(temp)
+}
+
+fn func_call(f: &FunctionCall, lines: &mut Vec<String>, args: &mut EvmArgs) -> EmitResult {
+    let func = &args.emitter.definition.functions[f.sym.i];
+    let name = format!("fn_{}", func.name.name);
+
+    let mut values = vec![];
+    for a in &f.args {
+        values.push(emit_expression(a, lines, args)?);
+    }
+
+    Ok(format!("{}({})", name, values.join(", ")))
+}
+
+fn builtin_call(f: &BuiltinCall, lines: &mut Vec<String>, args: &mut EvmArgs) -> EmitResult {
+    match f.func {
+        BuiltinFunction::Caller => Ok("caller()".to_string()),
+        BuiltinFunction::CurrentBlock => Ok("number()".to_string()),
+        BuiltinFunction::Balance => {
+            let account = emit_expression(&f.args[0], lines, args)?;
+            Ok(format!("balance({})", account))
+        }
+        // all integers are presented as 256-bit words.
+        BuiltinFunction::Cast if f.args[0].ty().widens_to(&f.returns) => {
+            emit_expression(&f.args[0], lines, args)
+        }
+        _ => unsupported(&f.loc, &format!("Calls of `{}`", f.func), args),
+    }
+}
+
+/// Only the fields of the states are accessible, as they are loaded into separate words.
+fn member_access(m: &MemberAccess, args: &mut EvmArgs) -> EmitResult {
+    if let Expression::Variable(u) = m.expr.as_ref() {
+        if let Some(ConcreteVar::Fields(fields)) = args.concrete_vars.get(&u.element) {
+            return Ok(fields[m.member.0].clone());
+        }
+    }
+
+    unsupported(&m.loc, "Member accesses of non-state values", args)
+}
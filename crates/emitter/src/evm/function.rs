@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+
+use folidity_diagnostics::{
+    Report,
+    Span,
+};
+use folidity_semantics::{
+    ast::{
+        Expression,
+        FuncReturnType,
+        Function,
+        FunctionVisibility,
+        Param,
+        TypeVariant,
+    },
+    SymbolInfo,
+};
+
+use super::{
+    abi::abi_type,
+    expression::emit_expression,
+    field_slot,
+    indent,
+    state_id,
+    statement::{
+        emit_bounds,
+        emit_statement,
+    },
+    EvmEmitter,
+    STATE_SLOT,
+};
+
+/// Yul representation of the variable.
+#[derive(Debug, Clone)]
+pub enum ConcreteVar {
+    /// Variable fits into a single word.
+    Value(String),
+    /// Variable is a state, each field is a separate word.
+    Fields(Vec<String>),
+}
+
+pub struct EvmArgs<'a, 'b> {
+    pub emitter: &'b mut EvmEmitter<'a>,
+    pub func: &'b Function,
+    pub diagnostics: &'b mut Vec<Report>,
+    /// Bounds that can not yet be emitted as some variables are unknown.
+    pub delayed_bounds: &'b mut Vec<Expression>,
+    /// Yul names of the variables in the function scope.
+    pub concrete_vars: HashMap<usize, ConcreteVar>,
+}
+
+/// Emit the function as a Yul function `fn_<name>`.
+///
+/// Function parameters, the return value and fields of the states are passed as words.
+pub fn emit_function(func: &Function, emitter: &mut EvmEmitter) -> Result<String, ()> {
+    let mut diagnostics = vec![];
+    let mut delayed_bounds = vec![];
+    let mut args = EvmArgs {
+        emitter,
+        func,
+        diagnostics: &mut diagnostics,
+        delayed_bounds: &mut delayed_bounds,
+        concrete_vars: HashMap::new(),
+    };
+
+    let mut error = false;
+    let mut params = vec![];
+    for (name, p) in &func.params {
+        error |= check_word(&p.ty.ty, &p.loc, &mut args).is_err();
+        let (p_no, _) = func.scope.find_var_index(name).expect("should exist");
+        let var = format!("v_{}", p_no);
+        params.push(var.clone());
+        args.concrete_vars.insert(p_no, ConcreteVar::Value(var));
+    }
+    if func.return_ty.ty() != &TypeVariant::Unit {
+        let loc = match &func.return_ty {
+            FuncReturnType::Type(ty) => &ty.loc,
+            FuncReturnType::ParamType(p) => &p.loc,
+        };
+        error |= check_word(func.return_ty.ty(), loc, &mut args).is_err();
+    }
+
+    let mut lines = vec![];
+
+    // the contract must be in the expected state.
+    let from = match &func.vis {
+        FunctionVisibility::View(v) => Some((&v.ty, Some(&v.name))),
+        _ => {
+            func.state_bound
+                .as_ref()
+                .and_then(|b| b.from.as_ref())
+                .map(|p| (&p.ty, p.name.as_ref()))
+        }
+    };
+    if let Some((sym, name)) = from {
+        lines.push(format!(
+            "if iszero(eq(sload({}), {})) {{ revert(0, 0) }}",
+            STATE_SLOT,
+            state_id(sym.i)
+        ));
+        if let Some(ident) = name {
+            let (v_no, _) = func
+                .scope
+                .find_var_index(&ident.name)
+                .expect("should exist");
+            match load_state(v_no, sym, &func.loc, &mut lines, &mut args) {
+                Ok(fields) => {
+                    args.concrete_vars.insert(v_no, ConcreteVar::Fields(fields));
+                }
+                Err(_) => error = true,
+            }
+        }
+    }
+
+    // emit access check.
+    if !func.access_attributes.is_empty()
+        && !func
+            .access_attributes
+            .iter()
+            .any(|e| e.is_access_wildcard(&func.scope))
+    {
+        let mut checks = vec![];
+        for e in &func.access_attributes {
+            if e.ty() != &TypeVariant::Address {
+                args.diagnostics.push(Report::emit_error(
+                    e.loc().clone(),
+                    "Non-address types are not supported by the EVM target.".to_string(),
+                ));
+                error = true;
+                continue;
+            }
+            match emit_expression(e, &mut lines, &mut args) {
+                Ok(a) => checks.push(format!("eq(caller(), {})", a)),
+                Err(_) => error = true,
+            }
+        }
+        if let Some(first) = checks.first() {
+            let check = checks
+                .iter()
+                .skip(1)
+                .fold(first.clone(), |acc, c| format!("or({}, {})", acc, c));
+            lines.push(format!("if iszero({}) {{ revert(0, 0) }}", check));
+        }
+    }
+
+    // bounds are checked as soon as all their variables are known.
+    if let Some(bounds) = &func.bounds {
+        args.delayed_bounds.extend_from_slice(&bounds.exprs);
+        emit_bounds(&mut lines, &mut args);
+    }
+
+    for stmt in &func.body {
+        error |= emit_statement(stmt, &mut lines, &mut args).is_err();
+    }
+
+    if error {
+        emitter.diagnostics.extend(diagnostics);
+        return Err(());
+    }
+
+    let returns = if func.return_ty.ty() == &TypeVariant::Unit {
+        String::new()
+    } else {
+        " -> ret".to_string()
+    };
+    Ok(format!(
+        "function fn_{}({}){} {{\n{}\n}}",
+        func.name.name,
+        params.join(", "),
+        returns,
+        indent(&lines.join("\n"), 1)
+    ))
+}
+
+/// Load the fields of the current state into local variables.
+pub fn load_state(
+    v_no: usize,
+    sym: &SymbolInfo,
+    loc: &Span,
+    lines: &mut Vec<String>,
+    args: &mut EvmArgs,
+) -> Result<Vec<String>, ()> {
+    let fields = state_fields(sym, loc, args)?;
+    let mut vars = vec![];
+    for k in 0..fields.len() {
+        let var = format!("s_{}_{}", v_no, k);
+        lines.push(format!("let {} := sload({})", var, field_slot(sym.i, k)));
+        vars.push(var);
+    }
+    Ok(vars)
+}
+
+/// Fields of the state, each of which must fit into a single word.
+pub fn state_fields(sym: &SymbolInfo, loc: &Span, args: &mut EvmArgs) -> Result<Vec<Param>, ()> {
+    let state_decl = &args.emitter.definition.states[sym.i];
+    if state_decl.from.is_some() {
+        args.diagnostics.push(Report::emit_error(
+            loc.clone(),
+            "States with the previous state are not supported by the EVM target.".to_string(),
+        ));
+        return Err(());
+    }
+    let fields = state_decl.fields(args.emitter.definition);
+    let mut error = false;
+    for f in &fields {
+        error |= check_word(&f.ty.ty, &f.loc, args).is_err();
+    }
+    if error {
+        return Err(());
+    }
+    Ok(fields)
+}
+
+/// Check that the value of the type fits into a single word.
+fn check_word(ty: &TypeVariant, loc: &Span, args: &mut EvmArgs) -> Result<(), ()> {
+    if abi_type(ty).is_none() {
+        args.diagnostics.push(Report::emit_error(
+            loc.clone(),
+            format!(
+                "Type `{}` is not supported by the EVM target.",
+                ty.display(args.emitter.definition)
+            ),
+        ));
+        return Err(());
+    }
+    Ok(())
+}
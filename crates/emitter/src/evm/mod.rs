@@ -0,0 +1,170 @@
+//! EVM backend of the compiler.
+//!
+//! The contract is emitted as a [Yul](https://docs.soliditylang.org/en/latest/yul.html) object
+//! which can be assembled into the EVM bytecode with `solc --strict-assembly`.
+//! Only the primitive types that fit into a single word are supported.
+//!
+//! # Storage layout
+//! - Slot `0` holds the id of the current state, `0` if the contract is not initialised.
+//! - Field `k` of the state with id `i` is stored in the slot `i << 64 | k`.
+//!
+//! State ids start from `1` in the order of declaration.
+
+use folidity_diagnostics::Report;
+use folidity_semantics::{
+    ast::{
+        FunctionVisibility,
+        TypeVariant,
+    },
+    ContractDefinition,
+};
+
+use self::{
+    abi::{
+        abi_json,
+        selector,
+    },
+    function::emit_function,
+};
+
+pub mod abi;
+mod expression;
+mod function;
+mod statement;
+
+/// Storage slot of the current state id.
+pub const STATE_SLOT: &str = "0";
+
+#[derive(Debug, Clone)]
+pub struct EvmArtifacts {
+    /// Yul object of the contract.
+    pub yul_bytes: Vec<u8>,
+    /// ABI specification of the contract in JSON.
+    pub abi: String,
+}
+
+#[derive(Debug)]
+pub struct EvmEmitter<'a> {
+    /// Nested definition of the contract.
+    pub definition: &'a ContractDefinition,
+    /// Errors and warning caused during emit process.
+    pub diagnostics: Vec<Report>,
+    /// Yul functions of the declared ones.
+    functions: Vec<String>,
+    /// Counter for temporary variables.
+    pub temp_counter: u64,
+}
+
+impl<'a> EvmEmitter<'a> {
+    pub fn new(definition: &'a ContractDefinition) -> Self {
+        Self {
+            definition,
+            diagnostics: vec![],
+            functions: vec![],
+            temp_counter: 0,
+        }
+    }
+
+    pub fn emit_functions(&mut self) -> bool {
+        let mut error = false;
+
+        for func in &self.definition.functions {
+            match emit_function(func, self) {
+                Ok(f) => self.functions.push(f),
+                Err(_) => error = true,
+            }
+        }
+
+        !error
+    }
+
+    pub fn compile(&self) -> EvmArtifacts {
+        let helpers = include_str!("../../helpers/checked_arithmetic.yul");
+        let mut functions = self.functions.join("\n\n");
+        functions.push_str("\n\n");
+        functions.push_str(helpers);
+
+        // the constructor is the init function, its arguments are appended to the init code.
+        let mut deploy = vec![];
+        if let Some(init) = self.definition.functions.iter().find(|f| f.is_init) {
+            let args_size = init.params.len() * 32;
+            let args: Vec<String> = (0..init.params.len())
+                .map(|i| format!("mload({})", i * 32))
+                .collect();
+            deploy.extend([
+                format!("codecopy(0, datasize(\"Contract\"), {})", args_size),
+                format!("fn_{}({})", init.name.name, args.join(", ")),
+            ]);
+        }
+        deploy.extend([
+            "datacopy(0, dataoffset(\"runtime\"), datasize(\"runtime\"))".to_string(),
+            "return(0, datasize(\"runtime\"))".to_string(),
+        ]);
+
+        let mut dispatch = vec!["switch shr(224, calldataload(0))".to_string()];
+        for f in self.definition.functions.iter().filter(|f| {
+            !f.is_init && matches!(f.vis, FunctionVisibility::Pub | FunctionVisibility::View(_))
+        }) {
+            let args: Vec<String> = (0..f.params.len())
+                .map(|i| format!("calldataload({})", 4 + i * 32))
+                .collect();
+            let call = format!("fn_{}({})", f.name.name, args.join(", "));
+            dispatch.push(format!("case {} {{", selector(f)));
+            if f.return_ty.ty() == &TypeVariant::Unit {
+                dispatch.extend([format!("    {}", call), "    return(0, 0)".to_string()]);
+            } else {
+                dispatch.extend([
+                    format!("    mstore(0, {})", call),
+                    "    return(0, 32)".to_string(),
+                ]);
+            }
+            dispatch.push("}".to_string());
+        }
+        dispatch.push("default { revert(0, 0) }".to_string());
+
+        let object = format!(
+            "object \"Contract\" {{\n    code {{\n{}\n\n{}\n    }}\n\n    object \"runtime\" {{\n        code {{\n{}\n\n{}\n        }}\n    }}\n}}\n",
+            indent(&deploy.join("\n"), 2),
+            indent(&functions, 2),
+            indent(&dispatch.join("\n"), 3),
+            indent(&functions, 3),
+        );
+
+        EvmArtifacts {
+            yul_bytes: object.into_bytes(),
+            abi: abi_json(self.definition),
+        }
+    }
+
+    /// Create a unique name of the temporary variable.
+    pub fn temp_var(&mut self) -> String {
+        let name = format!("_t{}", self.temp_counter);
+        self.temp_counter += 1;
+        name
+    }
+}
+
+/// Id of the state stored in [`STATE_SLOT`].
+pub fn state_id(state: usize) -> u128 {
+    state as u128 + 1
+}
+
+/// Storage slot of the state field.
+pub fn field_slot(state: usize, field: usize) -> u128 {
+    state_id(state) << 64 | field as u128
+}
+
+/// Indent every non-empty line of the text by the number of levels.
+pub fn indent(text: &str, level: usize) -> String {
+    let prefix = "    ".repeat(level);
+    text.lines()
+        .map(|l| {
+            if l.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", prefix, l)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -0,0 +1,254 @@
+use folidity_diagnostics::{
+    Report,
+    Span,
+};
+use folidity_semantics::{
+    ast::{
+        Assign,
+        Expression,
+        FuncReturnType,
+        IfElse,
+        StateBody,
+        Statement,
+        TypeVariant,
+        Variable,
+    },
+    symtable::Scope,
+};
+
+use super::{
+    expression::emit_expression,
+    field_slot,
+    function::{
+        state_fields,
+        ConcreteVar,
+        EvmArgs,
+    },
+    indent,
+    state_id,
+    STATE_SLOT,
+};
+
+type EmitResult = Result<(), ()>;
+
+pub fn emit_statement(stmt: &Statement, lines: &mut Vec<String>, args: &mut EvmArgs) -> EmitResult {
+    match stmt {
+        Statement::Variable(var) => variable(var, lines, args),
+        Statement::Assign(a) => assign(a, lines, args),
+        Statement::IfElse(b) => if_else(b, lines, args),
+        Statement::Return(r) => return_(&r.expr, lines, args),
+        Statement::Expression(e) => {
+            let value = emit_expression(e, lines, args)?;
+            if e.ty() == &TypeVariant::Unit {
+                lines.push(value);
+            } else {
+                lines.push(format!("pop({})", value));
+            }
+            Ok(())
+        }
+        Statement::StateTransition(e) => state_transition(e, lines, args),
+        Statement::Block(b) => {
+            let mut block_lines = vec![];
+            block(&b.statements, &mut block_lines, args)?;
+            lines.push(format!("{{\n{}\n}}", indent(&block_lines.join("\n"), 1)));
+            Ok(())
+        }
+        Statement::ForLoop(l) => unsupported(&l.loc, "Loops", args),
+        Statement::While(l) => unsupported(&l.loc, "Loops", args),
+        Statement::Iterator(it) => unsupported(&it.loc, "Iterators", args),
+        Statement::Match(m) => unsupported(&m.loc, "Match statements", args),
+        Statement::Skip(loc) | Statement::Break(loc) => unsupported(loc, "Loops", args),
+        Statement::Emit(e) => unsupported(&e.loc, "Events", args),
+        Statement::Error(_) => unreachable!(),
+    }
+}
+
+fn unsupported(loc: &Span, what: &str, args: &mut EvmArgs) -> EmitResult {
+    args.diagnostics.push(Report::emit_error(
+        loc.clone(),
+        format!("{} are not supported by the EVM target.", what),
+    ));
+    Err(())
+}
+
+fn block(stmts: &[Statement], lines: &mut Vec<String>, args: &mut EvmArgs) -> EmitResult {
+    let mut error = false;
+
+    for stmt in stmts {
+        error |= emit_statement(stmt, lines, args).is_err();
+    }
+
+    if error {
+        return Err(());
+    }
+
+    Ok(())
+}
+
+fn variable(var: &Variable, lines: &mut Vec<String>, args: &mut EvmArgs) -> EmitResult {
+    if var.names.len() != 1 {
+        return unsupported(&var.loc, "Destructuring assignments", args);
+    }
+
+    let value = match &var.value {
+        Some(e) => emit_expression(e, lines, args)?,
+        None => "0".to_string(),
+    };
+    let name = format!("v_{}", var.pos);
+    lines.push(format!("let {} := {}", name, value));
+    args.concrete_vars.insert(var.pos, ConcreteVar::Value(name));
+
+    Ok(())
+}
+
+fn assign(a: &Assign, lines: &mut Vec<String>, args: &mut EvmArgs) -> EmitResult {
+    if !a.fields.is_empty() {
+        return unsupported(&a.loc, "Field assignments", args);
+    }
+    let value = emit_expression(&a.value, lines, args)?;
+    let Some(ConcreteVar::Value(name)) = args.concrete_vars.get(&a.pos) else {
+        return unsupported(&a.loc, "Assignments to non-local variables", args);
+    };
+    lines.push(format!("{} := {}", name, value));
+
+    Ok(())
+}
+
+fn if_else(b: &IfElse, lines: &mut Vec<String>, args: &mut EvmArgs) -> EmitResult {
+    let mut error = false;
+    let condition = emit_expression(&b.condition, lines, args)?;
+
+    let mut body_lines = vec![];
+    error |= block(&b.body, &mut body_lines, args).is_err();
+    let mut else_lines = vec![];
+    error |= block(&b.else_part, &mut else_lines, args).is_err();
+
+    lines.extend([
+        format!("switch {}", condition),
+        "case 0 {".to_string(),
+        indent(&else_lines.join("\n"), 1),
+        "}".to_string(),
+        "default {".to_string(),
+        indent(&body_lines.join("\n"), 1),
+        "}".to_string(),
+    ]);
+
+    if error {
+        return Err(());
+    }
+
+    Ok(())
+}
+
+fn return_(e: &Option<Expression>, lines: &mut Vec<String>, args: &mut EvmArgs) -> EmitResult {
+    if let Some(expr) = e {
+        let value = emit_expression(expr, lines, args)?;
+        lines.push(format!("ret := {}", value));
+
+        // the named return value can be referenced in the bounds.
+        if let FuncReturnType::ParamType(param) = &args.func.return_ty {
+            let (p_no, _) = args
+                .func
+                .scope
+                .find_var_index(&param.name.name)
+                .expect("should exist");
+            args.concrete_vars
+                .insert(p_no, ConcreteVar::Value("ret".to_string()));
+            emit_bounds(lines, args);
+        }
+    }
+    lines.push("leave".to_string());
+
+    Ok(())
+}
+
+/// Store the fields of the new state, and update the current state id.
+///
+/// Bounds of the state and the function are checked before the storage is updated.
+fn state_transition(e: &Expression, lines: &mut Vec<String>, args: &mut EvmArgs) -> EmitResult {
+    let (TypeVariant::State(sym), Expression::StructInit(init)) = (e.ty(), e) else {
+        return unsupported(e.loc(), "Transitions to non-literal states", args);
+    };
+    let fields = state_fields(sym, e.loc(), args)?;
+
+    let mut values = vec![];
+    for a in &init.args {
+        let value = emit_expression(a, lines, args)?;
+        let temp = args.emitter.temp_var();
+        lines.push(format!("let {} := {}", temp, value));
+        values.push(temp);
+    }
+
+    // bounds of the state refer to its fields by name.
+    let state_decl = &args.emitter.definition.states[sym.i];
+    let mut scopes: Vec<&Scope> = vec![];
+    if let Some(bounds) = &state_decl.bounds {
+        args.delayed_bounds.extend_from_slice(&bounds.exprs);
+        scopes.push(&state_decl.scope);
+    }
+    if let Some(StateBody::Model(m_sym)) = &state_decl.body {
+        let model_decl = &args.emitter.definition.models[m_sym.i];
+        if let Some(bounds) = &model_decl.bounds {
+            args.delayed_bounds.extend_from_slice(&bounds.exprs);
+            scopes.push(&model_decl.scope);
+        }
+    }
+    for scope in scopes {
+        for (f, v) in fields.iter().zip(&values) {
+            if let Some((p_no, _)) = scope.find_var_index(&f.name.name) {
+                args.concrete_vars
+                    .insert(p_no, ConcreteVar::Value(v.clone()));
+            }
+        }
+    }
+
+    if let Some(param) = args
+        .func
+        .state_bound
+        .as_ref()
+        .and_then(|b| b.to.iter().find(|s| &s.ty == sym))
+    {
+        if let Some(name) = &param.name {
+            let (p_no, _) = args
+                .func
+                .scope
+                .find_var_index(&name.name)
+                .expect("should exist");
+            args.concrete_vars
+                .insert(p_no, ConcreteVar::Fields(values.clone()));
+        }
+    }
+    emit_bounds(lines, args);
+
+    for (k, v) in values.iter().enumerate() {
+        lines.push(format!("sstore({}, {})", field_slot(sym.i, k), v));
+    }
+    lines.push(format!("sstore({}, {})", STATE_SLOT, state_id(sym.i)));
+
+    Ok(())
+}
+
+/// Emit the checks of the delayed bounds whose variables are known.
+/// The rest are left in the delay.
+pub fn emit_bounds(lines: &mut Vec<String>, args: &mut EvmArgs) {
+    let mut delayed_bounds = vec![];
+    std::mem::swap(args.delayed_bounds, &mut delayed_bounds);
+
+    // save diagnostics state
+    let mut diagnostics = vec![];
+    std::mem::swap(args.diagnostics, &mut diagnostics);
+
+    for e in &delayed_bounds {
+        let mut try_lines = vec![];
+        // if expression can not be emitted, we add it back to the arguments.
+        let Ok(value) = emit_expression(e, &mut try_lines, args) else {
+            args.delayed_bounds.push(e.clone());
+            continue;
+        };
+        lines.extend(try_lines);
+        lines.push(format!("if iszero({}) {{ revert(0, 0) }}", value));
+    }
+
+    // recover the state.
+    std::mem::swap(args.diagnostics, &mut diagnostics);
+}
@@ -7,6 +7,8 @@ use folidity_semantics::{
     ast::{
         BinaryExpression,
         Bounds,
+        BuiltinCall,
+        Conditional,
         Expression,
         FunctionCall,
         MemberAccess,
@@ -15,8 +17,11 @@ use folidity_semantics::{
         StructInit,
         TypeVariant,
         UnaryExpression,
+        VariantInit,
     },
+    builtin::BuiltinFunction,
     symtable::Scope,
+    ContractDefinition,
 };
 use num_bigint::{
     BigInt,
@@ -27,16 +32,53 @@ use num_traits::ToPrimitive;
 
 use crate::{
     ast::{
+        has_size_block,
+        struct_size,
+        tuple_fields,
         Chunk,
         Constant,
         Instruction,
         TypeSizeHint,
+        ENUM_HEADER_SIZE,
+        OPTION_HEADER_SIZE,
     },
+    checked::emit_or,
+    crypto::emit_crypto_call,
+    integer::{
+        emit_cast,
+        emit_range_check,
+        u256,
+    },
+    interface::emit_interface_call,
+    list::{
+        emit_contains,
+        emit_index,
+        emit_list_call,
+        emit_set_equality,
+        emit_slice,
+        prefix_size,
+    },
+    mapping::{
+        emit_mapping_call,
+        emit_mapping_field,
+        to_bytes,
+    },
+    string::emit_string_call,
     teal::EmitArgs,
+    transaction::{
+        emit_context,
+        emit_transaction,
+    },
 };
 
 type EmitResult = Result<u64, ()>;
 
+/// Scale of the fixed-point representation of the floats, i.e. 6 decimal places.
+///
+/// # Note
+/// Must be in sync with the one in `helpers/fixed_point.teal`.
+pub const FLOAT_SCALE: u64 = 1_000_000;
+
 /// Emit expression returning the len of the type in bytes.
 pub fn emit_expression(
     expr: &Expression,
@@ -48,7 +90,8 @@ pub fn emit_expression(
 
         // literals
         Expression::Int(u) => int(&u.element, &u.loc, chunks, args),
-        Expression::UInt(u) => uint(&u.element, &u.loc, chunks, args),
+        Expression::UInt(u) if u.ty == TypeVariant::U256 => u256(&u.element, chunks),
+        Expression::UInt(u) => uint(u, chunks, args),
         Expression::Boolean(u) => bool(u, chunks, args),
         Expression::Char(u) => char(u, chunks, args),
         Expression::String(u) => string(u, chunks),
@@ -58,11 +101,12 @@ pub fn emit_expression(
         Expression::Float(u) => float(u, chunks, args),
 
         // operations
-        Expression::Add(b) => add(b, chunks, args),
-        Expression::Subtract(b) => sub(b, chunks, args),
-        Expression::Multiply(b) => mul(b, chunks, args),
-        Expression::Divide(b) => div(b, chunks, args),
-        Expression::Modulo(b) => modulo(b, chunks, args),
+        Expression::Add(b) if b.ty == TypeVariant::String => concat(b, chunks, args),
+        Expression::Add(b)
+        | Expression::Subtract(b)
+        | Expression::Multiply(b)
+        | Expression::Divide(b)
+        | Expression::Modulo(b) => arithmetic(expr, b, chunks, args),
         Expression::Equal(b) => eq(b, chunks, args),
         Expression::NotEqual(b) => neq(b, chunks, args),
         Expression::Greater(b) => ge(b, chunks, args),
@@ -75,25 +119,35 @@ pub fn emit_expression(
 
         // Complex
         Expression::FunctionCall(f) => func_call(f, chunks, args),
+        Expression::BuiltinCall(f) => builtin_call(f, chunks, args),
+        Expression::InterfaceCall(f) => emit_interface_call(f, chunks, args),
         Expression::In(b) => in_(b, chunks, args),
         Expression::MemberAccess(m) => member_access(m, chunks, args),
         Expression::StructInit(s) => struct_init(s, chunks, args),
+        Expression::VariantInit(v) => variant_init(v, chunks, args),
         Expression::List(u) => list(u, chunks, args),
+        Expression::Optional(u) => optional(u, chunks, args),
+        Expression::Tuple(u) => tuple(u, chunks, args),
+        Expression::Conditional(c) => conditional(c, chunks, args),
+        Expression::Index(i) => emit_index(i, chunks, args),
+        Expression::Slice(s) => emit_slice(s, chunks, args),
+        Expression::Lambda(l) => {
+            args.diagnostics.push(Report::emit_error(
+                l.loc.clone(),
+                "Lambdas can only be passed to `map` and `filter`.".to_string(),
+            ));
+            Err(())
+        }
     }
 }
 
-// todo: write a support teal function to checking inclusion and use it here.
-fn in_(b: &BinaryExpression, _chunks: &mut [Chunk], args: &mut EmitArgs) -> EmitResult {
-    args.diagnostics.push(Report::emit_error(
-        b.loc.clone(),
-        "Unsupported currently".to_string(),
-    ));
-    Err(())
+/// Check the membership of the element in the list or set.
+fn in_(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    emit_contains(&b.right, &b.left, chunks, args)
 }
 
 fn member_access(m: &MemberAccess, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
     let mut local_chunks = vec![];
-    let _ = emit_expression(&m.expr, &mut local_chunks, args)?;
 
     let fields = match m.expr.ty() {
         TypeVariant::Struct(sym) => {
@@ -108,6 +162,7 @@ fn member_access(m: &MemberAccess, chunks: &mut Vec<Chunk>, args: &mut EmitArgs)
             let model_decl = &args.emitter.definition.models[sym.i];
             model_decl.fields(args.emitter.definition)
         }
+        TypeVariant::Tuple(tys) => tuple_fields(tys),
         _ => {
             args.diagnostics.push(Report::emit_error(
                 m.loc.clone(),
@@ -117,6 +172,13 @@ fn member_access(m: &MemberAccess, chunks: &mut Vec<Chunk>, args: &mut EmitArgs)
         }
     };
 
+    // mappings are not stored in the struct, so we only need to push its prefix.
+    let field = &fields[m.member.0];
+    if let TypeVariant::Mapping(_) = &field.ty.ty {
+        return emit_mapping_field(m.expr.ty(), field, &m.loc, chunks, args);
+    }
+
+    let _ = emit_expression(&m.expr, &mut local_chunks, args)?;
     extract_field(&fields, m.member.0, None, &mut local_chunks, args)?;
 
     chunks.extend(local_chunks);
@@ -213,57 +275,34 @@ fn init_array(
         Chunk::new_single(Instruction::Store, Constant::Uint(array_index)),
     ]);
 
-    // iteratively parse each argument expression, and store it in the array.
-    let mut loc_offset = (s.args.len() as u64 - 1) * 8;
-    let mut data_offests = vec![];
-    for a in &s.args {
-        // push current offset to the list.
-        data_offests.push(loc_offset);
-
-        // emit expression
-        let size = emit_expression(a, &mut local_chunks, args)?;
+    store_fields(&s.args, array_index, 0, &mut local_chunks, args)?;
 
-        // and store it temporarily.
-        let data_index = args.emitter.scratch_index_incr()?;
-        local_chunks.push(Chunk::new_single(
-            Instruction::Store,
-            Constant::Uint(data_index),
-        ));
-
-        if a.ty().is_resizable() {
-            local_chunks.extend_from_slice(&[
-                Chunk::new_single(Instruction::Load, Constant::Uint(array_index)), // load array
-                Chunk::new_single(Instruction::PushInt, Constant::Uint(size)), // push actual size of data
-                Chunk::new_single(Instruction::Replace, Constant::Uint(loc_offset)), // place it in the block
-                Chunk::new_single(Instruction::Store, Constant::Uint(array_index)), // store the array
-            ]);
-            loc_offset += 8; // increment the offset by 8
-        }
-        local_chunks.extend_from_slice(&[
-            Chunk::new_single(Instruction::Load, Constant::Uint(array_index)), // load array
-            Chunk::new_single(Instruction::Load, Constant::Uint(data_index)),  // load data
-            Chunk::new_single(Instruction::Replace, Constant::Uint(loc_offset)), /* place it in
-                                                                                * the block */
-            Chunk::new_single(Instruction::Store, Constant::Uint(array_index)), // store the array
-        ]);
-
-        // increment offset for the next block.
-        loc_offset += a.ty().size_hint(args.emitter.definition);
-    }
+    // the bounds proven to hold are not asserted, depending on the mode.
+    let asserted: Vec<&Expression> = bounds
+        .iter()
+        .flat_map(|b| &b.exprs)
+        .filter(|e| args.emitter.runtime_checks.asserts(&s.loc, e.loc()))
+        .collect();
+    args.emitter.elided_asserts +=
+        (bounds.as_ref().map_or(0, |b| b.exprs.len()) - asserted.len()) as u64;
 
     // if there are bounds add them to the delay to be resolved after.
-    if let Some(bounds) = bounds {
+    if let Some(bounds) = bounds.as_ref().filter(|_| !asserted.is_empty()) {
         // create concrete chunks and insert them in the lookup table.
         for (i, f) in fields.iter().enumerate() {
             let (p_no, _) = scope.find_var_index(&f.name.name).expect("should exist");
             let mut concrete_chunks = vec![];
-            extract_field(fields, i, Some(array_index), &mut concrete_chunks, args)?;
+            if let TypeVariant::Mapping(_) = &f.ty.ty {
+                emit_mapping_field(&s.ty, f, &s.loc, &mut concrete_chunks, args)?;
+            } else {
+                extract_field(fields, i, Some(array_index), &mut concrete_chunks, args)?;
+            }
             args.emitter.concrete_vars.insert(p_no, concrete_chunks);
         }
 
         let mut error = false;
 
-        for e in &bounds.exprs {
+        for e in asserted {
             error |= emit_expression(e, &mut local_chunks, args).is_err();
             local_chunks.push(Chunk::new_empty(Instruction::Assert));
         }
@@ -286,6 +325,63 @@ fn init_array(
     Ok(())
 }
 
+/// Store the values of the fields in the array kept in the scratch,
+/// the fields are placed one after another starting from the `offset`.
+fn store_fields(
+    exprs: &[Expression],
+    array_index: u64,
+    offset: u64,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> Result<(), ()> {
+    let mut loc_offset = offset;
+    for a in exprs {
+        emit_expression(a, chunks, args)?;
+
+        // mappings live in the boxes of their fields, so we discard the value,
+        // which is either empty or the same field as ensured by the semantics.
+        if let TypeVariant::Mapping(_) = a.ty() {
+            chunks.push(Chunk::new_empty(Instruction::Pop));
+            continue;
+        }
+        // sized integers are stored in their declared width.
+        if matches!(a.ty(), TypeVariant::U8 | TypeVariant::U64) {
+            to_bytes(a.ty(), chunks);
+        }
+
+        // and store it temporarily.
+        let data_index = args.emitter.scratch_index_incr()?;
+        chunks.push(Chunk::new_single(
+            Instruction::Store,
+            Constant::Uint(data_index),
+        ));
+
+        if has_size_block(a.ty()) {
+            chunks.extend_from_slice(&[
+                Chunk::new_single(Instruction::Load, Constant::Uint(array_index)), // load array
+                Chunk::new_single(Instruction::Load, Constant::Uint(data_index)), // load data
+                Chunk::new_empty(Instruction::Len), // actual size of data is known at runtime
+                Chunk::new_empty(Instruction::Itob),
+                Chunk::new_single(Instruction::Replace, Constant::Uint(loc_offset)), // place it in the block
+                Chunk::new_single(Instruction::Store, Constant::Uint(array_index)), // store the array
+            ]);
+            loc_offset += 8; // increment the offset by 8
+        }
+        chunks.extend_from_slice(&[
+            Chunk::new_single(Instruction::Load, Constant::Uint(array_index)), // load array
+            Chunk::new_single(Instruction::Load, Constant::Uint(data_index)),  // load data
+            Chunk::new_single(Instruction::Replace, Constant::Uint(loc_offset)), /* place it in
+                                                                                * the block */
+            Chunk::new_single(Instruction::Store, Constant::Uint(array_index)), // store the array
+        ]);
+
+        // increment offset for the next block.
+        loc_offset += a.ty().size_hint(args.emitter.definition);
+    }
+
+    Ok(())
+}
+
 fn extract_field(
     fields: &[Param],
     member: usize,
@@ -311,7 +407,7 @@ fn extract_field(
             break;
         }
         offset_loc += f.ty.ty.size_hint(args.emitter.definition);
-        if f.ty.ty.is_resizable() {
+        if has_size_block(&f.ty.ty) {
             offset_loc += 8; // add 8 to the offset to accommodate for the size block.
         }
     }
@@ -323,7 +419,7 @@ fn extract_field(
     ]);
 
     let ty = &fields[member].ty.ty;
-    if ty.is_resizable() {
+    if has_size_block(ty) {
         let size_index = args.emitter.scratch_index_incr()?;
         let data_loc = offset_loc + 8;
         local_chunks.extend_from_slice(&[
@@ -347,6 +443,15 @@ fn extract_field(
         TypeVariant::Uint | TypeVariant::Float | TypeVariant::Bool | TypeVariant::Char
     ) {
         local_chunks.push(Chunk::new_empty(Instruction::ExtractUint))
+    } else if matches!(ty, TypeVariant::U8 | TypeVariant::U64) {
+        local_chunks.extend_from_slice(&[
+            Chunk::new_single(
+                Instruction::PushInt,
+                Constant::Uint(ty.size_hint(args.emitter.definition)),
+            ), // size
+            Chunk::new_empty(Instruction::Extract3), // extract data
+            Chunk::new_empty(Instruction::Btoi),
+        ])
     } else {
         local_chunks.extend_from_slice(&[
             Chunk::new_single(
@@ -380,7 +485,9 @@ fn list(
     let mut size = 0;
     let first_elem = &u.element[0];
     if let Ok(s) = emit_expression(first_elem, &mut list_chunks, args) {
-        // after every second element we want to concat them together
+        // elements are stored as bytes.
+        to_bytes(first_elem.ty(), &mut list_chunks);
+        prefix_size(first_elem.ty(), &mut list_chunks);
         size += s;
     } else {
         error |= true;
@@ -389,6 +496,8 @@ fn list(
     for e in u.element.iter().skip(1) {
         if let Ok(s) = emit_expression(e, &mut list_chunks, args) {
             // after first element we want to concat with the previous result.
+            to_bytes(e.ty(), &mut list_chunks);
+            prefix_size(e.ty(), &mut list_chunks);
             list_chunks.push(Chunk::new_empty(Instruction::Concat));
             size += s;
         } else {
@@ -408,6 +517,22 @@ fn list(
 fn func_call(f: &FunctionCall, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
     let mut arg_chunks: Vec<Chunk> = vec![];
 
+    // the scratch slots are shared by all invocations of the function,
+    // so the callee calling back the caller would overwrite its variables.
+    // the slots allocated so far are saved on the stack below the arguments.
+    let reentrant = args
+        .emitter
+        .current_func
+        .is_some_and(|i| args.emitter.recursive_cycles[f.sym.i].contains(&i));
+    let frame: Vec<u64> = if reentrant {
+        (args.emitter.frame_start..args.emitter.scratch_index).collect()
+    } else {
+        vec![]
+    };
+    for slot in &frame {
+        arg_chunks.push(Chunk::new_single(Instruction::Load, Constant::Uint(*slot)));
+    }
+
     let mut error = false;
     for e in &f.args {
         error |= emit_expression(e, &mut arg_chunks, args).is_err();
@@ -428,115 +553,213 @@ fn func_call(f: &FunctionCall, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) ->
         Constant::StringLit(name),
     ));
 
+    // restore the slots, keeping the returned value on top of the stack.
+    for slot in frame.iter().rev() {
+        if f.returns != TypeVariant::Unit {
+            chunks.push(Chunk::new_empty(Instruction::Swap));
+        }
+        chunks.push(Chunk::new_single(Instruction::Store, Constant::Uint(*slot)));
+    }
+
     Ok(f.returns.size_hint(args.emitter.definition))
 }
 
-fn add(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
-    // `left + right` should appear in stack as: `left => right => +`
+/// Convert the value between the types presented as bytes.
+///
+/// Only the length of `hex` converted to `address` is checked.
+fn bytes_cast(f: &BuiltinCall, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    emit_expression(&f.args[0], chunks, args)?;
+    if f.returns == TypeVariant::Address {
+        chunks.extend_from_slice(&[
+            Chunk::new_empty(Instruction::Dup),
+            Chunk::new_empty(Instruction::Len),
+            Chunk::new_single(Instruction::PushInt, Constant::Uint(32)),
+            Chunk::new_empty(Instruction::Eq),
+            Chunk::new_empty(Instruction::Assert),
+        ]);
+    }
 
-    let mut local_chunks = vec![];
-    let _ = emit_expression(&b.left, &mut local_chunks, args)?;
-    let _ = emit_expression(&b.right, &mut local_chunks, args)?;
+    Ok(f.returns.size_hint(args.emitter.definition))
+}
 
-    let chunk = match &b.left.ty() {
-        TypeVariant::Uint | TypeVariant::Float => Chunk::new_empty(Instruction::Plus),
-        TypeVariant::Int => {
-            Chunk::new_single(
-                Instruction::CallSub,
-                Constant::StringLit("signed_add".to_string()),
-            )
+/// Emit the assertion of the condition.
+///
+/// The message is logged before the program fails, so it appears in the failed
+/// transaction.
+fn emit_assertion(f: &BuiltinCall, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    emit_expression(&f.args[0], chunks, args)?;
+    let Some(message) = f.args.get(1) else {
+        chunks.push(Chunk::new_empty(Instruction::Assert));
+        return Ok(f.returns.size_hint(args.emitter.definition));
+    };
+
+    let index = args.emitter.cond_index_incr()?;
+    let ok_label = format!("{}_assert_ok", index);
+    chunks.push(Chunk::new_single(
+        Instruction::BranchNotZero,
+        Constant::StringLit(ok_label.clone()),
+    ));
+    emit_expression(message, chunks, args)?;
+    chunks.extend_from_slice(&[
+        Chunk::new_empty(Instruction::Log),
+        Chunk::new_empty(Instruction::Error),
+        Chunk::new_empty(Instruction::Label(ok_label)),
+    ]);
+
+    Ok(f.returns.size_hint(args.emitter.definition))
+}
+
+fn builtin_call(f: &BuiltinCall, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    match f.func {
+        BuiltinFunction::Cast if f.returns.is_integer() => return emit_cast(f, chunks, args),
+        BuiltinFunction::Cast => return bytes_cast(f, chunks, args),
+        BuiltinFunction::Or => return emit_or(f, chunks, args),
+        BuiltinFunction::Assert | BuiltinFunction::Require => {
+            return emit_assertion(f, chunks, args)
         }
-        TypeVariant::String => Chunk::new_empty(Instruction::Plus),
+        BuiltinFunction::Caller | BuiltinFunction::CurrentBlock | BuiltinFunction::Balance => {
+            return emit_context(f, chunks, args)
+        }
+        BuiltinFunction::Pay | BuiltinFunction::AssetTransfer => {
+            return emit_transaction(f, chunks, args)
+        }
+        BuiltinFunction::Hash
+        | BuiltinFunction::Sha256
+        | BuiltinFunction::Keccak256
+        | BuiltinFunction::Ed25519Verify => return emit_crypto_call(f, chunks, args),
+        BuiltinFunction::Pow | BuiltinFunction::Sqrt => return emit_math(f, chunks, args),
+        _ => {}
+    }
+    match f.args.first().map(|a| a.ty()) {
+        Some(TypeVariant::Mapping(_)) => emit_mapping_call(f, chunks, args),
+        Some(TypeVariant::List(_) | TypeVariant::Set(_)) => emit_list_call(f, chunks, args),
+        Some(TypeVariant::String) => emit_string_call(f, chunks, args),
         _ => {
             args.diagnostics.push(Report::emit_error(
-                b.loc.clone(),
-                "This type is not yet supported".to_string(),
+                f.loc.clone(),
+                format!("`{}` is not yet supported for this type.", f.func),
             ));
-            return Err(());
+            Err(())
         }
-    };
-
-    local_chunks.push(chunk);
-    chunks.extend(local_chunks);
-
-    Ok(b.ty.size_hint(args.emitter.definition))
+    }
 }
 
-fn sub(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
-    // `left - right` should appear in stack as: `left => right => -`
-
+/// Emit the power or the square root of the number.
+///
+/// The power of the unsigned integers is computed with `expw`,
+/// asserting that it fits in 64 bits, while the signed integers and the floats
+/// are handled by the helpers.
+fn emit_math(f: &BuiltinCall, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let callsub =
+        |name: &str| Chunk::new_single(Instruction::CallSub, Constant::StringLit(name.to_string()));
     let mut local_chunks = vec![];
-    let _ = emit_expression(&b.left, &mut local_chunks, args)?;
-    let _ = emit_expression(&b.right, &mut local_chunks, args)?;
+    for a in &f.args {
+        emit_expression(a, &mut local_chunks, args)?;
+    }
 
-    let chunk = match &b.left.ty() {
-        TypeVariant::Uint | TypeVariant::Float => Chunk::new_empty(Instruction::Minus),
-        TypeVariant::Int => {
-            Chunk::new_single(
-                Instruction::CallSub,
-                Constant::StringLit("signed_sub".to_string()),
-            )
+    match (f.func, &f.returns) {
+        (BuiltinFunction::Pow, TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64) => {
+            // the high word of the power is zero.
+            local_chunks.extend_from_slice(&[
+                Chunk::new_empty(Instruction::ExpW),
+                Chunk::new_empty(Instruction::Swap),
+                Chunk::new_empty(Instruction::Not),
+                Chunk::new_empty(Instruction::Assert),
+            ]);
+        }
+        (BuiltinFunction::Pow, TypeVariant::Int | TypeVariant::I64) => {
+            local_chunks.push(callsub("signed_pow"))
+        }
+        (BuiltinFunction::Pow, TypeVariant::Float) => local_chunks.push(callsub("fixed_pow")),
+        (BuiltinFunction::Sqrt, TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64) => {
+            local_chunks.push(Chunk::new_empty(Instruction::Sqrt))
         }
+        (BuiltinFunction::Sqrt, TypeVariant::U256) => {
+            local_chunks.push(Chunk::new_empty(Instruction::BSqrt))
+        }
+        (BuiltinFunction::Sqrt, TypeVariant::Int | TypeVariant::I64) => {
+            local_chunks.push(callsub("signed_sqrt"))
+        }
+        (BuiltinFunction::Sqrt, TypeVariant::Float) => local_chunks.push(callsub("fixed_sqrt")),
         _ => {
             args.diagnostics.push(Report::emit_error(
-                b.loc.clone(),
-                "This type is not yet supported".to_string(),
+                f.loc.clone(),
+                format!("`{}` is not yet supported for this type.", f.func),
             ));
             return Err(());
         }
-    };
-
-    local_chunks.push(chunk);
+    }
+    emit_range_check(&f.returns, &mut local_chunks);
     chunks.extend(local_chunks);
 
-    Ok(b.ty.size_hint(args.emitter.definition))
+    Ok(f.returns.size_hint(args.emitter.definition))
 }
 
-fn mul(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
-    // `left * right` should appear in stack as: `left => right => *`
-
+/// Emit the arithmetic operation.
+///
+/// `left op right` should appear in stack as: `left => right => op`
+fn arithmetic(
+    e: &Expression,
+    b: &BinaryExpression,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
     let mut local_chunks = vec![];
     let _ = emit_expression(&b.left, &mut local_chunks, args)?;
     let _ = emit_expression(&b.right, &mut local_chunks, args)?;
 
-    let chunk = match &b.left.ty() {
-        TypeVariant::Uint | TypeVariant::Float => Chunk::new_empty(Instruction::Mul),
-        TypeVariant::Int => {
-            Chunk::new_single(
-                Instruction::CallSub,
-                Constant::StringLit("signed_mul".to_string()),
-            )
-        }
-        _ => {
-            args.diagnostics.push(Report::emit_error(
-                b.loc.clone(),
-                "This type is not yet supported".to_string(),
-            ));
-            return Err(());
-        }
-    };
-
-    local_chunks.push(chunk);
+    local_chunks.push(arithmetic_op(e, b, args)?);
+    emit_range_check(&b.ty, &mut local_chunks);
     chunks.extend(local_chunks);
 
     Ok(b.ty.size_hint(args.emitter.definition))
 }
 
-fn div(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
-    // `left / right` should appear in stack as: `left => right => /`
+/// Instruction of the arithmetic operation over the operands on top of the stack.
+pub fn arithmetic_op(
+    e: &Expression,
+    b: &BinaryExpression,
+    args: &mut EmitArgs,
+) -> Result<Chunk, ()> {
+    let callsub =
+        |name: &str| Chunk::new_single(Instruction::CallSub, Constant::StringLit(name.to_string()));
+
+    let chunk = match (e, b.left.ty()) {
+        (
+            Expression::Add(_),
+            TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64 | TypeVariant::Float,
+        ) => Chunk::new_empty(Instruction::Plus),
+        (Expression::Add(_), TypeVariant::Int | TypeVariant::I64) => callsub("signed_add"),
+        (Expression::Add(_), TypeVariant::U256) => Chunk::new_empty(Instruction::BPlus),
+
+        (
+            Expression::Subtract(_),
+            TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64 | TypeVariant::Float,
+        ) => Chunk::new_empty(Instruction::Minus),
+        (Expression::Subtract(_), TypeVariant::Int | TypeVariant::I64) => callsub("signed_sub"),
+        (Expression::Subtract(_), TypeVariant::U256) => Chunk::new_empty(Instruction::BMinus),
+
+        (Expression::Multiply(_), TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64) => {
+            Chunk::new_empty(Instruction::Mul)
+        }
+        // the product has to be scaled back.
+        (Expression::Multiply(_), TypeVariant::Float) => callsub("fixed_mul"),
+        (Expression::Multiply(_), TypeVariant::Int | TypeVariant::I64) => callsub("signed_mul"),
+        (Expression::Multiply(_), TypeVariant::U256) => Chunk::new_empty(Instruction::BMul),
 
-    let mut local_chunks = vec![];
-    let _ = emit_expression(&b.left, &mut local_chunks, args)?;
-    let _ = emit_expression(&b.right, &mut local_chunks, args)?;
+        (Expression::Divide(_), TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64) => {
+            Chunk::new_empty(Instruction::Div)
+        }
+        // the dividend has to be scaled up before the division.
+        (Expression::Divide(_), TypeVariant::Float) => callsub("fixed_div"),
+        (Expression::Divide(_), TypeVariant::Int | TypeVariant::I64) => callsub("signed_div"),
+        (Expression::Divide(_), TypeVariant::U256) => Chunk::new_empty(Instruction::BDiv),
 
-    let chunk = match &b.left.ty() {
-        TypeVariant::Uint | TypeVariant::Float => Chunk::new_empty(Instruction::Div),
-        TypeVariant::Int => {
-            Chunk::new_single(
-                Instruction::CallSub,
-                Constant::StringLit("signed_div".to_string()),
-            )
+        (Expression::Modulo(_), TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64) => {
+            Chunk::new_empty(Instruction::Mod)
         }
+        (Expression::Modulo(_), TypeVariant::Int | TypeVariant::I64) => callsub("signed_mod"),
+        (Expression::Modulo(_), TypeVariant::U256) => Chunk::new_empty(Instruction::BMod),
         _ => {
             args.diagnostics.push(Report::emit_error(
                 b.loc.clone(),
@@ -546,38 +769,52 @@ fn div(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> Em
         }
     };
 
-    local_chunks.push(chunk);
-    chunks.extend(local_chunks);
-
-    Ok(b.ty.size_hint(args.emitter.definition))
+    Ok(chunk)
 }
 
-fn modulo(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
-    let mut local_chunks = vec![];
-    let _ = emit_expression(&b.left, &mut local_chunks, args)?;
-    let _ = emit_expression(&b.right, &mut local_chunks, args)?;
-
-    let chunk = match &b.left.ty() {
-        TypeVariant::Uint => Chunk::new_empty(Instruction::Mod),
-        TypeVariant::Int => {
-            Chunk::new_single(
-                Instruction::CallSub,
-                Constant::StringLit("signed_mod".to_string()),
-            )
+/// Emit string concatenation.
+///
+/// The chain of additions is flattened first, and adjacent string literals are folded
+/// into a single constant, so `x + "a" + "b"` is emitted as `x => "ab" => concat`.
+fn concat(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let mut operands = vec![];
+    flatten_concat(&b.left, &mut operands);
+    flatten_concat(&b.right, &mut operands);
+
+    let mut folded: Vec<Expression> = vec![];
+    for e in operands {
+        match (folded.last_mut(), e) {
+            (Some(Expression::String(prev)), Expression::String(next)) => {
+                prev.element.push_str(&next.element);
+            }
+            (_, e) => folded.push(e.clone()),
         }
-        _ => {
-            args.diagnostics.push(Report::emit_error(
-                b.loc.clone(),
-                "This type is not yet supported".to_string(),
-            ));
-            return Err(());
+    }
+
+    let mut local_chunks = vec![];
+    let mut size = 0;
+    for (i, e) in folded.iter().enumerate() {
+        size += emit_expression(e, &mut local_chunks, args)?;
+        // after the first operand we want to concat with the previous result.
+        if i > 0 {
+            local_chunks.push(Chunk::new_empty(Instruction::Concat));
         }
-    };
+    }
 
-    local_chunks.push(chunk);
     chunks.extend(local_chunks);
 
-    Ok(b.ty.size_hint(args.emitter.definition))
+    Ok(size)
+}
+
+/// Collect operands of the nested string additions in the evaluation order.
+fn flatten_concat<'a>(e: &'a Expression, operands: &mut Vec<&'a Expression>) {
+    match e {
+        Expression::Add(b) if b.ty == TypeVariant::String => {
+            flatten_concat(&b.left, operands);
+            flatten_concat(&b.right, operands);
+        }
+        _ => operands.push(e),
+    }
 }
 
 fn le(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
@@ -586,15 +823,18 @@ fn le(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> Emi
     emit_expression(&b.right, &mut local_chunks, args)?;
 
     let chunk = match &b.left.ty() {
-        TypeVariant::Uint | TypeVariant::Char | TypeVariant::Float => {
-            Chunk::new_empty(Instruction::Less)
-        }
-        TypeVariant::Int => {
+        TypeVariant::Uint
+        | TypeVariant::U8
+        | TypeVariant::U64
+        | TypeVariant::Char
+        | TypeVariant::Float => Chunk::new_empty(Instruction::Less),
+        TypeVariant::Int | TypeVariant::I64 => {
             Chunk::new_single(
                 Instruction::CallSub,
                 Constant::StringLit("signed_le".to_string()),
             )
         }
+        TypeVariant::U256 => Chunk::new_empty(Instruction::BLess),
         _ => {
             args.diagnostics.push(Report::emit_error(
                 b.loc.clone(),
@@ -616,15 +856,18 @@ fn leq(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> Em
     emit_expression(&b.right, &mut local_chunks, args)?;
 
     let chunk = match &b.left.ty() {
-        TypeVariant::Uint | TypeVariant::Char | TypeVariant::Float => {
-            Chunk::new_empty(Instruction::LessEq)
-        }
-        TypeVariant::Int => {
+        TypeVariant::Uint
+        | TypeVariant::U8
+        | TypeVariant::U64
+        | TypeVariant::Char
+        | TypeVariant::Float => Chunk::new_empty(Instruction::LessEq),
+        TypeVariant::Int | TypeVariant::I64 => {
             Chunk::new_single(
                 Instruction::CallSub,
                 Constant::StringLit("signed_leq".to_string()),
             )
         }
+        TypeVariant::U256 => Chunk::new_empty(Instruction::BLessEq),
         _ => {
             args.diagnostics.push(Report::emit_error(
                 b.loc.clone(),
@@ -646,15 +889,18 @@ fn ge(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> Emi
     emit_expression(&b.right, &mut local_chunks, args)?;
 
     let chunk = match &b.left.ty() {
-        TypeVariant::Uint | TypeVariant::Char | TypeVariant::Float => {
-            Chunk::new_empty(Instruction::Greater)
-        }
-        TypeVariant::Int => {
+        TypeVariant::Uint
+        | TypeVariant::U8
+        | TypeVariant::U64
+        | TypeVariant::Char
+        | TypeVariant::Float => Chunk::new_empty(Instruction::Greater),
+        TypeVariant::Int | TypeVariant::I64 => {
             Chunk::new_single(
                 Instruction::CallSub,
                 Constant::StringLit("signed_ge".to_string()),
             )
         }
+        TypeVariant::U256 => Chunk::new_empty(Instruction::BMore),
         _ => {
             args.diagnostics.push(Report::emit_error(
                 b.loc.clone(),
@@ -676,15 +922,18 @@ fn geq(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> Em
     emit_expression(&b.right, &mut local_chunks, args)?;
 
     let chunk = match &b.left.ty() {
-        TypeVariant::Uint | TypeVariant::Char | TypeVariant::Float => {
-            Chunk::new_empty(Instruction::GreaterEq)
-        }
-        TypeVariant::Int => {
+        TypeVariant::Uint
+        | TypeVariant::U8
+        | TypeVariant::U64
+        | TypeVariant::Char
+        | TypeVariant::Float => Chunk::new_empty(Instruction::GreaterEq),
+        TypeVariant::Int | TypeVariant::I64 => {
             Chunk::new_single(
                 Instruction::CallSub,
                 Constant::StringLit("signed_geq".to_string()),
             )
         }
+        TypeVariant::U256 => Chunk::new_empty(Instruction::BMoreEq),
         _ => {
             args.diagnostics.push(Report::emit_error(
                 b.loc.clone(),
@@ -706,7 +955,7 @@ fn eq(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> Emi
     emit_expression(&b.left, &mut local_chunks, args)?;
     emit_expression(&b.right, &mut local_chunks, args)?;
 
-    local_chunks.push(Chunk::new_empty(Instruction::Eq));
+    emit_equality(b.left.ty(), &b.loc, &mut local_chunks, args)?;
 
     chunks.extend(local_chunks);
 
@@ -719,13 +968,83 @@ fn neq(b: &BinaryExpression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> Em
     emit_expression(&b.left, &mut local_chunks, args)?;
     emit_expression(&b.right, &mut local_chunks, args)?;
 
-    local_chunks.push(Chunk::new_empty(Instruction::Neq));
+    if is_bytewise_equatable(b.left.ty(), args.emitter.definition) {
+        local_chunks.push(Chunk::new_empty(Instruction::Neq));
+    } else {
+        emit_equality(b.left.ty(), &b.loc, &mut local_chunks, args)?;
+        local_chunks.push(Chunk::new_empty(Instruction::Not));
+    }
 
     chunks.extend(local_chunks);
 
     Ok(b.ty.size_hint(args.emitter.definition))
 }
 
+/// Are the values of the type equal exactly when their bytes are equal.
+///
+/// The unused space of the resizable fields may be left dirty by the assignments,
+/// and the elements of sets are unordered.
+fn is_bytewise_equatable(ty: &TypeVariant, contract: &ContractDefinition) -> bool {
+    let fields = match ty {
+        TypeVariant::Set(_) => return false,
+        TypeVariant::Tuple(tys) => tuple_fields(tys),
+        _ => {
+            let Some(fields) = ty.fields(contract) else {
+                return true;
+            };
+            fields
+        }
+    };
+    fields.iter().all(|f| {
+        !has_size_block(&f.ty.ty) && (f.recursive || is_bytewise_equatable(&f.ty.ty, contract))
+    })
+}
+
+/// Compare the two values of the type on top of the stack.
+///
+/// Aggregates with the resizable fields are compared field by field,
+/// and sets by their elements, other values are compared as bytes.
+fn emit_equality(
+    ty: &TypeVariant,
+    loc: &Span,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> Result<(), ()> {
+    let definition = args.emitter.definition;
+    if is_bytewise_equatable(ty, definition) {
+        chunks.push(Chunk::new_empty(Instruction::Eq));
+        return Ok(());
+    }
+    let fields = match ty {
+        TypeVariant::Set(item) => return emit_set_equality(item, loc, chunks, args),
+        TypeVariant::Tuple(tys) => tuple_fields(tys),
+        _ => {
+            ty.fields(definition)
+                .expect("only aggregates have resizable fields")
+        }
+    };
+
+    let right_index = args.emitter.scratch_index_incr()?;
+    let left_index = args.emitter.scratch_index_incr()?;
+    chunks.extend_from_slice(&[
+        Chunk::new_single(Instruction::Store, Constant::Uint(right_index)),
+        Chunk::new_single(Instruction::Store, Constant::Uint(left_index)),
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(1)),
+    ]);
+    for (i, f) in fields.iter().enumerate() {
+        // mappings live in their own boxes.
+        if let TypeVariant::Mapping(_) = &f.ty.ty {
+            continue;
+        }
+        extract_field(&fields, i, Some(left_index), chunks, args)?;
+        extract_field(&fields, i, Some(right_index), chunks, args)?;
+        emit_equality(&f.ty.ty, loc, chunks, args)?;
+        chunks.push(Chunk::new_empty(Instruction::And));
+    }
+
+    Ok(())
+}
+
 fn not(
     u: &UnaryExpression<Box<Expression>>,
     chunks: &mut Vec<Chunk>,
@@ -813,7 +1132,7 @@ fn var(u: &UnaryExpression<usize>, chunks: &mut Vec<Chunk>, args: &mut EmitArgs)
         return Err(());
     };
 
-    let c = Constant::Uint(var.index as u64);
+    let c = Constant::Uint(var.index);
     let chunk = Chunk::new_single(Instruction::Load, c);
 
     chunks.push(chunk);
@@ -822,10 +1141,10 @@ fn var(u: &UnaryExpression<usize>, chunks: &mut Vec<Chunk>, args: &mut EmitArgs)
 }
 
 /// Handle unsigned integers.
-fn uint(n: &BigUint, loc: &Span, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
-    let Some(int_val) = n.to_u64() else {
+fn uint(u: &UnaryExpression<BigUint>, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let Some(int_val) = u.element.to_u64() else {
         args.diagnostics.push(Report::emit_error(
-            loc.clone(),
+            u.loc.clone(),
             String::from("Integer value is too large."),
         ));
         return Err(());
@@ -835,7 +1154,7 @@ fn uint(n: &BigUint, loc: &Span, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -
     let chunk = Chunk::new_single(Instruction::PushInt, c);
     chunks.push(chunk);
 
-    Ok(TypeVariant::Uint.size_hint(args.emitter.definition))
+    Ok(u.ty.size_hint(args.emitter.definition))
 }
 
 /// Handle unsigned integers.
@@ -848,7 +1167,14 @@ fn int(n: &BigInt, loc: &Span, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) ->
         return Err(());
     };
 
-    let abs = int_val.unsigned_abs();
+    int_constant(int_val, chunks);
+
+    Ok(TypeVariant::Int.size_hint(args.emitter.definition))
+}
+
+/// Emit the signed integer as the sign block followed by the absolute value.
+pub(crate) fn int_constant(value: i64, chunks: &mut Vec<Chunk>) {
+    let abs = value.unsigned_abs();
     let c = Constant::Uint(abs);
     let chunk = Chunk::new_single(Instruction::PushInt, c);
 
@@ -859,14 +1185,12 @@ fn int(n: &BigInt, loc: &Span, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) ->
         Chunk::new_single(Instruction::Replace, Constant::Uint(8)),
     ]);
 
-    if int_val.is_negative() {
+    if value.is_negative() {
         chunks.extend_from_slice(&[
             Chunk::new_single(Instruction::PushInt, Constant::Uint(1)),
             Chunk::new_single(Instruction::Replace, Constant::Uint(0)),
         ]);
     }
-
-    Ok(TypeVariant::Int.size_hint(args.emitter.definition))
 }
 
 /// Handle boolean values as `1` and `0` in Teal.
@@ -921,38 +1245,210 @@ fn address(
 }
 
 /// Handle enum literals, we construct it from `bytes(enums_pos) ++ byte(variant_number)`
+///
+/// The value is padded with zeros to the size of the largest payload of the enum.
 fn enum_(u: &UnaryExpression<usize>, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
-    let TypeVariant::Enum(s) = &u.ty else {
+    let size = u.ty.size_hint(args.emitter.definition);
+    if size > ENUM_HEADER_SIZE {
+        chunks.extend_from_slice(&[
+            Chunk::new_single(Instruction::PushInt, Constant::Uint(size)),
+            Chunk::new_empty(Instruction::ArrayInit),
+            enum_header(&u.ty, u.element),
+            Chunk::new_single(Instruction::Replace, Constant::Uint(0)),
+        ]);
+    } else {
+        chunks.push(enum_header(&u.ty, u.element));
+    }
+
+    Ok(size)
+}
+
+/// Handle enum variants with the payload, the fields of the payload are stored
+/// after the header of the enum in the same way as the fields of structs.
+fn variant_init(v: &VariantInit, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let array_index = args.emitter.scratch_index_incr()?;
+    let size = v.ty.size_hint(args.emitter.definition);
+    let mut local_chunks = vec![
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(size)),
+        Chunk::new_empty(Instruction::ArrayInit),
+        enum_header(&v.ty, v.variant),
+        Chunk::new_single(Instruction::Replace, Constant::Uint(0)),
+        Chunk::new_single(Instruction::Store, Constant::Uint(array_index)),
+    ];
+    store_fields(
+        &v.args,
+        array_index,
+        ENUM_HEADER_SIZE,
+        &mut local_chunks,
+        args,
+    )?;
+    local_chunks.push(Chunk::new_single(
+        Instruction::Load,
+        Constant::Uint(array_index),
+    ));
+    chunks.extend(local_chunks);
+
+    Ok(size)
+}
+
+/// Push the index of the enum and the tag of the variant.
+fn enum_header(ty: &TypeVariant, variant: usize) -> Chunk {
+    let TypeVariant::Enum(s) = ty else {
         unreachable!()
     };
 
     let mut enum_value: Vec<u8> = vec![];
     enum_value.extend_from_slice(&s.i.to_be_bytes());
-    enum_value.extend_from_slice(&u.element.to_be_bytes());
+    enum_value.extend_from_slice(&variant.to_be_bytes());
 
-    let c = Constant::Bytes(enum_value);
-    let chunk = Chunk::new_single(Instruction::PushBytes, c);
-    chunks.push(chunk);
+    Chunk::new_single(Instruction::PushBytes, Constant::Bytes(enum_value))
+}
 
-    Ok(u.ty.size_hint(args.emitter.definition))
+/// Push the fields of the payload of the variant from the enum value on top of the stack.
+pub fn extract_payload_field(
+    fields: &[Param],
+    member: usize,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> Result<(), ()> {
+    chunks.extend_from_slice(&[
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(ENUM_HEADER_SIZE)),
+        Chunk::new_single(
+            Instruction::PushInt,
+            Constant::Uint(struct_size(fields, args.emitter.definition)),
+        ),
+        Chunk::new_empty(Instruction::Extract3),
+    ]);
+    extract_field(fields, member, None, chunks, args)?;
+    Ok(())
 }
 
-/// Handle rational literals, for now we simply present them at f64 IEEE 754 standard.
+/// Handle tuples, the elements are stored in the same way as the fields of structs.
+fn tuple(
+    u: &UnaryExpression<Vec<Expression>>,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
+    let array_index = args.emitter.scratch_index_incr()?;
+    let size = u.ty.size_hint(args.emitter.definition);
+    let mut local_chunks = vec![
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(size)),
+        Chunk::new_empty(Instruction::ArrayInit),
+        Chunk::new_single(Instruction::Store, Constant::Uint(array_index)),
+    ];
+    store_fields(&u.element, array_index, 0, &mut local_chunks, args)?;
+    local_chunks.push(Chunk::new_single(
+        Instruction::Load,
+        Constant::Uint(array_index),
+    ));
+    chunks.extend(local_chunks);
+
+    Ok(size)
+}
+
+/// Emit the value of the branch selected by the condition.
+///
+/// Only the selected branch is evaluated.
+fn conditional(c: &Conditional, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let index = args.emitter.cond_index_incr()?;
+    let else_label = format!("{}_cond_else", index);
+    let end_label = format!("{}_cond_end", index);
+
+    let mut local_chunks = vec![];
+    emit_expression(&c.condition, &mut local_chunks, args)?;
+    local_chunks.push(Chunk::new_single(
+        Instruction::BranchZero,
+        Constant::StringLit(else_label.clone()),
+    ));
+    emit_expression(&c.then_expr, &mut local_chunks, args)?;
+    local_chunks.push(Chunk::new_single(
+        Instruction::Branch,
+        Constant::StringLit(end_label.clone()),
+    ));
+    local_chunks.push(Chunk::new_empty(Instruction::Label(else_label)));
+    emit_expression(&c.else_expr, &mut local_chunks, args)?;
+    local_chunks.push(Chunk::new_empty(Instruction::Label(end_label)));
+    chunks.extend(local_chunks);
+
+    Ok(c.ty.size_hint(args.emitter.definition))
+}
+
+/// Push the tag of the variant from the enum or the option value on top of the stack.
+///
+/// The tag of the option is its presence byte, `none` being zeroed.
+pub fn variant_tag(ty: &TypeVariant) -> Vec<Chunk> {
+    match ty {
+        TypeVariant::Option(_) => {
+            vec![
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(0)),
+                Chunk::new_empty(Instruction::GetByte),
+            ]
+        }
+        _ => {
+            vec![
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(8)),
+                Chunk::new_empty(Instruction::ExtractUint),
+            ]
+        }
+    }
+}
+
+/// Push the value of the option on top of the stack, dropping the presence byte.
+pub fn option_value() -> Chunk {
+    Chunk::new_multiple(
+        Instruction::Extract,
+        vec![Constant::Uint(OPTION_HEADER_SIZE), Constant::Uint(0)],
+    )
+}
+
+/// Handle `some(value)` and `none`.
+///
+/// The value is preceded by the presence byte, `none` is zeroed to the size of the
+/// option.
+fn optional(
+    u: &UnaryExpression<Option<Box<Expression>>>,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
+    let size = u.ty.size_hint(args.emitter.definition);
+    let Some(e) = &u.element else {
+        chunks.extend_from_slice(&[
+            Chunk::new_single(Instruction::PushInt, Constant::Uint(size)),
+            Chunk::new_empty(Instruction::ArrayInit),
+        ]);
+        return Ok(size);
+    };
+
+    let mut local_chunks = vec![Chunk::new_single(
+        Instruction::PushBytes,
+        Constant::Bytes(vec![1]),
+    )];
+    emit_expression(e, &mut local_chunks, args)?;
+    local_chunks.push(Chunk::new_empty(Instruction::Concat));
+    chunks.extend(local_chunks);
+
+    Ok(size)
+}
+
+/// Handle rational literals, they are presented as unsigned fixed-point numbers
+/// scaled by [`FLOAT_SCALE`], the fractional part beyond the scale is truncated.
 fn float(
     u: &UnaryExpression<BigRational>,
     chunks: &mut Vec<Chunk>,
     args: &mut EmitArgs,
 ) -> EmitResult {
-    let Some(float_val) = u.element.to_f64() else {
-        args.diagnostics.push(Report::emit_error(
-            u.loc.clone(),
-            String::from("Rational value is too large."),
-        ));
+    let scaled = (&u.element * BigRational::from_integer(FLOAT_SCALE.into())).to_integer();
+    let Some(val) = scaled.to_u64() else {
+        let msg = if u.element < BigRational::from_integer(0.into()) {
+            "Negative rational values are not supported."
+        } else {
+            "Rational value is too large."
+        };
+        args.diagnostics
+            .push(Report::emit_error(u.loc.clone(), String::from(msg)));
         return Err(());
     };
 
-    let bytes = float_val.to_bits().to_be_bytes();
-    let val = u64::from_be_bytes(bytes);
     let c = Constant::Uint(val);
     let chunk = Chunk::new_single(Instruction::PushInt, c);
     chunks.push(chunk);
@@ -1,6 +1,7 @@
 use folidity_diagnostics::Report;
 use folidity_semantics::{
     ast::{
+        Expression,
         Function,
         FunctionVisibility,
         TypeVariant,
@@ -10,9 +11,11 @@ use folidity_semantics::{
 
 use crate::{
     ast::{
+        locate,
         Chunk,
         Constant,
         Instruction,
+        TypeSizeHint,
     },
     expression::emit_expression,
     scratch_table::ScratchTable,
@@ -23,6 +26,7 @@ use crate::{
     teal::{
         EmitArgs,
         TealEmitter,
+        STATE_KEY,
     },
 };
 
@@ -45,16 +49,21 @@ pub fn emit_function(func: &Function, emitter: &mut TealEmitter) -> Result<Vec<C
 
     // inject arguments as concrete vars.
     // if the function is not a constructor, then the first app arg is a function signature.
-    for (name, _) in &func.params {
+    let mut arg_chunks = vec![];
+    for (name, param) in &func.params {
         let (p_no, _) = func.scope.find_var_index(name).expect("should exist");
-        let arg_index = args.emitter.cond_index_incr()?;
-        chunks.push(Chunk::new_single(
+        // mutable parameters are reassigned as the local variables.
+        let size = param.ty.ty.size_hint(args.emitter.definition);
+        let arg_index = args.scratch.add_var(p_no, size, args.emitter)?;
+        arg_chunks.push(Chunk::new_single(
             Instruction::Store,
             Constant::Uint(arg_index),
         ));
         let arg_chunk = Chunk::new_single(Instruction::Load, Constant::Uint(arg_index));
         args.emitter.concrete_vars.insert(p_no, vec![arg_chunk]);
     }
+    // the last argument is on top of the stack.
+    chunks.extend(arg_chunks.into_iter().rev());
 
     // Inject concrete vars for state bounds.
     if let Some(bounds) = &func.state_bound {
@@ -66,31 +75,38 @@ pub fn emit_function(func: &Function, emitter: &mut TealEmitter) -> Result<Vec<C
         }
     }
 
-    // emit access check chunks.
-    let mut access_chunks = vec![];
-    for e in &func.access_attributes {
-        if e.ty() != &TypeVariant::Address {
-            diagnostics.push(Report::emit_error(
-                e.loc().clone(),
-                "Non-address types are currently unsupported in emitter".to_string(),
-            ));
-            return Err(());
-        }
-
-        error |= emit_expression(e, &mut access_chunks, &mut args).is_err();
-        chunks.push(Chunk::new_single(
-            Instruction::Txn,
-            Constant::StringLit("Sender".to_string()),
-        ));
-    }
-
     // if view function, emit similar concrete state var.
     if let FunctionVisibility::View(s) = &func.vis {
         emit_state_var(&s.name.name, &s.ty, func, &mut args);
     }
 
+    // reject the calls of the senders that are not allowed by any of the access attributes.
+    let mut access_chunks = vec![];
+    for (i, e) in func.access_attributes.iter().enumerate() {
+        error |= emit_access_check(e, &mut access_chunks, &mut args).is_err();
+        if i > 0 {
+            access_chunks.push(Chunk::new_empty(Instruction::Or));
+        }
+    }
+    if !access_chunks.is_empty() {
+        access_chunks.push(Chunk::new_empty(Instruction::Assert));
+    }
     chunks.extend(access_chunks);
 
+    // reject the calls when the contract is not in the state the function transitions from.
+    if let Some(from) = func.state_bound.as_ref().and_then(|b| b.from.as_ref()) {
+        chunks.extend_from_slice(&[
+            Chunk::new_single(
+                Instruction::PushBytes,
+                Constant::String(STATE_KEY.to_string()),
+            ),
+            Chunk::new_empty(Instruction::AppGlobalGet),
+            Chunk::new_single(Instruction::PushInt, Constant::Uint(from.ty.i as u64 + 1)),
+            Chunk::new_empty(Instruction::Eq),
+            Chunk::new_empty(Instruction::Assert),
+        ]);
+    }
+
     // emit bound expressions for input state and args
     // any unresolved expression are added to the delay.
     if let Some(bounds) = &func.bounds {
@@ -107,6 +123,8 @@ pub fn emit_function(func: &Function, emitter: &mut TealEmitter) -> Result<Vec<C
     chunks.extend(body_chunks);
 
     chunks.push(Chunk::new_empty(Instruction::ReturnSubroutine));
+    // the code outside of the statements is attributed to the signature.
+    locate(&mut chunks, &func.name.loc);
 
     if error {
         emitter.diagnostics.extend(diagnostics);
@@ -116,6 +134,44 @@ pub fn emit_function(func: &Function, emitter: &mut TealEmitter) -> Result<Vec<C
     Ok(chunks)
 }
 
+/// Check that the sender of the transaction is the address,
+/// or is contained in the list or set of addresses.
+///
+/// The outcome of the check is left on top of the stack.
+fn emit_access_check(
+    e: &Expression,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> Result<(), ()> {
+    let sender = Chunk::new_single(Instruction::Txn, Constant::StringLit("Sender".to_string()));
+    match e.ty() {
+        TypeVariant::Address => {
+            emit_expression(e, chunks, args)?;
+            chunks.extend_from_slice(&[sender, Chunk::new_empty(Instruction::Eq)]);
+        }
+        TypeVariant::List(ty) | TypeVariant::Set(ty) if ty.as_ref() == &TypeVariant::Address => {
+            emit_expression(e, chunks, args)?;
+            chunks.extend_from_slice(&[
+                sender,
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(32)),
+                Chunk::new_single(
+                    Instruction::CallSub,
+                    Constant::StringLit("list_contains".to_string()),
+                ),
+            ]);
+        }
+        _ => {
+            args.diagnostics.push(Report::emit_error(
+                e.loc().clone(),
+                "Access attributes can only be addresses, or lists and sets of addresses."
+                    .to_string(),
+            ));
+            return Err(());
+        }
+    }
+    Ok(())
+}
+
 fn emit_state_var(ident: &str, sym: &SymbolInfo, func: &Function, args: &mut EmitArgs) {
     let state_decl = &args.emitter.definition.states[sym.i];
     let box_name = format!("__{}", state_decl.name.name);
@@ -0,0 +1,168 @@
+//! Emission of the sized integers.
+//!
+//! `u8` and `u64` are presented as `uint64` on the stack, and `i64` shares
+//! the encoding of `int`. `u256` is a 32-byte big-endian array operated on with the byte
+//! math.
+//!
+//! The AVM fails on the `uint64` overflow by itself,
+//! narrower types are checked explicitly after the operations that may overflow them.
+
+use folidity_semantics::ast::{
+    BuiltinCall,
+    TypeVariant,
+};
+use num_bigint::BigUint;
+
+use crate::{
+    ast::{
+        Chunk,
+        Constant,
+        Instruction,
+        TypeSizeHint,
+    },
+    expression::emit_expression,
+    teal::EmitArgs,
+};
+
+type EmitResult = Result<u64, ()>;
+
+/// Width of `u256` in bytes.
+pub const U256_WIDTH: u64 = 32;
+
+/// Is the integer type presented as `uint64` on the stack.
+fn is_uint64(ty: &TypeVariant) -> bool {
+    matches!(ty, TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64)
+}
+
+/// Push `u256` literal as the 32-byte array.
+pub fn u256(n: &BigUint, chunks: &mut Vec<Chunk>) -> EmitResult {
+    let mut bytes = vec![0; U256_WIDTH as usize];
+    let be = n.to_bytes_be();
+    bytes[U256_WIDTH as usize - be.len()..].copy_from_slice(&be);
+    chunks.push(Chunk::new_single(
+        Instruction::PushBytes,
+        Constant::Bytes(bytes),
+    ));
+    Ok(U256_WIDTH)
+}
+
+/// Check that the value on top of the stack fits in the range of the type.
+///
+/// `u256` results of the byte math are padded back to 32 bytes first.
+pub fn emit_range_check(ty: &TypeVariant, chunks: &mut Vec<Chunk>) {
+    match ty {
+        TypeVariant::U8 => {
+            chunks.extend_from_slice(&[
+                Chunk::new_empty(Instruction::Dup),
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(u8::MAX.into())),
+                Chunk::new_empty(Instruction::LessEq),
+                Chunk::new_empty(Instruction::Assert),
+            ]);
+        }
+        // the absolute value follows the sign block.
+        TypeVariant::I64 => {
+            chunks.extend_from_slice(&[
+                Chunk::new_empty(Instruction::Dup),
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(8)),
+                Chunk::new_empty(Instruction::ExtractUint),
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(i64::MAX as u64)),
+                Chunk::new_empty(Instruction::LessEq),
+                Chunk::new_empty(Instruction::Assert),
+            ]);
+        }
+        TypeVariant::U256 => {
+            chunks.extend_from_slice(&[
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(U256_WIDTH)),
+                Chunk::new_empty(Instruction::ArrayInit),
+                Chunk::new_empty(Instruction::BOr),
+                Chunk::new_empty(Instruction::Dup),
+                Chunk::new_empty(Instruction::Len),
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(U256_WIDTH)),
+                Chunk::new_empty(Instruction::Eq),
+                Chunk::new_empty(Instruction::Assert),
+            ]);
+        }
+        _ => {}
+    }
+}
+
+/// Emit the conversion of the integer to the integer of another type.
+///
+/// Values of different presentations are converted through `uint64`,
+/// and checked to fit in the range of the target type if it is narrower.
+pub fn emit_cast(f: &BuiltinCall, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let value = &f.args[0];
+    let from = value.ty();
+    let to = &f.returns;
+
+    let mut local_chunks = vec![];
+    emit_expression(value, &mut local_chunks, args)?;
+
+    let presentation = |ty: &TypeVariant| (is_uint64(ty), ty.is_signed());
+    if presentation(from) != presentation(to) {
+        to_uint64(from, &mut local_chunks);
+        from_uint64(to, &mut local_chunks);
+    }
+    if from != to && !from.widens_to(to) {
+        emit_range_check(to, &mut local_chunks);
+    }
+
+    chunks.extend(local_chunks);
+
+    Ok(to.size_hint(args.emitter.definition))
+}
+
+/// Convert the integer on top of the stack to `uint64`.
+///
+/// Fails on negative values and `u256` values that do not fit in 64 bits.
+fn to_uint64(ty: &TypeVariant, chunks: &mut Vec<Chunk>) {
+    if ty.is_signed() {
+        chunks.extend_from_slice(&[
+            Chunk::new_empty(Instruction::Dup),
+            Chunk::new_single(Instruction::PushInt, Constant::Uint(0)),
+            Chunk::new_empty(Instruction::ExtractUint),
+            Chunk::new_empty(Instruction::Not),
+            Chunk::new_empty(Instruction::Assert),
+            Chunk::new_single(Instruction::PushInt, Constant::Uint(8)),
+            Chunk::new_empty(Instruction::ExtractUint),
+        ]);
+    } else if ty == &TypeVariant::U256 {
+        let high = U256_WIDTH - 8;
+        chunks.extend_from_slice(&[
+            Chunk::new_empty(Instruction::Dup),
+            Chunk::new_multiple(
+                Instruction::Extract,
+                vec![Constant::Uint(0), Constant::Uint(high)],
+            ),
+            Chunk::new_single(Instruction::PushInt, Constant::Uint(high)),
+            Chunk::new_empty(Instruction::ArrayInit),
+            Chunk::new_empty(Instruction::BEq),
+            Chunk::new_empty(Instruction::Assert),
+            Chunk::new_multiple(
+                Instruction::Extract,
+                vec![Constant::Uint(high), Constant::Uint(8)],
+            ),
+            Chunk::new_empty(Instruction::Btoi),
+        ]);
+    }
+}
+
+/// Convert `uint64` on top of the stack to the presentation of the integer type.
+fn from_uint64(ty: &TypeVariant, chunks: &mut Vec<Chunk>) {
+    if ty.is_signed() {
+        chunks.extend_from_slice(&[
+            Chunk::new_empty(Instruction::Itob),
+            Chunk::new_single(Instruction::PushInt, Constant::Uint(16)),
+            Chunk::new_empty(Instruction::ArrayInit),
+            Chunk::new_empty(Instruction::Swap),
+            Chunk::new_single(Instruction::Replace, Constant::Uint(8)),
+        ]);
+    } else if ty == &TypeVariant::U256 {
+        chunks.extend_from_slice(&[
+            Chunk::new_empty(Instruction::Itob),
+            Chunk::new_single(Instruction::PushInt, Constant::Uint(U256_WIDTH)),
+            Chunk::new_empty(Instruction::ArrayInit),
+            Chunk::new_empty(Instruction::BOr),
+        ]);
+    }
+}
@@ -0,0 +1,173 @@
+//! Calls of the functions of other applications declared in the interfaces.
+//!
+//! The function is called with the inner application call transaction.
+//! The arguments are encoded with ARC-4 and the function is selected by its method
+//! selector, so the called application is expected to follow the ARC-4 calling
+//! convention.
+
+use folidity_semantics::ast::{
+    InterfaceCall,
+    TypeVariant,
+};
+
+use crate::{
+    app_spec::abi_type,
+    ast::{
+        Chunk,
+        Constant,
+        Instruction,
+        TypeSizeHint,
+    },
+    expression::emit_expression,
+    mapping::{
+        from_bytes,
+        to_bytes,
+    },
+    teal::EmitArgs,
+    transaction::{
+        itxn_field,
+        submit,
+    },
+};
+
+type EmitResult = Result<u64, ()>;
+
+/// Type of the application call transaction.
+const APPL_TYPE_ENUM: u64 = 6;
+
+/// Prefix of the value returned by the ARC-4 method.
+const RETURN_PREFIX: [u8; 4] = [0x15, 0x1f, 0x7c, 0x75];
+
+/// Emit the call of the interface function returning the decoded value.
+pub fn emit_interface_call(
+    call: &InterfaceCall,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
+    let interface = &args.emitter.definition.interfaces[call.sym.i];
+    let (_, func) = interface
+        .functions
+        .get_index(call.func)
+        .expect("function is declared");
+    let params: Vec<String> = func.params.values().map(|p| abi_type(&p.ty.ty)).collect();
+    let selector = format!(
+        "{}({}){}",
+        func.name.name,
+        params.join(","),
+        abi_type(func.return_ty.ty())
+    );
+
+    // the values are evaluated before the transaction is started,
+    // as the arguments can call other applications themselves.
+    let mut local_chunks = vec![];
+    let mut error = emit_expression(&call.app, &mut local_chunks, args).is_err();
+    let app = args.emitter.scratch_index_incr()?;
+    local_chunks.push(Chunk::new_single(Instruction::Store, Constant::Uint(app)));
+    let mut slots = vec![];
+    for a in &call.args {
+        error |= emit_expression(a, &mut local_chunks, args).is_err();
+        encode(a.ty(), &mut local_chunks);
+        let slot = args.emitter.scratch_index_incr()?;
+        local_chunks.push(Chunk::new_single(Instruction::Store, Constant::Uint(slot)));
+        slots.push(slot);
+    }
+    if error {
+        return Err(());
+    }
+
+    local_chunks.extend([
+        Chunk::new_empty(Instruction::ItxnBegin),
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(APPL_TYPE_ENUM)),
+        itxn_field("TypeEnum"),
+        Chunk::new_single(Instruction::Load, Constant::Uint(app)),
+        itxn_field("ApplicationID"),
+        Chunk::new_single(Instruction::Method, Constant::String(selector)),
+        itxn_field("ApplicationArgs"),
+    ]);
+    for slot in slots {
+        local_chunks.push(Chunk::new_single(Instruction::Load, Constant::Uint(slot)));
+        local_chunks.push(itxn_field("ApplicationArgs"));
+    }
+    submit(&mut local_chunks);
+
+    if call.returns != TypeVariant::Unit {
+        local_chunks.extend([
+            Chunk::new_single(
+                Instruction::Itxn,
+                Constant::StringLit("LastLog".to_string()),
+            ),
+            Chunk::new_empty(Instruction::Dup),
+            Chunk::new_multiple(
+                Instruction::Extract,
+                vec![Constant::Uint(0), Constant::Uint(4)],
+            ),
+            Chunk::new_single(
+                Instruction::PushBytes,
+                Constant::Bytes(RETURN_PREFIX.to_vec()),
+            ),
+            Chunk::new_empty(Instruction::Eq),
+            Chunk::new_empty(Instruction::Assert),
+            Chunk::new_multiple(
+                Instruction::Extract,
+                vec![Constant::Uint(4), Constant::Uint(0)],
+            ),
+        ]);
+        decode(&call.returns, &mut local_chunks);
+    }
+
+    chunks.extend(local_chunks);
+    Ok(call.returns.size_hint(args.emitter.definition))
+}
+
+/// Encode the value on top of the stack with ARC-4.
+pub(crate) fn encode(ty: &TypeVariant, chunks: &mut Vec<Chunk>) {
+    match ty {
+        // the value is kept in the highest bit of the byte.
+        TypeVariant::Bool => {
+            chunks.extend([
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(128)),
+                Chunk::new_empty(Instruction::Mul),
+                Chunk::new_empty(Instruction::Itob),
+                Chunk::new_multiple(
+                    Instruction::Extract,
+                    vec![Constant::Uint(7), Constant::Uint(1)],
+                ),
+            ]);
+        }
+        // dynamic arrays are prefixed with the 2-byte length.
+        TypeVariant::String | TypeVariant::Hex => {
+            chunks.extend([
+                Chunk::new_empty(Instruction::Dup),
+                Chunk::new_empty(Instruction::Len),
+                Chunk::new_empty(Instruction::Itob),
+                Chunk::new_multiple(
+                    Instruction::Extract,
+                    vec![Constant::Uint(6), Constant::Uint(2)],
+                ),
+                Chunk::new_empty(Instruction::Swap),
+                Chunk::new_empty(Instruction::Concat),
+            ]);
+        }
+        ty => to_bytes(ty, chunks),
+    }
+}
+
+/// Decode the ARC-4 value on top of the stack.
+fn decode(ty: &TypeVariant, chunks: &mut Vec<Chunk>) {
+    match ty {
+        TypeVariant::Bool => {
+            chunks.extend([
+                Chunk::new_empty(Instruction::Btoi),
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(128)),
+                Chunk::new_empty(Instruction::Div),
+            ]);
+        }
+        TypeVariant::String | TypeVariant::Hex => {
+            chunks.push(Chunk::new_multiple(
+                Instruction::Extract,
+                vec![Constant::Uint(2), Constant::Uint(0)],
+            ));
+        }
+        ty => from_bytes(ty, chunks),
+    }
+}
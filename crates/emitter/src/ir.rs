@@ -0,0 +1,268 @@
+//! TEAL backend of the intermediate representation.
+//!
+//! The entry point and the blocks dispatching the calls are shared with the
+//! [`TealEmitter`], while the subroutines of the functions are emitted from the basic
+//! blocks of the IR. Locals and temporaries are kept in the virtual scratch slots,
+//! the slots of the temporaries are reused once they are dead.
+
+use folidity_ir::{
+    BinaryOp,
+    BlockId,
+    Function,
+    Instruction as IrInstruction,
+    Module,
+    Terminator,
+    Type,
+    Value,
+};
+use folidity_semantics::{
+    ast::TypeVariant,
+    CompilationError,
+    ContractDefinition,
+    Runner,
+};
+
+use crate::{
+    add_padding,
+    ast::{
+        locate,
+        Chunk,
+        Constant,
+        Instruction,
+    },
+    expression::int_constant,
+    integer::emit_range_check,
+    teal::{
+        TealArtifacts,
+        TealEmitter,
+    },
+};
+
+/// Emitter of the TEAL programs going through the IR.
+///
+/// Only the functions supported by the IR can be emitted.
+#[derive(Debug)]
+pub struct IrTealEmitter;
+
+impl Runner<ContractDefinition, TealArtifacts> for IrTealEmitter {
+    fn run(source: &ContractDefinition) -> Result<TealArtifacts, CompilationError>
+    where
+        Self: std::marker::Sized,
+    {
+        let module = Module::run(source)?;
+        let mut emitter = TealEmitter::new(source);
+        emitter.emit_entry_point();
+        if !emitter.emit_ir_functions(&module) || !emitter.allocate_scratch() {
+            return Err(CompilationError::Emit(emitter.diagnostics));
+        }
+
+        Ok(emitter.compile())
+    }
+}
+
+/// Type of the value in the semantic definition.
+fn type_variant(ty: &Type) -> TypeVariant {
+    match ty {
+        Type::Bool => TypeVariant::Bool,
+        Type::U8 => TypeVariant::U8,
+        Type::U64 => TypeVariant::Uint,
+        Type::I64 => TypeVariant::I64,
+        Type::Int => TypeVariant::Int,
+    }
+}
+
+/// Instruction of the operation over the operands on top of the stack.
+fn binary_op(op: &BinaryOp, ty: &Type) -> Chunk {
+    let callsub =
+        |name: &str| Chunk::new_single(Instruction::CallSub, Constant::StringLit(name.to_string()));
+    let signed = matches!(ty, Type::I64 | Type::Int);
+    match (op, signed) {
+        (BinaryOp::Add, false) => Chunk::new_empty(Instruction::Plus),
+        (BinaryOp::Add, true) => callsub("signed_add"),
+        (BinaryOp::Sub, false) => Chunk::new_empty(Instruction::Minus),
+        (BinaryOp::Sub, true) => callsub("signed_sub"),
+        (BinaryOp::Mul, false) => Chunk::new_empty(Instruction::Mul),
+        (BinaryOp::Mul, true) => callsub("signed_mul"),
+        (BinaryOp::Div, false) => Chunk::new_empty(Instruction::Div),
+        (BinaryOp::Div, true) => callsub("signed_div"),
+        (BinaryOp::Mod, false) => Chunk::new_empty(Instruction::Mod),
+        (BinaryOp::Mod, true) => callsub("signed_mod"),
+        (BinaryOp::Gt, false) => Chunk::new_empty(Instruction::Greater),
+        (BinaryOp::Gt, true) => callsub("signed_ge"),
+        (BinaryOp::Lt, false) => Chunk::new_empty(Instruction::Less),
+        (BinaryOp::Lt, true) => callsub("signed_le"),
+        (BinaryOp::Ge, false) => Chunk::new_empty(Instruction::GreaterEq),
+        (BinaryOp::Ge, true) => callsub("signed_geq"),
+        (BinaryOp::Le, false) => Chunk::new_empty(Instruction::LessEq),
+        (BinaryOp::Le, true) => callsub("signed_leq"),
+        (BinaryOp::Eq, _) => Chunk::new_empty(Instruction::Eq),
+        (BinaryOp::Neq, _) => Chunk::new_empty(Instruction::Neq),
+        (BinaryOp::And, _) => Chunk::new_empty(Instruction::And),
+        (BinaryOp::Or, _) => Chunk::new_empty(Instruction::Or),
+    }
+}
+
+impl<'a> TealEmitter<'a> {
+    /// Emit the subroutines of the lowered functions.
+    pub fn emit_ir_functions(&mut self, module: &Module) -> bool {
+        let mut error = false;
+
+        for (i, func) in module.functions.iter().enumerate() {
+            self.current_func = Some(i);
+            self.frame_start = self.scratch_index;
+            if let Ok(mut chunks) = self.emit_ir_function(func, module) {
+                add_padding(&mut chunks);
+                self.chunks.extend(chunks);
+            } else {
+                error |= true;
+            }
+        }
+
+        !error
+    }
+
+    fn emit_ir_function(&mut self, func: &Function, module: &Module) -> Result<Vec<Chunk>, ()> {
+        let func_name = format!("__{}", func.name);
+        let label = |b: &BlockId| format!("{}_bb{}", func_name, b.0);
+        let load = |slot: u64| Chunk::new_single(Instruction::Load, Constant::Uint(slot));
+        let store = |slot: u64| Chunk::new_single(Instruction::Store, Constant::Uint(slot));
+
+        let mut chunks = vec![Chunk::new_empty(Instruction::Label(func_name.clone()))];
+        let locals = (0..func.locals.len())
+            .map(|_| self.scratch_index_incr())
+            .collect::<Result<Vec<u64>, ()>>()?;
+        let temps = (0..func.temps.len())
+            .map(|_| self.scratch_index_incr())
+            .collect::<Result<Vec<u64>, ()>>()?;
+
+        // the last argument is on top of the stack.
+        for p in func.params.iter().rev() {
+            chunks.push(store(locals[p.0]));
+        }
+
+        for (i, block) in func.blocks.iter().enumerate() {
+            chunks.push(Chunk::new_empty(Instruction::Label(label(&BlockId(i)))));
+            for inst in &block.instructions {
+                match inst {
+                    IrInstruction::Const { dest, value } => {
+                        match value {
+                            Value::Bool(b) => {
+                                chunks.push(Chunk::new_single(
+                                    Instruction::PushInt,
+                                    Constant::Uint(*b as u64),
+                                ));
+                            }
+                            Value::Uint(n) => {
+                                chunks.push(Chunk::new_single(
+                                    Instruction::PushInt,
+                                    Constant::Uint(*n),
+                                ));
+                            }
+                            Value::Int(n) => int_constant(*n, &mut chunks),
+                        }
+                        chunks.push(store(temps[dest.0]));
+                    }
+                    IrInstruction::Load { dest, local } => {
+                        chunks.extend([load(locals[local.0]), store(temps[dest.0])]);
+                    }
+                    IrInstruction::Store { local, value } => {
+                        chunks.extend([load(temps[value.0]), store(locals[local.0])]);
+                    }
+                    IrInstruction::Binary {
+                        dest,
+                        op,
+                        ty,
+                        left,
+                        right,
+                    } => {
+                        chunks.extend([
+                            load(temps[left.0]),
+                            load(temps[right.0]),
+                            binary_op(op, ty),
+                        ]);
+                        if !op.is_comparison() {
+                            emit_range_check(&type_variant(ty), &mut chunks);
+                        }
+                        chunks.push(store(temps[dest.0]));
+                    }
+                    IrInstruction::Not { dest, value } => {
+                        chunks.extend([
+                            load(temps[value.0]),
+                            Chunk::new_empty(Instruction::Not),
+                            store(temps[dest.0]),
+                        ]);
+                    }
+                    IrInstruction::Call {
+                        dest,
+                        func: f,
+                        args,
+                    } => {
+                        // the slots of the function are saved around the calls
+                        // that can reenter it, see `func_call`.
+                        let reentrant = self
+                            .current_func
+                            .is_some_and(|i| self.recursive_cycles[*f].contains(&i));
+                        let frame: Vec<u64> = if reentrant {
+                            (self.frame_start..self.scratch_index).collect()
+                        } else {
+                            vec![]
+                        };
+                        chunks.extend(frame.iter().map(|s| load(*s)));
+                        chunks.extend(args.iter().map(|a| load(temps[a.0])));
+                        chunks.push(Chunk::new_single(
+                            Instruction::CallSub,
+                            Constant::StringLit(format!("__{}", module.functions[*f].name)),
+                        ));
+                        for slot in frame.iter().rev() {
+                            if dest.is_some() {
+                                chunks.push(Chunk::new_empty(Instruction::Swap));
+                            }
+                            chunks.push(store(*slot));
+                        }
+                        if let Some(dest) = dest {
+                            chunks.push(store(temps[dest.0]));
+                        }
+                    }
+                    IrInstruction::Assert { cond } => {
+                        chunks.extend([load(temps[cond.0]), Chunk::new_empty(Instruction::Assert)]);
+                    }
+                }
+            }
+
+            match &block.terminator {
+                Terminator::Jump(b) => {
+                    chunks.push(Chunk::new_single(
+                        Instruction::Branch,
+                        Constant::StringLit(label(b)),
+                    ));
+                }
+                Terminator::Branch {
+                    cond,
+                    then,
+                    otherwise,
+                } => {
+                    chunks.extend([
+                        load(temps[cond.0]),
+                        Chunk::new_single(
+                            Instruction::BranchNotZero,
+                            Constant::StringLit(label(then)),
+                        ),
+                        Chunk::new_single(
+                            Instruction::Branch,
+                            Constant::StringLit(label(otherwise)),
+                        ),
+                    ]);
+                }
+                Terminator::Return(value) => {
+                    if let Some(t) = value {
+                        chunks.push(load(temps[t.0]));
+                    }
+                    chunks.push(Chunk::new_empty(Instruction::ReturnSubroutine));
+                }
+            }
+        }
+
+        locate(&mut chunks, &func.loc);
+        Ok(chunks)
+    }
+}
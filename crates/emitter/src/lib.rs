@@ -2,22 +2,43 @@ use ast::{
     Chunk,
     Instruction,
 };
+use evm::{
+    EvmArtifacts,
+    EvmEmitter,
+};
 use folidity_semantics::{
     CompilationError,
     ContractDefinition,
     Runner,
 };
 use teal::{
+    RuntimeChecks,
     TealArtifacts,
     TealEmitter,
 };
 
+pub mod app_spec;
 mod ast;
+mod checked;
+mod cost;
+mod crypto;
+mod event;
+pub mod evm;
 mod expression;
 mod function;
+mod integer;
+mod interface;
+pub mod ir;
+mod list;
+mod mapping;
+mod optimizer;
+mod schema;
 mod scratch_table;
+pub mod source_map;
 mod statement;
+mod string;
 pub mod teal;
+mod transaction;
 
 #[cfg(test)]
 mod tests;
@@ -27,8 +48,34 @@ impl<'a> Runner<ContractDefinition, TealArtifacts> for TealEmitter<'a> {
     where
         Self: std::marker::Sized,
     {
-        let mut emitter = TealEmitter::new(source);
-        emitter.emit_entry_point();
+        emit_teal(source, RuntimeChecks::All)
+    }
+}
+
+/// Emit the TEAL programs asserting the bounds of the instantiations according to
+/// `checks`.
+pub fn emit_teal(
+    source: &ContractDefinition,
+    checks: RuntimeChecks,
+) -> Result<TealArtifacts, CompilationError> {
+    let mut emitter = TealEmitter::new(source);
+    emitter.runtime_checks = checks;
+    emitter.emit_entry_point();
+    if !emitter.emit_functions() || !emitter.allocate_scratch() {
+        return Err(CompilationError::Emit(emitter.diagnostics));
+    }
+
+    let artifacts = emitter.compile();
+
+    Ok(artifacts)
+}
+
+impl<'a> Runner<ContractDefinition, EvmArtifacts> for EvmEmitter<'a> {
+    fn run(source: &ContractDefinition) -> Result<EvmArtifacts, CompilationError>
+    where
+        Self: std::marker::Sized,
+    {
+        let mut emitter = EvmEmitter::new(source);
         if !emitter.emit_functions() {
             return Err(CompilationError::Emit(emitter.diagnostics));
         }
@@ -0,0 +1,734 @@
+//! Lowering of the list operations.
+//!
+//! Lists and sets are stored as the concatenated bytes of their elements.
+//! The built-in functions support only elements of the fixed size, `uint64` values
+//! are stored as 8 big-endian bytes. Resizable elements are preceded by the `uint64`
+//! block of their size.
+
+use folidity_diagnostics::{
+    Report,
+    Span,
+};
+use folidity_semantics::{
+    ast::{
+        BuiltinCall,
+        Expression,
+        Index,
+        Slice,
+        TypeVariant,
+    },
+    builtin::BuiltinFunction,
+    ContractDefinition,
+};
+
+use crate::{
+    ast::{
+        has_size_block,
+        Chunk,
+        Constant,
+        Instruction,
+        TypeSizeHint,
+    },
+    expression::emit_expression,
+    integer::emit_range_check,
+    mapping::{
+        from_bytes,
+        to_bytes,
+    },
+    teal::EmitArgs,
+};
+
+type EmitResult = Result<u64, ()>;
+
+/// Size of the element in bytes if it is fixed.
+pub fn item_size(ty: &TypeVariant, contract: &ContractDefinition) -> Option<u64> {
+    match ty {
+        TypeVariant::U8 => Some(1),
+        TypeVariant::Uint
+        | TypeVariant::U64
+        | TypeVariant::Float
+        | TypeVariant::Bool
+        | TypeVariant::Char => Some(8),
+        TypeVariant::Int | TypeVariant::I64 => Some(16),
+        // the payloads are padded to the largest one.
+        TypeVariant::Enum(_) => Some(ty.size_hint(contract)),
+        // composite values are laid out in the fixed space of their fields.
+        TypeVariant::Struct(_)
+        | TypeVariant::Model(_)
+        | TypeVariant::Tuple(_)
+        | TypeVariant::Option(_)
+            if !has_size_block(ty) =>
+        {
+            Some(ty.size_hint(contract))
+        }
+        TypeVariant::U256 => Some(32),
+        TypeVariant::Address => Some(32),
+        _ => None,
+    }
+}
+
+/// Precede the bytes of the resizable element on top of the stack with its size.
+pub fn prefix_size(ty: &TypeVariant, chunks: &mut Vec<Chunk>) {
+    if has_size_block(ty) {
+        chunks.extend_from_slice(&[
+            Chunk::new_empty(Instruction::Dup),
+            Chunk::new_empty(Instruction::Len),
+            Chunk::new_empty(Instruction::Itob),
+            Chunk::new_empty(Instruction::Swap),
+            Chunk::new_empty(Instruction::Concat),
+        ]);
+    }
+}
+
+/// Scratch indices and labels of the loop over the elements.
+struct ListLoop {
+    list_index: u64,
+    offset_index: u64,
+    item_index: u64,
+    item_size: u64,
+    loop_label: String,
+    end_label: String,
+}
+
+/// Emit the call of the built-in function on the list or set.
+///
+/// Functions iterate over the elements, except for `size`
+/// which is derived from the length of the bytes, `contains`
+/// which calls the helper subroutine, and the updates of the collection.
+pub fn emit_list_call(f: &BuiltinCall, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let (TypeVariant::List(item) | TypeVariant::Set(item)) = f.args[0].ty() else {
+        unreachable!()
+    };
+    let returns_item = match &f.returns {
+        TypeVariant::List(r) | TypeVariant::Set(r) => r.as_ref(),
+        _ => item.as_ref(),
+    };
+
+    let supported = match f.func {
+        BuiltinFunction::Map => item_size(returns_item, args.emitter.definition).is_some(),
+        BuiltinFunction::Filter
+        | BuiltinFunction::Fold
+        | BuiltinFunction::All
+        | BuiltinFunction::Exists
+        | BuiltinFunction::Sum
+        | BuiltinFunction::Size
+        | BuiltinFunction::Contains
+        | BuiltinFunction::Add
+        | BuiltinFunction::Push
+        | BuiltinFunction::Pop
+        | BuiltinFunction::Insert
+        | BuiltinFunction::Remove
+        | BuiltinFunction::Union
+        | BuiltinFunction::Difference => true,
+        _ => false,
+    };
+    let (Some(size), true) = (item_size(item, args.emitter.definition), supported) else {
+        args.diagnostics.push(Report::emit_error(
+            f.loc.clone(),
+            format!("`{}` is not yet supported for this type.", f.func),
+        ));
+        return Err(());
+    };
+
+    if matches!(
+        f.func,
+        BuiltinFunction::Add
+            | BuiltinFunction::Push
+            | BuiltinFunction::Pop
+            | BuiltinFunction::Insert
+            | BuiltinFunction::Remove
+            | BuiltinFunction::Union
+            | BuiltinFunction::Difference
+    ) {
+        return emit_list_update(f, item, size, chunks, args);
+    }
+
+    let mut local_chunks = vec![];
+
+    // the number of elements is known from the length of the bytes.
+    if f.func == BuiltinFunction::Size {
+        emit_expression(&f.args[0], &mut local_chunks, args)?;
+        local_chunks.extend_from_slice(&[
+            Chunk::new_empty(Instruction::Len),
+            Chunk::new_single(Instruction::PushInt, Constant::Uint(size)),
+            Chunk::new_empty(Instruction::Div),
+        ]);
+        chunks.extend(local_chunks);
+        return Ok(f.returns.size_hint(args.emitter.definition));
+    }
+
+    if f.func == BuiltinFunction::Contains {
+        return emit_contains(&f.args[0], &f.args[1], chunks, args);
+    }
+
+    let result_index = args.emitter.scratch_index_incr()?;
+    match f.func {
+        // the result is an empty byte array initially.
+        BuiltinFunction::Map | BuiltinFunction::Filter => {
+            local_chunks.extend_from_slice(&[
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(0)),
+                Chunk::new_empty(Instruction::ArrayInit),
+            ]);
+        }
+        BuiltinFunction::Fold => {
+            emit_expression(&f.args[1], &mut local_chunks, args)?;
+        }
+        // `all` holds and `exists` does not for the empty collection.
+        BuiltinFunction::All | BuiltinFunction::Exists => {
+            let init = u64::from(f.func == BuiltinFunction::All);
+            local_chunks.push(Chunk::new_single(
+                Instruction::PushInt,
+                Constant::Uint(init),
+            ));
+        }
+        _ => zero(item, &mut local_chunks),
+    }
+    local_chunks.push(Chunk::new_single(
+        Instruction::Store,
+        Constant::Uint(result_index),
+    ));
+
+    let lp = begin_loop(f, item, size, &mut local_chunks, args)?;
+
+    match f.func {
+        BuiltinFunction::Map => {
+            apply_function(&f.args[1], &[lp.item_index], &mut local_chunks, args)?;
+            to_bytes(returns_item, &mut local_chunks);
+            local_chunks.extend_from_slice(&[
+                Chunk::new_single(Instruction::Load, Constant::Uint(result_index)),
+                Chunk::new_empty(Instruction::Swap),
+                Chunk::new_empty(Instruction::Concat),
+                Chunk::new_single(Instruction::Store, Constant::Uint(result_index)),
+            ]);
+        }
+        BuiltinFunction::Filter => {
+            apply_function(&f.args[1], &[lp.item_index], &mut local_chunks, args)?;
+            // skip the element if the predicate is not satisfied.
+            let skip_label = format!("{}_skip", lp.loop_label);
+            local_chunks.push(Chunk::new_single(
+                Instruction::BranchZero,
+                Constant::StringLit(skip_label.clone()),
+            ));
+            local_chunks.push(Chunk::new_single(
+                Instruction::Load,
+                Constant::Uint(result_index),
+            ));
+            extract_item(&lp, &mut local_chunks);
+            local_chunks.extend_from_slice(&[
+                Chunk::new_empty(Instruction::Concat),
+                Chunk::new_single(Instruction::Store, Constant::Uint(result_index)),
+                Chunk::new_empty(Instruction::Label(skip_label)),
+            ]);
+        }
+        BuiltinFunction::Fold => {
+            apply_function(
+                &f.args[2],
+                &[result_index, lp.item_index],
+                &mut local_chunks,
+                args,
+            )?;
+            local_chunks.push(Chunk::new_single(
+                Instruction::Store,
+                Constant::Uint(result_index),
+            ));
+        }
+        BuiltinFunction::All | BuiltinFunction::Exists => {
+            let op = if f.func == BuiltinFunction::All {
+                Instruction::And
+            } else {
+                Instruction::Or
+            };
+            local_chunks.push(Chunk::new_single(
+                Instruction::Load,
+                Constant::Uint(result_index),
+            ));
+            apply_function(&f.args[1], &[lp.item_index], &mut local_chunks, args)?;
+            local_chunks.extend_from_slice(&[
+                Chunk::new_empty(op),
+                Chunk::new_single(Instruction::Store, Constant::Uint(result_index)),
+            ]);
+        }
+        BuiltinFunction::Sum => {
+            let add = match item.as_ref() {
+                TypeVariant::Int | TypeVariant::I64 => {
+                    Chunk::new_single(
+                        Instruction::CallSub,
+                        Constant::StringLit("signed_add".to_string()),
+                    )
+                }
+                TypeVariant::U256 => Chunk::new_empty(Instruction::BPlus),
+                _ => Chunk::new_empty(Instruction::Plus),
+            };
+            local_chunks.extend_from_slice(&[
+                Chunk::new_single(Instruction::Load, Constant::Uint(result_index)),
+                Chunk::new_single(Instruction::Load, Constant::Uint(lp.item_index)),
+                add,
+            ]);
+            emit_range_check(item, &mut local_chunks);
+            local_chunks.extend_from_slice(&[Chunk::new_single(
+                Instruction::Store,
+                Constant::Uint(result_index),
+            )]);
+        }
+        _ => unreachable!(),
+    }
+
+    end_loop(lp, &mut local_chunks);
+    local_chunks.push(Chunk::new_single(
+        Instruction::Load,
+        Constant::Uint(result_index),
+    ));
+
+    chunks.extend(local_chunks);
+
+    Ok(f.returns.size_hint(args.emitter.definition))
+}
+
+/// Emit the updated list or set.
+///
+/// The bytes of the collection are updated rather than re-encoded:
+/// the items are appended with `concat` and cut out with `extract3`,
+/// while the unions of the sets and the differences call the helper subroutines.
+/// Removing the item, and adding it to the set, are the difference
+/// and the union with the single item.
+fn emit_list_update(
+    f: &BuiltinCall,
+    item: &TypeVariant,
+    size: u64,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
+    let load = |slot: u64| Chunk::new_single(Instruction::Load, Constant::Uint(slot));
+    let store = |slot: u64| Chunk::new_single(Instruction::Store, Constant::Uint(slot));
+    let push = |value: u64| Chunk::new_single(Instruction::PushInt, Constant::Uint(value));
+    let callsub =
+        |name: &str| Chunk::new_single(Instruction::CallSub, Constant::StringLit(name.to_string()));
+    let is_set = matches!(f.returns, TypeVariant::Set(_));
+
+    let mut local_chunks = vec![];
+    emit_expression(&f.args[0], &mut local_chunks, args)?;
+    match f.func {
+        BuiltinFunction::Add | BuiltinFunction::Push | BuiltinFunction::Remove => {
+            emit_expression(&f.args[1], &mut local_chunks, args)?;
+            to_bytes(item, &mut local_chunks);
+            if f.func == BuiltinFunction::Remove {
+                local_chunks.extend_from_slice(&[push(size), callsub("list_difference")]);
+            } else if is_set {
+                local_chunks.extend_from_slice(&[push(size), callsub("set_union")]);
+            } else {
+                local_chunks.push(Chunk::new_empty(Instruction::Concat));
+            }
+        }
+        BuiltinFunction::Union if !is_set => {
+            emit_expression(&f.args[1], &mut local_chunks, args)?;
+            local_chunks.push(Chunk::new_empty(Instruction::Concat));
+        }
+        BuiltinFunction::Union | BuiltinFunction::Difference => {
+            emit_expression(&f.args[1], &mut local_chunks, args)?;
+            let helper = if f.func == BuiltinFunction::Union {
+                "set_union"
+            } else {
+                "list_difference"
+            };
+            local_chunks.extend_from_slice(&[push(size), callsub(helper)]);
+        }
+        BuiltinFunction::Pop => {
+            let list_index = args.emitter.scratch_index_incr()?;
+            // the length underflows for the empty list, failing the call.
+            local_chunks.extend_from_slice(&[
+                store(list_index),
+                load(list_index),
+                push(0),
+                load(list_index),
+                Chunk::new_empty(Instruction::Len),
+                push(size),
+                Chunk::new_empty(Instruction::Minus),
+                Chunk::new_empty(Instruction::Extract3),
+            ]);
+        }
+        BuiltinFunction::Insert => {
+            let list_index = args.emitter.scratch_index_incr()?;
+            let offset_index = args.emitter.scratch_index_incr()?;
+            local_chunks.push(store(list_index));
+            emit_expression(&f.args[1], &mut local_chunks, args)?;
+            local_chunks.extend_from_slice(&[
+                push(size),
+                Chunk::new_empty(Instruction::Mul),
+                store(offset_index),
+                // offset <= len(list)
+                load(offset_index),
+                load(list_index),
+                Chunk::new_empty(Instruction::Len),
+                Chunk::new_empty(Instruction::LessEq),
+                Chunk::new_empty(Instruction::Assert),
+                // the elements before the index, the item, and the rest of the list.
+                load(list_index),
+                push(0),
+                load(offset_index),
+                Chunk::new_empty(Instruction::Extract3),
+            ]);
+            emit_expression(&f.args[2], &mut local_chunks, args)?;
+            to_bytes(item, &mut local_chunks);
+            local_chunks.extend_from_slice(&[
+                Chunk::new_empty(Instruction::Concat),
+                load(list_index),
+                load(offset_index),
+                load(list_index),
+                Chunk::new_empty(Instruction::Len),
+                load(offset_index),
+                Chunk::new_empty(Instruction::Minus),
+                Chunk::new_empty(Instruction::Extract3),
+                Chunk::new_empty(Instruction::Concat),
+            ]);
+        }
+        _ => unreachable!(),
+    }
+
+    chunks.extend(local_chunks);
+    Ok(f.returns.size_hint(args.emitter.definition))
+}
+
+/// Emit the element of the list at the index.
+///
+/// The element is extracted at the offset of the index,
+/// asserting that the offset is within the bytes of the list.
+pub fn emit_index(i: &Index, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let Some(size) = item_size(&i.ty, args.emitter.definition) else {
+        args.diagnostics.push(Report::emit_error(
+            i.loc.clone(),
+            "Indexing of lists of resizable elements is not yet supported.".to_string(),
+        ));
+        return Err(());
+    };
+    let list_index = args.emitter.scratch_index_incr()?;
+    let offset_index = args.emitter.scratch_index_incr()?;
+    let load = |slot: u64| Chunk::new_single(Instruction::Load, Constant::Uint(slot));
+    let store = |slot: u64| Chunk::new_single(Instruction::Store, Constant::Uint(slot));
+
+    let mut local_chunks = vec![];
+    emit_expression(&i.expr, &mut local_chunks, args)?;
+    local_chunks.push(store(list_index));
+    emit_expression(&i.index, &mut local_chunks, args)?;
+    local_chunks.extend_from_slice(&[
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(size)),
+        Chunk::new_empty(Instruction::Mul),
+        store(offset_index),
+        // offset < len(list)
+        load(offset_index),
+        load(list_index),
+        Chunk::new_empty(Instruction::Len),
+        Chunk::new_empty(Instruction::Less),
+        Chunk::new_empty(Instruction::Assert),
+        load(list_index),
+        load(offset_index),
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(size)),
+        Chunk::new_empty(Instruction::Extract3),
+    ]);
+    from_bytes(&i.ty, &mut local_chunks);
+
+    chunks.extend(local_chunks);
+    Ok(i.ty.size_hint(args.emitter.definition))
+}
+
+/// Emit the elements of the list between the indices.
+///
+/// The bytes are extracted between the offsets of the indices,
+/// asserting that `from <= to <= len(list)`.
+pub fn emit_slice(sl: &Slice, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let (TypeVariant::List(item) | TypeVariant::Set(item)) = &sl.ty else {
+        unreachable!("slices are lists")
+    };
+    let Some(size) = item_size(item, args.emitter.definition) else {
+        args.diagnostics.push(Report::emit_error(
+            sl.loc.clone(),
+            "Slicing of lists of resizable elements is not yet supported.".to_string(),
+        ));
+        return Err(());
+    };
+    let list_index = args.emitter.scratch_index_incr()?;
+    let from_index = args.emitter.scratch_index_incr()?;
+    let to_index = args.emitter.scratch_index_incr()?;
+    let load = |slot: u64| Chunk::new_single(Instruction::Load, Constant::Uint(slot));
+    let store = |slot: u64| Chunk::new_single(Instruction::Store, Constant::Uint(slot));
+    let push = |value: u64| Chunk::new_single(Instruction::PushInt, Constant::Uint(value));
+
+    let mut local_chunks = vec![];
+    emit_expression(&sl.expr, &mut local_chunks, args)?;
+    local_chunks.push(store(list_index));
+    // the offsets of the omitted indices are the ends of the list.
+    match &sl.from {
+        Some(from) => {
+            emit_expression(from, &mut local_chunks, args)?;
+            local_chunks.extend_from_slice(&[push(size), Chunk::new_empty(Instruction::Mul)]);
+        }
+        None => local_chunks.push(push(0)),
+    }
+    local_chunks.push(store(from_index));
+    match &sl.to {
+        Some(to) => {
+            emit_expression(to, &mut local_chunks, args)?;
+            local_chunks.extend_from_slice(&[push(size), Chunk::new_empty(Instruction::Mul)]);
+        }
+        None => {
+            local_chunks.extend_from_slice(&[load(list_index), Chunk::new_empty(Instruction::Len)]);
+        }
+    }
+    local_chunks.extend_from_slice(&[
+        store(to_index),
+        load(from_index),
+        load(to_index),
+        Chunk::new_empty(Instruction::LessEq),
+        Chunk::new_empty(Instruction::Assert),
+        load(to_index),
+        load(list_index),
+        Chunk::new_empty(Instruction::Len),
+        Chunk::new_empty(Instruction::LessEq),
+        Chunk::new_empty(Instruction::Assert),
+        load(list_index),
+        load(from_index),
+        load(to_index),
+        load(from_index),
+        Chunk::new_empty(Instruction::Minus),
+        Chunk::new_empty(Instruction::Extract3),
+    ]);
+
+    chunks.extend(local_chunks);
+    Ok(sl.ty.size_hint(args.emitter.definition))
+}
+
+/// Check whether the list or set contains the item.
+///
+/// Elements are compared as bytes by the `list_contains` helper subroutine.
+pub fn emit_contains(
+    list: &Expression,
+    item: &Expression,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
+    let Some(size) = item_size(item.ty(), args.emitter.definition) else {
+        args.diagnostics.push(Report::emit_error(
+            item.loc().clone(),
+            "Membership of resizable elements is not yet supported.".to_string(),
+        ));
+        return Err(());
+    };
+
+    let mut local_chunks = vec![];
+    emit_expression(list, &mut local_chunks, args)?;
+    emit_expression(item, &mut local_chunks, args)?;
+    to_bytes(item.ty(), &mut local_chunks);
+    local_chunks.extend_from_slice(&[
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(size)),
+        Chunk::new_single(
+            Instruction::CallSub,
+            Constant::StringLit("list_contains".to_string()),
+        ),
+    ]);
+
+    chunks.extend(local_chunks);
+
+    Ok(TypeVariant::Bool.size_hint(args.emitter.definition))
+}
+
+/// Compare the two sets on top of the stack.
+///
+/// The elements of sets are unique, so the sets are equal if they have the same length
+/// and every element of the left one is contained in the right one.
+pub fn emit_set_equality(
+    item: &TypeVariant,
+    loc: &Span,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> Result<(), ()> {
+    let Some(size) = item_size(item, args.emitter.definition) else {
+        args.diagnostics.push(Report::emit_error(
+            loc.clone(),
+            "Equality of sets of resizable elements is not yet supported.".to_string(),
+        ));
+        return Err(());
+    };
+    let loop_index = args.emitter.loop_index_incr()?;
+    let loop_label = format!("{}_set_eq_loop", loop_index);
+    let end_label = format!("{}_set_eq_end", loop_index);
+    let right_index = args.emitter.scratch_index_incr()?;
+    let left_index = args.emitter.scratch_index_incr()?;
+    let offset_index = args.emitter.scratch_index_incr()?;
+    let result_index = args.emitter.scratch_index_incr()?;
+    let load = |slot: u64| Chunk::new_single(Instruction::Load, Constant::Uint(slot));
+    let store = |slot: u64| Chunk::new_single(Instruction::Store, Constant::Uint(slot));
+    let push = |value: u64| Chunk::new_single(Instruction::PushInt, Constant::Uint(value));
+
+    chunks.extend_from_slice(&[
+        store(right_index),
+        store(left_index),
+        load(left_index),
+        Chunk::new_empty(Instruction::Len),
+        load(right_index),
+        Chunk::new_empty(Instruction::Len),
+        Chunk::new_empty(Instruction::Eq),
+        store(result_index),
+        push(0),
+        store(offset_index),
+        // stop at the end of the set, or at the first missing element.
+        Chunk::new_empty(Instruction::Label(loop_label.clone())),
+        load(offset_index),
+        load(left_index),
+        Chunk::new_empty(Instruction::Len),
+        Chunk::new_empty(Instruction::GreaterEq),
+        Chunk::new_single(
+            Instruction::BranchNotZero,
+            Constant::StringLit(end_label.clone()),
+        ),
+        load(result_index),
+        Chunk::new_single(
+            Instruction::BranchZero,
+            Constant::StringLit(end_label.clone()),
+        ),
+        load(right_index),
+        load(left_index),
+        load(offset_index),
+        push(size),
+        Chunk::new_empty(Instruction::Extract3),
+        push(size),
+        Chunk::new_single(
+            Instruction::CallSub,
+            Constant::StringLit("list_contains".to_string()),
+        ),
+        store(result_index),
+        load(offset_index),
+        push(size),
+        Chunk::new_empty(Instruction::Plus),
+        store(offset_index),
+        Chunk::new_single(Instruction::Branch, Constant::StringLit(loop_label)),
+        Chunk::new_empty(Instruction::Label(end_label)),
+        load(result_index),
+    ]);
+
+    Ok(())
+}
+
+/// Emit the collection, and the header of the loop over its elements.
+///
+/// The current element is stored in the scratch at the start of each iteration.
+fn begin_loop(
+    f: &BuiltinCall,
+    item: &TypeVariant,
+    item_size: u64,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> Result<ListLoop, ()> {
+    let loop_index = args.emitter.loop_index_incr()?;
+    let lp = ListLoop {
+        list_index: args.emitter.scratch_index_incr()?,
+        offset_index: args.emitter.scratch_index_incr()?,
+        item_index: args.emitter.scratch_index_incr()?,
+        item_size,
+        loop_label: format!("{}_{}_loop", loop_index, f.func),
+        end_label: format!("{}_{}_end", loop_index, f.func),
+    };
+
+    emit_expression(&f.args[0], chunks, args)?;
+    chunks.extend_from_slice(&[
+        Chunk::new_single(Instruction::Store, Constant::Uint(lp.list_index)),
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(0)),
+        Chunk::new_single(Instruction::Store, Constant::Uint(lp.offset_index)),
+        // exit when the offset reaches the end of the list.
+        Chunk::new_empty(Instruction::Label(lp.loop_label.clone())),
+        Chunk::new_single(Instruction::Load, Constant::Uint(lp.offset_index)),
+        Chunk::new_single(Instruction::Load, Constant::Uint(lp.list_index)),
+        Chunk::new_empty(Instruction::Len),
+        Chunk::new_empty(Instruction::GreaterEq),
+        Chunk::new_single(
+            Instruction::BranchNotZero,
+            Constant::StringLit(lp.end_label.clone()),
+        ),
+    ]);
+    extract_item(&lp, chunks);
+    from_bytes(item, chunks);
+    chunks.push(Chunk::new_single(
+        Instruction::Store,
+        Constant::Uint(lp.item_index),
+    ));
+
+    Ok(lp)
+}
+
+/// Move to the next element, and emit the end of the loop.
+fn end_loop(lp: ListLoop, chunks: &mut Vec<Chunk>) {
+    chunks.extend_from_slice(&[
+        Chunk::new_single(Instruction::Load, Constant::Uint(lp.offset_index)),
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(lp.item_size)),
+        Chunk::new_empty(Instruction::Plus),
+        Chunk::new_single(Instruction::Store, Constant::Uint(lp.offset_index)),
+        Chunk::new_single(Instruction::Branch, Constant::StringLit(lp.loop_label)),
+        Chunk::new_empty(Instruction::Label(lp.end_label)),
+    ]);
+}
+
+/// Push the bytes of the current element onto the stack.
+fn extract_item(lp: &ListLoop, chunks: &mut Vec<Chunk>) {
+    chunks.extend_from_slice(&[
+        Chunk::new_single(Instruction::Load, Constant::Uint(lp.list_index)),
+        Chunk::new_single(Instruction::Load, Constant::Uint(lp.offset_index)),
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(lp.item_size)),
+        Chunk::new_empty(Instruction::Extract3),
+    ]);
+}
+
+/// Push zero of the numeric type onto the stack.
+fn zero(ty: &TypeVariant, chunks: &mut Vec<Chunk>) {
+    if ty.is_signed() || ty == &TypeVariant::U256 {
+        chunks.extend_from_slice(&[
+            Chunk::new_single(
+                Instruction::PushInt,
+                Constant::Uint(if ty == &TypeVariant::U256 { 32 } else { 16 }),
+            ),
+            Chunk::new_empty(Instruction::ArrayInit),
+        ]);
+    } else {
+        chunks.push(Chunk::new_single(Instruction::PushInt, Constant::Uint(0)));
+    }
+}
+
+/// Apply the function to the values stored in the scratch.
+///
+/// Lambdas are inlined with the parameters bound to the values,
+/// named functions are called as subroutines.
+fn apply_function(
+    func: &Expression,
+    indices: &[u64],
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> Result<(), ()> {
+    match func {
+        Expression::Lambda(l) => {
+            for (p, i) in l.params.iter().zip(indices) {
+                args.emitter.concrete_vars.insert(
+                    *p,
+                    vec![Chunk::new_single(Instruction::Load, Constant::Uint(*i))],
+                );
+            }
+            emit_expression(&l.body, chunks, args)?;
+        }
+        Expression::Variable(u) => {
+            let func_decl = &args.emitter.definition.functions[u.element];
+            // we use `__<name>` convention for function names.
+            let name = format!("__{}", func_decl.name.name);
+            for i in indices {
+                chunks.push(Chunk::new_single(Instruction::Load, Constant::Uint(*i)));
+            }
+            chunks.push(Chunk::new_single(
+                Instruction::CallSub,
+                Constant::StringLit(name),
+            ));
+        }
+        _ => {
+            args.diagnostics.push(Report::emit_error(
+                func.loc().clone(),
+                "Only lambdas and named functions can be applied.".to_string(),
+            ));
+            return Err(());
+        }
+    }
+    Ok(())
+}
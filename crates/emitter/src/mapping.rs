@@ -0,0 +1,308 @@
+//! Lowering of mappings to the box storage.
+//!
+//! A mapping is identified by the box name prefix derived from the names of the state,
+//! model or struct and of the field it is declared in, e.g. `Voting.votes:`. Each entry
+//! is stored in a separate box named by the prefix followed by the encoded key.
+//! Therefore, mappings occupy no space inside the state box, and the mapping value on the
+//! stack is its prefix.
+
+use folidity_diagnostics::{
+    Report,
+    Span,
+};
+use folidity_semantics::{
+    ast::{
+        BuiltinCall,
+        Param,
+        TypeVariant,
+    },
+    builtin::BuiltinFunction,
+    ContractDefinition,
+};
+use serde::Serialize;
+
+use crate::{
+    ast::{
+        has_size_block,
+        struct_size,
+        Chunk,
+        Constant,
+        Instruction,
+        TypeSizeHint,
+    },
+    expression::emit_expression,
+    teal::EmitArgs,
+};
+
+type EmitResult = Result<u64, ()>;
+
+/// Max length of the box name in AVM.
+const MAX_BOX_NAME_LEN: u64 = 64;
+
+/// Max size of the box contents in AVM.
+pub const MAX_BOX_SIZE: u64 = 32768;
+
+/// Flat part of the minimum balance requirement for each box in microAlgos.
+const BOX_FLAT_MIN_BALANCE: u64 = 2500;
+
+/// Minimum balance requirement for each byte of the box name and contents in microAlgos.
+const BOX_BYTE_MIN_BALANCE: u64 = 400;
+
+/// Layout of the box storage used by the contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BoxLayout {
+    /// Name of the state box, or the name prefix of the mapping entries.
+    pub name: String,
+    /// Whether the box stores a state or a mapping entry.
+    pub kind: BoxKind,
+    /// Size of the mapping key in bytes, `None` if the key is resizable.
+    pub key_size: Option<u64>,
+    /// Size of the contents in bytes, `None` if the contents are resizable.
+    pub size: Option<u64>,
+    /// Minimum balance the application account must hold for each box in microAlgos.
+    pub min_balance: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoxKind {
+    State,
+    Mapping,
+}
+
+/// Box name prefix of the mapping declared in the field of the named declaration.
+pub fn mapping_prefix(owner: &str, field: &str) -> String {
+    format!("{}.{}:", owner, field)
+}
+
+/// Push the prefix of the mapping stored in the field of the struct, model or state
+/// onto the stack.
+pub fn emit_mapping_field(
+    owner: &TypeVariant,
+    field: &Param,
+    loc: &Span,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
+    let TypeVariant::Mapping(m) = &field.ty.ty else {
+        unreachable!()
+    };
+    let definition = args.emitter.definition;
+    let owner = match owner {
+        TypeVariant::Struct(s) => &definition.structs[s.i].name.name,
+        TypeVariant::Model(s) => &definition.models[s.i].name.name,
+        TypeVariant::State(s) => &definition.states[s.i].name.name,
+        _ => unreachable!("only aggregates have fields"),
+    };
+    let prefix = mapping_prefix(owner, &field.name.name);
+
+    // resizable keys are checked at runtime by AVM.
+    if !m.from_ty.is_resizable()
+        && prefix.len() as u64 + m.from_ty.size_hint(args.emitter.definition) > MAX_BOX_NAME_LEN
+    {
+        args.diagnostics.push(Report::emit_error(
+            loc.clone(),
+            format!(
+                "Mapping key of `{}` exceeds the max box name length of {} bytes.",
+                field.name.name, MAX_BOX_NAME_LEN
+            ),
+        ));
+        return Err(());
+    }
+
+    chunks.push(Chunk::new_single(
+        Instruction::PushBytes,
+        Constant::String(prefix),
+    ));
+
+    Ok(0)
+}
+
+/// Collect the boxes of the states and the mappings declared in their fields.
+pub fn storage_layout(definition: &ContractDefinition) -> Vec<BoxLayout> {
+    let mut boxes: Vec<BoxLayout> = vec![];
+
+    for state in &definition.states {
+        // states without a body are not stored.
+        if state.body.is_none() {
+            continue;
+        }
+        let name = format!("__{}", state.name.name);
+        let fields = state.fields(definition);
+        // the state is stored as the zero-padded array of its fields.
+        let size = struct_size(&fields, definition);
+        boxes.push(BoxLayout {
+            min_balance: min_balance(name.len() as u64, Some(size)),
+            name,
+            kind: BoxKind::State,
+            key_size: None,
+            size: Some(size),
+        });
+
+        for f in &fields {
+            let TypeVariant::Mapping(m) = &f.ty.ty else {
+                continue;
+            };
+            let prefix = mapping_prefix(&state.name.name, &f.name.name);
+            let key_size = encoded_size(&m.from_ty, definition);
+            let size = encoded_size(&m.to_ty, definition);
+            let min_balance = key_size.and_then(|k| min_balance(prefix.len() as u64 + k, size));
+            boxes.push(BoxLayout {
+                name: prefix,
+                kind: BoxKind::Mapping,
+                key_size,
+                size,
+                min_balance,
+            });
+        }
+    }
+
+    boxes
+}
+
+/// Size of the value of the type stored in the box, `None` if it is resizable.
+fn encoded_size(ty: &TypeVariant, definition: &ContractDefinition) -> Option<u64> {
+    if ty.is_resizable() || has_size_block(ty) {
+        None
+    } else {
+        Some(ty.size_hint(definition))
+    }
+}
+
+/// Minimum balance requirement of the box with the known name length and size.
+fn min_balance(name_len: u64, size: Option<u64>) -> Option<u64> {
+    size.map(|s| BOX_FLAT_MIN_BALANCE + BOX_BYTE_MIN_BALANCE * (name_len + s))
+}
+
+/// Emit the call of the built-in mapping function.
+///
+/// The mapping is evaluated once and kept in the scratch,
+/// so it can be returned by the functions updating the mapping.
+pub fn emit_mapping_call(
+    call: &BuiltinCall,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
+    let TypeVariant::Mapping(m) = call.args[0].ty() else {
+        unreachable!()
+    };
+    let mut local_chunks = vec![];
+
+    emit_expression(&call.args[0], &mut local_chunks, args)?;
+    let map_index = args.emitter.scratch_index_incr()?;
+    local_chunks.push(Chunk::new_single(
+        Instruction::Store,
+        Constant::Uint(map_index),
+    ));
+
+    // box name is `prefix ++ key`
+    local_chunks.push(Chunk::new_single(
+        Instruction::Load,
+        Constant::Uint(map_index),
+    ));
+    emit_expression(&call.args[1], &mut local_chunks, args)?;
+    to_bytes(call.args[1].ty(), &mut local_chunks);
+    local_chunks.push(Chunk::new_empty(Instruction::Concat));
+
+    let size = match call.func {
+        BuiltinFunction::Add => {
+            let size = encoded_size(&m.to_ty, args.emitter.definition);
+            if let Some(size) = size {
+                if size > MAX_BOX_SIZE {
+                    args.diagnostics.push(Report::emit_error(
+                        call.loc.clone(),
+                        format!(
+                            "Mapping value takes {} bytes, which exceeds the max box size of {} bytes.",
+                            size, MAX_BOX_SIZE
+                        ),
+                    ));
+                    return Err(());
+                }
+            }
+            if size.is_none() {
+                // `box_put` fails if the size of the contents changes,
+                // so we delete the box first.
+                let name_index = args.emitter.scratch_index_incr()?;
+                local_chunks.extend_from_slice(&[
+                    Chunk::new_single(Instruction::Store, Constant::Uint(name_index)),
+                    Chunk::new_single(Instruction::Load, Constant::Uint(name_index)),
+                    Chunk::new_empty(Instruction::BoxDel),
+                    Chunk::new_empty(Instruction::Pop),
+                    Chunk::new_single(Instruction::Load, Constant::Uint(name_index)),
+                ]);
+            }
+            emit_expression(&call.args[2], &mut local_chunks, args)?;
+            to_bytes(call.args[2].ty(), &mut local_chunks);
+            local_chunks.extend_from_slice(&[
+                Chunk::new_empty(Instruction::BoxPut),
+                Chunk::new_single(Instruction::Load, Constant::Uint(map_index)),
+            ]);
+            0
+        }
+        BuiltinFunction::Get => {
+            local_chunks.extend_from_slice(&[
+                Chunk::new_empty(Instruction::BoxGet),
+                Chunk::new_empty(Instruction::Assert),
+            ]);
+            from_bytes(&m.to_ty, &mut local_chunks);
+            m.to_ty.size_hint(args.emitter.definition)
+        }
+        BuiltinFunction::Contains => {
+            // `box_len` pushes the length and the existence flag.
+            local_chunks.extend_from_slice(&[
+                Chunk::new_empty(Instruction::BoxLen),
+                Chunk::new_empty(Instruction::Swap),
+                Chunk::new_empty(Instruction::Pop),
+            ]);
+            TypeVariant::Bool.size_hint(args.emitter.definition)
+        }
+        BuiltinFunction::Remove => {
+            local_chunks.extend_from_slice(&[
+                Chunk::new_empty(Instruction::BoxDel),
+                Chunk::new_empty(Instruction::Pop),
+                Chunk::new_single(Instruction::Load, Constant::Uint(map_index)),
+            ]);
+            0
+        }
+        // other functions are rejected for mappings in semantics.
+        _ => unreachable!(),
+    };
+
+    chunks.extend(local_chunks);
+
+    Ok(size)
+}
+
+/// Is the type represented as `uint64` on the stack.
+pub(crate) fn is_uint(ty: &TypeVariant) -> bool {
+    matches!(
+        ty,
+        TypeVariant::Uint
+            | TypeVariant::U8
+            | TypeVariant::U64
+            | TypeVariant::Float
+            | TypeVariant::Bool
+            | TypeVariant::Char
+    )
+}
+
+/// Convert the value on top of the stack to bytes.
+pub(crate) fn to_bytes(ty: &TypeVariant, chunks: &mut Vec<Chunk>) {
+    if is_uint(ty) {
+        chunks.push(Chunk::new_empty(Instruction::Itob));
+    }
+    // `u8` takes a single byte.
+    if ty == &TypeVariant::U8 {
+        chunks.push(Chunk::new_multiple(
+            Instruction::Extract,
+            vec![Constant::Uint(7), Constant::Uint(1)],
+        ));
+    }
+}
+
+/// Convert the bytes on top of the stack to the value of the type.
+pub(crate) fn from_bytes(ty: &TypeVariant, chunks: &mut Vec<Chunk>) {
+    if is_uint(ty) {
+        chunks.push(Chunk::new_empty(Instruction::Btoi));
+    }
+}
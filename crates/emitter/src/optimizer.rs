@@ -0,0 +1,166 @@
+//! Peephole optimizations of the emitted TEAL chunks.
+//!
+//! The emitter produces straightforward sequences of chunks, e.g. storing the value
+//! in the scratch space only to load it in the next instruction.
+//! The passes rewrite such sequences until none of them applies.
+
+use std::collections::HashMap;
+
+use crate::ast::{
+    Chunk,
+    Constant,
+    Instruction,
+};
+
+/// Optimize the chunks of the program.
+pub fn optimize(mut chunks: Vec<Chunk>) -> Vec<Chunk> {
+    loop {
+        let mut changed = fold_constants(&mut chunks);
+        changed |= remove_redundant_slots(&mut chunks);
+        changed |= remove_dead_values(&mut chunks);
+        if !changed {
+            break;
+        }
+    }
+    chunks
+}
+
+/// Slot of the `load` or `store` chunk.
+fn slot(chunk: &Chunk, op: &Instruction) -> Option<u64> {
+    match (&chunk.op, chunk.constants.as_slice()) {
+        (o, [Constant::Uint(n)]) if o == op => Some(*n),
+        _ => None,
+    }
+}
+
+/// Value pushed by the `pushint` chunk.
+fn int(chunk: &Chunk) -> Option<u64> {
+    slot(chunk, &Instruction::PushInt)
+}
+
+/// Evaluate the operation over two constants.
+///
+/// Operations that fail at runtime, e.g. overflowing ones, are not evaluated.
+fn eval(op: &Instruction, a: u64, b: u64) -> Option<u64> {
+    match op {
+        Instruction::Plus => a.checked_add(b),
+        Instruction::Minus => a.checked_sub(b),
+        Instruction::Mul => a.checked_mul(b),
+        Instruction::Div => a.checked_div(b),
+        Instruction::Less => Some((a < b) as u64),
+        Instruction::Greater => Some((a > b) as u64),
+        Instruction::LessEq => Some((a <= b) as u64),
+        Instruction::GreaterEq => Some((a >= b) as u64),
+        Instruction::Eq => Some((a == b) as u64),
+        Instruction::Neq => Some((a != b) as u64),
+        Instruction::And => Some((a != 0 && b != 0) as u64),
+        Instruction::Or => Some((a != 0 || b != 0) as u64),
+        _ => None,
+    }
+}
+
+/// Replace arithmetic over the pushed integers with the result.
+fn fold_constants(chunks: &mut Vec<Chunk>) -> bool {
+    let mut changed = false;
+    let mut i = 0;
+    while i < chunks.len() {
+        if i + 2 < chunks.len() {
+            if let (Some(a), Some(b)) = (int(&chunks[i]), int(&chunks[i + 1])) {
+                if let Some(value) = eval(&chunks[i + 2].op, a, b) {
+                    let loc = chunks[i + 2].loc.clone();
+                    chunks.splice(
+                        i..i + 3,
+                        [
+                            Chunk::new_single(Instruction::PushInt, Constant::Uint(value))
+                                .with_loc(loc),
+                        ],
+                    );
+                    changed = true;
+                    // the result can be folded with the preceding push.
+                    i = i.saturating_sub(1);
+                    continue;
+                }
+            }
+        }
+        if i + 1 < chunks.len() && chunks[i + 1].op == Instruction::Not {
+            if let Some(a) = int(&chunks[i]) {
+                let loc = chunks[i + 1].loc.clone();
+                chunks.splice(
+                    i..i + 2,
+                    [
+                        Chunk::new_single(Instruction::PushInt, Constant::Uint((a == 0) as u64))
+                            .with_loc(loc),
+                    ],
+                );
+                changed = true;
+                i = i.saturating_sub(1);
+                continue;
+            }
+        }
+        i += 1;
+    }
+    changed
+}
+
+/// Remove the values stored in the slots only to be loaded back,
+/// and turn the stores into the slots that are never loaded into `pop`.
+fn remove_redundant_slots(chunks: &mut Vec<Chunk>) -> bool {
+    let mut loads: HashMap<u64, usize> = HashMap::new();
+    for c in chunks.iter() {
+        if let Some(n) = slot(c, &Instruction::Load) {
+            *loads.entry(n).or_default() += 1;
+        }
+    }
+
+    let mut changed = false;
+    let mut i = 0;
+    while i < chunks.len() {
+        if let Some(n) = slot(&chunks[i], &Instruction::Store) {
+            let next = chunks.get(i + 1).and_then(|c| slot(c, &Instruction::Load));
+            if next == Some(n) && loads.get(&n) == Some(&1) {
+                // the value stays on the stack.
+                chunks.drain(i..i + 2);
+                loads.remove(&n);
+                changed = true;
+                continue;
+            }
+            if !loads.contains_key(&n) {
+                chunks[i] = Chunk::new_empty(Instruction::Pop).with_loc(chunks[i].loc.clone());
+                changed = true;
+            }
+        } else if let Some(n) = slot(&chunks[i], &Instruction::Load) {
+            let next = chunks.get(i + 1).and_then(|c| slot(c, &Instruction::Store));
+            if next == Some(n) {
+                // the slot is stored its own value.
+                chunks.drain(i..i + 2);
+                if let Some(count) = loads.get_mut(&n) {
+                    *count -= 1;
+                }
+                changed = true;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    changed
+}
+
+/// Remove the values that are popped right after they are pushed.
+fn remove_dead_values(chunks: &mut Vec<Chunk>) -> bool {
+    let mut changed = false;
+    let mut i = 0;
+    while i + 1 < chunks.len() {
+        let pure = matches!(
+            chunks[i].op,
+            Instruction::PushInt | Instruction::PushBytes | Instruction::Dup | Instruction::Load
+        );
+        if pure && chunks[i + 1].op == Instruction::Pop {
+            chunks.drain(i..i + 2);
+            changed = true;
+            i = i.saturating_sub(1);
+            continue;
+        }
+        i += 1;
+    }
+    changed
+}
@@ -0,0 +1,75 @@
+//! State schema of the application.
+//!
+//! States and mappings are stored in boxes, so the global storage only holds
+//! the index of the current state, and no local storage is used.
+//! Boxes are not a part of the schema, but their contents can only be accessed
+//! within the I/O budget granted by the box references of the transaction.
+
+use folidity_diagnostics::Report;
+use folidity_semantics::ContractDefinition;
+
+use crate::{
+    app_spec::{
+        SchemaSize,
+        StateSchema,
+    },
+    ast::struct_size,
+};
+
+/// Max number of the global key-value pairs in AVM.
+const MAX_GLOBAL_ENTRIES: u64 = 64;
+
+/// Max number of the local key-value pairs per account in AVM.
+const MAX_LOCAL_ENTRIES: u64 = 16;
+
+/// Bytes of the box contents accessible per box reference.
+const BOX_REF_BUDGET: u64 = 1024;
+
+/// Max number of the box references per transaction.
+const MAX_BOX_REFS: u64 = 8;
+
+/// Derive the schema of the global and local storage from the states of the contract.
+///
+/// Warns when the storage exceeds the limits of AVM.
+pub fn state_schema(definition: &ContractDefinition, warnings: &mut Vec<Report>) -> StateSchema {
+    let mut schema = StateSchema::default();
+    // the index of the current state.
+    if !definition.states.is_empty() {
+        schema.global.num_uints += 1;
+    }
+
+    check_entries(&schema.global, MAX_GLOBAL_ENTRIES, "global", warnings);
+    check_entries(&schema.local, MAX_LOCAL_ENTRIES, "local", warnings);
+
+    for state in &definition.states {
+        if state.body.is_none() {
+            continue;
+        }
+        let size = struct_size(&state.fields(definition), definition);
+        let refs = size.div_ceil(BOX_REF_BUDGET);
+        if refs > MAX_BOX_REFS {
+            warnings.push(Report::emit_warning(
+                state.name.loc.clone(),
+                format!(
+                    "State `{}` takes {} bytes, which needs {} box references to be accessed, but a transaction can only have {}.",
+                    state.name.name, size, refs, MAX_BOX_REFS
+                ),
+            ));
+        }
+    }
+
+    schema
+}
+
+fn check_entries(size: &SchemaSize, max: u64, storage: &str, warnings: &mut Vec<Report>) {
+    let entries = size.num_uints + size.num_byte_slices;
+    if entries > max {
+        warnings.push(Report::emit_warning(
+            Default::default(),
+            format!(
+                "Contract uses {} {} storage entries, but AVM only allows {}.",
+                entries, storage, max
+            ),
+        ));
+    }
+}
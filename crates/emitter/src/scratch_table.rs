@@ -1,11 +1,44 @@
+//! Scratch slots of the emitted program.
+//!
+//! The emitter hands out a fresh virtual slot for every value it keeps in the scratch
+//! space. Once the program is emitted, the live ranges of the virtual slots are computed
+//! over its control flow, and the slots that are never live at once share a cell.
+//! Every subroutine is a region of the program, the cells of the values live across
+//! a call are not shared with the slots of the called region and of its callees.
+
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+    HashMap,
+    HashSet,
+};
+
+use folidity_diagnostics::Report;
+use folidity_semantics::{
+    ContractDefinition,
+    Span,
+};
 use indexmap::IndexMap;
 
-use crate::teal::TealEmitter;
+use crate::{
+    ast::{
+        Chunk,
+        Constant,
+        Instruction,
+    },
+    teal::TealEmitter,
+};
+
+/// Number of the cells in the scratch space.
+const SCRATCH_SIZE: u64 = 256;
+
+/// Cells used by the helper subroutines, they are never allocated.
+const RESERVED_SLOTS: u64 = 2;
 
 #[derive(Debug, Clone)]
 pub struct ScratchVariable {
-    /// position in the scratch table.
-    pub index: u8,
+    /// virtual slot of the variable.
+    pub index: u64,
     /// size of a variable.
     pub size: u64,
 }
@@ -18,11 +51,15 @@ pub struct ScratchTable {
 
 impl ScratchTable {
     /// Add variable to the virtual scratch table.
-    pub fn add_var(&mut self, var_no: usize, size: u64, emitter: &mut TealEmitter) -> u8 {
-        let index = emitter.scratch_index;
+    pub fn add_var(
+        &mut self,
+        var_no: usize,
+        size: u64,
+        emitter: &mut TealEmitter,
+    ) -> Result<u64, ()> {
+        let index = emitter.scratch_index_incr()?;
         self.vars.insert(var_no, ScratchVariable { index, size });
-        emitter.scratch_index += 1;
-        index
+        Ok(index)
     }
 
     pub fn get_var(&self, no: usize) -> Option<&ScratchVariable> {
@@ -33,3 +70,261 @@ impl ScratchTable {
         self.vars.get_mut(&no)
     }
 }
+
+/// Subroutine of the program, or its entry point.
+#[derive(Debug, Default)]
+struct Region {
+    /// Label the subroutine starts at, `None` for the entry point.
+    label: Option<String>,
+    /// Slots loaded or stored in the region.
+    slots: HashSet<u64>,
+    /// Labels of the called subroutines.
+    callees: HashSet<String>,
+}
+
+/// Map the virtual slots of the chunks onto the cells of the scratch space.
+///
+/// # Errors
+/// - The values live at once in some function do not fit into the scratch space.
+pub fn allocate_slots(chunks: &mut [Chunk], definition: &ContractDefinition) -> Result<(), Report> {
+    let labels: HashMap<String, usize> = chunks
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| {
+            match &c.op {
+                Instruction::Label(l) => Some((l.clone(), i)),
+                _ => None,
+            }
+        })
+        .collect();
+    let (regions, region_of) = regions(chunks);
+    let live_out = liveness(chunks, &labels);
+
+    // slots clobbered by the call of the subroutine, including the ones of its callees.
+    let mut clobbers: HashMap<&str, HashSet<u64>> = regions
+        .iter()
+        .filter_map(|r| Some((r.label.as_deref()?, r.slots.clone())))
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for r in &regions {
+            let Some(label) = r.label.as_deref() else {
+                continue;
+            };
+            let called: HashSet<u64> = r
+                .callees
+                .iter()
+                .filter_map(|c| clobbers.get(c.as_str()))
+                .flatten()
+                .copied()
+                .collect();
+            let own = clobbers.get_mut(label).expect("region is added");
+            let len = own.len();
+            own.extend(called);
+            changed |= own.len() != len;
+        }
+    }
+
+    let mut graph: BTreeMap<u64, BTreeSet<u64>> = BTreeMap::new();
+    let add_edge = |graph: &mut BTreeMap<u64, BTreeSet<u64>>, a: u64, b: u64| {
+        if a != b {
+            graph.entry(a).or_default().insert(b);
+            graph.entry(b).or_default().insert(a);
+        }
+    };
+    for (i, c) in chunks.iter().enumerate() {
+        if let Some(n) = slot(c) {
+            graph.entry(n).or_default();
+        }
+        // the stored value overwrites the cell, so it can not hold any live value.
+        if c.op == Instruction::Store {
+            let n = slot(c).expect("store has a slot");
+            for m in &live_out[i] {
+                add_edge(&mut graph, n, *m);
+            }
+        }
+        if c.op == Instruction::CallSub {
+            let Some(clobbered) = target(c).and_then(|l| clobbers.get(l)) else {
+                continue;
+            };
+            for m in &live_out[i] {
+                for n in clobbered {
+                    add_edge(&mut graph, *n, *m);
+                }
+            }
+        }
+    }
+
+    // slots are coloured in the order they are emitted in.
+    let mut cells: HashMap<u64, u64> = HashMap::new();
+    for (n, neighbours) in &graph {
+        let taken: HashSet<u64> = neighbours
+            .iter()
+            .filter_map(|m| cells.get(m))
+            .copied()
+            .collect();
+        let cell = (RESERVED_SLOTS..)
+            .find(|c| !taken.contains(c))
+            .expect("cell is found");
+        if cell >= SCRATCH_SIZE {
+            let region = chunks
+                .iter()
+                .position(|c| slot(c) == Some(*n))
+                .map(|i| &regions[region_of[i]]);
+            let func = region
+                .and_then(|r| r.label.as_deref())
+                .and_then(|l| l.strip_prefix("__"))
+                .and_then(|name| definition.functions.iter().find(|f| f.name.name == name));
+            let (loc, name) = func
+                .map(|f| (f.loc.clone(), format!("Function `{}`", f.name.name)))
+                .unwrap_or((Span::default(), String::from("Entry point")));
+            return Err(Report::emit_error(
+                loc,
+                format!(
+                    "{} keeps too many values at once, only {} of them fit into the scratch space.",
+                    name,
+                    SCRATCH_SIZE - RESERVED_SLOTS
+                ),
+            ));
+        }
+        cells.insert(*n, cell);
+    }
+
+    for c in chunks.iter_mut() {
+        if !matches!(c.op, Instruction::Load | Instruction::Store) {
+            continue;
+        }
+        if let [Constant::Uint(n)] = c.constants.as_mut_slice() {
+            *n = cells[n];
+        }
+    }
+    Ok(())
+}
+
+/// Slot of the `load` or `store` chunk.
+fn slot(chunk: &Chunk) -> Option<u64> {
+    match (&chunk.op, chunk.constants.as_slice()) {
+        (Instruction::Load | Instruction::Store, [Constant::Uint(n)]) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Label the chunk branches to or calls.
+pub(crate) fn target(chunk: &Chunk) -> Option<&str> {
+    match (&chunk.op, chunk.constants.as_slice()) {
+        (
+            Instruction::Branch
+            | Instruction::BranchZero
+            | Instruction::BranchNotZero
+            | Instruction::CallSub,
+            [Constant::StringLit(l)],
+        ) => Some(l),
+        _ => None,
+    }
+}
+
+/// Split the chunks into the regions of the subroutines.
+///
+/// # Returns
+/// - Regions, the entry point being the first one.
+/// - Index of the region of every chunk.
+fn regions(chunks: &[Chunk]) -> (Vec<Region>, Vec<usize>) {
+    let called: HashSet<&str> = chunks
+        .iter()
+        .filter(|c| c.op == Instruction::CallSub)
+        .filter_map(target)
+        .collect();
+
+    let mut regions = vec![Region::default()];
+    let mut region_of = Vec::with_capacity(chunks.len());
+    for c in chunks {
+        match &c.op {
+            Instruction::Label(l) if called.contains(l.as_str()) => {
+                regions.push(Region {
+                    label: Some(l.clone()),
+                    ..Default::default()
+                });
+            }
+            Instruction::CallSub => {
+                if let Some(l) = target(c) {
+                    let current = regions.last_mut().expect("entry point is added");
+                    current.callees.insert(l.to_string());
+                }
+            }
+            _ => {}
+        }
+        if let Some(n) = slot(c) {
+            let current = regions.last_mut().expect("entry point is added");
+            current.slots.insert(n);
+        }
+        region_of.push(regions.len() - 1);
+    }
+    (regions, region_of)
+}
+
+/// Chunks the control can flow to after the chunk.
+///
+/// The called subroutines return to the next chunk.
+pub(crate) fn successors(
+    chunks: &[Chunk],
+    i: usize,
+    labels: &HashMap<String, usize>,
+) -> Vec<usize> {
+    let next = Some(i + 1).filter(|n| *n < chunks.len());
+    let jump = target(&chunks[i]).and_then(|l| labels.get(l)).copied();
+    match chunks[i].op {
+        Instruction::Branch => jump.into_iter().collect(),
+        Instruction::BranchZero | Instruction::BranchNotZero => {
+            jump.into_iter().chain(next).collect()
+        }
+        Instruction::Return | Instruction::ReturnSubroutine | Instruction::Error => vec![],
+        _ => next.into_iter().collect(),
+    }
+}
+
+/// Slots live after every chunk.
+fn liveness(chunks: &[Chunk], labels: &HashMap<String, usize>) -> Vec<HashSet<u64>> {
+    let succ: Vec<Vec<usize>> = (0..chunks.len())
+        .map(|i| successors(chunks, i, labels))
+        .collect();
+    let mut pred: Vec<Vec<usize>> = vec![vec![]; chunks.len()];
+    for (i, s) in succ.iter().enumerate() {
+        for j in s {
+            pred[*j].push(i);
+        }
+    }
+
+    let mut live_in: Vec<HashSet<u64>> = vec![HashSet::new(); chunks.len()];
+    let mut pending: Vec<usize> = (0..chunks.len()).collect();
+    let mut queued = vec![true; chunks.len()];
+    while let Some(i) = pending.pop() {
+        queued[i] = false;
+        let mut live: HashSet<u64> = succ[i]
+            .iter()
+            .flat_map(|s| live_in[*s].iter().copied())
+            .collect();
+        match (&chunks[i].op, slot(&chunks[i])) {
+            (Instruction::Store, Some(n)) => {
+                live.remove(&n);
+            }
+            (Instruction::Load, Some(n)) => {
+                live.insert(n);
+            }
+            _ => {}
+        }
+        if live != live_in[i] {
+            live_in[i] = live;
+            for p in &pred[i] {
+                if !queued[*p] {
+                    queued[*p] = true;
+                    pending.push(*p);
+                }
+            }
+        }
+    }
+
+    succ.iter()
+        .map(|s| s.iter().flat_map(|j| live_in[*j].iter().copied()).collect())
+        .collect()
+}
@@ -0,0 +1,151 @@
+//! Source map of the approval program.
+//!
+//! The lines of the TEAL program are mapped onto the lines of the Folidity modules
+//! the code is emitted for, following the Source Map Revision 3 format consumed
+//! by the debuggers.
+
+use folidity_diagnostics::ColumnUnit;
+use folidity_semantics::modules::Project;
+use serde_json::json;
+
+use crate::teal::TealArtifacts;
+
+/// Alphabet of the base64 digits of the mappings.
+const BASE64_DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Location in the source of the line of the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// Index of the module in the sources.
+    pub source: usize,
+    /// Line in the module, counted from zero.
+    pub line: usize,
+    /// Column in the line, counted from zero.
+    pub column: usize,
+}
+
+/// Mapping of the approval program onto the source code.
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    /// Paths to the modules of the project.
+    pub sources: Vec<String>,
+    /// Locations of the lines of the approval program.
+    pub lines: Vec<Option<SourceLocation>>,
+}
+
+impl SourceMap {
+    pub fn new(project: &Project, artifacts: &TealArtifacts) -> Self {
+        let maps: Vec<folidity_diagnostics::SourceMap> = project
+            .modules
+            .iter()
+            .map(|m| folidity_diagnostics::SourceMap::new(&m.contents))
+            .collect();
+        let lines = artifacts
+            .source_map
+            .iter()
+            .map(|loc| {
+                let (module, span) = project.locate(loc.as_ref()?)?;
+                let source = project
+                    .modules
+                    .iter()
+                    .position(|m| m.offset == module.offset)?;
+                let pos = maps[source].position(&module.contents, span.start, ColumnUnit::Byte);
+                Some(SourceLocation {
+                    source,
+                    line: pos.line,
+                    column: pos.column,
+                })
+            })
+            .collect();
+        Self {
+            sources: project
+                .modules
+                .iter()
+                .map(|m| m.path.to_string_lossy().to_string())
+                .collect(),
+            lines,
+        }
+    }
+
+    /// Encode the mappings of the lines, every located line has a single segment.
+    fn mappings(&self) -> String {
+        let mut mappings = String::new();
+        let mut previous = SourceLocation {
+            source: 0,
+            line: 0,
+            column: 0,
+        };
+        for (i, loc) in self.lines.iter().enumerate() {
+            if i > 0 {
+                mappings.push(';');
+            }
+            let Some(loc) = loc else {
+                continue;
+            };
+            // the fields are relative to the previous segment, but the column of the program.
+            vlq(0, &mut mappings);
+            vlq(loc.source as i64 - previous.source as i64, &mut mappings);
+            vlq(loc.line as i64 - previous.line as i64, &mut mappings);
+            vlq(loc.column as i64 - previous.column as i64, &mut mappings);
+            previous = loc.clone();
+        }
+        mappings
+    }
+
+    /// Add the comments referencing the source lines to the program,
+    /// wherever the line of the source changes.
+    ///
+    /// The comments are trailing, so that the lines of the program are not shifted.
+    pub fn annotate(&self, approval: &[u8]) -> Vec<u8> {
+        let program = String::from_utf8_lossy(approval);
+        let mut previous = None;
+        let mut lines = vec![];
+        for (i, line) in program.split('\n').enumerate() {
+            match self.lines.get(i).cloned().flatten() {
+                Some(loc) if previous.as_ref() != Some(&(loc.source, loc.line)) => {
+                    lines.push(format!(
+                        "{} // {}:{}",
+                        line.trim_end(),
+                        self.sources[loc.source],
+                        loc.line + 1
+                    ));
+                    previous = Some((loc.source, loc.line));
+                }
+                _ => lines.push(line.to_string()),
+            }
+        }
+        lines.join("\n").into_bytes()
+    }
+
+    pub fn to_json(&self, file: &str) -> String {
+        let map = json!({
+            "version": 3,
+            "file": file,
+            "sources": self.sources,
+            "names": [],
+            "mappings": self.mappings(),
+        });
+        serde_json::to_string_pretty(&map).expect("source map is always serializable")
+    }
+}
+
+/// Append the base64 VLQ encoding of the value.
+fn vlq(value: i64, out: &mut String) {
+    // the sign is kept in the lowest bit.
+    let mut rest = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = rest & 0b11111;
+        rest >>= 5;
+        if rest > 0 {
+            digit |= 0b100000;
+        }
+        out.push(BASE64_DIGITS[digit as usize] as char);
+        if rest == 0 {
+            break;
+        }
+    }
+}
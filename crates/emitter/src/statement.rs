@@ -8,20 +8,40 @@ use folidity_semantics::ast::{
     ForLoop,
     FuncReturnType,
     IfElse,
+    Match,
     Statement,
     TypeVariant,
     Variable,
+    WhileLoop,
 };
 
 use crate::{
     add_padding,
     ast::{
+        has_size_block,
+        locate,
         Chunk,
         Constant,
         Instruction,
+        TypeSizeHint,
+    },
+    event::emit_event,
+    expression::{
+        emit_expression,
+        extract_payload_field,
+        option_value,
+        variant_tag,
+    },
+    list,
+    mapping::{
+        from_bytes,
+        to_bytes,
+        MAX_BOX_SIZE,
+    },
+    teal::{
+        EmitArgs,
+        STATE_KEY,
     },
-    expression::emit_expression,
-    teal::EmitArgs,
 };
 
 type EmitResult = Result<(), ()>;
@@ -38,31 +58,145 @@ pub fn emit_statement(
         Statement::Expression(e) => emit_expression(e, &mut local_chunks, args).map(|_| ()),
         Statement::IfElse(b) => if_else(b, &mut local_chunks, args),
         Statement::ForLoop(l) => for_loop(l, &mut local_chunks, args),
-        Statement::Iterator(it) => iterator(it, chunks, args),
+        Statement::While(l) => while_loop(l, &mut local_chunks, args),
+        Statement::Iterator(it) => iterator(it, &mut local_chunks, args),
+        Statement::Match(m) => match_(m, &mut local_chunks, args),
         Statement::Return(r) => return_(&r.expr, &mut local_chunks, args),
         Statement::StateTransition(e) => state_transition(e, &mut local_chunks, args),
+        Statement::Emit(e) => emit_event(e, &mut local_chunks, args),
         Statement::Block(b) => block(&b.statements, &mut local_chunks, args),
-        Statement::Skip(loc) => skip(loc, &mut local_chunks, args),
+        Statement::Skip(loc) => loop_jump(loc, false, &mut local_chunks, args),
+        Statement::Break(loc) => loop_jump(loc, true, &mut local_chunks, args),
         Statement::Error(_) => unreachable!(),
     }?;
+    locate(&mut local_chunks, stmt.loc());
     add_padding(&mut local_chunks);
     chunks.extend(local_chunks);
 
     Ok(())
 }
 
+/// Iterate over the elements of the list or set.
+///
+/// Elements of the fixed size are stored back to back, while the resizable ones
+/// are preceded by the `uint64` block of their size.
+/// The offset is advanced past the element before the body is executed,
+/// so the next iteration starts from the check of the offset.
 fn iterator(
     it: &folidity_semantics::ast::Iterator,
-    _chunks: &mut Vec<Chunk>,
+    chunks: &mut Vec<Chunk>,
     args: &mut EmitArgs,
 ) -> EmitResult {
-    let _ = _chunks;
-    args.diagnostics.push(Report::ver_error(
-        it.loc.clone(),
-        "Iterators are not yer supported.".to_string(),
-    ));
+    let definition = args.emitter.definition;
+    let (TypeVariant::List(item) | TypeVariant::Set(item)) = it.list.ty() else {
+        unreachable!("resolved to a list or a set")
+    };
+    let item_size = if has_size_block(item) {
+        None
+    } else {
+        Some(list::item_size(item, definition).unwrap_or_else(|| item.size_hint(definition)))
+    };
+    let pos = it.names.first().and_then(|name| {
+        args.func
+            .scope
+            .vars
+            .iter()
+            .find(|(_, v)| v.ident.loc == name.loc)
+            .map(|(i, _)| *i)
+    });
+    let (Some(pos), false) = (pos, item_size == Some(0)) else {
+        args.diagnostics.push(Report::emit_error(
+            it.loc.clone(),
+            String::from("Elements of this type cannot be iterated over."),
+        ));
+        return Err(());
+    };
 
-    Err(())
+    let mut loop_chunks = vec![];
+    let loop_index = args.emitter.loop_index_incr()?;
+    let cond_label = format!("{}_loop_cond", loop_index);
+    let end_label = format!("{}_loop_end", loop_index);
+    let list_index = args.emitter.scratch_index_incr()?;
+    let offset_index = args.emitter.scratch_index_incr()?;
+    let load = |slot: u64| Chunk::new_single(Instruction::Load, Constant::Uint(slot));
+    let store = |slot: u64| Chunk::new_single(Instruction::Store, Constant::Uint(slot));
+
+    emit_expression(&it.list, &mut loop_chunks, args)?;
+    loop_chunks.extend([
+        store(list_index),
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(0)),
+        store(offset_index),
+        // exit when the offset reaches the end of the list.
+        Chunk::new_empty(Instruction::Label(cond_label.clone())),
+        load(offset_index),
+        load(list_index),
+        Chunk::new_empty(Instruction::Len),
+        Chunk::new_empty(Instruction::GreaterEq),
+        Chunk::new_single(
+            Instruction::BranchNotZero,
+            Constant::StringLit(end_label.clone()),
+        ),
+    ]);
+
+    match item_size {
+        Some(size) => {
+            loop_chunks.extend([
+                load(list_index),
+                load(offset_index),
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(size)),
+                Chunk::new_empty(Instruction::Extract3),
+                load(offset_index),
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(size)),
+                Chunk::new_empty(Instruction::Plus),
+                store(offset_index),
+            ]);
+            from_bytes(item, &mut loop_chunks);
+        }
+        None => {
+            // the size block is read first, and the data follows it.
+            let size_index = args.emitter.scratch_index_incr()?;
+            loop_chunks.extend([
+                load(list_index),
+                load(offset_index),
+                Chunk::new_empty(Instruction::ExtractUint),
+                store(size_index),
+                load(offset_index),
+                Chunk::new_single(Instruction::PushInt, Constant::Uint(8)),
+                Chunk::new_empty(Instruction::Plus),
+                store(offset_index),
+                load(list_index),
+                load(offset_index),
+                load(size_index),
+                Chunk::new_empty(Instruction::Extract3),
+                load(offset_index),
+                load(size_index),
+                Chunk::new_empty(Instruction::Plus),
+                store(offset_index),
+            ]);
+        }
+    }
+
+    // bind the element to the variable of the iterator.
+    let index = args
+        .scratch
+        .add_var(pos, item.size_hint(definition), args.emitter)?;
+    loop_chunks.push(store(index));
+    args.emitter.concrete_vars.insert(pos, vec![load(index)]);
+
+    args.loop_labels
+        .push((cond_label.clone(), end_label.clone()));
+    let result = block(&it.body, &mut loop_chunks, args);
+    args.loop_labels.pop();
+    result?;
+
+    loop_chunks.extend([
+        Chunk::new_single(Instruction::Branch, Constant::StringLit(cond_label)),
+        Chunk::new_empty(Instruction::Label(end_label)),
+    ]);
+
+    chunks.extend(loop_chunks);
+
+    Ok(())
 }
 
 fn variable(
@@ -88,7 +222,7 @@ fn variable(
         chunks.push(Chunk::new_empty(Instruction::ArrayInit));
     }
 
-    let index = args.scratch.add_var(var.pos, size, args.emitter) as u64;
+    let index = args.scratch.add_var(var.pos, size, args.emitter)?;
     chunks.push(Chunk::new_single(Instruction::Store, Constant::Uint(index)));
 
     args.emitter.concrete_vars.insert(
@@ -100,6 +234,9 @@ fn variable(
 }
 
 fn assign(var: &Assign, loc: &Span, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    if !var.fields.is_empty() {
+        return assign_field(var, loc, chunks, args);
+    }
     let size = emit_expression(&var.value, chunks, args)?;
     let Some(var_scratch) = args.scratch.get_var_mut(var.pos) else {
         args.diagnostics.push(Report::ver_error(
@@ -110,23 +247,89 @@ fn assign(var: &Assign, loc: &Span, chunks: &mut Vec<Chunk>, args: &mut EmitArgs
     };
 
     var_scratch.size = size;
-    let index = var_scratch.index as u64;
+    let index = var_scratch.index;
     chunks.push(Chunk::new_single(Instruction::Store, Constant::Uint(index)));
 
     Ok(())
 }
 
-fn skip(loc: &Span, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
-    if args.loop_labels.is_empty() {
+/// Replace the field of the object stored in the scratch with the value.
+///
+/// Nested objects are stored inline, so the offset of the field is the sum of the offsets
+/// along the chain of the fields.
+fn assign_field(
+    var: &Assign,
+    loc: &Span,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
+    let Some(index) = args.scratch.get_var(var.pos).map(|v| v.index) else {
+        args.diagnostics.push(Report::ver_error(
+            loc.clone(),
+            String::from("Variable is undeclared."),
+        ));
+        return Err(());
+    };
+
+    let definition = args.emitter.definition;
+    let mut ty = args.func.scope.vars[&var.pos].ty.clone();
+    let mut offset = 0;
+    for field in &var.fields {
+        let fields = ty.fields(definition).expect("fields are resolved");
+        for f in &fields[..*field] {
+            offset += f.ty.ty.size_hint(definition);
+            if has_size_block(&f.ty.ty) {
+                offset += 8;
+            }
+        }
+        ty = fields[*field].ty.ty.clone();
+    }
+
+    emit_expression(&var.value, chunks, args)?;
+    if matches!(ty, TypeVariant::U8 | TypeVariant::U64) {
+        to_bytes(&ty, chunks);
+    }
+    let data_index = args.emitter.scratch_index_incr()?;
+    chunks.push(Chunk::new_single(
+        Instruction::Store,
+        Constant::Uint(data_index),
+    ));
+
+    if has_size_block(&ty) {
+        chunks.extend_from_slice(&[
+            Chunk::new_single(Instruction::Load, Constant::Uint(index)),
+            Chunk::new_single(Instruction::Load, Constant::Uint(data_index)),
+            Chunk::new_empty(Instruction::Len),
+            Chunk::new_empty(Instruction::Itob),
+            Chunk::new_single(Instruction::Replace, Constant::Uint(offset)),
+            Chunk::new_single(Instruction::Store, Constant::Uint(index)),
+        ]);
+        offset += 8;
+    }
+    chunks.extend_from_slice(&[
+        Chunk::new_single(Instruction::Load, Constant::Uint(index)),
+        Chunk::new_single(Instruction::Load, Constant::Uint(data_index)),
+        Chunk::new_single(Instruction::Replace, Constant::Uint(offset)),
+        Chunk::new_single(Instruction::Store, Constant::Uint(index)),
+    ]);
+
+    Ok(())
+}
+
+/// Jump to the next iteration of the enclosing loop, or out of it for `break`.
+fn loop_jump(loc: &Span, exit: bool, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let Some((next_label, end_label)) = args.loop_labels.last() else {
         args.diagnostics.push(Report::ver_error(
             loc.clone(),
             String::from("Not a loop context."),
         ));
-    }
+        return Err(());
+    };
 
+    let label = if exit { end_label } else { next_label };
     chunks.push(Chunk::new_single(
         Instruction::Branch,
-        Constant::StringLit(args.loop_labels.last().expect("should exist").clone()),
+        Constant::StringLit(label.clone()),
     ));
 
     Ok(())
@@ -149,6 +352,7 @@ fn block(stmts: &[Statement], chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> E
 fn for_loop(l: &ForLoop, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
     let mut loop_chunks = vec![];
     let loop_index = args.emitter.loop_index_incr()?;
+    let cond_label = format!("{}_loop_cond", loop_index);
     let incr_label = format!("{}_loop_incr", loop_index);
     let end_label = format!("{}_loop_end", loop_index);
     let mut error = false;
@@ -156,28 +360,72 @@ fn for_loop(l: &ForLoop, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitRe
     // create var and store it.
     error |= variable(&l.var, &l.loc, &mut loop_chunks, args).is_err();
     // emit condition block
+    loop_chunks.push(Chunk::new_empty(Instruction::Label(cond_label.clone())));
     error |= emit_expression(&l.condition, &mut loop_chunks, args).is_err();
-    // jump to end if satisfies (i.e. 1)
+    // jump to end if not satisfies (i.e. 0)
     loop_chunks.push(Chunk::new_single(
-        Instruction::BranchNotZero,
+        Instruction::BranchZero,
         Constant::StringLit(end_label.clone()),
     ));
 
     // emit body
-    args.loop_labels.push(incr_label.clone());
+    args.loop_labels
+        .push((incr_label.clone(), end_label.clone()));
     error |= block(&l.body, &mut loop_chunks, args).is_err();
+    args.loop_labels.pop();
 
-    // emit increment logic
-    // create increment label
+    // the value of the incrementer is assigned to the loop variable.
     loop_chunks.push(Chunk::new_empty(Instruction::Label(incr_label.clone())));
     error |= emit_expression(&l.incrementer, &mut loop_chunks, args).is_err();
+    if let Some(var) = args.scratch.get_var(l.var.pos) {
+        loop_chunks.push(Chunk::new_single(
+            Instruction::Store,
+            Constant::Uint(var.index),
+        ));
+    }
+    loop_chunks.push(Chunk::new_single(
+        Instruction::Branch,
+        Constant::StringLit(cond_label),
+    ));
 
     // emit end label.
     loop_chunks.push(Chunk::new_empty(Instruction::Label(end_label.clone())));
 
-    // pop label
+    if error {
+        return Err(());
+    }
+
+    chunks.extend(loop_chunks);
+
+    Ok(())
+}
+
+fn while_loop(l: &WhileLoop, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let mut loop_chunks = vec![];
+    let loop_index = args.emitter.loop_index_incr()?;
+    let cond_label = format!("{}_loop_cond", loop_index);
+    let end_label = format!("{}_loop_end", loop_index);
+    let mut error = false;
+
+    loop_chunks.push(Chunk::new_empty(Instruction::Label(cond_label.clone())));
+    error |= emit_expression(&l.condition, &mut loop_chunks, args).is_err();
+    loop_chunks.push(Chunk::new_single(
+        Instruction::BranchZero,
+        Constant::StringLit(end_label.clone()),
+    ));
+
+    // the next iteration starts with the condition.
+    args.loop_labels
+        .push((cond_label.clone(), end_label.clone()));
+    error |= block(&l.body, &mut loop_chunks, args).is_err();
     args.loop_labels.pop();
 
+    loop_chunks.push(Chunk::new_single(
+        Instruction::Branch,
+        Constant::StringLit(cond_label),
+    ));
+    loop_chunks.push(Chunk::new_empty(Instruction::Label(end_label)));
+
     if error {
         return Err(());
     }
@@ -227,6 +475,102 @@ fn if_else(b: &IfElse, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResu
     Ok(())
 }
 
+/// Dispatch on the tag of the variant to the matching arm.
+///
+/// The fields of the payload are extracted into the variables of the arm before its body.
+fn match_(m: &Match, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let mut block_chunks = vec![];
+    let index = args.emitter.cond_index_incr()?;
+    let end_label = format!("{}_match_end", index);
+    let value_index = args.emitter.scratch_index_incr()?;
+    let mut error = false;
+
+    error |= emit_expression(&m.expr, &mut block_chunks, args).is_err();
+    block_chunks.push(Chunk::new_single(
+        Instruction::Store,
+        Constant::Uint(value_index),
+    ));
+
+    for (i, arm) in m.arms.iter().enumerate() {
+        let arm_label = Constant::StringLit(format!("{}_match_{}", index, i));
+        let Some(variant) = arm.variant else {
+            block_chunks.push(Chunk::new_single(Instruction::Branch, arm_label));
+            break;
+        };
+        block_chunks.push(Chunk::new_single(
+            Instruction::Load,
+            Constant::Uint(value_index),
+        ));
+        block_chunks.extend(variant_tag(m.expr.ty()));
+        block_chunks.extend_from_slice(&[
+            Chunk::new_single(Instruction::PushInt, Constant::Uint(variant as u64)),
+            Chunk::new_empty(Instruction::Eq),
+            Chunk::new_single(Instruction::BranchNotZero, arm_label),
+        ]);
+    }
+    // the arms cover every variant.
+    block_chunks.push(Chunk::new_empty(Instruction::Error));
+
+    for (i, arm) in m.arms.iter().enumerate() {
+        block_chunks.push(Chunk::new_empty(Instruction::Label(format!(
+            "{}_match_{}",
+            index, i
+        ))));
+
+        for (field, var) in &arm.bindings {
+            block_chunks.push(Chunk::new_single(
+                Instruction::Load,
+                Constant::Uint(value_index),
+            ));
+            let size = match m.expr.ty() {
+                TypeVariant::Option(ty) => {
+                    block_chunks.push(option_value());
+                    ty.size_hint(args.emitter.definition)
+                }
+                TypeVariant::Enum(sym) => {
+                    let enum_decl = &args.emitter.definition.enums[sym.i];
+                    let fields = arm
+                        .variant
+                        .and_then(|v| enum_decl.variants.get_index(v))
+                        .map(|(_, v)| v.fields.clone())
+                        .unwrap_or_default();
+                    error |=
+                        extract_payload_field(&fields, *field, &mut block_chunks, args).is_err();
+                    fields[*field].ty.ty.size_hint(args.emitter.definition)
+                }
+                _ => unreachable!(),
+            };
+            let var_index = args.scratch.add_var(*var, size, args.emitter)?;
+            block_chunks.push(Chunk::new_single(
+                Instruction::Store,
+                Constant::Uint(var_index),
+            ));
+            args.emitter.concrete_vars.insert(
+                *var,
+                vec![Chunk::new_single(
+                    Instruction::Load,
+                    Constant::Uint(var_index),
+                )],
+            );
+        }
+
+        error |= block(&arm.body, &mut block_chunks, args).is_err();
+        block_chunks.push(Chunk::new_single(
+            Instruction::Branch,
+            Constant::StringLit(end_label.clone()),
+        ));
+    }
+    block_chunks.push(Chunk::new_empty(Instruction::Label(end_label)));
+
+    if error {
+        return Err(());
+    }
+
+    chunks.extend(block_chunks);
+
+    Ok(())
+}
+
 fn state_transition(e: &Expression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
     let mut local_chunks = vec![];
 
@@ -236,6 +580,18 @@ fn state_transition(e: &Expression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs
     let state_decl = &args.emitter.definition.states[sym.i];
     let box_name = format!("__{}", state_decl.name.name);
 
+    let size = e.ty().size_hint(args.emitter.definition);
+    if size > MAX_BOX_SIZE {
+        args.diagnostics.push(Report::emit_error(
+            e.loc().clone(),
+            format!(
+                "State `{}` takes {} bytes, which exceeds the max box size of {} bytes.",
+                state_decl.name.name, size, MAX_BOX_SIZE
+            ),
+        ));
+        return Err(());
+    }
+
     // push name of a box onto stack
     let name_chunk = Chunk::new_single(Instruction::PushBytes, Constant::String(box_name));
     local_chunks.push(name_chunk);
@@ -277,6 +633,16 @@ fn state_transition(e: &Expression, chunks: &mut Vec<Chunk>, args: &mut EmitArgs
     let box_chunk = Chunk::new_empty(Instruction::BoxPut);
     local_chunks.push(box_chunk);
 
+    // track the state the contract is transitioned to.
+    local_chunks.extend_from_slice(&[
+        Chunk::new_single(
+            Instruction::PushBytes,
+            Constant::String(STATE_KEY.to_string()),
+        ),
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(sym.i as u64 + 1)),
+        Chunk::new_empty(Instruction::AppGlobalPut),
+    ]);
+
     chunks.extend(local_chunks);
 
     Ok(())
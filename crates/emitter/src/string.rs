@@ -0,0 +1,66 @@
+//! Lowering of the string operations.
+//!
+//! Strings are stored as raw bytes, so their length and slices
+//! map directly onto the byte instructions.
+
+use folidity_diagnostics::Report;
+use folidity_semantics::{
+    ast::BuiltinCall,
+    builtin::BuiltinFunction,
+};
+
+use crate::{
+    ast::{
+        Chunk,
+        Constant,
+        Instruction,
+        TypeSizeHint,
+    },
+    expression::emit_expression,
+    teal::EmitArgs,
+};
+
+type EmitResult = Result<u64, ()>;
+
+/// Emit the call of the built-in function on the string.
+///
+/// `starts_with` and `contains` call the helper subroutines,
+/// as the slices out of bounds fail the program.
+pub fn emit_string_call(
+    f: &BuiltinCall,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
+    let op = match f.func {
+        BuiltinFunction::Length => Chunk::new_empty(Instruction::Len),
+        BuiltinFunction::Substring => Chunk::new_empty(Instruction::Substring),
+        BuiltinFunction::StartsWith => {
+            Chunk::new_single(
+                Instruction::CallSub,
+                Constant::StringLit("string_starts_with".to_string()),
+            )
+        }
+        BuiltinFunction::Contains => {
+            Chunk::new_single(
+                Instruction::CallSub,
+                Constant::StringLit("string_contains".to_string()),
+            )
+        }
+        _ => {
+            args.diagnostics.push(Report::emit_error(
+                f.loc.clone(),
+                format!("`{}` is not yet supported for this type.", f.func),
+            ));
+            return Err(());
+        }
+    };
+
+    let mut local_chunks = vec![];
+    for a in &f.args {
+        emit_expression(a, &mut local_chunks, args)?;
+    }
+    local_chunks.push(op);
+    chunks.extend(local_chunks);
+
+    Ok(f.returns.size_hint(args.emitter.definition))
+}
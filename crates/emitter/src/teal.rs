@@ -5,22 +5,55 @@ use folidity_semantics::{
         Function,
         TypeVariant,
     },
+    optimizer::OptLevel,
     ContractDefinition,
     Span,
 };
 use indexmap::IndexMap;
+use std::collections::HashSet;
+
+pub use crate::{
+    cost::{
+        FunctionCost,
+        APP_CALL_BUDGET,
+    },
+    mapping::{
+        BoxKind,
+        BoxLayout,
+    },
+};
 
 use crate::{
     add_padding,
+    app_spec::{
+        abi_events,
+        abi_methods,
+        EventSpec,
+        MethodSpec,
+        StateSchema,
+    },
     ast::{
         Chunk,
         Constant,
         Instruction,
     },
+    cost::function_costs,
     function::emit_function,
-    scratch_table::ScratchTable,
+    mapping::storage_layout,
+    optimizer::optimize,
+    schema::state_schema,
+    scratch_table::{
+        allocate_slots,
+        ScratchTable,
+    },
 };
 
+/// Key of the global variable holding the current state of the contract.
+///
+/// The state is stored as its index incremented by one,
+/// so that the missing key of a fresh application means no state.
+pub const STATE_KEY: &str = "__state";
+
 /// Arguments for emitter operations.
 #[derive(Debug)]
 pub struct EmitArgs<'a, 'b> {
@@ -29,7 +62,8 @@ pub struct EmitArgs<'a, 'b> {
     pub emitter: &'b mut TealEmitter<'a>,
     pub delayed_bounds: &'b mut Vec<Expression>,
     pub func: &'b Function,
-    pub loop_labels: &'b mut Vec<String>,
+    /// Labels of the next iteration and of the end of the enclosing loops.
+    pub loop_labels: &'b mut Vec<(String, String)>,
 }
 
 #[derive(Debug, Clone)]
@@ -38,6 +72,57 @@ pub struct TealArtifacts {
     pub approval_bytes: Vec<u8>,
     /// Teal clear program bytes.
     pub clear_bytes: Vec<u8>,
+    /// Boxes used to store the states and mappings.
+    pub storage: Vec<BoxLayout>,
+    /// ARC-4 descriptions of the callable functions.
+    pub methods: Vec<MethodSpec>,
+    /// ARC-28 descriptions of the logged events.
+    pub events: Vec<EventSpec>,
+    /// Configuration of the application creation.
+    pub init: InitConfig,
+    /// Warnings about the storage exceeding the limits of AVM.
+    pub warnings: Vec<Report>,
+    /// Number of the bounds of the instantiations that are not asserted.
+    pub elided_asserts: u64,
+    /// Estimated opcode costs of the functions.
+    pub costs: Vec<FunctionCost>,
+    /// Locations of the source code of the lines of the approval program.
+    ///
+    /// The pragma and the appended helper subroutines are not located.
+    pub source_map: Vec<Option<Span>>,
+}
+
+/// Bounds of the instantiated structures asserted at runtime.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RuntimeChecks {
+    /// No bounds are asserted.
+    None,
+    /// Bounds are asserted unless they are proven to hold by the verifier,
+    /// given by the locations of the instantiation and the bound.
+    Verified(HashSet<(Span, Span)>),
+    /// Every bound is asserted.
+    #[default]
+    All,
+}
+
+impl RuntimeChecks {
+    /// Whether the bound at `loc` is asserted for the instantiation at `init`.
+    pub fn asserts(&self, init: &Span, loc: &Span) -> bool {
+        match self {
+            RuntimeChecks::None => false,
+            RuntimeChecks::Verified(proven) => !proven.contains(&(init.clone(), loc.clone())),
+            RuntimeChecks::All => true,
+        }
+    }
+}
+
+/// Configuration of the application creation.
+#[derive(Debug, Clone, Default)]
+pub struct InitConfig {
+    /// Name of the constructor called at the creation.
+    pub function: Option<String>,
+    /// Number of the global and local storage slots to allocate.
+    pub schema: StateSchema,
 }
 
 #[derive(Debug)]
@@ -45,13 +130,14 @@ pub struct TealEmitter<'a> {
     /// Nested definition of the contract.
     pub definition: &'a ContractDefinition,
     /// List of chunks that are emitted into the final build.
-    chunks: Vec<Chunk>,
+    pub(crate) chunks: Vec<Chunk>,
     /// Errors and warning caused during emit process.
     pub diagnostics: Vec<Report>,
-    /// Index for scratch space variable.
+    /// Index of the next virtual scratch slot.
     ///
-    /// We use `u8` as there are only 256 cells available.
-    pub scratch_index: u8,
+    /// The virtual slots are mapped onto the 256 cells of the scratch space
+    /// once the program is emitted.
+    pub scratch_index: u64,
 
     /// Counter for loops.
     pub loop_counter: u64,
@@ -60,6 +146,20 @@ pub struct TealEmitter<'a> {
     pub cond_counter: u64,
     /// list of concrete teal expression to access vars.
     pub concrete_vars: IndexMap<usize, Vec<Chunk>>,
+
+    /// Functions in the recursive cycle of every function.
+    pub recursive_cycles: Vec<HashSet<usize>>,
+    /// Index of the function being emitted.
+    pub current_func: Option<usize>,
+    /// Scratch index of the first variable of the function being emitted.
+    ///
+    /// The slots from it are saved on the stack around the recursive calls.
+    pub frame_start: u64,
+
+    /// Bounds of the instantiations asserted at runtime.
+    pub runtime_checks: RuntimeChecks,
+    /// Number of the bounds that are not asserted.
+    pub elided_asserts: u64,
 }
 
 impl<'a> TealEmitter<'a> {
@@ -72,6 +172,11 @@ impl<'a> TealEmitter<'a> {
             loop_counter: 0,
             cond_counter: 0,
             concrete_vars: IndexMap::new(),
+            recursive_cycles: definition.recursive_cycles(),
+            current_func: None,
+            frame_start: 0,
+            runtime_checks: RuntimeChecks::default(),
+            elided_asserts: 0,
         }
     }
 
@@ -90,21 +195,21 @@ impl<'a> TealEmitter<'a> {
             Constant::StringLit(create_end_label.clone()),
         ));
 
-        let init_func_i = self
-            .definition
-            .functions
-            .iter()
-            .position(|f| f.is_init)
-            .expect("should be defined");
-
-        let init_func_name = format!(
-            "__block__{}",
-            self.definition.functions[init_func_i].name.name
-        );
-        chunks.push(Chunk::new_single(
-            Instruction::Branch,
-            Constant::StringLit(init_func_name),
-        ));
+        // the constructor is only called at the creation of the application.
+        match self.definition.functions.iter().find(|f| f.is_init) {
+            Some(init) => {
+                chunks.push(Chunk::new_single(
+                    Instruction::Branch,
+                    Constant::StringLit(format!("__block__{}", init.name.name)),
+                ));
+            }
+            None => {
+                chunks.extend_from_slice(&[
+                    Chunk::new_single(Instruction::PushInt, Constant::Uint(1)),
+                    Chunk::new_empty(Instruction::Return),
+                ]);
+            }
+        }
         chunks.push(Chunk::new_empty(Instruction::Label(create_end_label)));
 
         chunks.extend_from_slice(&[
@@ -189,7 +294,14 @@ impl<'a> TealEmitter<'a> {
 
         chunks.push(Chunk::new_empty(Instruction::Label("on_call".to_string())));
 
-        for name in self.definition.functions.iter().map(|f| &f.name.name) {
+        // the constructor is not dispatched, so that the contract can not be reinitialised.
+        for name in self
+            .definition
+            .functions
+            .iter()
+            .filter(|f| !f.is_init)
+            .map(|f| &f.name.name)
+        {
             chunks.extend_from_slice(&[
                 Chunk::new_multiple(
                     Instruction::Txna,
@@ -219,7 +331,9 @@ impl<'a> TealEmitter<'a> {
     pub fn emit_functions(&mut self) -> bool {
         let mut error = false;
 
-        for func in &self.definition.functions {
+        for (i, func) in self.definition.functions.iter().enumerate() {
+            self.current_func = Some(i);
+            self.frame_start = self.scratch_index;
             if let Ok(mut chunks) = emit_function(func, self) {
                 add_padding(&mut chunks);
                 self.chunks.extend(chunks);
@@ -231,6 +345,18 @@ impl<'a> TealEmitter<'a> {
         !error
     }
 
+    /// Optimize the emitted chunks and allocate the cells of the scratch space to them.
+    pub fn allocate_scratch(&mut self) -> bool {
+        if self.definition.opt_level > OptLevel::O0 {
+            self.chunks = optimize(std::mem::take(&mut self.chunks));
+        }
+        if let Err(report) = allocate_slots(&mut self.chunks, self.definition) {
+            self.diagnostics.push(report);
+            return false;
+        }
+        true
+    }
+
     pub fn compile(&mut self) -> TealArtifacts {
         let approval_string = self
             .chunks
@@ -253,10 +379,36 @@ impl<'a> TealEmitter<'a> {
 
         let helper_bytes = include_bytes!("../helpers/signed_arithmetic.teal");
         approval_bytes.extend_from_slice(helper_bytes);
-
+        let list_helper_bytes = include_bytes!("../helpers/list.teal");
+        approval_bytes.extend_from_slice(list_helper_bytes);
+        let fixed_point_helper_bytes = include_bytes!("../helpers/fixed_point.teal");
+        approval_bytes.extend_from_slice(fixed_point_helper_bytes);
+        let string_helper_bytes = include_bytes!("../helpers/string.teal");
+        approval_bytes.extend_from_slice(string_helper_bytes);
+
+        let mut warnings = vec![];
+        let costs = function_costs(&self.chunks, self.definition, &mut warnings);
         TealArtifacts {
             approval_bytes,
             clear_bytes,
+            storage: storage_layout(self.definition),
+            methods: abi_methods(self.definition),
+            events: abi_events(self.definition),
+            init: InitConfig {
+                function: self
+                    .definition
+                    .functions
+                    .iter()
+                    .find(|f| f.is_init)
+                    .map(|f| f.name.name.clone()),
+                schema: state_schema(self.definition, &mut warnings),
+            },
+            warnings,
+            elided_asserts: self.elided_asserts,
+            costs,
+            source_map: std::iter::once(None)
+                .chain(self.chunks.iter().map(|c| c.loc.clone()))
+                .collect(),
         }
     }
 
@@ -270,7 +422,7 @@ impl<'a> TealEmitter<'a> {
             ))
         })?;
 
-        Ok(i as u64)
+        Ok(i)
     }
 
     #[allow(clippy::result_unit_err)]
@@ -5,10 +5,20 @@ use folidity_semantics::{
         FuncReturnType,
         Function,
         FunctionVisibility,
+        Statement,
         Type,
         TypeVariant,
         UnaryExpression,
     },
+    modules::{
+        Module,
+        Project,
+    },
+    optimizer::{
+        optimize,
+        OptLevel,
+    },
+    CompilationError,
     ContractDefinition,
     Identifier,
     Runner,
@@ -20,18 +30,44 @@ use num_bigint::{
     BigUint,
 };
 use num_traits::FromPrimitive;
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+};
 
 use crate::{
+    app_spec::{
+        AppSpec,
+        ArgSpec,
+        ContractSpec,
+        MethodSpec,
+        ReturnSpec,
+    },
     ast::{
         Chunk,
         Constant,
         Instruction,
     },
+    emit_teal,
+    evm::{
+        abi::selector,
+        EvmEmitter,
+    },
     expression::emit_expression,
-    scratch_table::ScratchTable,
+    ir::IrTealEmitter,
+    optimizer::optimize as optimize_chunks,
+    scratch_table::{
+        allocate_slots,
+        ScratchTable,
+    },
+    source_map::SourceMap,
     teal::{
+        BoxKind,
+        BoxLayout,
         EmitArgs,
+        RuntimeChecks,
         TealEmitter,
+        APP_CALL_BUDGET,
     },
 };
 
@@ -86,14 +122,17 @@ fn simple_exprs() {
         Chunk {
             op: Instruction::PushInt,
             constants: vec![Constant::Uint(100)],
+            loc: None,
         },
         Chunk {
             op: Instruction::PushInt,
             constants: vec![Constant::Uint(2)],
+            loc: None,
         },
         Chunk {
             op: Instruction::Mul,
             constants: vec![],
+            loc: None,
         },
     ];
 
@@ -151,46 +190,137 @@ fn signed_mul() {
         Chunk {
             op: Instruction::PushInt,
             constants: vec![Constant::Uint(16)],
+            loc: None,
         },
         Chunk {
             op: Instruction::ArrayInit,
             constants: vec![],
+            loc: None,
         },
         Chunk {
             op: Instruction::PushInt,
             constants: vec![Constant::Uint(100)],
+            loc: None,
         },
         Chunk {
             op: Instruction::Replace,
             constants: vec![Constant::Uint(8)],
+            loc: None,
         },
         Chunk {
             op: Instruction::PushInt,
             constants: vec![Constant::Uint(16)],
+            loc: None,
         },
         Chunk {
             op: Instruction::ArrayInit,
             constants: vec![],
+            loc: None,
         },
         Chunk {
             op: Instruction::PushInt,
             constants: vec![Constant::Uint(2)],
+            loc: None,
         },
         Chunk {
             op: Instruction::Replace,
             constants: vec![Constant::Uint(8)],
+            loc: None,
         },
         Chunk {
             op: Instruction::PushInt,
             constants: vec![Constant::Uint(1)],
+            loc: None,
         },
         Chunk {
             op: Instruction::Replace,
             constants: vec![Constant::Uint(0)],
+            loc: None,
         },
         Chunk {
             op: Instruction::CallSub,
             constants: vec![Constant::StringLit("signed_mul".to_string())],
+            loc: None,
+        },
+    ];
+
+    assert_eq!(chunks, expected)
+}
+
+#[test]
+fn folded_string_concat() {
+    let definition = ContractDefinition::default();
+    let mut emitter = TealEmitter::new(&definition);
+    let loc = Span { start: 0, end: 0 };
+
+    let mut scratch = ScratchTable::default();
+    scratch.add_var(0, 512, &mut emitter).unwrap();
+
+    let mut args = EmitArgs {
+        scratch: &mut scratch,
+        diagnostics: &mut vec![],
+        emitter: &mut emitter,
+        delayed_bounds: &mut vec![],
+        func: &Function::new(
+            loc.clone(),
+            false,
+            FunctionVisibility::Priv,
+            FuncReturnType::Type(Type::default()),
+            Identifier {
+                loc: loc.clone(),
+                name: "my_func".to_string(),
+            },
+            IndexMap::default(),
+            None,
+        ),
+        loop_labels: &mut vec![],
+    };
+
+    let string = |s: &str| {
+        Expression::String(UnaryExpression {
+            loc: loc.clone(),
+            element: s.to_string(),
+            ty: TypeVariant::String,
+        })
+    };
+    let var = Expression::Variable(UnaryExpression {
+        loc: loc.clone(),
+        element: 0,
+        ty: TypeVariant::String,
+    });
+    // `x + "Hello" + " World"`
+    let left = Expression::Add(BinaryExpression {
+        loc: loc.clone(),
+        left: Box::new(var),
+        right: Box::new(string("Hello")),
+        ty: TypeVariant::String,
+    });
+    let concat = Expression::Add(BinaryExpression {
+        loc: loc.clone(),
+        left: Box::new(left),
+        right: Box::new(string(" World")),
+        ty: TypeVariant::String,
+    });
+
+    let mut chunks = vec![];
+    let res = emit_expression(&concat, &mut chunks, &mut args);
+    assert!(res.is_ok());
+
+    let expected = vec![
+        Chunk {
+            op: Instruction::Load,
+            constants: vec![Constant::Uint(0)],
+            loc: None,
+        },
+        Chunk {
+            op: Instruction::PushBytes,
+            constants: vec![Constant::String("Hello World".to_string())],
+            loc: None,
+        },
+        Chunk {
+            op: Instruction::Concat,
+            constants: vec![],
+            loc: None,
         },
     ];
 
@@ -353,3 +483,1860 @@ fn test_complex_emit() {
 
     assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
 }
+
+const MAPPING_SRC: &str = r#"
+state VotingState {
+    votes: mapping<address -> int>,
+    total: int,
+}
+
+@init
+@(any)
+fn () init() when () -> VotingState {
+    move VotingState : { [], 0 };
+}
+
+@(any)
+fn () vote(voter: address, weight: int) when (VotingState s) -> VotingState {
+    let votes = s.votes :> add(voter, weight);
+    move VotingState : { votes, s.total + weight };
+}
+
+@(any)
+view(VotingState s) fn bool has_voted(voter: address) {
+    return s.votes :> contains(voter);
+}
+"#;
+
+#[test]
+fn test_mapping_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(MAPPING_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let artifacts = runner.unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+
+    assert_eq!(
+        artifacts.methods[1],
+        MethodSpec {
+            name: "vote".to_string(),
+            args: vec![
+                ArgSpec {
+                    ty: "address".to_string(),
+                    name: "voter".to_string(),
+                },
+                ArgSpec {
+                    ty: "byte[16]".to_string(),
+                    name: "weight".to_string(),
+                },
+            ],
+            returns: ReturnSpec {
+                ty: "void".to_string(),
+            },
+            readonly: false,
+            desc: None,
+        }
+    );
+    assert_eq!(artifacts.methods[2].returns.ty, "bool");
+    assert!(artifacts.methods[2].readonly);
+
+    assert!(approval.contains("pushbytes \"VotingState.votes:\""));
+    assert!(approval.contains("concat \nload 3\nbox_put"));
+    assert!(approval.contains("box_len \nswap \npop"));
+
+    // the mapping takes no space in the state box.
+    assert_eq!(
+        artifacts.storage,
+        vec![
+            BoxLayout {
+                name: "__VotingState".to_string(),
+                kind: BoxKind::State,
+                key_size: None,
+                size: Some(16),
+                min_balance: Some(2500 + 400 * (13 + 16)),
+            },
+            BoxLayout {
+                name: "VotingState.votes:".to_string(),
+                kind: BoxKind::Mapping,
+                key_size: Some(32),
+                size: Some(16),
+                min_balance: Some(2500 + 400 * (18 + 32 + 16)),
+            },
+        ]
+    );
+}
+
+const MAPPING_STATES_SRC: &str = r#"
+state Open {
+    votes: mapping<address -> int>,
+}
+
+state Closed {
+    votes: mapping<address -> int>,
+}
+
+@init
+@(any)
+fn () init() when () -> Open {
+    move Open : { [] };
+}
+
+@(any)
+fn () vote(voter: address) when (Open s) -> Open {
+    let votes = s.votes :> add(voter, 1);
+    move Open : { votes };
+}
+
+@(any)
+fn () close() when (Open s) -> Closed {
+    move Closed : { [] };
+}
+
+@(any)
+view(Closed s) fn bool has_voted(voter: address) {
+    return s.votes :> contains(voter);
+}
+"#;
+
+#[test]
+fn test_mapping_states_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(MAPPING_STATES_SRC).unwrap();
+    let res = ContractDefinition::run(&tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let artifacts = runner.unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+
+    // the fields of the same name in different states are stored in separate boxes.
+    assert!(approval.contains("pushbytes \"Open.votes:\""));
+    assert!(approval.contains("pushbytes \"Closed.votes:\""));
+    let mappings: Vec<&str> = artifacts
+        .storage
+        .iter()
+        .filter(|b| b.kind == BoxKind::Mapping)
+        .map(|b| b.name.as_str())
+        .collect();
+    assert_eq!(mappings, vec!["Open.votes:", "Closed.votes:"]);
+}
+
+const MAPPING_COPY_SRC: &str = r#"
+type Votes = mapping<address -> int>
+
+state Open {
+    votes: Votes,
+}
+
+state Closed {
+    ballots: Votes,
+}
+
+@init
+@(any)
+fn () init() when () -> Open {
+    move Open : { [] };
+}
+
+@(any)
+fn () close() when (Open s) -> Closed {
+    move Closed : { s.votes };
+}
+"#;
+
+#[test]
+fn test_mapping_copy_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(MAPPING_COPY_SRC).unwrap();
+
+    // the entries of another field can not be copied to the boxes of the new state.
+    let Err(e) = ContractDefinition::run(&tree) else {
+        panic!("the mapping of another field should not initialise the state");
+    };
+    let messages: Vec<&str> = e.diagnostics().iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec!["Mapping `ballots` can only be initialised with an empty mapping or with the same field of `state Closed`."]
+    );
+}
+
+const AUTO_OBJECT_SRC: &str = r#"
+struct Point {
+    x: int,
+    y: int,
+}
+
+model CounterModel {
+    counter: int,
+    step: int,
+}
+
+state CounterState(CounterModel)
+
+@init
+@(any)
+fn () init() when () -> CounterState {
+    move CounterState : { 0, 1 };
+}
+
+@(any)
+fn () incr() when (CounterState s) -> CounterState {
+    let p = Point : { 1, 2 };
+    let moved = Point : { 5 | ..p };
+    move CounterState : { s.counter + s.step | ..s };
+}
+"#;
+
+#[test]
+fn test_auto_object_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(AUTO_OBJECT_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+
+    // `y` of the moved point is copied from `p` stored in the scratch.
+    assert!(approval.contains("load 2\nstore 2\nload 2\npushint 16\npushint 16\nextract3"));
+    // `step` of the new state is copied from the current one.
+    assert!(approval.contains("store 3\nload 3\npushint 16\npushint 16\nextract3 \nstore 3"));
+}
+
+const EVM_SRC: &str = r#"
+model CounterModel {
+    counter: int,
+    owner: address,
+} st [
+    counter < 1000,
+]
+
+state CounterState(CounterModel)
+
+@init
+@(any)
+fn () init(owner: address) when () -> CounterState {
+    move CounterState : { 0, owner };
+}
+
+@(any)
+fn () incr(value: int) when (CounterState s) -> CounterState {
+    if value > 0 {
+        move CounterState : { s.counter + value, s.owner };
+    } else {
+        move CounterState : { s.counter - 1, s.owner };
+    }
+}
+
+@(s.owner)
+view(CounterState s) fn int get_value() {
+    return s.counter;
+}
+"#;
+
+#[test]
+fn test_evm_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(EVM_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = EvmEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let artifacts = runner.unwrap();
+    let yul = String::from_utf8(artifacts.yul_bytes).unwrap();
+
+    // the constructor reads its argument appended to the init code.
+    assert!(yul.contains("fn_init(mload(0))"));
+    // `incr(int256)` and `get_value()` are dispatched by their selectors.
+    assert!(yul.contains(&format!("case {} {{", selector(&contract.functions[1]))));
+    assert!(yul.contains("mstore(0, fn_get_value())"));
+    // the state is checked and updated.
+    assert!(yul.contains("if iszero(eq(sload(0), 1)) { revert(0, 0) }"));
+    assert!(yul.contains("sstore(18446744073709551616, _t"));
+    assert!(yul.contains("sstore(0, 1)"));
+    // the model bound and the access attribute are enforced.
+    assert!(yul.contains("slt(_t"));
+    assert!(yul.contains("if iszero(eq(caller(), s_"));
+    assert!(yul.contains("checked_sadd("));
+
+    assert!(artifacts.abi.contains("\"stateMutability\": \"view\""));
+    assert!(artifacts.abi.contains("\"type\": \"constructor\""));
+}
+
+const LAMBDA_SRC: &str = r#"
+state Empty
+
+@init
+@(any)
+fn () init() when () -> Empty {
+    move Empty : {};
+}
+
+fn list<int> scale(l: list<int>, k: int) {
+    return l :> filter(|x| x > k) :> map(|x| x * k);
+}
+
+fn list<bool> flags(l: list<bool>) {
+    return l :> map(|b| !b);
+}
+
+fn bool positive(l: list<int>) {
+    return all(l, |x| x > 0);
+}
+"#;
+
+#[test]
+fn test_lambda_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(LAMBDA_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+
+    // the predicate is inlined, and the element is copied if it is satisfied.
+    // the predicate is inlined, and the element is copied if it is satisfied.
+    assert!(approval.contains("callsub signed_ge\nbz 1_filter_loop_skip\nload 5"));
+    assert!(approval.contains("b 1_filter_loop\n1_filter_end: \nload 5"));
+    // boolean elements are converted from and to bytes.
+    assert!(approval.contains("extract3 \nbtoi \nstore 5\nload 5\n! \nitob"));
+    // the predicate is conjoined with the result for every element.
+    assert!(approval.contains("load 3\nload 5\npushint 16"));
+    assert!(approval.contains("callsub signed_ge\n&& \nstore 3"));
+}
+
+#[test]
+fn test_peephole_emit() {
+    let int = |n| Chunk::new_single(Instruction::PushInt, Constant::Uint(n));
+    let load = |n| Chunk::new_single(Instruction::Load, Constant::Uint(n));
+    let store = |n| Chunk::new_single(Instruction::Store, Constant::Uint(n));
+    let chunks = vec![
+        int(2),
+        int(3),
+        Chunk::new_empty(Instruction::Plus),
+        int(4),
+        Chunk::new_empty(Instruction::Mul),
+        store(5),
+        load(5),
+        store(7),
+        load(7),
+        load(7),
+        Chunk::new_empty(Instruction::Plus),
+        store(9),
+        load(3),
+        store(3),
+        int(u64::MAX),
+        int(1),
+        Chunk::new_empty(Instruction::Plus),
+        Chunk::new_empty(Instruction::ReturnSubroutine),
+    ];
+    assert_eq!(
+        optimize_chunks(chunks),
+        vec![
+            int(20),
+            store(7),
+            load(7),
+            load(7),
+            Chunk::new_empty(Instruction::Plus),
+            Chunk::new_empty(Instruction::Pop),
+            // overflow is left to fail at runtime.
+            int(u64::MAX),
+            int(1),
+            Chunk::new_empty(Instruction::Plus),
+            Chunk::new_empty(Instruction::ReturnSubroutine),
+        ]
+    );
+
+    let tree = folidity_parser::parse(LAMBDA_SRC).unwrap();
+    let mut contract = ContractDefinition::run(&tree).unwrap();
+    optimize(&mut contract, OptLevel::O1);
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+    assert!(approval.contains("extract3 \nbtoi \n! \nitob"));
+}
+
+const LIST_SRC: &str = r#"
+state Empty
+
+@init
+@(any)
+fn () init() when () -> Empty {
+    move Empty : {};
+}
+
+fn int stats(l: list<int>, x: int) {
+    let n = size(l);
+    let found = l :> contains(x);
+    let p = l :> fold(1, |acc, y| acc * y);
+    return sum(l) + p;
+}
+"#;
+
+#[test]
+fn test_list_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(LIST_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+    // arguments are stored in the reverse order.
+    assert!(approval.contains("__stats: \nstore 3\nstore 2"));
+    assert!(approval.contains("load 2\nlen \npushint 16\n/ \nstore 4"));
+    assert!(approval.contains("load 2\nload 3\npushint 16\ncallsub list_contains\nstore 3"));
+    assert!(approval.contains("load 3\nload 6\ncallsub signed_mul\nstore 3"));
+    assert!(approval.contains("load 4\nload 6\ncallsub signed_add\nstore 4"));
+}
+
+const IN_SRC: &str = r#"
+state Voting {
+    voters: list<address>,
+    votes: list<int>,
+} st [
+    a"2FMLYJHYQWRHMFKRHKTKX5UNB5DGO65U57O3YVLWUJWKRE4YYJYC2CWWBY" in voters
+]
+
+@init
+@(any)
+fn () init(voter: address) when () -> Voting {
+    move Voting : { [voter], [1, 2] };
+}
+
+@(any)
+view(Voting s) fn bool has_vote(v: int) {
+    return v in s.votes;
+}
+"#;
+
+#[test]
+fn test_in_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(IN_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+
+    // size of the list is stored at runtime.
+    assert!(approval.contains("load 3\nload 2\nlen \nitob \nreplace 0"));
+    // state bound checks the membership before the state is stored.
+    assert!(approval.contains("pushint 32\ncallsub list_contains\nassert \nload 3\nbox_put"));
+    assert!(approval.contains("load 2\npushint 16\ncallsub list_contains\nretsub"));
+    assert!(approval.contains("list_contains:\n    proto 3 1"));
+}
+
+const LONG_ITEM_SRC: &str = r#"
+struct Record {
+    a: int,
+    b: int,
+    c: int,
+    d: int,
+    e: int,
+}
+
+state Ledger {
+    records: list<Record>,
+}
+
+@init
+@(any)
+fn () init() when () -> Ledger {
+    move Ledger : { [] };
+}
+
+@(any)
+view(Ledger s) fn bool has_record(r: Record) {
+    return r in s.records;
+}
+"#;
+
+#[test]
+fn test_long_item_in_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(LONG_ITEM_SRC).unwrap();
+    let res = ContractDefinition::run(&tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+
+    // the 80-byte records are compared byte-wise, as `b==` only takes up to 64 bytes.
+    assert!(approval.contains("pushint 80\ncallsub list_contains"));
+    assert!(approval.contains("frame_dig -2\n        ==\n        bnz list_contains_found"));
+}
+
+const FLOAT_SRC: &str = r#"
+state Empty
+
+@init
+@(any)
+fn () init() when () -> Empty {
+    move Empty : {};
+}
+
+fn float scale(x: float) {
+    return x * 1.5 / 0.25 + 2.0;
+}
+"#;
+
+#[test]
+fn test_float_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(FLOAT_SRC).unwrap();
+    let res = ContractDefinition::run(&tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+
+    // literals are scaled, and the product and the quotient are scaled back.
+    assert!(approval.contains(
+        "pushint 1500000\ncallsub fixed_mul\npushint 250000\ncallsub fixed_div\npushint 2000000\n+"
+    ));
+    assert!(approval.contains("fixed_mul:\n    proto 2 1"));
+
+    let negative = FLOAT_SRC.replace("2.0", "-2.0");
+    let tree = folidity_parser::parse(&negative).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let Err(CompilationError::Emit(errors)) = TealEmitter::run(&contract) else {
+        panic!("Expected emit error");
+    };
+    assert_eq!(
+        errors[0].message,
+        "Negative rational values are not supported."
+    );
+}
+
+const STRING_SRC: &str = r#"
+state Empty
+
+@init
+@(any)
+fn () init() when () -> Empty {
+    move Empty : {};
+}
+
+fn bool check(name: string) {
+    let n = length(name);
+    let prefix = name :> substring(0, 2);
+    return name :> starts_with(prefix) || name :> contains(s"ice");
+}
+"#;
+
+#[test]
+fn test_string_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(STRING_SRC).unwrap();
+    let res = ContractDefinition::run(&tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+
+    assert!(approval.contains("load 2\nlen \nstore 3"));
+    assert!(approval.contains("load 2\npushint 0\npushint 2\nsubstring3 \nstore 3"));
+    assert!(approval.contains("load 2\nload 3\ncallsub string_starts_with"));
+    assert!(approval.contains("pushbytes \"ice\"\ncallsub string_contains"));
+    assert!(approval.contains("string_contains:\n    proto 2 1"));
+}
+
+const ACCESS_SRC: &str = r#"
+struct Inner {
+    flag: bool,
+    value: int
+}
+
+struct Outer {
+    count: int,
+    inner: Inner
+}
+
+state Empty
+
+@init
+@(any)
+fn () init() when () -> Empty {
+    move Empty : {};
+}
+
+fn int chained(o: Outer) {
+    return o.inner.value;
+}
+"#;
+
+#[test]
+fn test_chained_access_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(ACCESS_SRC).unwrap();
+    let res = ContractDefinition::run(&tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+
+    // the inner struct is extracted first, then its field.
+    assert!(approval.contains("load 2\npushint 16\npushint 24\nextract3 \nstore 2"));
+    assert!(approval.contains("load 2\npushint 8\npushint 16\nextract3 \nretsub"));
+}
+
+const DESTRUCTURE_SRC: &str = r#"
+struct Pair {
+    x: int,
+    y: int
+}
+
+state Empty
+
+@init
+@(any)
+fn () init() when () -> Empty {
+    move Empty : {};
+}
+
+fn int sum(p: Pair) {
+    let { x, y } = p;
+    return x + y;
+}
+"#;
+
+#[test]
+fn test_destructuring_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(DESTRUCTURE_SRC).unwrap();
+    let res = ContractDefinition::run(&tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+
+    // each binding is a member access of the destructured struct.
+    assert!(approval.contains("load 3\npushint 0\npushint 16\nextract3 \nstore 3"));
+    assert!(approval.contains("load 2\npushint 16\npushint 16\nextract3 \nstore 2"));
+    assert!(approval.contains("load 3\nload 2\ncallsub signed_add"));
+}
+
+const SIZED_SRC: &str = r#"
+struct Packed {
+    flag: u8,
+    total: u256
+}
+
+state Empty
+
+@init
+@(any)
+fn () init() when () -> Empty {
+    move Empty : {};
+}
+
+fn u8 add(a: u8, b: u8) {
+    return a + b;
+}
+
+fn u256 grow(p: Packed) {
+    return p.total + 1;
+}
+
+fn u8 narrow(x: int) {
+    return cast(x);
+}
+"#;
+
+#[test]
+fn test_sized_integer_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(SIZED_SRC).unwrap();
+    let res = ContractDefinition::run(&tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+
+    // `u8` is checked to fit in a byte after the addition.
+    assert!(approval.contains("load 2\nload 3\n+ \ndup \npushint 255\n<= \nassert \nretsub"));
+    // `u256` is a 32-byte field operated on with the byte math.
+    assert!(approval.contains("load 2\npushint 1\npushint 32\nextract3 \npushbytes 0x"));
+    assert!(approval.contains("b+ \npushint 32\nbzero \nb| \ndup \nlen \npushint 32\n== \nassert"));
+    // the sign block is checked before the absolute value is narrowed.
+    assert!(approval.contains(
+        "load 2\ndup \npushint 0\nextract_uint64 \n! \nassert \npushint 8\nextract_uint64 \ndup \npushint 255"
+    ));
+}
+
+const EVM_SIZED_SRC: &str = r#"
+state Empty
+
+@init
+@(any)
+fn () init() when () -> Empty {
+    move Empty : {};
+}
+
+@(any)
+fn u8 add(a: u8, b: u8) {
+    return a + b;
+}
+
+@(any)
+fn i64 sub(a: i64, b: i64) {
+    return a - b;
+}
+"#;
+
+#[test]
+fn test_evm_sized_integer_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(EVM_SIZED_SRC).unwrap();
+    let res = ContractDefinition::run(&tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = EvmEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let artifacts = runner.unwrap();
+    let yul = String::from_utf8(artifacts.yul_bytes).unwrap();
+
+    // the words are checked to fit into the sized integers after the operation.
+    assert!(yul.contains("checked_u8(checked_add(v_2, v_3))"));
+    assert!(yul.contains("checked_i64(checked_ssub(v_5, v_6))"));
+    assert!(yul.contains("if gt(a, 0xff) { revert(0, 0) }"));
+    assert!(yul.contains("if iszero(eq(signextend(7, a), a)) { revert(0, 0) }"));
+    assert!(artifacts.abi.contains("\"type\": \"uint8\""));
+}
+
+const OR_SRC: &str = r#"
+state Empty
+
+@init
+@(any)
+fn () init() when () -> Empty {
+    move Empty : {};
+}
+
+fn u64 factorial(value: u64) {
+    return value * (value - 1) :> or(1);
+}
+
+fn int quotient(a: int, b: int) {
+    return a / b :> or(0);
+}
+"#;
+
+#[test]
+fn test_or_fallback_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(OR_SRC).unwrap();
+    let res = ContractDefinition::run(&tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+
+    // operands are tested before the operations.
+    assert!(approval.contains("load 2\nload 4\n< \nbnz 0_or_fallback\nload 2\nload 4\n- "));
+    assert!(approval.contains(
+        "load 3\nload 2\nmulw \npop \nbnz 0_or_fallback\nload 3\nload 2\n* \nb 0_or_end"
+    ));
+    assert!(approval.contains("0_or_fallback: \npushint 1\n0_or_end: "));
+    // the magnitude of the signed divisor is tested to be non-zero.
+    assert!(approval.contains("load 3\npushint 8\nextract_uint64 \n! \nbnz 1_or_fallback"));
+}
+
+const ACCESS_CHECK_SRC: &str = r#"
+state Board {
+    admin: address,
+    members: set<address>,
+    total: int,
+}
+
+@init
+@(any)
+fn () init(admin: address) when () -> Board {
+    move Board : { admin, [], 0 };
+}
+
+@(s.admin | s.members)
+fn () add(value: int) when (Board s) -> Board {
+    move Board : { s.admin, s.members, s.total + value };
+}
+
+@(s.admin)
+view(Board s) fn int total() {
+    return s.total;
+}
+"#;
+
+#[test]
+fn test_access_check_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(ACCESS_CHECK_SRC).unwrap();
+    let res = ContractDefinition::run(&tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = TealEmitter::run(&contract);
+    assert!(runner.is_ok(), "{:#?}", runner.err().unwrap());
+    let approval = String::from_utf8(runner.unwrap().approval_bytes).unwrap();
+
+    // the sender is either the admin or one of the members.
+    assert!(approval.contains("txn Sender\n== \npushbytes \"__Board\""));
+    assert!(approval.contains("txn Sender\npushint 32\ncallsub list_contains\n|| \nassert "));
+    // the view is only accessible by the admin.
+    assert!(approval.contains("txn Sender\n== \nassert "));
+}
+
+#[test]
+fn test_state_guards_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(ACCESS_CHECK_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes.clone()).unwrap();
+
+    // every move records the new state.
+    assert_eq!(
+        approval
+            .matches("box_put \npushbytes \"__state\"\npushint 1\napp_global_put ")
+            .count(),
+        2
+    );
+    // `add` can only be called in the `Board` state.
+    assert!(approval
+        .contains("assert \npushbytes \"__state\"\napp_global_get \npushint 1\n== \nassert "));
+
+    // the current state is allocated in the global storage.
+    let spec = AppSpec::new("Board", &contract, &artifacts);
+    assert_eq!(spec.state.global.num_uints, 1);
+    assert!(spec.schema.global.declared.contains_key("__state"));
+}
+
+#[test]
+fn test_init_config() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(ACCESS_CHECK_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes.clone()).unwrap();
+
+    // the constructor is only called at the creation.
+    assert!(approval.contains("bz create_end\nb __block__init\ncreate_end: "));
+    assert!(!approval.contains("pushbytes \"init\""));
+    assert!(approval.contains("pushbytes \"add\""));
+
+    assert_eq!(artifacts.init.function.as_deref(), Some("init"));
+    assert_eq!(artifacts.init.schema.global.num_uints, 1);
+    assert_eq!(artifacts.init.schema.local.num_uints, 0);
+}
+
+const BIG_STATE_SRC: &str = r#"
+state Big {
+    f0: string,
+    f1: string,
+    f2: string,
+    f3: string,
+    f4: string,
+    f5: string,
+    f6: string,
+    f7: string,
+    f8: string,
+    f9: string,
+    f10: string,
+    f11: string,
+    f12: string,
+    f13: string,
+    f14: string,
+    f15: string,
+    f16: string,
+}
+"#;
+
+#[test]
+fn test_state_schema() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(ACCESS_CHECK_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    assert!(TealEmitter::run(&contract).unwrap().warnings.is_empty());
+
+    // 17 strings of 512 bytes with their offsets need 9 box references.
+    let tree = folidity_parser::parse(BIG_STATE_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    assert_eq!(artifacts.warnings.len(), 1);
+    assert_eq!(
+        artifacts.warnings[0].message,
+        "State `Big` takes 8840 bytes, which needs 9 box references to be accessed, but a transaction can only have 8."
+    );
+}
+
+const RECURSION_SRC: &str = r#"
+@(any)
+fn int countdown(n: int) {
+    let half = n / 2;
+    if n == 0 {
+        return 0;
+    } else {
+        return countdown(n - 1) + half;
+    }
+}
+
+@(any)
+fn int twice(n: int) {
+    return countdown(n) * 2;
+}
+"#;
+
+#[test]
+fn test_recursive_call_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(RECURSION_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    // the slots of `countdown` are saved before the arguments and restored after the call.
+    assert!(approval.contains(
+        "load 2\nload 3\nload 2\npushint 16\nbzero \npushint 1\nreplace 8\ncallsub signed_sub\ncallsub __countdown\nswap \nstore 3\nswap \nstore 2\n"
+    ));
+    // non-recursive calls do not save the slots.
+    assert!(approval.contains("load 2\ncallsub __countdown\n"));
+}
+
+#[test]
+fn test_scratch_allocation() {
+    let int = |n| Chunk::new_single(Instruction::PushInt, Constant::Uint(n));
+    let load = |n| Chunk::new_single(Instruction::Load, Constant::Uint(n));
+    let store = |n| Chunk::new_single(Instruction::Store, Constant::Uint(n));
+    let label = |l: &str| Chunk::new_empty(Instruction::Label(l.to_string()));
+    let call =
+        |l: &str| Chunk::new_single(Instruction::CallSub, Constant::StringLit(l.to_string()));
+    let retsub = Chunk::new_empty(Instruction::ReturnSubroutine);
+    let definition = ContractDefinition::default();
+
+    let mut chunks = vec![
+        label("__f"),
+        int(1),
+        store(10),
+        load(10),
+        store(11),
+        // `11` is live across the call, `10` is not.
+        call("__g"),
+        load(11),
+        retsub.clone(),
+        label("__g"),
+        int(2),
+        store(20),
+        load(20),
+        retsub,
+    ];
+    allocate_slots(&mut chunks, &definition).unwrap();
+    // the first two cells are left to the helpers.
+    assert_eq!(chunks[2], store(2));
+    assert_eq!(chunks[4], store(2));
+    // the slot of the callee does not share the cell of the value live across the call.
+    assert_eq!(chunks[10], store(3));
+
+    // the values live at once do not fit into the scratch space.
+    let mut chunks = vec![label("__f")];
+    chunks.extend((0..300).flat_map(|n| [int(n), store(n)]));
+    chunks.extend((0..300).map(load));
+    chunks.push(Chunk::new_empty(Instruction::ReturnSubroutine));
+    let Err(report) = allocate_slots(&mut chunks, &definition) else {
+        panic!("Expected allocation error");
+    };
+    assert_eq!(
+        report.message,
+        "Entry point keeps too many values at once, only 254 of them fit into the scratch space."
+    );
+}
+
+const FIELD_ASSIGN_SRC: &str = r#"
+struct Point {
+    x: int,
+    y: int,
+}
+
+@(any)
+fn int shift(mut p: Point) {
+    p.y = 5;
+    return p.y;
+}
+"#;
+
+#[test]
+fn test_field_assign_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(FIELD_ASSIGN_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    // the value replaces the bytes of `y` in the struct.
+    assert!(
+        approval.contains("pushint 5\nreplace 8\nstore 3\nload 2\nload 3\nreplace 16\nstore 2\n")
+    );
+}
+
+const MATCH_SRC: &str = r#"
+enum Shape {
+    Circle(radius: int),
+    Empty,
+}
+
+@(any)
+fn int area(s: Shape) {
+    match s {
+        Circle(r) => {
+            return r * r;
+        }
+        _ => {
+            return 0;
+        }
+    }
+}
+
+@(any)
+fn Shape circle() {
+    return Shape.Circle(3);
+}
+"#;
+
+#[test]
+fn test_match_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(MATCH_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    // the tag follows the index of the enum in the header.
+    assert!(approval.contains(
+        "pushint 8\nextract_uint64 \npushint 0\n== \nbnz 0_match_0\nb 0_match_1\nerr \n"
+    ));
+    // the payload starts after the header.
+    assert!(approval.contains("pushint 16\npushint 16\nextract3 \n"));
+}
+
+const OPTION_SRC: &str = r#"
+@(any)
+fn option<int> find(flag: bool) {
+    if flag {
+        return some(3);
+    }
+    return none;
+}
+
+@(any)
+fn int fallback(value: option<int>) {
+    return value :> or(0);
+}
+"#;
+
+#[test]
+fn test_option_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(OPTION_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    // the value follows the presence byte, `none` is zeroed.
+    assert!(
+        approval.contains("pushbytes 0x01\npushint 16\nbzero \npushint 3\nreplace 8\nconcat \n")
+    );
+    assert!(approval.contains("pushint 17\nbzero \nretsub \n"));
+    assert!(approval
+        .contains("pushint 0\ngetbyte \nbz 1_or_fallback\nload 2\nextract 1 0\nb 1_or_end\n"));
+}
+
+const TUPLE_SRC: &str = r#"
+@(any)
+fn (int, bool) pair() {
+    return (7, true);
+}
+
+@(any)
+fn bool second() {
+    let (_, b) = pair();
+    return b;
+}
+"#;
+
+#[test]
+fn test_tuple_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(TUPLE_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    // the elements are laid out as the fields of the struct.
+    assert!(approval.contains("pushint 24\nbzero \nstore 2\n"));
+    assert!(approval.contains("load 2\nstore 2\nload 2\npushint 16\nextract_uint64 \n"));
+}
+
+const CONST_SRC: &str = r#"
+const FEE: int = 40 + 2;
+const TAG = s"fee";
+
+@(any)
+fn int fee() {
+    return FEE;
+}
+
+@(any)
+fn string tag() {
+    return TAG;
+}
+"#;
+
+#[test]
+fn test_const_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(CONST_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    // the values are inlined where the constants are referenced.
+    assert!(approval.contains("pushint 16\nbzero \npushint 42\nreplace 8\nretsub \n"));
+    assert!(approval.contains("pushbytes \"fee\"\nretsub"));
+}
+
+const ESCAPE_SRC: &str = r#"
+@(any)
+fn string quote() {
+    return s"say \"hi\"\n\u{e9}";
+}
+"#;
+
+#[test]
+fn test_escaped_string_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(ESCAPE_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    // the escape sequences are written in the TEAL syntax, non-ASCII characters as bytes.
+    assert!(approval.contains(r#"pushbytes "say \"hi\"\n\xc3\xa9""#));
+}
+
+const DOC_SRC: &str = r#"
+## Fee charged for the call.
+## Constant for now.
+@(any)
+fn int fee() {
+    return 42;
+}
+"#;
+
+#[test]
+fn test_doc_comments_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(DOC_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let fee = &artifacts.methods[0];
+    assert_eq!(
+        fee.desc.as_deref(),
+        Some("Fee charged for the call.\nConstant for now.")
+    );
+    let json = serde_json::to_value(fee).unwrap();
+    assert_eq!(json["desc"], "Fee charged for the call.\nConstant for now.");
+}
+
+const INTERFACE_SRC: &str = r#"
+interface Token {
+    fn int balance(owner: address);
+    fn bool transfer(receiver: address, amount: unit);
+}
+
+@(any)
+fn bool pay(app: unit, receiver: address) {
+    return Token(app).transfer(receiver, 10);
+}
+"#;
+
+#[test]
+fn test_interface_call_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(INTERFACE_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    // the arguments are encoded with ARC-4 and passed after the method selector.
+    assert!(approval.contains(
+        "itxn_begin \npushint 6\nitxn_field TypeEnum\nload 2\nitxn_field ApplicationID\n\
+        method \"transfer(address,uint64)bool\"\nitxn_field ApplicationArgs\n\
+        load 3\nitxn_field ApplicationArgs\nload 4\nitxn_field ApplicationArgs\n\
+        pushint 0\nitxn_field Fee\nitxn_submit \n"
+    ));
+    // the returned value is checked for the ARC-4 prefix and decoded.
+    assert!(approval.contains(
+        "itxn LastLog\ndup \nextract 0 4\npushbytes 0x151f7c75\n== \nassert \n\
+        extract 4 0\nbtoi \npushint 128\n/ \nretsub"
+    ));
+}
+
+const EVENT_SRC: &str = r#"
+event Registered {
+    owner: address,
+    active: bool,
+    verified: bool,
+    name: string,
+    amount: unit
+}
+
+@(any)
+fn () register(owner: address, name: string) {
+    emit Registered : { owner, true, false, name, 10 };
+}
+"#;
+
+#[test]
+fn test_event_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(EVENT_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes.clone()).unwrap();
+    // the selector is followed by the head with the packed booleans and the offset
+    // of the string, which is appended at the end.
+    assert!(approval.contains(
+        "pushbytes \"Registered(address,bool,bool,string,uint64)\"\nsha512_256 \n\
+        extract 0 4\nload 2\nconcat \npushint 0\nload 4\npushint 128\n* \n+ \n\
+        load 5\npushint 64\n* \n+ \nitob \nextract 7 1\nconcat \n\
+        pushint 43\nitob \nextract 6 2\nconcat \nload 6\nconcat \nload 3\nconcat \nlog \n"
+    ));
+
+    let spec = ContractSpec::new("Registry", &artifacts);
+    let json = serde_json::to_value(&spec).unwrap();
+    assert_eq!(json["events"][0]["name"], "Registered");
+    assert_eq!(json["events"][0]["args"][3]["type"], "string");
+}
+
+const TRANSACTION_SRC: &str = r#"
+@(any)
+fn () withdraw(receiver: address, amount: unit) {
+    pay(receiver, amount);
+    asset_transfer(10, receiver, amount);
+}
+"#;
+
+#[test]
+fn test_transaction_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(TRANSACTION_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    assert!(approval.contains(
+        "itxn_begin \npushint 1\nitxn_field TypeEnum\nload 4\nitxn_field Receiver\n\
+        load 5\nitxn_field Amount\npushint 0\nitxn_field Fee\nitxn_submit \n"
+    ));
+    assert!(approval.contains(
+        "itxn_begin \npushint 4\nitxn_field TypeEnum\nload 4\nitxn_field XferAsset\n\
+        load 2\nitxn_field AssetReceiver\nload 3\nitxn_field AssetAmount\n\
+        pushint 0\nitxn_field Fee\nitxn_submit \n"
+    ));
+}
+
+const CONTEXT_SRC: &str = r#"
+@(any)
+fn bool can_withdraw(deadline: unit) {
+    return current_block > deadline && caller().balance > 1000;
+}
+"#;
+
+#[test]
+fn test_context_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(CONTEXT_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    assert!(approval
+        .contains("global Round\nload 2\n> \ntxn Sender\nbalance \npushint 1000\n> \n&& \nretsub"));
+}
+
+const CRYPTO_SRC: &str = r#"
+@(any)
+fn hex commitment(vote: bool, salt: unit) {
+    return hash(caller(), vote, salt);
+}
+
+@(any)
+fn bool signed(data: hex, signature: hex, key: address) {
+    return ed25519_verify(data, signature, key);
+}
+"#;
+
+#[test]
+fn test_crypto_emit() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(CRYPTO_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    // the values are encoded with ARC-4 and concatenated.
+    assert!(approval.contains(
+        "txn Sender\nload 2\npushint 128\n* \nitob \nextract 7 1\nconcat \n\
+        load 3\nitob \nconcat \nsha256 \nretsub"
+    ));
+    assert!(approval.contains("load 2\nload 3\nload 4\ned25519verify_bare \nretsub"));
+}
+
+const RUNTIME_CHECKS_SRC: &str = r#"
+state Counter {
+    value: unit,
+    limit: unit,
+} st [
+    value <= limit,
+    limit > 0,
+]
+
+@init
+@(any)
+fn () init() when () -> Counter {
+    move Counter : { 0, 10 };
+}
+"#;
+
+#[test]
+fn test_runtime_checks() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(RUNTIME_CHECKS_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let Statement::Block(block) = &contract.functions[0].body[0] else {
+        panic!("expected the block");
+    };
+    let Statement::StateTransition(Expression::StructInit(init)) = &block.statements[0] else {
+        panic!("expected the transition");
+    };
+    let bounds = contract.states[0].bounds.as_ref().unwrap();
+
+    let asserts = |bytes: Vec<u8>| String::from_utf8(bytes).unwrap().matches("assert").count();
+
+    let all = emit_teal(&contract, RuntimeChecks::All).unwrap();
+    assert_eq!(all.elided_asserts, 0);
+    let all_asserts = asserts(all.approval_bytes);
+    let none = emit_teal(&contract, RuntimeChecks::None).unwrap();
+    assert_eq!(none.elided_asserts, 2);
+    assert_eq!(asserts(none.approval_bytes), all_asserts - 2);
+
+    // only the second bound is left to be asserted.
+    let proven = HashSet::from([(init.loc.clone(), bounds.exprs[0].loc().clone())]);
+    let verified = emit_teal(&contract, RuntimeChecks::Verified(proven)).unwrap();
+    assert_eq!(verified.elided_asserts, 1);
+    assert_eq!(asserts(verified.approval_bytes), all_asserts - 1);
+}
+
+const COST_SRC: &str = r#"
+@(any)
+fn bool check(msg: hex, sig: hex, signer: address) {
+    return ed25519_verify(msg, sig, signer);
+}
+
+@(any)
+fn int sum(n: int) {
+    let mut total = 0;
+    for (let mut i = 0; i < n; i + 1) {
+        total = total + i;
+    }
+    return total;
+}
+
+@(any)
+fn bool verify_twice(msg: hex, sig: hex, signer: address) {
+    return check(msg, sig, signer) && check(msg, sig, signer);
+}
+"#;
+
+#[test]
+fn test_cost_estimate() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(COST_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let costs = &artifacts.costs;
+
+    assert!(costs[0].cost > 1900 && !costs[0].unbounded);
+    // the loop is counted once.
+    assert!(costs[1].cost < APP_CALL_BUDGET && costs[1].unbounded);
+    // the called subroutine is counted at every call.
+    assert!(costs[2].cost > 2 * costs[0].cost);
+
+    let warned: Vec<&str> = artifacts
+        .warnings
+        .iter()
+        .map(|w| &COST_SRC[w.loc.clone()])
+        .collect();
+    assert_eq!(warned, vec!["check", "verify_twice"]);
+}
+
+const SOURCE_MAP_SRC: &str = r#"@(any)
+fn int double(a: int) {
+    let b = a + a;
+    return b;
+}
+"#;
+
+#[test]
+fn test_source_map() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(SOURCE_MAP_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let project = Project {
+        modules: vec![Module {
+            path: PathBuf::from("double.fol"),
+            contents: SOURCE_MAP_SRC.to_string(),
+            offset: 0,
+        }],
+        contents: SOURCE_MAP_SRC.to_string(),
+        diagnostics: vec![],
+    };
+    let source_map = SourceMap::new(&project, &artifacts);
+
+    let approval = String::from_utf8(source_map.annotate(&artifacts.approval_bytes)).unwrap();
+    assert!(approval.contains("__double: // double.fol:2\n"));
+    // the rest of the statement is not annotated again.
+    assert!(approval.contains("callsub signed_add\nstore 2\n"));
+    assert!(approval.contains("// double.fol:3\n"));
+    assert!(approval.contains("// double.fol:4\n"));
+    // the comments do not shift the lines of the program.
+    assert_eq!(
+        approval.lines().count(),
+        String::from_utf8(artifacts.approval_bytes.clone())
+            .unwrap()
+            .lines()
+            .count()
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_str(&source_map.to_json("approval.teal")).unwrap();
+    assert_eq!(json["version"], 3);
+    assert_eq!(json["sources"][0], "double.fol");
+    // the pragma is not located.
+    assert!(json["mappings"].as_str().unwrap().starts_with(';'));
+}
+
+const IR_SRC: &str = r#"
+fn int sum(n: int) st [n >= 0] {
+    let mut acc = 0;
+    for (let mut i = 0; i < n; i + 1) {
+        acc = acc + n;
+    }
+    return acc;
+}
+
+fn bool small(a: unit) {
+    return a < 10;
+}
+"#;
+
+#[test]
+fn test_emit_via_ir() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(IR_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = IrTealEmitter::run(&contract).unwrap();
+    let direct = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes.clone()).unwrap();
+    println!("{}", approval);
+
+    // the entry point and the dispatch are shared with the direct emitter.
+    assert!(approval.contains("__block__sum: \ntxn ApplicationArgs 1\ncallsub __sum\nlog \n"));
+    assert!(approval.contains("callsub signed_geq\nstore 3\nload 3\nassert \n"));
+    // the loop branches to its body and jumps back to the header.
+    assert!(approval.contains("callsub signed_le\nstore 5\nload 5\nbnz __sum_bb2\nb __sum_bb4\n"));
+    assert!(approval.contains("b __sum_bb1\n"));
+    assert!(approval.contains("pushint 10\n"));
+    assert_eq!(artifacts.methods.len(), direct.methods.len());
+
+    let tree =
+        folidity_parser::parse("fn string greet(name: string) {\n    return name;\n}\n").unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let Err(CompilationError::Emit(reports)) = IrTealEmitter::run(&contract) else {
+        panic!("strings are not lowered");
+    };
+    assert_eq!(
+        reports[0].message,
+        "Type `string` is not supported by the IR yet."
+    );
+}
+
+const LOOPS_SRC: &str = r#"
+fn int count(n: int) {
+    let mut total = 0;
+    for (let mut i = 0; i < n; i + 1) {
+        if i == 2 {
+            skip;
+        }
+        total = total + i;
+    }
+    return total;
+}
+
+fn int first_over(limit: int) {
+    let mut i = 0;
+    while true {
+        i = i + 1;
+        if i * i <= limit {
+            continue;
+        }
+        break;
+    }
+    return i;
+}
+"#;
+
+#[test]
+fn test_loops() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(LOOPS_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    println!("{}", approval);
+
+    // the loop is left once the condition does not hold.
+    assert!(approval.contains("0_loop_cond: \nload 4\nload 2\ncallsub signed_le\nbz 0_loop_end\n"));
+    // `skip` jumps to the incrementer, whose value is assigned to the loop variable.
+    assert!(approval.contains("b 0_loop_incr\n"));
+    assert!(approval.contains("0_loop_incr: \nload 4\npushint 16\nbzero \npushint 1\nreplace 8\ncallsub signed_add\nstore 4\nb 0_loop_cond\n0_loop_end: \n"));
+    // `continue` jumps to the condition of the while loop, and `break` out of it.
+    assert!(approval.contains("pushint 1\nbz 1_loop_end\n"));
+    assert!(approval.contains("b 1_loop_cond\n"));
+    assert_eq!(approval.matches("b 1_loop_end\n").count(), 1);
+}
+
+const ITERATOR_SRC: &str = r#"
+fn int total(items: list<int>) {
+    let mut acc = 0;
+    for (x in items) {
+        if x == 0 {
+            skip;
+        }
+        acc = acc + x;
+    }
+    return acc;
+}
+
+fn int nested() {
+    let mut acc = 0;
+    for (xs in [[1, 2], [3]]) {
+        acc = acc + sum(xs);
+    }
+    return acc;
+}
+"#;
+
+#[test]
+fn test_iterator() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(ITERATOR_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    println!("{}", approval);
+
+    // the offset is advanced past the element before the body.
+    assert!(approval.contains("0_loop_cond: \nload 4\nload 2\nlen \n>= \nbnz 0_loop_end\n"));
+    assert!(approval
+        .contains("load 2\nload 4\npushint 16\nextract3 \nload 4\npushint 16\n+ \nstore 4\n"));
+    // `skip` proceeds to the next element.
+    assert_eq!(approval.matches("b 0_loop_cond\n").count(), 2);
+    // resizable elements are preceded by their size.
+    assert_eq!(
+        approval
+            .matches("dup \nlen \nitob \nswap \nconcat \n")
+            .count(),
+        2
+    );
+    assert!(approval.contains("load 3\nload 4\nextract_uint64 \nstore 5\nload 4\npushint 8\n+ \nstore 4\nload 3\nload 4\nload 5\nextract3 \n"));
+}
+
+const NESTED_SRC: &str = r#"
+struct Bag {
+    items: list<list<int>>,
+    label: option<string>,
+    count: int,
+}
+
+fn int total() {
+    let bag = Bag : { [[1, 2], [3]], some(s"bag"), 2 };
+    let mut acc = bag.count;
+    for (xs in bag.items) {
+        acc = acc + sum(xs);
+    }
+    return acc;
+}
+"#;
+
+#[test]
+fn test_nested_collections() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(NESTED_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    println!("{}", approval);
+
+    // the list of lists reserves the space for 4 inner lists with their size blocks,
+    // and the option of the string is preceded by the size block as well.
+    assert!(approval.contains("pushint 2625\nbzero \n"));
+    assert!(approval.contains("len \nitob \nreplace 2088\nstore 2\nload 2\nload 3\nreplace 2096\n"));
+    assert!(approval.contains("load 3\npushint 2609\npushint 16\nextract3 \n"));
+    // the inner lists are read by their size.
+    assert!(approval.contains("extract_uint64 \nstore 5\nload 4\npushint 8\n+ \nstore 4\n"));
+}
+
+const EQUALITY_SRC: &str = r#"
+struct Pair {
+    a: int,
+    b: int,
+}
+
+struct Tagged {
+    label: string,
+    value: int,
+}
+
+fn bool same(p: Pair, q: Pair) {
+    return p == q;
+}
+
+fn bool same_tag(t: Tagged, u: Tagged) {
+    return t != u;
+}
+
+fn bool same_items(l: set<int>, r: set<int>) {
+    return l == r;
+}
+"#;
+
+#[test]
+fn test_structural_equality() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(EQUALITY_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    println!("{}", approval);
+
+    // fixed layouts are compared as bytes.
+    assert!(approval.contains("__same: \nstore 3\nstore 2\n \n \nload 2\nload 3\n== \nretsub \n"));
+    // resizable fields are compared by their size, ignoring the unused space.
+    assert!(approval.contains("load 2\npushint 8\nload 4\nextract3 \n== \n&& \n"));
+    assert!(approval.contains("pushint 520\npushint 16\nextract3 \n== \n&& \n! \nretsub \n"));
+    // the elements of the sets are looked up regardless of their order.
+    assert!(approval.contains("load 3\nlen \nload 2\nlen \n== \nstore 5\n"));
+    assert!(approval.contains("pushint 16\ncallsub list_contains\nstore 5\n"));
+}
+
+const CAST_SRC: &str = r#"
+fn unit to_uint(a: int) {
+    return a as unit;
+}
+
+fn address to_address(data: hex) {
+    return data as address;
+}
+"#;
+
+#[test]
+fn test_as_conversions() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(CAST_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    println!("{}", approval);
+
+    // negative values trap.
+    assert!(approval.contains(
+        "load 2\ndup \npushint 0\nextract_uint64 \n! \nassert \npushint 8\nextract_uint64 \nretsub \n"
+    ));
+    assert!(approval.contains("load 2\ndup \nlen \npushint 32\n== \nassert \nretsub \n"));
+}
+
+const ASSERT_SRC: &str = r#"
+fn int withdraw(amount: int, balance: int) {
+    require(amount > 0);
+    assert(amount <= balance, s"insufficient balance");
+    return balance - amount;
+}
+"#;
+
+#[test]
+fn test_assertions() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(ASSERT_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    println!("{}", approval);
+
+    // the message is logged before the program fails.
+    assert!(approval.contains(
+        "bnz 0_assert_ok\npushbytes \"insufficient balance\"\nlog \nerr \n0_assert_ok: \n"
+    ));
+}
+
+const CONDITIONAL_SRC: &str = r#"
+fn int max(a: int, b: int) {
+    return if a > b then a else b;
+}
+"#;
+
+#[test]
+fn test_conditional() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(CONDITIONAL_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    println!("{}", approval);
+
+    // only the selected branch is evaluated.
+    assert!(approval.contains(
+        "bz 0_cond_else\nload 2\nb 0_cond_end\n0_cond_else: \nload 3\n0_cond_end: \nretsub \n"
+    ));
+}
+
+const MATH_SRC: &str = r#"
+fn unit compound(principal: unit, years: unit) {
+    return principal * pow(2, years);
+}
+
+fn float root(x: float) {
+    return sqrt(x);
+}
+"#;
+
+#[test]
+fn test_pow_and_sqrt() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(MATH_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    println!("{}", approval);
+
+    // the power has to fit in 64 bits.
+    assert!(approval.contains("expw \nswap \n! \nassert \n"));
+    assert!(approval.contains("callsub fixed_sqrt\n"));
+    assert!(approval.contains("fixed_sqrt:\n"));
+}
+
+const INDEXING_SRC: &str = r#"
+fn int second(xs: list<int>) {
+    return xs[1];
+}
+
+fn list<unit> tail(xs: list<unit>) {
+    return xs[1..];
+}
+"#;
+
+#[test]
+fn test_indexing() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(INDEXING_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    println!("{}", approval);
+
+    // the offset of the item is checked against the length of the list.
+    assert!(approval.contains("pushint 16\n* \nstore 3\nload 3\nload 2\nlen \n< \nassert \n"));
+    assert!(approval.contains("load 2\nload 3\npushint 16\nextract3 \n"));
+    // the slice is checked to be within the list.
+    assert!(approval.contains("load 4\nload 2\nlen \n<= \nassert \n"));
+    assert!(approval.contains("load 4\nload 3\n- \nextract3 \n"));
+}
+
+const UPDATES_SRC: &str = r#"
+fn list<unit> enqueue(l: list<unit>, x: unit) {
+    return l :> pop() :> push(x);
+}
+
+fn list<unit> shift(l: list<unit>, x: unit) {
+    return l :> insert(0, x);
+}
+
+fn set<unit> merge(a: set<unit>, b: set<unit>) {
+    return a :> push(1) :> difference(b);
+}
+"#;
+
+#[test]
+fn test_collection_updates() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = folidity_parser::parse(UPDATES_SRC).unwrap();
+    let contract = ContractDefinition::run(&tree).unwrap();
+    let artifacts = TealEmitter::run(&contract).unwrap();
+    let approval = String::from_utf8(artifacts.approval_bytes).unwrap();
+    println!("{}", approval);
+
+    // the items are appended and cut out of the bytes of the list.
+    assert!(approval.contains("len \npushint 8\n- \nextract3 \nload 3\nitob \nconcat \n"));
+    assert!(approval.contains("len \n<= \nassert \nload 2\npushint 0\nload 4\nextract3 \n"));
+    // the sets are updated by the helpers.
+    assert!(approval.contains("itob \npushint 8\ncallsub set_union\n"));
+    assert!(approval.contains("pushint 8\ncallsub list_difference\n"));
+    assert!(approval.contains("set_union:\n"));
+}
@@ -0,0 +1,122 @@
+//! Transactions of the application.
+//!
+//! The context values are read from the current transaction and the global state
+//! of the chain. Payments and asset transfers are sent from the account of
+//! the application as inner transactions, and their fees are pooled from the outer
+//! transaction.
+
+use folidity_semantics::{
+    ast::BuiltinCall,
+    builtin::BuiltinFunction,
+};
+
+use crate::{
+    ast::{
+        Chunk,
+        Constant,
+        Instruction,
+        TypeSizeHint,
+    },
+    expression::emit_expression,
+    teal::EmitArgs,
+};
+
+type EmitResult = Result<u64, ()>;
+
+/// Type of the payment transaction.
+const PAY_TYPE_ENUM: u64 = 1;
+
+/// Type of the asset transfer transaction.
+const AXFER_TYPE_ENUM: u64 = 4;
+
+/// Set the field of the inner transaction to the value on top of the stack.
+pub(crate) fn itxn_field(name: &str) -> Chunk {
+    Chunk::new_single(
+        Instruction::ItxnField,
+        Constant::StringLit(name.to_string()),
+    )
+}
+
+/// Set the fee of the inner transaction to zero and submit it.
+pub(crate) fn submit(chunks: &mut Vec<Chunk>) {
+    chunks.extend([
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(0)),
+        itxn_field("Fee"),
+        Chunk::new_empty(Instruction::ItxnSubmit),
+    ]);
+}
+
+/// Emit the value of the transaction context, e.g. `caller()` or `account.balance`.
+pub fn emit_context(f: &BuiltinCall, chunks: &mut Vec<Chunk>, args: &mut EmitArgs) -> EmitResult {
+    let mut local_chunks = vec![];
+    match f.func {
+        BuiltinFunction::Caller => {
+            local_chunks.push(Chunk::new_single(
+                Instruction::Txn,
+                Constant::StringLit("Sender".to_string()),
+            ));
+        }
+        BuiltinFunction::CurrentBlock => {
+            local_chunks.push(Chunk::new_single(
+                Instruction::Global,
+                Constant::StringLit("Round".to_string()),
+            ));
+        }
+        // the account must be available to the transaction.
+        BuiltinFunction::Balance => {
+            emit_expression(&f.args[0], &mut local_chunks, args)?;
+            local_chunks.push(Chunk::new_empty(Instruction::Balance));
+        }
+        _ => unreachable!("only context values are emitted"),
+    }
+
+    chunks.extend(local_chunks);
+    Ok(f.returns.size_hint(args.emitter.definition))
+}
+
+/// Emit the payment or the asset transfer.
+pub fn emit_transaction(
+    f: &BuiltinCall,
+    chunks: &mut Vec<Chunk>,
+    args: &mut EmitArgs,
+) -> EmitResult {
+    let (type_enum, fields): (u64, &[&str]) = match f.func {
+        BuiltinFunction::Pay => (PAY_TYPE_ENUM, &["Receiver", "Amount"]),
+        BuiltinFunction::AssetTransfer => {
+            (
+                AXFER_TYPE_ENUM,
+                &["XferAsset", "AssetReceiver", "AssetAmount"],
+            )
+        }
+        _ => unreachable!("only transactions are emitted"),
+    };
+
+    // the values are evaluated before the transaction is started,
+    // as they can call other applications themselves.
+    let mut local_chunks = vec![];
+    let mut error = false;
+    let mut slots = vec![];
+    for a in &f.args {
+        error |= emit_expression(a, &mut local_chunks, args).is_err();
+        let slot = args.emitter.scratch_index_incr()?;
+        local_chunks.push(Chunk::new_single(Instruction::Store, Constant::Uint(slot)));
+        slots.push(slot);
+    }
+    if error {
+        return Err(());
+    }
+
+    local_chunks.extend([
+        Chunk::new_empty(Instruction::ItxnBegin),
+        Chunk::new_single(Instruction::PushInt, Constant::Uint(type_enum)),
+        itxn_field("TypeEnum"),
+    ]);
+    for (slot, field) in slots.into_iter().zip(fields) {
+        local_chunks.push(Chunk::new_single(Instruction::Load, Constant::Uint(slot)));
+        local_chunks.push(itxn_field(field));
+    }
+    submit(&mut local_chunks);
+
+    chunks.extend(local_chunks);
+    Ok(f.returns.size_hint(args.emitter.definition))
+}
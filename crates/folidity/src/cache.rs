@@ -0,0 +1,248 @@
+//! Incremental compilation cache.
+//!
+//! Every top-level declaration is hashed from its source code. The cache persists
+//! the fingerprint of the last semantically valid contract and the fingerprints of
+//! the verified components of linked declarations, so unchanged parts of the contract
+//! are not re-checked or re-verified on subsequent runs. Unchanged contract that has been
+//! verified as a whole skips the semantic analysis and verification altogether.
+//!
+//! The fingerprints are SHA3-256 digests, as the hashers of `std` are not guaranteed
+//! to produce the same values across the releases of Rust.
+
+use std::{
+    collections::{
+        BTreeMap,
+        HashSet,
+    },
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use anyhow::Result;
+use folidity_parser::ast::{
+    Declaration,
+    Source,
+};
+use folidity_semantics::{
+    ContractDefinition,
+    GlobalSymbol,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tiny_keccak::{
+    Hasher,
+    Sha3,
+};
+
+/// Directory of the cache relative to the contract.
+const CACHE_DIR: &str = ".folidity";
+/// Name of the cache file.
+const CACHE_FILE: &str = "cache.json";
+
+/// Hex-encoded SHA3-256 digest.
+pub type Fingerprint = String;
+
+/// Hashes of the top-level declarations indexed by their names.
+pub type DeclarationHashes = BTreeMap<String, Fingerprint>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {
+    /// Version of the compiler that produced the cache.
+    version: String,
+    /// Fingerprint of the last semantically valid contract.
+    checked: Option<Fingerprint>,
+    /// Fingerprints of the verified components of linked declarations.
+    verified: HashSet<Fingerprint>,
+    /// Fingerprint of the last contract verified as a whole.
+    #[serde(default)]
+    contract_verified: Option<Fingerprint>,
+
+    /// Location of the cache file.
+    #[serde(skip)]
+    path: PathBuf,
+    /// Hashes of the declarations of the current contract.
+    #[serde(skip)]
+    hashes: DeclarationHashes,
+    /// Whether the cache can be used.
+    #[serde(skip)]
+    enabled: bool,
+}
+
+impl Cache {
+    /// Load the cache stored next to the contract and hash declarations of the current
+    /// contract.
+    ///
+    /// # Note
+    /// Missing, corrupted or outdated cache is silently replaced with an empty one.
+    pub fn load(contract_path: &Path, tree: &Source, content: &str, enabled: bool) -> Self {
+        let mut path = contract_path.to_path_buf();
+        path.pop();
+        path.push(CACHE_DIR);
+        path.push(CACHE_FILE);
+
+        let stored = if enabled {
+            fs::read_to_string(&path)
+                .ok()
+                .and_then(|s| serde_json::from_str::<Cache>(&s).ok())
+                .filter(|c| c.version == env!("CARGO_PKG_VERSION"))
+        } else {
+            None
+        };
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            path,
+            hashes: hash_declarations(tree, content),
+            enabled,
+            ..stored.unwrap_or_default()
+        }
+    }
+
+    /// Is the current contract known to be semantically valid?
+    pub fn is_checked(&self) -> bool {
+        self.enabled && self.checked == Some(self.fingerprint())
+    }
+
+    /// Mark the current contract as semantically valid.
+    pub fn set_checked(&mut self) {
+        self.checked = Some(self.fingerprint());
+    }
+
+    /// Is the current contract known to be valid and to have satisfiable constraints?
+    pub fn is_contract_verified(&self) -> bool {
+        self.is_checked() && self.contract_verified == Some(self.fingerprint())
+    }
+
+    /// Mark the current contract as verified as a whole.
+    pub fn set_contract_verified(&mut self) {
+        self.contract_verified = Some(self.fingerprint());
+    }
+
+    /// Has the component of linked declarations been verified before?
+    pub fn is_verified(&self, component: &[GlobalSymbol], contract: &ContractDefinition) -> bool {
+        self.enabled
+            && self
+                .component_fingerprint(component, contract)
+                .is_some_and(|f| self.verified.contains(&f))
+    }
+
+    /// Mark components of linked declarations as verified.
+    pub fn set_verified(
+        &mut self,
+        components: &[Vec<GlobalSymbol>],
+        contract: &ContractDefinition,
+    ) {
+        for c in components {
+            if let Some(f) = self.component_fingerprint(c, contract) {
+                self.verified.insert(f);
+            }
+        }
+    }
+
+    /// Persist the cache next to the contract.
+    pub fn save(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Fingerprint of the whole contract.
+    fn fingerprint(&self) -> Fingerprint {
+        let parts: Vec<&[u8]> = self
+            .hashes
+            .iter()
+            .flat_map(|(n, h)| [n.as_bytes(), h.as_bytes()])
+            .collect();
+        digest(&parts)
+    }
+
+    /// Fingerprint of the component of linked declarations.
+    ///
+    /// Structs, enums, constants and aliases can be referenced from any constraint,
+    /// so their hashes are included in every component.
+    fn component_fingerprint(
+        &self,
+        component: &[GlobalSymbol],
+        contract: &ContractDefinition,
+    ) -> Option<Fingerprint> {
+        let mut names: Vec<&str> = component
+            .iter()
+            .map(|s| symbol_name(s, contract))
+            .chain(contract.structs.iter().map(|s| s.name.name.as_str()))
+            .chain(contract.enums.iter().map(|e| e.name.name.as_str()))
+            .chain(contract.constants.iter().map(|c| c.name.name.as_str()))
+            .chain(contract.aliases.iter().map(|a| a.name.name.as_str()))
+            .collect();
+        names.sort();
+        names.dedup();
+
+        let mut parts: Vec<&[u8]> = vec![];
+        for n in names {
+            parts.push(n.as_bytes());
+            parts.push(self.hashes.get(n)?.as_bytes());
+        }
+        Some(digest(&parts))
+    }
+}
+
+/// Digest of the sequence of byte strings.
+fn digest(parts: &[&[u8]]) -> Fingerprint {
+    let mut sha = Sha3::v256();
+    for p in parts {
+        // the lengths delimit the parts, so that their boundaries affect the digest.
+        sha.update(&(p.len() as u64).to_le_bytes());
+        sha.update(p);
+    }
+    let mut hash = [0u8; 32];
+    sha.finalize(&mut hash);
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hash source code of every top-level declaration.
+fn hash_declarations(tree: &Source, content: &str) -> DeclarationHashes {
+    let mut hashes = DeclarationHashes::new();
+    for d in &tree.declarations {
+        let (name, loc) = match d {
+            Declaration::FunDeclaration(f) => (&f.name.name, &f.loc),
+            Declaration::EnumDeclaration(e) => (&e.name.name, &e.loc),
+            Declaration::StructDeclaration(s) => (&s.name.name, &s.loc),
+            Declaration::ModelDeclaration(m) => (&m.name.name, &m.loc),
+            Declaration::StateDeclaration(s) => (&s.name.name, &s.loc),
+            Declaration::ConstDeclaration(c) => (&c.name.name, &c.loc),
+            Declaration::TypeAlias(a) => (&a.name.name, &a.loc),
+            Declaration::InterfaceDeclaration(i) => (&i.name.name, &i.loc),
+            Declaration::EventDeclaration(e) => (&e.name.name, &e.loc),
+            Declaration::Import(_) | Declaration::TestDeclaration(_) | Declaration::Error(_) => {
+                continue
+            }
+        };
+        let source = content.get(loc.clone()).unwrap_or_default();
+        hashes.insert(name.clone(), digest(&[source.as_bytes()]));
+    }
+    hashes
+}
+
+fn symbol_name<'a>(sym: &GlobalSymbol, contract: &'a ContractDefinition) -> &'a str {
+    match sym {
+        GlobalSymbol::Struct(s) => &contract.structs[s.i].name.name,
+        GlobalSymbol::Model(s) => &contract.models[s.i].name.name,
+        GlobalSymbol::Enum(s) => &contract.enums[s.i].name.name,
+        GlobalSymbol::State(s) => &contract.states[s.i].name.name,
+        GlobalSymbol::Function(s) => &contract.functions[s.i].name.name,
+        GlobalSymbol::Constant(s) => &contract.constants[s.i].name.name,
+        GlobalSymbol::Alias(s) => &contract.aliases[s.i].name.name,
+        GlobalSymbol::Interface(s) => &contract.interfaces[s.i].name.name,
+        GlobalSymbol::Event(s) => &contract.events[s.i].name.name,
+        GlobalSymbol::Test(s) => &contract.tests[s.i].name,
+    }
+}
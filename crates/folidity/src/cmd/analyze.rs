@@ -0,0 +1,279 @@
+use anyhow::Result;
+use clap::Args;
+use folidity_semantics::{
+    ast::{
+        FunctionVisibility,
+        Param,
+        StateBody,
+    },
+    modules::Project,
+    ContractDefinition,
+    Span,
+};
+use serde::Serialize;
+use std::ffi::OsString;
+
+use super::{
+    build_report,
+    exec,
+    print_document,
+    read_contract,
+    resolve_contracts,
+    DiagnosticsFormat,
+};
+
+/// Analyze the contract and print the summary of its declarations for external tooling.
+#[derive(Args)]
+pub struct AnalyzeCommand {
+    /// Contract's file name or the project directory.
+    #[clap(value_parser, default_value = ".")]
+    contract: OsString,
+    /// Print the JSON summary of the models, states, functions and state transitions.
+    #[clap(long)]
+    symbols: bool,
+    /// Output format of the diagnostics.
+    #[clap(long, aliases = ["message-format", "diagnostics"], value_enum, default_value_t = DiagnosticsFormat::Pretty)]
+    format: DiagnosticsFormat,
+}
+
+/// Location of the declaration in the file of its module.
+#[derive(Serialize)]
+struct Location {
+    file: String,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Serialize)]
+struct FieldSummary {
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+    mutable: bool,
+    span: Location,
+}
+
+#[derive(Serialize)]
+struct ModelSummary {
+    name: String,
+    span: Location,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    doc: Option<String>,
+    /// Model the fields are inherited from.
+    parent: Option<String>,
+    /// Fields including the inherited ones.
+    fields: Vec<FieldSummary>,
+}
+
+#[derive(Serialize)]
+struct StateSummary {
+    name: String,
+    span: Location,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    doc: Option<String>,
+    #[serde(rename = "final")]
+    is_final: bool,
+    /// Model the fields are derived from.
+    model: Option<String>,
+    /// State it is declared to transition from.
+    from: Option<String>,
+    fields: Vec<FieldSummary>,
+}
+
+#[derive(Serialize)]
+struct FunctionSummary {
+    name: String,
+    span: Location,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    doc: Option<String>,
+    init: bool,
+    visibility: &'static str,
+    params: Vec<FieldSummary>,
+    return_type: String,
+    /// State the function transitions from.
+    from_state: Option<String>,
+    /// States the function transitions to.
+    to_states: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct TransitionSummary {
+    /// Original state, `None` for the initialisation.
+    from: Option<String>,
+    to: String,
+    /// Function making the transition, `None` for the declared `from` relation of the
+    /// state.
+    function: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SymbolsSummary {
+    contract: String,
+    models: Vec<ModelSummary>,
+    states: Vec<StateSummary>,
+    functions: Vec<FunctionSummary>,
+    transitions: Vec<TransitionSummary>,
+}
+
+impl AnalyzeCommand {
+    pub fn run(&self) -> Result<()> {
+        if !self.symbols {
+            anyhow::bail!(
+                "Nothing to analyze, pass `--symbols` to print the summary of the declarations."
+            )
+        }
+        self.format.setup();
+        let inputs = resolve_contracts(&self.contract)?;
+        let [input] = inputs.as_slice() else {
+            anyhow::bail!(
+                "Project has several contracts, pass the file of the contract to analyze."
+            )
+        };
+        let project = read_contract(&input.path)?;
+        let tree = project.parse().map_err(|errors| {
+            build_report(&project, &errors, self.format);
+            anyhow::anyhow!("Error during parsing")
+        })?;
+        let contract = exec::<_, _, ContractDefinition>(&tree, &project, self.format)?;
+
+        let summary = summarize(&input.name, &contract, &project);
+        print_document(&serde_json::to_string_pretty(&summary)?);
+        Ok(())
+    }
+}
+
+/// Collect the summary of the declarations of the contract.
+fn summarize(name: &str, contract: &ContractDefinition, project: &Project) -> SymbolsSummary {
+    let locate = |loc: &Span| {
+        let (file, span) = project
+            .locate(loc)
+            .map(|(m, span)| (m.path.to_string_lossy().to_string(), span))
+            .unwrap_or_else(|| (project.entry().to_string_lossy().to_string(), loc.clone()));
+        Location {
+            file,
+            start: span.start,
+            end: span.end,
+        }
+    };
+    let fields = |params: &[Param]| {
+        params
+            .iter()
+            .map(|p| {
+                FieldSummary {
+                    name: p.name.name.clone(),
+                    ty: p.ty.ty.display(contract),
+                    mutable: p.is_mut,
+                    span: locate(&p.loc),
+                }
+            })
+            .collect()
+    };
+    let state_name = |i: usize| contract.states[i].name.name.clone();
+
+    let models = contract
+        .models
+        .iter()
+        .map(|m| {
+            ModelSummary {
+                name: m.name.name.clone(),
+                span: locate(&m.loc),
+                doc: m.doc.clone(),
+                parent: m
+                    .parent
+                    .as_ref()
+                    .map(|p| contract.models[p.i].name.name.clone()),
+                fields: fields(&m.fields(contract)),
+            }
+        })
+        .collect();
+
+    let states = contract
+        .states
+        .iter()
+        .map(|s| {
+            StateSummary {
+                name: s.name.name.clone(),
+                span: locate(&s.loc),
+                doc: s.doc.clone(),
+                is_final: s.is_final,
+                model: match &s.body {
+                    Some(StateBody::Model(m)) => Some(contract.models[m.i].name.name.clone()),
+                    _ => None,
+                },
+                from: s.from.as_ref().map(|(f, _)| state_name(f.i)),
+                fields: fields(&s.fields(contract)),
+            }
+        })
+        .collect();
+
+    let functions = contract
+        .functions
+        .iter()
+        .map(|f| {
+            let params: Vec<Param> = f.params.values().cloned().collect();
+            FunctionSummary {
+                name: f.name.name.clone(),
+                span: locate(&f.loc),
+                doc: f.doc.clone(),
+                init: f.is_init,
+                visibility: match f.vis {
+                    FunctionVisibility::Pub => "pub",
+                    FunctionVisibility::View(_) => "view",
+                    FunctionVisibility::Priv => "priv",
+                },
+                params: fields(&params),
+                return_type: f.return_ty.ty().display(contract),
+                from_state: f
+                    .state_bound
+                    .as_ref()
+                    .and_then(|b| b.from.as_ref())
+                    .map(|s| state_name(s.ty.i)),
+                to_states: f
+                    .state_bound
+                    .iter()
+                    .flat_map(|b| &b.to)
+                    .map(|s| state_name(s.ty.i))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let mut transitions: Vec<TransitionSummary> = contract
+        .states
+        .iter()
+        .filter_map(|s| {
+            let (from, _) = s.from.as_ref()?;
+            Some(TransitionSummary {
+                from: Some(state_name(from.i)),
+                to: s.name.name.clone(),
+                function: None,
+            })
+        })
+        .collect();
+    for f in &contract.functions {
+        let Some(bound) = &f.state_bound else {
+            continue;
+        };
+        // the constructor is called before the contract is in any state.
+        let from = bound
+            .from
+            .as_ref()
+            .filter(|_| !f.is_init)
+            .map(|s| state_name(s.ty.i));
+        for to in &bound.to {
+            transitions.push(TransitionSummary {
+                from: from.clone(),
+                to: state_name(to.ty.i),
+                function: Some(f.name.name.clone()),
+            });
+        }
+    }
+
+    SymbolsSummary {
+        contract: name.to_string(),
+        models,
+        states,
+        functions,
+        transitions,
+    }
+}
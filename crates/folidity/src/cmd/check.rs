@@ -1,45 +1,77 @@
 use anyhow::Result;
-use folidity_parser::parse;
 use folidity_semantics::ContractDefinition;
 use std::ffi::OsString;
 use yansi::Paint;
 
 use clap::Args;
 
+use crate::cache::Cache;
+
 use super::{
     build_report,
     exec,
     read_contract,
+    report_warnings,
+    resolve_contracts,
+    ContractInput,
+    DiagnosticsFormat,
 };
 
 /// Check the contract's code for parser, semantic and type errors.
 #[derive(Args)]
 pub struct CheckCommand {
-    /// Contract's file name
-    #[clap(value_parser)]
+    /// Contract's file name or the project directory.
+    #[clap(value_parser, default_value = ".")]
     contract: OsString,
+    /// Output format of the diagnostics.
+    #[clap(long, aliases = ["message-format", "diagnostics"], value_enum, default_value_t = DiagnosticsFormat::Pretty)]
+    format: DiagnosticsFormat,
+    /// Do not reuse the results of previous runs.
+    #[clap(long)]
+    no_cache: bool,
+    /// Treat warnings as errors.
+    #[clap(long)]
+    deny_warnings: bool,
 }
 
 impl CheckCommand {
     pub fn run(&self) -> Result<()> {
-        let contract_contents = read_contract(&self.contract)?;
-        let parse_result = parse(&contract_contents);
+        self.format.setup();
+        let inputs = resolve_contracts(&self.contract)?;
+        for input in &inputs {
+            if inputs.len() > 1 {
+                status!(self.format, "{}: {}", "Contract".bold().cyan(), input.name);
+            }
+            self.check(input)?;
+        }
+        Ok(())
+    }
+
+    fn check(&self, input: &ContractInput) -> Result<()> {
+        let project = read_contract(&input.path)?;
+        let parse_result = project.parse();
         match parse_result {
             Ok(tree) => {
-                let _ = exec::<_, _, ContractDefinition>(
-                    &tree,
-                    &contract_contents,
-                    self.contract.to_str().expect("Valid path name."),
-                )?;
-                println!("{}", "Program is semantically valid.".green().bold());
+                let mut cache =
+                    Cache::load(project.entry(), &tree, &project.contents, !self.no_cache);
+                if !cache.is_checked() {
+                    let contract = exec::<_, _, ContractDefinition>(&tree, &project, self.format)?;
+                    report_warnings(&contract, &project, self.format, self.deny_warnings)?;
+                    // keep reporting the warnings until they are resolved.
+                    if contract.diagnostics.is_empty() {
+                        cache.set_checked();
+                        cache.save()?;
+                    }
+                }
+                status!(
+                    self.format,
+                    "{}",
+                    "Program is semantically valid.".green().bold()
+                );
                 Ok(())
             }
             Err(errors) => {
-                build_report(
-                    &contract_contents,
-                    &errors,
-                    self.contract.to_str().expect("Valid path name."),
-                );
+                build_report(&project, &errors, self.format);
                 anyhow::bail!("Error during parsing")
             }
         }
@@ -1,118 +1,502 @@
-use anyhow::{
-    Context,
-    Result,
+use anyhow::Result;
+use folidity_diagnostics::Report;
+use folidity_emitter::{
+    app_spec::{
+        AppSpec,
+        ContractSpec,
+    },
+    emit_teal,
+    evm::{
+        EvmArtifacts,
+        EvmEmitter,
+    },
+    ir::IrTealEmitter,
+    source_map::SourceMap,
+    teal::{
+        FunctionCost,
+        RuntimeChecks,
+        TealArtifacts,
+        APP_CALL_BUDGET,
+    },
 };
-use folidity_emitter::teal::{
-    TealArtifacts,
-    TealEmitter,
+use folidity_semantics::{
+    modules::Project,
+    optimizer::{
+        optimize,
+        OptLevel,
+    },
+    ContractDefinition,
 };
-use folidity_parser::parse;
-use folidity_semantics::ContractDefinition;
-use folidity_verifier::SymbolicExecutor;
+use folidity_verifier::SolverConfig;
+use serde_json::json;
 use std::{
+    collections::HashSet,
     ffi::OsString,
     fs::{
-        create_dir,
+        create_dir_all,
         File,
     },
     io::Write,
-    path::PathBuf,
+    path::{
+        Path,
+        PathBuf,
+    },
 };
 use yansi::Paint;
 
-use clap::Args;
+use clap::{
+    Args,
+    ValueEnum,
+};
+
+use crate::cache::Cache;
 
 use super::{
     build_report,
     exec,
+    exec_verify,
     read_contract,
+    report_error,
+    report_warnings,
+    resolve_contracts,
+    ContractInput,
+    DiagnosticsFormat,
+    Target,
 };
 
-/// Check the contract's code for errors
-/// and validate model consistency using static analysis and symbolic execution.
+/// Compile the contract into the approval and clear TEAL programs
+/// along with the ARC-32 application specification and the box storage layout,
+/// or into the Yul object along with the contract ABI for the EVM target.
+///
+/// A `manifest.json` listing the artifacts is written next to them.
 #[derive(Args)]
 pub struct CompileCommand {
-    /// Contract's file name
-    #[clap(value_parser)]
+    /// Contract's file name or the project directory.
+    #[clap(value_parser, default_value = ".")]
     contract: OsString,
+    /// Output format of the diagnostics.
+    #[clap(long, aliases = ["message-format", "diagnostics"], value_enum, default_value_t = DiagnosticsFormat::Pretty)]
+    format: DiagnosticsFormat,
+    /// Do not reuse the results of previous runs.
+    #[clap(long)]
+    no_cache: bool,
+    /// Treat warnings as errors.
+    #[clap(long)]
+    deny_warnings: bool,
     /// Skip formal verification stage.
+    #[clap(long)]
+    no_verify: bool,
+    /// Target platform of the compiled contract.
+    /// Defaults to the one of the project, or to `teal`.
+    #[clap(long, value_enum)]
+    target: Option<Target>,
+    /// Directory of the compiled artifacts.
+    /// Defaults to the one of the project, or to `build` directory next to the contract.
     #[clap(short, long)]
-    skip_verify: bool,
+    out_dir: Option<PathBuf>,
+    /// Optimization level: `0` emits the code as written,
+    /// `1` propagates constants and eliminates dead code.
+    #[clap(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=1))]
+    opt_level: u8,
+    /// Bounds of the instantiated models and states asserted in the TEAL program.
+    #[clap(long, value_enum, default_value_t = RuntimeChecksMode::All)]
+    runtime_checks: RuntimeChecksMode,
+    /// Print the estimated opcode cost of every function of the TEAL program.
+    #[clap(long)]
+    cost_report: bool,
+    /// Emit the TEAL program through the intermediate representation.
+    /// Only the functions over integers and booleans are supported.
+    #[clap(long)]
+    via_ir: bool,
+}
+
+/// Mode of the runtime checks of the bounds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum RuntimeChecksMode {
+    /// Do not assert the bounds.
+    None,
+    /// Assert the bounds that are not proven to hold by the verifier.
+    Verified,
+    /// Assert every bound.
+    #[default]
+    All,
 }
 
 impl CompileCommand {
     pub fn run(&self) -> Result<()> {
-        let contract_contents = read_contract(&self.contract)?;
-        let parse_result = parse(&contract_contents);
-        let file_name = self.contract.to_str().context("Invalid filename")?;
+        self.format.setup();
+        let inputs = resolve_contracts(&self.contract)?;
+        for input in &inputs {
+            if inputs.len() > 1 {
+                status!(self.format, "{}: {}", "Contract".bold().cyan(), input.name);
+            }
+            self.compile(input)?;
+        }
+        Ok(())
+    }
+
+    fn compile(&self, input: &ContractInput) -> Result<()> {
+        let project = read_contract(&input.path)?;
+        let parse_result = project.parse();
         match parse_result {
             Ok(tree) => {
-                let contract =
-                    exec::<_, _, ContractDefinition>(&tree, &contract_contents, file_name)?;
-
-                exec::<_, _, SymbolicExecutor>(&contract, &contract_contents, file_name)?;
-                println!(
-                    "{}",
-                    "Program model is consistent and has satisfiable constraints."
-                        .green()
-                        .bold()
+                // the proofs are only collected for the components that are verified again.
+                let mut cache = Cache::load(
+                    project.entry(),
+                    &tree,
+                    &project.contents,
+                    !self.no_cache && self.runtime_checks != RuntimeChecksMode::Verified,
                 );
+                let mut contract = exec::<_, _, ContractDefinition>(&tree, &project, self.format)?;
+                report_warnings(&contract, &project, self.format, self.deny_warnings)?;
+                // keep reporting the warnings until they are resolved.
+                if contract.diagnostics.is_empty() {
+                    cache.set_checked();
+                }
 
-                let artifacts = exec::<_, TealArtifacts, TealEmitter>(
-                    &contract,
-                    &contract_contents,
-                    file_name,
-                )?;
+                let mut proven = HashSet::new();
+                if self.no_verify {
+                    let mut warning = Report::ver_warning(
+                        0..0,
+                        String::from("Formal verification is skipped, the model consistency is not guaranteed."),
+                    );
+                    warning.note =
+                        String::from("Run the verification before deploying the contract.");
+                    build_report(&project, &[warning], self.format);
+                    cache.save()?;
+                } else {
+                    proven = exec_verify(
+                        &contract,
+                        &project,
+                        self.format,
+                        &SolverConfig {
+                            timeout_ms: input.timeout_ms,
+                            ..Default::default()
+                        },
+                        &mut cache,
+                        self.deny_warnings,
+                    )?
+                    .proven;
+                    status!(
+                        self.format,
+                        "{}",
+                        "Program model is consistent and has satisfiable constraints."
+                            .green()
+                            .bold()
+                    );
+                }
 
-                self.write_output(&artifacts)?;
+                optimize(&mut contract, OptLevel::from(self.opt_level));
+
+                match self.target(input) {
+                    Target::Teal => {
+                        let checks = match self.runtime_checks {
+                            RuntimeChecksMode::None => RuntimeChecks::None,
+                            RuntimeChecksMode::Verified => RuntimeChecks::Verified(proven),
+                            RuntimeChecksMode::All => RuntimeChecks::All,
+                        };
+                        let artifacts = if self.via_ir {
+                            exec::<_, TealArtifacts, IrTealEmitter>(
+                                &contract,
+                                &project,
+                                self.format,
+                            )?
+                        } else {
+                            emit_teal(&contract, checks)
+                                .map_err(|e| report_error(e, &project, self.format))?
+                        };
+                        if !artifacts.warnings.is_empty() {
+                            build_report(&project, &artifacts.warnings, self.format);
+                            if self.deny_warnings {
+                                anyhow::bail!("Warnings are denied")
+                            }
+                        }
+                        self.write_output(input, &project, &contract, &artifacts)?;
+                        if self.cost_report {
+                            print_costs(&artifacts.costs, self.format);
+                        }
+                    }
+                    Target::Evm => {
+                        let artifacts =
+                            exec::<_, EvmArtifacts, EvmEmitter>(&contract, &project, self.format)?;
+                        self.write_evm_output(input, &artifacts)?;
+                    }
+                }
 
                 Ok(())
             }
             Err(errors) => {
-                build_report(
-                    &contract_contents,
-                    &errors,
-                    self.contract.to_str().expect("Valid path name."),
-                );
+                build_report(&project, &errors, self.format);
                 anyhow::bail!("Error during parsing")
             }
         }
     }
 
-    fn write_output(&self, artifacts: &TealArtifacts) -> Result<()> {
-        let mut current_path = PathBuf::from(&self.contract);
-        current_path.pop();
+    /// Target set in the command line takes precedence over the one of the project.
+    fn target(&self, input: &ContractInput) -> Target {
+        self.target.or(input.target).unwrap_or_default()
+    }
 
-        current_path.push("build");
+    fn out_dir(&self, input: &ContractInput) -> Result<PathBuf> {
+        let current_path = if let Some(out_dir) = self.out_dir.as_ref().or(input.out_dir.as_ref()) {
+            out_dir.clone()
+        } else {
+            let mut path = input.path.clone();
+            path.pop();
+            path.push("build");
+            path
+        };
 
         if !current_path.exists() {
-            create_dir(&current_path)?;
+            create_dir_all(&current_path)?;
         }
 
+        Ok(current_path)
+    }
+
+    fn write_output(
+        &self,
+        input: &ContractInput,
+        project: &Project,
+        contract: &ContractDefinition,
+        artifacts: &TealArtifacts,
+    ) -> Result<()> {
+        let current_path = self.out_dir(input)?;
+
         let mut approval_path = current_path.clone();
         approval_path.push("approval.teal");
 
         let mut clear_path = current_path.clone();
         clear_path.push("clear.teal");
 
+        // the program is annotated with the source lines, which do not affect its code.
+        let source_map = SourceMap::new(project, artifacts);
         let mut approval_file = File::create(&approval_path)?;
-        approval_file.write_all(&artifacts.approval_bytes)?;
+        approval_file.write_all(&source_map.annotate(&artifacts.approval_bytes))?;
+
+        let mut source_map_path = current_path.clone();
+        source_map_path.push("approval.teal.map");
+        let mut source_map_file = File::create(&source_map_path)?;
+        source_map_file.write_all(source_map.to_json("approval.teal").as_bytes())?;
 
         let mut clear_file = File::create(&clear_path)?;
         clear_file.write_all(&artifacts.clear_bytes)?;
 
-        println!("{}", "Successfully executed compilation!".bold().green());
-        println!(
+        let mut spec_path = current_path.clone();
+        spec_path.push("application.json");
+
+        let spec = AppSpec::new(&input.name, contract, artifacts);
+        let mut spec_file = File::create(&spec_path)?;
+        spec_file.write_all(spec.to_json().as_bytes())?;
+
+        let mut abi_path = current_path.clone();
+        abi_path.push("contract.json");
+        let abi = ContractSpec::new(&input.name, artifacts);
+        let mut abi_file = File::create(&abi_path)?;
+        abi_file.write_all(abi.to_json().as_bytes())?;
+
+        let mut storage_path = current_path.clone();
+        storage_path.push("storage.json");
+        let mut storage_file = File::create(&storage_path)?;
+        storage_file.write_all(serde_json::to_string_pretty(&artifacts.storage)?.as_bytes())?;
+
+        let manifest_path = self.write_manifest(
+            input,
+            &current_path,
+            &[
+                ("approval", "approval.teal"),
+                ("source_map", "approval.teal.map"),
+                ("clear", "clear.teal"),
+                ("spec", "application.json"),
+                ("abi", "contract.json"),
+                ("storage", "storage.json"),
+            ],
+        )?;
+
+        status!(
+            self.format,
+            "{}",
+            "Successfully executed compilation!".bold().green()
+        );
+        status!(
+            self.format,
             "{}: {}",
             "Approval program".bold().cyan(),
             approval_path.to_str().unwrap()
         );
-        println!(
+        status!(
+            self.format,
+            "{}: {}",
+            "Source map".bold().cyan(),
+            source_map_path.to_str().unwrap()
+        );
+        status!(
+            self.format,
             "{}: {}",
             "Clear program".bold().cyan(),
             clear_path.to_str().unwrap()
         );
+        status!(
+            self.format,
+            "{}: {}",
+            "Application specification".bold().cyan(),
+            spec_path.to_str().unwrap()
+        );
+        status!(
+            self.format,
+            "{}: {}",
+            "Contract ABI".bold().cyan(),
+            abi_path.to_str().unwrap()
+        );
+        status!(
+            self.format,
+            "{}: {}",
+            "Storage layout".bold().cyan(),
+            storage_path.to_str().unwrap()
+        );
+        status!(
+            self.format,
+            "{}: {}",
+            "Manifest".bold().cyan(),
+            manifest_path.to_str().unwrap()
+        );
+        if self.runtime_checks != RuntimeChecksMode::All {
+            status!(
+                self.format,
+                "{}: {}",
+                "Elided bound assertions".bold().cyan(),
+                artifacts.elided_asserts
+            );
+        }
 
         Ok(())
     }
+
+    fn write_evm_output(&self, input: &ContractInput, artifacts: &EvmArtifacts) -> Result<()> {
+        let current_path = self.out_dir(input)?;
+
+        let mut yul_path = current_path.clone();
+        yul_path.push("contract.yul");
+        let mut yul_file = File::create(&yul_path)?;
+        yul_file.write_all(&artifacts.yul_bytes)?;
+
+        let mut abi_path = current_path.clone();
+        abi_path.push("abi.json");
+        let mut abi_file = File::create(&abi_path)?;
+        abi_file.write_all(artifacts.abi.as_bytes())?;
+
+        let manifest_path = self.write_manifest(
+            input,
+            &current_path,
+            &[("yul", "contract.yul"), ("abi", "abi.json")],
+        )?;
+
+        status!(
+            self.format,
+            "{}",
+            "Successfully executed compilation!".bold().green()
+        );
+        status!(
+            self.format,
+            "{}: {}",
+            "Yul object".bold().cyan(),
+            yul_path.to_str().unwrap()
+        );
+        status!(
+            self.format,
+            "{}: {}",
+            "Contract ABI".bold().cyan(),
+            abi_path.to_str().unwrap()
+        );
+        status!(
+            self.format,
+            "{}: {}",
+            "Manifest".bold().cyan(),
+            manifest_path.to_str().unwrap()
+        );
+
+        Ok(())
+    }
+
+    /// Write `manifest.json` describing the compiled artifacts.
+    /// Artifact paths are relative to the output directory.
+    fn write_manifest(
+        &self,
+        input: &ContractInput,
+        dir: &Path,
+        artifacts: &[(&str, &str)],
+    ) -> Result<PathBuf> {
+        let target = self
+            .target(input)
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        let files: serde_json::Map<String, serde_json::Value> = artifacts
+            .iter()
+            .map(|(kind, file)| (kind.to_string(), json!(file)))
+            .collect();
+        let manifest = json!({
+            "name": input.name,
+            "source": input.path.to_string_lossy(),
+            "target": target,
+            "compiler": env!("CARGO_PKG_VERSION"),
+            "verified": !self.no_verify,
+            "artifacts": files,
+        });
+
+        let mut manifest_path = dir.to_path_buf();
+        manifest_path.push("manifest.json");
+        let mut manifest_file = File::create(&manifest_path)?;
+        manifest_file.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+
+        Ok(manifest_path)
+    }
+}
+
+/// Print the estimated costs of the functions as a table,
+/// the costs of the functions with loops or recursion are lower bounds.
+fn print_costs(costs: &[FunctionCost], format: DiagnosticsFormat) {
+    let header = [
+        String::from("Function"),
+        String::from("Cost"),
+        String::from("Budget"),
+    ];
+    let rows: Vec<[String; 3]> = costs
+        .iter()
+        .map(|c| {
+            let prefix = if c.unbounded { ">= " } else { "" };
+            [
+                c.name.clone(),
+                format!("{}{}", prefix, c.cost),
+                format!("{}{}%", prefix, c.cost * 100 / APP_CALL_BUDGET),
+            ]
+        })
+        .collect();
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .chain([&header])
+                .map(|r| r[i].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let line = |row: &[String]| -> String {
+        row.iter()
+            .zip(&widths)
+            .map(|(v, w)| format!("{:<w$}", v, w = w))
+            .collect::<Vec<String>>()
+            .join(" | ")
+    };
+
+    status!(format, "{}", "Estimated opcode cost".bold().cyan());
+    status!(format, "  {}", line(&header).bold());
+    for (row, c) in rows.iter().zip(costs) {
+        // the functions exceeding the budget are also reported as warnings.
+        if c.cost > APP_CALL_BUDGET {
+            status!(format, "  {}", line(row).yellow());
+        } else {
+            status!(format, "  {}", line(row));
+        }
+    }
 }
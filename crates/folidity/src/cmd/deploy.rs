@@ -0,0 +1,346 @@
+//! Deployment of the compiled TEAL artifacts to Algorand and the calls of the deployed
+//! application.
+//!
+//! The artifacts are read from the output directory of `folidity compile`,
+//! the transactions are signed by the account recovered from the mnemonic.
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::Duration,
+};
+
+use algonaut::{
+    algod::v2::Algod,
+    core::Address,
+    transaction::{
+        account::Account,
+        builder::{
+            CallApplication,
+            CreateApplication,
+        },
+        transaction::StateSchema as AlgodSchema,
+        Transaction,
+        TxnBuilder,
+    },
+};
+use anyhow::{
+    Context,
+    Result,
+};
+use clap::Args;
+use folidity_emitter::app_spec::{
+    ContractSpec,
+    MethodHints,
+    MethodSpec,
+    SchemaSize,
+    StateSchema,
+};
+use num_bigint::{
+    BigInt,
+    BigUint,
+    Sign,
+};
+use serde::Deserialize;
+use yansi::Paint;
+
+use super::{
+    resolve_contracts,
+    ContractInput,
+};
+
+/// Number of the rounds to wait for the transaction to be confirmed.
+const CONFIRMATION_ROUNDS: u64 = 10;
+
+/// Connection to the Algorand node and the signing account.
+#[derive(Args)]
+pub struct NodeArgs {
+    /// URL of the algod node.
+    #[clap(long, env = "ALGOD_URL", default_value = "http://localhost:4001")]
+    algod_url: String,
+    /// API token of the algod node.
+    #[clap(long, env = "ALGOD_TOKEN", default_value = "")]
+    algod_token: String,
+    /// Mnemonic of the account signing the transactions.
+    #[clap(long, env = "FOLIDITY_MNEMONIC", hide_env_values = true)]
+    mnemonic: String,
+    /// Directory of the compiled artifacts, `build/` next to the contract by default.
+    #[clap(short, long)]
+    out_dir: Option<PathBuf>,
+}
+
+/// Deploy the compiled contract to Algorand.
+///
+/// The application is created with the call of its constructor,
+/// the arguments are encoded according to its signature.
+#[derive(Args)]
+pub struct DeployCommand {
+    /// Contract's file name or the project directory.
+    #[clap(value_parser, default_value = ".")]
+    contract: OsString,
+    /// Arguments of the constructor.
+    #[clap(long = "arg")]
+    args: Vec<String>,
+    #[clap(flatten)]
+    node: NodeArgs,
+}
+
+/// Call the function of the deployed contract.
+#[derive(Args)]
+pub struct CallCommand {
+    /// Name of the function.
+    function: String,
+    /// Arguments of the function.
+    args: Vec<String>,
+    /// Id of the deployed application.
+    #[clap(long)]
+    app_id: u64,
+    /// Contract's file name or the project directory.
+    #[clap(long, default_value = ".")]
+    contract: OsString,
+    #[clap(flatten)]
+    node: NodeArgs,
+}
+
+/// Part of the ARC-32 application specification needed for the deployment.
+#[derive(Deserialize)]
+struct ApplicationSpec {
+    hints: BTreeMap<String, MethodHints>,
+    state: StateSchema,
+}
+
+impl DeployCommand {
+    pub fn run(&self) -> Result<()> {
+        let input = single_contract(&self.contract)?;
+        let out_dir = self.node.out_dir(&input);
+        let approval = read_artifact(&out_dir, "approval.teal")?;
+        let clear = read_artifact(&out_dir, "clear.teal")?;
+        let spec: ApplicationSpec =
+            serde_json::from_slice(&read_artifact(&out_dir, "application.json")?)
+                .context("Application specification is not valid")?;
+        let interface = read_interface(&out_dir)?;
+
+        // the constructor is called at the creation and reads its arguments from the first one.
+        let init = spec
+            .hints
+            .iter()
+            .find(|(_, h)| h.call_config.values().any(|c| c == "CREATE"))
+            .and_then(|(sig, _)| sig.split('(').next())
+            .and_then(|name| interface.methods.iter().find(|m| m.name == name));
+        let args = match init {
+            Some(init) => encode_args(init, &self.args)?,
+            None if self.args.is_empty() => vec![],
+            None => anyhow::bail!("Contract has no constructor to pass the arguments to."),
+        };
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        let app_id = runtime.block_on(async {
+            let (algod, account) = self.node.connect()?;
+            let approval = algod.teal_compile(&approval, None).await?;
+            let clear = algod.teal_compile(&clear, None).await?;
+            let params = algod.suggested_transaction_params().await?;
+            let txn = TxnBuilder::with(
+                &params,
+                CreateApplication::new(
+                    account.address(),
+                    approval,
+                    clear,
+                    algod_schema(&spec.state.global),
+                    algod_schema(&spec.state.local),
+                )
+                .app_arguments(args)
+                .build(),
+            )
+            .build()?;
+            let pending = send(&algod, &account, txn).await?;
+            pending
+                .application_index
+                .context("Node did not return the id of the application")
+        })?;
+
+        println!("{}", "Successfully deployed the contract!".bold().green());
+        println!("{}: {}", "Application id".bold().cyan(), app_id);
+        Ok(())
+    }
+}
+
+impl CallCommand {
+    pub fn run(&self) -> Result<()> {
+        let input = single_contract(&self.contract)?;
+        let interface = read_interface(&self.node.out_dir(&input))?;
+        let Some(method) = interface.methods.iter().find(|m| m.name == self.function) else {
+            anyhow::bail!("Function `{}` is not declared.", self.function)
+        };
+
+        // the function is dispatched by its name in the first argument.
+        let mut args = vec![self.function.as_bytes().to_vec()];
+        args.extend(encode_args(method, &self.args)?);
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        let pending = runtime.block_on(async {
+            let (algod, account) = self.node.connect()?;
+            let params = algod.suggested_transaction_params().await?;
+            let txn = TxnBuilder::with(
+                &params,
+                CallApplication::new(account.address(), self.app_id)
+                    .app_arguments(args)
+                    .build(),
+            )
+            .build()?;
+            send(&algod, &account, txn).await
+        })?;
+
+        println!("{}", "Successfully called the contract!".bold().green());
+        // the returned value is logged by the application.
+        for log in pending.logs.unwrap_or_default() {
+            println!("{}: 0x{}", "Returned".bold().cyan(), hex::encode(log));
+        }
+        Ok(())
+    }
+}
+
+impl NodeArgs {
+    fn out_dir(&self, input: &ContractInput) -> PathBuf {
+        self.out_dir
+            .clone()
+            .or_else(|| input.out_dir.clone())
+            .unwrap_or_else(|| {
+                let mut path = input.path.clone();
+                path.pop();
+                path.push("build");
+                path
+            })
+    }
+
+    fn connect(&self) -> Result<(Algod, Account)> {
+        let algod = Algod::new(&self.algod_url, &self.algod_token)
+            .context("Failed to connect to the algod node")?;
+        let account = Account::from_mnemonic(&self.mnemonic).context("Mnemonic is not valid")?;
+        Ok((algod, account))
+    }
+}
+
+/// Sign and send the transaction, then wait for its confirmation.
+async fn send(
+    algod: &Algod,
+    account: &Account,
+    txn: Transaction,
+) -> Result<algonaut::algod::v2::PendingTransaction> {
+    let signed = account.sign_transaction(txn)?;
+    let response = algod.send_txn(&signed).await?;
+    for _ in 0..CONFIRMATION_ROUNDS {
+        let pending = algod.pending_txn(&response.tx_id).await?;
+        if pending.confirmed_round.is_some() {
+            return Ok(pending);
+        }
+        if !pending.pool_error.is_empty() {
+            anyhow::bail!("Transaction is rejected: {}", pending.pool_error)
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    anyhow::bail!("Transaction `{}` is not confirmed.", response.tx_id)
+}
+
+fn single_contract(path: &OsString) -> Result<ContractInput> {
+    let mut inputs = resolve_contracts(path)?;
+    if inputs.len() != 1 {
+        anyhow::bail!("Only a single contract can be deployed or called at once.")
+    }
+    Ok(inputs.remove(0))
+}
+
+fn read_artifact(out_dir: &Path, name: &str) -> Result<Vec<u8>> {
+    let path = out_dir.join(name);
+    fs::read(&path).with_context(|| {
+        format!(
+            "Failed to read `{}`, consider compiling the contract first",
+            path.display()
+        )
+    })
+}
+
+fn read_interface(out_dir: &Path) -> Result<ContractSpec> {
+    serde_json::from_slice(&read_artifact(out_dir, "contract.json")?)
+        .context("Contract interface is not valid")
+}
+
+fn algod_schema(size: &SchemaSize) -> AlgodSchema {
+    AlgodSchema {
+        number_ints: size.num_uints,
+        number_byteslices: size.num_byte_slices,
+    }
+}
+
+/// Encode the arguments according to the ARC-4 types of the method.
+fn encode_args(method: &MethodSpec, args: &[String]) -> Result<Vec<Vec<u8>>> {
+    if method.args.len() != args.len() {
+        anyhow::bail!(
+            "`{}` expects {} arguments, but {} are given.",
+            method.name,
+            method.args.len(),
+            args.len()
+        )
+    }
+    method
+        .args
+        .iter()
+        .zip(args)
+        .map(|(spec, arg)| {
+            encode_arg(&spec.ty, arg)
+                .with_context(|| format!("`{}` is not a valid value of {}.", arg, spec.ty))
+        })
+        .collect()
+}
+
+fn encode_arg(ty: &str, arg: &str) -> Result<Vec<u8>> {
+    let bytes = match ty {
+        "uint8" => fixed_uint(arg, 1)?,
+        "uint64" => fixed_uint(arg, 8)?,
+        "uint256" => fixed_uint(arg, 32)?,
+        // signed integers are encoded as a sign block followed by the absolute value.
+        "byte[16]" => {
+            let n: BigInt = arg.parse()?;
+            let mut bytes = fixed_uint(&u64::from(n.sign() == Sign::Minus).to_string(), 8)?;
+            bytes.extend(fixed_uint(&n.magnitude().to_string(), 8)?);
+            bytes
+        }
+        "bool" => {
+            let b: bool = arg.parse()?;
+            vec![if b { 0x80 } else { 0 }]
+        }
+        "address" => {
+            arg.parse::<Address>()
+                .map_err(anyhow::Error::msg)?
+                .0
+                .to_vec()
+        }
+        "string" => with_length(arg.as_bytes().to_vec())?,
+        _ => with_length(hex::decode(arg.trim_start_matches("0x"))?)?,
+    };
+    Ok(bytes)
+}
+
+/// Big-endian encoding of the unsigned integer of the fixed width.
+fn fixed_uint(arg: &str, width: usize) -> Result<Vec<u8>> {
+    let n: BigUint = arg.parse()?;
+    let be = n.to_bytes_be();
+    if be.len() > width {
+        anyhow::bail!("Value does not fit into {} bytes.", width)
+    }
+    let mut bytes = vec![0; width - be.len()];
+    bytes.extend(be);
+    Ok(bytes)
+}
+
+/// Prefix the dynamic value with its length.
+fn with_length(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    let len = u16::try_from(bytes.len()).context("Value is too long.")?;
+    let mut encoded = len.to_be_bytes().to_vec();
+    encoded.extend(bytes);
+    Ok(encoded)
+}
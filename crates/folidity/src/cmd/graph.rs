@@ -0,0 +1,219 @@
+use anyhow::Result;
+use clap::{
+    Args,
+    ValueEnum,
+};
+use folidity_semantics::{
+    ast::Bounds,
+    ContractDefinition,
+};
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::Write,
+    path::PathBuf,
+};
+use yansi::Paint;
+
+use super::{
+    build_report,
+    exec,
+    read_contract,
+    resolve_contracts,
+    DiagnosticsFormat,
+};
+
+/// Output format of the graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT.
+    #[default]
+    Dot,
+    /// Mermaid state diagram.
+    Mermaid,
+}
+
+/// Draw the state-transition diagram of the contract.
+///
+/// States are the nodes, and functions transitioning between them are the edges
+/// labelled with their `st` guards.
+#[derive(Args)]
+pub struct GraphCommand {
+    /// Contract's file name or the project directory.
+    #[clap(value_parser, default_value = ".")]
+    contract: OsString,
+    /// Format of the diagram.
+    #[clap(long, value_enum, default_value_t = GraphFormat::Dot)]
+    graph_format: GraphFormat,
+    /// Write the diagram to the file instead of the standard output.
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Transition between the states.
+struct Edge {
+    /// Index of the original state, `None` for the initialisation.
+    from: Option<usize>,
+    /// Index of the final state.
+    to: usize,
+    /// Label of the edge.
+    label: String,
+    /// Whether the edge is the declared `from` relation of the state.
+    declared: bool,
+}
+
+impl GraphCommand {
+    pub fn run(&self) -> Result<()> {
+        let inputs = resolve_contracts(&self.contract)?;
+        let [input] = inputs.as_slice() else {
+            anyhow::bail!("Project has several contracts, pass the file of the contract to draw.")
+        };
+        let project = read_contract(&input.path)?;
+        let tree = project.parse().map_err(|errors| {
+            build_report(&project, &errors, DiagnosticsFormat::Pretty);
+            anyhow::anyhow!("Error during parsing")
+        })?;
+        let contract =
+            exec::<_, _, ContractDefinition>(&tree, &project, DiagnosticsFormat::Pretty)?;
+
+        let graph = match self.graph_format {
+            GraphFormat::Dot => dot(&contract, &project.contents),
+            GraphFormat::Mermaid => mermaid(&contract, &project.contents),
+        };
+
+        match &self.output {
+            Some(path) => {
+                let mut file = File::create(path)?;
+                file.write_all(graph.as_bytes())?;
+                println!(
+                    "{}: {}",
+                    "State diagram".bold().cyan(),
+                    path.to_string_lossy()
+                );
+            }
+            None => print!("{}", graph),
+        }
+
+        Ok(())
+    }
+}
+
+/// Source code of the bounds joined into a single guard.
+fn guard(bounds: &Option<Bounds>, src: &str) -> Option<String> {
+    let exprs: Vec<&str> = bounds
+        .as_ref()?
+        .exprs
+        .iter()
+        .map(|e| src[e.loc().clone()].trim())
+        .collect();
+    if exprs.is_empty() {
+        None
+    } else {
+        Some(exprs.join(" && "))
+    }
+}
+
+/// Collect the declared state relations and the transitions of the functions.
+fn edges(contract: &ContractDefinition, src: &str) -> Vec<Edge> {
+    let mut edges = vec![];
+
+    for (i, s) in contract.states.iter().enumerate() {
+        if let Some((from, _)) = &s.from {
+            edges.push(Edge {
+                from: Some(from.i),
+                to: i,
+                label: String::from("from"),
+                declared: true,
+            });
+        }
+    }
+
+    for f in &contract.functions {
+        let Some(bound) = &f.state_bound else {
+            continue;
+        };
+        let label = match guard(&f.bounds, src) {
+            Some(g) => format!("{} st [{}]", f.name.name, g),
+            None => f.name.name.clone(),
+        };
+        for to in &bound.to {
+            edges.push(Edge {
+                from: bound.from.as_ref().map(|p| p.ty.i),
+                to: to.ty.i,
+                label: label.clone(),
+                declared: false,
+            });
+        }
+    }
+
+    edges
+}
+
+/// Render the diagram in Graphviz DOT.
+fn dot(contract: &ContractDefinition, src: &str) -> String {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut lines = vec![
+        "digraph states {".to_string(),
+        "    rankdir=LR;".to_string(),
+        "    node [shape=box, style=rounded];".to_string(),
+        "    __init [shape=point];".to_string(),
+    ];
+
+    for s in &contract.states {
+        let label = match guard(&s.bounds, src) {
+            Some(g) => format!("{}\\nst [{}]", s.name.name, escape(&g)),
+            None => s.name.name.clone(),
+        };
+        lines.push(format!("    {} [label=\"{}\"];", s.name.name, label));
+    }
+
+    for e in edges(contract, src) {
+        let from = e
+            .from
+            .map(|i| contract.states[i].name.name.clone())
+            .unwrap_or_else(|| "__init".to_string());
+        let style = if e.declared { ", style=dashed" } else { "" };
+        lines.push(format!(
+            "    {} -> {} [label=\"{}\"{}];",
+            from,
+            contract.states[e.to].name.name,
+            escape(&e.label),
+            style
+        ));
+    }
+
+    lines.push("}".to_string());
+    lines.join("\n") + "\n"
+}
+
+/// Render the diagram as the Mermaid state diagram.
+fn mermaid(contract: &ContractDefinition, src: &str) -> String {
+    let escape = |s: &str| s.replace('"', "#quot;").replace(':', "#colon;");
+    let mut lines = vec!["stateDiagram-v2".to_string()];
+
+    for s in &contract.states {
+        lines.push(format!("    state {}", s.name.name));
+        if let Some(g) = guard(&s.bounds, src) {
+            lines.push(format!(
+                "    note right of {} : st [{}]",
+                s.name.name,
+                escape(&g)
+            ));
+        }
+    }
+
+    for e in edges(contract, src) {
+        let from = e
+            .from
+            .map(|i| contract.states[i].name.name.clone())
+            .unwrap_or_else(|| "[*]".to_string());
+        lines.push(format!(
+            "    {} --> {} : {}",
+            from,
+            contract.states[e.to].name.name,
+            escape(&e.label)
+        ));
+    }
+
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,13 @@
+use anyhow::Result;
+use clap::Args;
+
+/// Start the language server over stdio.
+/// Provides diagnostics, go-to-definition and hover information to the editor.
+#[derive(Args)]
+pub struct LspCommand {}
+
+impl LspCommand {
+    pub fn run(&self) -> Result<()> {
+        folidity_lsp::start()
+    }
+}
@@ -1,42 +1,101 @@
 use std::{
+    cell::RefCell,
     ffi::OsString,
-    fs::File,
-    io::Read,
-    path::Path,
+    io::IsTerminal,
+    path::{
+        Path,
+        PathBuf,
+    },
 };
 
 use anyhow::{
     Context,
     Result,
 };
-use clap::Subcommand;
+use clap::{
+    Subcommand,
+    ValueEnum,
+};
 use folidity_diagnostics::{
+    disable_pretty_print,
+    reports_to_json,
+    reports_to_plain,
+    reports_to_sarif,
     Level,
     Report,
     Span,
 };
 use folidity_semantics::{
+    modules::Project,
     CompilationError,
+    ContractDefinition,
     Runner,
 };
+use folidity_verifier::{
+    verify_incremental,
+    SolverConfig,
+    Verification,
+    DEFAULT_TIMEOUT_MS,
+};
+use serde::Deserialize;
 use yansi::Paint;
 
+use crate::{
+    cache::Cache,
+    manifest::{
+        Manifest,
+        DEFAULT_ENTRY,
+    },
+};
+
+#[cfg(feature = "deploy")]
+use self::deploy::{
+    CallCommand,
+    DeployCommand,
+};
 use self::{
+    analyze::AnalyzeCommand,
     check::CheckCommand,
     compile::CompileCommand,
+    graph::GraphCommand,
+    lsp::LspCommand,
     new::NewCommand,
+    repl::ReplCommand,
+    simulate::SimulateCommand,
+    test::TestCommand,
     verify::VerifyCommand,
 };
 use ariadne::{
     Color,
     Label,
     Report as PrettyReport,
-    Source,
 };
 
+/// Print the human-readable output of the command.
+///
+/// The output goes to stderr in the machine-readable formats,
+/// so that stdout only carries the document of the diagnostics.
+macro_rules! status {
+    ($format:expr, $($arg:tt)*) => {
+        if $format.is_machine_readable() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+mod analyze;
 mod check;
 mod compile;
+#[cfg(feature = "deploy")]
+mod deploy;
+mod graph;
+mod lsp;
 mod new;
+mod repl;
+mod simulate;
+mod test;
 mod verify;
 
 #[derive(Subcommand)]
@@ -45,25 +104,239 @@ pub enum Commands {
     Check(CheckCommand),
     Verify(VerifyCommand),
     Compile(CompileCommand),
+    Graph(GraphCommand),
+    Analyze(AnalyzeCommand),
+    Lsp(LspCommand),
+    Repl(ReplCommand),
+    Simulate(SimulateCommand),
+    Test(TestCommand),
+    #[cfg(feature = "deploy")]
+    Deploy(DeployCommand),
+    #[cfg(feature = "deploy")]
+    Call(CallCommand),
 }
 
 impl Commands {
     pub fn run(&self) -> Result<()> {
-        match self {
+        let result = match self {
             Commands::New(cmd) => cmd.run(),
             Commands::Check(cmd) => cmd.run(),
             Commands::Verify(cmd) => cmd.run(),
             Commands::Compile(cmd) => cmd.run(),
+            Commands::Graph(cmd) => cmd.run(),
+            Commands::Analyze(cmd) => cmd.run(),
+            Commands::Lsp(cmd) => cmd.run(),
+            Commands::Repl(cmd) => cmd.run(),
+            Commands::Simulate(cmd) => cmd.run(),
+            Commands::Test(cmd) => cmd.run(),
+            #[cfg(feature = "deploy")]
+            Commands::Deploy(cmd) => cmd.run(),
+            #[cfg(feature = "deploy")]
+            Commands::Call(cmd) => cmd.run(),
+        };
+        flush_reports();
+        result
+    }
+}
+
+/// Output format of the diagnostics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum DiagnosticsFormat {
+    /// Human-readable reports with source code snippets.
+    #[default]
+    Pretty,
+    /// JSON array of reports for editors and CI tooling.
+    Json,
+    /// One JSON report per line for streaming consumers.
+    JsonLines,
+    /// SARIF 2.1.0 log for code scanning tools.
+    Sarif,
+    /// `file:line:col: level[type]: message` line per report for CI logs.
+    Plain,
+}
+
+impl DiagnosticsFormat {
+    /// Disable colouring of the messages for machine-readable formats,
+    /// and of the plain messages that are not printed to the terminal.
+    ///
+    /// The JSON and SARIF documents are emitted even if nothing is reported.
+    pub fn setup(&self) {
+        if matches!(self, DiagnosticsFormat::Json | DiagnosticsFormat::Sarif) {
+            PENDING.with(|p| p.borrow_mut().format = Some(*self));
+        }
+        match self {
+            DiagnosticsFormat::Pretty => {}
+            DiagnosticsFormat::Plain if std::io::stdout().is_terminal() => {}
+            _ => disable_pretty_print(),
         }
     }
+
+    /// Are the diagnostics printed as documents for other tools to parse?
+    pub fn is_machine_readable(&self) -> bool {
+        matches!(
+            self,
+            DiagnosticsFormat::Json | DiagnosticsFormat::JsonLines | DiagnosticsFormat::Sarif
+        )
+    }
+}
+
+/// Diagnostics of the invocation collected for the formats that emit
+/// a single document, e.g. of several contracts and stages.
+#[derive(Default)]
+struct PendingReports {
+    format: Option<DiagnosticsFormat>,
+    file_name: String,
+    reports: Vec<Report>,
+    sources: Vec<(String, String)>,
+}
+
+thread_local! {
+    static PENDING: RefCell<PendingReports> = RefCell::default();
+}
+
+/// Print the document the command produces on success, e.g. the summary of the
+/// declarations, in place of the document of the diagnostics.
+pub fn print_document(document: &str) {
+    PENDING.with(|p| p.take());
+    println!("{}", document);
+}
+
+/// Emit the collected diagnostics of the invocation as a single document.
+fn flush_reports() {
+    let pending = PENDING.with(|p| p.take());
+    match pending.format {
+        Some(DiagnosticsFormat::Json) => println!("{}", reports_to_json(&pending.reports)),
+        Some(DiagnosticsFormat::Sarif) => {
+            let sources: Vec<(String, &str)> = pending
+                .sources
+                .iter()
+                .map(|(name, contents)| (name.clone(), contents.as_str()))
+                .collect();
+            println!(
+                "{}",
+                reports_to_sarif(&pending.reports, &pending.file_name, &sources)
+            );
+        }
+        _ => {}
+    }
+}
+
+/// Target platform of the compiled contract.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Target {
+    /// Algorand Virtual Machine.
+    #[default]
+    Teal,
+    /// Ethereum Virtual Machine.
+    Evm,
+}
+
+/// Contract to process along with the settings of its project.
+#[derive(Debug, Clone)]
+pub struct ContractInput {
+    /// Name of the contract.
+    pub name: String,
+    /// Path to the entry file of the contract.
+    pub path: PathBuf,
+    /// Target platform set by the project.
+    pub target: Option<Target>,
+    /// Directory of the compiled artifacts set by the project.
+    pub out_dir: Option<PathBuf>,
+    /// Timeout for constraint solving in milliseconds.
+    pub timeout_ms: u64,
 }
 
-pub fn read_contract(path_str: &OsString) -> Result<String> {
+/// Resolve the contracts to process from the path given to the command.
+///
+/// The directory of the project with `folidity.toml` resolves to all contracts of the
+/// project, the directory without it resolves to `contract.fol` in it.
+/// Settings of the manifest are applied to the contracts of the project.
+pub fn resolve_contracts(path_str: &OsString) -> Result<Vec<ContractInput>> {
     let path = Path::new(path_str);
     if !path.exists() {
         anyhow::bail!("File does not exist.");
     }
 
+    let (dir, file) = if path.is_dir() {
+        (path.to_path_buf(), None)
+    } else {
+        let dir = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        (dir.to_path_buf(), Some(path.to_path_buf()))
+    };
+
+    let stem = |p: &Path| {
+        p.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+
+    let Some(manifest) = Manifest::load(&dir)? else {
+        let path = file.unwrap_or_else(|| dir.join(DEFAULT_ENTRY));
+        return Ok(vec![ContractInput {
+            name: stem(&path),
+            path,
+            target: None,
+            out_dir: None,
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+        }]);
+    };
+
+    let out_dir = dir.join(
+        manifest
+            .build
+            .out_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("build")),
+    );
+    let entries = manifest.contracts();
+    // artifacts of several contracts are separated by their names.
+    let several = entries.len() > 1;
+    let input = |name: String, path: PathBuf, target: Option<Target>| {
+        ContractInput {
+            out_dir: Some(
+                if several {
+                    out_dir.join(&name)
+                } else {
+                    out_dir.clone()
+                },
+            ),
+            name,
+            path,
+            target: Some(target.unwrap_or(manifest.build.target)),
+            timeout_ms: manifest.verifier.timeout_ms,
+        }
+    };
+
+    let Some(file) = file else {
+        return Ok(entries
+            .into_iter()
+            .map(|c| input(c.name, dir.join(&c.entry), c.target))
+            .collect());
+    };
+
+    // a single file of the project is processed with the settings of the project.
+    let canonical = file.canonicalize()?;
+    let entry = entries.into_iter().find(|c| {
+        dir.join(&c.entry)
+            .canonicalize()
+            .is_ok_and(|p| p == canonical)
+    });
+    Ok(vec![match entry {
+        Some(c) => input(c.name, file, c.target),
+        None => input(stem(&file), file, None),
+    }])
+}
+
+/// Read the contract along with the files it imports.
+pub fn read_contract(path: &Path) -> Result<Project> {
+    if !path.exists() {
+        anyhow::bail!("File does not exist.");
+    }
+
     let meta = path.metadata()?;
 
     if !meta.is_file() {
@@ -74,37 +347,91 @@ pub fn read_contract(path_str: &OsString) -> Result<String> {
     if !s.to_string_lossy().ends_with(".fol") {
         anyhow::bail!("File is not a valid folidity contract.")
     }
-    let mut file = File::open(path).context("Could not open a file.")?;
-    let mut buffer = String::new();
-    file.read_to_string(&mut buffer)
-        .context("Failed to read file contents")?;
-    Ok(buffer)
+    Project::load(path).context("Failed to read file contents")
 }
 
-pub fn build_report(content: &str, diagnostics: &[Report], file_name: &str) {
-    for r in diagnostics {
-        let notes: Vec<Label<(&str, Span)>> = r
+/// Report the diagnostics of the project.
+///
+/// # Note
+/// Locations of the reports are mapped back to the files of the modules in every format,
+/// the machine-readable ones name the file of each location.
+/// JSON and SARIF reports are emitted as a single document once the command finishes.
+pub fn build_report(project: &Project, diagnostics: &[Report], format: DiagnosticsFormat) {
+    let file_name = project.entry().to_string_lossy().to_string();
+    let diagnostics: Vec<Report> = diagnostics
+        .iter()
+        .map(|r| project.locate_report(r))
+        .collect();
+    let sources: Vec<(String, &str)> = project
+        .modules
+        .iter()
+        .map(|m| (m.path.to_string_lossy().to_string(), m.contents.as_str()))
+        .collect();
+    match format {
+        // emitted once the command finishes, along with the reports of the other stages.
+        DiagnosticsFormat::Json | DiagnosticsFormat::Sarif => {
+            PENDING.with(|p| {
+                let mut pending = p.borrow_mut();
+                pending.format = Some(format);
+                if pending.file_name.is_empty() {
+                    pending.file_name = file_name.clone();
+                }
+                pending.reports.extend(diagnostics.into_iter().map(|mut r| {
+                    r.file.get_or_insert_with(|| file_name.clone());
+                    r
+                }));
+                for (name, contents) in &sources {
+                    if !pending.sources.iter().any(|(n, _)| n == name) {
+                        pending.sources.push((name.clone(), contents.to_string()));
+                    }
+                }
+            });
+            return;
+        }
+        DiagnosticsFormat::JsonLines => {
+            for r in &diagnostics {
+                println!("{}", r.to_json());
+            }
+            return;
+        }
+        DiagnosticsFormat::Plain => {
+            if !diagnostics.is_empty() {
+                println!("{}", reports_to_plain(&diagnostics, &file_name, &sources));
+            }
+            return;
+        }
+        DiagnosticsFormat::Pretty => {}
+    }
+
+    let locate = |r: &Report| -> (String, Span) {
+        let name = r.file.clone().unwrap_or_else(|| file_name.clone());
+        (name, r.loc.clone())
+    };
+
+    for r in &diagnostics {
+        let notes: Vec<Label<(String, Span)>> = r
             .additional_info
             .iter()
             .filter(|x| x.level != Level::Info)
             .map(|ra| {
-                Label::new((file_name, ra.loc.clone()))
+                Label::new(locate(ra))
                     .with_message(ra.message.clone())
                     .with_color(Color::Yellow)
             })
             .collect();
         let title = format!("{} detected.", r.error_type.cyan().underline(),);
-        PrettyReport::build(r.level.clone().into(), file_name, r.loc.start)
+        let (name, loc) = locate(r);
+        PrettyReport::build(r.level.clone().into(), name.clone(), loc.start)
             .with_message(title)
             .with_label(
-                Label::new((file_name, r.loc.clone()))
+                Label::new((name, loc))
                     .with_message(r.message.clone())
                     .with_color(Color::Yellow),
             )
             .with_labels(notes)
             .with_note(r.note.clone())
             .finish()
-            .print((file_name, Source::from(content)))
+            .print(ariadne::sources(sources.clone()))
             .unwrap();
     }
 }
@@ -112,16 +439,76 @@ pub fn build_report(content: &str, diagnostics: &[Report], file_name: &str) {
 /// Execute the compilation stage using the runner.
 pub fn exec<I, O, W: Runner<I, O>>(
     input: &I,
-    contract_contents: &str,
-    file_name: &str,
+    project: &Project,
+    format: DiagnosticsFormat,
 ) -> Result<O> {
-    W::run(input).map_err(|e| {
-        let reports = e.diagnostics();
-        build_report(contract_contents, reports, file_name);
-        match e {
-            CompilationError::Syntax(_) => anyhow::anyhow!("Syntactical error occurred"),
-            CompilationError::Formal(_) => anyhow::anyhow!("Verification failed"),
-            CompilationError::Emit(_) => anyhow::anyhow!("Compilation failed"),
-        }
-    })
+    W::run(input).map_err(|e| report_error(e, project, format))
+}
+
+/// Report the non-fatal diagnostics of the resolved contract.
+///
+/// # Errors
+/// If any were reported and warnings are denied.
+pub fn report_warnings(
+    contract: &ContractDefinition,
+    project: &Project,
+    format: DiagnosticsFormat,
+    deny_warnings: bool,
+) -> Result<()> {
+    if contract.diagnostics.is_empty() {
+        return Ok(());
+    }
+    build_report(project, &contract.diagnostics, format);
+    if deny_warnings {
+        anyhow::bail!("Warnings are denied")
+    }
+    Ok(())
+}
+
+/// Execute the verification stage reusing the cached results for unchanged declarations.
+///
+/// # Returns
+/// - Outcome of the verification.
+///
+/// # Errors
+/// If the verification fails, or warnings were reported and they are denied.
+pub fn exec_verify(
+    contract: &ContractDefinition,
+    project: &Project,
+    format: DiagnosticsFormat,
+    config: &SolverConfig,
+    cache: &mut Cache,
+    deny_warnings: bool,
+) -> Result<Verification> {
+    let verification = verify_incremental(contract, config, |c| cache.is_verified(c, contract))
+        .map_err(|e| report_error(e, project, format))?;
+    if !verification.warnings.is_empty() {
+        build_report(project, &verification.warnings, format);
+    }
+    cache.set_verified(&verification.components, contract);
+    // keep reporting the warnings until they are resolved.
+    if contract.diagnostics.is_empty() && verification.warnings.is_empty() {
+        cache.set_contract_verified();
+    }
+    cache.save()?;
+    if deny_warnings && !verification.warnings.is_empty() {
+        anyhow::bail!("Warnings are denied")
+    }
+    Ok(verification)
+}
+
+/// Report diagnostics of the failed stage and convert the error.
+pub fn report_error(
+    e: CompilationError,
+    project: &Project,
+    format: DiagnosticsFormat,
+) -> anyhow::Error {
+    let reports = e.diagnostics();
+    build_report(project, reports, format);
+    match e {
+        CompilationError::Syntax(_) => anyhow::anyhow!("Syntactical error occurred"),
+        CompilationError::Formal(_) => anyhow::anyhow!("Verification failed"),
+        CompilationError::Emit(_) => anyhow::anyhow!("Compilation failed"),
+        CompilationError::Simulation(_) => anyhow::anyhow!("Simulation failed"),
+    }
 }
@@ -11,9 +11,15 @@ use std::{
 use walkdir::WalkDir;
 
 use clap::Args;
+use folidity_verifier::DEFAULT_TIMEOUT_MS;
+
+use crate::manifest::{
+    DEFAULT_ENTRY,
+    MANIFEST_FILE,
+};
 
 /// Creates a new templated `folidity` counter project.
-/// with a basic contract, README and approval teal code.
+/// with a basic contract, README and the `folidity.toml` manifest.
 #[derive(Args)]
 pub struct NewCommand {
     /// Path to the new project.
@@ -49,12 +55,23 @@ impl NewCommand {
         let contract_content = include_bytes!("../../../../examples/counter/contract.fol");
         let readme_content = include_bytes!("../../../../examples/counter/README.md");
 
-        let mut contract_file = File::create(Path::new(&out_dir).join("contract.fol"))?;
+        let mut contract_file = File::create(Path::new(&out_dir).join(DEFAULT_ENTRY))?;
         contract_file.write_all(contract_content)?;
 
         let mut readme_file = File::create(Path::new(&out_dir).join("README.md"))?;
         readme_file.write_all(readme_content)?;
 
+        let name = out_path
+            .canonicalize()?
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("counter"));
+        let manifest_content = format!(
+            "[project]\nname = \"{name}\"\n\n[[contract]]\nname = \"{name}\"\nentry = \"{DEFAULT_ENTRY}\"\n\n[verifier]\ntimeout-ms = {DEFAULT_TIMEOUT_MS}\n\n[build]\nout-dir = \"build\"\ntarget = \"teal\"\n"
+        );
+        let mut manifest_file = File::create(Path::new(&out_dir).join(MANIFEST_FILE))?;
+        manifest_file.write_all(manifest_content.as_bytes())?;
+
         Ok(())
     }
 }
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use clap::Args;
+use folidity_interpreter::Session;
+use folidity_semantics::modules::{
+    Module,
+    Project,
+};
+use std::{
+    io::{
+        self,
+        BufRead,
+        Write,
+    },
+    path::PathBuf,
+};
+use yansi::Paint;
+
+use super::{
+    build_report,
+    DiagnosticsFormat,
+};
+
+/// Evaluate expressions, statements and declarations interactively.
+///
+/// Enter `:reset` to clear the session, and `:quit` to exit.
+#[derive(Args)]
+pub struct ReplCommand {}
+
+impl ReplCommand {
+    pub fn run(&self) -> Result<()> {
+        println!(
+            "{}",
+            "Folidity REPL. Enter `:reset` to clear the session, `:quit` to exit.".bold()
+        );
+
+        let mut session = Session::default();
+        let mut lines = io::stdin().lock().lines();
+        loop {
+            let Some(input) = read_input(&mut lines)? else {
+                break;
+            };
+            match input.trim() {
+                "" => continue,
+                ":quit" | ":q" => break,
+                ":reset" => {
+                    session = Session::default();
+                    continue;
+                }
+                _ => {}
+            }
+
+            match session.eval(&input) {
+                Ok(Some(e)) => println!("{}: {}", e.value, e.ty.cyan()),
+                Ok(None) => {}
+                Err(errors) => {
+                    let input = input.trim().to_string();
+                    let project = Project {
+                        modules: vec![Module {
+                            path: PathBuf::from("<repl>"),
+                            contents: input.clone(),
+                            offset: 0,
+                        }],
+                        contents: input,
+                        diagnostics: vec![],
+                    };
+                    build_report(&project, &errors, DiagnosticsFormat::Pretty);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Read the input, continuing on the next lines until the braces are balanced.
+fn read_input(lines: &mut impl Iterator<Item = io::Result<String>>) -> Result<Option<String>> {
+    let mut input = String::new();
+    let mut prompt = "> ";
+    loop {
+        print!("{}", prompt.green());
+        io::stdout().flush()?;
+        let Some(line) = lines.next() else {
+            return Ok(None);
+        };
+        input.push_str(&line?);
+        input.push('\n');
+
+        let depth = input.matches('{').count() as i64 - input.matches('}').count() as i64;
+        if depth <= 0 {
+            return Ok(Some(input));
+        }
+        prompt = "| ";
+    }
+}
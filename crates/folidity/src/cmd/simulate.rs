@@ -0,0 +1,130 @@
+use anyhow::{
+    Context,
+    Result,
+};
+use folidity_interpreter::{
+    Scenario,
+    SimulationReport,
+    Simulator,
+};
+use folidity_semantics::ContractDefinition;
+use std::{
+    ffi::OsString,
+    fs,
+    path::PathBuf,
+};
+use yansi::Paint;
+
+use clap::Args;
+
+use super::{
+    build_report,
+    exec,
+    read_contract,
+    report_warnings,
+    resolve_contracts,
+    ContractInput,
+    DiagnosticsFormat,
+};
+
+/// Simulate the calls of the contract off-chain.
+///
+/// The scenario is a JSON file listing the calls,
+/// e.g. `{ "steps": [{ "call": "incr_by", "args": [150], "fails": true }] }`.
+/// The state of the contract is kept between the calls, and the bounds are checked
+/// at runtime. Without the scenario, the constructor of the contract is called.
+#[derive(Args)]
+pub struct SimulateCommand {
+    /// Contract's file name or the project directory.
+    #[clap(value_parser, default_value = ".")]
+    contract: OsString,
+    /// Scenario of the calls in JSON.
+    #[clap(short, long)]
+    scenario: Option<PathBuf>,
+    /// Output format of the diagnostics.
+    #[clap(long, aliases = ["message-format", "diagnostics"], value_enum, default_value_t = DiagnosticsFormat::Pretty)]
+    format: DiagnosticsFormat,
+}
+
+impl SimulateCommand {
+    pub fn run(&self) -> Result<()> {
+        self.format.setup();
+        let inputs = resolve_contracts(&self.contract)?;
+        if self.scenario.is_some() && inputs.len() > 1 {
+            anyhow::bail!("Scenario can only be simulated for a single contract.")
+        }
+        for input in &inputs {
+            if inputs.len() > 1 {
+                println!("{}: {}", "Contract".bold().cyan(), input.name);
+            }
+            self.simulate(input)?;
+        }
+        Ok(())
+    }
+
+    fn simulate(&self, input: &ContractInput) -> Result<()> {
+        let project = read_contract(&input.path)?;
+        let tree = match project.parse() {
+            Ok(tree) => tree,
+            Err(errors) => {
+                build_report(&project, &errors, self.format);
+                anyhow::bail!("Error during parsing")
+            }
+        };
+        let contract = exec::<_, _, ContractDefinition>(&tree, &project, self.format)?;
+        report_warnings(&contract, &project, self.format, false)?;
+
+        let Some(path) = &self.scenario else {
+            let report = exec::<_, _, Simulator>(&contract, &project, self.format)?;
+            print_report(&report);
+            return Ok(());
+        };
+
+        let contents = fs::read_to_string(path).context("Failed to read the scenario")?;
+        let scenario: Scenario =
+            serde_json::from_str(&contents).context("Scenario is not valid")?;
+        let report = Simulator::new(&contract).simulate(&scenario);
+        print_report(&report);
+
+        let errors: Vec<_> = report
+            .steps
+            .iter()
+            .filter(|s| !s.passed())
+            .flat_map(|s| s.errors.clone())
+            .collect();
+        build_report(&project, &errors, self.format);
+        if !report.passed() {
+            anyhow::bail!("Simulation failed")
+        }
+        Ok(())
+    }
+}
+
+/// Print the outcomes of the calls and the final state of the contract.
+fn print_report(report: &SimulationReport) {
+    for step in &report.steps {
+        let status = if step.passed() {
+            "ok".green().bold()
+        } else {
+            "failed".red().bold()
+        };
+        match &step.returned {
+            Some(value) => println!("{} {} = {}", status, step.call, value),
+            None => println!("{} {}", status, step.call),
+        }
+        if let Some(mismatch) = &step.mismatch {
+            println!("   {}", mismatch.yellow());
+        } else if let Some(e) = step.errors.first() {
+            println!("   {} {}", "reverted:".dim(), e.message.dim());
+        }
+    }
+
+    let state = report.state.as_deref().unwrap_or("none");
+    println!("{}: {}", "State".bold().cyan(), state);
+    let passed = report.steps.iter().filter(|s| s.passed()).count();
+    println!(
+        "{} of {} calls met the expectations.",
+        passed,
+        report.steps.len()
+    );
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use folidity_interpreter::{
+    TestReport,
+    TestRunner,
+};
+use folidity_semantics::ContractDefinition;
+use std::ffi::OsString;
+use yansi::Paint;
+
+use clap::Args;
+
+use super::{
+    build_report,
+    exec,
+    read_contract,
+    report_warnings,
+    resolve_contracts,
+    ContractInput,
+    DiagnosticsFormat,
+};
+
+/// Run the tests of the contract off-chain.
+///
+/// The tests are declared in the contract, e.g. `test "increments" { ... }`,
+/// and can call the functions, move the contract to a state and `assert` the conditions.
+#[derive(Args)]
+pub struct TestCommand {
+    /// Contract's file name or the project directory.
+    #[clap(value_parser, default_value = ".")]
+    contract: OsString,
+    /// Run only the tests whose names contain the filter.
+    #[clap(short, long)]
+    filter: Option<String>,
+    /// Output format of the diagnostics.
+    #[clap(long, aliases = ["message-format", "diagnostics"], value_enum, default_value_t = DiagnosticsFormat::Pretty)]
+    format: DiagnosticsFormat,
+}
+
+impl TestCommand {
+    pub fn run(&self) -> Result<()> {
+        self.format.setup();
+        let inputs = resolve_contracts(&self.contract)?;
+        let mut failed = false;
+        for input in &inputs {
+            if inputs.len() > 1 {
+                println!("{}: {}", "Contract".bold().cyan(), input.name);
+            }
+            failed |= !self.test(input)?;
+        }
+        if failed {
+            anyhow::bail!("Tests failed")
+        }
+        Ok(())
+    }
+
+    /// Run the tests of the contract.
+    ///
+    /// # Returns
+    /// Whether all tests passed.
+    fn test(&self, input: &ContractInput) -> Result<bool> {
+        let project = read_contract(&input.path)?;
+        let tree = match project.parse() {
+            Ok(tree) => tree,
+            Err(errors) => {
+                build_report(&project, &errors, self.format);
+                anyhow::bail!("Error during parsing")
+            }
+        };
+        let contract = exec::<_, _, ContractDefinition>(&tree, &project, self.format)?;
+        report_warnings(&contract, &project, self.format, false)?;
+
+        let reports = TestRunner::new(&contract).run(self.filter.as_deref());
+        print_reports(&reports);
+
+        let errors: Vec<_> = reports.iter().flat_map(|r| r.errors.clone()).collect();
+        build_report(&project, &errors, self.format);
+        Ok(reports.iter().all(TestReport::passed))
+    }
+}
+
+/// Print the outcomes of the tests.
+fn print_reports(reports: &[TestReport]) {
+    for report in reports {
+        let status = if report.passed() {
+            "ok".green().bold()
+        } else {
+            "failed".red().bold()
+        };
+        println!("{} test \"{}\"", status, report.name);
+        if let Some(e) = report.errors.first() {
+            println!("   {}", e.message.yellow());
+        }
+    }
+
+    let passed = reports.iter().filter(|r| r.passed()).count();
+    println!("{} of {} tests passed.", passed, reports.len());
+}
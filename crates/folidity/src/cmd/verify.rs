@@ -1,42 +1,133 @@
-use anyhow::{
-    Context,
-    Result,
+use anyhow::Result;
+use folidity_semantics::{
+    modules::Project,
+    ContractDefinition,
+};
+use folidity_verifier::{
+    generate_examples,
+    DeclarationExamples,
+    SolverConfig,
+    DEFAULT_EXAMPLES,
 };
-use folidity_parser::parse;
-use folidity_semantics::ContractDefinition;
-use folidity_verifier::SymbolicExecutor;
 use std::ffi::OsString;
 use yansi::Paint;
 
 use clap::Args;
 
+use crate::cache::Cache;
+
 use super::{
     build_report,
     exec,
+    exec_verify,
     read_contract,
+    report_error,
+    report_warnings,
+    resolve_contracts,
+    ContractInput,
+    DiagnosticsFormat,
 };
 
 /// Check the contract's code for errors
 /// and validate model consistency using static analysis and symbolic execution.
 #[derive(Args)]
 pub struct VerifyCommand {
-    /// Contract's file name
-    #[clap(value_parser)]
+    /// Contract's file name or the project directory.
+    #[clap(value_parser, default_value = ".")]
     contract: OsString,
+    /// Output format of the diagnostics.
+    #[clap(long, aliases = ["message-format", "diagnostics"], value_enum, default_value_t = DiagnosticsFormat::Pretty)]
+    format: DiagnosticsFormat,
+    /// Do not reuse the results of previous runs.
+    #[clap(long)]
+    no_cache: bool,
+    /// Treat warnings as errors.
+    #[clap(long)]
+    deny_warnings: bool,
+    /// Print valid and invalid instances of the models and states sampled from their
+    /// bounds.
+    #[clap(long)]
+    examples: bool,
+    /// Timeout for constraint solving in milliseconds, overrides the one of the project.
+    #[clap(long)]
+    timeout: Option<u64>,
+    /// Seed of the random choices made by the solver.
+    #[clap(long)]
+    seed: Option<u32>,
+    /// Z3 tactic to build the solvers from, e.g. `qfnia`.
+    #[clap(long)]
+    tactic: Option<String>,
+    /// Solve the constraints in parallel.
+    #[clap(long)]
+    parallel: bool,
+    /// Warn about the arithmetic that may overflow the 64-bit integers of the AVM.
+    #[clap(long)]
+    check_overflow: bool,
+    /// Warn about the indices, slices and insertions that may be out of bounds
+    /// of their lists, and the lists that may be empty when popped.
+    #[clap(long)]
+    check_bounds: bool,
 }
 
 impl VerifyCommand {
     pub fn run(&self) -> Result<()> {
-        let contract_contents = read_contract(&self.contract)?;
-        let parse_result = parse(&contract_contents);
-        let file_name = self.contract.to_str().context("Invalid filename")?;
+        self.format.setup();
+        let inputs = resolve_contracts(&self.contract)?;
+        for input in &inputs {
+            if inputs.len() > 1 {
+                status!(self.format, "{}: {}", "Contract".bold().cyan(), input.name);
+            }
+            self.verify(input)?;
+        }
+        Ok(())
+    }
+
+    fn verify(&self, input: &ContractInput) -> Result<()> {
+        let project = read_contract(&input.path)?;
+        let config = self.solver_config(input);
+        let parse_result = project.parse();
         match parse_result {
             Ok(tree) => {
-                let contract =
-                    exec::<_, _, ContractDefinition>(&tree, &contract_contents, file_name)?;
-
-                exec::<_, _, SymbolicExecutor>(&contract, &contract_contents, file_name)?;
-                println!(
+                // the cached results are not checked for the overflow and the bounds.
+                let mut cache = Cache::load(
+                    project.entry(),
+                    &tree,
+                    &project.contents,
+                    !self.no_cache && !self.check_overflow && !self.check_bounds,
+                );
+                if cache.is_contract_verified() && !self.examples {
+                    status!(
+                        self.format,
+                        "{}",
+                        "Program model is consistent and has satisfiable constraints."
+                            .green()
+                            .bold()
+                    );
+                    return Ok(());
+                }
+                let contract = exec::<_, _, ContractDefinition>(&tree, &project, self.format)?;
+                report_warnings(&contract, &project, self.format, self.deny_warnings)?;
+                // keep reporting the warnings until they are resolved.
+                if contract.diagnostics.is_empty() {
+                    cache.set_checked();
+                }
+                exec_verify(
+                    &contract,
+                    &project,
+                    self.format,
+                    &config,
+                    &mut cache,
+                    self.deny_warnings,
+                )?;
+                if self.examples {
+                    let examples = generate_examples(&contract, DEFAULT_EXAMPLES, &config)
+                        .map_err(|e| report_error(e, &project, self.format))?;
+                    for e in &examples {
+                        print_examples(e, &project, self.format);
+                    }
+                }
+                status!(
+                    self.format,
                     "{}",
                     "Program model is consistent and has satisfiable constraints."
                         .green()
@@ -45,13 +136,65 @@ impl VerifyCommand {
                 Ok(())
             }
             Err(errors) => {
-                build_report(
-                    &contract_contents,
-                    &errors,
-                    self.contract.to_str().expect("Valid path name."),
-                );
+                build_report(&project, &errors, self.format);
                 anyhow::bail!("Error during parsing")
             }
         }
     }
+
+    /// Settings of the solver given by the options, falling back to the ones of the
+    /// project.
+    fn solver_config(&self, input: &ContractInput) -> SolverConfig {
+        SolverConfig {
+            timeout_ms: self.timeout.unwrap_or(input.timeout_ms),
+            seed: self.seed,
+            tactic: self.tactic.clone(),
+            parallel: self.parallel,
+            check_overflow: self.check_overflow,
+            check_bounds: self.check_bounds,
+        }
+    }
+}
+
+/// Print the sampled instances of the declaration as a table,
+/// invalid instances are annotated with the constraint they violate.
+fn print_examples(examples: &DeclarationExamples, project: &Project, format: DiagnosticsFormat) {
+    let mut rows: Vec<Vec<String>> = vec![];
+    for e in examples.valid.iter().chain(&examples.invalid) {
+        let outcome = match &e.violates {
+            Some(loc) => {
+                let constraint = project.contents.get(loc.clone()).unwrap_or_default();
+                format!("violates `{}`", constraint.trim())
+            }
+            None => String::from("valid"),
+        };
+        let mut row = e.values.clone();
+        row.push(outcome);
+        rows.push(row);
+    }
+
+    let mut header = examples.fields.clone();
+    header.push(String::new());
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .chain([&header])
+                .map(|r| r.get(i).map_or(0, |v| v.chars().count()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let line = |row: &[String]| -> String {
+        row.iter()
+            .zip(&widths)
+            .map(|(v, w)| format!("{:<w$}", v, w = w))
+            .collect::<Vec<String>>()
+            .join(" | ")
+    };
+
+    status!(format, "{}", examples.name.bold().cyan());
+    status!(format, "  {}", line(&header).bold());
+    for row in &rows {
+        status!(format, "  {}", line(row));
+    }
 }
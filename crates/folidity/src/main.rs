@@ -5,7 +5,9 @@ use yansi::{
     Paint,
 };
 
+mod cache;
 mod cmd;
+mod manifest;
 
 #[derive(Parser)]
 #[command(author = env!("CARGO_PKG_AUTHORS"), version = concat!("version ", env!("CARGO_PKG_VERSION")), about = env!("CARGO_PKG_DESCRIPTION"), subcommand_required = true)]
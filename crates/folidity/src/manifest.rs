@@ -0,0 +1,134 @@
+//! Project manifest.
+//!
+//! `folidity.toml` in the project directory describes the contracts of the project,
+//! and the settings of the verifier and the build:
+//!
+//! ```toml
+//! [project]
+//! name = "counter"
+//!
+//! [[contract]]
+//! name = "counter"
+//! entry = "contract.fol"
+//!
+//! [verifier]
+//! timeout-ms = 10000
+//!
+//! [build]
+//! out-dir = "build"
+//! target = "teal"
+//! ```
+
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use anyhow::{
+    Context,
+    Result,
+};
+use folidity_verifier::DEFAULT_TIMEOUT_MS;
+use serde::Deserialize;
+
+use crate::cmd::Target;
+
+/// Name of the manifest file.
+pub const MANIFEST_FILE: &str = "folidity.toml";
+/// Entry file of the project without the manifest.
+pub const DEFAULT_ENTRY: &str = "contract.fol";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Manifest {
+    /// Project metadata.
+    pub project: ProjectInfo,
+    /// Contracts of the project.
+    /// Defaults to `contract.fol` named after the project.
+    #[serde(default, rename = "contract")]
+    pub contracts: Vec<ContractEntry>,
+    /// Settings of the verifier.
+    #[serde(default)]
+    pub verifier: VerifierSettings,
+    /// Settings of the compilation.
+    #[serde(default)]
+    pub build: BuildSettings,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProjectInfo {
+    /// Name of the project.
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ContractEntry {
+    /// Name of the contract.
+    pub name: String,
+    /// Entry file of the contract relative to the project directory.
+    pub entry: PathBuf,
+    /// Target platform overriding the one of the project.
+    pub target: Option<Target>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct VerifierSettings {
+    /// Timeout for constraint solving in milliseconds.
+    #[serde(default = "default_timeout")]
+    pub timeout_ms: u64,
+}
+
+impl Default for VerifierSettings {
+    fn default() -> Self {
+        Self {
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct BuildSettings {
+    /// Directory of the compiled artifacts relative to the project directory.
+    /// Defaults to `build`.
+    pub out_dir: Option<PathBuf>,
+    /// Target platform of the contracts.
+    #[serde(default)]
+    pub target: Target,
+}
+
+fn default_timeout() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+impl Manifest {
+    /// Load the manifest from the project directory, if there is one.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path).context("Failed to read the manifest")?;
+        let manifest: Manifest = toml::from_str(&contents)
+            .with_context(|| format!("Invalid manifest {}", path.display()))?;
+        Ok(Some(manifest))
+    }
+
+    /// Contracts of the project, falling back to `contract.fol` named after the project.
+    pub fn contracts(&self) -> Vec<ContractEntry> {
+        if !self.contracts.is_empty() {
+            return self.contracts.clone();
+        }
+        vec![ContractEntry {
+            name: self.project.name.clone(),
+            entry: PathBuf::from(DEFAULT_ENTRY),
+            target: None,
+        }]
+    }
+}
@@ -0,0 +1,337 @@
+use algonaut_core::Address;
+use folidity_diagnostics::Span;
+use folidity_semantics::{
+    ast::{
+        BuiltinCall,
+        Lambda,
+        TypeVariant,
+    },
+    builtin::{
+        float_pow,
+        float_sqrt,
+        int_pow,
+        BuiltinFunction,
+    },
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::Signed;
+
+use crate::{
+    Frame,
+    Interpreter,
+    Value,
+};
+
+impl<'a> Interpreter<'a> {
+    pub(crate) fn builtin_call(
+        &mut self,
+        f: &BuiltinCall,
+        frame: &mut Frame<'a>,
+    ) -> Result<Value, ()> {
+        match f.func {
+            BuiltinFunction::Caller
+            | BuiltinFunction::CurrentBlock
+            | BuiltinFunction::Hash
+            | BuiltinFunction::Sha256
+            | BuiltinFunction::Keccak256
+            | BuiltinFunction::Ed25519Verify
+            | BuiltinFunction::Balance
+            | BuiltinFunction::Pay
+            | BuiltinFunction::AssetTransfer => {
+                let value = match f.func {
+                    BuiltinFunction::Caller => self.caller.map(Value::Address),
+                    BuiltinFunction::CurrentBlock => self.block.map(|b| Value::Uint(b.into())),
+                    _ => None,
+                };
+                return match value {
+                    Some(value) => Ok(value),
+                    None => {
+                        self.error(
+                            &f.loc,
+                            format!("`{}` can not be evaluated outside of the chain.", f.func),
+                        )
+                    }
+                };
+            }
+            BuiltinFunction::Or if matches!(f.args[0].ty(), TypeVariant::Option(_)) => {
+                return match self.eval(&f.args[0], frame)? {
+                    Value::Option(Some(value)) => Ok(*value),
+                    _ => self.eval(&f.args[1], frame),
+                };
+            }
+            // failures of the value are discarded in favour of the fallback.
+            BuiltinFunction::Or => {
+                let reported = self.diagnostics.len();
+                return match self.eval(&f.args[0], frame) {
+                    Ok(value) => Ok(value),
+                    Err(_) => {
+                        self.diagnostics.truncate(reported);
+                        self.eval(&f.args[1], frame)
+                    }
+                };
+            }
+            _ => {}
+        }
+
+        let mut args = vec![];
+        for a in &f.args {
+            args.push(self.eval(a, frame)?);
+        }
+        let mut args = args.into_iter();
+        let first = args.next().unwrap_or(Value::Unit);
+        let mut next = || args.next().unwrap_or(Value::Unit);
+
+        match (f.func, first) {
+            (BuiltinFunction::Cast, Value::Hex(bytes)) if f.returns == TypeVariant::Address => {
+                match <[u8; 32]>::try_from(bytes.as_slice()) {
+                    Ok(bytes) => Ok(Value::Address(Address(bytes))),
+                    Err(_) => self.error(&f.loc, "Address must be 32 bytes long."),
+                }
+            }
+            (BuiltinFunction::Cast, Value::Address(a)) => Ok(Value::Hex(a.0.to_vec())),
+            (BuiltinFunction::Cast, Value::String(s)) => Ok(Value::Hex(s.into_bytes())),
+            (BuiltinFunction::Cast, value) => {
+                let Some(n) = value.as_integer() else {
+                    return self.error(&f.loc, "Only integers can be cast.");
+                };
+                self.integer(n, &f.returns, &f.loc)
+            }
+
+            (BuiltinFunction::Assert | BuiltinFunction::Require, Value::Bool(holds)) => {
+                match next() {
+                    _ if holds => Ok(Value::Unit),
+                    Value::String(message) => {
+                        self.error(f.args[0].loc(), format!("Assertion failed: {}", message))
+                    }
+                    _ => self.error(f.args[0].loc(), "Assertion failed."),
+                }
+            }
+            (BuiltinFunction::Length, Value::String(s)) => Ok(Value::Uint(s.len().into())),
+            (BuiltinFunction::Substring, Value::String(s)) => {
+                let (start, end) = (next(), next());
+                let range = index(&start).zip(index(&end));
+                match range.and_then(|(start, end)| s.get(start..end)) {
+                    Some(sub) => Ok(Value::String(sub.to_string())),
+                    None => self.error(&f.loc, "Substring is out of bounds of the string."),
+                }
+            }
+            (BuiltinFunction::StartsWith, Value::String(s)) => {
+                let Value::String(prefix) = next() else {
+                    unreachable!("resolved to string")
+                };
+                Ok(Value::Bool(s.starts_with(&prefix)))
+            }
+            (BuiltinFunction::Contains, Value::String(s)) => {
+                let Value::String(sub) = next() else {
+                    unreachable!("resolved to string")
+                };
+                Ok(Value::Bool(s.contains(&sub)))
+            }
+
+            (BuiltinFunction::Add, Value::Mapping(mut entries)) => {
+                let (key, value) = (next(), next());
+                match entries.iter_mut().find(|(k, _)| k == &key) {
+                    Some(entry) => entry.1 = value,
+                    None => entries.push((key, value)),
+                }
+                Ok(Value::Mapping(entries))
+            }
+            (BuiltinFunction::Get, Value::Mapping(entries)) => {
+                let key = next();
+                match entries.into_iter().find(|(k, _)| k == &key) {
+                    Some((_, value)) => Ok(value),
+                    None => self.error(&f.loc, "Key is not present in the mapping."),
+                }
+            }
+            (BuiltinFunction::Contains, Value::Mapping(entries)) => {
+                let key = next();
+                Ok(Value::Bool(entries.iter().any(|(k, _)| k == &key)))
+            }
+            (BuiltinFunction::Remove, Value::Mapping(mut entries)) => {
+                let key = next();
+                entries.retain(|(k, _)| k != &key);
+                Ok(Value::Mapping(entries))
+            }
+
+            (BuiltinFunction::Add | BuiltinFunction::Push, Value::List(mut items)) => {
+                let item = next();
+                let is_set = matches!(f.returns, TypeVariant::Set(_));
+                if !is_set || !items.contains(&item) {
+                    items.push(item);
+                }
+                Ok(Value::List(items))
+            }
+            (BuiltinFunction::Contains, Value::List(items)) => {
+                Ok(Value::Bool(items.contains(&next())))
+            }
+            // every occurrence of the item is removed.
+            (BuiltinFunction::Remove, Value::List(mut items)) => {
+                let item = next();
+                items.retain(|i| i != &item);
+                Ok(Value::List(items))
+            }
+            (BuiltinFunction::Pop, Value::List(mut items)) => {
+                match items.pop() {
+                    Some(_) => Ok(Value::List(items)),
+                    None => self.error(&f.loc, "Can not pop from the empty list."),
+                }
+            }
+            (BuiltinFunction::Insert, Value::List(mut items)) => {
+                let (at, item) = (next(), next());
+                match index(&at).filter(|i| *i <= items.len()) {
+                    Some(i) => {
+                        items.insert(i, item);
+                        Ok(Value::List(items))
+                    }
+                    None => {
+                        self.error(
+                            &f.loc,
+                            format!(
+                                "Index is out of bounds of the list of {} elements.",
+                                items.len()
+                            ),
+                        )
+                    }
+                }
+            }
+            (BuiltinFunction::Union, Value::List(mut items)) => {
+                let Value::List(other) = next() else {
+                    unreachable!("resolved to the collection")
+                };
+                let is_set = matches!(f.returns, TypeVariant::Set(_));
+                for item in other {
+                    if !is_set || !items.contains(&item) {
+                        items.push(item);
+                    }
+                }
+                Ok(Value::List(items))
+            }
+            (BuiltinFunction::Difference, Value::List(mut items)) => {
+                let Value::List(other) = next() else {
+                    unreachable!("resolved to the collection")
+                };
+                items.retain(|i| !other.contains(i));
+                Ok(Value::List(items))
+            }
+            (BuiltinFunction::Size, Value::List(items)) => Ok(Value::Uint(items.len().into())),
+            (BuiltinFunction::Map, Value::List(items)) => {
+                let Value::Lambda(l) = next() else {
+                    return self.error(&f.loc, "Expected a function.");
+                };
+                let mut mapped = vec![];
+                for item in items {
+                    mapped.push(self.apply(&l, vec![item], frame)?);
+                }
+                Ok(Value::List(mapped))
+            }
+            (BuiltinFunction::Filter, Value::List(items)) => {
+                let Value::Lambda(l) = next() else {
+                    return self.error(&f.loc, "Expected a function.");
+                };
+                let mut filtered = vec![];
+                for item in items {
+                    if self.apply(&l, vec![item.clone()], frame)? == Value::Bool(true) {
+                        filtered.push(item);
+                    }
+                }
+                Ok(Value::List(filtered))
+            }
+            // stops at the first element deciding the result.
+            (func @ (BuiltinFunction::All | BuiltinFunction::Exists), Value::List(items)) => {
+                let Value::Lambda(l) = next() else {
+                    return self.error(&f.loc, "Expected a function.");
+                };
+                let expected = func == BuiltinFunction::Exists;
+                for item in items {
+                    if (self.apply(&l, vec![item], frame)? == Value::Bool(true)) == expected {
+                        return Ok(Value::Bool(expected));
+                    }
+                }
+                Ok(Value::Bool(!expected))
+            }
+            (BuiltinFunction::Fold, Value::List(items)) => {
+                let mut acc = next();
+                let Value::Lambda(l) = next() else {
+                    return self.error(&f.loc, "Expected a function.");
+                };
+                for item in items {
+                    acc = self.apply(&l, vec![acc, item], frame)?;
+                }
+                Ok(acc)
+            }
+            (BuiltinFunction::Sum, Value::List(items)) => self.sum(items, &f.returns, &f.loc),
+            (BuiltinFunction::Pow, Value::Float(base)) => {
+                let Value::Uint(exp) = next() else {
+                    unreachable!("resolved to uint")
+                };
+                match float_pow(&base, &exp) {
+                    Some(n) => Ok(Value::Float(n)),
+                    None => self.error(&f.loc, "The exponent of the power is too large."),
+                }
+            }
+            (BuiltinFunction::Pow, base) => {
+                let (Some(base), Value::Uint(exp)) = (base.as_integer(), next()) else {
+                    unreachable!("resolved to integers")
+                };
+                match int_pow(&base, &exp) {
+                    Some(n) => self.integer(n, &f.returns, &f.loc),
+                    None => self.error(&f.loc, "The exponent of the power is too large."),
+                }
+            }
+            (BuiltinFunction::Sqrt, Value::Float(n)) => {
+                match float_sqrt(&n) {
+                    Some(root) => Ok(Value::Float(root)),
+                    None => self.error(&f.loc, "Square root of the negative number is undefined."),
+                }
+            }
+            (BuiltinFunction::Sqrt, value) => {
+                match value.as_integer().filter(|n| !n.is_negative()) {
+                    Some(n) => self.integer(n.sqrt(), &f.returns, &f.loc),
+                    None => self.error(&f.loc, "Square root of the negative number is undefined."),
+                }
+            }
+
+            (func, _) => {
+                self.error(
+                    &f.loc,
+                    format!("`{}` is not supported for this value.", func),
+                )
+            }
+        }
+    }
+
+    /// Apply the function to the arguments.
+    ///
+    /// Parameters of the lambda belong to the scope of the enclosing function,
+    /// so it is evaluated in the frame of the call site.
+    fn apply(&mut self, l: &Lambda, args: Vec<Value>, frame: &mut Frame<'a>) -> Result<Value, ()> {
+        for (pos, value) in l.params.iter().zip(args) {
+            frame.vars.insert(*pos, value);
+        }
+        self.eval(&l.body, frame)
+    }
+
+    fn sum(&mut self, items: Vec<Value>, ty: &TypeVariant, loc: &Span) -> Result<Value, ()> {
+        if ty == &TypeVariant::Float {
+            let mut total = BigRational::from_integer(BigInt::default());
+            for item in items {
+                if let Value::Float(n) = item {
+                    total += n;
+                }
+            }
+            return Ok(Value::Float(total));
+        }
+
+        let total: BigInt = items.iter().filter_map(Value::as_integer).sum();
+        self.integer(total, ty, loc)
+    }
+}
+
+/// Index of the string or the collection.
+pub(crate) fn index(value: &Value) -> Option<usize> {
+    match value {
+        Value::Uint(n) => usize::try_from(n.clone()).ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,372 @@
+use std::cmp::Ordering;
+
+use folidity_diagnostics::Span;
+use folidity_semantics::{
+    ast::{
+        BinaryExpression,
+        Bounds,
+        Expression,
+        Param,
+        StateBody,
+        StructInit,
+        TypeVariant,
+    },
+    symtable::Scope,
+};
+use num_bigint::BigInt;
+use num_traits::Zero;
+
+use crate::{
+    builtin::index,
+    Frame,
+    Interpreter,
+    Value,
+};
+
+impl<'a> Interpreter<'a> {
+    /// Evaluate the expression in the frame.
+    #[allow(clippy::result_unit_err)]
+    pub fn eval(&mut self, expr: &Expression, frame: &mut Frame<'a>) -> Result<Value, ()> {
+        match expr {
+            Expression::Variable(u) => {
+                match frame.vars.get(&u.element) {
+                    Some(value) => Ok(value.clone()),
+                    None => {
+                        self.unbound = true;
+                        self.error(
+                            &u.loc,
+                            "Variable has no value, it can not be evaluated outside of the chain.",
+                        )
+                    }
+                }
+            }
+
+            // literals
+            Expression::Int(u) => Ok(Value::Int(u.element.clone())),
+            Expression::UInt(u) => Ok(Value::Uint(u.element.clone())),
+            Expression::Float(u) => Ok(Value::Float(u.element.clone())),
+            Expression::Boolean(u) => Ok(Value::Bool(u.element)),
+            Expression::String(u) => Ok(Value::String(u.element.clone())),
+            Expression::Char(u) => Ok(Value::Char(u.element)),
+            Expression::Hex(u) => Ok(Value::Hex(u.element.clone())),
+            Expression::Address(u) => Ok(Value::Address(u.element)),
+            Expression::Enum(u) => Ok(Value::Enum(u.element, vec![])),
+
+            // operations
+            Expression::Add(b)
+            | Expression::Subtract(b)
+            | Expression::Multiply(b)
+            | Expression::Divide(b)
+            | Expression::Modulo(b) => {
+                let left = self.eval(&b.left, frame)?;
+                let right = self.eval(&b.right, frame)?;
+                self.arithmetic(expr, b, left, right)
+            }
+            Expression::Equal(b) => {
+                let left = self.eval(&b.left, frame)?;
+                let right = self.eval(&b.right, frame)?;
+                Ok(Value::Bool(equal(b.left.ty(), &left, &right)))
+            }
+            Expression::NotEqual(b) => {
+                let left = self.eval(&b.left, frame)?;
+                let right = self.eval(&b.right, frame)?;
+                Ok(Value::Bool(!equal(b.left.ty(), &left, &right)))
+            }
+            Expression::Greater(b) => self.compare(b, frame, |o| o == Ordering::Greater),
+            Expression::Less(b) => self.compare(b, frame, |o| o == Ordering::Less),
+            Expression::GreaterEq(b) => self.compare(b, frame, |o| o != Ordering::Less),
+            Expression::LessEq(b) => self.compare(b, frame, |o| o != Ordering::Greater),
+            Expression::In(b) => {
+                let item = self.eval(&b.left, frame)?;
+                match self.eval(&b.right, frame)? {
+                    Value::List(items) => Ok(Value::Bool(items.contains(&item))),
+                    Value::Mapping(entries) => {
+                        Ok(Value::Bool(entries.iter().any(|(k, _)| k == &item)))
+                    }
+                    _ => self.error(&b.loc, "Membership can only be checked in collections."),
+                }
+            }
+            Expression::Not(u) => {
+                let value = self.condition(&u.element, frame)?;
+                Ok(Value::Bool(!value))
+            }
+            Expression::Or(b) => {
+                let value = self.condition(&b.left, frame)? || self.condition(&b.right, frame)?;
+                Ok(Value::Bool(value))
+            }
+            Expression::And(b) => {
+                let value = self.condition(&b.left, frame)? && self.condition(&b.right, frame)?;
+                Ok(Value::Bool(value))
+            }
+            Expression::Conditional(c) => {
+                if self.condition(&c.condition, frame)? {
+                    self.eval(&c.then_expr, frame)
+                } else {
+                    self.eval(&c.else_expr, frame)
+                }
+            }
+
+            // complex
+            Expression::FunctionCall(f) => {
+                let mut args = vec![];
+                for a in &f.args {
+                    args.push(self.eval(a, frame)?);
+                }
+                self.call(f.sym.i, args, &f.loc)
+            }
+            Expression::BuiltinCall(f) => self.builtin_call(f, frame),
+            Expression::InterfaceCall(f) => {
+                self.unbound = true;
+                self.error(
+                    &f.loc,
+                    "Other applications can not be called outside of the chain.",
+                )
+            }
+            Expression::MemberAccess(m) => {
+                match self.eval(&m.expr, frame)? {
+                    Value::Struct(mut fields) if m.member.0 < fields.len() => {
+                        Ok(fields.swap_remove(m.member.0))
+                    }
+                    _ => self.error(&m.loc, "Value has no such member."),
+                }
+            }
+            Expression::StructInit(s) => self.struct_init(s, frame),
+            Expression::VariantInit(v) => {
+                let mut fields = vec![];
+                for a in &v.args {
+                    fields.push(self.eval(a, frame)?);
+                }
+                Ok(Value::Enum(v.variant, fields))
+            }
+            Expression::Lambda(l) => Ok(Value::Lambda(l.clone())),
+            Expression::List(u) => {
+                let mut items: Vec<Value> = vec![];
+                for e in &u.element {
+                    let item = self.eval(e, frame)?;
+                    // sets keep the first occurrence of the item.
+                    if matches!(u.ty, TypeVariant::Set(_)) && items.contains(&item) {
+                        continue;
+                    }
+                    items.push(item);
+                }
+                Ok(Value::List(items))
+            }
+            Expression::Tuple(u) => {
+                let mut items = vec![];
+                for e in &u.element {
+                    items.push(self.eval(e, frame)?);
+                }
+                Ok(Value::Struct(items))
+            }
+            Expression::Optional(u) => {
+                match &u.element {
+                    Some(e) => Ok(Value::Option(Some(Box::new(self.eval(e, frame)?)))),
+                    None => Ok(Value::Option(None)),
+                }
+            }
+            Expression::Index(i) => {
+                let Value::List(mut items) = self.eval(&i.expr, frame)? else {
+                    return self.error(&i.loc, "Only lists can be indexed.");
+                };
+                let len = items.len();
+                match index(&self.eval(&i.index, frame)?) {
+                    Some(n) if n < len => Ok(items.swap_remove(n)),
+                    _ => {
+                        self.error(
+                            &i.loc,
+                            format!("Index is out of bounds of the list of {} elements.", len),
+                        )
+                    }
+                }
+            }
+            Expression::Slice(s) => {
+                let Value::List(items) = self.eval(&s.expr, frame)? else {
+                    return self.error(&s.loc, "Only lists can be sliced.");
+                };
+                let len = items.len();
+                let from = match &s.from {
+                    Some(e) => index(&self.eval(e, frame)?),
+                    None => Some(0),
+                };
+                let to = match &s.to {
+                    Some(e) => index(&self.eval(e, frame)?),
+                    None => Some(len),
+                };
+                match (from, to) {
+                    (Some(from), Some(to)) if from <= to && to <= len => {
+                        Ok(Value::List(items[from..to].to_vec()))
+                    }
+                    _ => {
+                        self.error(
+                            &s.loc,
+                            format!("Slice is out of bounds of the list of {} elements.", len),
+                        )
+                    }
+                }
+            }
+        }
+    }
+
+    /// Evaluate the boolean expression.
+    pub(crate) fn condition(
+        &mut self,
+        expr: &Expression,
+        frame: &mut Frame<'a>,
+    ) -> Result<bool, ()> {
+        match self.eval(expr, frame)? {
+            Value::Bool(b) => Ok(b),
+            _ => self.error(expr.loc(), "Expression is not a boolean."),
+        }
+    }
+
+    /// Wrap the integer into the value of the type,
+    /// checking that it fits in the range of the type.
+    pub(crate) fn integer(&mut self, n: BigInt, ty: &TypeVariant, loc: &Span) -> Result<Value, ()> {
+        let (min, max) = ty.int_range().unwrap_or((None, None));
+        let below = min.is_some_and(|min| n < min);
+        let above = max.is_some_and(|max| n > max);
+        if below || above {
+            return self.error(
+                loc,
+                format!(
+                    "Value {} is out of range of {}.",
+                    n,
+                    ty.display(self.contract)
+                ),
+            );
+        }
+        if ty.is_signed() {
+            return Ok(Value::Int(n));
+        }
+        Ok(Value::Uint(
+            n.to_biguint().expect("unsigned types are non-negative"),
+        ))
+    }
+
+    fn arithmetic(
+        &mut self,
+        e: &Expression,
+        b: &BinaryExpression,
+        left: Value,
+        right: Value,
+    ) -> Result<Value, ()> {
+        match (left, right) {
+            (Value::String(l), Value::String(r)) => Ok(Value::String(l + &r)),
+            (Value::Float(l), Value::Float(r)) => {
+                let value = match e {
+                    Expression::Add(_) => l + r,
+                    Expression::Subtract(_) => l - r,
+                    Expression::Multiply(_) => l * r,
+                    Expression::Divide(_) if r.is_zero() => {
+                        return self.error(&b.loc, "Division by zero.")
+                    }
+                    Expression::Divide(_) => l / r,
+                    _ => return self.error(&b.loc, "Operation is not supported for floats."),
+                };
+                Ok(Value::Float(value))
+            }
+            (l, r) => {
+                let (Some(l), Some(r)) = (l.as_integer(), r.as_integer()) else {
+                    return self.error(&b.loc, "Operation is not supported for these values.");
+                };
+                let value = match e {
+                    Expression::Add(_) => l + r,
+                    Expression::Subtract(_) => l - r,
+                    Expression::Multiply(_) => l * r,
+                    Expression::Divide(_) | Expression::Modulo(_) if r.is_zero() => {
+                        return self.error(&b.loc, "Division by zero.")
+                    }
+                    Expression::Divide(_) => l / r,
+                    _ => l % r,
+                };
+                self.integer(value, &b.ty, &b.loc)
+            }
+        }
+    }
+
+    fn compare(
+        &mut self,
+        b: &BinaryExpression,
+        frame: &mut Frame<'a>,
+        holds: impl Fn(Ordering) -> bool,
+    ) -> Result<Value, ()> {
+        let left = self.eval(&b.left, frame)?;
+        let right = self.eval(&b.right, frame)?;
+        match left.compare(&right) {
+            Some(o) => Ok(Value::Bool(holds(o))),
+            None => self.error(&b.loc, "Values can not be compared."),
+        }
+    }
+
+    /// Instantiate the struct, the model or the state checking its bounds.
+    fn struct_init(&mut self, s: &StructInit, frame: &mut Frame<'a>) -> Result<Value, ()> {
+        let mut fields = vec![];
+        for a in &s.args {
+            fields.push(self.eval(a, frame)?);
+        }
+
+        let contract = self.contract;
+        let decl = match &s.ty {
+            TypeVariant::Model(sym) => Some(&contract.models[sym.i]),
+            TypeVariant::State(sym) => {
+                let state = &contract.states[sym.i];
+                match &state.body {
+                    Some(StateBody::Model(m)) => Some(&contract.models[m.i]),
+                    _ => {
+                        let params = state.fields(contract);
+                        self.check_bounds(&state.scope, &params, &state.bounds, &fields, s)?;
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        if let Some(model) = decl {
+            let params = model.fields(contract);
+            self.check_bounds(&model.scope, &params, &model.bounds, &fields, s)?;
+        }
+
+        Ok(Value::Struct(fields))
+    }
+
+    /// Evaluate the bounds of the declaration with the fields of the instance.
+    fn check_bounds(
+        &mut self,
+        scope: &'a Scope,
+        params: &[Param],
+        bounds: &'a Option<Bounds>,
+        fields: &[Value],
+        s: &StructInit,
+    ) -> Result<(), ()> {
+        let Some(bounds) = bounds else {
+            return Ok(());
+        };
+
+        let mut bounds_frame = Frame::new(scope);
+        for (p, value) in params.iter().zip(fields) {
+            if let Some((pos, _)) = scope.find_var_index(&p.name.name) {
+                bounds_frame.vars.insert(pos, value.clone());
+            }
+        }
+
+        for e in &bounds.exprs {
+            if !self.condition(e, &mut bounds_frame)? {
+                return self.error(
+                    &s.loc,
+                    format!("Instance of `{}` violates its bounds.", s.name.name),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare the values of the type, the elements of sets are unordered.
+fn equal(ty: &TypeVariant, left: &Value, right: &Value) -> bool {
+    match (ty, left, right) {
+        (TypeVariant::Set(_), Value::List(l), Value::List(r)) => {
+            l.len() == r.len() && l.iter().all(|item| r.contains(item))
+        }
+        _ => left == right,
+    }
+}
@@ -0,0 +1,263 @@
+//! Interpreter of the Folidity language.
+//!
+//! The interpreter walks the resolved expressions and statements of the contract,
+//! so the code can be evaluated without compiling and deploying it.
+//! The chain context, e.g. the current state and the caller, is provided by the
+//! [`Simulator`], evaluating it without one is reported as the runtime error.
+//! The tests declared in the contract are run by the [`TestRunner`].
+
+use std::collections::HashMap;
+
+use algonaut_core::Address;
+use folidity_diagnostics::{
+    Report,
+    Span,
+};
+use folidity_semantics::{
+    ast::{
+        Expression,
+        FuncReturnType,
+        Function,
+        FunctionVisibility,
+    },
+    symtable::Scope,
+    ContractDefinition,
+};
+
+pub use session::{
+    Evaluated,
+    Session,
+};
+pub use simulator::{
+    Scenario,
+    SimulationReport,
+    Simulator,
+    Step,
+    StepReport,
+};
+pub use testing::{
+    TestReport,
+    TestRunner,
+};
+pub use value::Value;
+
+mod builtin;
+mod expression;
+mod session;
+mod simulator;
+mod statement;
+mod testing;
+mod value;
+
+#[cfg(test)]
+mod tests;
+
+/// Max number of the statements executed in a single evaluation.
+pub const MAX_STEPS: usize = 1_000_000;
+/// Max depth of the nested function calls.
+pub const MAX_CALL_DEPTH: usize = 256;
+
+/// Variables of the function being executed.
+#[derive(Debug, Clone)]
+pub struct Frame<'a> {
+    /// Scope table of the function.
+    pub scope: &'a Scope,
+    /// Values of the variables by their indices in the scope table.
+    pub vars: HashMap<usize, Value>,
+    /// Function being executed.
+    pub func: Option<&'a Function>,
+    /// Bounds of the function whose variables have no values yet.
+    pub bounds: Vec<&'a Expression>,
+}
+
+impl<'a> Frame<'a> {
+    pub fn new(scope: &'a Scope) -> Self {
+        Self {
+            scope,
+            vars: HashMap::new(),
+            func: None,
+            bounds: vec![],
+        }
+    }
+}
+
+/// Tree-walking interpreter of the resolved contract.
+#[derive(Debug)]
+pub struct Interpreter<'a> {
+    /// Resolved contract definition.
+    pub contract: &'a ContractDefinition,
+    /// Errors occurred during the evaluation.
+    pub diagnostics: Vec<Report>,
+    /// Current state of the contract, its index and value.
+    pub state: Option<(usize, Value)>,
+    /// Sender of the transaction.
+    pub caller: Option<Address>,
+    /// Number of the current block.
+    pub block: Option<u64>,
+    /// Number of the executed statements.
+    steps: usize,
+    /// Depth of the current function call.
+    depth: usize,
+    /// Whether the variable without value has been evaluated.
+    unbound: bool,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(contract: &'a ContractDefinition) -> Self {
+        Self {
+            contract,
+            diagnostics: vec![],
+            state: None,
+            caller: None,
+            block: None,
+            steps: 0,
+            depth: 0,
+            unbound: false,
+        }
+    }
+
+    /// Call the function of the contract with the given arguments.
+    #[allow(clippy::result_unit_err)]
+    pub fn call(&mut self, func: usize, args: Vec<Value>, loc: &Span) -> Result<Value, ()> {
+        let contract = self.contract;
+        let func = &contract.functions[func];
+
+        if self.depth >= MAX_CALL_DEPTH {
+            return self.error(
+                loc,
+                format!("Exceeded the max call depth of {}.", MAX_CALL_DEPTH),
+            );
+        }
+
+        let mut frame = Frame::new(&func.scope);
+        for (name, value) in func.params.keys().zip(args) {
+            let (pos, _) = func
+                .scope
+                .find_var_index(name)
+                .expect("parameter is declared");
+            frame.vars.insert(pos, value);
+        }
+
+        self.depth += 1;
+        let result = self.execute(func, &mut frame);
+        self.depth -= 1;
+        result
+    }
+
+    /// Execute the body of the function in the frame.
+    ///
+    /// The state the function is called in and its access attributes are checked
+    /// before the body. Bounds of the function are checked as soon as the variables
+    /// they refer to are assigned, i.e. the parameters on entry,
+    /// the final state on the transition and the return parameter on return.
+    ///
+    /// # Returns
+    /// The returned value, or [`Value::Unit`] if the function returns nothing.
+    #[allow(clippy::result_unit_err)]
+    pub fn execute(&mut self, func: &'a Function, frame: &mut Frame<'a>) -> Result<Value, ()> {
+        frame.func = Some(func);
+        self.enter(func, frame)?;
+
+        let value = match self.block(&func.body, frame)? {
+            statement::Flow::Return(value) => value,
+            _ => Value::Unit,
+        };
+
+        if let FuncReturnType::ParamType(p) = &func.return_ty {
+            if let Some((pos, _)) = func.scope.find_var_index(&p.name.name) {
+                frame.vars.insert(pos, value.clone());
+            }
+        }
+        self.function_bounds(frame)?;
+        Ok(value)
+    }
+
+    /// Bind the state the function is called in, check the access of the caller
+    /// and the bounds of the parameters.
+    fn enter(&mut self, func: &'a Function, frame: &mut Frame<'a>) -> Result<(), ()> {
+        let from = func
+            .state_bound
+            .as_ref()
+            .and_then(|b| b.from.as_ref())
+            .map(|s| (&s.ty, s.name.as_ref()));
+        let view = match &func.vis {
+            FunctionVisibility::View(v) => Some((&v.ty, Some(&v.name))),
+            _ => None,
+        };
+        if let Some((ty, name)) = from.or(view) {
+            let value = match &self.state {
+                Some((i, value)) if *i == ty.i => value.clone(),
+                _ => {
+                    return self.error(
+                        &func.name.loc,
+                        format!(
+                            "`{}` can only be called in `{}` state.",
+                            func.name.name, self.contract.states[ty.i].name.name
+                        ),
+                    );
+                }
+            };
+            if let Some((pos, _)) = name.and_then(|n| func.scope.find_var_index(&n.name)) {
+                frame.vars.insert(pos, value);
+            }
+        }
+
+        if !func.access_attributes.is_empty() {
+            let Some(caller) = self.caller else {
+                return self.error(
+                    &func.name.loc,
+                    "Access of the caller can not be checked outside of the chain.",
+                );
+            };
+            let mut allowed = false;
+            for e in &func.access_attributes {
+                allowed |= match self.eval(e, frame)? {
+                    Value::Address(a) => a == caller,
+                    Value::List(items) => items.contains(&Value::Address(caller)),
+                    _ => false,
+                };
+            }
+            if !allowed {
+                return self.error(
+                    &func.name.loc,
+                    format!("Caller `{}` has no access to `{}`.", caller, func.name.name),
+                );
+            }
+        }
+
+        if let Some(bounds) = &func.bounds {
+            frame.bounds.extend(bounds.exprs.iter());
+        }
+        self.function_bounds(frame)
+    }
+
+    /// Check the bounds of the function whose variables have values,
+    /// the rest are kept until they are assigned.
+    pub(crate) fn function_bounds(&mut self, frame: &mut Frame<'a>) -> Result<(), ()> {
+        let pending = std::mem::take(&mut frame.bounds);
+        for e in pending {
+            let reported = self.diagnostics.len();
+            self.unbound = false;
+            match self.condition(e, frame) {
+                Ok(true) => {}
+                Ok(false) => {
+                    let name = frame.func.map(|f| f.name.name.as_str()).unwrap_or_default();
+                    return self.error(e.loc(), format!("Bound of `{}` is violated.", name));
+                }
+                Err(_) if self.unbound => {
+                    self.diagnostics.truncate(reported);
+                    frame.bounds.push(e);
+                }
+                Err(_) => return Err(()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Report the runtime error.
+    fn error<T>(&mut self, loc: &Span, message: impl Into<String>) -> Result<T, ()> {
+        self.diagnostics
+            .push(Report::runtime_error(loc.clone(), message.into()));
+        Err(())
+    }
+}
@@ -0,0 +1,191 @@
+//! Interactive evaluation session.
+//!
+//! Declarations and statements entered so far are kept as the source code.
+//! Every input is resolved in the body of the synthetic function following the
+//! declarations, and the function is interpreted from the start, so the variables keep
+//! their values between the inputs.
+
+use folidity_diagnostics::{
+    Level,
+    Report,
+    Span,
+};
+use folidity_parser::parse;
+use folidity_semantics::{
+    ast::Statement,
+    resolve_contract,
+};
+
+use crate::{
+    Frame,
+    Interpreter,
+};
+
+/// Name of the synthetic function evaluating the inputs.
+const SESSION_FN: &str = "__repl";
+/// Name of the variable the evaluated expression is bound to.
+const RESULT_VAR: &str = "__result";
+
+/// Value of the evaluated expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluated {
+    /// Value in the source syntax.
+    pub value: String,
+    /// Type of the value.
+    pub ty: String,
+}
+
+/// Kind of the input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Input {
+    /// Declaration of the contract, e.g. a function or a struct.
+    Declaration,
+    /// Statement of the function body.
+    Statement,
+    /// Expression to evaluate.
+    Expression,
+}
+
+impl Input {
+    fn of(input: &str) -> Self {
+        let word = input
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default();
+        match word {
+            "struct" | "enum" | "model" | "state" | "fn" => Input::Declaration,
+            _ if input.starts_with('@') => Input::Declaration,
+            "let" | "if" | "for" => Input::Statement,
+            _ if input.ends_with(';') => Input::Statement,
+            _ => Input::Expression,
+        }
+    }
+}
+
+/// Session of the REPL.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    /// Declarations entered so far.
+    declarations: Vec<String>,
+    /// Statements entered so far.
+    statements: Vec<String>,
+}
+
+impl Session {
+    /// Evaluate the input within the session.
+    ///
+    /// Declarations and statements are kept in the session if they are evaluated
+    /// successfully.
+    ///
+    /// # Returns
+    /// The value of the expression, or `None` for the declarations and the statements.
+    ///
+    /// # Errors
+    /// Errors of the input with locations relative to it.
+    pub fn eval(&mut self, input: &str) -> Result<Option<Evaluated>, Vec<Report>> {
+        let input = input.trim();
+        let kind = Input::of(input);
+
+        let mut declarations = self.declarations.clone();
+        let mut statements = self.statements.clone();
+        let mut src = String::new();
+        let offset;
+        match kind {
+            Input::Declaration => {
+                src.push_str(&declarations.join("\n"));
+                src.push('\n');
+                offset = src.len();
+                declarations.push(input.to_string());
+                src.push_str(input);
+                src.push_str(&format!("\nfn () {}() {{\n", SESSION_FN));
+                src.push_str(&statements.join("\n"));
+            }
+            _ => {
+                src.push_str(&declarations.join("\n"));
+                src.push_str(&format!("\nfn () {}() {{\n", SESSION_FN));
+                src.push_str(&statements.join("\n"));
+                src.push('\n');
+                if kind == Input::Expression {
+                    src.push_str(&format!("let {} = ", RESULT_VAR));
+                }
+                offset = src.len();
+                src.push_str(input);
+                if kind == Input::Expression {
+                    src.push(';');
+                } else {
+                    statements.push(input.to_string());
+                }
+            }
+        }
+        src.push_str("\n}\n");
+
+        let relative = |reports: Vec<Report>| -> Vec<Report> {
+            reports
+                .into_iter()
+                .map(|mut r| {
+                    r.loc = relative_span(&r.loc, offset, input.len());
+                    r
+                })
+                .collect()
+        };
+
+        let tree = parse(&src).map_err(relative)?;
+        let contract = resolve_contract(&tree);
+        let errors: Vec<Report> = contract
+            .diagnostics
+            .iter()
+            .filter(|r| r.level == Level::Error)
+            .cloned()
+            .collect();
+        if !errors.is_empty() {
+            return Err(relative(errors));
+        }
+
+        let func = contract
+            .functions
+            .iter()
+            .find(|f| f.name.name == SESSION_FN)
+            .expect("session function is declared");
+
+        let mut interpreter = Interpreter::new(&contract);
+        let mut frame = Frame::new(&func.scope);
+        if interpreter.execute(func, &mut frame).is_err() {
+            return Err(relative(interpreter.diagnostics));
+        }
+
+        self.declarations = declarations;
+        self.statements = statements;
+        if kind != Input::Expression {
+            return Ok(None);
+        }
+
+        let Some(Statement::Block(block)) = func.body.first() else {
+            return Ok(None);
+        };
+        let Some(Statement::Variable(result)) = block.statements.last() else {
+            return Ok(None);
+        };
+        let value = frame
+            .vars
+            .get(&result.pos)
+            .map(|v| v.display(&result.ty, &contract))
+            .unwrap_or_default();
+        Ok(Some(Evaluated {
+            value,
+            ty: result.ty.display(&contract),
+        }))
+    }
+}
+
+/// Location relative to the input,
+/// locations outside of it are mapped to the whole input.
+fn relative_span(loc: &Span, offset: usize, len: usize) -> Span {
+    if loc.start >= offset && loc.end <= offset + len {
+        Span {
+            start: loc.start - offset,
+            end: loc.end - offset,
+        }
+    } else {
+        Span { start: 0, end: len }
+    }
+}
@@ -0,0 +1,425 @@
+//! Off-chain simulation of the contract.
+//!
+//! The scenario is a sequence of calls of the contract functions.
+//! The state of the contract is carried between the calls, and the failed calls
+//! are reverted, as they would be on the chain.
+
+use std::str::FromStr;
+
+use algonaut_core::Address;
+use folidity_diagnostics::{
+    Report,
+    Span,
+};
+use folidity_semantics::{
+    ast::TypeVariant,
+    CompilationError,
+    ContractDefinition,
+    Runner,
+    SymbolInfo,
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use serde::Deserialize;
+use serde_json::Value as Json;
+
+use crate::{
+    Interpreter,
+    Value,
+};
+
+/// Sequence of the calls to simulate.
+///
+/// # Example
+/// ```json
+/// {
+///     "steps": [
+///         { "call": "initialise" },
+///         { "call": "incr_by", "args": [150], "state": "CounterState" },
+///         { "call": "decr_by", "args": [50], "fails": true },
+///         { "call": "get_value", "returns": "150" }
+///     ]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Scenario {
+    /// Address the calls are made from, the zero address by default.
+    #[serde(default)]
+    pub caller: Option<String>,
+    /// Calls in the order of execution.
+    pub steps: Vec<Step>,
+}
+
+/// Call of the contract function.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Step {
+    /// Name of the function.
+    pub call: String,
+    /// Arguments in JSON, e.g. objects for structs and `[key, value]` pairs for mappings.
+    #[serde(default)]
+    pub args: Vec<Json>,
+    /// Address the call is made from, overrides the caller of the scenario.
+    #[serde(default)]
+    pub caller: Option<String>,
+    /// Is the call expected to fail?
+    #[serde(default)]
+    pub fails: bool,
+    /// Expected returned value in the source syntax.
+    #[serde(default)]
+    pub returns: Option<String>,
+    /// Expected name of the state after the call.
+    #[serde(default)]
+    pub state: Option<String>,
+}
+
+impl Scenario {
+    /// Scenario calling the constructor of the contract without arguments.
+    pub fn init(contract: &ContractDefinition) -> Self {
+        let steps = contract
+            .functions
+            .iter()
+            .filter(|f| f.is_init)
+            .map(|f| {
+                Step {
+                    call: f.name.name.clone(),
+                    ..Default::default()
+                }
+            })
+            .collect();
+        Self {
+            caller: None,
+            steps,
+        }
+    }
+}
+
+/// Outcome of the simulated call.
+#[derive(Debug, Clone)]
+pub struct StepReport {
+    /// Call in the source syntax, e.g. `incr_by(150)`.
+    pub call: String,
+    /// Returned value of the successful call.
+    pub returned: Option<String>,
+    /// State of the contract after the call.
+    pub state: Option<String>,
+    /// Errors of the failed call.
+    pub errors: Vec<Report>,
+    /// Reason the call does not meet the expectations of the step.
+    pub mismatch: Option<String>,
+}
+
+impl StepReport {
+    /// Does the call meet the expectations of the step?
+    pub fn passed(&self) -> bool {
+        self.mismatch.is_none()
+    }
+}
+
+/// Outcome of the simulated scenario.
+#[derive(Debug, Clone, Default)]
+pub struct SimulationReport {
+    /// Outcomes of the steps in the order of execution.
+    pub steps: Vec<StepReport>,
+    /// Final state of the contract.
+    pub state: Option<String>,
+}
+
+impl SimulationReport {
+    /// Do all calls meet the expectations of their steps?
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(StepReport::passed)
+    }
+}
+
+/// Simulator of the contract keeping its state between the calls.
+#[derive(Debug)]
+pub struct Simulator<'a> {
+    /// Resolved contract definition.
+    contract: &'a ContractDefinition,
+    /// Current state of the contract, its index and value.
+    state: Option<(usize, Value)>,
+    /// Number of the current block, one block is produced per call.
+    block: u64,
+}
+
+impl<'a> Simulator<'a> {
+    pub fn new(contract: &'a ContractDefinition) -> Self {
+        Self {
+            contract,
+            state: None,
+            block: 0,
+        }
+    }
+
+    /// Simulate the calls of the scenario in order.
+    pub fn simulate(&mut self, scenario: &Scenario) -> SimulationReport {
+        let steps = scenario
+            .steps
+            .iter()
+            .map(|s| self.step(s, s.caller.as_ref().or(scenario.caller.as_ref())))
+            .collect();
+        SimulationReport {
+            steps,
+            state: self.state(),
+        }
+    }
+
+    /// Simulate the call, the state is kept only if the call succeeds.
+    pub fn step(&mut self, step: &Step, caller: Option<&String>) -> StepReport {
+        let mut report = StepReport {
+            call: format!("{}()", step.call),
+            returned: None,
+            state: None,
+            errors: vec![],
+            mismatch: None,
+        };
+        match self.call(step, caller, &mut report) {
+            Ok(()) => report.mismatch = expectations(step, &report, self.state_name()),
+            Err(message) => report.mismatch = Some(message),
+        }
+        report.state = self.state();
+        report
+    }
+
+    fn call(
+        &mut self,
+        step: &Step,
+        caller: Option<&String>,
+        report: &mut StepReport,
+    ) -> Result<(), String> {
+        let contract = self.contract;
+        let Some(i) = contract
+            .functions
+            .iter()
+            .position(|f| f.name.name == step.call)
+        else {
+            return Err(format!("Function `{}` is not declared.", step.call));
+        };
+        let func = &contract.functions[i];
+        if func.params.len() != step.args.len() {
+            return Err(format!(
+                "`{}` expects {} arguments, but {} are given.",
+                step.call,
+                func.params.len(),
+                step.args.len()
+            ));
+        }
+
+        let mut args = vec![];
+        for (p, json) in func.params.values().zip(&step.args) {
+            args.push(value(json, &p.ty.ty, contract)?);
+        }
+        let rendered: Vec<String> = func
+            .params
+            .values()
+            .zip(&args)
+            .map(|(p, v)| v.display(&p.ty.ty, contract))
+            .collect();
+        report.call = format!("{}({})", step.call, rendered.join(", "));
+
+        let caller = match caller {
+            Some(a) => {
+                Address::from_str(a).map_err(|_| format!("`{}` is not a valid address.", a))?
+            }
+            None => Address::new([0; 32]),
+        };
+
+        self.block += 1;
+        let mut interpreter = Interpreter::new(contract);
+        interpreter.state = self.state.clone();
+        interpreter.caller = Some(caller);
+        interpreter.block = Some(self.block);
+        match interpreter.call(i, args, &func.loc) {
+            Ok(value) => {
+                self.state = interpreter.state;
+                if value != Value::Unit {
+                    report.returned = Some(value.display(func.return_ty.ty(), contract));
+                }
+            }
+            Err(_) => report.errors = interpreter.diagnostics,
+        }
+        Ok(())
+    }
+
+    /// Name of the current state.
+    fn state_name(&self) -> Option<&str> {
+        self.state
+            .as_ref()
+            .map(|(i, _)| self.contract.states[*i].name.name.as_str())
+    }
+
+    /// Current state in the source syntax.
+    fn state(&self) -> Option<String> {
+        self.state.as_ref().map(|(i, value)| {
+            let decl = &self.contract.states[*i];
+            let ty = TypeVariant::State(SymbolInfo::new(decl.loc.clone(), *i));
+            value.display(&ty, self.contract)
+        })
+    }
+}
+
+impl<'a> Runner<ContractDefinition, SimulationReport> for Simulator<'a> {
+    /// Simulate the deployment of the contract, i.e. the call of its constructor.
+    fn run(source: &ContractDefinition) -> Result<SimulationReport, CompilationError>
+    where
+        Self: std::marker::Sized,
+    {
+        let report = Simulator::new(source).simulate(&Scenario::init(source));
+        if !report.passed() {
+            let errors = report
+                .steps
+                .iter()
+                .flat_map(|s| {
+                    if s.errors.is_empty() {
+                        let message = s.mismatch.clone().unwrap_or_default();
+                        vec![Report::runtime_error(Span::default(), message)]
+                    } else {
+                        s.errors.clone()
+                    }
+                })
+                .collect();
+            return Err(CompilationError::Simulation(errors));
+        }
+        Ok(report)
+    }
+}
+
+/// Reason the successful or failed call does not meet the expectations of the step.
+fn expectations(step: &Step, report: &StepReport, state: Option<&str>) -> Option<String> {
+    if let Some(e) = report.errors.first() {
+        return (!step.fails).then(|| format!("Call failed: {}", e.message));
+    }
+    if step.fails {
+        return Some(String::from("Call is expected to fail."));
+    }
+    if let Some(expected) = &step.returns {
+        let returned = report.returned.as_deref().unwrap_or("()");
+        if returned != expected {
+            return Some(format!(
+                "Expected `{}` to be returned, got `{}`.",
+                expected, returned
+            ));
+        }
+    }
+    if let Some(expected) = &step.state {
+        if state != Some(expected.as_str()) {
+            return Some(format!(
+                "Expected the contract to be in `{}` state, got `{}`.",
+                expected,
+                state.unwrap_or("none")
+            ));
+        }
+    }
+    None
+}
+
+/// Convert the JSON argument to the value of the type.
+fn value(json: &Json, ty: &TypeVariant, contract: &ContractDefinition) -> Result<Value, String> {
+    let invalid = || {
+        format!(
+            "`{}` is not a valid value of {}.",
+            json,
+            ty.display(contract)
+        )
+    };
+
+    let value = match (ty, json) {
+        (TypeVariant::Option(_), Json::Null) => Value::Option(None),
+        (TypeVariant::Option(item), json) => {
+            Value::Option(Some(Box::new(value(json, item, contract)?)))
+        }
+        (ty, Json::Number(_) | Json::String(_)) if ty.is_integer() => {
+            let n: BigInt = match json {
+                Json::String(s) => s.parse(),
+                _ => json.to_string().parse(),
+            }
+            .map_err(|_| invalid())?;
+            let (min, max) = ty.int_range().unwrap_or((None, None));
+            if min.is_some_and(|min| n < min) || max.is_some_and(|max| n > max) {
+                return Err(invalid());
+            }
+            if ty.is_signed() {
+                Value::Int(n)
+            } else {
+                Value::Uint(n.to_biguint().ok_or_else(invalid)?)
+            }
+        }
+        (TypeVariant::Float, Json::Number(n)) => {
+            Value::Float(
+                n.as_f64()
+                    .and_then(BigRational::from_float)
+                    .ok_or_else(invalid)?,
+            )
+        }
+        (TypeVariant::Bool, Json::Bool(b)) => Value::Bool(*b),
+        (TypeVariant::Char, Json::String(s)) if s.chars().count() == 1 => {
+            Value::Char(s.chars().next().unwrap_or_default())
+        }
+        (TypeVariant::String, Json::String(s)) => Value::String(s.clone()),
+        (TypeVariant::Hex, Json::String(s)) => Value::Hex(hex::decode(s).map_err(|_| invalid())?),
+        (TypeVariant::Address, Json::String(s)) => {
+            Value::Address(Address::from_str(s).map_err(|_| invalid())?)
+        }
+        (TypeVariant::Enum(sym), Json::String(s)) => {
+            let variant = s.rsplit('.').next().unwrap_or_default();
+            let (i, _, v) = contract.enums[sym.i]
+                .variants
+                .get_full(variant)
+                .ok_or_else(invalid)?;
+            // the payloads can not be given in the inputs.
+            if !v.fields.is_empty() {
+                return Err(invalid());
+            }
+            Value::Enum(i, vec![])
+        }
+        (TypeVariant::Tuple(tys), Json::Array(items)) if tys.len() == items.len() => {
+            let mut values = vec![];
+            for (ty, item) in tys.iter().zip(items) {
+                values.push(value(item, ty, contract)?);
+            }
+            Value::Struct(values)
+        }
+        (TypeVariant::List(item) | TypeVariant::Set(item), Json::Array(items)) => {
+            let mut values: Vec<Value> = vec![];
+            for i in items {
+                let v = value(i, item, contract)?;
+                if matches!(ty, TypeVariant::Set(_)) && values.contains(&v) {
+                    continue;
+                }
+                values.push(v);
+            }
+            Value::List(values)
+        }
+        (TypeVariant::Mapping(m), Json::Array(entries)) => {
+            let mut values = vec![];
+            for e in entries {
+                let Some([k, v]) = e.as_array().map(Vec::as_slice) else {
+                    return Err(invalid());
+                };
+                values.push((
+                    value(k, &m.from_ty, contract)?,
+                    value(v, &m.to_ty, contract)?,
+                ));
+            }
+            Value::Mapping(values)
+        }
+        (
+            TypeVariant::Struct(sym) | TypeVariant::Model(sym) | TypeVariant::State(sym),
+            Json::Object(object),
+        ) => {
+            let fields = match ty {
+                TypeVariant::Struct(_) => contract.structs[sym.i].fields.clone(),
+                TypeVariant::Model(_) => contract.models[sym.i].fields(contract),
+                _ => contract.states[sym.i].fields(contract),
+            };
+            let mut values = vec![];
+            for f in &fields {
+                let json = object.get(&f.name.name).ok_or_else(invalid)?;
+                values.push(value(json, &f.ty.ty, contract)?);
+            }
+            Value::Struct(values)
+        }
+        _ => return Err(invalid()),
+    };
+    Ok(value)
+}
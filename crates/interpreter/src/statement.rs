@@ -0,0 +1,219 @@
+use folidity_semantics::ast::{
+    Expression,
+    ForLoop,
+    IfElse,
+    Iterator,
+    Match,
+    Statement,
+    TypeVariant,
+    Variable,
+    WhileLoop,
+};
+
+use crate::{
+    Frame,
+    Interpreter,
+    Value,
+    MAX_STEPS,
+};
+
+/// Control flow after the executed statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Flow {
+    /// Continue with the next statement.
+    Next,
+    /// Skip to the next iteration of the loop.
+    Skip,
+    /// Leave the loop.
+    Break,
+    /// Return from the function.
+    Return(Value),
+}
+
+impl<'a> Interpreter<'a> {
+    /// Execute the statements until the control flow leaves the block.
+    pub(crate) fn block(&mut self, stmts: &[Statement], frame: &mut Frame<'a>) -> Result<Flow, ()> {
+        for stmt in stmts {
+            let flow = self.statement(stmt, frame)?;
+            if flow != Flow::Next {
+                return Ok(flow);
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn statement(&mut self, stmt: &Statement, frame: &mut Frame<'a>) -> Result<Flow, ()> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return self.error(
+                stmt.loc(),
+                format!("Exceeded the limit of {} executed statements.", MAX_STEPS),
+            );
+        }
+
+        match stmt {
+            Statement::Variable(var) => self.variable(var, frame).map(|_| Flow::Next),
+            Statement::Assign(a) => {
+                let value = self.eval(&a.value, frame)?;
+                let Some((last, path)) = a.fields.split_last() else {
+                    frame.vars.insert(a.pos, value);
+                    return Ok(Flow::Next);
+                };
+                let mut target = frame.vars.get_mut(&a.pos);
+                for i in path {
+                    target = match target {
+                        Some(Value::Struct(fields)) => fields.get_mut(*i),
+                        _ => None,
+                    };
+                }
+                match target {
+                    Some(Value::Struct(fields)) if *last < fields.len() => {
+                        fields[*last] = value;
+                        Ok(Flow::Next)
+                    }
+                    _ => self.error(&a.loc, "Value has no such member."),
+                }
+            }
+            Statement::IfElse(b) => self.if_else(b, frame),
+            Statement::ForLoop(l) => self.for_loop(l, frame),
+            Statement::While(l) => self.while_loop(l, frame),
+            Statement::Iterator(it) => self.iterator(it, frame),
+            Statement::Match(m) => self.match_(m, frame),
+            Statement::Return(r) => {
+                let value = match &r.expr {
+                    Some(e) => self.eval(e, frame)?,
+                    None => Value::Unit,
+                };
+                Ok(Flow::Return(value))
+            }
+            Statement::Expression(e) => self.eval(e, frame).map(|_| Flow::Next),
+            Statement::StateTransition(e) => self.state_transition(e, frame).map(|_| Flow::Next),
+            // the logs are not observed by the simulated calls.
+            Statement::Emit(e) => {
+                for a in &e.args {
+                    self.eval(a, frame)?;
+                }
+                Ok(Flow::Next)
+            }
+            Statement::Block(b) => self.block(&b.statements, frame),
+            Statement::Skip(_) => Ok(Flow::Skip),
+            Statement::Break(_) => Ok(Flow::Break),
+            Statement::Error(_) => unreachable!(),
+        }
+    }
+
+    fn variable(&mut self, var: &Variable, frame: &mut Frame<'a>) -> Result<(), ()> {
+        if let Some(e) = &var.value {
+            let value = self.eval(e, frame)?;
+            frame.vars.insert(var.pos, value);
+        }
+        Ok(())
+    }
+
+    /// Move the contract to the new state,
+    /// and check the bounds of the function referring to it.
+    fn state_transition(&mut self, e: &Expression, frame: &mut Frame<'a>) -> Result<(), ()> {
+        let value = self.eval(e, frame)?;
+        let TypeVariant::State(sym) = e.ty() else {
+            unreachable!("resolved to state")
+        };
+        self.state = Some((sym.i, value.clone()));
+
+        let Some(func) = frame.func else {
+            return Ok(());
+        };
+        let to = func
+            .state_bound
+            .iter()
+            .flat_map(|b| &b.to)
+            .find(|s| &s.ty == sym)
+            .and_then(|s| s.name.as_ref());
+        if let Some((pos, _)) = to.and_then(|n| func.scope.find_var_index(&n.name)) {
+            frame.vars.insert(pos, value);
+        }
+        self.function_bounds(frame)
+    }
+
+    fn if_else(&mut self, b: &IfElse, frame: &mut Frame<'a>) -> Result<Flow, ()> {
+        if self.condition(&b.condition, frame)? {
+            self.block(&b.body, frame)
+        } else {
+            self.block(&b.else_part, frame)
+        }
+    }
+
+    /// Execute the first arm matching the variant of the value,
+    /// binding the fields of its payload.
+    fn match_(&mut self, m: &Match, frame: &mut Frame<'a>) -> Result<Flow, ()> {
+        let (tag, payload) = match self.eval(&m.expr, frame)? {
+            Value::Enum(tag, payload) => (tag, payload),
+            Value::Option(None) => (0, vec![]),
+            Value::Option(Some(value)) => (1, vec![*value]),
+            _ => return self.error(&m.loc, "Only enums and options can be matched."),
+        };
+        let Some(arm) = m.arms.iter().find(|a| a.variant.is_none_or(|v| v == tag)) else {
+            return self.error(&m.loc, "No arm matches the value.");
+        };
+        for (field, pos) in &arm.bindings {
+            let Some(value) = payload.get(*field) else {
+                return self.error(&arm.loc, "Value has no such member.");
+            };
+            frame.vars.insert(*pos, value.clone());
+        }
+        self.block(&arm.body, frame)
+    }
+
+    /// Execute the body while the condition holds,
+    /// the loop variable is assigned the value of the incrementer after each iteration.
+    fn for_loop(&mut self, l: &ForLoop, frame: &mut Frame<'a>) -> Result<Flow, ()> {
+        self.variable(&l.var, frame)?;
+        while self.condition(&l.condition, frame)? {
+            match self.block(&l.body, frame)? {
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                Flow::Break => break,
+                Flow::Next | Flow::Skip => {}
+            }
+            let next = self.eval(&l.incrementer, frame)?;
+            frame.vars.insert(l.var.pos, next);
+        }
+        Ok(Flow::Next)
+    }
+
+    fn while_loop(&mut self, l: &WhileLoop, frame: &mut Frame<'a>) -> Result<Flow, ()> {
+        while self.condition(&l.condition, frame)? {
+            match self.block(&l.body, frame)? {
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                Flow::Break => break,
+                Flow::Next | Flow::Skip => {}
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn iterator(&mut self, it: &Iterator, frame: &mut Frame<'a>) -> Result<Flow, ()> {
+        let Value::List(items) = self.eval(&it.list, frame)? else {
+            return self.error(&it.loc, "Only lists and sets can be iterated over.");
+        };
+        let pos = it.names.first().and_then(|name| {
+            frame
+                .scope
+                .vars
+                .iter()
+                .find(|(_, v)| v.ident.loc == name.loc)
+                .map(|(i, _)| *i)
+        });
+        let Some(pos) = pos else {
+            return self.error(&it.loc, "Iterator variable is not declared.");
+        };
+
+        for item in items {
+            frame.vars.insert(pos, item);
+            match self.block(&it.body, frame)? {
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                Flow::Break => break,
+                Flow::Next | Flow::Skip => {}
+            }
+        }
+        Ok(Flow::Next)
+    }
+}
@@ -0,0 +1,78 @@
+//! Tests of the contract declared with `test "name" { ... }`.
+//!
+//! Every test is run in a fresh interpreter, so the contract has no state
+//! until the test calls the constructor or moves it to a state itself.
+//! The calls are made from the zero address within a single block.
+
+use algonaut_core::Address;
+use folidity_diagnostics::{
+    Report,
+    Span,
+};
+use folidity_semantics::{
+    ast::TestDeclaration,
+    ContractDefinition,
+};
+
+use crate::{
+    Frame,
+    Interpreter,
+};
+
+/// Outcome of the test.
+#[derive(Debug, Clone)]
+pub struct TestReport {
+    /// Name of the test.
+    pub name: String,
+    /// Location of the test declaration.
+    pub loc: Span,
+    /// Errors of the failed test, e.g. the failed assertion.
+    pub errors: Vec<Report>,
+}
+
+impl TestReport {
+    /// Has the test run to the end?
+    pub fn passed(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Runner of the tests of the contract.
+#[derive(Debug)]
+pub struct TestRunner<'a> {
+    /// Resolved contract definition.
+    contract: &'a ContractDefinition,
+}
+
+impl<'a> TestRunner<'a> {
+    pub fn new(contract: &'a ContractDefinition) -> Self {
+        Self { contract }
+    }
+
+    /// Run the tests whose names contain the filter, or all of them without one.
+    pub fn run(&self, filter: Option<&str>) -> Vec<TestReport> {
+        self.contract
+            .tests
+            .iter()
+            .filter(|t| filter.is_none_or(|f| t.name.contains(f)))
+            .map(|t| self.run_test(t))
+            .collect()
+    }
+
+    /// Run the body of the test.
+    pub fn run_test(&self, test: &'a TestDeclaration) -> TestReport {
+        let mut interpreter = Interpreter::new(self.contract);
+        interpreter.caller = Some(Address::new([0; 32]));
+        interpreter.block = Some(1);
+        let mut frame = Frame::new(&test.scope);
+        let errors = match interpreter.block(&test.body, &mut frame) {
+            Ok(_) => vec![],
+            Err(_) => interpreter.diagnostics,
+        };
+        TestReport {
+            name: test.name.clone(),
+            loc: test.loc.clone(),
+            errors,
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use folidity_parser::parse;
+use folidity_semantics::{
+    ContractDefinition,
+    Runner,
+};
+use num_bigint::BigInt;
+
+use crate::{
+    Evaluated,
+    Interpreter,
+    Scenario,
+    Session,
+    SimulationReport,
+    Simulator,
+    TestRunner,
+    Value,
+};
+
+const CALC_SRC: &str = r#"
+struct Pair {
+    x: int,
+    y: int
+}
+
+fn int factorial(n: int) {
+    if n <= 1 {
+        return 1;
+    }
+    return n * factorial(n - 1);
+}
+
+fn int max(a: int, b: int) {
+    return if a > b then a else b;
+}
+
+fn int sum_pair(p: Pair) {
+    return p.x + p.y;
+}
+
+fn int total(l: list<int>) {
+    let mut acc = 0;
+    for (item in l) {
+        acc = acc + item;
+    }
+    return acc;
+}
+
+fn int safe_div(a: int, b: int) {
+    return a / b :> or(0);
+}
+
+fn int first_square_over(limit: int) {
+    let mut i = 0;
+    while true {
+        i = i + 1;
+        if i * i <= limit {
+            continue;
+        }
+        break;
+    }
+    return i;
+}
+
+fn bool all_within(l: list<int>, limit: int) {
+    return all(l, |x| x <= limit) && !exists(l, |x| x < 0);
+}
+
+fn int hypot(a: int, b: int) {
+    return sqrt(pow(a, 2) + pow(b, 2));
+}
+
+fn int last_of_window(l: list<int>, lo: unit, hi: unit) {
+    let w = l[lo..hi];
+    return w[size(w) - 1];
+}
+
+fn list<int> reorder(l: list<int>, x: int) {
+    return l :> pop() :> insert(1, x) :> push(x) :> remove(0);
+}
+
+fn set<int> merge(a: set<int>, b: set<int>) {
+    return a :> push(1) :> union(b) :> difference([2]);
+}
+"#;
+
+fn call(contract: &ContractDefinition, name: &str, args: Vec<Value>) -> Result<Value, String> {
+    let i = contract
+        .functions
+        .iter()
+        .position(|f| f.name.name == name)
+        .expect("function exists");
+    let mut interpreter = Interpreter::new(contract);
+    interpreter
+        .call(i, args, &contract.functions[i].loc)
+        .map_err(|_| interpreter.diagnostics[0].message.clone())
+}
+
+#[test]
+fn test_interpret_functions() {
+    folidity_diagnostics::disable_pretty_print();
+    let tree = parse(CALC_SRC).unwrap();
+    let res = ContractDefinition::run(&tree);
+    let Ok(contract) = res else {
+        panic!("{:#?}", res.err().unwrap());
+    };
+    let int = |n: i64| Value::Int(BigInt::from(n));
+
+    assert_eq!(call(&contract, "factorial", vec![int(5)]), Ok(int(120)));
+    assert_eq!(
+        call(
+            &contract,
+            "sum_pair",
+            vec![Value::Struct(vec![int(2), int(-5)])]
+        ),
+        Ok(int(-3))
+    );
+    assert_eq!(
+        call(
+            &contract,
+            "total",
+            vec![Value::List(vec![int(1), int(2), int(3)])]
+        ),
+        Ok(int(6))
+    );
+    assert_eq!(
+        call(&contract, "safe_div", vec![int(7), int(2)]),
+        Ok(int(3))
+    );
+    // the division by zero falls back to the default.
+    assert_eq!(
+        call(&contract, "safe_div", vec![int(7), int(0)]),
+        Ok(int(0))
+    );
+    assert_eq!(
+        call(&contract, "first_square_over", vec![int(10)]),
+        Ok(int(4))
+    );
+    let list = Value::List(vec![int(1), int(5), int(3)]);
+    assert_eq!(
+        call(&contract, "all_within", vec![list.clone(), int(5)]),
+        Ok(Value::Bool(true))
+    );
+    assert_eq!(
+        call(&contract, "all_within", vec![list, int(4)]),
+        Ok(Value::Bool(false))
+    );
+    assert_eq!(call(&contract, "max", vec![int(2), int(7)]), Ok(int(7)));
+    assert_eq!(call(&contract, "max", vec![int(7), int(2)]), Ok(int(7)));
+    assert_eq!(call(&contract, "hypot", vec![int(-3), int(4)]), Ok(int(5)));
+    assert_eq!(call(&contract, "hypot", vec![int(2), int(2)]), Ok(int(2)));
+    let list = Value::List(vec![int(1), int(5), int(3)]);
+    let uint = |n: u32| Value::Uint(n.into());
+    assert_eq!(
+        call(
+            &contract,
+            "last_of_window",
+            vec![list.clone(), uint(0), uint(2)]
+        ),
+        Ok(int(5))
+    );
+    assert_eq!(
+        call(&contract, "last_of_window", vec![list, uint(1), uint(4)]),
+        Err("Slice is out of bounds of the list of 3 elements.".to_string())
+    );
+    assert_eq!(
+        call(
+            &contract,
+            "reorder",
+            vec![Value::List(vec![int(0), int(5), int(0), int(3)]), int(7)]
+        ),
+        Ok(Value::List(vec![int(7), int(5), int(7)]))
+    );
+    assert_eq!(
+        call(&contract, "reorder", vec![Value::List(vec![]), int(7)]),
+        Err("Can not pop from the empty list.".to_string())
+    );
+    // sets keep the first occurrence of the item.
+    assert_eq!(
+        call(
+            &contract,
+            "merge",
+            vec![
+                Value::List(vec![int(3), int(1)]),
+                Value::List(vec![int(2), int(4), int(3)])
+            ]
+        ),
+        Ok(Value::List(vec![int(3), int(1), int(4)]))
+    );
+}
+
+#[test]
+fn test_session() {
+    folidity_diagnostics::disable_pretty_print();
+    let mut session = Session::default();
+    let value = |value: &str, ty: &str| {
+        Ok(Some(Evaluated {
+            value: value.to_string(),
+            ty: ty.to_string(),
+        }))
+    };
+
+    assert_eq!(session.eval("1 + 2"), value("3", "int"));
+    assert_eq!(session.eval("let mut x = 10;"), Ok(None));
+    assert_eq!(session.eval("x = x * 2;"), Ok(None));
+    assert_eq!(session.eval("x"), value("20", "int"));
+    assert_eq!(
+        session.eval("fn int double(a: int) { return a * 2; }"),
+        Ok(None)
+    );
+    assert_eq!(session.eval("double(x) + 1"), value("41", "int"));
+    assert_eq!(
+        session.eval("s\"ab\" + s\"cd\""),
+        value("s\"abcd\"", "string")
+    );
+    assert_eq!(
+        session.eval("[1, 2, 3] :> map(|i| i * 2)"),
+        value("[2, 4, 6]", "list<int>")
+    );
+
+    // errors are relative to the input and do not alter the session.
+    let errors = session.eval("x / 0").unwrap_err();
+    assert_eq!(errors[0].message, "Division by zero.");
+    assert_eq!(errors[0].loc.start, 0);
+    assert_eq!(errors[0].loc.end, 5);
+    let errors = session.eval("let y: bool = 1;").unwrap_err();
+    assert!(!errors.is_empty());
+    assert!(session.eval("y").is_err());
+    assert_eq!(session.eval("x"), value("20", "int"));
+}
+
+const COUNTER_SRC: &str = r#"
+state CounterState {
+    counter: int,
+} st [
+    counter < 1000,
+    counter > -1000
+]
+
+@init
+@(any)
+fn () initialise() when () -> CounterState {
+    move CounterState : { 0 };
+}
+
+@(any)
+fn () incr_by(value: int) when (CounterState s) -> (CounterState new_s)
+st [
+    value > 100,
+    new_s.counter > s.counter,
+] {
+    let value = s.counter + value;
+    move CounterState : { value };
+}
+
+@(a"2FMLYJHYQWRHMFKRHKTKX5UNB5DGO65U57O3YVLWUJWKRE4YYJYC2CWWBY")
+fn () reset() when (CounterState s) -> CounterState {
+    move CounterState : { 0 };
+}
+
+@(any)
+view(CounterState s) fn int get_value() {
+    return s.counter;
+}
+"#;
+
+const COUNTER_SCENARIO: &str = r#"
+{
+    "steps": [
+        { "call": "incr_by", "args": [150], "fails": true },
+        { "call": "initialise", "state": "CounterState" },
+        { "call": "incr_by", "args": [150] },
+        { "call": "incr_by", "args": [50], "fails": true },
+        { "call": "incr_by", "args": [900], "fails": true },
+        { "call": "get_value", "returns": "150" },
+        { "call": "reset", "fails": true },
+        {
+            "call": "reset",
+            "caller": "2FMLYJHYQWRHMFKRHKTKX5UNB5DGO65U57O3YVLWUJWKRE4YYJYC2CWWBY"
+        },
+        { "call": "get_value", "returns": "0" }
+    ]
+}
+"#;
+
+fn simulate(src: &str, scenario: &str) -> SimulationReport {
+    let tree = parse(src).unwrap();
+    let res = ContractDefinition::run(&tree);
+    let Ok(contract) = res else {
+        panic!("{:#?}", res.err().unwrap());
+    };
+    let scenario: Scenario = serde_json::from_str(scenario).unwrap();
+    Simulator::new(&contract).simulate(&scenario)
+}
+
+#[test]
+fn test_simulate_counter() {
+    folidity_diagnostics::disable_pretty_print();
+    let report = simulate(COUNTER_SRC, COUNTER_SCENARIO);
+    for step in &report.steps {
+        assert!(step.passed(), "{}: {:?}", step.call, step.mismatch);
+    }
+    let error = |i: usize| report.steps[i].errors[0].message.clone();
+
+    assert_eq!(
+        error(0),
+        "`incr_by` can only be called in `CounterState` state."
+    );
+    assert_eq!(report.steps[2].call, "incr_by(150)");
+    assert_eq!(
+        report.steps[2].state.as_deref(),
+        Some("CounterState { counter: 150 }")
+    );
+    assert_eq!(error(3), "Bound of `incr_by` is violated.");
+    // the bounds of the state are violated, and the state is reverted.
+    assert_eq!(error(4), "Instance of `CounterState` violates its bounds.");
+    assert_eq!(
+        report.steps[4].state.as_deref(),
+        Some("CounterState { counter: 150 }")
+    );
+    assert!(error(6).contains("has no access to `reset`"));
+    assert_eq!(report.state.as_deref(), Some("CounterState { counter: 0 }"));
+
+    // unmet expectations are reported.
+    let report = simulate(
+        COUNTER_SRC,
+        r#"{ "steps": [{ "call": "initialise" }, { "call": "get_value", "returns": "1" }] }"#,
+    );
+    assert!(!report.passed());
+    assert_eq!(
+        report.steps[1].mismatch.as_deref(),
+        Some("Expected `1` to be returned, got `0`.")
+    );
+}
+
+const TESTS_SRC: &str = r#"
+test "increments" {
+    initialise();
+    incr_by(150);
+    assert(get_value() == 150);
+}
+
+test "starts from the state" {
+    move CounterState : { 500 };
+    incr_by(200);
+    assert(get_value() == 600);
+}
+
+test "requires the state" {
+    incr_by(150);
+}
+
+test "reports the message" {
+    initialise();
+    incr_by(150);
+    assert(get_value() == 0, s"not reset");
+}
+"#;
+
+#[test]
+fn test_run_tests() {
+    folidity_diagnostics::disable_pretty_print();
+    let src = format!("{}{}", COUNTER_SRC, TESTS_SRC);
+    let tree = parse(&src).unwrap();
+    let res = ContractDefinition::run(&tree);
+    let Ok(contract) = res else {
+        panic!("{:#?}", res.err().unwrap());
+    };
+    let reports = TestRunner::new(&contract).run(None);
+    let outcomes: Vec<(&str, bool)> = reports
+        .iter()
+        .map(|r| (r.name.as_str(), r.passed()))
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            ("increments", true),
+            ("starts from the state", false),
+            ("requires the state", false),
+            ("reports the message", false),
+        ]
+    );
+    let failed = &reports[1].errors[0];
+    assert_eq!(failed.message, "Assertion failed.");
+    assert_eq!(&src[failed.loc.clone()], "get_value() == 600");
+    assert_eq!(
+        reports[2].errors[0].message,
+        "`incr_by` can only be called in `CounterState` state."
+    );
+
+    assert_eq!(reports[3].errors[0].message, "Assertion failed: not reset");
+
+    let reports = TestRunner::new(&contract).run(Some("state"));
+    assert_eq!(reports.len(), 2);
+}
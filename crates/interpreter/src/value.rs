@@ -0,0 +1,168 @@
+//! Values of the interpreted expressions.
+//!
+//! Values do not carry their types, they are rendered with the type
+//! of the expression they are evaluated from.
+
+use std::cmp::Ordering;
+
+use algonaut_core::Address;
+use folidity_semantics::{
+    ast::{
+        Lambda,
+        TypeVariant,
+    },
+    ContractDefinition,
+};
+use num_bigint::{
+    BigInt,
+    BigUint,
+};
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Result of the function that returns nothing.
+    Unit,
+    /// `int` and `i64`.
+    Int(BigInt),
+    /// `uint`, `u8`, `u64` and `u256`.
+    Uint(BigUint),
+    Float(BigRational),
+    Bool(bool),
+    Char(char),
+    String(String),
+    Hex(Vec<u8>),
+    Address(Address),
+    /// Index of the variant of the enum and the fields of its payload.
+    Enum(usize, Vec<Value>),
+    /// Fields of the struct, the model or the state in the declaration order,
+    /// or the elements of the tuple.
+    Struct(Vec<Value>),
+    /// Items of the list or the set.
+    List(Vec<Value>),
+    /// Value of the option, `None` if it is missing.
+    Option(Option<Box<Value>>),
+    /// Entries of the mapping in the insertion order.
+    Mapping(Vec<(Value, Value)>),
+    /// Function passed to the collection built-ins.
+    Lambda(Lambda),
+}
+
+impl Value {
+    /// Integer value as the signed number.
+    pub fn as_integer(&self) -> Option<BigInt> {
+        match self {
+            Value::Int(n) => Some(n.clone()),
+            Value::Uint(n) => Some(BigInt::from(n.clone())),
+            _ => None,
+        }
+    }
+
+    /// Compare the values of the ordered types.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Float(a), Value::Float(b)) => Some(a.cmp(b)),
+            (Value::Char(a), Value::Char(b)) => Some(a.cmp(b)),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (a, b) => Some(a.as_integer()?.cmp(&b.as_integer()?)),
+        }
+    }
+
+    /// Render the value of the given type in the source syntax.
+    pub fn display(&self, ty: &TypeVariant, contract: &ContractDefinition) -> String {
+        let items = |values: &[Value], ty: &TypeVariant| -> String {
+            values
+                .iter()
+                .map(|v| v.display(ty, contract))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+
+        match (self, ty) {
+            (Value::Unit, _) => String::from("()"),
+            (Value::Int(n), _) => n.to_string(),
+            (Value::Uint(n), _) => n.to_string(),
+            (Value::Float(n), _) => n.to_f64().unwrap_or_default().to_string(),
+            (Value::Bool(b), _) => b.to_string(),
+            (Value::Char(c), _) => format!("'{}'", c),
+            (Value::String(s), _) => format!("s\"{}\"", s),
+            (Value::Hex(h), _) => format!("hex\"{}\"", hex::encode(h)),
+            (Value::Address(a), _) => format!("a\"{}\"", a),
+            (Value::Enum(i, payload), TypeVariant::Enum(sym)) => {
+                let decl = &contract.enums[sym.i];
+                let Some((variant, v)) = decl.variants.get_index(*i) else {
+                    return format!("{}.{}", decl.name.name, i);
+                };
+                if v.fields.is_empty() {
+                    return format!("{}.{}", decl.name.name, variant);
+                }
+                let fields: Vec<String> = v
+                    .fields
+                    .iter()
+                    .zip(payload)
+                    .map(|(f, v)| v.display(&f.ty.ty, contract))
+                    .collect();
+                format!("{}.{}({})", decl.name.name, variant, fields.join(", "))
+            }
+            (Value::Option(None), _) => String::from("none"),
+            (Value::Option(Some(v)), TypeVariant::Option(item)) => {
+                format!("some({})", v.display(item, contract))
+            }
+            (Value::Option(Some(v)), _) => {
+                format!("some({})", v.display(&TypeVariant::Unit, contract))
+            }
+            (Value::List(values), TypeVariant::List(item) | TypeVariant::Set(item)) => {
+                format!("[{}]", items(values, item))
+            }
+            (Value::Mapping(entries), TypeVariant::Mapping(m)) => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(k, v)| {
+                        format!(
+                            "{} -> {}",
+                            k.display(&m.from_ty, contract),
+                            v.display(&m.to_ty, contract)
+                        )
+                    })
+                    .collect();
+                format!("[{}]", entries.join(", "))
+            }
+            (Value::Struct(values), TypeVariant::Tuple(tys)) => {
+                let items: Vec<String> = values
+                    .iter()
+                    .zip(tys)
+                    .map(|(v, ty)| v.display(ty, contract))
+                    .collect();
+                format!("({})", items.join(", "))
+            }
+            (Value::Struct(values), _) => {
+                let (name, fields) = match ty {
+                    TypeVariant::Struct(sym) => {
+                        let decl = &contract.structs[sym.i];
+                        (&decl.name.name, decl.fields.clone())
+                    }
+                    TypeVariant::Model(sym) => {
+                        let decl = &contract.models[sym.i];
+                        (&decl.name.name, decl.fields(contract))
+                    }
+                    TypeVariant::State(sym) => {
+                        let decl = &contract.states[sym.i];
+                        (&decl.name.name, decl.fields(contract))
+                    }
+                    _ => return format!("{{ {} }}", items(values, &TypeVariant::Unit)),
+                };
+                let fields: Vec<String> = fields
+                    .iter()
+                    .zip(values)
+                    .map(|(f, v)| format!("{}: {}", f.name.name, v.display(&f.ty.ty, contract)))
+                    .collect();
+                format!("{} {{ {} }}", name, fields.join(", "))
+            }
+            (Value::Lambda(_), _) => String::from("<lambda>"),
+            (Value::Enum(i, _), _) => i.to_string(),
+            (Value::List(values), _) => format!("[{}]", items(values, &TypeVariant::Unit)),
+            (Value::Mapping(entries), _) => format!("<mapping of {}>", entries.len()),
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::fmt::Display;
+
+use folidity_semantics::Span;
+
+/// Temporary holding the result of a single instruction.
+///
+/// Every temporary is assigned exactly once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Temp(pub usize);
+
+/// Mutable variable of the function, e.g. a parameter or a `let` binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Local(pub usize);
+
+/// Index of the basic block in the function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockId(pub usize);
+
+/// Type of the values in the IR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    Bool,
+    U8,
+    /// `uint` and `u64`.
+    U64,
+    I64,
+    Int,
+}
+
+/// Constant value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    Bool(bool),
+    Uint(u64),
+    Int(i64),
+}
+
+/// Binary operation over the values of the same type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    Neq,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    /// Whether the operation compares the operands, resulting in a boolean.
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinaryOp::Eq
+                | BinaryOp::Neq
+                | BinaryOp::Gt
+                | BinaryOp::Lt
+                | BinaryOp::Ge
+                | BinaryOp::Le
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    /// `dest = value`
+    Const { dest: Temp, value: Value },
+    /// `dest = local`
+    Load { dest: Temp, local: Local },
+    /// `local = value`
+    Store { local: Local, value: Temp },
+    /// `dest = left op right`, `ty` is the type of the operands.
+    Binary {
+        dest: Temp,
+        op: BinaryOp,
+        ty: Type,
+        left: Temp,
+        right: Temp,
+    },
+    /// `dest = !value`
+    Not { dest: Temp, value: Temp },
+    /// Call of the function of the module, `dest` holds the returned value.
+    Call {
+        dest: Option<Temp>,
+        func: usize,
+        args: Vec<Temp>,
+    },
+    /// Revert the call if the value is false.
+    Assert { cond: Temp },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Jump(BlockId),
+    /// Jump to `then` if the condition holds, otherwise to `otherwise`.
+    Branch {
+        cond: Temp,
+        then: BlockId,
+        otherwise: BlockId,
+    },
+    Return(Option<Temp>),
+}
+
+/// Straight sequence of instructions ending with the transfer of control.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub instructions: Vec<Instruction>,
+    pub terminator: Terminator,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    /// Location of the function name.
+    pub loc: Span,
+    pub name: String,
+    /// Locals holding the parameters, in the declaration order.
+    pub params: Vec<Local>,
+    /// Types of the locals.
+    pub locals: Vec<Type>,
+    /// Types of the temporaries.
+    pub temps: Vec<Type>,
+    /// Return type, `None` for the functions returning nothing.
+    pub returns: Option<Type>,
+    /// Basic blocks, the first one is the entry.
+    pub blocks: Vec<Block>,
+}
+
+/// Functions of the contract, in the order of the definition.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Module {
+    pub functions: Vec<Function>,
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let word = match self {
+            Type::Bool => "bool",
+            Type::U8 => "u8",
+            Type::U64 => "u64",
+            Type::I64 => "i64",
+            Type::Int => "int",
+        };
+        f.write_str(word)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Uint(n) => write!(f, "{}", n),
+            Value::Int(n) => write!(f, "{}i", n),
+        }
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let word = match self {
+            BinaryOp::Add => "add",
+            BinaryOp::Sub => "sub",
+            BinaryOp::Mul => "mul",
+            BinaryOp::Div => "div",
+            BinaryOp::Mod => "mod",
+            BinaryOp::Eq => "eq",
+            BinaryOp::Neq => "neq",
+            BinaryOp::Gt => "gt",
+            BinaryOp::Lt => "lt",
+            BinaryOp::Ge => "ge",
+            BinaryOp::Le => "le",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+        };
+        f.write_str(word)
+    }
+}
+
+impl Display for Temp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "t{}", self.0)
+    }
+}
+
+impl Display for Local {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "l{}", self.0)
+    }
+}
+
+impl Display for BlockId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::Const { dest, value } => write!(f, "{} = {}", dest, value),
+            Instruction::Load { dest, local } => write!(f, "{} = load {}", dest, local),
+            Instruction::Store { local, value } => write!(f, "store {}, {}", local, value),
+            Instruction::Binary {
+                dest,
+                op,
+                ty,
+                left,
+                right,
+            } => write!(f, "{} = {}.{} {}, {}", dest, op, ty, left, right),
+            Instruction::Not { dest, value } => write!(f, "{} = not {}", dest, value),
+            Instruction::Call { dest, func, args } => {
+                if let Some(dest) = dest {
+                    write!(f, "{} = ", dest)?;
+                }
+                let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+                write!(f, "call f{}({})", func, args.join(", "))
+            }
+            Instruction::Assert { cond } => write!(f, "assert {}", cond),
+        }
+    }
+}
+
+impl Display for Terminator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Terminator::Jump(b) => write!(f, "jump {}", b),
+            Terminator::Branch {
+                cond,
+                then,
+                otherwise,
+            } => write!(f, "branch {}, {}, {}", cond, then, otherwise),
+            Terminator::Return(Some(t)) => write!(f, "return {}", t),
+            Terminator::Return(None) => write!(f, "return"),
+        }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|p| format!("{}: {}", p, self.locals[p.0]))
+            .collect();
+        write!(f, "fn {}({})", self.name, params.join(", "))?;
+        if let Some(ty) = &self.returns {
+            write!(f, " -> {}", ty)?;
+        }
+        writeln!(f, " {{")?;
+        for (i, ty) in self.locals.iter().enumerate() {
+            if !self.params.contains(&Local(i)) {
+                writeln!(f, "    let l{}: {}", i, ty)?;
+            }
+        }
+        for (i, b) in self.blocks.iter().enumerate() {
+            writeln!(f, "{}:", BlockId(i))?;
+            for inst in &b.instructions {
+                writeln!(f, "    {}", inst)?;
+            }
+            writeln!(f, "    {}", b.terminator)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl Display for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, func) in self.functions.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "; f{}", i)?;
+            writeln!(f, "{}", func)?;
+        }
+        Ok(())
+    }
+}
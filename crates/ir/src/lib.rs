@@ -0,0 +1,31 @@
+//! Intermediate representation of the contract functions.
+//!
+//! The functions are lowered from the resolved definition into the basic blocks
+//! of instructions over the temporaries, every temporary being assigned once.
+//! Mutable variables are kept in the locals, that are explicitly loaded and stored.
+//! The backends consume the flat blocks instead of walking the nested expressions.
+
+use folidity_semantics::{
+    CompilationError,
+    ContractDefinition,
+    Runner,
+};
+
+mod ast;
+mod lower;
+
+#[cfg(test)]
+mod tests;
+
+pub use ast::*;
+pub use lower::lower_contract;
+
+impl Runner<ContractDefinition, Module> for Module {
+    fn run(source: &ContractDefinition) -> Result<Module, CompilationError>
+    where
+        Self: std::marker::Sized,
+    {
+        let mut diagnostics = vec![];
+        lower_contract(source, &mut diagnostics).map_err(|_| CompilationError::Emit(diagnostics))
+    }
+}
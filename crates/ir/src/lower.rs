@@ -0,0 +1,549 @@
+//! Lowering of the resolved functions into the basic blocks.
+//!
+//! Only the scalar subset of the language is lowered for now: the integers and booleans,
+//! the arithmetic and logic over them, the control flow and the calls of other functions.
+//! Everything else is reported as not supported.
+
+use std::collections::HashMap;
+
+use folidity_diagnostics::Report;
+use folidity_semantics::{
+    ast::{
+        BinaryExpression,
+        Expression,
+        FunctionCall,
+        FunctionVisibility,
+        Statement,
+        TypeVariant,
+        Variable,
+    },
+    ContractDefinition,
+    Span,
+};
+use num_traits::ToPrimitive;
+
+use crate::ast::{
+    BinaryOp,
+    Block,
+    BlockId,
+    Function,
+    Instruction,
+    Local,
+    Module,
+    Temp,
+    Terminator,
+    Type,
+    Value,
+};
+
+/// Lower all functions of the contract.
+#[allow(clippy::result_unit_err)]
+pub fn lower_contract(
+    contract: &ContractDefinition,
+    diagnostics: &mut Vec<Report>,
+) -> Result<Module, ()> {
+    let mut module = Module::default();
+    let mut error = false;
+    for func in &contract.functions {
+        match lower_function(func, contract, diagnostics) {
+            Ok(f) => module.functions.push(f),
+            Err(_) => error = true,
+        }
+    }
+    if error {
+        return Err(());
+    }
+    Ok(module)
+}
+
+/// Type of the value in the IR, if it is supported.
+fn ir_type(ty: &TypeVariant) -> Option<Type> {
+    match ty {
+        TypeVariant::Bool => Some(Type::Bool),
+        TypeVariant::U8 => Some(Type::U8),
+        TypeVariant::Uint | TypeVariant::U64 => Some(Type::U64),
+        TypeVariant::I64 => Some(Type::I64),
+        TypeVariant::Int => Some(Type::Int),
+        _ => None,
+    }
+}
+
+fn unsupported(loc: &Span, message: &str, diagnostics: &mut Vec<Report>) {
+    diagnostics.push(Report::emit_error(
+        loc.clone(),
+        format!("{} not supported by the IR yet.", message),
+    ));
+}
+
+fn lower_function(
+    func: &folidity_semantics::ast::Function,
+    contract: &ContractDefinition,
+    diagnostics: &mut Vec<Report>,
+) -> Result<Function, ()> {
+    if func.state_bound.is_some() || matches!(func.vis, FunctionVisibility::View(_)) {
+        unsupported(&func.name.loc, "State of the contract is", diagnostics);
+        return Err(());
+    }
+    if !func.access_attributes.is_empty() {
+        unsupported(&func.name.loc, "Access attributes are", diagnostics);
+        return Err(());
+    }
+
+    let mut lowering = FunctionLowering {
+        contract,
+        locals: vec![],
+        vars: HashMap::new(),
+        temps: vec![],
+        blocks: vec![(vec![], None)],
+        current: 0,
+        loops: vec![],
+        diagnostics,
+    };
+
+    let mut error = false;
+    let mut params = vec![];
+    for (name, p) in &func.params {
+        let Ok(ty) = lowering.ty(&p.ty.ty, &p.loc) else {
+            error = true;
+            continue;
+        };
+        let (pos, _) = func.scope.find_var_index(name).expect("should exist");
+        let local = lowering.local(ty);
+        lowering.vars.insert(pos, local);
+        params.push(local);
+    }
+    let returns = match func.return_ty.ty() {
+        TypeVariant::Unit => None,
+        ty => {
+            let ty = lowering.ty(ty, &func.name.loc);
+            error |= ty.is_err();
+            ty.ok()
+        }
+    };
+    if error {
+        return Err(());
+    }
+
+    // the bounds of the parameters are asserted when the function is entered.
+    if let Some(bounds) = &func.bounds {
+        for e in &bounds.exprs {
+            match lowering.expr(e) {
+                Ok(cond) => lowering.push(Instruction::Assert { cond }),
+                Err(_) => error = true,
+            }
+        }
+    }
+    error |= lowering.statements(&func.body).is_err();
+    if error {
+        return Err(());
+    }
+
+    Ok(Function {
+        loc: func.name.loc.clone(),
+        name: func.name.name.clone(),
+        params,
+        locals: lowering.locals,
+        temps: lowering.temps,
+        returns,
+        blocks: reachable_blocks(lowering.blocks),
+    })
+}
+
+/// Finish the blocks, dropping the ones that can not be reached from the entry.
+///
+/// The blocks left open fall through to the end of the function.
+fn reachable_blocks(blocks: Vec<(Vec<Instruction>, Option<Terminator>)>) -> Vec<Block> {
+    let blocks: Vec<Block> = blocks
+        .into_iter()
+        .map(|(instructions, terminator)| {
+            Block {
+                instructions,
+                terminator: terminator.unwrap_or(Terminator::Return(None)),
+            }
+        })
+        .collect();
+
+    let mut ids: HashMap<usize, usize> = HashMap::new();
+    let mut order = vec![];
+    let mut stack = vec![0];
+    while let Some(b) = stack.pop() {
+        if ids.contains_key(&b) {
+            continue;
+        }
+        ids.insert(b, order.len());
+        order.push(b);
+        match &blocks[b].terminator {
+            Terminator::Jump(next) => stack.push(next.0),
+            Terminator::Branch {
+                then, otherwise, ..
+            } => {
+                stack.push(otherwise.0);
+                stack.push(then.0);
+            }
+            Terminator::Return(_) => {}
+        }
+    }
+
+    let id = |b: &BlockId| BlockId(ids[&b.0]);
+    order
+        .into_iter()
+        .map(|b| {
+            let block = &blocks[b];
+            let terminator = match &block.terminator {
+                Terminator::Jump(next) => Terminator::Jump(id(next)),
+                Terminator::Branch {
+                    cond,
+                    then,
+                    otherwise,
+                } => {
+                    Terminator::Branch {
+                        cond: *cond,
+                        then: id(then),
+                        otherwise: id(otherwise),
+                    }
+                }
+                Terminator::Return(t) => Terminator::Return(*t),
+            };
+            Block {
+                instructions: block.instructions.clone(),
+                terminator,
+            }
+        })
+        .collect()
+}
+
+struct FunctionLowering<'a> {
+    contract: &'a ContractDefinition,
+    /// Types of the locals.
+    locals: Vec<Type>,
+    /// Locals of the variables by their index in the scope.
+    vars: HashMap<usize, Local>,
+    /// Types of the temporaries.
+    temps: Vec<Type>,
+    /// Instructions of the blocks, and their terminators once the blocks are finished.
+    blocks: Vec<(Vec<Instruction>, Option<Terminator>)>,
+    /// Block the instructions are appended to.
+    current: usize,
+    /// Blocks of the next iteration and of the end of the enclosing loops.
+    loops: Vec<(BlockId, BlockId)>,
+    diagnostics: &'a mut Vec<Report>,
+}
+
+impl<'a> FunctionLowering<'a> {
+    fn ty(&mut self, ty: &TypeVariant, loc: &Span) -> Result<Type, ()> {
+        ir_type(ty).ok_or_else(|| {
+            unsupported(
+                loc,
+                &format!("Type `{}` is", ty.display(self.contract)),
+                self.diagnostics,
+            )
+        })
+    }
+
+    fn local(&mut self, ty: Type) -> Local {
+        self.locals.push(ty);
+        Local(self.locals.len() - 1)
+    }
+
+    fn temp(&mut self, ty: Type) -> Temp {
+        self.temps.push(ty);
+        Temp(self.temps.len() - 1)
+    }
+
+    fn block(&mut self) -> BlockId {
+        self.blocks.push((vec![], None));
+        BlockId(self.blocks.len() - 1)
+    }
+
+    fn push(&mut self, instruction: Instruction) {
+        self.blocks[self.current].0.push(instruction);
+    }
+
+    /// Finish the current block, unless it has been finished by `return` or `skip`.
+    fn terminate(&mut self, terminator: Terminator) {
+        let block = &mut self.blocks[self.current];
+        if block.1.is_none() {
+            block.1 = Some(terminator);
+        }
+    }
+
+    fn switch(&mut self, block: BlockId) {
+        self.current = block.0;
+    }
+
+    fn constant(&mut self, ty: Type, value: Value) -> Temp {
+        let dest = self.temp(ty);
+        self.push(Instruction::Const { dest, value });
+        dest
+    }
+
+    fn statements(&mut self, stmts: &[Statement]) -> Result<(), ()> {
+        let mut error = false;
+        for stmt in stmts {
+            error |= self.statement(stmt).is_err();
+        }
+        if error {
+            return Err(());
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &Statement) -> Result<(), ()> {
+        match stmt {
+            Statement::Variable(var) => self.variable(var)?,
+            Statement::Assign(a) => {
+                if !a.fields.is_empty() {
+                    unsupported(&a.loc, "Assignment of the fields is", self.diagnostics);
+                    return Err(());
+                }
+                let value = self.expr(&a.value)?;
+                let local = self.var(a.pos, &a.name.loc)?;
+                self.push(Instruction::Store { local, value });
+            }
+            Statement::IfElse(b) => {
+                let cond = self.expr(&b.condition)?;
+                let then = self.block();
+                let otherwise = self.block();
+                let end = self.block();
+                self.terminate(Terminator::Branch {
+                    cond,
+                    then,
+                    otherwise,
+                });
+
+                self.switch(then);
+                let body = self.statements(&b.body);
+                self.terminate(Terminator::Jump(end));
+                self.switch(otherwise);
+                let else_part = self.statements(&b.else_part);
+                self.terminate(Terminator::Jump(end));
+                self.switch(end);
+                body.and(else_part)?;
+            }
+            Statement::ForLoop(l) => {
+                self.variable(&l.var)?;
+                let var = self.var(l.var.pos, &l.var.loc)?;
+                let header = self.block();
+                let body = self.block();
+                let next = self.block();
+                let end = self.block();
+                self.terminate(Terminator::Jump(header));
+
+                self.switch(header);
+                let cond = self.expr(&l.condition)?;
+                self.terminate(Terminator::Branch {
+                    cond,
+                    then: body,
+                    otherwise: end,
+                });
+
+                self.switch(body);
+                self.loops.push((next, end));
+                let stmts = self.statements(&l.body);
+                self.loops.pop();
+                self.terminate(Terminator::Jump(next));
+
+                // the value of the incrementer is assigned to the loop variable.
+                self.switch(next);
+                let value = self.expr(&l.incrementer)?;
+                self.push(Instruction::Store { local: var, value });
+                self.terminate(Terminator::Jump(header));
+                self.switch(end);
+                stmts?;
+            }
+            Statement::Return(r) => {
+                let value = match &r.expr {
+                    Some(e) => Some(self.expr(e)?),
+                    None => None,
+                };
+                self.terminate(Terminator::Return(value));
+                // the statements after the return are unreachable.
+                let rest = self.block();
+                self.switch(rest);
+            }
+            Statement::While(l) => {
+                let header = self.block();
+                let body = self.block();
+                let end = self.block();
+                self.terminate(Terminator::Jump(header));
+
+                self.switch(header);
+                let cond = self.expr(&l.condition)?;
+                self.terminate(Terminator::Branch {
+                    cond,
+                    then: body,
+                    otherwise: end,
+                });
+
+                self.switch(body);
+                self.loops.push((header, end));
+                let stmts = self.statements(&l.body);
+                self.loops.pop();
+                self.terminate(Terminator::Jump(header));
+                self.switch(end);
+                stmts?;
+            }
+            Statement::Skip(_) | Statement::Break(_) => {
+                let (next, end) = *self.loops.last().expect("only allowed in loops");
+                let target = if matches!(stmt, Statement::Break(_)) {
+                    end
+                } else {
+                    next
+                };
+                self.terminate(Terminator::Jump(target));
+                let rest = self.block();
+                self.switch(rest);
+            }
+            Statement::Expression(Expression::FunctionCall(call)) => {
+                self.call(call)?;
+            }
+            Statement::Expression(e) => {
+                self.expr(e)?;
+            }
+            Statement::Block(b) => self.statements(&b.statements)?,
+            _ => {
+                unsupported(stmt.loc(), "This statement is", self.diagnostics);
+                return Err(());
+            }
+        }
+        Ok(())
+    }
+
+    fn variable(&mut self, var: &Variable) -> Result<(), ()> {
+        if var.names.len() != 1 {
+            unsupported(&var.loc, "Destructuring is", self.diagnostics);
+            return Err(());
+        }
+        let ty = self.ty(&var.ty, &var.loc)?;
+        let value = match &var.value {
+            Some(e) => self.expr(e)?,
+            None => {
+                let zero = match ty {
+                    Type::Bool => Value::Bool(false),
+                    Type::U8 | Type::U64 => Value::Uint(0),
+                    Type::I64 | Type::Int => Value::Int(0),
+                };
+                self.constant(ty, zero)
+            }
+        };
+        let local = self.local(ty);
+        self.push(Instruction::Store { local, value });
+        self.vars.insert(var.pos, local);
+        Ok(())
+    }
+
+    fn var(&mut self, pos: usize, loc: &Span) -> Result<Local, ()> {
+        self.vars.get(&pos).copied().ok_or_else(|| {
+            unsupported(loc, "This variable is", self.diagnostics);
+        })
+    }
+
+    fn expr(&mut self, e: &Expression) -> Result<Temp, ()> {
+        let temp = match e {
+            Expression::Variable(u) => {
+                let local = self.var(u.element, &u.loc)?;
+                let dest = self.temp(self.locals[local.0]);
+                self.push(Instruction::Load { dest, local });
+                dest
+            }
+            Expression::UInt(u) => {
+                let ty = self.ty(&u.ty, &u.loc)?;
+                let Some(n) = u.element.to_u64() else {
+                    self.diagnostics.push(Report::emit_error(
+                        u.loc.clone(),
+                        String::from("Integer value is too large."),
+                    ));
+                    return Err(());
+                };
+                self.constant(ty, Value::Uint(n))
+            }
+            Expression::Int(u) => {
+                let ty = self.ty(&u.ty, &u.loc)?;
+                let Some(n) = u.element.to_i64() else {
+                    self.diagnostics.push(Report::emit_error(
+                        u.loc.clone(),
+                        String::from("Integer value is too large."),
+                    ));
+                    return Err(());
+                };
+                self.constant(ty, Value::Int(n))
+            }
+            Expression::Boolean(u) => self.constant(Type::Bool, Value::Bool(u.element)),
+            Expression::Add(b) => self.binary(BinaryOp::Add, b)?,
+            Expression::Subtract(b) => self.binary(BinaryOp::Sub, b)?,
+            Expression::Multiply(b) => self.binary(BinaryOp::Mul, b)?,
+            Expression::Divide(b) => self.binary(BinaryOp::Div, b)?,
+            Expression::Modulo(b) => self.binary(BinaryOp::Mod, b)?,
+            Expression::Equal(b) => self.binary(BinaryOp::Eq, b)?,
+            Expression::NotEqual(b) => self.binary(BinaryOp::Neq, b)?,
+            Expression::Greater(b) => self.binary(BinaryOp::Gt, b)?,
+            Expression::Less(b) => self.binary(BinaryOp::Lt, b)?,
+            Expression::GreaterEq(b) => self.binary(BinaryOp::Ge, b)?,
+            Expression::LessEq(b) => self.binary(BinaryOp::Le, b)?,
+            Expression::And(b) => self.binary(BinaryOp::And, b)?,
+            Expression::Or(b) => self.binary(BinaryOp::Or, b)?,
+            Expression::Not(u) => {
+                let value = self.expr(&u.element)?;
+                let dest = self.temp(Type::Bool);
+                self.push(Instruction::Not { dest, value });
+                dest
+            }
+            Expression::FunctionCall(call) => {
+                let Some(dest) = self.call(call)? else {
+                    self.diagnostics.push(Report::emit_error(
+                        call.loc.clone(),
+                        String::from("Function does not return a value."),
+                    ));
+                    return Err(());
+                };
+                dest
+            }
+            _ => {
+                unsupported(e.loc(), "This expression is", self.diagnostics);
+                return Err(());
+            }
+        };
+        Ok(temp)
+    }
+
+    fn binary(&mut self, op: BinaryOp, b: &BinaryExpression) -> Result<Temp, ()> {
+        let ty = self.ty(b.left.ty(), &b.loc)?;
+        let left = self.expr(&b.left);
+        let right = self.expr(&b.right);
+        let (left, right) = (left?, right?);
+        let dest = self.temp(if op.is_comparison() { Type::Bool } else { ty });
+        self.push(Instruction::Binary {
+            dest,
+            op,
+            ty,
+            left,
+            right,
+        });
+        Ok(dest)
+    }
+
+    fn call(&mut self, call: &FunctionCall) -> Result<Option<Temp>, ()> {
+        let mut args = vec![];
+        let mut error = false;
+        for a in &call.args {
+            match self.expr(a) {
+                Ok(t) => args.push(t),
+                Err(_) => error = true,
+            }
+        }
+        let dest = match &call.returns {
+            TypeVariant::Unit => None,
+            ty => Some(self.ty(ty, &call.loc)?),
+        }
+        .map(|ty| self.temp(ty));
+        if error {
+            return Err(());
+        }
+        self.push(Instruction::Call {
+            dest,
+            func: call.sym.i,
+            args,
+        });
+        Ok(dest)
+    }
+}
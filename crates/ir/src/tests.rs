@@ -0,0 +1,126 @@
+use folidity_parser::parse;
+use folidity_semantics::{
+    ContractDefinition,
+    Runner,
+};
+
+use crate::{
+    BinaryOp,
+    Instruction,
+    Module,
+    Terminator,
+    Type,
+};
+
+const SRC: &str = r#"
+fn int sum(n: int) st [n >= 0] {
+    let mut acc = 0;
+    for (let mut i = 0; i < n; i + 1) {
+        if i % 2 == 0 {
+            skip;
+        }
+        acc = acc + square(i);
+    }
+    return acc;
+}
+
+fn int square(x: int) {
+    return x * x;
+}
+
+fn bool positive(a: unit) {
+    return !(a == 0);
+}
+"#;
+
+fn lower(src: &str) -> Result<Module, String> {
+    let tree = parse(src).expect("should parse");
+    let contract = ContractDefinition::run(&tree).expect("should resolve");
+    Module::run(&contract).map_err(|e| e.diagnostics()[0].message.clone())
+}
+
+#[test]
+fn test_lowering() {
+    let module = lower(SRC).expect("should lower");
+    println!("{}", module);
+    assert_eq!(module.functions.len(), 3);
+
+    let sum = &module.functions[0];
+    assert_eq!(sum.params.len(), 1);
+    assert_eq!(sum.returns, Some(Type::Int));
+    // `n >= 0` is asserted at the entry.
+    assert!(matches!(
+        sum.blocks[0].instructions[2],
+        Instruction::Binary {
+            op: BinaryOp::Ge,
+            ty: Type::Int,
+            ..
+        }
+    ));
+    assert!(matches!(
+        sum.blocks[0].instructions[3],
+        Instruction::Assert { .. }
+    ));
+    // the loop jumps back to its header.
+    let header = sum
+        .blocks
+        .iter()
+        .position(|b| matches!(b.terminator, Terminator::Branch { .. }))
+        .expect("loop header");
+    let back_edges = sum
+        .blocks
+        .iter()
+        .filter(|b| b.terminator == Terminator::Jump(crate::BlockId(header)))
+        .count();
+    assert_eq!(back_edges, 2);
+    assert!(sum.blocks.iter().any(|b| {
+        b.instructions.iter().any(|i| {
+            matches!(
+                i,
+                Instruction::Call {
+                    func: 1,
+                    dest: Some(_),
+                    ..
+                }
+            )
+        })
+    }));
+    // every temporary is assigned once.
+    let mut assigned: Vec<usize> = sum
+        .blocks
+        .iter()
+        .flat_map(|b| &b.instructions)
+        .filter_map(|i| {
+            match i {
+                Instruction::Const { dest, .. }
+                | Instruction::Load { dest, .. }
+                | Instruction::Binary { dest, .. }
+                | Instruction::Not { dest, .. } => Some(dest.0),
+                Instruction::Call { dest, .. } => dest.map(|d| d.0),
+                _ => None,
+            }
+        })
+        .collect();
+    let count = assigned.len();
+    assigned.dedup();
+    assert_eq!(assigned.len(), count);
+
+    let positive = &module.functions[2];
+    assert_eq!(positive.blocks.len(), 1);
+    assert_eq!(positive.locals, vec![Type::U64]);
+    assert!(matches!(
+        positive.blocks[0].terminator,
+        Terminator::Return(Some(_))
+    ));
+}
+
+#[test]
+fn test_lowering_unsupported() {
+    let src = r#"
+fn string greet(name: string) {
+    return name;
+}
+"#;
+    let err = lower(src).expect_err("strings are not lowered");
+    assert_eq!(err, "Type `string` is not supported by the IR yet.");
+}
@@ -0,0 +1,56 @@
+use folidity_diagnostics::{
+    Level,
+    Report,
+};
+use lsp_types::{
+    Diagnostic,
+    DiagnosticRelatedInformation,
+    DiagnosticSeverity,
+    Location,
+    NumberOrString,
+    Url,
+};
+
+use crate::document::Document;
+
+/// Map the level of the report to the severity of the diagnostic.
+pub fn severity(level: &Level) -> DiagnosticSeverity {
+    match level {
+        Level::Info => DiagnosticSeverity::INFORMATION,
+        Level::Warning => DiagnosticSeverity::WARNING,
+        Level::Error => DiagnosticSeverity::ERROR,
+    }
+}
+
+/// Convert the report into the LSP diagnostic.
+///
+/// Additional reports are attached as the related information,
+/// and the note is appended to the message.
+pub fn to_diagnostic(report: &Report, doc: &Document, uri: &Url) -> Diagnostic {
+    let related: Vec<DiagnosticRelatedInformation> = report
+        .additional_info
+        .iter()
+        .map(|r| {
+            DiagnosticRelatedInformation {
+                location: Location::new(uri.clone(), doc.lines.range(&doc.text, &r.loc)),
+                message: r.message.clone(),
+            }
+        })
+        .collect();
+
+    let message = if report.note.is_empty() {
+        report.message.clone()
+    } else {
+        format!("{}\nNote: {}", report.message, report.note)
+    };
+
+    Diagnostic {
+        range: doc.lines.range(&doc.text, &report.loc),
+        severity: Some(severity(&report.level)),
+        code: Some(NumberOrString::String(report.error_type.to_string())),
+        source: Some("folidity".to_string()),
+        message,
+        related_information: (!related.is_empty()).then_some(related),
+        ..Default::default()
+    }
+}
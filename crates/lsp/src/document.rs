@@ -0,0 +1,120 @@
+use folidity_diagnostics::{
+    ColumnUnit,
+    Report,
+    SourceMap,
+    Span,
+};
+use folidity_parser::parse;
+use folidity_semantics::{
+    resolve_contract,
+    ContractDefinition,
+};
+use lsp_types::{
+    Position,
+    Range,
+    TextDocumentContentChangeEvent,
+};
+
+/// Opened text document and the results of its analysis.
+#[derive(Debug, Clone)]
+pub struct Document {
+    /// Source code of the contract.
+    pub text: String,
+    /// Line index of the source code.
+    pub lines: LineIndex,
+    /// Contract definition from the last parsable version of the document.
+    pub definition: Option<ContractDefinition>,
+    /// Errors and warnings of the current version of the document.
+    pub diagnostics: Vec<Report>,
+}
+
+impl Document {
+    pub fn new(text: String) -> Self {
+        let mut doc = Self {
+            text: String::new(),
+            lines: LineIndex::default(),
+            definition: None,
+            diagnostics: vec![],
+        };
+        doc.update(text);
+        doc
+    }
+
+    /// Replace the source code and analyse it again.
+    ///
+    /// If the new source code can not be parsed, the previous definition is kept
+    /// so the navigation still works while the user is typing.
+    pub fn update(&mut self, text: String) {
+        self.lines = LineIndex::new(&text);
+        match parse(&text) {
+            Ok(tree) => {
+                let definition = resolve_contract(&tree);
+                self.diagnostics = definition.diagnostics.clone();
+                self.definition = Some(definition);
+            }
+            Err(reports) => {
+                self.diagnostics = reports;
+            }
+        }
+        self.text = text;
+    }
+
+    /// Apply the edits in order and analyse the resulting source code once.
+    ///
+    /// Changes without the range replace the whole text.
+    pub fn apply_changes(&mut self, changes: Vec<TextDocumentContentChangeEvent>) {
+        let mut text = std::mem::take(&mut self.text);
+        for change in changes {
+            match change.range {
+                Some(range) => {
+                    let start = self.lines.offset(&text, range.start);
+                    let end = self.lines.offset(&text, range.end).max(start);
+                    text.replace_range(start..end, &change.text);
+                }
+                None => text = change.text,
+            }
+            // positions of the next change refer to the edited text.
+            self.lines = LineIndex::new(&text);
+        }
+        self.update(text);
+    }
+}
+
+/// Mapping between the byte offsets and the LSP positions.
+///
+/// LSP positions count characters in UTF-16 code units.
+#[derive(Debug, Clone, Default)]
+pub struct LineIndex {
+    map: SourceMap,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        Self {
+            map: SourceMap::new(text),
+        }
+    }
+
+    /// Convert the byte offset into the position in the text.
+    pub fn position(&self, text: &str, offset: usize) -> Position {
+        let pos = self.map.position(text, offset, ColumnUnit::Utf16);
+        Position::new(pos.line as u32, pos.column as u32)
+    }
+
+    /// Convert the position in the text into the byte offset.
+    pub fn offset(&self, text: &str, position: Position) -> usize {
+        let pos = folidity_diagnostics::Position {
+            line: position.line as usize,
+            column: position.character as usize,
+        };
+        self.map.offset(text, pos, ColumnUnit::Utf16)
+    }
+
+    /// Convert the span into the range in the text.
+    pub fn range(&self, text: &str, span: &Span) -> Range {
+        Range::new(
+            self.position(text, span.start),
+            self.position(text, span.end),
+        )
+    }
+}
@@ -0,0 +1,54 @@
+//! Language server of the Folidity language.
+//!
+//! The server communicates with the client over stdio and provides:
+//! - diagnostics reported by the parser and semantic analysis as the document changes.
+//! - go-to-definition for declarations and variables.
+//! - hover information with types of declarations and variables.
+
+use anyhow::Result;
+use folidity_diagnostics::disable_pretty_print;
+use lsp_server::Connection;
+use lsp_types::{
+    HoverProviderCapability,
+    OneOf,
+    ServerCapabilities,
+    TextDocumentSyncCapability,
+    TextDocumentSyncKind,
+};
+use server::Server;
+
+mod convert;
+mod document;
+mod navigation;
+mod server;
+
+#[cfg(test)]
+mod tests;
+
+/// Start the language server over stdio and block until the client shuts it down.
+pub fn start() -> Result<()> {
+    // messages are rendered by the editor, so they must not contain colour codes.
+    disable_pretty_print();
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = serde_json::to_value(capabilities())?;
+    connection.initialize(capabilities)?;
+
+    // the connection must be dropped before joining, so the writer thread can finish.
+    Server::default().main_loop(connection)?;
+    io_threads.join()?;
+
+    Ok(())
+}
+
+/// Features supported by the server.
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        ..Default::default()
+    }
+}
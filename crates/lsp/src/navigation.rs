@@ -0,0 +1,227 @@
+use folidity_diagnostics::Span;
+use folidity_semantics::{
+    ast::Param,
+    symtable::{
+        Scope,
+        VariableSym,
+    },
+    ContractDefinition,
+    GlobalSymbol,
+    Identifier,
+};
+
+/// Symbol referenced at the position in the document.
+pub enum Target<'a> {
+    /// Local variable, parameter or state binding.
+    Variable(&'a VariableSym),
+    /// Globally declared symbol.
+    Declaration(&'a GlobalSymbol),
+}
+
+/// Find the identifier at the given byte offset.
+pub fn word_at(text: &str, offset: usize) -> Option<(&str, Span)> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    if offset > text.len() || !text.is_char_boundary(offset) {
+        return None;
+    }
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_ident(*c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let end = text[offset..]
+        .char_indices()
+        .find(|(_, c)| !is_ident(*c))
+        .map_or(text.len(), |(i, _)| offset + i);
+
+    if start == end {
+        return None;
+    }
+    Some((&text[start..end], start..end))
+}
+
+/// Resolve the symbol that the identifier at the offset refers to.
+///
+/// Variables take precedence over the declarations as they can shadow them.
+pub fn find_target<'a>(
+    contract: &'a ContractDefinition,
+    name: &str,
+    offset: usize,
+) -> Option<Target<'a>> {
+    if let Some(var) = find_variable(contract, name, offset) {
+        return Some(Target::Variable(var));
+    }
+    contract
+        .declaration_symbols
+        .get(name)
+        .map(Target::Declaration)
+}
+
+/// Location of the identifier that introduced the target.
+pub fn definition_span(contract: &ContractDefinition, target: &Target) -> Option<Span> {
+    match target {
+        Target::Variable(var) => Some(var.ident.loc.clone()),
+        Target::Declaration(sym) => declaration_name(contract, sym).map(|i| i.loc.clone()),
+    }
+}
+
+/// Hover description of the target.
+pub fn describe(contract: &ContractDefinition, target: &Target) -> Option<String> {
+    match target {
+        Target::Variable(var) => {
+            let kw = if var.mutable { "mut " } else { "" };
+            Some(format!(
+                "{}{}: {}",
+                kw,
+                var.ident.name,
+                var.ty.display(contract)
+            ))
+        }
+        Target::Declaration(sym) => describe_declaration(contract, sym),
+    }
+}
+
+/// Find the closest variable with the given name declared before the offset
+/// in the scope of the enclosing declaration.
+fn find_variable<'a>(
+    contract: &'a ContractDefinition,
+    name: &str,
+    offset: usize,
+) -> Option<&'a VariableSym> {
+    let scopes = contract
+        .functions
+        .iter()
+        .map(|f| (&f.loc, &f.scope))
+        .chain(contract.models.iter().map(|m| (&m.loc, &m.scope)))
+        .chain(contract.states.iter().map(|s| (&s.loc, &s.scope)));
+
+    let scope: &Scope = scopes
+        .filter(|(loc, _)| loc.contains(&offset))
+        .map(|(_, scope)| scope)
+        .next()?;
+
+    scope
+        .vars
+        .values()
+        .filter(|v| v.ident.name == name && v.ident.loc.start <= offset)
+        .max_by_key(|v| v.ident.loc.start)
+}
+
+/// Name of the declaration referenced by the global symbol.
+fn declaration_name<'a>(
+    contract: &'a ContractDefinition,
+    sym: &GlobalSymbol,
+) -> Option<&'a Identifier> {
+    let i = sym.symbol_info().i;
+    match sym {
+        GlobalSymbol::Struct(_) => contract.structs.get(i).map(|s| &s.name),
+        GlobalSymbol::Model(_) => contract.models.get(i).map(|m| &m.name),
+        GlobalSymbol::Enum(_) => contract.enums.get(i).map(|e| &e.name),
+        GlobalSymbol::State(_) => contract.states.get(i).map(|s| &s.name),
+        GlobalSymbol::Function(_) => contract.functions.get(i).map(|f| &f.name),
+        GlobalSymbol::Constant(_) => contract.constants.get(i).map(|c| &c.name),
+        GlobalSymbol::Alias(_) => contract.aliases.get(i).map(|a| &a.name),
+        GlobalSymbol::Interface(_) => contract.interfaces.get(i).map(|a| &a.name),
+        GlobalSymbol::Event(_) => contract.events.get(i).map(|e| &e.name),
+        // tests are named with the strings.
+        GlobalSymbol::Test(_) => None,
+    }
+}
+
+fn describe_declaration(contract: &ContractDefinition, sym: &GlobalSymbol) -> Option<String> {
+    let i = sym.symbol_info().i;
+    let text = match sym {
+        GlobalSymbol::Struct(_) => {
+            let s = contract.structs.get(i)?;
+            format!(
+                "struct {} {}",
+                s.name.name,
+                describe_fields(contract, &s.fields)
+            )
+        }
+        GlobalSymbol::Model(_) => {
+            let m = contract.models.get(i)?;
+            format!(
+                "model {} {}",
+                m.name.name,
+                describe_fields(contract, &m.fields(contract))
+            )
+        }
+        GlobalSymbol::State(_) => {
+            let s = contract.states.get(i)?;
+            format!(
+                "state {} {}",
+                s.name.name,
+                describe_fields(contract, &s.fields(contract))
+            )
+        }
+        GlobalSymbol::Enum(_) => {
+            let e = contract.enums.get(i)?;
+            let variants: Vec<&str> = e.variants.keys().map(|v| v.as_str()).collect();
+            format!("enum {} {{ {} }}", e.name.name, variants.join(", "))
+        }
+        GlobalSymbol::Function(_) => {
+            let f = contract.functions.get(i)?;
+            let params: Vec<String> = f
+                .params
+                .values()
+                .map(|p| describe_param(contract, p))
+                .collect();
+            format!(
+                "fn {} {}({})",
+                f.return_ty.ty().display(contract),
+                f.name.name,
+                params.join(", ")
+            )
+        }
+        GlobalSymbol::Constant(_) => {
+            let c = contract.constants.get(i)?;
+            format!("const {}: {}", c.name.name, c.ty.display(contract))
+        }
+        GlobalSymbol::Alias(_) => {
+            let a = contract.aliases.get(i)?;
+            format!("type {} = {}", a.name.name, a.ty.display(contract))
+        }
+        GlobalSymbol::Interface(_) => {
+            let a = contract.interfaces.get(i)?;
+            let functions: Vec<String> = a
+                .functions
+                .values()
+                .map(|f| {
+                    let params: Vec<String> = f
+                        .params
+                        .values()
+                        .map(|p| describe_param(contract, p))
+                        .collect();
+                    format!(
+                        "fn {} {}({});",
+                        f.return_ty.ty().display(contract),
+                        f.name.name,
+                        params.join(", ")
+                    )
+                })
+                .collect();
+            format!("interface {} {{ {} }}", a.name.name, functions.join(" "))
+        }
+        GlobalSymbol::Event(_) => {
+            let e = contract.events.get(i)?;
+            format!(
+                "event {} {}",
+                e.name.name,
+                describe_fields(contract, &e.fields)
+            )
+        }
+        GlobalSymbol::Test(_) => format!("test \"{}\"", contract.tests.get(i)?.name),
+    };
+    Some(text)
+}
+
+fn describe_fields(contract: &ContractDefinition, fields: &[Param]) -> String {
+    let fields: Vec<String> = fields.iter().map(|p| describe_param(contract, p)).collect();
+    format!("{{ {} }}", fields.join(", "))
+}
+
+fn describe_param(contract: &ContractDefinition, param: &Param) -> String {
+    format!("{}: {}", param.name.name, param.ty.ty.display(contract))
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use lsp_server::{
+    Connection,
+    Message,
+    Notification,
+    Request,
+    RequestId,
+    Response,
+};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument,
+        DidCloseTextDocument,
+        DidOpenTextDocument,
+        Notification as _,
+        PublishDiagnostics,
+    },
+    request::{
+        GotoDefinition,
+        HoverRequest,
+        Request as _,
+    },
+    GotoDefinitionParams,
+    GotoDefinitionResponse,
+    Hover,
+    HoverContents,
+    HoverParams,
+    Location,
+    MarkupContent,
+    MarkupKind,
+    PublishDiagnosticsParams,
+    TextDocumentPositionParams,
+    Url,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    convert::to_diagnostic,
+    document::Document,
+    navigation::{
+        definition_span,
+        describe,
+        find_target,
+        word_at,
+    },
+};
+
+/// State of the language server.
+#[derive(Default)]
+pub struct Server {
+    /// Documents opened by the client.
+    documents: HashMap<Url, Document>,
+}
+
+impl Server {
+    /// Process the messages from the client until the shutdown is requested.
+    pub fn main_loop(&mut self, connection: Connection) -> Result<()> {
+        for msg in &connection.receiver {
+            match msg {
+                Message::Request(req) => {
+                    if connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    let resp = self.handle_request(req);
+                    connection.sender.send(Message::Response(resp))?;
+                }
+                Message::Notification(not) => {
+                    if let Some(uri) = self.handle_notification(not) {
+                        self.publish_diagnostics(&connection, uri)?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&self, req: Request) -> Response {
+        let id = req.id.clone();
+        let result = match req.method.as_str() {
+            GotoDefinition::METHOD => {
+                params::<GotoDefinitionParams>(req).map(|p| {
+                    serde_json::to_value(self.definition(&p.text_document_position_params))
+                })
+            }
+            HoverRequest::METHOD => {
+                params::<HoverParams>(req)
+                    .map(|p| serde_json::to_value(self.hover(&p.text_document_position_params)))
+            }
+            _ => {
+                return Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("Unsupported request `{}`.", req.method),
+                )
+            }
+        };
+
+        match result {
+            Ok(Ok(value)) => Response::new_ok(id, value),
+            Ok(Err(e)) => error_response(id, e.to_string()),
+            Err(e) => error_response(id, e),
+        }
+    }
+
+    /// Update the document state.
+    /// Returns the uri of the document whose diagnostics need to be published.
+    fn handle_notification(&mut self, not: Notification) -> Option<Url> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = notification_params::<DidOpenTextDocument>(not)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), Document::new(params.text_document.text));
+                Some(uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = notification_params::<DidChangeTextDocument>(not)?;
+                let uri = params.text_document.uri;
+                match self.documents.get_mut(&uri) {
+                    Some(doc) => doc.apply_changes(params.content_changes),
+                    None => {
+                        // without the opened document only the full text can be applied.
+                        let text = params.content_changes.into_iter().last()?.text;
+                        self.documents.insert(uri.clone(), Document::new(text));
+                    }
+                }
+                Some(uri)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = notification_params::<DidCloseTextDocument>(not)?;
+                self.documents.remove(&params.text_document.uri);
+                Some(params.text_document.uri)
+            }
+            _ => None,
+        }
+    }
+
+    fn publish_diagnostics(&self, connection: &Connection, uri: Url) -> Result<()> {
+        let diagnostics = self
+            .documents
+            .get(&uri)
+            .map(|doc| {
+                doc.diagnostics
+                    .iter()
+                    .map(|r| to_diagnostic(r, doc, &uri))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        let not = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        connection.sender.send(Message::Notification(not))?;
+        Ok(())
+    }
+
+    fn definition(&self, params: &TextDocumentPositionParams) -> Option<GotoDefinitionResponse> {
+        let uri = &params.text_document.uri;
+        let doc = self.documents.get(uri)?;
+        let contract = doc.definition.as_ref()?;
+        let offset = doc.lines.offset(&doc.text, params.position);
+        let (name, _) = word_at(&doc.text, offset)?;
+        let target = find_target(contract, name, offset)?;
+        let span = definition_span(contract, &target)?;
+
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            uri.clone(),
+            doc.lines.range(&doc.text, &span),
+        )))
+    }
+
+    fn hover(&self, params: &TextDocumentPositionParams) -> Option<Hover> {
+        let doc = self.documents.get(&params.text_document.uri)?;
+        let contract = doc.definition.as_ref()?;
+        let offset = doc.lines.offset(&doc.text, params.position);
+        let (name, span) = word_at(&doc.text, offset)?;
+        let target = find_target(contract, name, offset)?;
+        let text = describe(contract, &target)?;
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```folidity\n{}\n```", text),
+            }),
+            range: Some(doc.lines.range(&doc.text, &span)),
+        })
+    }
+}
+
+fn params<P: DeserializeOwned>(req: Request) -> Result<P, String> {
+    serde_json::from_value(req.params).map_err(|e| e.to_string())
+}
+
+fn notification_params<N: lsp_types::notification::Notification>(
+    not: Notification,
+) -> Option<N::Params> {
+    serde_json::from_value(not.params).ok()
+}
+
+fn error_response(id: RequestId, message: String) -> Response {
+    Response::new_err(id, lsp_server::ErrorCode::InvalidParams as i32, message)
+}
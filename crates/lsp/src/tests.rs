@@ -0,0 +1,154 @@
+use folidity_diagnostics::{
+    Level,
+    Report,
+};
+use lsp_types::{
+    DiagnosticSeverity,
+    Position,
+    Range,
+    TextDocumentContentChangeEvent,
+    Url,
+};
+
+use crate::{
+    convert::{
+        severity,
+        to_diagnostic,
+    },
+    document::{
+        Document,
+        LineIndex,
+    },
+    navigation::{
+        definition_span,
+        describe,
+        find_target,
+        word_at,
+    },
+};
+
+const COUNTER_SRC: &str = r#"
+state CounterState {
+    counter: int,
+} st [
+    counter < 1000,
+    counter > -1000
+]
+
+@init
+@(any)
+fn () initialise() when () -> CounterState {
+    move CounterState : { 0 };
+}
+
+@(any)
+fn () incr_by(value: int) when (CounterState s) -> CounterState {
+    let new_value = s.counter + value;
+    move CounterState : { new_value };
+}
+"#;
+
+fn lookup(doc: &Document, needle: &str, nth: usize) -> (Option<String>, Option<String>) {
+    let offset = doc.text.match_indices(needle).nth(nth).unwrap().0 + 1;
+    let contract = doc.definition.as_ref().unwrap();
+    let (name, _) = word_at(&doc.text, offset).unwrap();
+    let target = find_target(contract, name, offset).unwrap();
+    let def = definition_span(contract, &target).map(|s| doc.text[s].to_string());
+    let hover = describe(contract, &target);
+    (def, hover)
+}
+
+#[test]
+fn test_line_index() {
+    let text = "ab\nc€d\n";
+    let lines = LineIndex::new(text);
+
+    assert_eq!(lines.position(text, 0), Position::new(0, 0));
+    assert_eq!(lines.position(text, 3), Position::new(1, 0));
+    // `€` is 3 bytes in UTF-8, but a single UTF-16 code unit.
+    assert_eq!(lines.position(text, 7), Position::new(1, 2));
+    assert_eq!(lines.offset(text, Position::new(1, 2)), 7);
+    assert_eq!(lines.offset(text, Position::new(0, 10)), 2);
+    assert_eq!(lines.offset(text, Position::new(5, 0)), text.len());
+}
+
+#[test]
+fn test_diagnostics() {
+    let doc = Document::new("fn () main() { let a = b; }".to_string());
+    assert!(!doc.diagnostics.is_empty());
+
+    let uri = Url::parse("file:///contract.fol").unwrap();
+    let report = Report::semantic_warning(6..10, "Unused.".to_string());
+    let diagnostic = to_diagnostic(&report, &doc, &uri);
+    assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(diagnostic.range.start, Position::new(0, 6));
+    assert!(diagnostic.message.starts_with("Unused."));
+
+    assert_eq!(severity(&Level::Error), DiagnosticSeverity::ERROR);
+    assert_eq!(severity(&Level::Info), DiagnosticSeverity::INFORMATION);
+}
+
+#[test]
+fn test_incremental_changes() {
+    let mut doc = Document::new("fn () main() {\n    let a = b;\n}".to_string());
+    assert!(!doc.diagnostics.is_empty());
+
+    let change = |range: Option<Range>, text: &str| {
+        TextDocumentContentChangeEvent {
+            range,
+            range_length: None,
+            text: text.to_string(),
+        }
+    };
+    doc.apply_changes(vec![
+        // `let a = b;` => `let a = 1;`
+        change(
+            Some(Range::new(Position::new(1, 12), Position::new(1, 13))),
+            "1",
+        ),
+        // the second edit refers to the text after the first one.
+        change(
+            Some(Range::new(Position::new(1, 13), Position::new(1, 13))),
+            " + 2",
+        ),
+    ]);
+    assert_eq!(doc.text, "fn () main() {\n    let a = 1 + 2;\n}");
+    // only the lints of the unused code are left.
+    assert!(
+        doc.diagnostics.iter().all(|r| r.level == Level::Warning),
+        "{:#?}",
+        doc.diagnostics
+    );
+
+    doc.apply_changes(vec![change(None, "fn")]);
+    assert_eq!(doc.text, "fn");
+    assert_eq!(doc.lines.position(&doc.text, 2), Position::new(0, 2));
+}
+
+#[test]
+fn test_navigation() {
+    let mut doc = Document::new(COUNTER_SRC.to_string());
+    assert!(doc.diagnostics.is_empty(), "{:#?}", doc.diagnostics);
+
+    let (def, hover) = lookup(&doc, "CounterState :", 1);
+    assert_eq!(def.as_deref(), Some("CounterState"));
+    assert_eq!(
+        hover.as_deref(),
+        Some("state CounterState { counter: int }")
+    );
+
+    let (def, hover) = lookup(&doc, "new_value }", 0);
+    assert_eq!(def.as_deref(), Some("new_value"));
+    assert_eq!(hover.as_deref(), Some("new_value: int"));
+
+    let (_, hover) = lookup(&doc, "s.counter", 0);
+    assert_eq!(hover.as_deref(), Some("s: state CounterState"));
+
+    let (_, hover) = lookup(&doc, "incr_by", 0);
+    assert_eq!(hover.as_deref(), Some("fn unit incr_by(value: int)"));
+
+    // unparsable source keeps the last definition.
+    doc.update(format!("{COUNTER_SRC} fn"));
+    assert!(!doc.diagnostics.is_empty());
+    assert!(doc.definition.is_some());
+}
@@ -30,6 +30,12 @@ pub enum Declaration {
     StructDeclaration(Box<StructDeclaration>),
     ModelDeclaration(Box<ModelDeclaration>),
     StateDeclaration(Box<StateDeclaration>),
+    ConstDeclaration(Box<ConstDeclaration>),
+    TypeAlias(Box<TypeAlias>),
+    InterfaceDeclaration(Box<InterfaceDeclaration>),
+    EventDeclaration(Box<EventDeclaration>),
+    Import(Box<Import>),
+    TestDeclaration(Box<TestDeclaration>),
     Error(Span),
 }
 
@@ -43,6 +49,10 @@ pub struct Type {
 pub enum TypeVariant {
     Int,
     Uint,
+    U8,
+    U64,
+    I64,
+    U256,
     Float,
     Char,
     String,
@@ -52,6 +62,9 @@ pub enum TypeVariant {
     Bool,
     Set(Set),
     List(List),
+    Option(Optional),
+    /// Types of the elements, e.g. `(int, string)`.
+    Tuple(Vec<Type>),
     Mapping(Mapping),
     Custom(Identifier),
 }
@@ -66,6 +79,12 @@ pub struct List {
     pub ty: Box<Type>,
 }
 
+/// Value that may be missing, e.g. `option<int>`.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Optional {
+    pub ty: Box<Type>,
+}
+
 #[derive(Clone, Debug, PartialEq, Node, Default)]
 pub struct MappingRelation {
     pub loc: Span,
@@ -184,8 +203,12 @@ pub struct FunctionDeclaration {
     pub state_bound: Option<StateBound>,
     /// Function logical bounds
     pub st_block: Option<StBlock>,
+    /// Measure decreasing in the recursive calls, `decreases n`.
+    pub decreases: Option<Expression>,
     /// The body of the function.
     pub body: Statement,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -195,7 +218,101 @@ pub struct EnumDeclaration {
     /// Name of the enum.
     pub name: Identifier,
     /// Variants of the enum.
-    pub variants: Vec<Identifier>,
+    pub variants: Vec<EnumVariant>,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
+}
+
+/// Variant of the enum, e.g. `Circle(radius: int)`.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct EnumVariant {
+    pub loc: Span,
+    pub name: Identifier,
+    /// Fields of the payload, empty for the plain variants.
+    pub fields: Vec<Param>,
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Import {
+    /// Location span of the import.
+    pub loc: Span,
+    /// Path of the imported file relative to the importing one.
+    pub path: String,
+}
+
+/// Test of the contract run off-chain, e.g. `test "increments" { ... }`.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct TestDeclaration {
+    /// Location span of the test.
+    pub loc: Span,
+    /// Name of the test.
+    pub name: String,
+    /// The body of the test.
+    pub body: Statement,
+}
+
+/// Constant of the contract, e.g. `const MAX_SIZE: int = 100;`.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct ConstDeclaration {
+    /// Location span of the constant.
+    pub loc: Span,
+    /// Name of the constant.
+    pub name: Identifier,
+    /// Type annotation, the type of the value is used if `None`.
+    pub ty: Option<Type>,
+    /// Value evaluated at the compile time.
+    pub value: Expression,
+}
+
+/// Name given to the type, e.g. `type Votes = mapping<address -> Choice>`.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct TypeAlias {
+    /// Location span of the alias.
+    pub loc: Span,
+    /// Name of the alias.
+    pub name: Identifier,
+    /// Aliased type.
+    pub ty: Type,
+}
+
+/// Interface of another application, e.g. `interface Token { fn int balance(a: address);
+/// }`.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct InterfaceDeclaration {
+    /// Location span of the interface.
+    pub loc: Span,
+    /// Name of the interface.
+    pub name: Identifier,
+    /// Signatures of the functions of the application.
+    pub functions: Vec<FunctionSignature>,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
+}
+
+/// Event logged by the contract, e.g. `event Transfer { owner: address, amount: int }`.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct EventDeclaration {
+    /// Location span of the event.
+    pub loc: Span,
+    /// Name of the event.
+    pub name: Identifier,
+    /// Fields of the event.
+    pub fields: Vec<Param>,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
+}
+
+/// Function of the interface declared without the body.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct FunctionSignature {
+    /// Location span of the signature.
+    pub loc: Span,
+    /// Function return type declaration.
+    pub return_ty: FuncReturnType,
+    /// Function name.
+    pub name: Identifier,
+    /// List of parameters.
+    pub params: Vec<Param>,
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -206,6 +323,8 @@ pub struct StructDeclaration {
     pub name: Identifier,
     /// Fields of the struct.
     pub fields: Vec<Param>,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -220,6 +339,8 @@ pub struct ModelDeclaration {
     pub parent: Option<Identifier>,
     /// Model logical bounds.
     pub st_block: Option<StBlock>,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -234,6 +355,9 @@ pub enum StateBody {
 pub struct StateDeclaration {
     /// Location span of the model.
     pub loc: Span,
+    /// Is it a final state?
+    /// Marked with `@final`
+    pub is_final: bool,
     /// Model name.
     pub name: Identifier,
     /// Body of the state. Its fields.
@@ -243,6 +367,8 @@ pub struct StateDeclaration {
     pub from: Option<(Identifier, Option<Identifier>)>,
     /// Model logical bounds.
     pub st_block: Option<StBlock>,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -265,11 +391,18 @@ pub enum Statement {
     Assign(Assign),
     IfElse(IfElse),
     ForLoop(ForLoop),
+    While(WhileLoop),
     Iterator(Iterator),
+    Match(Match),
     Return(Return),
     Expression(Expression),
     StateTransition(Expression),
+    /// Log of the event, e.g. `emit Transfer : { owner, amount };`.
+    Emit(StructInit),
+    /// `skip` or `continue`, jumping to the next iteration of the loop.
     Skip(Span),
+    /// `break`, leaving the loop.
+    Break(Span),
 
     Block(StatementBlock),
     Error(Span),
@@ -293,16 +426,42 @@ pub struct Variable {
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct Assign {
     pub loc: Span,
-    pub name: Identifier,
+    /// Assigned variable or its field, e.g. `p.x`.
+    pub target: Expression,
+    /// Operator of the compound assignment, e.g. `+=` or `++`.
+    pub op: Option<AssignOperator>,
     pub value: Expression,
 }
 
+/// Operator combining the target with the assigned value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssignOperator {
+    /// `+=` or `++`
+    Add,
+    /// `-=` or `--`
+    Subtract,
+    /// `*=`
+    Multiply,
+    /// `/=`
+    Divide,
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct IfElse {
     pub loc: Span,
     pub condition: Expression,
     pub body: Box<StatementBlock>,
-    pub else_part: Option<Box<Statement>>,
+    /// Chain of `else if` branches in the order of declaration.
+    pub else_ifs: Vec<ElseIf>,
+    pub else_part: Option<Box<StatementBlock>>,
+}
+
+/// `else if` branch of the conditional statement.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct ElseIf {
+    pub loc: Span,
+    pub condition: Expression,
+    pub body: Box<StatementBlock>,
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -310,7 +469,27 @@ pub struct ForLoop {
     pub loc: Span,
     pub var: Variable,
     pub condition: Expression,
-    pub incrementer: Expression,
+    pub incrementer: Incrementer,
+    /// Loop invariant `invariant [...]`.
+    pub invariant: Option<StBlock>,
+    pub body: Box<StatementBlock>,
+}
+
+/// Update of the loop variable after every iteration.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Incrementer {
+    /// Next value of the variable, e.g. `i + 1`.
+    Value(Expression),
+    /// Assignment of the variable, e.g. `i += 2` or `i++`.
+    Assign(Assign),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct WhileLoop {
+    pub loc: Span,
+    pub condition: Expression,
+    /// Loop invariant `invariant [...]`.
+    pub invariant: Option<StBlock>,
     pub body: Box<StatementBlock>,
 }
 
@@ -319,6 +498,28 @@ pub struct Iterator {
     pub loc: Span,
     pub names: Vec<Identifier>,
     pub list: Expression,
+    /// Loop invariant `invariant [...]`.
+    pub invariant: Option<StBlock>,
+    pub body: Box<StatementBlock>,
+}
+
+/// `match` statement over the variants of the enum.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Match {
+    pub loc: Span,
+    /// Matched expression.
+    pub expr: Expression,
+    pub arms: Vec<MatchArm>,
+}
+
+/// Arm of the `match` statement, e.g. `Circle(r) => { ... }`.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct MatchArm {
+    pub loc: Span,
+    /// Name of the matched variant, `_` matches any variant.
+    pub variant: Identifier,
+    /// Names bound to the fields of the payload.
+    pub bindings: Vec<Identifier>,
     pub body: Box<StatementBlock>,
 }
 
@@ -345,6 +546,10 @@ pub enum Expression {
     Hex(UnaryExpression<String>),
     Address(UnaryExpression<String>),
     List(UnaryExpression<Vec<Expression>>),
+    /// `some(value)` or `none`.
+    Optional(UnaryExpression<Option<Box<Expression>>>),
+    /// Elements of the tuple, e.g. `(a, b)`.
+    Tuple(UnaryExpression<Vec<Expression>>),
 
     // Maths operations.
     Multiply(BinaryExpression),
@@ -371,6 +576,17 @@ pub enum Expression {
     MemberAccess(MemberAccess),
     Pipe(BinaryExpression),
     StructInit(StructInit),
+    VariantInit(VariantInit),
+    Lambda(Lambda),
+    Labelled(Labelled),
+    /// Explicit conversion of the value, e.g. `balance as uint`.
+    Cast(Cast),
+    /// Value selected by the condition, e.g. `if a > b then a else b`.
+    Conditional(Conditional),
+    /// Element of the list, e.g. `xs[i]`.
+    Index(Index),
+    /// Part of the list, e.g. `xs[1..3]`.
+    Slice(Slice),
 }
 
 impl Expression {
@@ -390,12 +606,8 @@ impl Expression {
         Expression::Hex(UnaryExpression::new(start, end, hex.to_string()))
     }
 
-    pub fn new_string(start: usize, end: usize, value: &str) -> Self {
-        let reg = regex::Regex::new(r#"(s\")([\w\W][^"]*)(\")"#).unwrap();
-        let Some((_, [_, string, _])) = reg.captures(value).map(|caps| caps.extract()) else {
-            panic!()
-        };
-        Expression::String(UnaryExpression::new(start, end, string.to_string()))
+    pub fn new_string(start: usize, end: usize, value: String) -> Self {
+        Expression::String(UnaryExpression::new(start, end, value))
     }
 }
 
@@ -409,6 +621,105 @@ pub struct FunctionCall {
     pub args: Vec<Expression>,
 }
 
+/// Anonymous function.
+///
+/// # Example
+/// `|v| v > 10`
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Lambda {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Names of the parameters.
+    pub params: Vec<Identifier>,
+    /// Body of the function.
+    pub body: Box<Expression>,
+}
+
+/// Constraint of the `st` block with a label to refer to it in the reports.
+///
+/// # Example
+/// `"positive_start": start_block > 0`
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Labelled {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Label of the constraint.
+    pub label: Identifier,
+    /// Labelled constraint.
+    pub expr: Box<Expression>,
+}
+
+/// Value of one of the branches selected by the condition.
+///
+/// # Example
+/// `if a > b then a else b`
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Conditional {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Condition selecting the branch.
+    pub condition: Box<Expression>,
+    /// Value if the condition holds.
+    pub then_expr: Box<Expression>,
+    /// Value otherwise.
+    pub else_expr: Box<Expression>,
+}
+
+/// Element of the list at the index, counted from zero.
+///
+/// # Example
+/// `xs[i]`
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Index {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Indexed list.
+    pub expr: Box<Expression>,
+    /// Index of the element.
+    pub index: Box<Expression>,
+}
+
+/// Elements of the list from the start index up to the end one, excluding it.
+///
+/// # Example
+/// `xs[1..3]`, `xs[..n]`
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Slice {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Sliced list.
+    pub expr: Box<Expression>,
+    /// First index of the slice, the start of the list if omitted.
+    pub from: Option<Box<Expression>>,
+    /// Index past the slice, the end of the list if omitted.
+    pub to: Option<Box<Expression>>,
+}
+
+/// Conversion of the value to the type.
+///
+/// # Example
+/// `amount as u64`
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Cast {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Converted expression.
+    pub expr: Box<Expression>,
+    /// Type the value is converted to.
+    pub ty: Type,
+}
+
+/// Variant of the enum constructed with the payload, e.g. `Shape.Circle(5)`.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct VariantInit {
+    pub loc: Span,
+    /// Enum the variant belongs to.
+    pub expr: Box<Expression>,
+    pub variant: Identifier,
+    /// Values of the payload fields.
+    pub args: Vec<Expression>,
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct MemberAccess {
     /// Location of the parent expression.
@@ -463,6 +774,8 @@ impl Expression {
             Expression::Hex(u) => &u.loc,
             Expression::Address(u) => &u.loc,
             Expression::List(u) => &u.loc,
+            Expression::Optional(u) => &u.loc,
+            Expression::Tuple(u) => &u.loc,
             Expression::Multiply(b) => &b.loc,
             Expression::Divide(b) => &b.loc,
             Expression::Modulo(b) => &b.loc,
@@ -482,6 +795,13 @@ impl Expression {
             Expression::MemberAccess(m) => &m.loc,
             Expression::Pipe(b) => &b.loc,
             Expression::StructInit(s) => &s.loc,
+            Expression::VariantInit(v) => &v.loc,
+            Expression::Lambda(l) => &l.loc,
+            Expression::Labelled(l) => &l.loc,
+            Expression::Cast(c) => &c.loc,
+            Expression::Conditional(c) => &c.loc,
+            Expression::Index(i) => &i.loc,
+            Expression::Slice(s) => &s.loc,
         }
     }
 }
@@ -493,12 +813,16 @@ impl Statement {
             Statement::Assign(a) => &a.loc,
             Statement::IfElse(br) => &br.loc,
             Statement::ForLoop(l) => &l.loc,
+            Statement::While(l) => &l.loc,
             Statement::Iterator(i) => &i.loc,
+            Statement::Match(m) => &m.loc,
             Statement::Return(e) => &e.loc,
             Statement::Expression(e) => e.loc(),
             Statement::StateTransition(tr) => tr.loc(),
+            Statement::Emit(e) => &e.loc,
             Statement::Block(b) => &b.loc,
             Statement::Skip(s) => s,
+            Statement::Break(s) => s,
             Statement::Error(s) => s,
         }
     }
@@ -14,9 +14,17 @@ pub enum LogosError {
     #[default]
     InvalidToken,
     InvalidInteger,
+    /// Escape sequence at the range relative to the start of the token.
+    InvalidEscape(Span),
+    InvalidHex,
+    OddHexLength,
+    UnterminatedString,
+    UnterminatedHex,
+    NumberTooLong,
 }
 
-#[derive(Default, Clone, Debug, PartialEq, Error)]
+/// Error of the lexer spanning the offending slice of the input.
+#[derive(Clone, Debug, PartialEq, Error)]
 pub enum LexicalError {
     #[error("Invalid token found")]
     InvalidToken(Span),
@@ -24,14 +32,44 @@ pub enum LexicalError {
     #[error("Invalid integer value")]
     InvalidInteger(Span),
 
-    #[error("Invalid else block. Expected block or `if`")]
-    InvalidElseBlock(Span),
+    #[error("Invalid escape sequence")]
+    InvalidEscape(Span),
 
-    #[default]
-    #[error("Unknown error occurred")]
-    UnknownError,
+    #[error("Invalid hex digits")]
+    InvalidHex(Span),
+
+    #[error("Odd number of hex digits")]
+    OddHexLength(Span),
+
+    #[error("Unterminated string")]
+    UnterminatedString(Span),
+
+    #[error("Unterminated hex literal")]
+    UnterminatedHex(Span),
+
+    #[error("Number is too long")]
+    NumberTooLong(Span),
+}
+
+impl LexicalError {
+    /// Span of the offending slice.
+    pub fn loc(&self) -> &Span {
+        match self {
+            LexicalError::InvalidToken(l)
+            | LexicalError::InvalidInteger(l)
+            | LexicalError::InvalidEscape(l)
+            | LexicalError::InvalidHex(l)
+            | LexicalError::OddHexLength(l)
+            | LexicalError::UnterminatedString(l)
+            | LexicalError::UnterminatedHex(l)
+            | LexicalError::NumberTooLong(l) => l,
+        }
+    }
 }
 
+/// Number of digits of the largest 256-bit unsigned integer.
+pub const MAX_NUMBER_DIGITS: usize = 78;
+
 /// Error type returned by calling `lex.slice().parse()` to u8.
 impl From<ParseIntError> for LogosError {
     fn from(_: ParseIntError) -> Self {
@@ -43,19 +81,26 @@ impl From<ParseIntError> for LogosError {
 pub type Spanned<Tok, Loc> = (Loc, Tok, Loc);
 
 #[derive(Logos, Debug, PartialEq, Clone)]
-#[logos(skip r"[\t\n\f]+")] // Ignore this regex pattern between tokens
+#[logos(skip r"[ \t\r\n\f]+")] // Ignore this regex pattern between tokens
 #[logos(error = LogosError)]
 pub enum Token<'input> {
     // Type values
-    #[regex("-?[0-9]+", |lex| lex.slice(), priority = 2)]
+    #[regex("-?[0-9]+", |lex| number(lex.slice()), priority = 2)]
     Number(&'input str),
     #[regex("-?([0-9]*[.])?[0-9]+", |lex| lex.slice(), priority = 1)]
     Float(&'input str),
     #[regex("\'[a-zA-Z]\'", |lex| lex.slice().parse().ok())]
     Char(char),
-    #[regex(r#"s\"[\w\W][^"]*\""#, |lex| lex.slice())]
-    String(&'input str),
-    #[regex("hex\"[a-zA-Z0-9]+\"", |lex| lex.slice())]
+    /// Contents of the string literal with the escape sequences replaced.
+    #[regex(r#"s"([^"\\]|\\[\s\S])*""#, |lex| unescape(lex.slice(), 2))]
+    #[regex(r#"s"([^"\\]|\\[\s\S])*\\?"#, |_| Err(LogosError::UnterminatedString))]
+    #[regex(r#"r"[^"]*""#, |lex| raw(lex.slice()))]
+    #[regex(r#"r"[^"]*"#, |_| Err(LogosError::UnterminatedString))]
+    String(String),
+    #[regex(r#""[^"]*""#, |lex| lex.slice())]
+    Path(&'input str),
+    #[regex(r#"hex"[^"]+""#, |lex| hex(lex.slice()))]
+    #[regex(r#"hex"[^"]*"#, |_| Err(LogosError::UnterminatedHex))]
     Hex(&'input str),
     #[regex("a\"[a-zA-Z0-9]+\"", |lex| lex.slice())]
     Address(&'input str),
@@ -101,6 +146,20 @@ pub enum Token<'input> {
     #[token("%")]
     Modulo,
 
+    // Assignment ops
+    #[token("+=")]
+    AddAssign,
+    #[token("-=")]
+    SubAssign,
+    #[token("*=")]
+    MulAssign,
+    #[token("/=")]
+    DivAssign,
+    #[token("++")]
+    Increment,
+    #[token("--")]
+    Decrement,
+
     #[token("!")]
     Not,
 
@@ -115,6 +174,8 @@ pub enum Token<'input> {
     Meq,
     #[token("in")]
     In,
+    #[token("as")]
+    As,
 
     // Bool operations
     #[token("||")]
@@ -127,6 +188,14 @@ pub enum Token<'input> {
     IntType,
     #[token("unit")]
     UIntType,
+    #[token("u8")]
+    U8Type,
+    #[token("u64")]
+    U64Type,
+    #[token("i64")]
+    I64Type,
+    #[token("u256")]
+    U256Type,
     #[token("float")]
     FloatType,
     #[token("char")]
@@ -149,6 +218,24 @@ pub enum Token<'input> {
     Set,
     #[token("list")]
     List,
+    #[token("option")]
+    Option,
+    #[token("some")]
+    Some,
+    #[token("none")]
+    None,
+    #[token("const")]
+    Const,
+    #[token("type")]
+    Type,
+    #[token("interface")]
+    Interface,
+    #[token("event")]
+    Event,
+    #[token("emit")]
+    Emit,
+    #[token("test")]
+    Test,
     #[token("struct")]
     Struct,
     #[token("enum")]
@@ -173,6 +260,8 @@ pub enum Token<'input> {
     If,
     #[token("else")]
     Else,
+    #[token("then")]
+    Then,
     #[token("st")]
     St,
     #[token("when")]
@@ -183,6 +272,8 @@ pub enum Token<'input> {
     View,
     #[token("@init")]
     Init,
+    #[token("@final")]
+    Final,
     #[token("version")]
     Version,
     #[token("author")]
@@ -193,10 +284,24 @@ pub enum Token<'input> {
     Mut,
     #[token("skip")]
     Skip,
+    #[token("while")]
+    While,
+    #[token("break")]
+    Break,
+    #[token("continue")]
+    Continue,
+    #[token("invariant")]
+    Invariant,
+    #[token("decreases")]
+    Decreases,
+    #[token("match")]
+    Match,
 
     // Misc chars
     #[token("->")]
     Arr,
+    #[token("=>")]
+    FatArr,
     #[token(";")]
     SemiCol,
     #[token(":")]
@@ -216,10 +321,15 @@ pub enum Token<'input> {
 
     #[token("move")]
     Move,
+    #[token("import")]
+    Import,
 
     // comment
     #[regex(r"#[^\n]*", |lex| lex.slice())]
     Comment(&'input str),
+    /// Documentation of the following declaration, `## ...`.
+    #[regex(r"##[^\n]*", |lex| lex.slice())]
+    DocComment(&'input str),
 }
 
 impl<'input> fmt::Display for Token<'input> {
@@ -230,6 +340,7 @@ impl<'input> fmt::Display for Token<'input> {
             Token::Float(n) => write!(f, "{n}"),
             Token::Char(c) => write!(f, "\'{c}'"),
             Token::String(s) => write!(f, "\"{s}\""),
+            Token::Path(s) => write!(f, "{s}"),
             Token::Hex(s) => write!(f, "hex\"{s}\""),
             Token::Address(s) => write!(f, "hex\"{s}\""),
             Token::Identifier(i) => write!(f, "{i}"),
@@ -249,16 +360,27 @@ impl<'input> fmt::Display for Token<'input> {
             Token::Mul => word("*"),
             Token::Div => word("/"),
             Token::Modulo => word("%"),
+            Token::AddAssign => word("+="),
+            Token::SubAssign => word("-="),
+            Token::MulAssign => word("*="),
+            Token::DivAssign => word("/="),
+            Token::Increment => word("++"),
+            Token::Decrement => word("--"),
             Token::Not => word("!"),
             Token::Eq => word("=="),
-            Token::Neq => word("/="),
+            Token::Neq => word("!="),
             Token::Leq => word("<="),
             Token::Meq => word(">="),
             Token::In => word("in"),
+            Token::As => word("as"),
             Token::Or => word("||"),
             Token::And => word("&&"),
             Token::IntType => word("int"),
             Token::UIntType => word("unit"),
+            Token::U8Type => word("u8"),
+            Token::U64Type => word("u64"),
+            Token::I64Type => word("i64"),
+            Token::U256Type => word("u256"),
             Token::FloatType => word("float"),
             Token::CharType => word("char"),
             Token::StringType => word("string"),
@@ -269,6 +391,15 @@ impl<'input> fmt::Display for Token<'input> {
             Token::Mapping => word("mapping"),
             Token::Set => word("set"),
             Token::List => word("list"),
+            Token::Option => word("option"),
+            Token::Some => word("some"),
+            Token::None => word("none"),
+            Token::Const => word("const"),
+            Token::Type => word("type"),
+            Token::Interface => word("interface"),
+            Token::Event => word("event"),
+            Token::Emit => word("emit"),
+            Token::Test => word("test"),
             Token::Struct => word("struct"),
             Token::Enum => word("enum"),
             Token::Model => word("model"),
@@ -281,17 +412,26 @@ impl<'input> fmt::Display for Token<'input> {
             Token::To => word("to"),
             Token::If => word("if"),
             Token::Else => word("else"),
+            Token::Then => word("then"),
             Token::St => word("st"),
             Token::When => word("when"),
             Token::Pub => word("pub"),
             Token::View => word("view"),
             Token::Init => word("@init"),
+            Token::Final => word("@final"),
             Token::Version => word("version"),
             Token::Author => word("author"),
             Token::Let => word("let"),
             Token::Mut => word("mut"),
             Token::Skip => word("skip"),
+            Token::While => word("while"),
+            Token::Break => word("break"),
+            Token::Continue => word("continue"),
+            Token::Invariant => word("invariant"),
+            Token::Decreases => word("decreases"),
+            Token::Match => word("match"),
             Token::Arr => word("->"),
+            Token::FatArr => word("=>"),
             Token::Col => word(":"),
             Token::SemiCol => word(";"),
             Token::At => word("@"),
@@ -301,7 +441,9 @@ impl<'input> fmt::Display for Token<'input> {
             Token::DoubleDot => word(".."),
             Token::Coma => word(","),
             Token::Move => word("move"),
+            Token::Import => word("import"),
             Token::Comment(c) => write!(f, "{c}"),
+            Token::DocComment(c) => write!(f, "{c}"),
         }
     }
 }
@@ -331,12 +473,20 @@ impl<'input> Iterator for Lexer<'input> {
             match tok_res {
                 Ok(tok) => {
                     match tok {
-                        Token::Comment(_) => self.next(),
+                        Token::Comment(_) | Token::DocComment(_) => self.next(),
                         _ => Some((span.start, tok, span.end)),
                     }
                 }
                 Err(err) => {
-                    self.errors.push(logos_to_lexical_error(&err, &span));
+                    let error = logos_to_lexical_error(&err, &span);
+                    // adjacent invalid characters are reported as a single token.
+                    match (self.errors.last_mut(), &error) {
+                        (
+                            Some(LexicalError::InvalidToken(last)),
+                            LexicalError::InvalidToken(next),
+                        ) if last.end == next.start => last.end = next.end,
+                        _ => self.errors.push(error),
+                    }
                     self.next()
                 }
             }
@@ -346,9 +496,125 @@ impl<'input> Iterator for Lexer<'input> {
     }
 }
 
+/// Doc comments of the input with their spans, the leading `##` is stripped.
+pub fn doc_comments(input: &str) -> Vec<(Span, &str)> {
+    Token::lexer(input)
+        .spanned()
+        .filter_map(|(tok, span)| {
+            match tok {
+                Ok(Token::DocComment(c)) => Some((span, c[2..].trim())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
 fn logos_to_lexical_error(error: &LogosError, span: &Span) -> LexicalError {
     match error {
         LogosError::InvalidToken => LexicalError::InvalidToken(span.clone()),
         LogosError::InvalidInteger => LexicalError::InvalidInteger(span.clone()),
+        LogosError::InvalidEscape(r) => {
+            LexicalError::InvalidEscape(span.start + r.start..span.start + r.end)
+        }
+        LogosError::InvalidHex => LexicalError::InvalidHex(span.clone()),
+        LogosError::OddHexLength => LexicalError::OddHexLength(span.clone()),
+        LogosError::UnterminatedString => LexicalError::UnterminatedString(span.clone()),
+        LogosError::UnterminatedHex => LexicalError::UnterminatedHex(span.clone()),
+        LogosError::NumberTooLong => LexicalError::NumberTooLong(span.clone()),
+    }
+}
+
+/// Replace the escape sequences in the string literal.
+///
+/// `prefix` is the length of the opening of the literal, e.g. `s"`.
+/// The errors are spanned relative to the start of the literal.
+///
+/// # Errors
+/// - Unknown escape sequence.
+/// - `\xNN` is not a hex ASCII code.
+/// - `\u{...}` is not a valid unicode code point of at most 6 hex digits.
+fn unescape(literal: &str, prefix: usize) -> Result<String, LogosError> {
+    let contents = &literal[prefix..literal.len() - 1];
+    let is_hex = |digits: &str| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit());
+    let mut value = String::with_capacity(contents.len());
+    let mut chars = contents.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let invalid = |end: usize| LogosError::InvalidEscape(prefix + i..prefix + end);
+        let Some((j, e)) = chars.next() else {
+            return Err(invalid(contents.len()));
+        };
+        let escaped = match e {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            'x' => {
+                let code = contents
+                    .get(j + 1..j + 3)
+                    .filter(|d| is_hex(d))
+                    .and_then(|d| u8::from_str_radix(d, 16).ok())
+                    .filter(u8::is_ascii)
+                    .ok_or_else(|| invalid((j + 3).min(contents.len())))?;
+                chars.nth(1);
+                char::from(code)
+            }
+            'u' => {
+                let rest = &contents[j + 1..];
+                let end = rest
+                    .find('}')
+                    .filter(|_| rest.starts_with('{'))
+                    .ok_or_else(|| invalid(j + 1))?;
+                let digits = &rest[1..end];
+                let code = Some(digits)
+                    .filter(|d| is_hex(d) && d.len() <= 6)
+                    .and_then(|d| u32::from_str_radix(d, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| invalid(j + 2 + end))?;
+                chars.nth(end);
+                code
+            }
+            _ => return Err(invalid(j + e.len_utf8())),
+        };
+        value.push(escaped);
+    }
+    Ok(value)
+}
+
+/// Contents of the raw string literal, the escape sequences are kept as they are.
+fn raw(literal: &str) -> Result<String, LogosError> {
+    Ok(literal[2..literal.len() - 1].to_string())
+}
+
+/// Check that the integer fits into the largest integer type.
+///
+/// # Errors
+/// - The number has more than [`MAX_NUMBER_DIGITS`] digits.
+fn number(literal: &str) -> Result<&str, LogosError> {
+    if literal.trim_start_matches('-').len() > MAX_NUMBER_DIGITS {
+        return Err(LogosError::NumberTooLong);
+    }
+    Ok(literal)
+}
+
+/// Check that the hex literal consists of the whole bytes.
+///
+/// # Errors
+/// - The literal contains non-hex digits.
+/// - The number of digits is odd.
+fn hex(literal: &str) -> Result<&str, LogosError> {
+    let digits = &literal[4..literal.len() - 1];
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(LogosError::InvalidHex);
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(LogosError::OddHexLength);
     }
+    Ok(literal)
 }
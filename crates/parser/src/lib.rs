@@ -1,7 +1,10 @@
 pub mod ast;
 pub mod lexer;
 
-use ast::Source;
+use ast::{
+    Declaration,
+    Source,
+};
 use folidity_diagnostics::Report;
 use lalrpop_util::{
     lalrpop_mod,
@@ -9,9 +12,11 @@ use lalrpop_util::{
     ParseError,
 };
 use lexer::{
+    doc_comments,
     Lexer,
     LexicalError,
     Token,
+    MAX_NUMBER_DIGITS,
 };
 use std::ops::Range;
 
@@ -36,48 +41,118 @@ pub fn parse(src: &str) -> Result<Source, Vec<Report>> {
     let mut parser_errors: Vec<ErrorRecovery<usize, Token, LexicalError>> = Vec::new();
     let res = folidity::FolidityTreeParser::new().parse(&mut parser_errors, tokens);
 
-    let mut reports: Vec<Report> = Vec::new();
+    let res = res.map_err(|e| parser_error_to_report(&e, src));
+    let parser_reports: Vec<Report> = parser_errors
+        .iter()
+        .map(|pe| parser_error_to_report(&pe.error, src))
+        .collect();
+    // the recovered tokens borrow the lexer errors.
+    drop(parser_errors);
 
-    for pe in &parser_errors {
-        reports.push(parser_error_to_report(&pe.error));
-    }
+    let mut reports: Vec<Report> = lexer_errors
+        .iter()
+        .map(|e| lexer_error_to_report(e, src))
+        .collect();
+    reports.extend(parser_reports);
 
     match res {
         Err(e) => {
-            reports.push(parser_error_to_report(&e));
+            reports.push(e);
             Err(reports)
         }
         // Ok(_) if !reports.is_empty() => Err(reports),
         Ok(mut tree) => {
             tree.diagnostics.extend(reports);
+            attach_docs(&mut tree, src);
             Ok(tree)
         }
     }
 }
 
-impl From<LexicalError> for Report {
-    fn from(value: LexicalError) -> Self {
-        match value {
-            LexicalError::InvalidToken(l) => {
-                Report::lexer_error(l, "Invalid token present".to_string())
-            }
-            LexicalError::InvalidInteger(l) => {
-                Report::lexer_error(l, "Invalid integer present".to_string())
-            }
-            LexicalError::InvalidElseBlock(l) => {
-                Report::lexer_error(l, "Invalid branch block".to_string())
-            }
-            LexicalError::UnknownError => {
-                Report::lexer_error(
-                    Range { start: 0, end: 0 },
-                    "Unknown error occurred".to_string(),
-                )
+/// Attach the doc comments to the declarations following them.
+///
+/// The consecutive `##` lines right before the declaration form its documentation.
+fn attach_docs(tree: &mut Source, src: &str) {
+    let docs = doc_comments(src);
+    for d in &mut tree.declarations {
+        let (loc, doc) = match d {
+            Declaration::FunDeclaration(f) => (&f.loc, &mut f.doc),
+            Declaration::EnumDeclaration(e) => (&e.loc, &mut e.doc),
+            Declaration::StructDeclaration(s) => (&s.loc, &mut s.doc),
+            Declaration::ModelDeclaration(m) => (&m.loc, &mut m.doc),
+            Declaration::StateDeclaration(s) => (&s.loc, &mut s.doc),
+            Declaration::InterfaceDeclaration(i) => (&i.loc, &mut i.doc),
+            Declaration::EventDeclaration(e) => (&e.loc, &mut e.doc),
+            _ => continue,
+        };
+        let mut start = loc.start;
+        let mut lines = vec![];
+        for (span, line) in docs.iter().rev().skip_while(|(s, _)| s.start >= loc.start) {
+            if !src[span.end..start].trim().is_empty() {
+                break;
             }
+            lines.push(*line);
+            start = span.start;
+        }
+        if !lines.is_empty() {
+            lines.reverse();
+            *doc = Some(lines.join("\n"));
+        }
+    }
+}
+
+/// Convert the lexer error into the report quoting the offending slice of the source.
+fn lexer_error_to_report(error: &LexicalError, src: &str) -> Report {
+    let loc = error.loc().clone();
+    let slice = snippet(src, &loc);
+    let message = match error {
+        LexicalError::InvalidToken(_) => format!("Invalid token `{}` present.", slice),
+        LexicalError::InvalidInteger(_) => format!("Invalid integer `{}` present.", slice),
+        LexicalError::InvalidEscape(_) => {
+            format!(
+                "Invalid escape sequence `{}`, expected one of `\\n`, `\\r`, `\\t`, `\\0`, `\\\\`, `\\\"`, `\\'`, `\\xNN` or `\\u{{...}}`.",
+                slice
+            )
+        }
+        LexicalError::InvalidHex(_) => {
+            format!("Hex literal `{}` can only contain hex digits.", slice)
+        }
+        LexicalError::OddHexLength(_) => {
+            format!(
+                "Hex literal `{}` must have an even number of digits.",
+                slice
+            )
+        }
+        LexicalError::UnterminatedString(_) => {
+            format!("String literal `{}` is not terminated.", slice)
         }
+        LexicalError::UnterminatedHex(_) => {
+            format!("Hex literal `{}` is not terminated.", slice)
+        }
+        LexicalError::NumberTooLong(_) => {
+            format!(
+                "Number `{}` is too long, integers can have at most {} digits.",
+                slice, MAX_NUMBER_DIGITS
+            )
+        }
+    };
+    Report::lexer_error(loc, message)
+}
+
+/// First line of the source at the location, shortened to fit into the message.
+fn snippet(src: &str, loc: &Span) -> String {
+    const MAX_LEN: usize = 32;
+    let slice = src.get(loc.clone()).unwrap_or_default();
+    let line = slice.lines().next().unwrap_or_default();
+    if line.chars().count() > MAX_LEN || line.len() < slice.len() {
+        let short: String = line.chars().take(MAX_LEN).collect();
+        format!("{}...", short)
+    } else {
+        line.to_string()
     }
 }
 
-fn parser_error_to_report(error: &ParseError<usize, Token<'_>, LexicalError>) -> Report {
+fn parser_error_to_report(error: &ParseError<usize, Token<'_>, LexicalError>, src: &str) -> Report {
     match error {
         ParseError::InvalidToken { location } => {
             Report::parser_error(*location, *location, "Invalid token found".to_string())
@@ -117,6 +192,6 @@ fn parser_error_to_report(error: &ParseError<usize, Token<'_>, LexicalError>) ->
             let message = format!("Unrecognised token, {}, at this location", token.1);
             Report::parser_error(token.0, token.2, message)
         }
-        ParseError::User { error } => Report::from(error.clone()),
+        ParseError::User { error } => lexer_error_to_report(error, src),
     }
 }
@@ -2,9 +2,11 @@ use crate::{
     ast::{
         self,
         AccessAttribute,
+        AssignOperator,
         BinaryExpression,
         Declaration,
         EnumDeclaration,
+        EnumVariant,
         Expression,
         FuncReturnType,
         FunctionCall,
@@ -12,6 +14,7 @@ use crate::{
         FunctionVisibility,
         Identifier,
         IfElse,
+        Incrementer,
         List,
         Mapping,
         MappingRelation,
@@ -33,7 +36,9 @@ use crate::{
     },
     lexer::{
         Lexer,
+        LexicalError,
         Token,
+        MAX_NUMBER_DIGITS,
     },
     parse,
 };
@@ -74,15 +79,76 @@ fn comment_token() {
 
 #[test]
 fn strings() {
-    let input = "s\"Hello World\" a\"ABC\" hex\"ABC\"";
+    let input = "s\"Hello World\" a\"ABC\" hex\"ABCD\"";
     let mut errors = Vec::new();
     let mut tokens = Lexer::new(input, &mut errors);
     assert_eq!(
         tokens.next(),
-        Some((0, Token::String("s\"Hello World\""), 14))
+        Some((0, Token::String("Hello World".to_string()), 14))
     );
     assert_eq!(tokens.next(), Some((15, Token::Address("a\"ABC\""), 21)));
-    assert_eq!(tokens.next(), Some((22, Token::Hex("hex\"ABC\""), 30)));
+    assert_eq!(tokens.next(), Some((22, Token::Hex("hex\"ABCD\""), 31)));
+}
+
+#[test]
+fn escaped_strings() {
+    let input = r#"s"say \"hi\"\n\x41\u{1F600}" r"C:\dir\n""#;
+    let mut errors = Vec::new();
+    let tokens: Vec<Token> = Lexer::new(input, &mut errors).map(|t| t.1).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::String("say \"hi\"\nA\u{1F600}".to_string()),
+            Token::String("C:\\dir\\n".to_string()),
+        ]
+    );
+    assert!(errors.is_empty());
+
+    let input = r#"s"bad \q" hex"ABC" hex"XY" s"open"#;
+    let mut errors = Vec::new();
+    let tokens: Vec<Token> = Lexer::new(input, &mut errors).map(|t| t.1).collect();
+    assert!(tokens.is_empty());
+    assert_eq!(
+        errors,
+        vec![
+            LexicalError::InvalidEscape(6..8),
+            LexicalError::OddHexLength(10..18),
+            LexicalError::InvalidHex(19..26),
+            LexicalError::UnterminatedString(27..33),
+        ]
+    );
+}
+
+#[test]
+fn lexer_error_spans() {
+    let input = "a $$ b hex\"AB\n";
+    let mut errors = Vec::new();
+    let tokens: Vec<Token> = Lexer::new(input, &mut errors).map(|t| t.1).collect();
+    assert_eq!(tokens, vec![Token::Identifier("a"), Token::Identifier("b")]);
+    assert_eq!(
+        errors,
+        vec![
+            LexicalError::InvalidToken(2..4),
+            LexicalError::UnterminatedHex(7..14),
+        ]
+    );
+
+    // lexer errors are recovered from and reported along with the tree.
+    let reports = |src: &str| {
+        match parse(src) {
+            Ok(tree) => tree.diagnostics,
+            Err(errors) => errors,
+        }
+    };
+    let number = "9".repeat(MAX_NUMBER_DIGITS + 1);
+    let errors = reports(&format!("fn int a() {{ return {}; }}", number));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].loc, 20..20 + number.len());
+    assert!(errors[0].message.starts_with("Number `9999"));
+
+    let errors = reports("fn int a() { return 1 $ 2; }");
+    assert_eq!(errors[0].loc, 22..23);
+    assert_eq!(errors[0].message, "Invalid token `$` present.");
 }
 
 fn unwrap_tree(src: &str) -> Result<Source, String> {
@@ -185,7 +251,9 @@ fn test_factorial_tree() -> Result<(), String> {
         diagnostics: vec![],
         declarations: vec![
             Declaration::StateDeclaration(Box::new(StateDeclaration {
+                doc: None,
                 loc: 1..17,
+                is_final: false,
                 name: Identifier {
                     loc: 7..17,
                     name: "EmptyState".to_string(),
@@ -195,6 +263,7 @@ fn test_factorial_tree() -> Result<(), String> {
                 st_block: None,
             })),
             Declaration::FunDeclaration(Box::new(FunctionDeclaration {
+                doc: None,
                 loc: 19..352,
                 is_init: false,
                 access_attributes: vec![],
@@ -259,6 +328,7 @@ fn test_factorial_tree() -> Result<(), String> {
                         ],
                     )),
                 }),
+                decreases: None,
                 body: Statement::Block(StatementBlock {
                     loc: 93..352,
                     statements: vec![Statement::IfElse(IfElse {
@@ -295,7 +365,8 @@ fn test_factorial_tree() -> Result<(), String> {
                                 }),
                             ],
                         }),
-                        else_part: Some(Box::new(Statement::Block(StatementBlock {
+                        else_ifs: vec![],
+                        else_part: Some(Box::new(StatementBlock {
                             loc: 176..350,
                             statements: vec![Statement::Return(Return {
                                 loc: 186..343,
@@ -345,11 +416,12 @@ fn test_factorial_tree() -> Result<(), String> {
                                     })],
                                 })),
                             })],
-                        }))),
+                        })),
                     })],
                 }),
             })),
             Declaration::FunDeclaration(Box::new(FunctionDeclaration {
+                doc: None,
                 loc: 354..435,
                 is_init: false,
                 access_attributes: vec![AccessAttribute {
@@ -395,6 +467,7 @@ fn test_factorial_tree() -> Result<(), String> {
                         })),
                     }),
                 }),
+                decreases: None,
                 body: Statement::Return(Return {
                     loc: 411..434,
                     expr: Some(Expression::FunctionCall(FunctionCall {
@@ -430,6 +503,7 @@ fn test_lists() -> Result<(), String> {
     let parsed = Source {
         diagnostics: vec![],
         declarations: vec![Declaration::FunDeclaration(Box::new(FunctionDeclaration {
+            doc: None,
             loc: 1..148,
             is_init: false,
             access_attributes: vec![],
@@ -445,6 +519,7 @@ fn test_lists() -> Result<(), String> {
             params: vec![],
             state_bound: None,
             st_block: None,
+            decreases: None,
             body: Statement::Block(StatementBlock {
                 loc: 15..148,
                 statements: vec![
@@ -588,6 +663,7 @@ fn test_structs_enums() -> Result<(), String> {
         diagnostics: vec![],
         declarations: vec![
             Declaration::StructDeclaration(Box::new(StructDeclaration {
+                doc: None,
                 loc: 1..47,
                 name: Identifier {
                     loc: 8..16,
@@ -621,23 +697,33 @@ fn test_structs_enums() -> Result<(), String> {
                 ],
             })),
             Declaration::EnumDeclaration(Box::new(EnumDeclaration {
+                doc: None,
                 loc: 49..78,
                 name: Identifier {
                     loc: 54..60,
                     name: "MyEnum".to_string(),
                 },
                 variants: vec![
-                    Identifier {
+                    EnumVariant {
                         loc: 67..68,
-                        name: "A".to_string(),
+                        name: Identifier {
+                            loc: 67..68,
+                            name: "A".to_string(),
+                        },
+                        fields: vec![],
                     },
-                    Identifier {
+                    EnumVariant {
                         loc: 75..76,
-                        name: "B".to_string(),
+                        name: Identifier {
+                            loc: 75..76,
+                            name: "B".to_string(),
+                        },
+                        fields: vec![],
                     },
                 ],
             })),
             Declaration::FunDeclaration(Box::new(FunctionDeclaration {
+                doc: None,
                 loc: 80..208,
                 is_init: false,
                 access_attributes: vec![],
@@ -653,6 +739,7 @@ fn test_structs_enums() -> Result<(), String> {
                 params: vec![],
                 state_bound: None,
                 st_block: None,
+                decreases: None,
                 body: Statement::Block(StatementBlock {
                     loc: 96..208,
                     statements: vec![
@@ -734,6 +821,7 @@ fn test_structs_enums() -> Result<(), String> {
                 }),
             })),
             Declaration::ModelDeclaration(Box::new(ModelDeclaration {
+                doc: None,
                 loc: 210..240,
                 name: Identifier {
                     loc: 216..223,
@@ -876,8 +964,7 @@ fn () start_reveal() when (VotingState s) -> RevealState {
     move RevealState : {
         endblock + 10,
         proposal,
-        # we need to add lambda to grammar later
-        commits :> map(map_lambda),
+        commits :> map(|item| map_lambda(item)),
         0,
         0
     };
@@ -918,8 +1005,7 @@ st s1.commits.size == s2.commits.size
 @(any)
 fn () execute() when (RevealState s) -> ExecuteState {
     let votes = s.commits.values;
-    # add lambda later
-    # let yay = votes :> filter(|v| v == Choice::Yay).sum();
+    let yay = votes :> filter(|v| v == Choice::Yay) :> size();
     let mut passed = false;
     [1, 2, 3] :> func1 :> func2 :> func3;
     func3();
@@ -952,3 +1038,851 @@ fn parse_complete_program() {
         }
     }
 }
+
+const ELSE_IF_SRC: &str = r#"
+fn int sign(value: int) {
+    if value > 0 {
+        return 1;
+    } else if value < 0 {
+        return -1;
+    } else if value == 0 {
+        return 0;
+    } else {
+        return 2;
+    }
+}
+"#;
+
+#[test]
+fn test_else_if_chain() -> Result<(), String> {
+    let tree = unwrap_tree(ELSE_IF_SRC)?;
+    let Declaration::FunDeclaration(func) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(block) = &func.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Statement::IfElse(branch) = &block.statements[0] else {
+        return Err("Expected branching".to_string());
+    };
+
+    assert_eq!(branch.else_ifs.len(), 2);
+    assert!(matches!(branch.else_ifs[0].condition, Expression::Less(_)));
+    assert!(matches!(branch.else_ifs[1].condition, Expression::Equal(_)));
+    assert_eq!(
+        &ELSE_IF_SRC[branch.else_ifs[0].loc.clone()],
+        "else if value < 0 {\n        return -1;\n    }"
+    );
+    assert!(branch.else_part.is_some());
+    Ok(())
+}
+
+const LAMBDA_SRC: &str = r#"
+fn list<int> scale(l: list<int>, k: int) {
+    return l :> map(|x| x * k) :> filter(|x| x > 0);
+}
+
+fn int pair() {
+    return apply(|a, b| a + b);
+}
+"#;
+
+#[test]
+fn test_lambda() -> Result<(), String> {
+    let tree = unwrap_tree(LAMBDA_SRC)?;
+    let Declaration::FunDeclaration(func) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(block) = &func.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Statement::Return(Return {
+        expr: Some(Expression::Pipe(pipe)),
+        ..
+    }) = &block.statements[0]
+    else {
+        return Err("Expected return of the pipe".to_string());
+    };
+    let Expression::Pipe(inner) = pipe.left.as_ref() else {
+        return Err("Expected pipe".to_string());
+    };
+    let Expression::FunctionCall(map) = inner.right.as_ref() else {
+        return Err("Expected function call".to_string());
+    };
+    let Expression::Lambda(lambda) = &map.args[0] else {
+        return Err("Expected lambda".to_string());
+    };
+    assert_eq!(lambda.params.len(), 1);
+    assert_eq!(lambda.params[0].name, "x");
+    assert!(matches!(lambda.body.as_ref(), Expression::Multiply(_)));
+    assert_eq!(&LAMBDA_SRC[lambda.loc.clone()], "|x| x * k");
+
+    let Declaration::FunDeclaration(func) = &tree.declarations[1] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(block) = &func.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Statement::Return(Return {
+        expr: Some(Expression::FunctionCall(call)),
+        ..
+    }) = &block.statements[0]
+    else {
+        return Err("Expected return of the function call".to_string());
+    };
+    let Expression::Lambda(lambda) = &call.args[0] else {
+        return Err("Expected lambda".to_string());
+    };
+    let names: Vec<&str> = lambda.params.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b"]);
+    assert!(matches!(lambda.body.as_ref(), Expression::Add(_)));
+    Ok(())
+}
+
+const IMPORT_SRC: &str = r#"
+import "models/token.fol"
+
+state Empty
+"#;
+
+#[test]
+fn test_import() -> Result<(), String> {
+    let tree = parse(IMPORT_SRC).map_err(|e| format!("{:#?}", e))?;
+    assert_eq!(tree.declarations.len(), 2);
+    let Declaration::Import(import) = &tree.declarations[0] else {
+        return Err("Expected import".to_string());
+    };
+    assert_eq!(import.path, "models/token.fol");
+    assert_eq!(
+        &IMPORT_SRC[import.loc.clone()],
+        "import \"models/token.fol\""
+    );
+    Ok(())
+}
+
+const LABELLED_SRC: &str = r#"
+model Auction {
+    start_block: int,
+    end_block: int,
+} st [
+    "positive_start": start_block > 0,
+    end_block > start_block,
+]
+"#;
+
+#[test]
+fn test_labelled_constraints() -> Result<(), String> {
+    let tree = unwrap_tree(LABELLED_SRC)?;
+    let Declaration::ModelDeclaration(model) = &tree.declarations[0] else {
+        return Err("Expected model declaration".to_string());
+    };
+    let Some(Expression::List(list)) = model.st_block.as_ref().map(|st| &st.expr) else {
+        return Err("Expected list of constraints".to_string());
+    };
+    let Expression::Labelled(labelled) = &list.element[0] else {
+        return Err("Expected labelled constraint".to_string());
+    };
+    assert_eq!(labelled.label.name, "positive_start");
+    assert_eq!(&LABELLED_SRC[labelled.label.loc.clone()], "positive_start");
+    assert!(matches!(labelled.expr.as_ref(), Expression::Greater(_)));
+    assert!(matches!(&list.element[1], Expression::Greater(_)));
+    Ok(())
+}
+
+const FINAL_SRC: &str = r#"
+@final
+state Closed
+
+state Open {
+    count: int
+}
+"#;
+
+#[test]
+fn test_final_state() -> Result<(), String> {
+    let tree = unwrap_tree(FINAL_SRC)?;
+    let finals: Vec<bool> = tree
+        .declarations
+        .iter()
+        .filter_map(|d| {
+            match d {
+                Declaration::StateDeclaration(s) => Some(s.is_final),
+                _ => None,
+            }
+        })
+        .collect();
+    assert_eq!(finals, vec![true, false]);
+    let Declaration::StateDeclaration(closed) = &tree.declarations[0] else {
+        return Err("Expected state declaration".to_string());
+    };
+    assert_eq!(&FINAL_SRC[closed.loc.clone()], "@final\nstate Closed");
+    Ok(())
+}
+
+const INVARIANT_SRC: &str = r#"
+fn () loops(n: int) {
+    for (let mut i = 0; i < n; i + 1) invariant [i <= n] {
+        skip;
+    }
+}
+"#;
+
+#[test]
+fn test_loop_invariant() -> Result<(), String> {
+    let tree = unwrap_tree(INVARIANT_SRC)?;
+    let Declaration::FunDeclaration(f) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::ForLoop(l)) = body.statements.first() else {
+        return Err("Expected for loop".to_string());
+    };
+    let Some(invariant) = &l.invariant else {
+        return Err("Expected loop invariant".to_string());
+    };
+    assert_eq!(&INVARIANT_SRC[invariant.loc.clone()], "invariant [i <= n]");
+    assert!(matches!(invariant.expr, Expression::List(_)));
+    Ok(())
+}
+
+const WHILE_SRC: &str = r#"
+fn () loops(n: int) {
+    let mut i = 0;
+    while i < n invariant [i <= n] {
+        if i == 2 {
+            continue;
+        }
+        break;
+    }
+}
+"#;
+
+#[test]
+fn test_while_loop() -> Result<(), String> {
+    let tree = unwrap_tree(WHILE_SRC)?;
+    let Declaration::FunDeclaration(f) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::While(l)) = body.statements.get(1) else {
+        return Err("Expected while loop".to_string());
+    };
+    assert_eq!(&WHILE_SRC[l.condition.loc().clone()], "i < n");
+    assert!(l.invariant.is_some());
+    // `continue` is the same statement as `skip`.
+    let Some(Statement::IfElse(b)) = l.body.statements.first() else {
+        return Err("Expected if statement".to_string());
+    };
+    assert!(matches!(b.body.statements[0], Statement::Skip(_)));
+    let Some(Statement::Break(loc)) = l.body.statements.get(1) else {
+        return Err("Expected break".to_string());
+    };
+    assert_eq!(&WHILE_SRC[loc.clone()], "break");
+    Ok(())
+}
+
+const DECREASES_SRC: &str = r#"
+fn int countdown(n: int)
+st n >= 0
+decreases n - 1 = return countdown(n - 1);
+"#;
+
+#[test]
+fn test_decreases() -> Result<(), String> {
+    let tree = unwrap_tree(DECREASES_SRC)?;
+    let Declaration::FunDeclaration(f) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    assert!(f.st_block.is_some());
+    let Some(Expression::Subtract(measure)) = &f.decreases else {
+        return Err("Expected the measure".to_string());
+    };
+    assert_eq!(&DECREASES_SRC[measure.loc.clone()], "n - 1");
+    Ok(())
+}
+
+const FIELD_ASSIGN_SRC: &str = r#"
+fn () update(mut p: Point) {
+    p.x = 5;
+}
+"#;
+
+#[test]
+fn test_field_assign() -> Result<(), String> {
+    let tree = unwrap_tree(FIELD_ASSIGN_SRC)?;
+    let Declaration::FunDeclaration(f) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::Assign(a)) = body.statements.first() else {
+        return Err("Expected assignment".to_string());
+    };
+    let Expression::MemberAccess(m) = &a.target else {
+        return Err("Expected member access".to_string());
+    };
+    assert_eq!(m.member.name, "x");
+    assert_eq!(&FIELD_ASSIGN_SRC[a.target.loc().clone()], "p.x");
+    Ok(())
+}
+
+const MATCH_SRC: &str = r#"
+enum Shape {
+    Circle(radius: int),
+    Empty,
+}
+
+fn int area(s: Shape) {
+    match s {
+        Circle(r) => {
+            return r * r;
+        }
+        _ => {
+            return 0;
+        }
+    }
+}
+
+fn Shape circle() {
+    return Shape.Circle(1);
+}
+"#;
+
+#[test]
+fn test_match() -> Result<(), String> {
+    let tree = unwrap_tree(MATCH_SRC)?;
+    let Declaration::EnumDeclaration(e) = &tree.declarations[0] else {
+        return Err("Expected enum declaration".to_string());
+    };
+    assert_eq!(e.variants.len(), 2);
+    assert_eq!(e.variants[0].fields.len(), 1);
+    assert!(e.variants[1].fields.is_empty());
+
+    let Declaration::FunDeclaration(f) = &tree.declarations[1] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::Match(m)) = body.statements.first() else {
+        return Err("Expected match statement".to_string());
+    };
+    let arms: Vec<(&str, usize)> = m
+        .arms
+        .iter()
+        .map(|a| (a.variant.name.as_str(), a.bindings.len()))
+        .collect();
+    assert_eq!(arms, vec![("Circle", 1), ("_", 0)]);
+
+    let Declaration::FunDeclaration(f) = &tree.declarations[2] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::Return(Return {
+        expr: Some(Expression::VariantInit(v)),
+        ..
+    })) = body.statements.first()
+    else {
+        return Err("Expected variant initialisation".to_string());
+    };
+    assert_eq!(v.variant.name, "Circle");
+    assert_eq!(&MATCH_SRC[v.loc.clone()], "Shape.Circle(1)");
+    Ok(())
+}
+
+const OPTION_SRC: &str = r#"
+fn option<int> find(flag: bool) {
+    if flag {
+        return some(1);
+    }
+    return none;
+}
+
+fn int unwrap(value: option<int>) {
+    match value {
+        some(v) => {
+            return v;
+        }
+        none => {
+            return value :> or(0);
+        }
+    }
+}
+"#;
+
+#[test]
+fn test_option() -> Result<(), String> {
+    let tree = unwrap_tree(OPTION_SRC)?;
+    let Declaration::FunDeclaration(f) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let TypeVariant::Option(o) = f.return_ty.ty() else {
+        return Err("Expected option type".to_string());
+    };
+    assert_eq!(o.ty.ty, TypeVariant::Int);
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::Return(Return {
+        expr: Some(Expression::Optional(none)),
+        ..
+    })) = body.statements.last()
+    else {
+        return Err("Expected `none`".to_string());
+    };
+    assert!(none.element.is_none());
+
+    let Declaration::FunDeclaration(f) = &tree.declarations[1] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::Match(m)) = body.statements.first() else {
+        return Err("Expected match statement".to_string());
+    };
+    let arms: Vec<(&str, usize)> = m
+        .arms
+        .iter()
+        .map(|a| (a.variant.name.as_str(), a.bindings.len()))
+        .collect();
+    assert_eq!(arms, vec![("some", 1), ("none", 0)]);
+    Ok(())
+}
+
+const TUPLE_SRC: &str = r#"
+fn (int, string) pair() {
+    return (1, s"one");
+}
+
+fn int first() {
+    let (a, _) = pair();
+    return a;
+}
+"#;
+
+#[test]
+fn test_tuple() -> Result<(), String> {
+    let tree = unwrap_tree(TUPLE_SRC)?;
+    let Declaration::FunDeclaration(f) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let TypeVariant::Tuple(tys) = f.return_ty.ty() else {
+        return Err("Expected tuple type".to_string());
+    };
+    let tys: Vec<&TypeVariant> = tys.iter().map(|t| &t.ty).collect();
+    assert_eq!(tys, vec![&TypeVariant::Int, &TypeVariant::String]);
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::Return(Return {
+        expr: Some(Expression::Tuple(t)),
+        ..
+    })) = body.statements.first()
+    else {
+        return Err("Expected tuple".to_string());
+    };
+    assert_eq!(t.element.len(), 2);
+
+    let Declaration::FunDeclaration(f) = &tree.declarations[1] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::Variable(v)) = body.statements.first() else {
+        return Err("Expected variable".to_string());
+    };
+    let names: Vec<&str> = v.names.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "_"]);
+    Ok(())
+}
+
+const CONST_SRC: &str = r#"
+const MAX_SIZE: unit = 10 * 10;
+const GREETING = s"hello";
+"#;
+
+#[test]
+fn test_const() -> Result<(), String> {
+    let tree = unwrap_tree(CONST_SRC)?;
+    let Declaration::ConstDeclaration(c) = &tree.declarations[0] else {
+        return Err("Expected constant declaration".to_string());
+    };
+    assert_eq!(c.name.name, "MAX_SIZE");
+    assert_eq!(c.ty.as_ref().map(|t| &t.ty), Some(&TypeVariant::Uint));
+    assert!(matches!(c.value, Expression::Multiply(_)));
+
+    let Declaration::ConstDeclaration(c) = &tree.declarations[1] else {
+        return Err("Expected constant declaration".to_string());
+    };
+    assert!(c.ty.is_none());
+    assert!(matches!(c.value, Expression::String(_)));
+    Ok(())
+}
+
+const ALIAS_SRC: &str = r#"
+type Votes = mapping<address -> int>
+
+model Election {
+    votes: Votes
+}
+"#;
+
+#[test]
+fn test_type_alias() -> Result<(), String> {
+    let tree = unwrap_tree(ALIAS_SRC)?;
+    let Declaration::TypeAlias(alias) = &tree.declarations[0] else {
+        return Err("Expected type alias".to_string());
+    };
+    assert_eq!(alias.name.name, "Votes");
+    assert!(matches!(alias.ty.ty, TypeVariant::Mapping(_)));
+
+    let Declaration::ModelDeclaration(m) = &tree.declarations[1] else {
+        return Err("Expected model declaration".to_string());
+    };
+    let TypeVariant::Custom(ident) = &m.fields[0].ty.ty else {
+        return Err("Expected the alias to be referenced".to_string());
+    };
+    assert_eq!(ident.name, "Votes");
+    Ok(())
+}
+
+const INTERFACE_SRC: &str = r#"
+interface Token {
+    fn int balance(owner: address);
+    fn () transfer(receiver: address, amount: int);
+}
+
+fn int balance_of(app: unit, owner: address) {
+    return Token(app).balance(owner);
+}
+"#;
+
+#[test]
+fn test_interface() -> Result<(), String> {
+    let tree = unwrap_tree(INTERFACE_SRC)?;
+    let Declaration::InterfaceDeclaration(interface) = &tree.declarations[0] else {
+        return Err("Expected interface declaration".to_string());
+    };
+    assert_eq!(interface.name.name, "Token");
+    let names: Vec<&str> = interface
+        .functions
+        .iter()
+        .map(|f| f.name.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["balance", "transfer"]);
+    assert!(tree.diagnostics.is_empty());
+    assert_eq!(interface.functions[1].params.len(), 2);
+
+    // the call is parsed as the function of the application called on the interface.
+    let Declaration::FunDeclaration(f) = &tree.declarations[1] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(block) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Statement::Return(Return {
+        expr: Some(Expression::VariantInit(call)),
+        ..
+    }) = &block.statements[0]
+    else {
+        return Err("Expected call of the interface function".to_string());
+    };
+    assert_eq!(call.variant.name, "balance");
+    assert!(matches!(call.expr.as_ref(), Expression::FunctionCall(c) if c.name.name == "Token"));
+    Ok(())
+}
+
+const DOC_SRC: &str = r#"
+## Ballot of the election.
+## Counts the votes.
+struct Ballot {
+    votes: int
+}
+
+## Detached from the declaration.
+
+# plain comment
+@(any)
+fn int count() {
+    ## Not a declaration.
+    return 0;
+}
+"#;
+
+#[test]
+fn test_doc_comments() -> Result<(), String> {
+    let tree = unwrap_tree(DOC_SRC)?;
+    let Declaration::StructDeclaration(s) = &tree.declarations[0] else {
+        return Err("Expected struct declaration".to_string());
+    };
+    assert_eq!(
+        s.doc.as_deref(),
+        Some("Ballot of the election.\nCounts the votes.")
+    );
+    let Declaration::FunDeclaration(f) = &tree.declarations[1] else {
+        return Err("Expected function declaration".to_string());
+    };
+    assert_eq!(f.doc, None);
+    Ok(())
+}
+
+const RECOVERY_SRC: &str = r#"
+fn int first(x: int) {
+    let y = foo(1 2 3);
+    if x > {
+        return 1;
+    }
+    return y +;
+}
+
+model M: {
+    a: int,
+}
+
+fn int second(s: Shape) {
+    match s {
+        A => 1,
+    }
+    move S : { 1 2 };
+    return 1
+}
+
+struct P {
+    a: int,
+}
+"#;
+
+#[test]
+fn test_error_recovery() -> Result<(), String> {
+    let tree = unwrap_tree(RECOVERY_SRC)?;
+    let errors: Vec<&str> = tree
+        .diagnostics
+        .iter()
+        .map(|d| &RECOVERY_SRC[d.loc.clone()])
+        .collect();
+    assert_eq!(errors, vec!["2", "{", ";", "{", "1", "2", "}"]);
+
+    // declarations around the broken ones are still parsed.
+    assert_eq!(tree.declarations.len(), 4);
+    assert!(matches!(
+        &tree.declarations[3],
+        Declaration::StructDeclaration(s) if s.name.name == "P"
+    ));
+    Ok(())
+}
+
+const EVENT_SRC: &str = r#"
+## Tokens moved between the accounts.
+event Transfer { receiver: address, amount: int }
+
+@(any)
+fn () pay(receiver: address) {
+    emit Transfer : { receiver, 10 };
+}
+"#;
+
+#[test]
+fn test_event() -> Result<(), String> {
+    let tree = unwrap_tree(EVENT_SRC)?;
+    let Declaration::EventDeclaration(event) = &tree.declarations[0] else {
+        return Err("Expected event declaration".to_string());
+    };
+    assert_eq!(event.name.name, "Transfer");
+    assert_eq!(event.fields.len(), 2);
+    assert_eq!(
+        event.doc.as_deref(),
+        Some("Tokens moved between the accounts.")
+    );
+
+    let Declaration::FunDeclaration(f) = &tree.declarations[1] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(block) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Statement::Emit(init) = &block.statements[0] else {
+        return Err("Expected emit statement".to_string());
+    };
+    assert_eq!(init.name.name, "Transfer");
+    assert_eq!(init.args.len(), 2);
+    Ok(())
+}
+
+const CAST_SRC: &str = r#"
+fn u64 convert(a: int, b: int) {
+    return a * b as u64;
+}
+"#;
+
+#[test]
+fn test_cast() -> Result<(), String> {
+    let tree = unwrap_tree(CAST_SRC)?;
+    let Declaration::FunDeclaration(f) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::Return(ret)) = body.statements.first() else {
+        return Err("Expected return".to_string());
+    };
+    // `as` binds tighter than the arithmetic.
+    let Some(Expression::Multiply(m)) = &ret.expr else {
+        return Err("Expected multiplication".to_string());
+    };
+    let Expression::Cast(c) = m.right.as_ref() else {
+        return Err("Expected cast".to_string());
+    };
+    assert_eq!(&CAST_SRC[c.loc.clone()], "b as u64");
+    assert_eq!(c.ty.ty, TypeVariant::U64);
+    Ok(())
+}
+
+const TEST_BLOCK_SRC: &str = r#"
+test "counter increments" {
+    initialise();
+    assert(get_value() == 0);
+}
+"#;
+
+#[test]
+fn test_test_block() -> Result<(), String> {
+    let tree = unwrap_tree(TEST_BLOCK_SRC)?;
+    let Declaration::TestDeclaration(t) = &tree.declarations[0] else {
+        return Err("Expected test declaration".to_string());
+    };
+    assert_eq!(t.name, "counter increments");
+    let Statement::Block(body) = &t.body else {
+        return Err("Expected test body".to_string());
+    };
+    assert_eq!(body.statements.len(), 2);
+    Ok(())
+}
+
+const COMPOUND_ASSIGN_SRC: &str = r#"
+fn () update(mut total: int) {
+    for (let mut i = 0; i < 10; i++) {
+        total *= i;
+    }
+    total--;
+}
+"#;
+
+#[test]
+fn test_compound_assign() -> Result<(), String> {
+    let tree = unwrap_tree(COMPOUND_ASSIGN_SRC)?;
+    let Declaration::FunDeclaration(f) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::ForLoop(l)) = body.statements.first() else {
+        return Err("Expected for loop".to_string());
+    };
+    let Incrementer::Assign(incr) = &l.incrementer else {
+        return Err("Expected the incrementing assignment".to_string());
+    };
+    assert_eq!(incr.op, Some(AssignOperator::Add));
+    assert_eq!(&COMPOUND_ASSIGN_SRC[incr.value.loc().clone()], "++");
+    let Some(Statement::Assign(a)) = l.body.statements.first() else {
+        return Err("Expected assignment".to_string());
+    };
+    assert_eq!(a.op, Some(AssignOperator::Multiply));
+    assert_eq!(&COMPOUND_ASSIGN_SRC[a.value.loc().clone()], "i");
+    let Some(Statement::Assign(a)) = body.statements.get(1) else {
+        return Err("Expected assignment".to_string());
+    };
+    assert_eq!(a.op, Some(AssignOperator::Subtract));
+    Ok(())
+}
+
+const CONDITIONAL_SRC: &str = r#"
+fn int clamp(a: int, max: int) {
+    return if a > max then max else if a < 0 then 0 else a;
+}
+"#;
+
+#[test]
+fn test_conditional() -> Result<(), String> {
+    let tree = unwrap_tree(CONDITIONAL_SRC)?;
+    let Declaration::FunDeclaration(f) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::Return(Return {
+        expr: Some(Expression::Conditional(c)),
+        ..
+    })) = body.statements.first()
+    else {
+        return Err("Expected the conditional expression".to_string());
+    };
+    assert_eq!(&CONDITIONAL_SRC[c.condition.loc().clone()], "a > max");
+    let Expression::Conditional(nested) = c.else_expr.as_ref() else {
+        return Err("Expected the nested conditional expression".to_string());
+    };
+    assert_eq!(
+        &CONDITIONAL_SRC[nested.loc.clone()],
+        "if a < 0 then 0 else a"
+    );
+    Ok(())
+}
+
+const INDEXING_SRC: &str = r#"
+fn list<int> window(xs: list<int>, n: unit) {
+    let first = xs[0];
+    let middle = xs[1..n];
+    return xs[..n];
+}
+"#;
+
+#[test]
+fn test_indexing() -> Result<(), String> {
+    let tree = unwrap_tree(INDEXING_SRC)?;
+    let Declaration::FunDeclaration(f) = &tree.declarations[0] else {
+        return Err("Expected function declaration".to_string());
+    };
+    let Statement::Block(body) = &f.body else {
+        return Err("Expected function body".to_string());
+    };
+    let Some(Statement::Variable(Variable {
+        value: Some(Expression::Index(i)),
+        ..
+    })) = body.statements.first()
+    else {
+        return Err("Expected the index".to_string());
+    };
+    assert_eq!(&INDEXING_SRC[i.index.loc().clone()], "0");
+
+    let Some(Statement::Variable(Variable {
+        value: Some(Expression::Slice(s)),
+        ..
+    })) = body.statements.get(1)
+    else {
+        return Err("Expected the slice".to_string());
+    };
+    assert_eq!(&INDEXING_SRC[s.loc.clone()], "xs[1..n]");
+    assert!(s.from.is_some() && s.to.is_some());
+
+    let Some(Statement::Return(Return {
+        expr: Some(Expression::Slice(s)),
+        ..
+    })) = body.statements.get(2)
+    else {
+        return Err("Expected the slice".to_string());
+    };
+    assert!(s.from.is_none());
+    assert_eq!(&INDEXING_SRC[s.to.as_ref().unwrap().loc().clone()], "n");
+    Ok(())
+}
@@ -16,6 +16,7 @@ use num_bigint::{
 use num_rational::BigRational;
 
 use crate::{
+    builtin::BuiltinFunction,
     contract::ContractDefinition,
     expression::resolve_nested_fields,
     global_symbol::SymbolInfo,
@@ -34,6 +35,10 @@ pub enum TypeVariant {
     #[default]
     Int,
     Uint,
+    U8,
+    U64,
+    I64,
+    U256,
     Float,
     Char,
     String,
@@ -43,6 +48,10 @@ pub enum TypeVariant {
     Bool,
     Set(Box<TypeVariant>),
     List(Box<TypeVariant>),
+    /// Value that may be missing.
+    Option(Box<TypeVariant>),
+    /// Types of the elements of the tuple.
+    Tuple(Vec<TypeVariant>),
     Mapping(Mapping),
     Function(FunctionType),
     Struct(SymbolInfo),
@@ -65,6 +74,10 @@ impl TypeVariant {
             &self,
             TypeVariant::Int
                 | TypeVariant::Uint
+                | TypeVariant::U8
+                | TypeVariant::U64
+                | TypeVariant::I64
+                | TypeVariant::U256
                 | TypeVariant::Float
                 | TypeVariant::Char
                 | TypeVariant::String
@@ -87,11 +100,79 @@ impl TypeVariant {
         )
     }
 
+    /// Is data type an integer of any width.
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            &self,
+            TypeVariant::Int
+                | TypeVariant::Uint
+                | TypeVariant::U8
+                | TypeVariant::U64
+                | TypeVariant::I64
+                | TypeVariant::U256
+        )
+    }
+
+    /// Is data type a signed integer.
+    pub fn is_signed(&self) -> bool {
+        matches!(&self, TypeVariant::Int | TypeVariant::I64)
+    }
+
+    /// Inclusive range of the values of the integer type.
+    ///
+    /// The bound is `None` if the integer is unbounded in that direction.
+    pub fn int_range(&self) -> Option<(Option<BigInt>, Option<BigInt>)> {
+        let range = |min: BigInt, max: BigInt| Some((Some(min), Some(max)));
+        match self {
+            TypeVariant::Int => Some((None, None)),
+            TypeVariant::Uint => Some((Some(BigInt::from(0)), None)),
+            TypeVariant::U8 => range(BigInt::from(0), BigInt::from(u8::MAX)),
+            TypeVariant::U64 => range(BigInt::from(0), BigInt::from(u64::MAX)),
+            TypeVariant::I64 => range(BigInt::from(i64::MIN), BigInt::from(i64::MAX)),
+            TypeVariant::U256 => range(BigInt::from(0), (BigInt::from(1) << 256) - 1),
+            _ => None,
+        }
+    }
+
+    /// Can the value of the sized integer type be implicitly converted to the other one.
+    ///
+    /// The conversion is allowed if it is lossless, i.e. the range of the other type
+    /// contains the range of this type.
+    /// Integers of arbitrary precision are never converted implicitly.
+    pub fn widens_to(&self, other: &TypeVariant) -> bool {
+        let is_sized = |ty: &TypeVariant| !matches!(ty, TypeVariant::Int | TypeVariant::Uint);
+        if self == other || !is_sized(self) {
+            return false;
+        }
+        let (Some((from_min, from_max)), Some((to_min, to_max))) =
+            (self.int_range(), other.int_range())
+        else {
+            return false;
+        };
+        let min_fits = match (from_min, to_min) {
+            (_, None) => true,
+            (Some(a), Some(b)) => a >= b,
+            (None, Some(_)) => false,
+        };
+        let max_fits = match (from_max, to_max) {
+            (_, None) => true,
+            (Some(a), Some(b)) => a <= b,
+            (None, Some(_)) => false,
+        };
+        min_fits && max_fits
+    }
+
     /// Find the set of dependent user defined types that are encapsulated by this type.
     pub fn custom_type_dependencies(&self) -> HashSet<usize> {
         match &self {
             TypeVariant::Set(ty) => ty.custom_type_dependencies(),
             TypeVariant::List(ty) => ty.custom_type_dependencies(),
+            TypeVariant::Option(ty) => ty.custom_type_dependencies(),
+            TypeVariant::Tuple(tys) => {
+                tys.iter()
+                    .flat_map(|ty| ty.custom_type_dependencies())
+                    .collect()
+            }
             TypeVariant::Mapping(m) => {
                 let mut set = m.from_ty.custom_type_dependencies();
                 set.extend(m.to_ty.custom_type_dependencies());
@@ -198,6 +279,8 @@ pub struct Bounds {
     pub loc: Span,
     /// Final state
     pub exprs: Vec<Expression>,
+    /// Labels of the constraints in the order of `exprs`.
+    pub labels: Vec<Option<String>>,
 }
 
 #[derive(Clone, Debug)]
@@ -221,10 +304,14 @@ pub struct Function {
     pub bounds: Option<Bounds>,
     /// Bounds for the state transition.
     pub state_bound: Option<StateBound>,
+    /// Measure decreasing in the recursive calls.
+    pub decreases: Option<Expression>,
     /// The body of the function.
     pub body: Vec<Statement>,
     /// Scope table for the function context.
     pub scope: Scope,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
 }
 
 impl Function {
@@ -246,9 +333,11 @@ impl Function {
             name,
             params,
             state_bound,
+            decreases: None,
             body: Vec::new(),
             bounds: None,
             scope: Scope::default(),
+            doc: None,
         }
     }
 }
@@ -260,7 +349,94 @@ pub struct EnumDeclaration {
     /// Name of the enum.
     pub name: Identifier,
     /// Variants of the enum.
-    pub variants: IndexMap<String, Span>,
+    pub variants: IndexMap<String, EnumVariant>,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
+}
+
+/// Variant of the enum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumVariant {
+    /// Location span of the variant.
+    pub loc: Span,
+    /// Fields of the payload, empty for the plain variants.
+    pub fields: Vec<Param>,
+}
+
+/// Constant of the contract, its value is inlined where it is referenced.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstDeclaration {
+    /// Location span of the constant.
+    pub loc: Span,
+    /// Name of the constant.
+    pub name: Identifier,
+    /// Type of the constant.
+    pub ty: TypeVariant,
+    /// Literal value evaluated at the compile time.
+    pub value: Expression,
+}
+
+/// Name given to the type, it is resolved to the aliased type where it is referenced.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeAlias {
+    /// Location span of the alias.
+    pub loc: Span,
+    /// Name of the alias.
+    pub name: Identifier,
+    /// Aliased type.
+    pub ty: TypeVariant,
+}
+
+/// Event logged by the contract.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventDeclaration {
+    /// Location span of the event.
+    pub loc: Span,
+    /// Name of the event.
+    pub name: Identifier,
+    /// Fields of the event, only the primitive types are allowed.
+    pub fields: Vec<Param>,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
+}
+
+/// Test of the contract run by the interpreter.
+#[derive(Clone, Debug)]
+pub struct TestDeclaration {
+    /// Location span of the test.
+    pub loc: Span,
+    /// Name of the test.
+    pub name: String,
+    /// The body of the test.
+    pub body: Vec<Statement>,
+    /// Scope table for the test context.
+    pub scope: Scope,
+}
+
+/// Interface of another application the contract can call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterfaceDeclaration {
+    /// Location span of the interface.
+    pub loc: Span,
+    /// Name of the interface.
+    pub name: Identifier,
+    /// Signatures of the functions of the application.
+    pub functions: IndexMap<String, InterfaceFunction>,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
+}
+
+/// Function of the called application.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterfaceFunction {
+    /// Location span of the signature.
+    pub loc: Span,
+    /// Function name.
+    pub name: Identifier,
+    /// Function parameters, only the primitive types are allowed.
+    pub params: IndexMap<String, Param>,
+    /// Return type of the function.
+    pub return_ty: FuncReturnType,
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -271,6 +447,8 @@ pub struct StructDeclaration {
     pub name: Identifier,
     /// Fields of the struct.
     pub fields: Vec<Param>,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
 }
 
 #[derive(Clone, Debug, Node)]
@@ -290,6 +468,8 @@ pub struct ModelDeclaration {
     pub recursive_parent: bool,
     /// Scope table for the bounds context.
     pub scope: Scope,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
 }
 
 impl ModelDeclaration {
@@ -314,6 +494,9 @@ pub enum StateBody {
 pub struct StateDeclaration {
     /// Location span of the model.
     pub loc: Span,
+    /// Is it a final state?
+    /// Marked with `@final`, the contract is not expected to leave it.
+    pub is_final: bool,
     /// Model name.
     pub name: Identifier,
     /// Body of the state. Its fields.
@@ -327,6 +510,8 @@ pub struct StateDeclaration {
     pub recursive_parent: bool,
     /// Scope table for the bounds context.
     pub scope: Scope,
+    /// Documentation given with the `##` comments.
+    pub doc: Option<String>,
 }
 
 impl StateDeclaration {
@@ -363,16 +548,33 @@ pub enum Statement {
     Assign(Assign),
     IfElse(IfElse),
     ForLoop(ForLoop),
+    While(WhileLoop),
     Iterator(Iterator),
+    Match(Match),
     Return(Return),
     Expression(Expression),
     StateTransition(Expression),
+    /// Log of the event.
+    Emit(Emit),
 
     Block(StatementBlock),
+    /// Jump to the next iteration of the loop.
     Skip(Span),
+    /// Leave the loop.
+    Break(Span),
     Error(Span),
 }
 
+/// Log of the event with its fields.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Emit {
+    pub loc: Span,
+    /// Symbol of the event.
+    pub sym: SymbolInfo,
+    /// Values of the fields in the declaration order.
+    pub args: Vec<Expression>,
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct StatementBlock {
     pub loc: Span,
@@ -394,6 +596,9 @@ pub struct Assign {
     pub loc: Span,
     pub name: Identifier,
     pub pos: usize,
+    /// Indices of the assigned fields of the variable, e.g. `p.x.y = v`,
+    /// empty if the variable itself is assigned.
+    pub fields: Vec<usize>,
     pub value: Expression,
 }
 
@@ -411,6 +616,17 @@ pub struct ForLoop {
     pub var: Variable,
     pub condition: Expression,
     pub incrementer: Expression,
+    /// Constraints holding before every iteration and after the loop.
+    pub invariant: Option<Bounds>,
+    pub body: Vec<Statement>,
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct WhileLoop {
+    pub loc: Span,
+    pub condition: Expression,
+    /// Constraints holding before every iteration and after the loop.
+    pub invariant: Option<Bounds>,
     pub body: Vec<Statement>,
 }
 
@@ -419,6 +635,28 @@ pub struct Iterator {
     pub loc: Span,
     pub names: Vec<Identifier>,
     pub list: Expression,
+    /// Constraints holding before every iteration and after the loop.
+    pub invariant: Option<Bounds>,
+    pub body: Vec<Statement>,
+}
+
+/// `match` statement over the variants of the enum or the option.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Match {
+    pub loc: Span,
+    /// Matched expression of the enum or the option type.
+    pub expr: Expression,
+    pub arms: Vec<MatchArm>,
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct MatchArm {
+    pub loc: Span,
+    /// Index of the matched variant, `None` for `_` matching the rest of them.
+    /// The variants of the option are `none` and `some`, in this order.
+    pub variant: Option<usize>,
+    /// Indices of the payload fields and of the variables bound to them.
+    pub bindings: Vec<(usize, usize)>,
     pub body: Vec<Statement>,
 }
 
@@ -474,10 +712,37 @@ pub enum Expression {
     And(BinaryExpression),
 
     FunctionCall(FunctionCall),
+    BuiltinCall(BuiltinCall),
+    /// Call of the function of another application, e.g. `Token(app).balance(a)`.
+    InterfaceCall(InterfaceCall),
     MemberAccess(MemberAccess),
     StructInit(StructInit),
+    VariantInit(VariantInit),
+    Lambda(Lambda),
+    /// Value selected by the condition, e.g. `if a > b then a else b`.
+    Conditional(Conditional),
+    /// Element of the list, e.g. `xs[i]`.
+    Index(Index),
+    /// Part of the list, e.g. `xs[1..3]`.
+    Slice(Slice),
 
     List(UnaryExpression<Vec<Expression>>),
+    /// `some(value)` or `none`.
+    Optional(UnaryExpression<Option<Box<Expression>>>),
+    /// Elements of the tuple, e.g. `(a, b)`.
+    Tuple(UnaryExpression<Vec<Expression>>),
+}
+
+/// Variant of the enum constructed with its payload.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct VariantInit {
+    pub loc: Span,
+    /// Index of the variant.
+    pub variant: usize,
+    /// Values of the payload fields.
+    pub args: Vec<Expression>,
+    /// Type of an expression.
+    pub ty: TypeVariant,
 }
 
 /// Represents unary style expression.
@@ -518,6 +783,92 @@ pub struct FunctionCall {
     pub returns: TypeVariant,
 }
 
+/// Call of the function declared in the interface.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct InterfaceCall {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Symbol of the interface.
+    pub sym: SymbolInfo,
+    /// Index of the function in the interface.
+    pub func: usize,
+    /// Id of the called application.
+    pub app: Box<Expression>,
+    /// List of arguments.
+    pub args: Vec<Expression>,
+    pub returns: TypeVariant,
+}
+
+/// Call of the built-in function.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct BuiltinCall {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Called built-in function.
+    pub func: BuiltinFunction,
+    /// List of arguments.
+    pub args: Vec<Expression>,
+    pub returns: TypeVariant,
+}
+
+/// Value of one of the branches selected by the condition.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Conditional {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Condition selecting the branch.
+    pub condition: Box<Expression>,
+    /// Value if the condition holds.
+    pub then_expr: Box<Expression>,
+    /// Value otherwise.
+    pub else_expr: Box<Expression>,
+    /// Type of both branches.
+    pub ty: TypeVariant,
+}
+
+/// Element of the list at the index.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Index {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Indexed list.
+    pub expr: Box<Expression>,
+    /// Index of the element, of `uint` type.
+    pub index: Box<Expression>,
+    /// Type of the element.
+    pub ty: TypeVariant,
+}
+
+/// Elements of the list between the indices, excluding the end one.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Slice {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Sliced list.
+    pub expr: Box<Expression>,
+    /// First index of the slice, the start of the list if omitted.
+    pub from: Option<Box<Expression>>,
+    /// Index past the slice, the end of the list if omitted.
+    pub to: Option<Box<Expression>>,
+    /// Type of the list.
+    pub ty: TypeVariant,
+}
+
+/// Anonymous function.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct Lambda {
+    /// Location of the parent expression.
+    pub loc: Span,
+    /// Indices of the parameters in the scope table.
+    pub params: Vec<usize>,
+    /// Body of the function.
+    pub body: Box<Expression>,
+    /// Variables of the enclosing scopes referenced in the body.
+    pub captures: Vec<usize>,
+    /// Type of an expression.
+    pub ty: TypeVariant,
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct MemberAccess {
     /// Location of the parent expression.
@@ -531,11 +882,65 @@ pub struct MemberAccess {
 }
 
 impl TypeVariant {
+    /// Fields of the struct, model or state.
+    pub fn fields(&self, contract: &ContractDefinition) -> Option<Vec<Param>> {
+        match self {
+            TypeVariant::Struct(s) => Some(contract.structs[s.i].fields.clone()),
+            TypeVariant::Model(s) => Some(contract.models[s.i].fields(contract)),
+            TypeVariant::State(s) => Some(contract.states[s.i].fields(contract)),
+            _ => None,
+        }
+    }
+
+    /// Can the values of the type be compared for equality.
+    ///
+    /// Aggregates are compared structurally, so none of their parts can be a mapping
+    /// or a function.
+    pub fn is_equatable(&self, contract: &ContractDefinition) -> bool {
+        self.equatable(contract, &mut vec![])
+    }
+
+    fn equatable(&self, contract: &ContractDefinition, seen: &mut Vec<TypeVariant>) -> bool {
+        // recursive types are checked once.
+        if seen.contains(self) {
+            return true;
+        }
+        seen.push(self.clone());
+        match self {
+            TypeVariant::Mapping(_)
+            | TypeVariant::Function(_)
+            | TypeVariant::Generic(_)
+            | TypeVariant::Unit => false,
+            TypeVariant::List(ty) | TypeVariant::Set(ty) | TypeVariant::Option(ty) => {
+                ty.equatable(contract, seen)
+            }
+            TypeVariant::Tuple(tys) => tys.iter().all(|ty| ty.equatable(contract, seen)),
+            TypeVariant::Enum(s) => {
+                contract.enums[s.i]
+                    .variants
+                    .values()
+                    .flat_map(|v| &v.fields)
+                    .all(|f| f.ty.ty.equatable(contract, seen))
+            }
+            TypeVariant::Struct(_) | TypeVariant::Model(_) | TypeVariant::State(_) => {
+                self.fields(contract)
+                    .unwrap_or_default()
+                    .iter()
+                    .all(|f| f.ty.ty.equatable(contract, seen))
+            }
+            _ => true,
+        }
+    }
+
     pub fn display(&self, contract: &ContractDefinition) -> String {
         let word = |s: &str| -> String { s.to_string() };
         match self {
             TypeVariant::Int => word("int"),
             TypeVariant::Uint => word("uint"),
+            TypeVariant::U8 => word("u8"),
+            TypeVariant::U64 => word("u64"),
+            TypeVariant::I64 => word("i64"),
+            TypeVariant::U256 => word("u256"),
             TypeVariant::Float => word("float"),
             TypeVariant::Char => word("char"),
             TypeVariant::String => word("string"),
@@ -545,6 +950,11 @@ impl TypeVariant {
             TypeVariant::Bool => word("bool"),
             TypeVariant::Set(ty) => format!("set<{}>", ty.display(contract)),
             TypeVariant::List(ty) => format!("list<{}>", ty.display(contract)),
+            TypeVariant::Option(ty) => format!("option<{}>", ty.display(contract)),
+            TypeVariant::Tuple(tys) => {
+                let tys: Vec<String> = tys.iter().map(|ty| ty.display(contract)).collect();
+                format!("({})", tys.join(", "))
+            }
             TypeVariant::Mapping(m) => {
                 format!(
                     "set<{} -> {}>",
@@ -588,6 +998,30 @@ impl Expression {
         )
     }
 
+    /// Whether the expression is a literal or a variant of the enum.
+    pub fn is_constant(&self) -> bool {
+        self.is_literal() || matches!(self, Expression::Enum(_))
+    }
+
+    /// Copy the literal to the new location.
+    pub fn with_loc(&self, loc: &Span) -> Expression {
+        let mut value = self.clone();
+        match &mut value {
+            Expression::Int(u) => u.loc = loc.clone(),
+            Expression::UInt(u) => u.loc = loc.clone(),
+            Expression::Float(u) => u.loc = loc.clone(),
+            Expression::Boolean(u) => u.loc = loc.clone(),
+            Expression::String(u) => u.loc = loc.clone(),
+            Expression::Char(u) => u.loc = loc.clone(),
+            Expression::Hex(u) => u.loc = loc.clone(),
+            Expression::Address(u) => u.loc = loc.clone(),
+            Expression::Enum(u) => u.loc = loc.clone(),
+            Expression::List(u) => u.loc = loc.clone(),
+            _ => {}
+        }
+        value
+    }
+
     /// Check if the expression is a wildcard `any` variable.
     pub fn is_access_wildcard(&self, scope: &Scope) -> bool {
         if let Expression::Variable(var) = self {
@@ -686,6 +1120,8 @@ impl Expression {
             Expression::Enum(u) => &u.loc,
             Expression::Address(u) => &u.loc,
             Expression::List(u) => &u.loc,
+            Expression::Optional(u) => &u.loc,
+            Expression::Tuple(u) => &u.loc,
             Expression::Multiply(b) => &b.loc,
             Expression::Divide(b) => &b.loc,
             Expression::Modulo(b) => &b.loc,
@@ -702,8 +1138,15 @@ impl Expression {
             Expression::Or(b) => &b.loc,
             Expression::And(b) => &b.loc,
             Expression::FunctionCall(f) => &f.loc,
+            Expression::BuiltinCall(f) => &f.loc,
+            Expression::InterfaceCall(f) => &f.loc,
             Expression::MemberAccess(m) => &m.loc,
             Expression::StructInit(s) => &s.loc,
+            Expression::VariantInit(v) => &v.loc,
+            Expression::Lambda(l) => &l.loc,
+            Expression::Conditional(c) => &c.loc,
+            Expression::Index(i) => &i.loc,
+            Expression::Slice(s) => &s.loc,
         }
     }
 }
@@ -715,12 +1158,16 @@ impl Statement {
             Statement::Assign(a) => &a.loc,
             Statement::IfElse(br) => &br.loc,
             Statement::ForLoop(l) => &l.loc,
+            Statement::While(l) => &l.loc,
             Statement::Iterator(i) => &i.loc,
+            Statement::Match(m) => &m.loc,
             Statement::Return(e) => &e.loc,
             Statement::Expression(e) => e.loc(),
             Statement::StateTransition(tr) => tr.loc(),
+            Statement::Emit(e) => &e.loc,
             Statement::Block(b) => &b.loc,
             Statement::Skip(s) => s,
+            Statement::Break(s) => s,
             Statement::Error(s) => s,
         }
     }
@@ -1,3 +1,4 @@
+use folidity_diagnostics::Report;
 use folidity_parser::ast as parsed_ast;
 
 use crate::{
@@ -23,7 +24,8 @@ use crate::{
     },
 };
 
-/// Resolve `st` model bounds on states, models and functions.
+/// Resolve `st` model bounds on states, models and functions,
+/// and the decreasing measures of functions.
 pub fn resolve_bounds(contract: &mut ContractDefinition, delay: &DelayedDeclarations) {
     for model_delay in &delay.models {
         let Some(st) = &model_delay.decl.st_block else {
@@ -50,15 +52,12 @@ pub fn resolve_bounds(contract: &mut ContractDefinition, delay: &DelayedDeclarat
             );
         }
 
-        let Ok(bounds) = resolve_bound_exprs(&st.expr, &mut scope, contract) else {
+        let Ok(bounds) = resolve_bound_exprs(st, &mut scope, contract) else {
             continue;
         };
 
         contract.models[model_delay.i].scope = scope;
-        contract.models[model_delay.i].bounds = Some(Bounds {
-            loc: st.loc.clone(),
-            exprs: bounds,
-        });
+        contract.models[model_delay.i].bounds = Some(bounds);
     }
 
     for state_delay in &delay.states {
@@ -101,14 +100,11 @@ pub fn resolve_bounds(contract: &mut ContractDefinition, delay: &DelayedDeclarat
             );
         });
 
-        let Ok(bounds) = resolve_bound_exprs(&st.expr, &mut scope, contract) else {
+        let Ok(bounds) = resolve_bound_exprs(st, &mut scope, contract) else {
             continue;
         };
 
-        contract.states[state_delay.i].bounds = Some(Bounds {
-            loc: st.loc.clone(),
-            exprs: bounds,
-        });
+        contract.states[state_delay.i].bounds = Some(bounds);
         contract.states[state_delay.i].scope = scope;
     }
 
@@ -117,27 +113,76 @@ pub fn resolve_bounds(contract: &mut ContractDefinition, delay: &DelayedDeclarat
         std::mem::swap(&mut contract.functions[func_delay.i].scope, &mut scope);
 
         if let Some(st) = &func_delay.decl.st_block {
-            let bounds = if let Ok(exprs) = resolve_bound_exprs(&st.expr, &mut scope, contract) {
-                exprs
-            } else {
-                vec![]
-            };
-            contract.functions[func_delay.i].bounds = Some(Bounds {
+            let bounds = resolve_bound_exprs(st, &mut scope, contract).unwrap_or(Bounds {
                 loc: st.loc.clone(),
-                exprs: bounds,
+                exprs: vec![],
+                labels: vec![],
             });
+            contract.functions[func_delay.i].bounds = Some(bounds);
+        }
+
+        if let Some(e) = &func_delay.decl.decreases {
+            contract.functions[func_delay.i].decreases = expression(
+                e,
+                ExpectedType::Dynamic(vec![
+                    TypeVariant::Int,
+                    TypeVariant::Uint,
+                    TypeVariant::U8,
+                    TypeVariant::U64,
+                    TypeVariant::I64,
+                    TypeVariant::U256,
+                ]),
+                &mut scope,
+                contract,
+            )
+            .ok();
         }
 
         std::mem::swap(&mut scope, &mut contract.functions[func_delay.i].scope);
     }
 }
 
-fn resolve_bound_exprs(
-    expr: &parsed_ast::Expression,
+/// Resolve the constraints of `st` block along with their labels.
+pub(crate) fn resolve_bound_exprs(
+    st: &parsed_ast::StBlock,
     scope: &mut Scope,
     contract: &mut ContractDefinition,
-) -> Result<Vec<Expression>, ()> {
-    let mut bounds = Vec::new();
+) -> Result<Bounds, ()> {
+    // labels are stripped from the constraints before resolving them.
+    let mut labels: Vec<Option<String>> = vec![];
+    let mut error = false;
+    let stripped;
+    let expr = match &st.expr {
+        parsed_ast::Expression::List(list) => {
+            let mut items = vec![];
+            for e in &list.element {
+                let parsed_ast::Expression::Labelled(l) = e else {
+                    labels.push(None);
+                    items.push(e.clone());
+                    continue;
+                };
+                if labels.contains(&Some(l.label.name.clone())) {
+                    contract.diagnostics.push(Report::semantic_error(
+                        l.label.loc.clone(),
+                        format!("Constraint label `{}` is already used.", l.label.name),
+                    ));
+                    error = true;
+                }
+                labels.push(Some(l.label.name.clone()));
+                items.push(*l.expr.clone());
+            }
+            stripped = parsed_ast::Expression::List(parsed_ast::UnaryExpression {
+                loc: list.loc.clone(),
+                element: items,
+            });
+            &stripped
+        }
+        e => {
+            labels.push(None);
+            e
+        }
+    };
+
     let Ok(resolved) = expression(
         expr,
         ExpectedType::Dynamic(vec![
@@ -149,11 +194,19 @@ fn resolve_bound_exprs(
     ) else {
         return Err(());
     };
+    if error {
+        return Err(());
+    }
 
-    if let Expression::List(list) = &resolved {
-        bounds.extend(list.element.iter().cloned());
+    let exprs = if let Expression::List(list) = resolved {
+        list.element
     } else {
-        bounds.push(resolved);
-    }
-    Ok(bounds)
+        vec![resolved]
+    };
+    labels.resize(exprs.len(), None);
+    Ok(Bounds {
+        loc: st.loc.clone(),
+        exprs,
+        labels,
+    })
 }
@@ -0,0 +1,1033 @@
+//! Built-in functions of the language.
+//!
+//! Built-in functions are resolved before the user defined ones,
+//! and their signatures are derived from the type of the first argument.
+
+use std::fmt::Display;
+
+use num_bigint::{
+    BigInt,
+    BigUint,
+};
+use num_rational::BigRational;
+use num_traits::{
+    One,
+    Signed,
+    Zero,
+};
+
+use folidity_diagnostics::Report;
+use folidity_parser::{
+    ast::{
+        self as parsed_ast,
+        Identifier,
+    },
+    Span,
+};
+
+use crate::{
+    ast::{
+        BuiltinCall,
+        Expression,
+        FunctionType,
+        FunctionVisibility,
+        TypeVariant,
+        UnaryExpression,
+    },
+    contract::ContractDefinition,
+    expression::{
+        eval::eval_const,
+        expression,
+        nums::check_range,
+    },
+    global_symbol::{
+        GlobalSymbol,
+        SymbolKind,
+    },
+    symtable::Scope,
+    types::{
+        map_type,
+        report_type_mismatch,
+        ExpectedType,
+    },
+};
+
+/// Registry of the built-in functions.
+///
+/// Collection functions are generic over the type of the collection
+/// passed as the first argument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuiltinFunction {
+    /// - `add(m: mapping<K, V>, key: K, value: V) -> mapping<K, V>`
+    /// - `add(l: list<T>, item: T) -> list<T>`
+    /// - `add(s: set<T>, item: T) -> set<T>`
+    Add,
+    /// `get(m: mapping<K, V>, key: K) -> V`
+    Get,
+    /// - `contains(m: mapping<K, V>, key: K) -> bool`
+    /// - `contains(l: list<T>, item: T) -> bool`
+    /// - `contains(s: set<T>, item: T) -> bool`
+    /// - `contains(s: string, sub: string) -> bool`
+    Contains,
+    /// - `remove(m: mapping<K, V>, key: K) -> mapping<K, V>`
+    /// - `remove(l: list<T>, item: T) -> list<T>`
+    /// - `remove(s: set<T>, item: T) -> set<T>`
+    Remove,
+    /// - `push(l: list<T>, item: T) -> list<T>`
+    /// - `push(s: set<T>, item: T) -> set<T>`
+    ///
+    /// Appends the item to the end, sets skip the items they already contain.
+    Push,
+    /// `pop(l: list<T>) -> list<T>`
+    ///
+    /// Removes the last element of the non-empty list.
+    Pop,
+    /// `insert(l: list<T>, index: uint, item: T) -> list<T>`
+    ///
+    /// Inserts the item before the element at the index,
+    /// or at the end if the index is the size of the list.
+    Insert,
+    /// - `union(l: list<T>, other: list<T>) -> list<T>`
+    /// - `union(s: set<T>, other: set<T>) -> set<T>`
+    ///
+    /// Appends the elements of the other collection,
+    /// sets skip the elements they already contain.
+    Union,
+    /// - `difference(l: list<T>, other: list<T>) -> list<T>`
+    /// - `difference(s: set<T>, other: set<T>) -> set<T>`
+    ///
+    /// Keeps the elements not contained in the other collection.
+    Difference,
+    /// - `map(l: list<T>, f: fn(T) -> R) -> list<R>`
+    /// - `map(s: set<T>, f: fn(T) -> R) -> set<R>`
+    Map,
+    /// - `filter(l: list<T>, f: fn(T) -> bool) -> list<T>`
+    /// - `filter(s: set<T>, f: fn(T) -> bool) -> set<T>`
+    Filter,
+    /// - `fold(l: list<T>, init: A, f: fn(A, T) -> A) -> A`
+    /// - `fold(s: set<T>, init: A, f: fn(A, T) -> A) -> A`
+    Fold,
+    /// - `all(l: list<T>, f: fn(T) -> bool) -> bool`
+    /// - `all(s: set<T>, f: fn(T) -> bool) -> bool`
+    ///
+    /// Holds if every element satisfies the predicate, e.g. for an empty collection.
+    All,
+    /// - `exists(l: list<T>, f: fn(T) -> bool) -> bool`
+    /// - `exists(s: set<T>, f: fn(T) -> bool) -> bool`
+    ///
+    /// Holds if some element satisfies the predicate.
+    Exists,
+    /// - `sum(l: list<T>) -> T`
+    /// - `sum(s: set<T>) -> T`
+    ///
+    /// `T` is one of `int`, `uint` or `float`.
+    Sum,
+    /// `pow(base: T, exp: uint) -> T`
+    ///
+    /// `T` is any integer type or `float`.
+    Pow,
+    /// `sqrt(value: T) -> T`
+    ///
+    /// `T` is any integer type or `float`. The root is rounded down,
+    /// the ones of the floats to the precision of their fixed-point encoding.
+    Sqrt,
+    /// - `size(l: list<T>) -> uint`
+    /// - `size(s: set<T>) -> uint`
+    Size,
+    /// `length(s: string) -> uint`
+    Length,
+    /// `substring(s: string, start: uint, end: uint) -> string`
+    Substring,
+    /// `starts_with(s: string, prefix: string) -> bool`
+    StartsWith,
+    /// `hash(values: T...) -> hex`
+    ///
+    /// SHA-256 of the values encoded with ARC-4 and concatenated.
+    /// `T` is any primitive type, the types of the values can differ.
+    Hash,
+    /// `sha256(values: T...) -> hex`
+    ///
+    /// Same as `hash`.
+    Sha256,
+    /// `keccak256(values: T...) -> hex`
+    ///
+    /// Keccak-256 of the values encoded as for `hash`.
+    Keccak256,
+    /// `ed25519_verify(data: hex, signature: hex, key: address) -> bool`
+    ///
+    /// Verifies the signature of the data by the public key of the account.
+    Ed25519Verify,
+    /// `caller() -> address`
+    Caller,
+    /// `current_block() -> uint`
+    CurrentBlock,
+    /// `balance(account: address) -> uint`
+    ///
+    /// Accessed as the member of the account, e.g. `account.balance`.
+    Balance,
+    /// `pay(receiver: address, amount: uint)`
+    ///
+    /// Sends the amount of microAlgos from the account of the application.
+    Pay,
+    /// `asset_transfer(asset: uint, receiver: address, amount: uint)`
+    ///
+    /// Transfers the amount of the asset from the account of the application.
+    AssetTransfer,
+    /// `cast(value: I) -> J`
+    ///
+    /// `I` and `J` are integer types, `J` is inferred from the expected type.
+    /// The value is checked to fit in the range of `J`.
+    ///
+    /// Also produced by `value as J`, which converts `hex` to `address`
+    /// and `address` or `string` to `hex` as well.
+    Cast,
+    /// - `or(value: T, fallback: T) -> T`
+    /// - `or(value: option<T>, fallback: T) -> T`
+    ///
+    /// For a numeric `T`, evaluates to `fallback`
+    /// if the arithmetic of `value` overflows or divides by zero.
+    /// For an option, evaluates to `fallback` if the value is missing.
+    /// Usually written as the pipe `value :> or(fallback)`.
+    Or,
+    /// - `assert(condition: bool)`
+    /// - `assert(condition: bool, message: string)`
+    ///
+    /// Reverts the call if the condition does not hold, logging the message.
+    /// The verifier reports the assertions that may fail.
+    Assert,
+    /// - `require(condition: bool)`
+    /// - `require(condition: bool, message: string)`
+    ///
+    /// Same as `assert`, but the condition is the requirement of the call
+    /// the verifier assumes rather than checks, e.g. of its arguments.
+    Require,
+}
+
+impl BuiltinFunction {
+    /// Find the built-in function by its name.
+    pub fn lookup(name: &str) -> Option<Self> {
+        match name {
+            "add" => Some(BuiltinFunction::Add),
+            "get" => Some(BuiltinFunction::Get),
+            "contains" => Some(BuiltinFunction::Contains),
+            "remove" => Some(BuiltinFunction::Remove),
+            "push" => Some(BuiltinFunction::Push),
+            "pop" => Some(BuiltinFunction::Pop),
+            "insert" => Some(BuiltinFunction::Insert),
+            "union" => Some(BuiltinFunction::Union),
+            "difference" => Some(BuiltinFunction::Difference),
+            "map" => Some(BuiltinFunction::Map),
+            "filter" => Some(BuiltinFunction::Filter),
+            "fold" => Some(BuiltinFunction::Fold),
+            "all" => Some(BuiltinFunction::All),
+            "exists" => Some(BuiltinFunction::Exists),
+            "sum" => Some(BuiltinFunction::Sum),
+            "pow" => Some(BuiltinFunction::Pow),
+            "sqrt" => Some(BuiltinFunction::Sqrt),
+            "size" => Some(BuiltinFunction::Size),
+            "length" => Some(BuiltinFunction::Length),
+            "substring" => Some(BuiltinFunction::Substring),
+            "starts_with" => Some(BuiltinFunction::StartsWith),
+            "hash" => Some(BuiltinFunction::Hash),
+            "sha256" => Some(BuiltinFunction::Sha256),
+            "keccak256" => Some(BuiltinFunction::Keccak256),
+            "ed25519_verify" => Some(BuiltinFunction::Ed25519Verify),
+            "caller" => Some(BuiltinFunction::Caller),
+            "current_block" => Some(BuiltinFunction::CurrentBlock),
+            "pay" => Some(BuiltinFunction::Pay),
+            "asset_transfer" => Some(BuiltinFunction::AssetTransfer),
+            "cast" => Some(BuiltinFunction::Cast),
+            "or" => Some(BuiltinFunction::Or),
+            "assert" => Some(BuiltinFunction::Assert),
+            "require" => Some(BuiltinFunction::Require),
+            _ => None,
+        }
+    }
+
+    /// Context value accessed by its name without the call, e.g. `current_block`.
+    pub fn context_value(name: &str) -> Option<Self> {
+        Self::lookup(name).filter(|f| f.fixed_signature().is_some_and(|(args, _)| args.is_empty()))
+    }
+
+    /// Function accessed as the member of the value of the given type.
+    pub fn member(ty: &TypeVariant, name: &str) -> Option<Self> {
+        match (ty, name) {
+            (TypeVariant::Address, "balance") => Some(BuiltinFunction::Balance),
+            _ => None,
+        }
+    }
+
+    /// Number of arguments the function accepts
+    /// when applied to the value of the given type.
+    ///
+    /// Functions without arguments ignore the type.
+    pub fn arity(&self, ty: &TypeVariant) -> usize {
+        match self {
+            BuiltinFunction::Caller | BuiltinFunction::CurrentBlock => 0,
+            BuiltinFunction::Size
+            | BuiltinFunction::Pop
+            | BuiltinFunction::Sum
+            | BuiltinFunction::Sqrt
+            | BuiltinFunction::Length
+            | BuiltinFunction::Balance
+            | BuiltinFunction::Cast
+            | BuiltinFunction::Assert
+            | BuiltinFunction::Require => 1,
+            BuiltinFunction::Add if matches!(ty, TypeVariant::Mapping(_)) => 3,
+            BuiltinFunction::Fold
+            | BuiltinFunction::Insert
+            | BuiltinFunction::Substring
+            | BuiltinFunction::AssetTransfer
+            | BuiltinFunction::Ed25519Verify => 3,
+            _ => 2,
+        }
+    }
+
+    /// Types of the arguments following the first one and the return type
+    /// of the function applied to the value of the given type.
+    ///
+    /// The return type of the function argument of `map` is [`TypeVariant::Generic`],
+    /// it is inferred from the function passed at the call site.
+    /// Similarly, the accumulator type of `fold` is inferred from the initial value.
+    fn signature(&self, ty: &TypeVariant) -> Option<(Vec<TypeVariant>, TypeVariant)> {
+        let sig = match (self, ty) {
+            (BuiltinFunction::Or, ty) if ty.is_integer() || ty == &TypeVariant::Float => {
+                (vec![ty.clone()], ty.clone())
+            }
+            (BuiltinFunction::Or, TypeVariant::Option(item)) => {
+                (vec![item.as_ref().clone()], item.as_ref().clone())
+            }
+            (BuiltinFunction::Pow, ty) if ty.is_integer() || ty == &TypeVariant::Float => {
+                (vec![TypeVariant::Uint], ty.clone())
+            }
+            (BuiltinFunction::Sqrt, ty) if ty.is_integer() || ty == &TypeVariant::Float => {
+                (vec![], ty.clone())
+            }
+            (_, TypeVariant::String) => {
+                match self {
+                    BuiltinFunction::Length => (vec![], TypeVariant::Uint),
+                    BuiltinFunction::Substring => {
+                        (
+                            vec![TypeVariant::Uint, TypeVariant::Uint],
+                            TypeVariant::String,
+                        )
+                    }
+                    BuiltinFunction::StartsWith | BuiltinFunction::Contains => {
+                        (vec![TypeVariant::String], TypeVariant::Bool)
+                    }
+                    _ => return None,
+                }
+            }
+            (_, TypeVariant::Mapping(m)) => {
+                let key = m.from_ty.as_ref().clone();
+                let value = m.to_ty.as_ref().clone();
+                match self {
+                    BuiltinFunction::Add => (vec![key, value], ty.clone()),
+                    BuiltinFunction::Get => (vec![key], value),
+                    BuiltinFunction::Contains => (vec![key], TypeVariant::Bool),
+                    BuiltinFunction::Remove => (vec![key], ty.clone()),
+                    _ => return None,
+                }
+            }
+            (_, TypeVariant::List(item) | TypeVariant::Set(item)) => {
+                let item = item.as_ref().clone();
+                let predicate = |returns: TypeVariant| {
+                    TypeVariant::Function(FunctionType {
+                        params: vec![item.clone()],
+                        returns: Box::new(returns),
+                    })
+                };
+                let is_list = matches!(ty, TypeVariant::List(_));
+                match self {
+                    BuiltinFunction::Add | BuiltinFunction::Remove | BuiltinFunction::Push => {
+                        (vec![item.clone()], ty.clone())
+                    }
+                    // sets are unordered.
+                    BuiltinFunction::Pop if is_list => (vec![], ty.clone()),
+                    BuiltinFunction::Insert if is_list => {
+                        (vec![TypeVariant::Uint, item.clone()], ty.clone())
+                    }
+                    BuiltinFunction::Union | BuiltinFunction::Difference => {
+                        (vec![ty.clone()], ty.clone())
+                    }
+                    BuiltinFunction::Contains => (vec![item.clone()], TypeVariant::Bool),
+                    BuiltinFunction::Map => {
+                        let generic = TypeVariant::Generic(vec![]);
+                        (vec![predicate(generic.clone())], with_item(ty, generic))
+                    }
+                    BuiltinFunction::Filter => (vec![predicate(TypeVariant::Bool)], ty.clone()),
+                    BuiltinFunction::All | BuiltinFunction::Exists => {
+                        (vec![predicate(TypeVariant::Bool)], TypeVariant::Bool)
+                    }
+                    BuiltinFunction::Fold => {
+                        let acc = TypeVariant::Generic(vec![]);
+                        let f = TypeVariant::Function(FunctionType {
+                            params: vec![acc.clone(), item.clone()],
+                            returns: Box::new(acc.clone()),
+                        });
+                        (vec![acc.clone(), f], acc)
+                    }
+                    BuiltinFunction::Sum if item.is_integer() || item == TypeVariant::Float => {
+                        (vec![], item.clone())
+                    }
+                    BuiltinFunction::Size => (vec![], TypeVariant::Uint),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        Some(sig)
+    }
+
+    /// Types of the arguments and the return type of the function
+    /// whose signature does not depend on the arguments.
+    fn fixed_signature(&self) -> Option<(Vec<TypeVariant>, TypeVariant)> {
+        let sig = match self {
+            BuiltinFunction::Caller => (vec![], TypeVariant::Address),
+            BuiltinFunction::CurrentBlock => (vec![], TypeVariant::Uint),
+            BuiltinFunction::Balance => (vec![TypeVariant::Address], TypeVariant::Uint),
+            BuiltinFunction::Ed25519Verify => {
+                (
+                    vec![TypeVariant::Hex, TypeVariant::Hex, TypeVariant::Address],
+                    TypeVariant::Bool,
+                )
+            }
+            BuiltinFunction::Pay => {
+                (
+                    vec![TypeVariant::Address, TypeVariant::Uint],
+                    TypeVariant::Unit,
+                )
+            }
+            BuiltinFunction::AssetTransfer => {
+                (
+                    vec![TypeVariant::Uint, TypeVariant::Address, TypeVariant::Uint],
+                    TypeVariant::Unit,
+                )
+            }
+            _ => return None,
+        };
+        Some(sig)
+    }
+
+    /// Does the function hash any number of values?
+    pub fn is_hash(&self) -> bool {
+        matches!(
+            self,
+            BuiltinFunction::Hash | BuiltinFunction::Sha256 | BuiltinFunction::Keccak256
+        )
+    }
+
+    /// Does the function revert the call if the condition does not hold?
+    pub fn is_assertion(&self) -> bool {
+        matches!(self, BuiltinFunction::Assert | BuiltinFunction::Require)
+    }
+
+    /// Does the function submit the inner transaction?
+    pub fn is_transaction(&self) -> bool {
+        matches!(self, BuiltinFunction::Pay | BuiltinFunction::AssetTransfer)
+    }
+}
+
+impl Display for BuiltinFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut word = |s: &str| -> std::fmt::Result { write!(f, "{s}") };
+        match self {
+            BuiltinFunction::Add => word("add"),
+            BuiltinFunction::Get => word("get"),
+            BuiltinFunction::Contains => word("contains"),
+            BuiltinFunction::Remove => word("remove"),
+            BuiltinFunction::Push => word("push"),
+            BuiltinFunction::Pop => word("pop"),
+            BuiltinFunction::Insert => word("insert"),
+            BuiltinFunction::Union => word("union"),
+            BuiltinFunction::Difference => word("difference"),
+            BuiltinFunction::Map => word("map"),
+            BuiltinFunction::Filter => word("filter"),
+            BuiltinFunction::Fold => word("fold"),
+            BuiltinFunction::All => word("all"),
+            BuiltinFunction::Exists => word("exists"),
+            BuiltinFunction::Sum => word("sum"),
+            BuiltinFunction::Pow => word("pow"),
+            BuiltinFunction::Sqrt => word("sqrt"),
+            BuiltinFunction::Size => word("size"),
+            BuiltinFunction::Length => word("length"),
+            BuiltinFunction::Substring => word("substring"),
+            BuiltinFunction::StartsWith => word("starts_with"),
+            BuiltinFunction::Hash => word("hash"),
+            BuiltinFunction::Sha256 => word("sha256"),
+            BuiltinFunction::Keccak256 => word("keccak256"),
+            BuiltinFunction::Ed25519Verify => word("ed25519_verify"),
+            BuiltinFunction::Caller => word("caller"),
+            BuiltinFunction::CurrentBlock => word("current_block"),
+            BuiltinFunction::Balance => word("balance"),
+            BuiltinFunction::Pay => word("pay"),
+            BuiltinFunction::AssetTransfer => word("asset_transfer"),
+            BuiltinFunction::Cast => word("cast"),
+            BuiltinFunction::Or => word("or"),
+            BuiltinFunction::Assert => word("assert"),
+            BuiltinFunction::Require => word("require"),
+        }
+    }
+}
+
+/// Resolve the call of the built-in function.
+///
+/// The first argument is resolved without type hints, except for `or`
+/// whose value has the type of the call, the rest are checked against
+/// the signature derived from its type.
+pub(crate) fn resolve_builtin_call(
+    func: BuiltinFunction,
+    ident: &Identifier,
+    args: &[parsed_ast::Expression],
+    loc: Span,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    let report_arity = |arity: usize, contract: &mut ContractDefinition| {
+        contract.diagnostics.push(Report::semantic_error(
+            loc.clone(),
+            format!(
+                "`{}` expects {} arguments, but {} were provided.",
+                func,
+                arity,
+                args.len()
+            ),
+        ));
+    };
+
+    if func == BuiltinFunction::Cast {
+        if args.len() != 1 {
+            report_arity(1, contract);
+            return Err(());
+        }
+        return resolve_cast(&args[0], loc, scope, contract, expected_ty);
+    }
+
+    if func.is_transaction() {
+        if let GlobalSymbol::Function(sym) = &scope.symbol {
+            if matches!(contract.functions[sym.i].vis, FunctionVisibility::View(_)) {
+                contract.diagnostics.push(Report::semantic_error(
+                    loc.clone(),
+                    String::from("View functions can not send transactions."),
+                ));
+                return Err(());
+            }
+        }
+    }
+
+    if func.is_hash() {
+        return resolve_hash(func, args, loc, scope, contract, expected_ty);
+    }
+
+    if func.is_assertion() {
+        return resolve_assertion(func, args, loc, scope, contract, expected_ty);
+    }
+
+    if let Some((arg_tys, return_ty)) = func.fixed_signature() {
+        if args.len() != arg_tys.len() {
+            report_arity(arg_tys.len(), contract);
+            return Err(());
+        }
+        let mut resolved_args = vec![];
+        let mut error = false;
+        for (arg, ty) in args.iter().zip(arg_tys) {
+            match expression(arg, ExpectedType::Concrete(ty), scope, contract) {
+                Ok(e) => resolved_args.push(e),
+                Err(_) => error = true,
+            }
+        }
+        if error {
+            return Err(());
+        }
+        check_return_ty(&return_ty, &expected_ty, &loc, contract)?;
+        return Ok(Expression::BuiltinCall(BuiltinCall {
+            loc,
+            func,
+            args: resolved_args,
+            returns: return_ty,
+        }));
+    }
+
+    let Some(first_arg) = args.first() else {
+        report_arity(func.arity(&TypeVariant::Generic(vec![])), contract);
+        return Err(());
+    };
+    let first = match (func, &expected_ty) {
+        // the value is either of the expected type, or the option of it.
+        (BuiltinFunction::Or, ExpectedType::Concrete(ty)) => {
+            let reported = contract.diagnostics.len();
+            match expression(first_arg, expected_ty.clone(), scope, contract) {
+                Ok(e) => e,
+                Err(_) => {
+                    let errors = contract.diagnostics.split_off(reported);
+                    let option_ty =
+                        ExpectedType::Concrete(TypeVariant::Option(Box::new(ty.clone())));
+                    match expression(first_arg, option_ty, scope, contract) {
+                        Ok(e) => e,
+                        Err(_) => {
+                            contract.diagnostics.truncate(reported);
+                            contract.diagnostics.extend(errors);
+                            return Err(());
+                        }
+                    }
+                }
+            }
+        }
+        (BuiltinFunction::Or, _) => expression(first_arg, expected_ty.clone(), scope, contract)?,
+        // the number has the type of the call.
+        (BuiltinFunction::Pow | BuiltinFunction::Sqrt, ExpectedType::Concrete(ty))
+            if ty.is_integer() || ty == &TypeVariant::Float =>
+        {
+            expression(first_arg, expected_ty.clone(), scope, contract)?
+        }
+        _ => expression(first_arg, ExpectedType::Dynamic(vec![]), scope, contract)?,
+    };
+    let Some((arg_tys, mut return_ty)) = func.signature(first.ty()) else {
+        contract.diagnostics.push(Report::type_error(
+            ident.loc.clone(),
+            format!(
+                "`{}` is not supported for {}.",
+                func,
+                first.ty().display(contract)
+            ),
+        ));
+        return Err(());
+    };
+
+    if args.len() != func.arity(first.ty()) {
+        report_arity(func.arity(first.ty()), contract);
+        return Err(());
+    }
+
+    let mut resolved_args = vec![first];
+    let mut error = false;
+    // type of the accumulator inferred from the initial value.
+    let mut acc_ty: Option<TypeVariant> = None;
+    for (arg, ty) in args.iter().skip(1).zip(arg_tys) {
+        let ty = match &acc_ty {
+            Some(acc) => substitute_generic(&ty, acc),
+            None => ty,
+        };
+        let resolved = match &ty {
+            TypeVariant::Generic(_) => {
+                let hint = match &expected_ty {
+                    ExpectedType::Concrete(e_ty) => ExpectedType::Concrete(e_ty.clone()),
+                    _ => ExpectedType::Dynamic(vec![]),
+                };
+                expression(arg, hint, scope, contract).inspect(|e| {
+                    acc_ty = Some(e.ty().clone());
+                    return_ty = e.ty().clone();
+                })
+            }
+            TypeVariant::Function(f_ty)
+                if matches!(f_ty.returns.as_ref(), TypeVariant::Generic(_)) =>
+            {
+                resolve_generic_function(arg, &f_ty.params, scope, contract).map(|(e, r)| {
+                    return_ty = with_item(&return_ty, r);
+                    e
+                })
+            }
+            _ => expression(arg, ExpectedType::Concrete(ty), scope, contract),
+        };
+        match resolved {
+            Ok(e) => resolved_args.push(e),
+            Err(_) => error = true,
+        }
+    }
+    if error {
+        return Err(());
+    }
+
+    check_return_ty(&return_ty, &expected_ty, &loc, contract)?;
+
+    // powers and roots of the literals are folded.
+    let foldable = matches!(func, BuiltinFunction::Pow | BuiltinFunction::Sqrt)
+        && resolved_args.iter().all(|a| a.is_literal());
+    let call = Expression::BuiltinCall(BuiltinCall {
+        loc: loc.clone(),
+        func,
+        args: resolved_args,
+        returns: return_ty,
+    });
+    if foldable {
+        eval_const(&call, loc, contract)
+    } else {
+        Ok(call)
+    }
+}
+
+/// Resolve the assertion of the condition followed by the optional message.
+fn resolve_assertion(
+    func: BuiltinFunction,
+    args: &[parsed_ast::Expression],
+    loc: Span,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    if args.is_empty() || args.len() > 2 {
+        contract.diagnostics.push(Report::semantic_error(
+            loc.clone(),
+            format!(
+                "`{}` expects the condition and an optional message, but {} arguments were provided.",
+                func,
+                args.len()
+            ),
+        ));
+        return Err(());
+    }
+
+    let mut resolved_args = vec![];
+    let mut error = false;
+    for (arg, ty) in args.iter().zip([TypeVariant::Bool, TypeVariant::String]) {
+        match expression(arg, ExpectedType::Concrete(ty), scope, contract) {
+            Ok(e) => resolved_args.push(e),
+            Err(_) => error = true,
+        }
+    }
+    if error {
+        return Err(());
+    }
+    check_return_ty(&TypeVariant::Unit, &expected_ty, &loc, contract)?;
+
+    Ok(Expression::BuiltinCall(BuiltinCall {
+        loc,
+        func,
+        args: resolved_args,
+        returns: TypeVariant::Unit,
+    }))
+}
+
+/// Resolve the hash of the values of any primitive types.
+fn resolve_hash(
+    func: BuiltinFunction,
+    args: &[parsed_ast::Expression],
+    loc: Span,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    if args.is_empty() {
+        contract.diagnostics.push(Report::semantic_error(
+            loc,
+            format!("`{}` expects at least one argument.", func),
+        ));
+        return Err(());
+    }
+
+    let mut resolved_args = vec![];
+    let mut error = false;
+    for arg in args {
+        let Ok(e) = expression(arg, ExpectedType::Dynamic(vec![]), scope, contract) else {
+            error = true;
+            continue;
+        };
+        if !e.ty().is_primitive() || e.ty() == &TypeVariant::Unit {
+            contract.diagnostics.push(Report::type_error(
+                e.loc().clone(),
+                format!(
+                    "Only primitive types can be hashed, got {}.",
+                    e.ty().display(contract)
+                ),
+            ));
+            error = true;
+            continue;
+        }
+        resolved_args.push(e);
+    }
+    if error {
+        return Err(());
+    }
+
+    check_return_ty(&TypeVariant::Hex, &expected_ty, &loc, contract)?;
+    Ok(Expression::BuiltinCall(BuiltinCall {
+        loc,
+        func,
+        args: resolved_args,
+        returns: TypeVariant::Hex,
+    }))
+}
+
+/// Resolve the member of the value provided by the built-in function, e.g.
+/// `account.balance`.
+pub(crate) fn resolve_builtin_member(
+    func: BuiltinFunction,
+    value: Expression,
+    loc: Span,
+    contract: &mut ContractDefinition,
+    expected_ty: &ExpectedType,
+) -> Result<Expression, ()> {
+    let (_, returns) = func
+        .fixed_signature()
+        .expect("members have fixed signatures");
+    check_return_ty(&returns, expected_ty, &loc, contract)?;
+    Ok(Expression::BuiltinCall(BuiltinCall {
+        loc,
+        func,
+        args: vec![value],
+        returns,
+    }))
+}
+
+/// Resolve the explicit conversion of the integer to the expected integer type.
+///
+/// Literals are converted at compile time,
+/// other values are checked to fit in the range of the type at runtime.
+fn resolve_cast(
+    arg: &parsed_ast::Expression,
+    loc: Span,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    let target = match &expected_ty {
+        ExpectedType::Concrete(ty) => Some(ty.clone()),
+        ExpectedType::Dynamic(tys) => tys.iter().find(|ty| ty.is_integer()).cloned(),
+        ExpectedType::Empty => None,
+    };
+    let Some(target) = target.filter(|ty| ty.is_integer()) else {
+        contract.diagnostics.push(Report::type_error(
+            loc,
+            String::from("`cast` can only convert to the integer type inferred from the context."),
+        ));
+        return Err(());
+    };
+
+    let value = expression(arg, ExpectedType::Dynamic(vec![]), scope, contract)?;
+    if !value.ty().is_integer() {
+        contract.diagnostics.push(Report::type_error(
+            value.loc().clone(),
+            format!(
+                "`cast` is not supported for {}.",
+                value.ty().display(contract)
+            ),
+        ));
+        return Err(());
+    }
+
+    convert_integer(value, target, loc, contract)
+}
+
+/// Resolve the conversion of the value with `as`.
+///
+/// Integers are converted to each other as with `cast`,
+/// `hex` is converted to `address` checking its length at runtime,
+/// and the addresses and strings are converted to their bytes.
+pub(crate) fn resolve_as(
+    c: &parsed_ast::Cast,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    let target = map_type(contract, &c.ty)?.ty;
+    let value = expression(&c.expr, ExpectedType::Dynamic(vec![]), scope, contract)?;
+    let from = value.ty().clone();
+    let allowed = from == target
+        || (from.is_integer() && target.is_integer())
+        || matches!(
+            (&from, &target),
+            (TypeVariant::Hex, TypeVariant::Address)
+                | (TypeVariant::Address, TypeVariant::Hex)
+                | (TypeVariant::String, TypeVariant::Hex)
+        );
+    if !allowed {
+        contract.diagnostics.push(Report::type_error(
+            c.loc.clone(),
+            format!(
+                "{} cannot be converted to {}.",
+                from.display(contract),
+                target.display(contract)
+            ),
+        ));
+        return Err(());
+    }
+    check_return_ty(&target, &expected_ty, &c.loc, contract)?;
+
+    if from == target {
+        Ok(value)
+    } else if target.is_integer() {
+        convert_integer(value, target, c.loc.clone(), contract)
+    } else {
+        Ok(cast(value, target, c.loc.clone()))
+    }
+}
+
+/// Convert the integer to the integer type, literals are converted at compile time.
+pub(crate) fn convert_integer(
+    value: Expression,
+    target: TypeVariant,
+    loc: Span,
+    contract: &mut ContractDefinition,
+) -> Result<Expression, ()> {
+    let number = match &value {
+        Expression::Int(u) => Some(u.element.clone()),
+        Expression::UInt(u) => Some(BigInt::from(u.element.clone())),
+        _ => None,
+    };
+    if let Some(number) = number {
+        check_range(&number, &target, &loc, contract)?;
+        return Ok(integer_literal(number, target, loc));
+    }
+
+    Ok(cast(value, target, loc))
+}
+
+/// Convert the value to the type.
+pub(crate) fn cast(value: Expression, ty: TypeVariant, loc: Span) -> Expression {
+    Expression::BuiltinCall(BuiltinCall {
+        loc,
+        func: BuiltinFunction::Cast,
+        args: vec![value],
+        returns: ty,
+    })
+}
+
+/// Create the literal of the integer type.
+pub(crate) fn integer_literal(number: BigInt, ty: TypeVariant, loc: Span) -> Expression {
+    if ty.is_signed() {
+        Expression::Int(UnaryExpression {
+            loc,
+            element: number,
+            ty,
+        })
+    } else {
+        Expression::UInt(UnaryExpression {
+            loc,
+            element: number.to_biguint().expect("range is checked"),
+            ty,
+        })
+    }
+}
+
+/// Check that the return type of the call matches the expected one.
+pub(crate) fn check_return_ty(
+    return_ty: &TypeVariant,
+    expected_ty: &ExpectedType,
+    loc: &Span,
+    contract: &mut ContractDefinition,
+) -> Result<(), ()> {
+    let mismatch = match expected_ty {
+        ExpectedType::Concrete(ty) => ty != return_ty,
+        ExpectedType::Dynamic(tys) => !tys.is_empty() && !tys.contains(return_ty),
+        ExpectedType::Empty => false,
+    };
+    if mismatch {
+        report_type_mismatch(expected_ty, std::slice::from_ref(return_ty), loc, contract);
+        return Err(());
+    }
+    Ok(())
+}
+
+/// Resolve the function passed as an argument whose return type is generic.
+///
+/// The return type is inferred from the declaration of the function,
+/// or from the body of the lambda.
+fn resolve_generic_function(
+    arg: &parsed_ast::Expression,
+    params: &[TypeVariant],
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+) -> Result<(Expression, TypeVariant), ()> {
+    if let parsed_ast::Expression::Lambda(_) = arg {
+        let f_ty = TypeVariant::Function(FunctionType {
+            params: params.to_vec(),
+            returns: Box::new(TypeVariant::Generic(vec![])),
+        });
+        let e = expression(arg, ExpectedType::Concrete(f_ty), scope, contract)?;
+        let TypeVariant::Function(resolved_ty) = e.ty() else {
+            unreachable!()
+        };
+        let returns = resolved_ty.returns.as_ref().clone();
+        return Ok((e, returns));
+    }
+
+    let parsed_ast::Expression::Variable(ident) = arg else {
+        contract.diagnostics.push(Report::type_error(
+            arg.loc().clone(),
+            String::from("Expected the name of the function or a lambda."),
+        ));
+        return Err(());
+    };
+    let sym = contract
+        .find_global_symbol(ident, SymbolKind::Function)
+        .ok_or(())?;
+    let returns = contract.functions[sym.i].return_ty.ty().clone();
+
+    let f_ty = TypeVariant::Function(FunctionType {
+        params: params.to_vec(),
+        returns: Box::new(returns.clone()),
+    });
+    let e = expression(arg, ExpectedType::Concrete(f_ty), scope, contract)?;
+    Ok((e, returns))
+}
+
+/// Replace the generic types in the signature with the inferred one.
+fn substitute_generic(ty: &TypeVariant, inferred: &TypeVariant) -> TypeVariant {
+    match ty {
+        TypeVariant::Generic(_) => inferred.clone(),
+        TypeVariant::Function(f_ty) => {
+            TypeVariant::Function(FunctionType {
+                params: f_ty
+                    .params
+                    .iter()
+                    .map(|p| substitute_generic(p, inferred))
+                    .collect(),
+                returns: Box::new(substitute_generic(&f_ty.returns, inferred)),
+            })
+        }
+        _ => ty.clone(),
+    }
+}
+
+/// Replace the item type of the collection, or the type itself if it is not a collection.
+fn with_item(ty: &TypeVariant, item: TypeVariant) -> TypeVariant {
+    match ty {
+        TypeVariant::List(_) => TypeVariant::List(Box::new(item)),
+        TypeVariant::Set(_) => TypeVariant::Set(Box::new(item)),
+        _ => item,
+    }
+}
+
+/// Largest exponent of the powers evaluated at compile time and by the interpreter.
+///
+/// Powers of the numbers other than `0`, `1` and `-1` overflow every numeric type beyond
+/// it.
+const MAX_EXPONENT: u32 = 256;
+
+/// Decimal digits of the square roots of the floats,
+/// matching the precision of their fixed-point encoding.
+const SQRT_DIGITS: u32 = 6;
+
+/// Raise the integer to the power, `None` if the exponent is too large.
+pub fn int_pow(base: &BigInt, exp: &BigUint) -> Option<BigInt> {
+    if base.magnitude() <= &BigUint::one() {
+        let odd = exp.bit(0);
+        return Some(
+            if exp.is_zero() || (base.is_negative() && !odd) {
+                BigInt::one()
+            } else {
+                base.clone()
+            },
+        );
+    }
+    let exp = u32::try_from(exp).ok().filter(|e| *e <= MAX_EXPONENT)?;
+    Some(base.pow(exp))
+}
+
+/// Raise the float to the power, `None` if the exponent is too large.
+pub fn float_pow(base: &BigRational, exp: &BigUint) -> Option<BigRational> {
+    let exp = u32::try_from(exp).ok().filter(|e| *e <= MAX_EXPONENT)?;
+    Some(base.pow(exp as i32))
+}
+
+/// Square root of the float rounded down to [`SQRT_DIGITS`] decimal digits,
+/// `None` for the negative values.
+pub fn float_sqrt(value: &BigRational) -> Option<BigRational> {
+    if value.is_negative() {
+        return None;
+    }
+    let scale = BigInt::from(10u32).pow(SQRT_DIGITS);
+    let scaled = (value * BigRational::from_integer(&scale * &scale)).to_integer();
+    Some(BigRational::new(scaled.sqrt(), scale))
+}
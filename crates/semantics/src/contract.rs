@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 
 use folidity_diagnostics::Report;
 use folidity_parser::{
@@ -13,30 +16,54 @@ use indexmap::IndexMap;
 
 use crate::{
     ast::{
+        ConstDeclaration,
         EnumDeclaration,
+        EnumVariant,
+        EventDeclaration,
+        FuncReturnType,
         Function,
+        InterfaceDeclaration,
+        InterfaceFunction,
         ModelDeclaration,
         Param,
         StateBody,
         StateDeclaration,
         StructDeclaration,
+        TestDeclaration,
+        TypeAlias,
+        TypeVariant,
+    },
+    expression::{
+        eval::eval_const,
+        expression,
+    },
+    optimizer::OptLevel,
+    statement::statement,
+    symtable::{
+        Scope,
+        ScopeContext,
     },
-    symtable::Scope,
 };
 
 use crate::{
-    functions::function_decl,
+    functions::{
+        function_decl,
+        resolve_func_param,
+        resolve_func_return,
+    },
     global_symbol::{
         GlobalSymbol,
         SymbolInfo,
         SymbolKind,
     },
+    lints::called_functions,
     types::{
         find_user_type_recursion,
         map_type,
         validate_fields,
         DelayedDeclaration,
         DelayedDeclarations,
+        ExpectedType,
     },
 };
 
@@ -45,8 +72,8 @@ use crate::{
 const MAX_ENUM_ITEMS: usize = 120;
 /// List of reserved type names that shouldn't be used as the name for a declaration.
 const RESERVED_TYPE_NAMES: &[&str] = &[
-    "model", "state", "enum", "fn", "mapping", "list", "set", "int", "uint", "float", "string",
-    "address", "hex", "char", "bool", "unit",
+    "model", "state", "enum", "fn", "mapping", "list", "set", "option", "int", "uint", "float",
+    "string", "address", "hex", "char", "bool", "unit",
 ];
 
 /// Semantically analysed contract definition.
@@ -63,12 +90,24 @@ pub struct ContractDefinition {
     pub states: Vec<StateDeclaration>,
     /// list of all functions in the contract.
     pub functions: Vec<Function>,
+    /// List of all constants in the contract.
+    pub constants: Vec<ConstDeclaration>,
+    /// List of all type aliases in the contract.
+    pub aliases: Vec<TypeAlias>,
+    /// List of all interfaces of the called applications.
+    pub interfaces: Vec<InterfaceDeclaration>,
+    /// List of all events logged by the contract.
+    pub events: Vec<EventDeclaration>,
+    /// List of all tests of the contract.
+    pub tests: Vec<TestDeclaration>,
     /// Mapping from identifiers to global declaration symbols.
     pub declaration_symbols: HashMap<String, GlobalSymbol>,
     /// Id of the next variable in the sym table.
     pub next_var_id: usize,
     /// Errors during semantic analysis.
     pub diagnostics: Vec<Report>,
+    /// Level of the optimizations applied to the contract.
+    pub opt_level: OptLevel,
 }
 
 impl ContractDefinition {
@@ -76,6 +115,7 @@ impl ContractDefinition {
     pub fn resolve_declarations(&mut self, tree: &Source) -> DelayedDeclarations {
         let mut delay = DelayedDeclarations {
             structs: Vec::new(),
+            enums: Vec::new(),
             models: Vec::new(),
             states: Vec::new(),
             functions: Vec::new(),
@@ -83,7 +123,9 @@ impl ContractDefinition {
 
         for item in &tree.declarations {
             match item {
-                parsed_ast::Declaration::EnumDeclaration(enum_) => self.analyze_enum(enum_),
+                parsed_ast::Declaration::EnumDeclaration(enum_) => {
+                    self.analyze_enum(enum_, &mut delay)
+                }
                 parsed_ast::Declaration::StructDeclaration(struct_) => {
                     self.analyze_struct(struct_, &mut delay)
                 }
@@ -100,6 +142,108 @@ impl ContractDefinition {
         delay
     }
 
+    /// Resolve type aliases in the order of declaration
+    /// and adds them to the global symbol table.
+    ///
+    /// An alias can only refer to the aliases declared before it.
+    pub fn resolve_aliases(&mut self, tree: &Source) {
+        for a in tree.declarations.iter().filter_map(|d| {
+            match d {
+                parsed_ast::Declaration::TypeAlias(a) => Some(a),
+                _ => None,
+            }
+        }) {
+            let Ok(ty) = map_type(self, &a.ty) else {
+                continue;
+            };
+            let pos = self.aliases.len();
+            if self.add_global_symbol(
+                &a.name,
+                GlobalSymbol::Alias(SymbolInfo::new(a.loc.clone(), pos)),
+            ) {
+                self.aliases.push(TypeAlias {
+                    loc: a.loc.clone(),
+                    name: a.name.clone(),
+                    ty: ty.ty,
+                });
+            }
+        }
+    }
+
+    /// Resolve interfaces of the called applications
+    /// and adds them to the global symbol table.
+    pub fn resolve_interfaces(&mut self, tree: &Source) {
+        for i in tree.declarations.iter().filter_map(|d| {
+            match d {
+                parsed_ast::Declaration::InterfaceDeclaration(i) => Some(i),
+                _ => None,
+            }
+        }) {
+            let _ = self.analyze_interface(i);
+        }
+    }
+
+    /// Resolve events and adds them to the global symbol table.
+    pub fn resolve_events(&mut self, tree: &Source) {
+        for e in tree.declarations.iter().filter_map(|d| {
+            match d {
+                parsed_ast::Declaration::EventDeclaration(e) => Some(e),
+                _ => None,
+            }
+        }) {
+            let _ = self.analyze_event(e);
+        }
+    }
+
+    /// Resolve constants in the order of declaration
+    /// and adds them to the global symbol table.
+    ///
+    /// A constant can only refer to the ones declared before it.
+    pub fn resolve_constants(&mut self, tree: &Source) {
+        for c in tree.declarations.iter().filter_map(|d| {
+            match d {
+                parsed_ast::Declaration::ConstDeclaration(c) => Some(c),
+                _ => None,
+            }
+        }) {
+            let _ = self.analyze_const(c);
+        }
+    }
+
+    /// Resolve the bodies of the tests.
+    ///
+    /// Tests are not compiled, so they can call any function
+    /// and move the contract to any state.
+    pub fn resolve_tests(&mut self, tree: &Source) {
+        for t in tree.declarations.iter().filter_map(|d| {
+            match d {
+                parsed_ast::Declaration::TestDeclaration(t) => Some(t),
+                _ => None,
+            }
+        }) {
+            let pos = self.tests.len();
+            if self.tests.iter().any(|other| other.name == t.name) {
+                self.diagnostics.push(Report::semantic_error(
+                    t.loc.clone(),
+                    format!("The test `{}` has already been defined earlier.", t.name),
+                ));
+            }
+            let mut scope = Scope::new(
+                &GlobalSymbol::Test(SymbolInfo::new(t.loc.clone(), pos)),
+                ScopeContext::FunctionBody,
+            );
+            let mut body = vec![];
+            let mut mutating = false;
+            let _ = statement(&t.body, &mut body, &mut scope, &mut mutating, self);
+            self.tests.push(TestDeclaration {
+                loc: t.loc.clone(),
+                name: t.name.clone(),
+                body,
+                scope,
+            });
+        }
+    }
+
     /// Resolve function signatures
     /// and adds it to the global symbol table.
     pub fn resolve_functions(&mut self, tree: &Source, delayed_decls: &mut DelayedDeclarations) {
@@ -129,6 +273,20 @@ impl ContractDefinition {
             self.structs[s.i].fields = s_fields;
         }
 
+        for e in &delay.enums {
+            for v in e.decl.variants.iter().filter(|v| !v.fields.is_empty()) {
+                let v_fields = self.analyze_fields(&v.fields, &v.name);
+                let variants = &mut self.enums[e.i].variants;
+                // the duplicated variants are not added.
+                if let Some(variant) = variants
+                    .get_mut(&v.name.name)
+                    .filter(|variant| variant.loc == v.loc)
+                {
+                    variant.fields = v_fields;
+                }
+            }
+        }
+
         for m in &delay.models {
             let m_fields = self.analyze_fields(&m.decl.fields, &m.decl.name);
             self.models[m.i].fields = m_fields;
@@ -221,8 +379,12 @@ impl ContractDefinition {
         analyzed_fields
     }
 
-    /// Resolves enum declarations. This is done in one pass.
-    fn analyze_enum(&mut self, item: &parsed_ast::EnumDeclaration) {
+    /// Resolves enum declarations creating a delay for the fields of the payloads.
+    fn analyze_enum(
+        &mut self,
+        item: &parsed_ast::EnumDeclaration,
+        delay: &mut DelayedDeclarations,
+    ) {
         if item.variants.is_empty() {
             self.diagnostics.push(Report::semantic_error(
                 item.loc.clone(),
@@ -235,28 +397,39 @@ impl ContractDefinition {
             ));
         }
 
-        let mut entries: IndexMap<String, Span> = IndexMap::new();
+        let mut entries: IndexMap<String, EnumVariant> = IndexMap::new();
 
         for e in item.variants.iter() {
-            if entries.get(&e.name).is_some() {
+            if entries.get(&e.name.name).is_some() {
                 self.diagnostics.push(Report::semantic_error(
-                    e.loc.clone(),
-                    format!("`{}` has already been defined", e.name),
+                    e.name.loc.clone(),
+                    format!("`{}` has already been defined", e.name.name),
                 ));
                 continue;
             }
-            entries.insert(e.name.clone(), e.loc.clone());
+            entries.insert(
+                e.name.name.clone(),
+                EnumVariant {
+                    loc: e.loc.clone(),
+                    fields: vec![],
+                },
+            );
         }
 
         let decl = EnumDeclaration {
             loc: item.loc.clone(),
             name: item.name.clone(),
             variants: entries,
+            doc: item.doc.clone(),
         };
 
         let pos = self.enums.len();
 
         self.enums.push(decl);
+        delay.enums.push(DelayedDeclaration {
+            i: pos,
+            decl: item.clone(),
+        });
 
         self.add_global_symbol(
             &item.name.clone(),
@@ -264,6 +437,189 @@ impl ContractDefinition {
         );
     }
 
+    /// Evaluates the value of the constant and adds it to the symbol table.
+    fn analyze_const(&mut self, item: &parsed_ast::ConstDeclaration) -> Result<(), ()> {
+        let expected = match &item.ty {
+            Some(ty) => ExpectedType::Concrete(map_type(self, ty)?.ty),
+            None => ExpectedType::Dynamic(vec![]),
+        };
+        let pos = self.constants.len();
+        let mut scope = Scope::new(
+            &GlobalSymbol::Constant(SymbolInfo::new(item.loc.clone(), pos)),
+            ScopeContext::DeclarationBounds,
+        );
+        let value = expression(&item.value, expected, &mut scope, self)?;
+        let value = if value.is_constant() {
+            value
+        } else {
+            let diagnostics = self.diagnostics.len();
+            eval_const(&value, value.loc().clone(), self).map_err(|_| {
+                self.diagnostics.truncate(diagnostics);
+                self.diagnostics.push(Report::semantic_error(
+                    item.value.loc().clone(),
+                    String::from("The value of the constant must be known at compile time."),
+                ));
+            })?
+        };
+
+        if self.add_global_symbol(
+            &item.name,
+            GlobalSymbol::Constant(SymbolInfo::new(item.loc.clone(), pos)),
+        ) {
+            self.constants.push(ConstDeclaration {
+                loc: item.loc.clone(),
+                name: item.name.clone(),
+                ty: value.ty().clone(),
+                value,
+            });
+        }
+        Ok(())
+    }
+
+    /// Analyses the signatures of the interface functions.
+    ///
+    /// The values are passed to the other application encoded with ARC-4,
+    /// so only the primitive types are allowed in the signatures.
+    fn analyze_interface(&mut self, item: &parsed_ast::InterfaceDeclaration) -> Result<(), ()> {
+        let mut error = false;
+        let mut functions: IndexMap<String, InterfaceFunction> = IndexMap::new();
+        for f in &item.functions {
+            if functions.contains_key(&f.name.name) {
+                self.diagnostics.push(Report::semantic_error(
+                    f.name.loc.clone(),
+                    format!(
+                        "Function `{}` is already declared in the interface.",
+                        f.name.name
+                    ),
+                ));
+                error = true;
+                continue;
+            }
+            let Ok(params) = resolve_func_param(&f.params, self) else {
+                error = true;
+                continue;
+            };
+            let names: Vec<String> = params.keys().cloned().collect();
+            let Ok(return_ty) = resolve_func_return(&f.return_ty, &names, self) else {
+                error = true;
+                continue;
+            };
+
+            for p in params.values() {
+                if p.is_mut {
+                    self.diagnostics.push(Report::semantic_error(
+                        p.loc.clone(),
+                        String::from("Parameters of the interface functions can not be mutable."),
+                    ));
+                    error = true;
+                }
+                if !p.ty.ty.is_primitive() || p.ty.ty == TypeVariant::Unit {
+                    self.diagnostics.push(Report::semantic_error(
+                        p.ty.loc.clone(),
+                        String::from(
+                            "Only primitive types can be passed to the interface functions.",
+                        ),
+                    ));
+                    error = true;
+                }
+            }
+            let ret = match &return_ty {
+                FuncReturnType::Type(ty) => ty,
+                FuncReturnType::ParamType(p) => &p.ty,
+            };
+            if !ret.ty.is_primitive() {
+                self.diagnostics.push(Report::semantic_error(
+                    ret.loc.clone(),
+                    String::from(
+                        "Only primitive types can be returned from the interface functions.",
+                    ),
+                ));
+                error = true;
+            }
+
+            functions.insert(
+                f.name.name.clone(),
+                InterfaceFunction {
+                    loc: f.loc.clone(),
+                    name: f.name.clone(),
+                    params,
+                    return_ty,
+                },
+            );
+        }
+
+        let pos = self.interfaces.len();
+        if self.add_global_symbol(
+            &item.name,
+            GlobalSymbol::Interface(SymbolInfo::new(item.loc.clone(), pos)),
+        ) {
+            self.interfaces.push(InterfaceDeclaration {
+                loc: item.loc.clone(),
+                name: item.name.clone(),
+                functions,
+                doc: item.doc.clone(),
+            });
+        }
+        if error {
+            return Err(());
+        }
+        Ok(())
+    }
+
+    /// Analyses the fields of the event.
+    ///
+    /// The event is logged encoded with ARC-4, so only the primitive types are allowed
+    /// in the fields.
+    fn analyze_event(&mut self, item: &parsed_ast::EventDeclaration) -> Result<(), ()> {
+        let mut error = false;
+        let mut fields: Vec<Param> = Vec::new();
+        for f in &item.fields {
+            if fields.iter().any(|p| p.name.name == f.name.name) {
+                self.diagnostics.push(Report::semantic_error(
+                    f.loc.clone(),
+                    String::from("Field with this name exist."),
+                ));
+                error = true;
+                continue;
+            }
+            let Ok(ty) = map_type(self, &f.ty) else {
+                error = true;
+                continue;
+            };
+            if !ty.ty.is_primitive() || ty.ty == TypeVariant::Unit {
+                self.diagnostics.push(Report::semantic_error(
+                    ty.loc.clone(),
+                    String::from("Only primitive types can be logged in the events."),
+                ));
+                error = true;
+            }
+            fields.push(Param {
+                loc: f.loc.clone(),
+                ty,
+                name: f.name.clone(),
+                is_mut: false,
+                recursive: false,
+            });
+        }
+
+        let pos = self.events.len();
+        if self.add_global_symbol(
+            &item.name,
+            GlobalSymbol::Event(SymbolInfo::new(item.loc.clone(), pos)),
+        ) {
+            self.events.push(EventDeclaration {
+                loc: item.loc.clone(),
+                name: item.name.clone(),
+                fields,
+                doc: item.doc.clone(),
+            });
+        }
+        if error {
+            return Err(());
+        }
+        Ok(())
+    }
+
     /// Analyses struct declaration creating a delay in the symbol table.
     fn analyze_struct(
         &mut self,
@@ -281,6 +637,7 @@ impl ContractDefinition {
                 loc: item.loc.clone(),
                 name: item.name.clone(),
                 fields: Vec::new(),
+                doc: item.doc.clone(),
             });
 
             delay
@@ -313,6 +670,7 @@ impl ContractDefinition {
                 bounds: None,
                 recursive_parent: false,
                 scope: Scope::default(),
+                doc: item.doc.clone(),
             });
 
             delay
@@ -338,12 +696,14 @@ impl ContractDefinition {
         ) {
             self.states.push(StateDeclaration {
                 loc: item.loc.clone(),
+                is_final: item.is_final,
                 name: item.name.clone(),
                 body: None,
                 from: None,
                 bounds: None,
                 recursive_parent: false,
                 scope: Scope::default(),
+                doc: item.doc.clone(),
             });
 
             delay
@@ -355,6 +715,38 @@ impl ContractDefinition {
         }
     }
 
+    /// Functions in the recursive cycle of every function, i.e. the ones calling it back
+    /// directly or transitively, including itself.
+    ///
+    /// Cycles of the functions that are not recursive are empty.
+    pub fn recursive_cycles(&self) -> Vec<HashSet<usize>> {
+        let calls: Vec<Vec<usize>> = self.functions.iter().map(called_functions).collect();
+        // functions reachable from every function through the calls.
+        let reachable: Vec<HashSet<usize>> = (0..calls.len())
+            .map(|i| {
+                let mut reached = HashSet::new();
+                let mut pending = calls[i].clone();
+                while let Some(j) = pending.pop() {
+                    if reached.insert(j) {
+                        pending.extend(&calls[j]);
+                    }
+                }
+                reached
+            })
+            .collect();
+
+        (0..calls.len())
+            .map(|i| {
+                if !reachable[i].contains(&i) {
+                    return HashSet::new();
+                }
+                (0..calls.len())
+                    .filter(|j| reachable[i].contains(j) && reachable[*j].contains(&i))
+                    .collect()
+            })
+            .collect()
+    }
+
     /// Add a symbol to the global symbol table.
     ///
     /// # Errors
@@ -376,6 +768,11 @@ impl ContractDefinition {
                 GlobalSymbol::Enum(_) => "enum",
                 GlobalSymbol::State(_) => "state",
                 GlobalSymbol::Function(_) => "function",
+                GlobalSymbol::Constant(_) => "constant",
+                GlobalSymbol::Alias(_) => "type alias",
+                GlobalSymbol::Interface(_) => "interface",
+                GlobalSymbol::Event(_) => "event",
+                GlobalSymbol::Test(_) => "test",
             };
             let err_msg = format!(
                 "The {} `{}` has already been defined earlier.",
@@ -450,6 +847,14 @@ impl ContractDefinition {
                     None
                 }
             }
+            SymbolKind::Event => {
+                if let GlobalSymbol::Event(s) = sym {
+                    Some(s.clone())
+                } else {
+                    report_error(self, SymbolKind::Event.to_string(), kind.to_string());
+                    None
+                }
+            }
         }
     }
 }
@@ -14,16 +14,28 @@ use folidity_parser::{
 
 use crate::{
     ast::{
-        self,
+        Conditional,
         Expression,
         FunctionCall,
         FunctionType,
+        Index,
+        InterfaceCall,
+        Lambda,
         MemberAccess,
         Param,
+        Slice,
         StateBody,
         StructInit,
         TypeVariant,
         UnaryExpression,
+        VariantInit,
+    },
+    builtin::{
+        cast,
+        check_return_ty,
+        resolve_builtin_call,
+        resolve_builtin_member,
+        BuiltinFunction,
     },
     contract::ContractDefinition,
     global_symbol::{
@@ -31,14 +43,23 @@ use crate::{
         SymbolInfo,
         SymbolKind,
     },
-    symtable::Scope,
+    statement::mutable_arguments,
+    symtable::{
+        Scope,
+        ScopeContext,
+        VariableKind,
+        VariableSym,
+    },
     types::{
         report_type_mismatch,
         ExpectedType,
     },
 };
 
-use super::expression;
+use super::{
+    eval::eval_const,
+    expression,
+};
 
 /// Resolve variable to a AST expression.
 ///
@@ -118,8 +139,16 @@ pub fn resolve_variable(
                         returns: f_ty.returns.clone(),
                     }),
                 }))
-            } else if let Some((var_id, _)) = scope.find_var_index(&ident.name) {
-                let sym = scope.find_symbol(&var_id).unwrap();
+            } else if let Some((var_id, sym)) = find_variable(&ident.name, scope) {
+                scope.mark_used(&var_id);
+                if sym.ty.widens_to(ty) {
+                    let var = Expression::Variable(UnaryExpression {
+                        loc: ident.loc.clone(),
+                        element: var_id,
+                        ty: sym.ty.clone(),
+                    });
+                    return Ok(cast(var, ty.clone(), ident.loc.clone()));
+                }
                 if &sym.ty != ty {
                     report_type_mismatch(&expected_ty, &[sym.ty.clone()], &ident.loc, contract);
                     return Err(());
@@ -130,6 +159,20 @@ pub fn resolve_variable(
                     element: var_id,
                     ty: sym.ty.clone(),
                 }))
+            } else if let Some(GlobalSymbol::Constant(sym)) =
+                contract.declaration_symbols.get(&ident.name).cloned()
+            {
+                resolve_constant(ident, &sym, contract, &expected_ty)
+            } else if let Some(func) = BuiltinFunction::context_value(&ident.name) {
+                resolve_builtin_call(
+                    func,
+                    ident,
+                    &[],
+                    ident.loc.clone(),
+                    scope,
+                    contract,
+                    expected_ty.clone(),
+                )
             } else if let Some(sym) = &contract.find_global_symbol(ident, SymbolKind::Enum) {
                 // todo: rewrite this to reduce code duplication.
                 let enum_ty = TypeVariant::Enum(sym.clone());
@@ -155,8 +198,8 @@ pub fn resolve_variable(
             }
         }
         ExpectedType::Dynamic(tys) => {
-            if let Some((var_id, _)) = scope.find_var_index(&ident.name) {
-                let sym = scope.find_symbol(&var_id).unwrap();
+            if let Some((var_id, sym)) = find_variable(&ident.name, scope) {
+                scope.mark_used(&var_id);
                 if !tys.is_empty() && !tys.contains(&sym.ty) {
                     report_type_mismatch(&expected_ty, &[sym.ty.clone()], &ident.loc, contract);
                     return Err(());
@@ -167,6 +210,20 @@ pub fn resolve_variable(
                     element: var_id,
                     ty: sym.ty.clone(),
                 }))
+            } else if let Some(GlobalSymbol::Constant(sym)) =
+                contract.declaration_symbols.get(&ident.name).cloned()
+            {
+                resolve_constant(ident, &sym, contract, &expected_ty)
+            } else if let Some(func) = BuiltinFunction::context_value(&ident.name) {
+                resolve_builtin_call(
+                    func,
+                    ident,
+                    &[],
+                    ident.loc.clone(),
+                    scope,
+                    contract,
+                    expected_ty.clone(),
+                )
             } else if let Some(sym) = &contract.find_global_symbol(ident, SymbolKind::Enum) {
                 let ty = TypeVariant::Enum(sym.clone());
                 if !tys.is_empty() && !tys.contains(&ty) {
@@ -200,9 +257,43 @@ pub fn resolve_variable(
     }
 }
 
+/// Find the variable accessible in the scope, along with its index.
+fn find_variable(name: &str, scope: &Scope) -> Option<(usize, VariableSym)> {
+    let (var_id, _) = scope.find_var_index(name)?;
+    let sym = scope.find_symbol(&var_id)?;
+    Some((var_id, sym.clone()))
+}
+
+/// Inline the value of the constant at the location of the identifier.
+///
+/// # Errors
+/// - The type of the constant mismatched.
+fn resolve_constant(
+    ident: &Identifier,
+    sym: &SymbolInfo,
+    contract: &mut ContractDefinition,
+    expected_ty: &ExpectedType,
+) -> Result<Expression, ()> {
+    let constant = &contract.constants[sym.i];
+    let value = constant.value.with_loc(&ident.loc);
+    let ty = constant.ty.clone();
+    match expected_ty {
+        ExpectedType::Concrete(t) if ty.widens_to(t) => {
+            Ok(cast(value, t.clone(), ident.loc.clone()))
+        }
+        ExpectedType::Concrete(t) if &ty == t => Ok(value),
+        ExpectedType::Dynamic(tys) if tys.is_empty() || tys.contains(&ty) => Ok(value),
+        _ => {
+            report_type_mismatch(expected_ty, &[ty], &ident.loc, contract);
+            Err(())
+        }
+    }
+}
+
 /// Resolves function call to a concrete ASP expression.
 ///
 /// # Notes
+/// - Built-in functions take precedence over the user defined ones.
 /// - We first resolve the the arguments iteratively and check for any errors.
 /// - We then pattern match the function return type with the expected one.
 ///     - If it is a concrete one, then we simple check for equality.
@@ -223,6 +314,10 @@ pub fn resolve_func_call(
     contract: &mut ContractDefinition,
     expected_ty: ExpectedType,
 ) -> Result<Expression, ()> {
+    if let Some(func) = BuiltinFunction::lookup(&ident.name) {
+        return resolve_builtin_call(func, ident, args, loc, scope, contract, expected_ty);
+    }
+
     let symbol = contract
         .find_global_symbol(ident, SymbolKind::Function)
         .ok_or(())?;
@@ -233,16 +328,11 @@ pub fn resolve_func_call(
         return Err(());
     }
 
-    let (parsed_args, error_args) = parse_args(
-        args,
-        func.params
-            .iter()
-            .map(|p| p.1.clone())
-            .collect::<Vec<Param>>()
-            .as_slice(),
-        scope,
-        contract,
-    );
+    let params: Vec<Param> = func.params.values().cloned().collect();
+    let (parsed_args, error_args) = parse_args(args, &params, scope, contract);
+    if !error_args {
+        mutable_arguments(&parsed_args, &params, scope, contract)?;
+    }
 
     if error_args {
         contract.diagnostics.push(Report::semantic_error(
@@ -411,8 +501,9 @@ pub fn resolve_func_call(
 /// Resolve member access.
 ///
 /// # Note
-/// Currently only variables are supported.
-/// - Check that the var and declaration exist.
+/// The accessed expression is resolved recursively,
+/// so the accesses can be chained, e.g. `s.a.b` or `foo().bar`.
+/// - Check that the expression and declaration exist.
 /// - Check that the member exists.
 /// - Check the type match.
 pub fn resolve_member_access(
@@ -423,40 +514,17 @@ pub fn resolve_member_access(
     contract: &mut ContractDefinition,
     expected_ty: ExpectedType,
 ) -> Result<Expression, ()> {
-    if let parsed_ast::Expression::Variable(_) = expr {
-        let resolved_expr = expression(expr, ExpectedType::Dynamic(vec![]), scope, contract)?;
-        let ast::Expression::Variable(var) = &resolved_expr else {
-            return Err(());
-        };
+    let resolved_expr = expression(expr, ExpectedType::Dynamic(vec![]), scope, contract)?;
 
-        let (mty, pos) = match &var.ty {
-            TypeVariant::State(s) => {
-                let state_decl = &contract.states[s.i].clone();
-                if state_decl.body.is_some() {
-                    let members = state_decl.fields(contract);
+    if let Some(func) = BuiltinFunction::member(resolved_expr.ty(), &member.name) {
+        return resolve_builtin_member(func, resolved_expr, loc, contract, &expected_ty);
+    }
 
-                    if let Some(pos) = members.iter().position(|m| m.name.name == member.name) {
-                        let field = &members[pos];
-                        let ty = field.ty.ty.clone();
-                        (ty, pos)
-                    } else {
-                        contract.diagnostics.push(Report::semantic_error(
-                            member.loc.clone(),
-                            String::from("Member does not exist"),
-                        ));
-                        return Err(());
-                    }
-                } else {
-                    contract.diagnostics.push(Report::semantic_error(
-                        loc.clone(),
-                        String::from("This state has no members."),
-                    ));
-                    return Err(());
-                }
-            }
-            TypeVariant::Struct(s) => {
-                let state_decl = &contract.structs[s.i];
-                let members = &state_decl.fields;
+    let (mty, pos) = match resolved_expr.ty() {
+        TypeVariant::State(s) => {
+            let state_decl = &contract.states[s.i].clone();
+            if state_decl.body.is_some() {
+                let members = state_decl.fields(contract);
 
                 if let Some(pos) = members.iter().position(|m| m.name.name == member.name) {
                     let field = &members[pos];
@@ -469,84 +537,118 @@ pub fn resolve_member_access(
                     ));
                     return Err(());
                 }
+            } else {
+                contract.diagnostics.push(Report::semantic_error(
+                    loc.clone(),
+                    String::from("This state has no members."),
+                ));
+                return Err(());
             }
-            TypeVariant::Model(s) => {
-                let members = contract.models[s.i].fields(contract);
+        }
+        TypeVariant::Struct(s) => {
+            let state_decl = &contract.structs[s.i];
+            let members = &state_decl.fields;
 
-                if let Some(pos) = members.iter().position(|m| m.name.name == member.name) {
-                    let field = &members[pos];
-                    let ty = field.ty.ty.clone();
-                    (ty, pos)
-                } else {
-                    contract.diagnostics.push(Report::semantic_error(
-                        member.loc.clone(),
-                        String::from("Member does not exist"),
-                    ));
-                    return Err(());
-                }
+            if let Some(pos) = members.iter().position(|m| m.name.name == member.name) {
+                let field = &members[pos];
+                let ty = field.ty.ty.clone();
+                (ty, pos)
+            } else {
+                contract.diagnostics.push(Report::semantic_error(
+                    member.loc.clone(),
+                    String::from("Member does not exist"),
+                ));
+                return Err(());
             }
-            TypeVariant::Enum(s) => {
-                let state_decl = &contract.enums[s.i];
-                let members: &Vec<&String> = &state_decl.variants.keys().collect();
+        }
+        TypeVariant::Model(s) => {
+            let members = contract.models[s.i].fields(contract);
 
-                if let Some(pos) = &members.iter().position(|m| *m == &member.name) {
-                    let ty = TypeVariant::Enum(s.clone());
-                    (ty, *pos)
-                } else {
+            if let Some(pos) = members.iter().position(|m| m.name.name == member.name) {
+                let field = &members[pos];
+                let ty = field.ty.ty.clone();
+                (ty, pos)
+            } else {
+                contract.diagnostics.push(Report::semantic_error(
+                    member.loc.clone(),
+                    String::from("Member does not exist"),
+                ));
+                return Err(());
+            }
+        }
+        TypeVariant::Enum(s) => {
+            let enum_decl = &contract.enums[s.i];
+
+            if let Some((pos, _, variant)) = enum_decl.variants.get_full(&member.name) {
+                if !variant.fields.is_empty() {
                     contract.diagnostics.push(Report::semantic_error(
                         member.loc.clone(),
-                        String::from("Member does not exist"),
+                        format!(
+                            "Variant `{}` carries a payload, it must be initialised with its fields.",
+                            member.name
+                        ),
                     ));
                     return Err(());
                 }
-            }
-            _ => {
+                let ty = TypeVariant::Enum(s.clone());
+                (ty, pos)
+            } else {
                 contract.diagnostics.push(Report::semantic_error(
-                    loc.clone(),
-                    String::from("This type does not support member access."),
+                    member.loc.clone(),
+                    String::from("Member does not exist"),
                 ));
                 return Err(());
             }
-        };
+        }
+        _ => {
+            contract.diagnostics.push(Report::semantic_error(
+                loc.clone(),
+                String::from("This type does not support member access."),
+            ));
+            return Err(());
+        }
+    };
 
-        let ty = match &expected_ty {
-            ExpectedType::Concrete(ty) => {
-                if ty != &mty {
-                    report_type_mismatch(&expected_ty, &[mty], &loc, contract);
-                    return Err(());
-                }
-                mty
-            }
-            ExpectedType::Dynamic(tys) => {
-                if !tys.contains(&mty) && !tys.is_empty() {
-                    report_type_mismatch(&expected_ty, &[mty], &loc, contract);
-                    return Err(());
-                } else {
-                    mty
-                }
+    let ty = match &expected_ty {
+        ExpectedType::Concrete(ty) => {
+            if ty != &mty {
+                report_type_mismatch(&expected_ty, &[mty], &loc, contract);
+                return Err(());
             }
-            ExpectedType::Empty => {
-                contract.diagnostics.push(Report::semantic_error(
-                    loc,
-                    String::from("Member access can only be used in expressions or statements."),
-                ));
+            mty
+        }
+        ExpectedType::Dynamic(tys) => {
+            if !tys.contains(&mty) && !tys.is_empty() {
+                report_type_mismatch(&expected_ty, &[mty], &loc, contract);
                 return Err(());
+            } else {
+                mty
             }
-        };
+        }
+        ExpectedType::Empty => {
+            contract.diagnostics.push(Report::semantic_error(
+                loc,
+                String::from("Member access can only be used in expressions or statements."),
+            ));
+            return Err(());
+        }
+    };
 
-        Ok(Expression::MemberAccess(MemberAccess {
+    // variants are accessed from the enum itself.
+    if let TypeVariant::Enum(_) = resolved_expr.ty() {
+        return Ok(Expression::Enum(UnaryExpression {
             loc: loc.clone(),
-            expr: Box::new(resolved_expr),
-            member: (pos, member.loc.clone()),
+            element: pos,
             ty,
-        }))
-    } else {
-        contract.diagnostics.push(Report::semantic_error(
-            loc.clone(),
-            String::from("Non variable access is currently unsupported"),
-        ));
-        Err(())
+        }));
     }
+
+    Ok(Expression::MemberAccess(MemberAccess {
+        loc: loc.clone(),
+        expr: Box::new(resolved_expr),
+        member: (pos, member.loc.clone()),
+        ty,
+    }))
 }
 
 /// Resolve piping. We simply convert to a nested function call.
@@ -580,55 +682,432 @@ pub fn resolve_pipe(
     )
 }
 
-/// Resolve initialise of the structure type.
-/// # Note
-/// - Auto-object fill is currently unsupported.
+/// Resolve the lambda expression.
+///
+/// # Notes
+/// - Types of the parameters are derived from the expected function type.
+/// - If the expected return type is generic, it is inferred from the body.
+/// - Variables of the enclosing scopes referenced in the body are captured.
+///
 /// # Errors
-/// - The type of the structure mismatches the expected one.
-/// - Invalid number of type of arguments.
-pub fn resolve_struct_init(
-    ident: &Identifier,
-    args: &[parsed_ast::Expression],
-    auto_object: &Option<Identifier>,
+/// - The expected type is not a function.
+/// - Invalid number of parameters.
+/// - The type of the body mismatches the expected return type.
+pub fn resolve_lambda(
+    params: &[Identifier],
+    body: &parsed_ast::Expression,
     loc: Span,
-    contract: &mut ContractDefinition,
     scope: &mut Scope,
+    contract: &mut ContractDefinition,
     expected_ty: ExpectedType,
 ) -> Result<Expression, ()> {
-    if auto_object.is_some() {
-        // todo: implement auto-object
+    let ExpectedType::Concrete(TypeVariant::Function(f_ty)) = &expected_ty else {
+        contract.diagnostics.push(Report::type_error(
+            loc.clone(),
+            String::from(
+                "Type of the lambda can not be inferred, it can only be passed as a function.",
+            ),
+        ));
+        return Err(());
+    };
+
+    if f_ty.params.len() != params.len() {
         contract.diagnostics.push(Report::semantic_error(
             loc.clone(),
-            String::from("Auto-object is currently unsupported."),
+            format!(
+                "Lambda is expected to have {} parameters, but {} were declared.",
+                f_ty.params.len(),
+                params.len()
+            ),
         ));
         return Err(());
     }
-    let Some(sym) = GlobalSymbol::lookup(contract, ident) else {
+
+    // every variable declared inside the lambda has a greater index.
+    let first_id = contract.next_var_id;
+    scope.push(ScopeContext::Lambda);
+    let mut param_ids = vec![];
+    for (ident, ty) in params.iter().zip(&f_ty.params) {
+        let id = scope.add(
+            ident,
+            ty.clone(),
+            None,
+            VariableKind::Lambda,
+            false,
+            scope.current,
+            contract,
+        );
+        param_ids.push(id);
+    }
+
+    let body_ty = match f_ty.returns.as_ref() {
+        TypeVariant::Generic(tys) => ExpectedType::Dynamic(tys.clone()),
+        ty => ExpectedType::Concrete(ty.clone()),
+    };
+    let resolved = expression(body, body_ty, scope, contract);
+    scope.pop();
+    let body = resolved?;
+
+    let mut captures = vec![];
+    captured_vars(&body, first_id, scope, &mut captures);
+
+    Ok(Expression::Lambda(Lambda {
+        loc,
+        params: param_ids,
+        captures,
+        ty: TypeVariant::Function(FunctionType {
+            params: f_ty.params.clone(),
+            returns: Box::new(body.ty().clone()),
+        }),
+        body: Box::new(body),
+    }))
+}
+
+/// Resolve the value of one of the branches selected by the condition.
+///
+/// # Notes
+/// - Both branches are resolved to the same type.
+/// - If the expected type is dynamic, the type of the `then` branch is preferred, and the
+///   one of the `else` branch is tried otherwise, e.g. `if c then 1 else a`.
+///
+/// # Errors
+/// - The condition is not a boolean.
+/// - Branches can not be resolved to the same type.
+/// - The expression is not used as a value.
+pub fn resolve_conditional(
+    c: &parsed_ast::Conditional,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    let ty = match expected_ty {
+        ExpectedType::Concrete(ty) => ty,
+        ExpectedType::Dynamic(tys) => branch_type(c, &tys, scope, contract)?,
+        ExpectedType::Empty => {
+            contract.diagnostics.push(Report::semantic_error(
+                c.loc.clone(),
+                String::from("Conditional expression can only be used as a value."),
+            ));
+            return Err(());
+        }
+    };
+
+    let condition = expression(
+        &c.condition,
+        ExpectedType::Concrete(TypeVariant::Bool),
+        scope,
+        contract,
+    );
+    let then_expr = expression(
+        &c.then_expr,
+        ExpectedType::Concrete(ty.clone()),
+        scope,
+        contract,
+    );
+    let else_expr = expression(
+        &c.else_expr,
+        ExpectedType::Concrete(ty.clone()),
+        scope,
+        contract,
+    );
+    let (Ok(condition), Ok(then_expr), Ok(else_expr)) = (condition, then_expr, else_expr) else {
         return Err(());
     };
 
-    let resolve_model = |s: &SymbolInfo,
-                         scope: &mut Scope,
-                         contract: &mut ContractDefinition|
-     -> Result<(Vec<Expression>, Option<SymbolInfo>), ()> {
-        let model_decl = contract.models[s.i].clone();
-        let fields = &model_decl.fields(contract);
-        let parent = model_decl.parent;
+    Ok(Expression::Conditional(Conditional {
+        loc: c.loc.clone(),
+        condition: Box::new(condition),
+        then_expr: Box::new(then_expr),
+        else_expr: Box::new(else_expr),
+        ty,
+    }))
+}
 
-        if fields.len() != args.len() {
-            report_mismatched_args_len(&loc, fields.len(), args.len(), contract);
+/// Find the type of the allowed ones both branches of the conditional expression
+/// can be resolved to.
+fn branch_type(
+    c: &parsed_ast::Conditional,
+    tys: &[TypeVariant],
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+) -> Result<TypeVariant, ()> {
+    // the resolution is dry run on the copies, as the branches are resolved again.
+    let mut dry_scope = scope.clone();
+    let mut dry_contract = contract.clone();
+    let mut resolve = |e: &parsed_ast::Expression, ty: ExpectedType| {
+        expression(e, ty, &mut dry_scope, &mut dry_contract)
+            .ok()
+            .map(|e| e.ty().clone())
+    };
+    let candidates = [
+        resolve(&c.then_expr, ExpectedType::Dynamic(tys.to_vec())),
+        resolve(&c.else_expr, ExpectedType::Dynamic(tys.to_vec())),
+    ];
+    for ty in candidates.into_iter().flatten() {
+        if resolve(&c.then_expr, ExpectedType::Concrete(ty.clone())).is_some()
+            && resolve(&c.else_expr, ExpectedType::Concrete(ty.clone())).is_some()
+        {
+            return Ok(ty);
+        }
+    }
+
+    // the errors of the branches themselves are reported first.
+    let then_resolved = expression(
+        &c.then_expr,
+        ExpectedType::Dynamic(tys.to_vec()),
+        scope,
+        contract,
+    );
+    let else_resolved = expression(
+        &c.else_expr,
+        ExpectedType::Dynamic(tys.to_vec()),
+        scope,
+        contract,
+    );
+    if let (Ok(then_expr), Ok(else_expr)) = (then_resolved, else_resolved) {
+        contract.diagnostics.push(Report::type_error(
+            c.loc.clone(),
+            format!(
+                "Branches of the conditional expression have different types: {} and {}.",
+                then_expr.ty().display(contract).cyan(),
+                else_expr.ty().display(contract).cyan()
+            ),
+        ));
+    }
+    Err(())
+}
+
+/// Resolve the element of the list at the index.
+///
+/// # Notes
+/// - The elements of the list literal are resolved to the expected type.
+/// - Indices of the list literals are checked at compile time, the rest at runtime.
+///
+/// # Errors
+/// - The indexed value is not a list.
+/// - The index is not a `uint`.
+/// - The literal index is out of bounds of the list literal.
+pub fn resolve_index(
+    i: &parsed_ast::Index,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    let list_ty = match &expected_ty {
+        ExpectedType::Concrete(ty) => {
+            ExpectedType::Concrete(TypeVariant::List(Box::new(ty.clone())))
+        }
+        _ => ExpectedType::Dynamic(vec![]),
+    };
+    let list = expression(&i.expr, list_ty, scope, contract);
+    let index = expression(
+        &i.index,
+        ExpectedType::Concrete(TypeVariant::Uint),
+        scope,
+        contract,
+    );
+    let (Ok(list), Ok(index)) = (list, index) else {
+        return Err(());
+    };
+    let ty = list_item(&list, contract)?;
+    check_return_ty(&ty, &expected_ty, &i.loc, contract)?;
+
+    let foldable = is_constant_list(&list) && index.is_literal();
+    let expr = Expression::Index(Index {
+        loc: i.loc.clone(),
+        expr: Box::new(list),
+        index: Box::new(index),
+        ty,
+    });
+    if foldable {
+        eval_const(&expr, i.loc.clone(), contract)
+    } else {
+        Ok(expr)
+    }
+}
+
+/// Resolve the part of the list between the indices.
+///
+/// # Errors
+/// - The sliced value is not a list.
+/// - The indices are not `uint`.
+/// - The literal start is past the literal end, or the slice is out of bounds of the list
+///   literal.
+pub fn resolve_slice(
+    sl: &parsed_ast::Slice,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    let list_ty = match &expected_ty {
+        ExpectedType::Concrete(_) => expected_ty.clone(),
+        _ => ExpectedType::Dynamic(vec![]),
+    };
+    let list = expression(&sl.expr, list_ty, scope, contract);
+    let mut index = |e: &Option<Box<parsed_ast::Expression>>| -> Result<_, ()> {
+        e.as_ref()
+            .map(|e| {
+                expression(
+                    e,
+                    ExpectedType::Concrete(TypeVariant::Uint),
+                    scope,
+                    contract,
+                )
+                .map(Box::new)
+            })
+            .transpose()
+    };
+    let (from, to) = (index(&sl.from), index(&sl.to));
+    let (Ok(list), Ok(from), Ok(to)) = (list, from, to) else {
+        return Err(());
+    };
+    list_item(&list, contract)?;
+    let ty = list.ty().clone();
+    check_return_ty(&ty, &expected_ty, &sl.loc, contract)?;
+
+    if let (Some(Expression::UInt(start)), Some(Expression::UInt(end))) =
+        (from.as_deref(), to.as_deref())
+    {
+        if start.element > end.element {
+            contract.diagnostics.push(Report::semantic_error(
+                sl.loc.clone(),
+                format!(
+                    "Slice starts at {} after its end at {}.",
+                    start.element, end.element
+                ),
+            ));
             return Err(());
         }
-        let (parsed_args, error_args) = parse_args(args, fields, scope, contract);
+    }
 
-        if error_args {
+    let foldable = is_constant_list(&list)
+        && [&from, &to]
+            .iter()
+            .all(|e| e.as_ref().is_none_or(|e| e.is_literal()));
+    let expr = Expression::Slice(Slice {
+        loc: sl.loc.clone(),
+        expr: Box::new(list),
+        from,
+        to,
+        ty,
+    });
+    if foldable {
+        eval_const(&expr, sl.loc.clone(), contract)
+    } else {
+        Ok(expr)
+    }
+}
+
+/// Type of the elements of the indexed list.
+fn list_item(list: &Expression, contract: &mut ContractDefinition) -> Result<TypeVariant, ()> {
+    match list.ty() {
+        TypeVariant::List(item) => Ok(item.as_ref().clone()),
+        ty => {
             contract.diagnostics.push(Report::type_error(
-                loc.clone(),
-                String::from("Argument mismatched."),
+                list.loc().clone(),
+                format!(
+                    "Only lists can be indexed, got {}.",
+                    ty.display(contract).cyan()
+                ),
             ));
-            return Err(());
+            Err(())
+        }
+    }
+}
+
+/// Is the expression a literal of the list of constants?
+fn is_constant_list(e: &Expression) -> bool {
+    matches!(e, Expression::List(u) if u.element.iter().all(Expression::is_constant))
+}
+
+/// Collect variables declared before `first_id` that are referenced in the expression.
+fn captured_vars(e: &Expression, first_id: usize, scope: &Scope, captures: &mut Vec<usize>) {
+    let mut visit = |e: &Expression| captured_vars(e, first_id, scope, captures);
+    match e {
+        Expression::Variable(u) => {
+            // functions and enums are referenced by their global indices.
+            let is_var = scope
+                .find_symbol(&u.element)
+                .is_some_and(|sym| sym.ty == u.ty);
+            if is_var && u.element < first_id && !captures.contains(&u.element) {
+                captures.push(u.element);
+            }
+        }
+        Expression::Multiply(b)
+        | Expression::Divide(b)
+        | Expression::Modulo(b)
+        | Expression::Add(b)
+        | Expression::Subtract(b)
+        | Expression::Equal(b)
+        | Expression::NotEqual(b)
+        | Expression::Greater(b)
+        | Expression::Less(b)
+        | Expression::GreaterEq(b)
+        | Expression::LessEq(b)
+        | Expression::In(b)
+        | Expression::Or(b)
+        | Expression::And(b) => {
+            visit(&b.left);
+            visit(&b.right);
+        }
+        Expression::Not(u) => visit(&u.element),
+        Expression::FunctionCall(f) => f.args.iter().for_each(visit),
+        Expression::BuiltinCall(f) => f.args.iter().for_each(visit),
+        Expression::InterfaceCall(f) => {
+            visit(&f.app);
+            f.args.iter().for_each(visit);
         }
-        Ok((parsed_args, parent))
+        Expression::MemberAccess(m) => visit(&m.expr),
+        Expression::StructInit(s) => s.args.iter().for_each(visit),
+        Expression::VariantInit(v) => v.args.iter().for_each(visit),
+        Expression::List(u) => u.element.iter().for_each(visit),
+        Expression::Optional(u) => u.element.iter().for_each(|e| visit(e)),
+        Expression::Tuple(u) => u.element.iter().for_each(visit),
+        Expression::Lambda(l) => visit(&l.body),
+        Expression::Conditional(c) => {
+            visit(&c.condition);
+            visit(&c.then_expr);
+            visit(&c.else_expr);
+        }
+        Expression::Index(i) => {
+            visit(&i.expr);
+            visit(&i.index);
+        }
+        Expression::Slice(sl) => {
+            visit(&sl.expr);
+            sl.from.iter().chain(&sl.to).for_each(|e| visit(e));
+        }
+        Expression::Int(_)
+        | Expression::UInt(_)
+        | Expression::Float(_)
+        | Expression::Boolean(_)
+        | Expression::String(_)
+        | Expression::Char(_)
+        | Expression::Hex(_)
+        | Expression::Address(_)
+        | Expression::Enum(_) => {}
+    }
+}
+
+/// Resolve initialise of the structure type.
+/// # Note
+/// - If the auto-object is provided via `..obj`, the explicitly listed arguments fill the
+///   leading fields, and the remaining fields are populated from the object's members.
+/// # Errors
+/// - The type of the structure mismatches the expected one.
+/// - Invalid number of type of arguments.
+/// - The auto-object is missing fields or their types mismatch.
+pub fn resolve_struct_init(
+    ident: &Identifier,
+    args: &[parsed_ast::Expression],
+    auto_object: &Option<Identifier>,
+    loc: Span,
+    contract: &mut ContractDefinition,
+    scope: &mut Scope,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    let Some(sym) = GlobalSymbol::lookup(contract, ident) else {
+        return Err(());
     };
 
     let check_types = |tv: TypeVariant, contract: &mut ContractDefinition| -> Result<(), ()> {
@@ -656,26 +1135,17 @@ pub fn resolve_struct_init(
         GlobalSymbol::Struct(s) => {
             check_types(TypeVariant::Struct(s.clone()), contract)?;
 
-            let struct_decl = contract.structs[s.i].clone();
-            if struct_decl.fields.len() != args.len() {
-                report_mismatched_args_len(&loc, struct_decl.fields.len(), args.len(), contract);
-                return Err(());
-            }
-            let (parsed_args, error_args) = parse_args(args, &struct_decl.fields, scope, contract);
-
-            if error_args {
-                contract.diagnostics.push(Report::type_error(
-                    loc.clone(),
-                    String::from("Argument types mismatched."),
-                ));
-                return Err(());
-            }
+            let fields = contract.structs[s.i].fields.clone();
+            let (parsed_args, auto_object) =
+                resolve_init_args(args, &fields, auto_object, &loc, scope, contract)?;
+            let ty = TypeVariant::Struct(s.clone());
+            check_mapping_args(&ty, &fields, &parsed_args, scope, contract)?;
 
             Ok(Expression::StructInit(StructInit {
                 loc: loc.clone(),
                 name: ident.clone(),
                 args: parsed_args,
-                auto_object: None,
+                auto_object,
                 parent: None,
                 ty: TypeVariant::Struct(s.clone()),
             }))
@@ -683,14 +1153,19 @@ pub fn resolve_struct_init(
         GlobalSymbol::Model(s) => {
             check_types(TypeVariant::Model(s.clone()), contract)?;
 
-            let (parsed_args, parent) = resolve_model(&s, scope, contract)?;
+            let model_decl = contract.models[s.i].clone();
+            let fields = model_decl.fields(contract);
+            let (parsed_args, auto_object) =
+                resolve_init_args(args, &fields, auto_object, &loc, scope, contract)?;
+            let ty = TypeVariant::Model(s.clone());
+            check_mapping_args(&ty, &fields, &parsed_args, scope, contract)?;
 
             Ok(Expression::StructInit(StructInit {
                 loc: loc.clone(),
                 name: ident.clone(),
                 args: parsed_args,
-                auto_object: None,
-                parent,
+                auto_object,
+                parent: model_decl.parent,
                 ty: TypeVariant::Model(s.clone()),
             }))
         }
@@ -698,8 +1173,8 @@ pub fn resolve_struct_init(
             check_types(TypeVariant::State(s.clone()), contract)?;
 
             let state_decl = contract.states[s.i].clone();
-            if state_decl.body.is_none() {
-                if !args.is_empty() {
+            let Some(body) = &state_decl.body else {
+                if !args.is_empty() || auto_object.is_some() {
                     contract.diagnostics.push(Report::semantic_error(
                         loc.clone(),
                         String::from("This state has no body to initialise."),
@@ -715,63 +1190,57 @@ pub fn resolve_struct_init(
                         ty: TypeVariant::State(s.clone()),
                     }));
                 };
-            }
+            };
 
-            let body = &state_decl.body.unwrap();
-            let (parsed_args, parent) = match body {
-                StateBody::Raw(fields) => {
-                    if fields.len() != args.len() {
-                        report_mismatched_args_len(&loc, fields.len(), args.len(), contract);
-                        return Err(());
-                    }
-                    let (parsed_args, error_args) = parse_args(args, fields, scope, contract);
-
-                    if error_args {
-                        contract.diagnostics.push(Report::type_error(
-                            loc.clone(),
-                            String::from("Argument types mismatched."),
-                        ));
-                        return Err(());
-                    }
-                    (parsed_args, None)
+            let fields = state_decl.fields(contract);
+            let (parsed_args, auto_object, parent) = match body {
+                StateBody::Raw(_) => {
+                    let (parsed_args, auto_object) =
+                        resolve_init_args(args, &fields, auto_object, &loc, scope, contract)?;
+                    (parsed_args, auto_object, None)
                 }
-                StateBody::Model(s) => {
-                    // todo: support destructuring of fields.
-                    // if we have a single argument, then it is probably a model var.
-                    if args.len() == 1 {
-                        let attempted_expr = expression(
-                            &args[0],
-                            ExpectedType::Concrete(TypeVariant::Model(s.clone())),
-                            scope,
-                            contract,
-                        );
-                        if let Ok(Expression::Variable(var)) = attempted_expr {
-                            return Ok(Expression::StructInit(StructInit {
-                                loc: loc.clone(),
-                                name: ident.clone(),
-                                args: vec![],
-                                auto_object: Some(var.element),
-                                parent: None,
-                                ty: TypeVariant::State(s.clone()),
-                            }));
-                        } else {
-                            resolve_model(s, scope, contract)?
+                StateBody::Model(m) => {
+                    let parent = contract.models[m.i].parent.clone();
+                    // if we have a single model variable, then we initialise the state from it
+                    // as if it was an auto-object.
+                    let model_var = match (args, auto_object) {
+                        ([parsed_ast::Expression::Variable(v)], None) => {
+                            scope
+                                .find_var_index(&v.name)
+                                .and_then(|(i, _)| scope.find_symbol(&i))
+                                .filter(|sym| sym.ty == TypeVariant::Model(m.clone()))
+                                .map(|_| v.clone())
                         }
+                        _ => None,
+                    };
+                    let (args, auto_object) = if model_var.is_some() {
+                        (&[][..], &model_var)
                     } else {
-                        resolve_model(s, scope, contract)?
-                    }
+                        (args, auto_object)
+                    };
+                    let (parsed_args, auto_object) =
+                        resolve_init_args(args, &fields, auto_object, &loc, scope, contract)?;
+                    (parsed_args, auto_object, parent)
                 }
             };
+            let ty = TypeVariant::State(s.clone());
+            check_mapping_args(&ty, &fields, &parsed_args, scope, contract)?;
             Ok(Expression::StructInit(StructInit {
                 loc: loc.clone(),
                 name: ident.clone(),
                 args: parsed_args,
-                auto_object: None,
+                auto_object,
                 parent,
                 ty: TypeVariant::State(s.clone()),
             }))
         }
-        GlobalSymbol::Function(_) | GlobalSymbol::Enum(_) => {
+        GlobalSymbol::Function(_)
+        | GlobalSymbol::Enum(_)
+        | GlobalSymbol::Constant(_)
+        | GlobalSymbol::Alias(_)
+        | GlobalSymbol::Interface(_)
+        | GlobalSymbol::Event(_)
+        | GlobalSymbol::Test(_) => {
             contract.diagnostics.push(Report::semantic_error(
                 ident.loc.clone(),
                 String::from("Functions, States and Enums be initialised."),
@@ -781,6 +1250,356 @@ pub fn resolve_struct_init(
     }
 }
 
+/// Check that the mappings are initialised with the empty mapping, or with the mapping
+/// of the same field of the same declaration, e.g. updated by its functions.
+///
+/// # Note
+/// Entries of the mapping are stored in the boxes of its field, which can not be copied
+/// to the boxes of another field.
+fn check_mapping_args(
+    ty: &TypeVariant,
+    fields: &[Param],
+    args: &[Expression],
+    scope: &Scope,
+    contract: &mut ContractDefinition,
+) -> Result<(), ()> {
+    let mut error = false;
+    for (i, (f, a)) in fields.iter().zip(args).enumerate() {
+        if !matches!(f.ty.ty, TypeVariant::Mapping(_)) {
+            continue;
+        }
+        let is_empty = matches!(a, Expression::List(l) if l.element.is_empty());
+        if is_empty || mapping_origin(a, scope) == Some((ty, i)) {
+            continue;
+        }
+        contract.diagnostics.push(Report::semantic_error(
+            a.loc().clone(),
+            format!(
+                "Mapping `{}` can only be initialised with an empty mapping or with the same field of `{}`.",
+                f.name.name.yellow().bold(),
+                ty.display(contract)
+            ),
+        ));
+        error = true;
+    }
+    if error {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+/// Declaration and the index of the field the entries of the mapping are stored in,
+/// `None` if it is not known statically.
+fn mapping_origin<'a>(e: &'a Expression, scope: &'a Scope) -> Option<(&'a TypeVariant, usize)> {
+    match e {
+        Expression::MemberAccess(m) => Some((m.expr.ty(), m.member.0)),
+        // the functions of the mapping return the updated mapping.
+        Expression::BuiltinCall(c) if matches!(c.returns, TypeVariant::Mapping(_)) => {
+            let map = c.args.first()?;
+            if !matches!(map.ty(), TypeVariant::Mapping(_)) {
+                return None;
+            }
+            mapping_origin(map, scope)
+        }
+        Expression::Variable(v) => {
+            let sym = scope.find_symbol(&v.element)?;
+            // the value of the reassigned variable depends on the path.
+            if sym.mutated {
+                return None;
+            }
+            mapping_origin(sym.value.as_ref()?, scope)
+        }
+        _ => None,
+    }
+}
+
+/// Resolve initialisation of the enum variant with its payload, e.g. `Shape.Circle(5)`.
+///
+/// # Errors
+/// - The expression is not an enum.
+/// - The variant does not exist or carries no payload.
+/// - Invalid number or types of arguments.
+/// - The type of the enum mismatches the expected one.
+pub fn resolve_variant_init(
+    expr: &parsed_ast::Expression,
+    variant: &Identifier,
+    args: &[parsed_ast::Expression],
+    loc: Span,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    if let parsed_ast::Expression::FunctionCall(call) = expr {
+        if let Some(GlobalSymbol::Interface(sym)) =
+            contract.declaration_symbols.get(&call.name.name).cloned()
+        {
+            return resolve_interface_call(
+                &sym,
+                call,
+                variant,
+                args,
+                loc,
+                scope,
+                contract,
+                expected_ty,
+            );
+        }
+    }
+    let parsed_ast::Expression::Variable(ident) = expr else {
+        contract.diagnostics.push(Report::semantic_error(
+            expr.loc().clone(),
+            String::from("Only variants of enums can be initialised with a payload."),
+        ));
+        return Err(());
+    };
+    let Some(sym) = contract.find_global_symbol(ident, SymbolKind::Enum) else {
+        return Err(());
+    };
+
+    let Some((pos, _, decl)) = contract.enums[sym.i].variants.get_full(&variant.name) else {
+        contract.diagnostics.push(Report::semantic_error(
+            variant.loc.clone(),
+            String::from("Member does not exist"),
+        ));
+        return Err(());
+    };
+    let fields = decl.fields.clone();
+    if fields.is_empty() {
+        contract.diagnostics.push(Report::semantic_error(
+            variant.loc.clone(),
+            format!("Variant `{}` carries no payload.", variant.name),
+        ));
+        return Err(());
+    }
+
+    let ty = TypeVariant::Enum(sym);
+    match &expected_ty {
+        ExpectedType::Empty => {
+            contract.diagnostics.push(Report::type_error(
+                loc.clone(),
+                String::from("Initialisation can only happen in variable declaration."),
+            ));
+            return Err(());
+        }
+        ExpectedType::Concrete(e_ty) if e_ty != &ty => {
+            report_type_mismatch(&expected_ty, &[ty], &loc, contract);
+            return Err(());
+        }
+        ExpectedType::Dynamic(tys) if !tys.contains(&ty) && !tys.is_empty() => {
+            report_type_mismatch(&expected_ty, &[ty], &loc, contract);
+            return Err(());
+        }
+        _ => {}
+    }
+
+    let (args, _) = resolve_init_args(args, &fields, &None, &loc, scope, contract)?;
+    Ok(Expression::VariantInit(VariantInit {
+        loc,
+        variant: pos,
+        args,
+        ty,
+    }))
+}
+
+/// Resolve call of the interface function of the application, e.g.
+/// `Token(app).balance(a)`.
+///
+/// # Errors
+/// - The interface takes other than a single application id.
+/// - The function is not declared in the interface.
+/// - Invalid number or types of arguments.
+/// - The return type mismatches the expected one.
+#[allow(clippy::too_many_arguments)]
+fn resolve_interface_call(
+    sym: &SymbolInfo,
+    call: &parsed_ast::FunctionCall,
+    func: &Identifier,
+    args: &[parsed_ast::Expression],
+    loc: Span,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    let [app] = call.args.as_slice() else {
+        contract.diagnostics.push(Report::semantic_error(
+            call.loc.clone(),
+            String::from("Interface is bound to the application by its id, e.g. `Token(app)`."),
+        ));
+        return Err(());
+    };
+    let app = expression(
+        app,
+        ExpectedType::Concrete(TypeVariant::Uint),
+        scope,
+        contract,
+    );
+
+    let interface = &contract.interfaces[sym.i];
+    let Some((pos, _, decl)) = interface.functions.get_full(&func.name) else {
+        contract.diagnostics.push(Report::semantic_error(
+            func.loc.clone(),
+            format!(
+                "Function `{}` is not declared in the interface `{}`.",
+                func.name, interface.name.name
+            ),
+        ));
+        return Err(());
+    };
+    let params: Vec<Param> = decl.params.values().cloned().collect();
+    let returns = decl.return_ty.ty().clone();
+    if params.len() != args.len() {
+        report_mismatched_args_len(&loc, params.len(), args.len(), contract);
+        return Err(());
+    }
+    let (parsed_args, error_args) = parse_args(args, &params, scope, contract);
+    if error_args {
+        contract.diagnostics.push(Report::semantic_error(
+            loc.clone(),
+            String::from("Functional call has invalid arguments."),
+        ));
+    }
+
+    let error_return_ty = match &expected_ty {
+        ExpectedType::Concrete(ty) => ty != &returns,
+        ExpectedType::Dynamic(tys) => !tys.is_empty() && !tys.contains(&returns),
+        ExpectedType::Empty => false,
+    };
+    if error_return_ty {
+        report_type_mismatch(&expected_ty, std::slice::from_ref(&returns), &loc, contract);
+    }
+    let app = app?;
+    if error_args || error_return_ty {
+        return Err(());
+    }
+
+    Ok(Expression::InterfaceCall(InterfaceCall {
+        loc,
+        sym: sym.clone(),
+        func: pos,
+        app: Box::new(app),
+        args: parsed_args,
+        returns,
+    }))
+}
+
+/// Resolve arguments of the struct initialisation against the declared `fields`.
+///
+/// # Note
+/// If the auto-object is provided, every field that is not listed explicitly
+/// is populated by accessing the member with the same name in the object.
+///
+/// # Errors
+/// - Invalid number or types of arguments.
+/// - The auto-object is not a struct, model or state.
+/// - The auto-object does not contain the field or its type mismatches.
+pub(crate) fn resolve_init_args(
+    args: &[parsed_ast::Expression],
+    fields: &[Param],
+    auto_object: &Option<Identifier>,
+    loc: &Span,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+) -> Result<(Vec<Expression>, Option<usize>), ()> {
+    let Some(obj_ident) = auto_object else {
+        if fields.len() != args.len() {
+            report_mismatched_args_len(loc, fields.len(), args.len(), contract);
+            return Err(());
+        }
+        let (parsed_args, error_args) = parse_args(args, fields, scope, contract);
+        if error_args {
+            contract.diagnostics.push(Report::type_error(
+                loc.clone(),
+                String::from("Argument types mismatched."),
+            ));
+            return Err(());
+        }
+        return Ok((parsed_args, None));
+    };
+
+    if args.len() > fields.len() {
+        report_mismatched_args_len(loc, fields.len(), args.len(), contract);
+        return Err(());
+    }
+
+    let obj = expression(
+        &parsed_ast::Expression::Variable(obj_ident.clone()),
+        ExpectedType::Dynamic(vec![]),
+        scope,
+        contract,
+    )?;
+    let Expression::Variable(var) = &obj else {
+        contract.diagnostics.push(Report::semantic_error(
+            obj_ident.loc.clone(),
+            String::from("Auto-object must be a variable."),
+        ));
+        return Err(());
+    };
+
+    let obj_fields = match &var.ty {
+        TypeVariant::Struct(s) => contract.structs[s.i].fields.clone(),
+        TypeVariant::Model(s) => contract.models[s.i].fields(contract),
+        TypeVariant::State(s) => contract.states[s.i].fields(contract),
+        _ => {
+            contract.diagnostics.push(Report::semantic_error(
+                obj_ident.loc.clone(),
+                String::from("Auto-object must be a struct, model or state."),
+            ));
+            return Err(());
+        }
+    };
+
+    let (mut parsed_args, mut error_args) = parse_args(args, fields, scope, contract);
+    if error_args {
+        contract.diagnostics.push(Report::type_error(
+            loc.clone(),
+            String::from("Argument types mismatched."),
+        ));
+    }
+
+    for f in fields.iter().skip(args.len()) {
+        let Some(pos) = obj_fields.iter().position(|of| of.name.name == f.name.name) else {
+            contract.diagnostics.push(Report::semantic_error(
+                obj_ident.loc.clone(),
+                format!(
+                    "`{}` is not initialised and is missing in the auto-object.",
+                    f.name.name.yellow().bold()
+                ),
+            ));
+            error_args = true;
+            continue;
+        };
+
+        let member_ty = &obj_fields[pos].ty.ty;
+        if member_ty != &f.ty.ty {
+            contract.diagnostics.push(Report::type_error(
+                obj_ident.loc.clone(),
+                format!(
+                    "`{}` has type {} in the auto-object, expected {}.",
+                    f.name.name.yellow().bold(),
+                    member_ty.display(contract).red().bold(),
+                    f.ty.ty.display(contract).green().bold()
+                ),
+            ));
+            error_args = true;
+            continue;
+        }
+
+        parsed_args.push(Expression::MemberAccess(MemberAccess {
+            loc: obj_ident.loc.clone(),
+            expr: Box::new(obj.clone()),
+            member: (pos, obj_ident.loc.clone()),
+            ty: f.ty.ty.clone(),
+        }));
+    }
+
+    if error_args {
+        return Err(());
+    }
+
+    Ok((parsed_args, Some(var.element)))
+}
+
 fn parse_args(
     args: &[parsed_ast::Expression],
     params: &[Param],
@@ -1,4 +1,7 @@
-//! Evaluate binary expression of two literals to a single literal.
+//! Evaluate constant expressions to a single literal.
+//!
+//! The operands are evaluated recursively, so that the expressions referencing
+//! constants, enum variants and list literals can be folded as well.
 
 use algonaut_core::Address;
 use folidity_diagnostics::Report;
@@ -14,20 +17,31 @@ use num_traits::{
     CheckedEuclid,
     CheckedMul,
     CheckedSub,
+    Signed,
 };
 
 use crate::{
     ast::{
         BinaryExpression,
+        BuiltinCall,
         Expression,
         TryGetValue,
         TypeVariant,
         UnaryExpression,
     },
+    builtin::{
+        convert_integer,
+        float_pow,
+        float_sqrt,
+        int_pow,
+        integer_literal,
+        BuiltinFunction,
+    },
     contract::ContractDefinition,
 };
 
-// TODO: rewrite when casting is supported.
+use super::nums::check_literal_range;
+
 /// Evaluate constant expression to a literal value.
 /// It assumes that type checking has been done correctly.
 ///
@@ -35,29 +49,265 @@ use crate::{
 /// - Division by 0
 /// - Overflow
 /// - Underflow
+/// - The result is out of range of the sized integer
 /// - Invalid types
+/// - The expression depends on the runtime values
 pub fn eval_const(
     expr: &Expression,
     loc: Span,
     contract: &mut ContractDefinition,
+) -> Result<Expression, ()> {
+    let result = eval(expr, loc.clone(), contract)?;
+    check_literal_range(&result, &loc, contract)?;
+    Ok(result)
+}
+
+/// Evaluate the expression if it is constant, discarding the errors.
+///
+/// The evaluation does not depend on the declarations of the contract,
+/// so it can be used by the later stages on the resolved expressions.
+pub fn fold_constant(expr: &Expression) -> Option<Expression> {
+    // only collects the diagnostics of the evaluation.
+    let mut contract = ContractDefinition::default();
+    eval_const(expr, expr.loc().clone(), &mut contract).ok()
+}
+
+fn eval(expr: &Expression, loc: Span, contract: &mut ContractDefinition) -> Result<Expression, ()> {
+    match expr {
+        Expression::Int(_)
+        | Expression::UInt(_)
+        | Expression::Float(_)
+        | Expression::Boolean(_)
+        | Expression::String(_)
+        | Expression::Char(_)
+        | Expression::Hex(_)
+        | Expression::Address(_)
+        | Expression::Enum(_) => Ok(expr.with_loc(&loc)),
+        Expression::List(u) => {
+            let element = u
+                .element
+                .iter()
+                .map(|e| eval(e, e.loc().clone(), contract))
+                .collect::<Result<Vec<Expression>, ()>>()?;
+            Ok(Expression::List(UnaryExpression {
+                loc,
+                element,
+                ty: u.ty.clone(),
+            }))
+        }
+        Expression::And(b) | Expression::Or(b) => {
+            // the right operand is only evaluated if the left one does not decide the result.
+            let decisive = matches!(expr, Expression::Or(_));
+            let left = eval(&b.left, b.left.loc().clone(), contract)?;
+            let value = if TryGetValue::<bool>::try_get(&left)? == decisive {
+                decisive
+            } else {
+                let right = eval(&b.right, b.right.loc().clone(), contract)?;
+                TryGetValue::<bool>::try_get(&right)?
+            };
+            Ok(bool_literal(value, loc))
+        }
+        Expression::Not(u) => {
+            let element = eval(&u.element, u.element.loc().clone(), contract)?;
+            Ok(bool_literal(!TryGetValue::<bool>::try_get(&element)?, loc))
+        }
+        Expression::Multiply(_)
+        | Expression::Divide(_)
+        | Expression::Modulo(_)
+        | Expression::Add(_)
+        | Expression::Subtract(_)
+        | Expression::Equal(_)
+        | Expression::NotEqual(_)
+        | Expression::Greater(_)
+        | Expression::Less(_)
+        | Expression::GreaterEq(_)
+        | Expression::LessEq(_)
+        | Expression::In(_) => {
+            let mut expr = expr.clone();
+            match &mut expr {
+                Expression::Multiply(b)
+                | Expression::Divide(b)
+                | Expression::Modulo(b)
+                | Expression::Add(b)
+                | Expression::Subtract(b)
+                | Expression::Equal(b)
+                | Expression::NotEqual(b)
+                | Expression::Greater(b)
+                | Expression::Less(b)
+                | Expression::GreaterEq(b)
+                | Expression::LessEq(b)
+                | Expression::In(b) => {
+                    *b.left = eval(&b.left, b.left.loc().clone(), contract)?;
+                    *b.right = eval(&b.right, b.right.loc().clone(), contract)?;
+                }
+                _ => unreachable!(),
+            }
+            operation(&expr, loc, contract)
+        }
+        Expression::BuiltinCall(c) => builtin(c, loc, contract),
+        Expression::Index(i) => {
+            let list = eval_list(&i.expr, contract)?;
+            let index = eval(&i.index, i.index.loc().clone(), contract)?;
+            let index = TryGetValue::<BigUint>::try_get(&index)?;
+            match usize::try_from(&index).ok().and_then(|n| list.get(n)) {
+                Some(item) => Ok(item.with_loc(&loc)),
+                None => {
+                    contract.diagnostics.push(Report::semantic_error(
+                        loc.clone(),
+                        format!(
+                            "Index {} is out of bounds of the list of {} elements.",
+                            index,
+                            list.len()
+                        ),
+                    ));
+                    Err(())
+                }
+            }
+        }
+        Expression::Slice(sl) => {
+            let list = eval_list(&sl.expr, contract)?;
+            let mut bound = |e: &Option<Box<Expression>>, default: usize| -> Result<BigUint, ()> {
+                match e {
+                    Some(e) => {
+                        TryGetValue::<BigUint>::try_get(&eval(e, e.loc().clone(), contract)?)
+                    }
+                    None => Ok(BigUint::from(default)),
+                }
+            };
+            let (from, to) = (bound(&sl.from, 0)?, bound(&sl.to, list.len())?);
+            let range = usize::try_from(&from).ok().zip(usize::try_from(&to).ok());
+            match range.and_then(|(from, to)| list.get(from..to)) {
+                Some(items) => {
+                    Ok(Expression::List(UnaryExpression {
+                        loc,
+                        element: items.to_vec(),
+                        ty: sl.ty.clone(),
+                    }))
+                }
+                None => {
+                    contract.diagnostics.push(Report::semantic_error(
+                        loc.clone(),
+                        format!(
+                            "Slice {}..{} is out of bounds of the list of {} elements.",
+                            from,
+                            to,
+                            list.len()
+                        ),
+                    ));
+                    Err(())
+                }
+            }
+        }
+        _ => {
+            contract.diagnostics.push(Report::type_error(
+                loc.clone(),
+                String::from("This expression cannot be evaluated to a literal."),
+            ));
+            Err(())
+        }
+    }
+}
+
+/// Evaluate the elements of the list literal.
+fn eval_list(e: &Expression, contract: &mut ContractDefinition) -> Result<Vec<Expression>, ()> {
+    match eval(e, e.loc().clone(), contract)? {
+        Expression::List(u) => Ok(u.element),
+        _ => Err(()),
+    }
+}
+
+/// Evaluate the built-in function applied to the constant arguments.
+fn builtin(
+    c: &BuiltinCall,
+    loc: Span,
+    contract: &mut ContractDefinition,
+) -> Result<Expression, ()> {
+    let args = c
+        .args
+        .iter()
+        .map(|e| eval(e, e.loc().clone(), contract))
+        .collect::<Result<Vec<Expression>, ()>>()?;
+    match (&c.func, args.as_slice()) {
+        (BuiltinFunction::Cast, [value @ (Expression::Int(_) | Expression::UInt(_))])
+            if c.returns.is_integer() =>
+        {
+            convert_integer(value.clone(), c.returns.clone(), loc, contract)
+        }
+        (BuiltinFunction::Size, [Expression::List(u)]) => {
+            Ok(uint_literal(BigUint::from(u.element.len()), loc))
+        }
+        (BuiltinFunction::Length, [Expression::String(u)]) => {
+            Ok(uint_literal(BigUint::from(u.element.len()), loc))
+        }
+        (BuiltinFunction::Pow, [base, Expression::UInt(exp)]) => {
+            let power = match base {
+                Expression::Float(u) => {
+                    float_pow(&u.element, &exp.element).map(|n| float_literal(n, loc.clone()))
+                }
+                _ => {
+                    integer_value(base)
+                        .and_then(|n| int_pow(&n, &exp.element))
+                        .map(|n| integer_literal(n, c.returns.clone(), loc.clone()))
+                }
+            };
+            power.ok_or_else(|| {
+                contract.diagnostics.push(Report::semantic_error(
+                    loc.clone(),
+                    String::from("The exponent of the power is too large."),
+                ));
+            })
+        }
+        (BuiltinFunction::Sqrt, [value]) => {
+            let root = match value {
+                Expression::Float(u) => {
+                    float_sqrt(&u.element).map(|n| float_literal(n, loc.clone()))
+                }
+                _ => {
+                    integer_value(value)
+                        .filter(|n| !n.is_negative())
+                        .map(|n| integer_literal(n.sqrt(), c.returns.clone(), loc.clone()))
+                }
+            };
+            root.ok_or_else(|| {
+                contract.diagnostics.push(Report::semantic_error(
+                    loc.clone(),
+                    String::from("Square root of the negative number is undefined."),
+                ));
+            })
+        }
+        _ => {
+            contract.diagnostics.push(Report::type_error(
+                loc.clone(),
+                format!("`{}` cannot be evaluated at compile time.", c.func),
+            ));
+            Err(())
+        }
+    }
+}
+
+/// Evaluate the operation over the literal operands.
+fn operation(
+    expr: &Expression,
+    loc: Span,
+    contract: &mut ContractDefinition,
 ) -> Result<Expression, ()> {
     match expr {
         Expression::Multiply(u) => {
             match u.ty {
-                TypeVariant::Int => {
+                TypeVariant::Int | TypeVariant::I64 => {
                     Ok(Expression::Int(calc::<BigInt, _, _>(
                         u,
                         loc,
-                        TypeVariant::Int,
+                        u.ty.clone(),
                         |a, b| a.checked_mul(&b),
                         contract,
                     )?))
                 }
-                TypeVariant::Uint => {
+                TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64 | TypeVariant::U256 => {
                     Ok(Expression::UInt(calc::<BigUint, _, _>(
                         u,
                         loc,
-                        TypeVariant::Uint,
+                        u.ty.clone(),
                         |a, b| a.checked_mul(&b),
                         contract,
                     )?))
@@ -76,20 +326,20 @@ pub fn eval_const(
         }
         Expression::Divide(u) => {
             match u.ty {
-                TypeVariant::Int => {
+                TypeVariant::Int | TypeVariant::I64 => {
                     Ok(Expression::Int(calc::<BigInt, _, _>(
                         u,
                         loc,
-                        TypeVariant::Int,
+                        u.ty.clone(),
                         |a, b| a.checked_div(&b),
                         contract,
                     )?))
                 }
-                TypeVariant::Uint => {
+                TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64 | TypeVariant::U256 => {
                     Ok(Expression::UInt(calc::<BigUint, _, _>(
                         u,
                         loc,
-                        TypeVariant::Uint,
+                        u.ty.clone(),
                         |a, b| a.checked_div(&b),
                         contract,
                     )?))
@@ -108,20 +358,20 @@ pub fn eval_const(
         }
         Expression::Modulo(u) => {
             match u.ty {
-                TypeVariant::Int => {
+                TypeVariant::Int | TypeVariant::I64 => {
                     Ok(Expression::Int(calc::<BigInt, _, _>(
                         u,
                         loc,
-                        TypeVariant::Int,
+                        u.ty.clone(),
                         |a, b| a.checked_rem_euclid(&b),
                         contract,
                     )?))
                 }
-                TypeVariant::Uint => {
+                TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64 | TypeVariant::U256 => {
                     Ok(Expression::UInt(calc::<BigUint, _, _>(
                         u,
                         loc,
-                        TypeVariant::Uint,
+                        u.ty.clone(),
                         |a, b| a.checked_rem_euclid(&b),
                         contract,
                     )?))
@@ -131,20 +381,20 @@ pub fn eval_const(
         }
         Expression::Add(u) => {
             match u.ty {
-                TypeVariant::Int => {
+                TypeVariant::Int | TypeVariant::I64 => {
                     Ok(Expression::Int(calc::<BigInt, _, _>(
                         u,
                         loc,
-                        TypeVariant::Int,
+                        u.ty.clone(),
                         |a, b| a.checked_add(&b),
                         contract,
                     )?))
                 }
-                TypeVariant::Uint => {
+                TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64 | TypeVariant::U256 => {
                     Ok(Expression::UInt(calc::<BigUint, _, _>(
                         u,
                         loc,
-                        TypeVariant::Uint,
+                        u.ty.clone(),
                         |a, b| a.checked_add(&b),
                         contract,
                     )?))
@@ -173,20 +423,20 @@ pub fn eval_const(
         }
         Expression::Subtract(u) => {
             match u.ty {
-                TypeVariant::Int => {
+                TypeVariant::Int | TypeVariant::I64 => {
                     Ok(Expression::Int(calc::<BigInt, _, _>(
                         u,
                         loc,
-                        TypeVariant::Int,
+                        u.ty.clone(),
                         |a, b| a.checked_sub(&b),
                         contract,
                     )?))
                 }
-                TypeVariant::Uint => {
+                TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64 | TypeVariant::U256 => {
                     Ok(Expression::UInt(calc::<BigUint, _, _>(
                         u,
                         loc,
-                        TypeVariant::Uint,
+                        u.ty.clone(),
                         |a, b| a.checked_sub(&b),
                         contract,
                     )?))
@@ -204,7 +454,8 @@ pub fn eval_const(
             }
         }
         Expression::Equal(u) => {
-            match u.ty {
+            match operand_ty(u) {
+                TypeVariant::Enum(_) => Ok(bool_literal(enum_variants_eq(u)?, loc)),
                 TypeVariant::Int => {
                     Ok(Expression::Boolean(calc::<BigInt, _, _>(
                         u,
@@ -281,7 +532,8 @@ pub fn eval_const(
             }
         }
         Expression::NotEqual(u) => {
-            match u.ty {
+            match operand_ty(u) {
+                TypeVariant::Enum(_) => Ok(bool_literal(!enum_variants_eq(u)?, loc)),
                 TypeVariant::Int => {
                     Ok(Expression::Boolean(calc::<BigInt, _, _>(
                         u,
@@ -358,7 +610,7 @@ pub fn eval_const(
             }
         }
         Expression::Greater(u) => {
-            match u.ty {
+            match operand_ty(u) {
                 TypeVariant::Int => {
                     Ok(Expression::Boolean(calc::<BigInt, _, _>(
                         u,
@@ -399,7 +651,7 @@ pub fn eval_const(
             }
         }
         Expression::Less(u) => {
-            match u.ty {
+            match operand_ty(u) {
                 TypeVariant::Int => {
                     Ok(Expression::Boolean(calc::<BigInt, _, _>(
                         u,
@@ -440,7 +692,7 @@ pub fn eval_const(
             }
         }
         Expression::GreaterEq(u) => {
-            match u.ty {
+            match operand_ty(u) {
                 TypeVariant::Int => {
                     Ok(Expression::Boolean(calc::<BigInt, _, _>(
                         u,
@@ -481,7 +733,7 @@ pub fn eval_const(
             }
         }
         Expression::LessEq(u) => {
-            match u.ty {
+            match operand_ty(u) {
                 TypeVariant::Int => {
                     Ok(Expression::Boolean(calc::<BigInt, _, _>(
                         u,
@@ -521,43 +773,21 @@ pub fn eval_const(
                 _ => Err(()),
             }
         }
-        Expression::Or(u) => {
-            match u.ty {
-                TypeVariant::Bool => {
-                    Ok(Expression::Boolean(calc::<bool, _, _>(
-                        u,
-                        loc,
-                        TypeVariant::Bool,
-                        |a, b| Some(a || b),
-                        contract,
-                    )?))
-                }
-                _ => Err(()),
-            }
-        }
-        Expression::And(u) => {
-            match u.ty {
-                TypeVariant::Bool => {
-                    Ok(Expression::Boolean(calc::<bool, _, _>(
-                        u,
-                        loc,
-                        TypeVariant::Bool,
-                        |a, b| Some(a && b),
-                        contract,
-                    )?))
-                }
-                _ => Err(()),
-            }
-        }
-        Expression::Not(u) => {
-            let value = !TryGetValue::<bool>::try_get(u.element.as_ref())?;
-            Ok(Expression::Boolean({
-                UnaryExpression {
-                    loc,
-                    element: value,
+        Expression::In(u) => {
+            let Expression::List(list) = u.right.as_ref() else {
+                return Err(());
+            };
+            let mut found = false;
+            for item in &list.element {
+                let equal = Expression::Equal(BinaryExpression {
+                    loc: loc.clone(),
+                    left: u.left.clone(),
+                    right: Box::new(item.clone()),
                     ty: TypeVariant::Bool,
-                }
-            }))
+                });
+                found |= TryGetValue::<bool>::try_get(&operation(&equal, loc.clone(), contract)?)?;
+            }
+            Ok(bool_literal(found, loc))
         }
         _ => {
             contract.diagnostics.push(Report::type_error(
@@ -569,6 +799,64 @@ pub fn eval_const(
     }
 }
 
+/// Type of the operands of the binary expression.
+/// Comparisons are typed as `bool`, so their operands are inspected instead.
+fn operand_ty(u: &BinaryExpression) -> TypeVariant {
+    match u.left.as_ref() {
+        Expression::Int(_) => TypeVariant::Int,
+        Expression::UInt(_) => TypeVariant::Uint,
+        Expression::Float(_) => TypeVariant::Float,
+        Expression::Boolean(_) => TypeVariant::Bool,
+        Expression::String(_) => TypeVariant::String,
+        Expression::Char(_) => TypeVariant::Char,
+        Expression::Hex(_) => TypeVariant::Hex,
+        Expression::Address(_) => TypeVariant::Address,
+        Expression::Enum(e) => e.ty.clone(),
+        _ => u.ty.clone(),
+    }
+}
+
+/// Whether both operands are the same variant of the enum.
+fn enum_variants_eq(u: &BinaryExpression) -> Result<bool, ()> {
+    match (u.left.as_ref(), u.right.as_ref()) {
+        (Expression::Enum(a), Expression::Enum(b)) => Ok(a.element == b.element),
+        _ => Err(()),
+    }
+}
+
+fn bool_literal(value: bool, loc: Span) -> Expression {
+    Expression::Boolean(UnaryExpression {
+        loc,
+        element: value,
+        ty: TypeVariant::Bool,
+    })
+}
+
+fn float_literal(value: BigRational, loc: Span) -> Expression {
+    Expression::Float(UnaryExpression {
+        loc,
+        element: value,
+        ty: TypeVariant::Float,
+    })
+}
+
+/// Value of the literal of any integer type.
+fn integer_value(expr: &Expression) -> Option<BigInt> {
+    match expr {
+        Expression::Int(u) => Some(u.element.clone()),
+        Expression::UInt(u) => Some(BigInt::from(u.element.clone())),
+        _ => None,
+    }
+}
+
+fn uint_literal(value: BigUint, loc: Span) -> Expression {
+    Expression::UInt(UnaryExpression {
+        loc,
+        element: value,
+        ty: TypeVariant::Uint,
+    })
+}
+
 /// Calculate expression with the given function `func`.
 /// # Errors
 /// - The operation cannot be performed due to calculation error.
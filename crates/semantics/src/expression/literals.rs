@@ -311,6 +311,14 @@ pub fn resolve_lists(
             match ty {
                 TypeVariant::Set(ty) => derive_expr(ty),
                 TypeVariant::List(ty) => derive_expr(ty),
+                // empty literal creates an empty mapping.
+                TypeVariant::Mapping(_) if exprs.is_empty() => {
+                    Ok(Expression::List(UnaryExpression {
+                        loc: loc.clone(),
+                        element: vec![],
+                        ty: ty.clone(),
+                    }))
+                }
                 _ => {
                     report_type_mismatch(
                         &expected_ty,
@@ -369,3 +377,151 @@ pub fn resolve_lists(
         }
     }
 }
+
+/// Resolve `some(value)` or `none` to an expression.
+///
+/// # Errors
+/// - The expected type is not an option.
+/// - The type of `none` can not be inferred from the context.
+/// - The value is of a different type.
+pub fn resolve_optional(
+    value: &Option<Box<parsed_ast::Expression>>,
+    loc: Span,
+    contract: &mut ContractDefinition,
+    scope: &mut Scope,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    let item_ty = match &expected_ty {
+        ExpectedType::Concrete(TypeVariant::Option(ty)) => Some(ty.as_ref().clone()),
+        ExpectedType::Dynamic(tys) => {
+            let option_tys: Vec<&TypeVariant> = tys
+                .iter()
+                .filter(|ty| matches!(ty, TypeVariant::Option(_)))
+                .collect();
+            match option_tys.first() {
+                Some(TypeVariant::Option(ty)) => Some(ty.as_ref().clone()),
+                _ if tys.is_empty() => None,
+                _ => {
+                    report_type_mismatch(
+                        &expected_ty,
+                        &[TypeVariant::Option(Box::default())],
+                        &loc,
+                        contract,
+                    );
+                    return Err(());
+                }
+            }
+        }
+        ExpectedType::Concrete(_) => {
+            report_type_mismatch(
+                &expected_ty,
+                &[TypeVariant::Option(Box::default())],
+                &loc,
+                contract,
+            );
+            return Err(());
+        }
+        ExpectedType::Empty => {
+            contract.diagnostics.push(Report::semantic_error(
+                loc,
+                String::from("Option literals can only be used in expressions."),
+            ));
+            return Err(());
+        }
+    };
+
+    let element = match value {
+        Some(e) => {
+            let hint = match &item_ty {
+                Some(ty) => ExpectedType::Concrete(ty.clone()),
+                None => ExpectedType::Dynamic(vec![]),
+            };
+            Some(Box::new(expression(e, hint, scope, contract)?))
+        }
+        None => None,
+    };
+    let Some(item_ty) = item_ty.or_else(|| element.as_ref().map(|e| e.ty().clone())) else {
+        contract.diagnostics.push(Report::type_error(
+            loc,
+            String::from("Cannot derive type of `none` without the type annotation."),
+        ));
+        return Err(());
+    };
+
+    Ok(Expression::Optional(UnaryExpression {
+        loc,
+        element,
+        ty: TypeVariant::Option(Box::new(item_ty)),
+    }))
+}
+
+/// Resolve the tuple literal to an expression.
+///
+/// # Errors
+/// - The expected type is not a tuple of the same number of elements.
+/// - Elements are of different types than expected.
+pub fn resolve_tuple(
+    elements: &[parsed_ast::Expression],
+    loc: Span,
+    contract: &mut ContractDefinition,
+    scope: &mut Scope,
+    expected_ty: ExpectedType,
+) -> Result<Expression, ()> {
+    let element_tys = match &expected_ty {
+        ExpectedType::Concrete(TypeVariant::Tuple(tys)) if tys.len() == elements.len() => {
+            Some(tys.clone())
+        }
+        ExpectedType::Dynamic(tys) => {
+            tys.iter()
+                .find_map(|ty| {
+                    match ty {
+                        TypeVariant::Tuple(tys) if tys.len() == elements.len() => Some(tys.clone()),
+                        _ => None,
+                    }
+                })
+                .or_else(|| tys.is_empty().then(Vec::new))
+        }
+        ExpectedType::Concrete(_) => None,
+        ExpectedType::Empty => {
+            contract.diagnostics.push(Report::semantic_error(
+                loc,
+                String::from("Tuple literals can only be used in expressions."),
+            ));
+            return Err(());
+        }
+    };
+    let Some(element_tys) = element_tys else {
+        contract.diagnostics.push(Report::type_error(
+            loc,
+            format!(
+                "Mismatched types: expected to resolve to {}, but found the tuple of {} elements.",
+                expected_ty.display(contract),
+                elements.len()
+            ),
+        ));
+        return Err(());
+    };
+
+    let mut error = false;
+    let mut resolved = vec![];
+    for (i, e) in elements.iter().enumerate() {
+        let hint = match element_tys.get(i) {
+            Some(ty) => ExpectedType::Concrete(ty.clone()),
+            None => ExpectedType::Dynamic(vec![]),
+        };
+        match expression(e, hint, scope, contract) {
+            Ok(e) => resolved.push(e),
+            Err(_) => error = true,
+        }
+    }
+    if error {
+        return Err(());
+    }
+
+    let ty = TypeVariant::Tuple(resolved.iter().map(|e| e.ty().clone()).collect());
+    Ok(Expression::Tuple(UnaryExpression {
+        loc,
+        element: resolved,
+        ty,
+    }))
+}
@@ -1,11 +1,12 @@
-mod complex;
-mod eval;
+pub(crate) mod complex;
+pub(crate) mod eval;
 mod literals;
-mod nums;
+pub(crate) mod nums;
 mod ops;
 #[cfg(test)]
 mod tests;
 
+use folidity_diagnostics::Report;
 use folidity_parser::ast::{
     self as parsed_ast,
 };
@@ -16,6 +17,7 @@ use crate::{
         Param,
         TypeVariant,
     },
+    builtin::resolve_as,
     contract::ContractDefinition,
     global_symbol::SymbolInfo,
     symtable::Scope,
@@ -24,11 +26,16 @@ use crate::{
 
 use self::{
     complex::{
+        resolve_conditional,
         resolve_func_call,
+        resolve_index,
+        resolve_lambda,
         resolve_member_access,
         resolve_pipe,
+        resolve_slice,
         resolve_struct_init,
         resolve_variable,
+        resolve_variant_init,
     },
     literals::{
         resolve_address,
@@ -36,7 +43,9 @@ use self::{
         resolve_char,
         resolve_hex,
         resolve_lists,
+        resolve_optional,
         resolve_string,
+        resolve_tuple,
     },
     nums::{
         resolve_float,
@@ -94,6 +103,12 @@ pub fn expression(
         parsed_ast::Expression::List(u) => {
             resolve_lists(&u.element, u.loc.clone(), contract, scope, expected_ty)
         }
+        parsed_ast::Expression::Optional(u) => {
+            resolve_optional(&u.element, u.loc.clone(), contract, scope, expected_ty)
+        }
+        parsed_ast::Expression::Tuple(u) => {
+            resolve_tuple(&u.element, u.loc.clone(), contract, scope, expected_ty)
+        }
         // operations
         parsed_ast::Expression::Multiply(b) => {
             resolve_multiply(
@@ -276,6 +291,40 @@ pub fn expression(
                 expected_ty,
             )
         }
+        parsed_ast::Expression::VariantInit(v) => {
+            resolve_variant_init(
+                &v.expr,
+                &v.variant,
+                &v.args,
+                v.loc.clone(),
+                scope,
+                contract,
+                expected_ty,
+            )
+        }
+        parsed_ast::Expression::Lambda(l) => {
+            resolve_lambda(
+                &l.params,
+                &l.body,
+                l.loc.clone(),
+                scope,
+                contract,
+                expected_ty,
+            )
+        }
+        parsed_ast::Expression::Cast(c) => resolve_as(c, scope, contract, expected_ty),
+        parsed_ast::Expression::Conditional(c) => {
+            resolve_conditional(c, scope, contract, expected_ty)
+        }
+        parsed_ast::Expression::Index(i) => resolve_index(i, scope, contract, expected_ty),
+        parsed_ast::Expression::Slice(s) => resolve_slice(s, scope, contract, expected_ty),
+        parsed_ast::Expression::Labelled(l) => {
+            contract.diagnostics.push(Report::semantic_error(
+                l.label.loc.clone(),
+                String::from("Labels can only be given to the constraints of `st` blocks."),
+            ));
+            Err(())
+        }
     }
 }
 
@@ -38,25 +38,27 @@ pub fn resolve_integer(
     match &expected_ty {
         ExpectedType::Concrete(ty) => {
             match ty {
-                TypeVariant::Int => {
+                TypeVariant::Int | TypeVariant::I64 => {
                     let number = BigInt::from_str(number_str).unwrap();
+                    check_range(&number, ty, &loc, contract)?;
                     Ok(Expression::Int(UnaryExpression {
                         loc,
                         element: number,
-                        ty: TypeVariant::Int,
+                        ty: ty.clone(),
                     }))
                 }
-                TypeVariant::Uint => {
+                TypeVariant::Uint | TypeVariant::U8 | TypeVariant::U64 | TypeVariant::U256 => {
                     let number = BigUint::from_str(number_str).map_err(|_| {
                         contract.diagnostics.push(Report::semantic_error(
                             loc.clone(),
                             String::from("Expected unsigned integer, got signed one"),
                         ));
                     })?;
+                    check_range(&BigInt::from(number.clone()), ty, &loc, contract)?;
                     Ok(Expression::UInt(UnaryExpression {
                         loc,
                         element: number,
-                        ty: TypeVariant::Uint,
+                        ty: ty.clone(),
                     }))
                 }
                 _ => {
@@ -70,7 +72,14 @@ pub fn resolve_integer(
             // otherwise we resolve to signed int
             // The latter can happen when we have var declaration without the type
             // annotation.
-            let allowed = [TypeVariant::Int, TypeVariant::Uint];
+            let allowed = [
+                TypeVariant::Int,
+                TypeVariant::Uint,
+                TypeVariant::U8,
+                TypeVariant::U64,
+                TypeVariant::I64,
+                TypeVariant::U256,
+            ];
             match resolve_expected_type(&allowed, tys) {
                 Ok(expected) => resolve_integer(number_str, loc, contract, expected),
                 Err(_) => {
@@ -108,7 +117,7 @@ pub fn resolve_float(
             match ty {
                 TypeVariant::Float => {
                     let number_str = if number_str.starts_with('.') {
-                        format!("0.{number_str}")
+                        format!("0{number_str}")
                     } else {
                         number_str.to_string()
                     };
@@ -171,6 +180,51 @@ pub fn resolve_float(
     }
 }
 
+/// Check that the integer value fits in the range of its type.
+///
+/// # Errors
+/// - The value is out of range.
+pub fn check_range(
+    value: &BigInt,
+    ty: &TypeVariant,
+    loc: &Span,
+    contract: &mut ContractDefinition,
+) -> Result<(), ()> {
+    let Some((min, max)) = ty.int_range() else {
+        return Ok(());
+    };
+    let below = min.is_some_and(|min| value < &min);
+    let above = max.is_some_and(|max| value > &max);
+    if below || above {
+        contract.diagnostics.push(Report::semantic_error(
+            loc.clone(),
+            format!(
+                "Value {} is out of range of {}.",
+                value,
+                ty.display(contract)
+            ),
+        ));
+        return Err(());
+    }
+    Ok(())
+}
+
+/// Check that the integer literal fits in the range of its type.
+///
+/// # Errors
+/// - The value is out of range.
+pub fn check_literal_range(
+    expr: &Expression,
+    loc: &Span,
+    contract: &mut ContractDefinition,
+) -> Result<(), ()> {
+    match expr {
+        Expression::Int(u) => check_range(&u.element, &u.ty, loc, contract),
+        Expression::UInt(u) => check_range(&BigInt::from(u.element.clone()), &u.ty, loc, contract),
+        _ => Ok(()),
+    }
+}
+
 fn resolve_expected_type(allowed: &[TypeVariant], tys: &[TypeVariant]) -> Result<ExpectedType, ()> {
     let expected = if tys.is_empty() {
         dynamic_to_concrete_type(&[], allowed)
@@ -38,11 +38,25 @@ pub fn resolve_multiply(
     contract: &mut ContractDefinition,
     expected_ty: ExpectedType,
 ) -> Result<Expression, ()> {
-    let allowed_tys = &[TypeVariant::Int, TypeVariant::Uint, TypeVariant::Float];
+    let allowed_tys = &[
+        TypeVariant::Int,
+        TypeVariant::Uint,
+        TypeVariant::U8,
+        TypeVariant::U64,
+        TypeVariant::I64,
+        TypeVariant::U256,
+        TypeVariant::Float,
+    ];
     match &expected_ty {
         ExpectedType::Concrete(ty) => {
             match ty {
-                TypeVariant::Int | TypeVariant::Uint | TypeVariant::Float => {
+                TypeVariant::Int
+                | TypeVariant::Uint
+                | TypeVariant::U8
+                | TypeVariant::U64
+                | TypeVariant::I64
+                | TypeVariant::U256
+                | TypeVariant::Float => {
                     let resolved_left = expression(left, expected_ty.clone(), scope, contract);
                     let resolved_right = expression(right, expected_ty.clone(), scope, contract);
 
@@ -98,11 +112,25 @@ pub fn resolve_division(
     contract: &mut ContractDefinition,
     expected_ty: ExpectedType,
 ) -> Result<Expression, ()> {
-    let allowed_tys = &[TypeVariant::Int, TypeVariant::Uint, TypeVariant::Float];
+    let allowed_tys = &[
+        TypeVariant::Int,
+        TypeVariant::Uint,
+        TypeVariant::U8,
+        TypeVariant::U64,
+        TypeVariant::I64,
+        TypeVariant::U256,
+        TypeVariant::Float,
+    ];
     match &expected_ty {
         ExpectedType::Concrete(ty) => {
             match ty {
-                TypeVariant::Int | TypeVariant::Uint | TypeVariant::Float => {
+                TypeVariant::Int
+                | TypeVariant::Uint
+                | TypeVariant::U8
+                | TypeVariant::U64
+                | TypeVariant::I64
+                | TypeVariant::U256
+                | TypeVariant::Float => {
                     let resolved_left = expression(left, expected_ty.clone(), scope, contract);
                     let resolved_right = expression(right, expected_ty.clone(), scope, contract);
 
@@ -158,11 +186,23 @@ pub fn resolve_modulo(
     contract: &mut ContractDefinition,
     expected_ty: ExpectedType,
 ) -> Result<Expression, ()> {
-    let allowed_tys = &[TypeVariant::Int, TypeVariant::Uint];
+    let allowed_tys = &[
+        TypeVariant::Int,
+        TypeVariant::Uint,
+        TypeVariant::U8,
+        TypeVariant::U64,
+        TypeVariant::I64,
+        TypeVariant::U256,
+    ];
     match &expected_ty {
         ExpectedType::Concrete(ty) => {
             match ty {
-                TypeVariant::Int | TypeVariant::Uint => {
+                TypeVariant::Int
+                | TypeVariant::Uint
+                | TypeVariant::U8
+                | TypeVariant::U64
+                | TypeVariant::I64
+                | TypeVariant::U256 => {
                     let resolved_left = expression(left, expected_ty.clone(), scope, contract);
                     let resolved_right = expression(right, expected_ty.clone(), scope, contract);
 
@@ -221,13 +261,24 @@ pub fn resolve_addition(
     let allowed_tys = &[
         TypeVariant::Int,
         TypeVariant::Uint,
+        TypeVariant::U8,
+        TypeVariant::U64,
+        TypeVariant::I64,
+        TypeVariant::U256,
         TypeVariant::Float,
         TypeVariant::String,
     ];
     match &expected_ty {
         ExpectedType::Concrete(ty) => {
             match ty {
-                TypeVariant::Int | TypeVariant::Uint | TypeVariant::Float | TypeVariant::String => {
+                TypeVariant::Int
+                | TypeVariant::Uint
+                | TypeVariant::U8
+                | TypeVariant::U64
+                | TypeVariant::I64
+                | TypeVariant::U256
+                | TypeVariant::Float
+                | TypeVariant::String => {
                     let resolved_left = expression(left, expected_ty.clone(), scope, contract);
                     let resolved_right = expression(right, expected_ty.clone(), scope, contract);
 
@@ -283,11 +334,25 @@ pub fn resolve_subtraction(
     contract: &mut ContractDefinition,
     expected_ty: ExpectedType,
 ) -> Result<Expression, ()> {
-    let allowed_tys = &[TypeVariant::Int, TypeVariant::Uint, TypeVariant::Float];
+    let allowed_tys = &[
+        TypeVariant::Int,
+        TypeVariant::Uint,
+        TypeVariant::U8,
+        TypeVariant::U64,
+        TypeVariant::I64,
+        TypeVariant::U256,
+        TypeVariant::Float,
+    ];
     match &expected_ty {
         ExpectedType::Concrete(ty) => {
             match ty {
-                TypeVariant::Int | TypeVariant::Uint | TypeVariant::Float => {
+                TypeVariant::Int
+                | TypeVariant::Uint
+                | TypeVariant::U8
+                | TypeVariant::U64
+                | TypeVariant::I64
+                | TypeVariant::U256
+                | TypeVariant::Float => {
                     let resolved_left = expression(left, expected_ty.clone(), scope, contract);
                     let resolved_right = expression(right, expected_ty.clone(), scope, contract);
 
@@ -346,6 +411,10 @@ pub fn resolve_equality(
     let allowed_tys = &[
         TypeVariant::Int,
         TypeVariant::Uint,
+        TypeVariant::U8,
+        TypeVariant::U64,
+        TypeVariant::I64,
+        TypeVariant::U256,
         TypeVariant::Float,
         TypeVariant::String,
         TypeVariant::Char,
@@ -369,6 +438,7 @@ pub fn resolve_equality(
 
                     let right = Box::new(resolved_right.unwrap());
                     let left = Box::new(resolved_left.unwrap());
+                    check_equatable(left.ty(), &loc, contract)?;
 
                     let expr = Expression::Equal(BinaryExpression {
                         loc: loc.clone(),
@@ -376,7 +446,7 @@ pub fn resolve_equality(
                         right: right.clone(),
                         ty: TypeVariant::Bool,
                     });
-                    if right.is_literal() && left.is_literal() {
+                    if right.is_constant() && left.is_constant() {
                         eval_const(&expr, loc, contract)
                     } else {
                         Ok(expr)
@@ -432,6 +502,10 @@ pub fn resolve_inequality(
     let allowed_tys = &[
         TypeVariant::Int,
         TypeVariant::Uint,
+        TypeVariant::U8,
+        TypeVariant::U64,
+        TypeVariant::I64,
+        TypeVariant::U256,
         TypeVariant::Float,
         TypeVariant::String,
         TypeVariant::Char,
@@ -455,6 +529,7 @@ pub fn resolve_inequality(
 
                     let right = Box::new(resolved_right.unwrap());
                     let left = Box::new(resolved_left.unwrap());
+                    check_equatable(left.ty(), &loc, contract)?;
 
                     let expr = Expression::NotEqual(BinaryExpression {
                         loc: loc.clone(),
@@ -462,7 +537,7 @@ pub fn resolve_inequality(
                         right: right.clone(),
                         ty: TypeVariant::Bool,
                     });
-                    if right.is_literal() && left.is_literal() {
+                    if right.is_constant() && left.is_constant() {
                         eval_const(&expr, loc, contract)
                     } else {
                         Ok(expr)
@@ -518,6 +593,10 @@ pub fn resolve_greater(
     let allowed_tys = &[
         TypeVariant::Int,
         TypeVariant::Uint,
+        TypeVariant::U8,
+        TypeVariant::U64,
+        TypeVariant::I64,
+        TypeVariant::U256,
         TypeVariant::Float,
         TypeVariant::Char,
     ];
@@ -600,6 +679,10 @@ pub fn resolve_less(
     let allowed_tys = &[
         TypeVariant::Int,
         TypeVariant::Uint,
+        TypeVariant::U8,
+        TypeVariant::U64,
+        TypeVariant::I64,
+        TypeVariant::U256,
         TypeVariant::Float,
         TypeVariant::Char,
     ];
@@ -682,6 +765,10 @@ pub fn resolve_greater_eq(
     let allowed_tys = &[
         TypeVariant::Int,
         TypeVariant::Uint,
+        TypeVariant::U8,
+        TypeVariant::U64,
+        TypeVariant::I64,
+        TypeVariant::U256,
         TypeVariant::Float,
         TypeVariant::Char,
     ];
@@ -764,6 +851,10 @@ pub fn resolve_less_eq(
     let allowed_tys = &[
         TypeVariant::Int,
         TypeVariant::Uint,
+        TypeVariant::U8,
+        TypeVariant::U64,
+        TypeVariant::I64,
+        TypeVariant::U256,
         TypeVariant::Float,
         TypeVariant::Char,
     ];
@@ -1136,6 +1227,27 @@ pub fn resolve_in(
     }
 }
 
+/// Check that the values of the type can be compared for equality.
+///
+/// Besides the primitive types, aggregates are compared structurally.
+fn check_equatable(
+    ty: &TypeVariant,
+    loc: &Span,
+    contract: &mut ContractDefinition,
+) -> Result<(), ()> {
+    if ty.is_equatable(contract) {
+        return Ok(());
+    }
+    contract.diagnostics.push(Report::type_error(
+        loc.clone(),
+        format!(
+            "Values of type `{}` cannot be compared for equality.",
+            ty.display(contract)
+        ),
+    ));
+    Err(())
+}
+
 /// Find a valid concrete type from the list of allowed types.
 /// - If suggested types are empty, we resolve the type from the left hand expression.
 /// - Otherwise, we check every possible allowed type and filter out the ones to which the
@@ -426,6 +426,7 @@ fn member_access() {
             name: "MyStruct".to_string(),
         },
         fields: vec![a.clone(), b.clone(), c.clone()],
+        doc: None,
     });
 
     contract.add_global_symbol(
@@ -639,6 +640,7 @@ fn init_struct() {
             name: "MyStruct".to_string(),
         },
         fields: vec![a.clone(), b.clone()],
+        doc: None,
     });
 
     contract.add_global_symbol(
@@ -156,6 +156,13 @@ pub fn function_decl(
         }
     }
     let s_bound = if let Some(state_bound) = &func.state_bound {
+        if matches!(func.vis, parsed_ast::FunctionVisibility::View(_)) {
+            contract.diagnostics.push(Report::semantic_error(
+                state_bound.loc.clone(),
+                String::from("View functions can not transition the contract to other states."),
+            ));
+            error = true;
+        }
         match resolve_func_state_bound(state_bound, contract) {
             Ok(v) => Some(v),
             Err(_) => {
@@ -199,6 +206,42 @@ pub fn function_decl(
         );
     }
 
+    // add params to the scope.
+    for param in params.values() {
+        scope.add(
+            &param.name,
+            param.ty.ty.clone(),
+            None,
+            VariableKind::Param,
+            param.is_mut,
+            scope.current,
+            contract,
+        );
+    }
+
+    let add_state_param = |param: &StateParam,
+                           kind: VariableKind,
+                           scope: &mut Scope,
+                           contract: &mut ContractDefinition| {
+        if let Some(ident) = &param.name {
+            scope.add(
+                ident,
+                TypeVariant::State(param.ty.clone()),
+                None,
+                kind,
+                false,
+                0,
+                contract,
+            );
+        }
+    };
+
+    if let Some(from) = s_bound.as_ref().and_then(|b| b.from.as_ref()) {
+        add_state_param(from, VariableKind::FromState, &mut scope, contract);
+    }
+
+    // access attributes can refer to the params and the current state,
+    // e.g. `@(s.voters)` for `when (VotingState s)`.
     let mut access_attributes: Vec<Expression> = func
         .access_attributes
         .iter()
@@ -243,39 +286,9 @@ pub fn function_decl(
         return Err(());
     }
 
-    // add params to the scope.
-    for param in params.values() {
-        scope.add(
-            &param.name,
-            param.ty.ty.clone(),
-            None,
-            VariableKind::Param,
-            param.is_mut,
-            scope.current,
-            contract,
-        );
-    }
-
-    let mut add_state_param = |param: &StateParam, kind: VariableKind| {
-        if let Some(ident) = &param.name {
-            scope.add(
-                ident,
-                TypeVariant::State(param.ty.clone()),
-                None,
-                kind,
-                false,
-                0,
-                contract,
-            );
-        }
-    };
-
     if let Some(b) = &s_bound {
-        if let Some(from) = &b.from {
-            add_state_param(from, VariableKind::FromState);
-        }
         for state_param in &b.to {
-            add_state_param(state_param, VariableKind::ToState);
+            add_state_param(state_param, VariableKind::ToState, &mut scope, contract);
         }
     }
 
@@ -303,6 +316,7 @@ pub fn function_decl(
 
     decl.scope = scope;
     decl.access_attributes = access_attributes;
+    decl.doc = func.doc.clone();
 
     contract
         .declaration_symbols
@@ -389,7 +403,7 @@ pub fn resolve_func_body(
 }
 
 /// Resolve function parameters.
-fn resolve_func_param(
+pub(crate) fn resolve_func_param(
     parsed_params: &[parsed_ast::Param],
     contract: &mut ContractDefinition,
 ) -> Result<IndexMap<String, Param>, ()> {
@@ -425,7 +439,7 @@ fn resolve_func_param(
     Ok(params)
 }
 
-fn resolve_func_return(
+pub(crate) fn resolve_func_return(
     parsed_ty: &parsed_ast::FuncReturnType,
     params: &[String],
     contract: &mut ContractDefinition,
@@ -15,6 +15,7 @@ pub enum SymbolKind {
     State,
     Enum,
     Function,
+    Event,
 }
 
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
@@ -24,6 +25,12 @@ pub enum GlobalSymbol {
     Enum(SymbolInfo),
     State(SymbolInfo),
     Function(SymbolInfo),
+    Constant(SymbolInfo),
+    Alias(SymbolInfo),
+    Interface(SymbolInfo),
+    Event(SymbolInfo),
+    /// Test of the contract, it is not declared in the global symbol table.
+    Test(SymbolInfo),
 }
 
 impl Default for GlobalSymbol {
@@ -56,6 +63,11 @@ impl GlobalSymbol {
             GlobalSymbol::Enum(s) => &s.loc,
             GlobalSymbol::State(s) => &s.loc,
             GlobalSymbol::Function(s) => &s.loc,
+            GlobalSymbol::Constant(s) => &s.loc,
+            GlobalSymbol::Alias(s) => &s.loc,
+            GlobalSymbol::Interface(s) => &s.loc,
+            GlobalSymbol::Event(s) => &s.loc,
+            GlobalSymbol::Test(s) => &s.loc,
         }
     }
 
@@ -67,6 +79,11 @@ impl GlobalSymbol {
             GlobalSymbol::Enum(s) => s,
             GlobalSymbol::State(s) => s,
             GlobalSymbol::Function(s) => s,
+            GlobalSymbol::Constant(s) => s,
+            GlobalSymbol::Alias(s) => s,
+            GlobalSymbol::Interface(s) => s,
+            GlobalSymbol::Event(s) => s,
+            GlobalSymbol::Test(s) => s,
         }
     }
 }
@@ -80,6 +97,11 @@ impl Display for GlobalSymbol {
             GlobalSymbol::Enum(_) => word("enum"),
             GlobalSymbol::State(_) => word("state"),
             GlobalSymbol::Function(_) => word("function"),
+            GlobalSymbol::Constant(_) => word("constant"),
+            GlobalSymbol::Alias(_) => word("type alias"),
+            GlobalSymbol::Interface(_) => word("interface"),
+            GlobalSymbol::Event(_) => word("event"),
+            GlobalSymbol::Test(_) => word("test"),
         }
     }
 }
@@ -93,6 +115,7 @@ impl Display for SymbolKind {
             SymbolKind::Enum => word("enum"),
             SymbolKind::State => word("state"),
             SymbolKind::Function => word("function"),
+            SymbolKind::Event => word("event"),
         }
     }
 }
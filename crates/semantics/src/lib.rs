@@ -1,6 +1,10 @@
 use bounds::resolve_bounds;
 pub use contract::ContractDefinition;
-use folidity_diagnostics::Report;
+pub use expression::eval::fold_constant;
+use folidity_diagnostics::{
+    Level,
+    Report,
+};
 use folidity_parser::ast::Source;
 pub use folidity_parser::{
     ast::Identifier,
@@ -18,10 +22,14 @@ use types::check_inheritance;
 
 pub mod ast;
 mod bounds;
+pub mod builtin;
 mod contract;
 mod expression;
 mod functions;
 mod global_symbol;
+mod lints;
+pub mod modules;
+pub mod optimizer;
 mod statement;
 pub mod symtable;
 mod types;
@@ -38,6 +46,8 @@ pub enum CompilationError {
     Formal(Vec<Report>),
     /// Error occurred during code emission.
     Emit(Vec<Report>),
+    /// Error occurred during the off-chain simulation.
+    Simulation(Vec<Report>),
 }
 
 impl CompilationError {
@@ -47,8 +57,14 @@ impl CompilationError {
             CompilationError::Syntax(r) => r,
             CompilationError::Formal(r) => r,
             CompilationError::Emit(r) => r,
+            CompilationError::Simulation(r) => r,
         }
     }
+
+    /// The most severe level among the reports.
+    pub fn max_level(&self) -> Option<Level> {
+        self.diagnostics().iter().map(|r| r.level.clone()).max()
+    }
 }
 
 /// Program runner that performs some operations on the input and output artifacts.
@@ -67,29 +83,72 @@ pub trait Runner<I, O> {
 
 impl Runner<Source, ContractDefinition> for ContractDefinition {
     fn run(source: &Source) -> Result<ContractDefinition, CompilationError> {
-        let mut definition = ContractDefinition::default();
-        definition.diagnostics.extend(source.diagnostics.clone());
-        let mut delay = definition.resolve_declarations(source);
-        definition.resolve_fields(&delay);
+        let definition = resolve_contract(source);
 
-        check_inheritance(&mut definition, &delay);
+        // warnings and infos are kept in the definition and do not fail the stage.
+        if definition
+            .diagnostics
+            .iter()
+            .any(|r| r.level == Level::Error)
+        {
+            return Err(CompilationError::Syntax(definition.diagnostics));
+        }
 
-        // todo: add built-in function to environment.
+        Ok(definition)
+    }
+}
 
-        // we can now resolve functions and create scopes.
-        definition.resolve_functions(source, &mut delay);
+/// Parse and semantically analyse the source of the contract.
+///
+/// Malformed input is reported in the diagnostics, this function never panics
+/// and is the entry point of the fuzzing harness.
+///
+/// # Errors
+/// - A list of [`Report`] of the parser and semantic errors.
+pub fn parse_and_check(src: &str) -> Result<ContractDefinition, Vec<Report>> {
+    let tree = folidity_parser::parse(src)?;
+    ContractDefinition::run(&tree).map_err(|e| e.diagnostics().clone())
+}
 
-        // now we can resolve model bounds on all declarations.
-        resolve_bounds(&mut definition, &delay);
+/// Semantically analyse the parsed contract.
+///
+/// Unlike [`Runner::run`], the partially resolved definition is returned
+/// even if errors occur. They are collected in [`ContractDefinition::diagnostics`].
+pub fn resolve_contract(source: &Source) -> ContractDefinition {
+    let mut definition = ContractDefinition::default();
+    definition.diagnostics.extend(source.diagnostics.clone());
+    let mut delay = definition.resolve_declarations(source);
+    definition.resolve_aliases(source);
+    definition.resolve_fields(&delay);
 
-        for f in &delay.functions {
-            let _ = resolve_func_body(&f.decl, f.i, &mut definition);
-        }
+    check_inheritance(&mut definition, &delay);
 
-        if !definition.diagnostics.is_empty() {
-            return Err(CompilationError::Syntax(definition.diagnostics));
-        }
+    // interfaces and events are referenced in the bodies of the functions.
+    definition.resolve_interfaces(source);
+    definition.resolve_events(source);
 
-        Ok(definition)
+    // we can now resolve functions and create scopes.
+    definition.resolve_functions(source, &mut delay);
+
+    // constants are evaluated before they are referenced in the bounds and bodies.
+    definition.resolve_constants(source);
+
+    // now we can resolve model bounds on all declarations.
+    resolve_bounds(&mut definition, &delay);
+
+    for f in &delay.functions {
+        let _ = resolve_func_body(&f.decl, f.i, &mut definition);
     }
+    definition.resolve_tests(source);
+
+    // lints are only meaningful for the successfully resolved contract.
+    if !definition
+        .diagnostics
+        .iter()
+        .any(|r| r.level == Level::Error)
+    {
+        lints::lint(&mut definition);
+    }
+
+    definition
 }
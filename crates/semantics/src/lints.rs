@@ -0,0 +1,283 @@
+//! Lints of the resolved contract.
+//!
+//! They catch the code that is valid, but is likely to be a mistake,
+//! and report it as warnings that do not fail the compilation.
+
+use std::collections::HashSet;
+
+use folidity_diagnostics::Report;
+
+use crate::{
+    ast::{
+        Expression,
+        Function,
+        FunctionVisibility,
+        Statement,
+        TypeVariant,
+    },
+    contract::ContractDefinition,
+    symtable::VariableKind,
+};
+
+/// Report unused variables, unreachable states and functions that can never be called.
+pub fn lint(contract: &mut ContractDefinition) {
+    let mut reports = vec![];
+    unused_variables(contract, &mut reports);
+    unneeded_mut(contract, &mut reports);
+    unreachable_states(contract, &mut reports);
+    uncallable_functions(contract, &mut reports);
+    contract.diagnostics.extend(reports);
+}
+
+/// Report the parameters and local variables of functions that are never read.
+///
+/// Variables prefixed with `_` are ignored.
+fn unused_variables(contract: &ContractDefinition, reports: &mut Vec<Report>) {
+    for f in &contract.functions {
+        for var in f.scope.vars.values() {
+            let name = &var.ident.name;
+            if var.used || name.starts_with('_') {
+                continue;
+            }
+            // `any` and the destructured object are introduced implicitly,
+            // and the viewed state is reported with the function instead.
+            let implicit = name == "any"
+                || name.starts_with('{')
+                || matches!(&f.vis, FunctionVisibility::View(v) if v.name == var.ident);
+            let message = match var.usage {
+                VariableKind::Param => format!("Parameter `{}` is never used.", name),
+                VariableKind::Local | VariableKind::Destructor | VariableKind::Loop
+                    if !implicit =>
+                {
+                    format!("Variable `{}` is never used.", name)
+                }
+                _ => continue,
+            };
+            reports.push(Report::semantic_warning(var.ident.loc.clone(), message));
+        }
+    }
+}
+
+/// Report the mutable parameters and local variables of functions that are never mutated.
+///
+/// Unused variables are already reported by [`unused_variables`].
+fn unneeded_mut(contract: &ContractDefinition, reports: &mut Vec<Report>) {
+    for f in &contract.functions {
+        for var in f.scope.vars.values() {
+            if !var.mutable || var.mutated || !var.used {
+                continue;
+            }
+            let name = &var.ident.name;
+            let message = match var.usage {
+                VariableKind::Param => {
+                    format!(
+                        "Parameter `{}` is declared mutable, but is never mutated.",
+                        name
+                    )
+                }
+                VariableKind::Local | VariableKind::Destructor => {
+                    format!(
+                        "Variable `{}` is declared mutable, but is never mutated.",
+                        name
+                    )
+                }
+                _ => continue,
+            };
+            let mut report = Report::semantic_warning(var.ident.loc.clone(), message);
+            report.note = String::from("Consider removing the `mut` keyword.");
+            reports.push(report);
+        }
+    }
+}
+
+/// Report the states no function transitions to.
+///
+/// Contracts without functions only declare the types for others, and are not linted.
+fn unreachable_states(contract: &ContractDefinition, reports: &mut Vec<Report>) {
+    if contract.functions.is_empty() {
+        return;
+    }
+    let reachable: HashSet<usize> = contract
+        .functions
+        .iter()
+        .filter_map(|f| f.state_bound.as_ref())
+        .flat_map(|b| b.to.iter().map(|s| s.ty.i))
+        .collect();
+    for (i, s) in contract.states.iter().enumerate() {
+        if !reachable.contains(&i) {
+            reports.push(Report::semantic_warning(
+                s.name.loc.clone(),
+                format!(
+                    "State `{}` is unreachable, no function transitions to it.",
+                    s.name.name
+                ),
+            ));
+        }
+    }
+}
+
+/// Report the functions without access attributes that are not called
+/// by the accessible ones, directly or transitively.
+///
+/// Functions with access attributes are public, and the view functions are accessible
+/// or already reported during their resolution.
+fn uncallable_functions(contract: &ContractDefinition, reports: &mut Vec<Report>) {
+    let mut callable: HashSet<usize> = HashSet::new();
+    let mut pending: Vec<usize> = contract
+        .functions
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| f.is_init || f.vis != FunctionVisibility::Priv)
+        .map(|(i, _)| i)
+        .collect();
+    while let Some(i) = pending.pop() {
+        if !callable.insert(i) {
+            continue;
+        }
+        pending.extend(called_functions(&contract.functions[i]));
+    }
+
+    for (i, f) in contract.functions.iter().enumerate() {
+        if callable.contains(&i) || matches!(f.vis, FunctionVisibility::View(_)) {
+            continue;
+        }
+        reports.push(Report::semantic_warning(
+            f.name.loc.clone(),
+            format!(
+                "Function `{}` can never be called, it has no access attributes and is not called by the accessible functions.",
+                f.name.name
+            ),
+        ));
+    }
+}
+
+/// Indices of the functions called or referenced in the body of the function.
+pub(crate) fn called_functions(f: &Function) -> Vec<usize> {
+    let mut called = vec![];
+    for stmt in &f.body {
+        statement_calls(stmt, f, &mut called);
+    }
+    called
+}
+
+fn statement_calls(stmt: &Statement, f: &Function, called: &mut Vec<usize>) {
+    let block = |stmts: &[Statement], called: &mut Vec<usize>| {
+        stmts.iter().for_each(|s| statement_calls(s, f, called))
+    };
+    match stmt {
+        Statement::Variable(v) => {
+            if let Some(e) = &v.value {
+                expression_calls(e, f, called);
+            }
+        }
+        Statement::Assign(a) => expression_calls(&a.value, f, called),
+        Statement::IfElse(b) => {
+            expression_calls(&b.condition, f, called);
+            block(&b.body, called);
+            block(&b.else_part, called);
+        }
+        Statement::ForLoop(l) => {
+            if let Some(e) = &l.var.value {
+                expression_calls(e, f, called);
+            }
+            expression_calls(&l.condition, f, called);
+            expression_calls(&l.incrementer, f, called);
+            block(&l.body, called);
+        }
+        Statement::While(l) => {
+            expression_calls(&l.condition, f, called);
+            block(&l.body, called);
+        }
+        Statement::Iterator(it) => {
+            expression_calls(&it.list, f, called);
+            block(&it.body, called);
+        }
+        Statement::Match(m) => {
+            expression_calls(&m.expr, f, called);
+            m.arms.iter().for_each(|a| block(&a.body, called));
+        }
+        Statement::Return(r) => {
+            if let Some(e) = &r.expr {
+                expression_calls(e, f, called);
+            }
+        }
+        Statement::Expression(e) | Statement::StateTransition(e) => expression_calls(e, f, called),
+        Statement::Emit(e) => e.args.iter().for_each(|a| expression_calls(a, f, called)),
+        Statement::Block(b) => block(&b.statements, called),
+        Statement::Skip(_) | Statement::Break(_) | Statement::Error(_) => {}
+    }
+}
+
+fn expression_calls(e: &Expression, f: &Function, called: &mut Vec<usize>) {
+    let mut visit = |e: &Expression| expression_calls(e, f, called);
+    match e {
+        Expression::Variable(u) => {
+            // functions are referenced by their global indices, unlike the variables.
+            let is_var = f
+                .scope
+                .find_symbol(&u.element)
+                .is_some_and(|s| s.ty == u.ty);
+            if matches!(u.ty, TypeVariant::Function(_)) && !is_var {
+                called.push(u.element);
+            }
+        }
+        Expression::Multiply(b)
+        | Expression::Divide(b)
+        | Expression::Modulo(b)
+        | Expression::Add(b)
+        | Expression::Subtract(b)
+        | Expression::Equal(b)
+        | Expression::NotEqual(b)
+        | Expression::Greater(b)
+        | Expression::Less(b)
+        | Expression::GreaterEq(b)
+        | Expression::LessEq(b)
+        | Expression::In(b)
+        | Expression::Or(b)
+        | Expression::And(b) => {
+            visit(&b.left);
+            visit(&b.right);
+        }
+        Expression::Not(u) => visit(&u.element),
+        Expression::FunctionCall(call) => {
+            called.push(call.sym.i);
+            for arg in &call.args {
+                expression_calls(arg, f, called);
+            }
+        }
+        Expression::BuiltinCall(call) => call.args.iter().for_each(visit),
+        Expression::InterfaceCall(call) => {
+            visit(&call.app);
+            call.args.iter().for_each(visit);
+        }
+        Expression::MemberAccess(m) => visit(&m.expr),
+        Expression::StructInit(s) => s.args.iter().for_each(visit),
+        Expression::VariantInit(v) => v.args.iter().for_each(visit),
+        Expression::List(u) => u.element.iter().for_each(visit),
+        Expression::Optional(u) => u.element.iter().for_each(|e| visit(e)),
+        Expression::Tuple(u) => u.element.iter().for_each(visit),
+        Expression::Lambda(l) => visit(&l.body),
+        Expression::Conditional(c) => {
+            visit(&c.condition);
+            visit(&c.then_expr);
+            visit(&c.else_expr);
+        }
+        Expression::Index(i) => {
+            visit(&i.expr);
+            visit(&i.index);
+        }
+        Expression::Slice(sl) => {
+            visit(&sl.expr);
+            sl.from.iter().chain(&sl.to).for_each(|e| visit(e));
+        }
+        Expression::Int(_)
+        | Expression::UInt(_)
+        | Expression::Float(_)
+        | Expression::Boolean(_)
+        | Expression::String(_)
+        | Expression::Char(_)
+        | Expression::Hex(_)
+        | Expression::Address(_)
+        | Expression::Enum(_) => {}
+    }
+}
@@ -0,0 +1,188 @@
+//! Resolution of multi-file projects.
+//!
+//! Files imported with `import "path.fol"` are loaded recursively and concatenated
+//! into a single source, so all declarations share one symbol table and every location
+//! stays unique. Each module keeps its offset in the combined source
+//! to map the diagnostics back to the original file.
+
+use std::{
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use folidity_diagnostics::Report;
+use folidity_parser::{
+    ast::{
+        Declaration,
+        Source,
+    },
+    parse,
+    Span,
+};
+
+/// Source file of the project.
+#[derive(Debug, Clone)]
+pub struct Module {
+    /// Path to the file.
+    pub path: PathBuf,
+    /// Contents of the file.
+    pub contents: String,
+    /// Offset of the file in the combined source.
+    pub offset: usize,
+}
+
+/// Entry file with all the files imported from it.
+#[derive(Debug, Clone, Default)]
+pub struct Project {
+    /// Modules in the order they appear in the combined source.
+    pub modules: Vec<Module>,
+    /// Combined source of all modules.
+    pub contents: String,
+    /// Errors occurred during the resolution of the imports.
+    pub diagnostics: Vec<Report>,
+}
+
+impl Project {
+    /// Load the entry file and resolve the imports recursively.
+    ///
+    /// # Errors
+    /// - The entry file can not be read.
+    ///
+    /// # Note
+    /// Unresolved imports and syntax errors of the modules are reported in `diagnostics`.
+    pub fn load(entry: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(entry)?;
+        let mut project = Project::default();
+        project.add_module(entry.to_path_buf(), contents);
+        Ok(project)
+    }
+
+    /// Path to the entry file of the project.
+    pub fn entry(&self) -> &Path {
+        self.modules
+            .first()
+            .map(|m| m.path.as_path())
+            .unwrap_or(Path::new(""))
+    }
+
+    /// Parse the combined source of the project.
+    pub fn parse(&self) -> Result<Source, Vec<Report>> {
+        if !self.diagnostics.is_empty() {
+            return Err(self.diagnostics.clone());
+        }
+        parse(&self.contents)
+    }
+
+    /// Find the module the location belongs to, and the location relative to it.
+    pub fn locate(&self, loc: &Span) -> Option<(&Module, Span)> {
+        self.modules
+            .iter()
+            .filter(|m| m.offset <= loc.start)
+            .max_by_key(|m| m.offset)
+            .map(|m| {
+                let end = (loc.end - m.offset).min(m.contents.len());
+                (
+                    m,
+                    Span {
+                        start: loc.start - m.offset,
+                        end,
+                    },
+                )
+            })
+    }
+
+    /// Make the locations of the report and its notes relative to the files of the
+    /// modules.
+    ///
+    /// The locations that are already relative to a file are kept.
+    pub fn locate_report(&self, report: &Report) -> Report {
+        let mut report = report.clone();
+        if report.file.is_none() {
+            if let Some((module, loc)) = self.locate(&report.loc) {
+                report.file = Some(module.path.to_string_lossy().to_string());
+                report.loc = loc;
+            }
+        }
+        report.additional_info = report
+            .additional_info
+            .iter()
+            .map(|r| self.locate_report(r))
+            .collect();
+        report
+    }
+
+    fn add_module(&mut self, path: PathBuf, contents: String) {
+        if !self.contents.is_empty() {
+            self.contents.push('\n');
+        }
+        let offset = self.contents.len();
+        self.contents.push_str(&contents);
+        self.modules.push(Module {
+            path: path.clone(),
+            contents: contents.clone(),
+            offset,
+        });
+
+        let tree = match parse(&contents) {
+            Ok(tree) => tree,
+            Err(errors) => {
+                self.diagnostics
+                    .extend(errors.into_iter().map(|r| shift_report(r, offset)));
+                return;
+            }
+        };
+
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        for d in &tree.declarations {
+            let Declaration::Import(import) = d else {
+                continue;
+            };
+            let loc = shift_span(&import.loc, offset);
+            let import_path = dir.join(&import.path);
+            let resolved = fs::canonicalize(&import_path).and_then(|canonical| {
+                let imported = self
+                    .modules
+                    .iter()
+                    .any(|m| fs::canonicalize(&m.path).is_ok_and(|p| p == canonical));
+                if imported {
+                    Ok(None)
+                } else {
+                    fs::read_to_string(&canonical).map(Some)
+                }
+            });
+
+            match resolved {
+                Ok(Some(contents)) => self.add_module(import_path, contents),
+                // the module has been imported before.
+                Ok(None) => {}
+                Err(e) => {
+                    self.diagnostics.push(Report::semantic_error(
+                        loc,
+                        format!("Cannot import `{}`: {}.", import.path, e),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn shift_span(loc: &Span, offset: usize) -> Span {
+    Span {
+        start: loc.start + offset,
+        end: loc.end + offset,
+    }
+}
+
+fn shift_report(mut report: Report, offset: usize) -> Report {
+    report.loc = shift_span(&report.loc, offset);
+    report.additional_info = report
+        .additional_info
+        .into_iter()
+        .map(|r| shift_report(r, offset))
+        .collect();
+    report
+}
@@ -0,0 +1,249 @@
+//! Constant propagation through immutable `let` bindings
+//! and folding of the expressions whose operands became literals.
+
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use crate::{
+    ast::{
+        Expression,
+        Statement,
+    },
+    expression::eval::eval_const,
+    ContractDefinition,
+};
+
+/// Literal values of the variables indexed by their position in the scope.
+type Constants = HashMap<usize, Expression>;
+
+/// Propagate the constants in the function body.
+pub fn propagate(body: &mut [Statement], contract: &mut ContractDefinition) {
+    let mut assigned = HashSet::new();
+    assigned_vars(body, &mut assigned);
+
+    let mut consts = Constants::new();
+    statements(body, &assigned, &mut consts, contract);
+}
+
+/// Collect positions of the variables that are reassigned in the body.
+fn assigned_vars(stmts: &[Statement], assigned: &mut HashSet<usize>) {
+    for stmt in stmts {
+        match stmt {
+            Statement::Assign(a) => {
+                assigned.insert(a.pos);
+            }
+            Statement::IfElse(b) => {
+                assigned_vars(&b.body, assigned);
+                assigned_vars(&b.else_part, assigned);
+            }
+            Statement::ForLoop(l) => {
+                // the loop variable is updated by the incrementer.
+                assigned.insert(l.var.pos);
+                assigned_vars(&l.body, assigned);
+            }
+            Statement::While(l) => assigned_vars(&l.body, assigned),
+            Statement::Iterator(it) => assigned_vars(&it.body, assigned),
+            Statement::Match(m) => m.arms.iter().for_each(|a| assigned_vars(&a.body, assigned)),
+            Statement::Block(b) => assigned_vars(&b.statements, assigned),
+            _ => {}
+        }
+    }
+}
+
+fn statements(
+    stmts: &mut [Statement],
+    assigned: &HashSet<usize>,
+    consts: &mut Constants,
+    contract: &mut ContractDefinition,
+) {
+    for stmt in stmts {
+        statement(stmt, assigned, consts, contract);
+    }
+}
+
+fn statement(
+    stmt: &mut Statement,
+    assigned: &HashSet<usize>,
+    consts: &mut Constants,
+    contract: &mut ContractDefinition,
+) {
+    match stmt {
+        Statement::Variable(var) => {
+            if let Some(value) = &mut var.value {
+                expression(value, consts, contract);
+                if !var.mutable
+                    && var.names.len() == 1
+                    && !assigned.contains(&var.pos)
+                    && value.is_literal()
+                {
+                    consts.insert(var.pos, value.clone());
+                }
+            }
+        }
+        Statement::Assign(a) => expression(&mut a.value, consts, contract),
+        Statement::IfElse(b) => {
+            expression(&mut b.condition, consts, contract);
+            statements(&mut b.body, assigned, consts, contract);
+            statements(&mut b.else_part, assigned, consts, contract);
+        }
+        Statement::ForLoop(l) => {
+            if let Some(value) = &mut l.var.value {
+                expression(value, consts, contract);
+            }
+            expression(&mut l.condition, consts, contract);
+            expression(&mut l.incrementer, consts, contract);
+            statements(&mut l.body, assigned, consts, contract);
+        }
+        Statement::While(l) => {
+            expression(&mut l.condition, consts, contract);
+            statements(&mut l.body, assigned, consts, contract);
+        }
+        Statement::Iterator(it) => {
+            expression(&mut it.list, consts, contract);
+            statements(&mut it.body, assigned, consts, contract);
+        }
+        Statement::Match(m) => {
+            expression(&mut m.expr, consts, contract);
+            for arm in &mut m.arms {
+                statements(&mut arm.body, assigned, consts, contract);
+            }
+        }
+        Statement::Return(r) => {
+            if let Some(e) = &mut r.expr {
+                expression(e, consts, contract);
+            }
+        }
+        Statement::Expression(e) | Statement::StateTransition(e) => expression(e, consts, contract),
+        Statement::Emit(e) => {
+            for a in &mut e.args {
+                expression(a, consts, contract);
+            }
+        }
+        Statement::Block(b) => statements(&mut b.statements, assigned, consts, contract),
+        Statement::Skip(_) | Statement::Break(_) | Statement::Error(_) => {}
+    }
+}
+
+fn expression(expr: &mut Expression, consts: &Constants, contract: &mut ContractDefinition) {
+    match expr {
+        Expression::Variable(u) => {
+            if let Some(value) = consts.get(&u.element) {
+                *expr = value.with_loc(&u.loc);
+            }
+        }
+        Expression::Multiply(b)
+        | Expression::Divide(b)
+        | Expression::Modulo(b)
+        | Expression::Add(b)
+        | Expression::Subtract(b)
+        | Expression::Equal(b)
+        | Expression::NotEqual(b)
+        | Expression::Greater(b)
+        | Expression::Less(b)
+        | Expression::GreaterEq(b)
+        | Expression::LessEq(b)
+        | Expression::Or(b)
+        | Expression::And(b) => {
+            expression(&mut b.left, consts, contract);
+            expression(&mut b.right, consts, contract);
+            if b.left.is_constant() && b.right.is_constant() {
+                fold(expr, contract);
+            }
+        }
+        Expression::In(b) => {
+            expression(&mut b.left, consts, contract);
+            expression(&mut b.right, consts, contract);
+        }
+        Expression::Not(u) => {
+            expression(&mut u.element, consts, contract);
+            if u.element.is_literal() {
+                fold(expr, contract);
+            }
+        }
+        Expression::FunctionCall(f) => {
+            for a in &mut f.args {
+                expression(a, consts, contract);
+            }
+        }
+        Expression::BuiltinCall(f) => {
+            for a in &mut f.args {
+                expression(a, consts, contract);
+            }
+        }
+        Expression::InterfaceCall(f) => {
+            expression(&mut f.app, consts, contract);
+            for a in &mut f.args {
+                expression(a, consts, contract);
+            }
+        }
+        Expression::StructInit(s) => {
+            for a in &mut s.args {
+                expression(a, consts, contract);
+            }
+        }
+        Expression::VariantInit(v) => {
+            for a in &mut v.args {
+                expression(a, consts, contract);
+            }
+        }
+        Expression::List(u) | Expression::Tuple(u) => {
+            for e in &mut u.element {
+                expression(e, consts, contract);
+            }
+        }
+        Expression::Optional(u) => {
+            if let Some(e) = &mut u.element {
+                expression(e, consts, contract);
+            }
+        }
+        Expression::MemberAccess(m) => expression(&mut m.expr, consts, contract),
+        Expression::Lambda(l) => expression(&mut l.body, consts, contract),
+        Expression::Conditional(c) => {
+            expression(&mut c.condition, consts, contract);
+            expression(&mut c.then_expr, consts, contract);
+            expression(&mut c.else_expr, consts, contract);
+            // the branch of the constant condition is selected.
+            if let Expression::Boolean(u) = c.condition.as_ref() {
+                let selected = if u.element {
+                    &c.then_expr
+                } else {
+                    &c.else_expr
+                };
+                *expr = selected.as_ref().clone();
+            }
+        }
+        Expression::Index(i) => {
+            expression(&mut i.expr, consts, contract);
+            expression(&mut i.index, consts, contract);
+        }
+        Expression::Slice(sl) => {
+            expression(&mut sl.expr, consts, contract);
+            for e in sl.from.iter_mut().chain(&mut sl.to) {
+                expression(e, consts, contract);
+            }
+        }
+        Expression::Int(_)
+        | Expression::UInt(_)
+        | Expression::Float(_)
+        | Expression::Boolean(_)
+        | Expression::String(_)
+        | Expression::Char(_)
+        | Expression::Hex(_)
+        | Expression::Address(_)
+        | Expression::Enum(_) => {}
+    }
+}
+
+/// Evaluate the expression of literals in place.
+///
+/// Expressions that can not be evaluated, e.g. overflowing ones, are left intact
+/// to fail at runtime.
+fn fold(expr: &mut Expression, contract: &mut ContractDefinition) {
+    let diagnostics = contract.diagnostics.len();
+    match eval_const(expr, expr.loc().clone(), contract) {
+        Ok(value) => *expr = value,
+        Err(_) => contract.diagnostics.truncate(diagnostics),
+    }
+}
@@ -0,0 +1,82 @@
+//! Elimination of the branches with constant conditions and of the unreachable
+//! statements.
+
+use crate::ast::{
+    Expression,
+    Statement,
+    StatementBlock,
+};
+
+/// Eliminate the dead code in the function body.
+pub fn eliminate(body: &mut Vec<Statement>) {
+    let mut stmts = std::mem::take(body);
+    statements(&mut stmts);
+    *body = stmts;
+}
+
+fn statements(stmts: &mut Vec<Statement>) {
+    let mut optimized = Vec::new();
+    for mut stmt in std::mem::take(stmts) {
+        statement(&mut stmt);
+        let Some(stmt) = branch(stmt) else {
+            continue;
+        };
+        let terminates = terminates(&stmt);
+        optimized.push(stmt);
+        // the rest of the statements are never executed.
+        if terminates {
+            break;
+        }
+    }
+    *stmts = optimized;
+}
+
+fn statement(stmt: &mut Statement) {
+    match stmt {
+        Statement::IfElse(b) => {
+            statements(&mut b.body);
+            statements(&mut b.else_part);
+        }
+        Statement::ForLoop(l) => statements(&mut l.body),
+        Statement::While(l) => statements(&mut l.body),
+        Statement::Iterator(it) => statements(&mut it.body),
+        Statement::Match(m) => m.arms.iter_mut().for_each(|a| statements(&mut a.body)),
+        Statement::Block(b) => statements(&mut b.statements),
+        _ => {}
+    }
+}
+
+/// Replace the branching with the constant condition with the taken branch.
+/// The branch is kept in a block to preserve the scope.
+fn branch(stmt: Statement) -> Option<Statement> {
+    let Statement::IfElse(b) = stmt else {
+        return Some(stmt);
+    };
+    let Expression::Boolean(cond) = &b.condition else {
+        return Some(Statement::IfElse(b));
+    };
+    let mut statements = if cond.element { b.body } else { b.else_part };
+    match statements.len() {
+        0 => None,
+        // the branch is already a block.
+        1 if matches!(statements[0], Statement::Block(_)) => statements.pop(),
+        _ => {
+            Some(Statement::Block(StatementBlock {
+                loc: b.loc,
+                statements,
+            }))
+        }
+    }
+}
+
+/// Does the statement always transfer the control out of the enclosing block?
+fn terminates(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Return(_) | Statement::Skip(_) | Statement::Break(_) => true,
+        Statement::Block(b) => b.statements.iter().any(terminates),
+        Statement::IfElse(b) => b.body.iter().any(terminates) && b.else_part.iter().any(terminates),
+        // the arms cover every variant.
+        Statement::Match(m) => m.arms.iter().all(|a| a.body.iter().any(terminates)),
+        _ => false,
+    }
+}
@@ -0,0 +1,46 @@
+//! Optimization passes over the resolved contract.
+//!
+//! The passes run on the bodies of the functions after they have been resolved
+//! and preserve the semantics of the code, so they can be safely applied before the
+//! emission.
+
+use crate::ContractDefinition;
+
+mod constants;
+mod dead_code;
+
+/// Level of the optimizations applied to the contract.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptLevel {
+    /// No optimizations.
+    #[default]
+    O0,
+    /// Constant propagation and dead code elimination.
+    O1,
+}
+
+impl From<u8> for OptLevel {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => OptLevel::O0,
+            _ => OptLevel::O1,
+        }
+    }
+}
+
+/// Optimize the bodies of the functions of the contract.
+///
+/// The level is recorded in the contract, so the emitters can apply their own passes.
+pub fn optimize(contract: &mut ContractDefinition, level: OptLevel) {
+    contract.opt_level = level;
+    if level == OptLevel::O0 {
+        return;
+    }
+
+    for i in 0..contract.functions.len() {
+        let mut body = std::mem::take(&mut contract.functions[i].body);
+        constants::propagate(&mut body, contract);
+        dead_code::eliminate(&mut body);
+        contract.functions[i].body = body;
+    }
+}
@@ -1,21 +1,45 @@
 use folidity_diagnostics::Report;
-use folidity_parser::ast as parsed_ast;
+use folidity_parser::{
+    ast::{
+        self as parsed_ast,
+        Identifier,
+    },
+    Span,
+};
 
 use crate::{
     ast::{
         Assign,
+        Emit,
+        Expression,
         ForLoop,
+        FunctionVisibility,
         IfElse,
         Iterator,
+        Match,
+        MatchArm,
+        MemberAccess,
+        Param,
         Return,
+        StateBody,
         Statement,
         StatementBlock,
         TypeVariant,
+        UnaryExpression,
         Variable,
+        WhileLoop,
     },
+    bounds::resolve_bound_exprs,
     contract::ContractDefinition,
-    expression::expression,
-    global_symbol::GlobalSymbol,
+    expression::{
+        complex::resolve_init_args,
+        expression,
+    },
+    global_symbol::{
+        GlobalSymbol,
+        SymbolInfo,
+        SymbolKind,
+    },
     symtable::{
         Scope,
         ScopeContext,
@@ -67,13 +91,9 @@ pub fn statement(
                 }
             };
 
-            // todo: destructure fields.
             if var.names.len() != 1 {
-                contract.diagnostics.push(Report::semantic_error(
-                    stmt.loc().clone(),
-                    String::from("Destructuring is currently unsupported."),
-                ));
-                return Err(());
+                destructure(var, expr, ty, resolved, scope, contract)?;
+                return Ok(true);
             }
 
             let pos = scope.add(
@@ -97,18 +117,23 @@ pub fn statement(
             Ok(true)
         }
         parsed_ast::Statement::Assign(a) => {
-            let Some((v_i, _)) = scope.find_var_index(&a.name.name) else {
+            let (v_i, fields, ty) = assign_target(&a.target, scope, contract)?;
+            let mut sym = scope.find_symbol(&v_i).unwrap().clone();
+
+            if is_viewed_state(&sym.ident, scope, contract) {
                 contract.diagnostics.push(Report::semantic_error(
-                    a.name.loc.clone(),
-                    String::from("Cannot find the variable"),
+                    a.target.loc().clone(),
+                    format!(
+                        "View functions can not modify the state `{}`.",
+                        sym.ident.name
+                    ),
                 ));
                 return Err(());
-            };
-            let mut sym = scope.find_symbol(&v_i).unwrap().clone();
+            }
 
             if !sym.mutable {
                 contract.diagnostics.push(Report::semantic_error(
-                    a.name.loc.clone(),
+                    a.target.loc().clone(),
                     String::from(
                         "Variable is immutable. Annotate with `mut` keyword to allow mutation.",
                     ),
@@ -116,20 +141,30 @@ pub fn statement(
                 return Err(());
             }
 
-            let resolved_value = expression(
-                &a.value,
-                ExpectedType::Concrete(sym.ty.clone()),
-                scope,
-                contract,
-            )?;
+            if matches!(ty, TypeVariant::Mapping(_)) && !fields.is_empty() {
+                contract.diagnostics.push(Report::semantic_error(
+                    a.target.loc().clone(),
+                    String::from("Mappings can not be reassigned, use their functions to update the entries."),
+                ));
+                return Err(());
+            }
 
-            sym.value = Some(resolved_value.clone());
+            let value = assigned_value(a);
+            let resolved_value = expression(&value, ExpectedType::Concrete(ty), scope, contract)?;
+
+            // the value is only tracked when the variable is assigned as a whole.
+            if fields.is_empty() {
+                sym.value = Some(resolved_value.clone());
+            }
+            sym.mutated = true;
+            let name = sym.ident.clone();
             scope.vars.insert(v_i, sym);
 
             resolved.push(Statement::Assign(Assign {
                 loc: a.loc.clone(),
-                name: a.name.clone(),
+                name,
                 pos: v_i,
+                fields,
                 value: resolved_value,
             }));
             Ok(true)
@@ -147,7 +182,7 @@ pub fn statement(
                         b_stmt.loc().clone(),
                         String::from("Unreachable statement."),
                     ));
-                    return Err(());
+                    break;
                 }
                 let mut local_mut = false;
                 reachable =
@@ -185,9 +220,26 @@ pub fn statement(
             scope.pop();
 
             let mut other_stmts = Vec::new();
-            if let Some(else_block) = &branch.else_part {
+            if let Some((next, rest)) = branch.else_ifs.split_first() {
+                // `else if` chain is lowered into the nested branch
+                // spanning from the next `else if` to the end of the chain.
+                let nested = parsed_ast::IfElse {
+                    loc: next.loc.start..branch.loc.end,
+                    condition: next.condition.clone(),
+                    body: next.body.clone(),
+                    else_ifs: rest.to_vec(),
+                    else_part: branch.else_part.clone(),
+                };
                 reachable_block |= statement(
-                    else_block,
+                    &parsed_ast::Statement::IfElse(nested),
+                    &mut other_stmts,
+                    scope,
+                    &mut local_mut,
+                    contract,
+                )?;
+            } else if let Some(else_block) = &branch.else_part {
+                reachable_block |= statement(
+                    &parsed_ast::Statement::Block(*else_block.clone()),
                     &mut other_stmts,
                     scope,
                     &mut local_mut,
@@ -213,7 +265,6 @@ pub fn statement(
             scope.push(ScopeContext::Loop);
 
             let mut loop_stmts = Vec::new();
-            let mut reachable;
             statement(
                 &parsed_ast::Statement::Variable(for_loop.var.clone()),
                 &mut loop_stmts,
@@ -221,30 +272,61 @@ pub fn statement(
                 mutating,
                 contract,
             )?;
+            // the loop variable is updated by the incrementer.
+            if let Some(Statement::Variable(var)) = loop_stmts.first() {
+                scope.mark_mutated(&var.pos);
+            }
             let eval_cond = expression(
                 &for_loop.condition,
                 ExpectedType::Concrete(TypeVariant::Bool),
                 scope,
                 contract,
             )?;
+            let incrementer = match &for_loop.incrementer {
+                parsed_ast::Incrementer::Value(e) => e.clone(),
+                parsed_ast::Incrementer::Assign(a) => {
+                    let Some(Statement::Variable(var)) = loop_stmts.first() else {
+                        return Err(());
+                    };
+                    let updates_var = match &a.target {
+                        parsed_ast::Expression::Variable(ident) => {
+                            scope.find_var_index(&ident.name).map(|(i, _)| i) == Some(var.pos)
+                        }
+                        _ => false,
+                    };
+                    if !updates_var {
+                        contract.diagnostics.push(Report::semantic_error(
+                            a.target.loc().clone(),
+                            String::from(
+                                "Only the loop variable can be updated by the incrementer.",
+                            ),
+                        ));
+                        return Err(());
+                    }
+                    assigned_value(a)
+                }
+            };
             let eval_incr = expression(
-                &for_loop.incrementer,
+                &incrementer,
                 ExpectedType::Concrete(TypeVariant::Int),
                 scope,
                 contract,
             )?;
+            // the variables declared in the body are not visible to the invariant.
+            let invariant = for_loop
+                .invariant
+                .as_ref()
+                .map(|st| resolve_bound_exprs(st, scope, contract))
+                .transpose()?;
 
-            if for_loop.body.statements.is_empty() {
-                reachable = true;
-            } else {
-                reachable = statement(
-                    &parsed_ast::Statement::Block(*for_loop.body.clone()),
-                    &mut loop_stmts,
-                    scope,
-                    mutating,
-                    contract,
-                )?;
-            }
+            // the empty body is resolved too, so that the block is always present.
+            let mut reachable = statement(
+                &parsed_ast::Statement::Block(*for_loop.body.clone()),
+                &mut loop_stmts,
+                scope,
+                mutating,
+                contract,
+            )?;
 
             let Statement::Variable(var) = loop_stmts[0].clone() else {
                 unreachable!()
@@ -256,7 +338,7 @@ pub fn statement(
             if body
                 .statements
                 .iter()
-                .any(|s| matches!(&s, Statement::Skip(_)))
+                .any(|s| matches!(&s, Statement::Skip(_) | Statement::Break(_)))
             {
                 reachable = true;
             }
@@ -268,11 +350,51 @@ pub fn statement(
                 var,
                 condition: eval_cond,
                 incrementer: eval_incr,
+                invariant,
                 body: body.statements,
             }));
 
             Ok(reachable)
         }
+        parsed_ast::Statement::While(while_loop) => {
+            scope.push(ScopeContext::Loop);
+
+            let eval_cond = expression(
+                &while_loop.condition,
+                ExpectedType::Concrete(TypeVariant::Bool),
+                scope,
+                contract,
+            )?;
+            let invariant = while_loop
+                .invariant
+                .as_ref()
+                .map(|st| resolve_bound_exprs(st, scope, contract))
+                .transpose()?;
+
+            let mut loop_stmts = Vec::new();
+            statement(
+                &parsed_ast::Statement::Block(*while_loop.body.clone()),
+                &mut loop_stmts,
+                scope,
+                mutating,
+                contract,
+            )?;
+            let Some(Statement::Block(body)) = loop_stmts.pop() else {
+                unreachable!()
+            };
+
+            scope.pop();
+
+            resolved.push(Statement::While(WhileLoop {
+                loc: while_loop.loc.clone(),
+                condition: eval_cond,
+                invariant,
+                body: body.statements,
+            }));
+
+            // the condition may not hold before the first iteration.
+            Ok(true)
+        }
         parsed_ast::Statement::Iterator(it) => {
             scope.push(ScopeContext::Loop);
             let mut body = Vec::new();
@@ -286,6 +408,14 @@ pub fn statement(
                 return Err(());
             }
             let (TypeVariant::List(ty) | TypeVariant::Set(ty)) = list_expr.ty() else {
+                // mappings are stored in boxes which cannot be enumerated.
+                contract.diagnostics.push(Report::semantic_error(
+                    it.list.loc().clone(),
+                    format!(
+                        "Only lists and sets can be iterated over, found `{}`.",
+                        list_expr.ty().display(contract)
+                    ),
+                ));
                 return Err(());
             };
             for ident in &it.names {
@@ -299,6 +429,11 @@ pub fn statement(
                     contract,
                 );
             }
+            let invariant = it
+                .invariant
+                .as_ref()
+                .map(|st| resolve_bound_exprs(st, scope, contract))
+                .transpose()?;
 
             statement(
                 &parsed_ast::Statement::Block(*it.body.clone()),
@@ -314,11 +449,13 @@ pub fn statement(
                 loc: it.loc.clone(),
                 names: it.names.clone(),
                 list: list_expr,
+                invariant,
                 body,
             }));
 
             Ok(true)
         }
+        parsed_ast::Statement::Match(m) => match_(m, resolved, scope, mutating, contract),
         parsed_ast::Statement::Return(ret) => {
             let GlobalSymbol::Function(sym) = &scope.symbol else {
                 contract.diagnostics.push(Report::semantic_error(
@@ -364,6 +501,25 @@ pub fn statement(
             Ok(false)
         }
         parsed_ast::Statement::StateTransition(trans) => {
+            // tests can start from any state.
+            if let GlobalSymbol::Test(_) = &scope.symbol {
+                let states = (0..contract.states.len())
+                    .map(|i| {
+                        let loc = contract.states[i].loc.clone();
+                        TypeVariant::State(SymbolInfo::new(loc, i))
+                    })
+                    .collect();
+                let value = expression(trans, ExpectedType::Dynamic(states), scope, contract)?;
+                if !matches!(value.ty(), TypeVariant::State(_)) {
+                    contract.diagnostics.push(Report::semantic_error(
+                        trans.loc().clone(),
+                        String::from("The contract can only be moved to a state."),
+                    ));
+                    return Err(());
+                }
+                resolved.push(Statement::StateTransition(value));
+                return Ok(true);
+            }
             let GlobalSymbol::Function(sym) = &scope.symbol else {
                 contract.diagnostics.push(Report::semantic_error(
                     trans.loc().clone(),
@@ -373,6 +529,13 @@ pub fn statement(
             };
 
             let func = &contract.functions[sym.i];
+            if matches!(func.vis, FunctionVisibility::View(_)) {
+                contract.diagnostics.push(Report::semantic_error(
+                    trans.loc().clone(),
+                    String::from("View functions can not perform state transitions."),
+                ));
+                return Err(());
+            }
             let Some(bound) = &func.state_bound else {
                 contract.diagnostics.push(Report::semantic_error(
                     trans.loc().clone(),
@@ -399,11 +562,45 @@ pub fn statement(
 
             Ok(true)
         }
-        parsed_ast::Statement::Skip(loc) => {
+        parsed_ast::Statement::Emit(init) => {
+            if let GlobalSymbol::Function(sym) = &scope.symbol {
+                if matches!(contract.functions[sym.i].vis, FunctionVisibility::View(_)) {
+                    contract.diagnostics.push(Report::semantic_error(
+                        init.loc.clone(),
+                        String::from("View functions can not emit events."),
+                    ));
+                    return Err(());
+                }
+            }
+            let sym = contract
+                .find_global_symbol(&init.name, SymbolKind::Event)
+                .ok_or(())?;
+            let fields = contract.events[sym.i].fields.clone();
+            let (args, _) = resolve_init_args(
+                &init.args,
+                &fields,
+                &init.auto_object,
+                &init.loc,
+                scope,
+                contract,
+            )?;
+            resolved.push(Statement::Emit(Emit {
+                loc: init.loc.clone(),
+                sym,
+                args,
+            }));
+
+            Ok(true)
+        }
+        parsed_ast::Statement::Skip(loc) | parsed_ast::Statement::Break(loc) => {
+            let (resolved_stmt, keyword) = match stmt {
+                parsed_ast::Statement::Skip(_) => (Statement::Skip(loc.clone()), "skip"),
+                _ => (Statement::Break(loc.clone()), "break"),
+            };
             let mut i = scope.current;
             while i > 0 {
                 if matches!(scope.tables[i].context, ScopeContext::Loop) {
-                    resolved.push(Statement::Skip(loc.clone()));
+                    resolved.push(resolved_stmt);
                     return Ok(false);
                 }
                 i -= 1;
@@ -411,7 +608,7 @@ pub fn statement(
 
             contract.diagnostics.push(Report::semantic_error(
                 loc.clone(),
-                String::from("`skip` can only be used inside loops and iterators"),
+                format!("`{}` can only be used inside loops and iterators", keyword),
             ));
 
             Err(())
@@ -423,6 +620,511 @@ pub fn statement(
 
             Ok(true)
         }
-        parsed_ast::Statement::Error(_) => unimplemented!("Error statement can not be evaluated."),
+        // the syntax error has been reported by the parser.
+        parsed_ast::Statement::Error(_) => Err(()),
     }
 }
+
+/// Resolve the destructuring binding `let { a, b, rest } = obj;` into separate bindings.
+///
+/// Names matching the fields of the object are bound to the fields.
+/// The last name can bind the rest of the object typed as its parent model,
+/// e.g. the model of the state.
+/// Elements of the tuple are bound in order, `let (a, _) = pair;` skips the ones named
+/// `_`.
+///
+/// The object is bound to the hidden variable first unless it is a variable,
+/// so it is evaluated once.
+fn destructure(
+    var: &parsed_ast::Variable,
+    value: Option<Expression>,
+    ty: TypeVariant,
+    resolved: &mut Vec<Statement>,
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+) -> Result<(), ()> {
+    let Some(value) = value else {
+        contract.diagnostics.push(Report::semantic_error(
+            var.loc.clone(),
+            String::from("Destructured variable must be initialised."),
+        ));
+        return Err(());
+    };
+
+    let (fields, parent) = match &ty {
+        TypeVariant::Struct(s) => (contract.structs[s.i].fields.clone(), None),
+        TypeVariant::Model(s) => {
+            let model = &contract.models[s.i];
+            (model.fields(contract), model.parent.clone())
+        }
+        TypeVariant::State(s) => {
+            let state = &contract.states[s.i];
+            let parent = match &state.body {
+                Some(StateBody::Model(m)) => Some(m.clone()),
+                _ => None,
+            };
+            (state.fields(contract), parent)
+        }
+        TypeVariant::Tuple(tys) => {
+            if tys.len() != var.names.len() {
+                contract.diagnostics.push(Report::semantic_error(
+                    var.loc.clone(),
+                    format!(
+                        "Tuple has {} elements, but {} are bound.",
+                        tys.len(),
+                        var.names.len()
+                    ),
+                ));
+                return Err(());
+            }
+            (vec![], None)
+        }
+        _ => {
+            contract.diagnostics.push(Report::type_error(
+                value.loc().clone(),
+                format!(
+                    "Only structs, models, states and tuples can be destructured, but {} was provided.",
+                    ty.display(contract)
+                ),
+            ));
+            return Err(());
+        }
+    };
+
+    let obj = match value {
+        Expression::Variable(_) => value,
+        _ => {
+            let ident = Identifier {
+                loc: var.loc.clone(),
+                name: format!(
+                    "{{{}}}",
+                    var.names
+                        .iter()
+                        .map(|n| n.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+            let pos = scope.add(
+                &ident,
+                ty.clone(),
+                Some(value.clone()),
+                VariableKind::Local,
+                false,
+                scope.current,
+                contract,
+            );
+            resolved.push(Statement::Variable(Variable {
+                loc: var.loc.clone(),
+                pos,
+                names: vec![ident],
+                mutable: false,
+                ty: ty.clone(),
+                value: Some(value),
+            }));
+            Expression::Variable(UnaryExpression {
+                loc: var.loc.clone(),
+                element: pos,
+                ty: ty.clone(),
+            })
+        }
+    };
+
+    let mut error = false;
+    for (i, name) in var.names.iter().enumerate() {
+        let binding = if let TypeVariant::Tuple(tys) = &ty {
+            if name.name == "_" {
+                continue;
+            }
+            Some((
+                tys[i].clone(),
+                Expression::MemberAccess(MemberAccess {
+                    loc: name.loc.clone(),
+                    expr: Box::new(obj.clone()),
+                    member: (i, name.loc.clone()),
+                    ty: tys[i].clone(),
+                }),
+            ))
+        } else if let Some(pos) = fields.iter().position(|f| f.name.name == name.name) {
+            let field_ty = fields[pos].ty.ty.clone();
+            Some((
+                field_ty.clone(),
+                Expression::MemberAccess(MemberAccess {
+                    loc: name.loc.clone(),
+                    expr: Box::new(obj.clone()),
+                    member: (pos, name.loc.clone()),
+                    ty: field_ty,
+                }),
+            ))
+        } else if i == var.names.len() - 1 {
+            parent.as_ref().map(|p| {
+                let rest_ty = TypeVariant::Model(p.clone());
+                (rest_ty.clone(), with_ty(&obj, rest_ty, &name.loc))
+            })
+        } else {
+            None
+        };
+
+        let Some((binding_ty, binding_value)) = binding else {
+            contract.diagnostics.push(Report::semantic_error(
+                name.loc.clone(),
+                format!(
+                    "`{}` is not a member of {}.",
+                    name.name,
+                    ty.display(contract)
+                ),
+            ));
+            error = true;
+            continue;
+        };
+
+        let pos = scope.add(
+            name,
+            binding_ty.clone(),
+            Some(binding_value.clone()),
+            VariableKind::Local,
+            var.mutable,
+            scope.current,
+            contract,
+        );
+        resolved.push(Statement::Variable(Variable {
+            loc: name.loc.clone(),
+            pos,
+            names: vec![name.clone()],
+            mutable: var.mutable,
+            ty: binding_ty,
+            value: Some(binding_value),
+        }));
+    }
+
+    if error {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+/// View of the object as the value of its parent type.
+///
+/// Fields of the parent come first in the object, so the layout of the parent
+/// is the prefix of the object's one.
+fn with_ty(obj: &Expression, ty: TypeVariant, loc: &Span) -> Expression {
+    let mut obj = obj.clone();
+    if let Expression::Variable(u) = &mut obj {
+        u.ty = ty;
+        u.loc = loc.clone();
+    }
+    obj
+}
+
+/// Resolve the `match` statement over the variants of the enum.
+///
+/// The fields of the payloads are bound to the immutable variables of the arms.
+///
+/// # Errors
+/// - The matched expression is not an enum.
+/// - The variant does not exist, or the number of bindings mismatches its fields.
+/// - Some variants are not matched by any arm.
+fn match_(
+    m: &parsed_ast::Match,
+    resolved: &mut Vec<Statement>,
+    scope: &mut Scope,
+    mutating: &mut bool,
+    contract: &mut ContractDefinition,
+) -> Result<bool, ()> {
+    let expr = expression(&m.expr, ExpectedType::Dynamic(vec![]), scope, contract)?;
+    // variants in the order of their tags, with the types of their fields.
+    let (target, variants): (String, Vec<(String, Vec<TypeVariant>)>) = match expr.ty() {
+        TypeVariant::Enum(sym) => {
+            let decl = &contract.enums[sym.i];
+            let variants = decl
+                .variants
+                .iter()
+                .map(|(name, v)| {
+                    (
+                        name.clone(),
+                        v.fields.iter().map(|f| f.ty.ty.clone()).collect(),
+                    )
+                })
+                .collect();
+            (format!("enum `{}`", decl.name.name), variants)
+        }
+        TypeVariant::Option(ty) => {
+            let variants = vec![
+                (String::from("none"), vec![]),
+                (String::from("some"), vec![ty.as_ref().clone()]),
+            ];
+            (expr.ty().display(contract), variants)
+        }
+        _ => {
+            contract.diagnostics.push(Report::semantic_error(
+                m.expr.loc().clone(),
+                format!(
+                    "Only enums and options can be matched, found {}.",
+                    expr.ty().display(contract)
+                ),
+            ));
+            return Err(());
+        }
+    };
+
+    let mut matched = vec![false; variants.len()];
+    let mut wildcard = false;
+    let mut reachable = false;
+    let mut error = false;
+    let mut arms = vec![];
+    for arm in &m.arms {
+        let variant = if arm.variant.name == "_" {
+            if !arm.bindings.is_empty() {
+                contract.diagnostics.push(Report::semantic_error(
+                    arm.variant.loc.clone(),
+                    String::from("`_` matches any variant, it can not bind the fields."),
+                ));
+                error = true;
+                continue;
+            }
+            None
+        } else {
+            let Some(pos) = variants
+                .iter()
+                .position(|(name, _)| name == &arm.variant.name)
+            else {
+                contract.diagnostics.push(Report::semantic_error(
+                    arm.variant.loc.clone(),
+                    format!(
+                        "Variant `{}` does not exist in {}.",
+                        arm.variant.name, target
+                    ),
+                ));
+                error = true;
+                continue;
+            };
+            let fields = &variants[pos].1;
+            if fields.len() != arm.bindings.len() {
+                contract.diagnostics.push(Report::semantic_error(
+                    arm.variant.loc.clone(),
+                    format!(
+                        "Variant `{}` has {} fields, but {} are bound.",
+                        arm.variant.name,
+                        fields.len(),
+                        arm.bindings.len()
+                    ),
+                ));
+                error = true;
+                continue;
+            }
+            Some(pos)
+        };
+
+        let is_matched = match variant {
+            Some(pos) => std::mem::replace(&mut matched[pos], true),
+            None => matched.iter().all(|m| *m),
+        };
+        if wildcard || is_matched {
+            contract.diagnostics.push(Report::semantic_warning(
+                arm.variant.loc.clone(),
+                String::from("Arm is unreachable, its variants are matched by the previous arms."),
+            ));
+        }
+        wildcard |= variant.is_none();
+
+        scope.push(ScopeContext::Block);
+        let mut bindings = vec![];
+        if let Some(pos) = variant {
+            for (i, (ident, ty)) in arm.bindings.iter().zip(&variants[pos].1).enumerate() {
+                if ident.name == "_" {
+                    continue;
+                }
+                let var = scope.add(
+                    ident,
+                    ty.clone(),
+                    None,
+                    VariableKind::Destructor,
+                    false,
+                    scope.current,
+                    contract,
+                );
+                bindings.push((i, var));
+            }
+        }
+        let mut body = vec![];
+        let mut local_mut = false;
+        let arm_reachable = statement(
+            &parsed_ast::Statement::Block(*arm.body.clone()),
+            &mut body,
+            scope,
+            &mut local_mut,
+            contract,
+        );
+        scope.pop();
+        *mutating |= local_mut;
+
+        let Ok(arm_reachable) = arm_reachable else {
+            error = true;
+            continue;
+        };
+        reachable |= arm_reachable;
+        arms.push(MatchArm {
+            loc: arm.loc.clone(),
+            variant,
+            bindings,
+            body,
+        });
+    }
+
+    let missing: Vec<String> = variants
+        .iter()
+        .map(|(name, _)| name)
+        .zip(&matched)
+        .filter(|(_, m)| !**m)
+        .map(|(name, _)| format!("`{}`", name))
+        .collect();
+    if !wildcard && !missing.is_empty() {
+        let mut report = Report::semantic_error(
+            m.loc.clone(),
+            format!(
+                "Match is not exhaustive, the variants {} are not matched.",
+                missing.join(", ")
+            ),
+        );
+        report.note = String::from("Add the arms for the missing variants, or the `_` arm.");
+        contract.diagnostics.push(report);
+        return Err(());
+    }
+    if error {
+        return Err(());
+    }
+
+    resolved.push(Statement::Match(Match {
+        loc: m.loc.clone(),
+        expr,
+        arms,
+    }));
+    Ok(reachable)
+}
+
+/// Value assigned to the target, with the compound assignment desugared,
+/// e.g. `x += 2` into `x = x + 2`.
+fn assigned_value(a: &parsed_ast::Assign) -> parsed_ast::Expression {
+    let Some(op) = a.op else {
+        return a.value.clone();
+    };
+    let binary = parsed_ast::BinaryExpression::new(
+        a.loc.start,
+        a.loc.end,
+        Box::new(a.target.clone()),
+        Box::new(a.value.clone()),
+    );
+    match op {
+        parsed_ast::AssignOperator::Add => parsed_ast::Expression::Add(binary),
+        parsed_ast::AssignOperator::Subtract => parsed_ast::Expression::Subtract(binary),
+        parsed_ast::AssignOperator::Multiply => parsed_ast::Expression::Multiply(binary),
+        parsed_ast::AssignOperator::Divide => parsed_ast::Expression::Divide(binary),
+    }
+}
+
+/// Resolve the assigned variable, or the chain of its fields, e.g. `p.x.y`.
+///
+/// # Returns
+/// `(variable index, field indices, type of the target)`
+fn assign_target(
+    target: &parsed_ast::Expression,
+    scope: &Scope,
+    contract: &mut ContractDefinition,
+) -> Result<(usize, Vec<usize>, TypeVariant), ()> {
+    let mut members = vec![];
+    let mut root = target;
+    while let parsed_ast::Expression::MemberAccess(m) = root {
+        members.push(&m.member);
+        root = &m.expr;
+    }
+    let parsed_ast::Expression::Variable(ident) = root else {
+        contract.diagnostics.push(Report::semantic_error(
+            target.loc().clone(),
+            String::from("Only variables and their fields can be assigned."),
+        ));
+        return Err(());
+    };
+    let Some((v_i, _)) = scope.find_var_index(&ident.name) else {
+        contract.diagnostics.push(Report::semantic_error(
+            ident.loc.clone(),
+            String::from("Cannot find the variable"),
+        ));
+        return Err(());
+    };
+
+    let mut ty = scope.find_symbol(&v_i).unwrap().ty.clone();
+    let mut fields = vec![];
+    for member in members.into_iter().rev() {
+        let Some(params) = ty.fields(contract) else {
+            contract.diagnostics.push(Report::semantic_error(
+                member.loc.clone(),
+                format!("{} has no fields to assign.", ty.display(contract)),
+            ));
+            return Err(());
+        };
+        let Some(pos) = params.iter().position(|p| p.name.name == member.name) else {
+            contract.diagnostics.push(Report::semantic_error(
+                member.loc.clone(),
+                String::from("Member does not exist"),
+            ));
+            return Err(());
+        };
+        ty = params[pos].ty.ty.clone();
+        fields.push(pos);
+    }
+    Ok((v_i, fields, ty))
+}
+
+/// Check that the variables passed to the mutable parameters of the function are mutable.
+///
+/// The variables, including the ones whose fields are passed, are marked as mutated.
+pub(crate) fn mutable_arguments(
+    args: &[Expression],
+    params: &[Param],
+    scope: &mut Scope,
+    contract: &mut ContractDefinition,
+) -> Result<(), ()> {
+    let mut error = false;
+    for (arg, param) in args.iter().zip(params).filter(|(_, p)| p.is_mut) {
+        let mut root = arg;
+        while let Expression::MemberAccess(m) = root {
+            root = &m.expr;
+        }
+        let Expression::Variable(var) = root else {
+            continue;
+        };
+        let Some(sym) = scope.find_symbol(&var.element) else {
+            continue;
+        };
+        if !sym.mutable {
+            let mut report = Report::semantic_error(
+                arg.loc().clone(),
+                format!(
+                    "Variable `{}` is immutable, but is passed to the mutable parameter `{}`.",
+                    sym.ident.name, param.name.name
+                ),
+            );
+            report.note =
+                String::from("Annotate the variable with `mut` keyword to allow mutation.");
+            contract.diagnostics.push(report);
+            error = true;
+            continue;
+        }
+        scope.mark_mutated(&var.element);
+    }
+
+    if error {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+/// Is the variable the state bound to the view function of the scope?
+fn is_viewed_state(ident: &Identifier, scope: &Scope, contract: &ContractDefinition) -> bool {
+    let GlobalSymbol::Function(sym) = &scope.symbol else {
+        return false;
+    };
+    matches!(&contract.functions[sym.i].vis, FunctionVisibility::View(v) if &v.name == ident)
+}
@@ -26,6 +26,8 @@ pub struct VariableSym {
     pub usage: VariableKind,
     /// Can the variable be mutated.
     pub mutable: bool,
+    /// Has the variable been reassigned, or passed to a mutable parameter?
+    pub mutated: bool,
 }
 
 impl VariableSym {
@@ -45,6 +47,8 @@ pub enum VariableKind {
     ToState,
     Loop,
     Return,
+    /// Parameter of the lambda.
+    Lambda,
 }
 
 /// Context of the scope in the symtable.
@@ -59,6 +63,8 @@ pub enum ScopeContext {
     FunctionBody,
     Loop,
     Block,
+    /// Body of the lambda, it inherits the context of the enclosing scope.
+    Lambda,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -128,6 +134,7 @@ impl Scope {
                 usage,
                 used: false,
                 mutable,
+                mutated: false,
             },
         );
 
@@ -146,9 +153,15 @@ impl Scope {
         let mut table_i = self.current;
         let mut table = &self.tables[table_i];
 
+        // lambdas can access whatever is accessible from where they are declared.
+        let mut context_i = table_i;
+        while context_i > 0 && self.tables[context_i].context == ScopeContext::Lambda {
+            context_i -= 1;
+        }
+
         // we need to decide which variables we are allowed traverse depending on the context of
         // the current scope.
-        let whitelists = match &table.context {
+        let mut whitelists = match &self.tables[context_i].context {
             // if we are inside bound context, we can only traverse params, access attributes,
             // return param, and state bounds.
             ScopeContext::DeclarationBounds => {
@@ -171,7 +184,9 @@ impl Scope {
                     VariableKind::Destructor,
                 ]
             }
+            ScopeContext::Lambda => vec![],
         };
+        whitelists.push(VariableKind::Lambda);
 
         let mut v_i = table.names.get(name);
         while table_i > 0 && v_i.is_none() {
@@ -201,6 +216,20 @@ impl Scope {
         self.vars.get(index)
     }
 
+    /// Mark the variable with the given index as used.
+    pub fn mark_used(&mut self, index: &usize) {
+        if let Some(var) = self.vars.get_mut(index) {
+            var.used = true;
+        }
+    }
+
+    /// Mark the variable with the given index as mutated.
+    pub fn mark_mutated(&mut self, index: &usize) {
+        if let Some(var) = self.vars.get_mut(index) {
+            var.mutated = true;
+        }
+    }
+
     /// Pushes the scope context onto the stack.
     pub fn push(&mut self, context: ScopeContext) {
         if self.current == self.tables.len() - 1 {
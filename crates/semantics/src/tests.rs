@@ -1,9 +1,25 @@
 use crate::{
-    ast::TypeVariant,
+    ast::{
+        Expression,
+        Statement,
+        TypeVariant,
+    },
+    builtin::BuiltinFunction,
+    modules::Project,
+    optimizer::{
+        optimize,
+        OptLevel,
+    },
+    parse_and_check,
+    resolve_contract,
     symtable::VariableSym,
     ContractDefinition,
     Runner,
 };
+use folidity_diagnostics::{
+    Level,
+    Report,
+};
 use folidity_parser::parse;
 
 const DECL_SRC: &str = r#"
@@ -161,7 +177,15 @@ fn test_program() {
     let res = ContractDefinition::run(tree);
     assert!(res.is_ok(), "{:#?}", res.err().unwrap());
     let contract = res.unwrap();
-    assert_eq!(contract.diagnostics.len(), 0, "{:#?}", contract.diagnostics);
+    // the program is not meant to be deployed, only the lints are reported.
+    assert!(
+        contract
+            .diagnostics
+            .iter()
+            .all(|r| r.level == Level::Warning),
+        "{:#?}",
+        contract.diagnostics
+    );
     assert_eq!(contract.models.len(), 2);
     assert_eq!(contract.states.len(), 3);
     assert_eq!(contract.functions.len(), 5);
@@ -305,3 +329,2533 @@ fn test_err_program() {
         &errors.next().unwrap()
     );
 }
+
+const AUTO_OBJECT_SRC: &str = r#"
+struct Point {
+    x: int,
+    y: int,
+    label: string
+}
+
+struct Other {
+    label: bool
+}
+
+model ParentModel {
+    a: int
+}
+
+model MyModel: ParentModel {
+    b: int
+}
+
+state Counter(MyModel)
+
+@init
+@(any)
+fn () start() when () -> Counter {
+    let p = Point : { 1, 2, s"origin" };
+    let moved = Point : { 5 | ..p };
+    let copy = Point : { ..p };
+    let m = MyModel : { 1, 2 };
+    move Counter : { 10 | ..m };
+}
+
+fn () fail() {
+    let o = Other : { true };
+    let p = Point : { 1, 2 | ..o };
+}
+
+fn () fail_missing() {
+    let o = Other : { true };
+    let p = Point : { 1 | ..o };
+}
+"#;
+
+#[test]
+fn test_auto_object() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(AUTO_OBJECT_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let result = ContractDefinition::run(tree);
+    let Err(e) = result else {
+        panic!("The contract is expected to fail")
+    };
+    let errors: Vec<String> = e.diagnostics().iter().map(|r| r.message.clone()).collect();
+    assert_eq!(
+        errors,
+        vec![
+            "`label` has type bool in the auto-object, expected string.".to_string(),
+            "`y` is not initialised and is missing in the auto-object.".to_string(),
+            "`label` has type bool in the auto-object, expected string.".to_string(),
+        ]
+    );
+}
+
+const MAPPING_SRC: &str = r#"
+state VotingState {
+    votes: mapping<address -> int>,
+    total: int,
+}
+
+@init
+@(any)
+fn () init() when () -> VotingState {
+    move VotingState : { [], 0 };
+}
+
+@(any)
+fn () vote(voter: address, weight: int) when (VotingState s) -> VotingState {
+    let votes = s.votes :> add(voter, weight);
+    move VotingState : { votes, s.total + weight };
+}
+
+@(any)
+view(VotingState s) fn bool has_voted(voter: address) {
+    return s.votes :> contains(voter);
+}
+
+@(any)
+view(VotingState s) fn int fail(voter: address) {
+    let a: bool = get(s.votes, voter);
+    return 0;
+}
+
+@(any)
+view(VotingState s) fn int fail_ty(voter: address) {
+    return get(s.total, voter);
+}
+"#;
+
+#[test]
+fn test_mapping_builtins() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(MAPPING_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let errors: Vec<String> = def.diagnostics.iter().map(|r| r.message.clone()).collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Mismatched types: expected to resolve to bool, but expression can only resolve to int"
+                .to_string(),
+            "`get` is not supported for int.".to_string(),
+        ]
+    );
+
+    let vote = def
+        .functions
+        .iter()
+        .find(|f| f.name.name == "vote")
+        .unwrap();
+    let Some(Statement::Block(block)) = vote.body.first() else {
+        panic!("Expected function body");
+    };
+    let Statement::Variable(var) = &block.statements[0] else {
+        panic!("Expected variable declaration");
+    };
+    let Some(Expression::BuiltinCall(call)) = &var.value else {
+        panic!("Expected built-in call");
+    };
+    assert_eq!(call.func, BuiltinFunction::Add);
+    assert_eq!(call.args.len(), 3);
+    assert!(matches!(call.returns, TypeVariant::Mapping(_)));
+}
+
+const COLLECTION_SRC: &str = r#"
+fn bool is_positive(x: int) {
+    return x > 0;
+}
+
+fn bool is_even(x: int) {
+    return x / 2 * 2 == x;
+}
+
+fn list<bool> positive_parity(l: list<int>) {
+    return l :> filter(is_positive) :> map(is_even);
+}
+
+fn set<int> update(s: set<int>, x: int) {
+    let s2 = s :> add(x) :> remove(0);
+    let n = size(s2);
+    return s2;
+}
+
+fn bool check(l: list<int>) {
+    return contains(l, 5);
+}
+
+fn hex sender_hash() {
+    let sender: address = caller();
+    let block = current_block();
+    return hash(sender);
+}
+
+fn int fail_map(l: list<int>) {
+    let l2: list<int> = map(l, is_even);
+    return 0;
+}
+
+fn int fail_filter(l: list<int>) {
+    let l2 = filter(l, update);
+    return 0;
+}
+
+fn int fail_arity() {
+    let a = caller(1);
+    return 0;
+}
+
+fn int fail_size(l: list<int>) {
+    let s = size(l, 1);
+    return 0;
+}
+"#;
+
+#[test]
+fn test_collection_builtins() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(COLLECTION_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let errors: Vec<String> = def.diagnostics.iter().map(|r| r.message.clone()).collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Mismatched types: expected to resolve to list<int>, but expression can only resolve to list<bool>"
+                .to_string(),
+            "Function has invalid number of parameters.".to_string(),
+            "`caller` expects 0 arguments, but 1 were provided.".to_string(),
+            "`size` expects 1 arguments, but 2 were provided.".to_string(),
+        ]
+    );
+
+    let parity = def
+        .functions
+        .iter()
+        .find(|f| f.name.name == "positive_parity")
+        .unwrap();
+    let Some(Statement::Block(block)) = parity.body.first() else {
+        panic!("Expected function body");
+    };
+    let Statement::Return(ret) = &block.statements[0] else {
+        panic!("Expected return statement");
+    };
+    let Some(Expression::BuiltinCall(call)) = &ret.expr else {
+        panic!("Expected built-in call");
+    };
+    assert_eq!(call.func, BuiltinFunction::Map);
+    assert_eq!(call.returns, TypeVariant::List(Box::new(TypeVariant::Bool)));
+    let Expression::BuiltinCall(inner) = &call.args[0] else {
+        panic!("Expected built-in call");
+    };
+    assert_eq!(inner.func, BuiltinFunction::Filter);
+}
+
+const STRING_SRC: &str = r#"
+fn string greeting(name: string) {
+    let n = length(name);
+    let short = n < 8 && name :> starts_with(s"Al");
+    let found = name :> contains(s"ice");
+    return name :> substring(0, 3);
+}
+
+fn bool fail_contains(name: string) {
+    return name :> contains(1);
+}
+
+fn string fail_arity(name: string) {
+    return name :> substring(0);
+}
+"#;
+
+#[test]
+fn test_string_builtins() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(STRING_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let errors: Vec<String> = def.diagnostics.iter().map(|r| r.message.clone()).collect();
+    assert_eq!(errors.len(), 2, "{:#?}", errors);
+    assert!(errors[0].starts_with("Mismatched types"));
+    assert_eq!(
+        errors[1],
+        "`substring` expects 3 arguments, but 2 were provided."
+    );
+
+    let Some(Statement::Block(block)) = def.functions[0].body.first() else {
+        panic!("Expected function body");
+    };
+    let Statement::Variable(n) = &block.statements[0] else {
+        panic!("Expected variable declaration");
+    };
+    assert_eq!(n.ty, TypeVariant::Uint);
+    let Statement::Return(ret) = &block.statements[3] else {
+        panic!("Expected return statement");
+    };
+    let Some(Expression::BuiltinCall(call)) = &ret.expr else {
+        panic!("Expected built-in call");
+    };
+    assert_eq!(call.func, BuiltinFunction::Substring);
+    assert_eq!(call.returns, TypeVariant::String);
+}
+
+const ACCESS_SRC: &str = r#"
+struct Inner {
+    value: int
+}
+
+struct Outer {
+    inner: Inner,
+    flag: bool
+}
+
+fn Outer make() {
+    let i = Inner : { 1 };
+    return Outer : { i, true };
+}
+
+fn int chained(o: Outer) {
+    return o.inner.value;
+}
+
+fn int call_access() {
+    return make().inner.value;
+}
+
+fn int fail_member(o: Outer) {
+    return o.flag.value;
+}
+"#;
+
+#[test]
+fn test_chained_member_access() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(ACCESS_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let errors: Vec<String> = def.diagnostics.iter().map(|r| r.message.clone()).collect();
+    assert_eq!(
+        errors,
+        vec!["This type does not support member access.".to_string()]
+    );
+
+    for f in &def.functions[1..3] {
+        let Some(Statement::Block(block)) = f.body.first() else {
+            panic!("Expected function body");
+        };
+        let Statement::Return(ret) = &block.statements[0] else {
+            panic!("Expected return statement");
+        };
+        let Some(Expression::MemberAccess(outer)) = &ret.expr else {
+            panic!("Expected member access");
+        };
+        assert_eq!(outer.ty, TypeVariant::Int);
+        let Expression::MemberAccess(inner) = outer.expr.as_ref() else {
+            panic!("Expected member access");
+        };
+        assert_eq!(inner.member.0, 0);
+        assert!(matches!(inner.ty, TypeVariant::Struct(_)));
+    }
+}
+
+const DESTRUCTURE_SRC: &str = r#"
+model ParentModel {
+    a: int
+}
+
+model MyModel: ParentModel {
+    c: int,
+    b: string
+}
+
+state MyState(MyModel)
+
+struct Pair {
+    x: int,
+    y: int
+}
+
+fn Pair make() {
+    return Pair : { 1, 2 };
+}
+
+fn () touch() when (MyState s) -> MyState {
+    let { a, rest } = s;
+    move MyState : { a + 1 | ..rest };
+}
+
+fn int sum() when (MyState m) -> () {
+    let { c, inner } = m;
+    let { a, parent } = inner;
+    let { x, y } = make();
+    return a + c + x + y;
+}
+
+fn int fail_member(p: Pair) {
+    let { z, y } = p;
+    return y;
+}
+"#;
+
+#[test]
+fn test_destructuring() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(DESTRUCTURE_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let errors: Vec<String> = def.diagnostics.iter().map(|r| r.message.clone()).collect();
+    assert_eq!(
+        errors,
+        vec!["`z` is not a member of struct Pair.".to_string()]
+    );
+
+    let body = |i: usize| -> Vec<Statement> {
+        let Some(Statement::Block(block)) = def.functions[i].body.first() else {
+            panic!("Expected function body");
+        };
+        block.statements.clone()
+    };
+    let tys = |stmts: &[Statement]| -> Vec<String> {
+        stmts
+            .iter()
+            .filter_map(|s| {
+                match s {
+                    Statement::Variable(v) => Some(v.ty.display(&def)),
+                    _ => None,
+                }
+            })
+            .collect()
+    };
+
+    let touch = body(1);
+    assert_eq!(tys(&touch), vec!["int", "model MyModel"]);
+    let Statement::Variable(a) = &touch[0] else {
+        panic!("Expected variable declaration");
+    };
+    assert!(matches!(&a.value, Some(Expression::MemberAccess(m)) if m.member.0 == 0));
+
+    // the call is evaluated once into the hidden variable.
+    let sum = body(2);
+    assert_eq!(
+        tys(&sum),
+        vec![
+            "int",
+            "model MyModel",
+            "int",
+            "model ParentModel",
+            "struct Pair",
+            "int",
+            "int"
+        ]
+    );
+    let Statement::Variable(hidden) = &sum[4] else {
+        panic!("Expected variable declaration");
+    };
+    assert_eq!(hidden.names[0].name, "{x, y}");
+    assert!(matches!(&hidden.value, Some(Expression::FunctionCall(_))));
+}
+
+const SIZED_SRC: &str = r#"
+fn u8 narrow(x: u64) {
+    return cast(x);
+}
+
+fn u64 widen(x: u8) {
+    return x;
+}
+
+fn u8 folded() {
+    return 200 + 55;
+}
+
+fn u256 big() {
+    return cast(5);
+}
+
+fn u8 fail_overflow() {
+    return 200 + 56;
+}
+
+fn u8 fail_literal() {
+    return 300;
+}
+
+fn u8 fail_implicit(x: u64) {
+    return x;
+}
+"#;
+
+#[test]
+fn test_sized_integers() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(SIZED_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let errors: Vec<String> = def.diagnostics.iter().map(|r| r.message.clone()).collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Value 256 is out of range of u8.".to_string(),
+            "Value 300 is out of range of u8.".to_string(),
+            "Mismatched types: expected to resolve to u8, but expression can only resolve to u64"
+                .to_string(),
+        ]
+    );
+
+    let returned = |i: usize| -> Expression {
+        let Some(Statement::Block(block)) = def.functions[i].body.first() else {
+            panic!("Expected function body");
+        };
+        let Some(Statement::Return(r)) = block.statements.first() else {
+            panic!("Expected return statement");
+        };
+        r.expr.clone().expect("returns value")
+    };
+
+    let Expression::BuiltinCall(narrow) = returned(0) else {
+        panic!("Expected cast");
+    };
+    assert_eq!(narrow.func, BuiltinFunction::Cast);
+    assert_eq!(narrow.returns, TypeVariant::U8);
+    assert_eq!(narrow.args[0].ty(), &TypeVariant::U64);
+
+    // widening is implicit.
+    let Expression::BuiltinCall(widen) = returned(1) else {
+        panic!("Expected cast");
+    };
+    assert_eq!(widen.returns, TypeVariant::U64);
+    assert_eq!(widen.args[0].ty(), &TypeVariant::U8);
+
+    assert!(
+        matches!(returned(2), Expression::UInt(u) if u.element == 255u32.into() && u.ty == TypeVariant::U8)
+    );
+    assert!(matches!(returned(3), Expression::UInt(u) if u.ty == TypeVariant::U256));
+}
+
+const OR_SRC: &str = r#"
+fn int factorial(value: int) {
+    return value * (value - 1) :> or(1);
+}
+
+fn u8 double(a: u8) {
+    return a * 2 :> or(255);
+}
+
+fn bool fail_bool(a: bool) {
+    return a :> or(true);
+}
+"#;
+
+#[test]
+fn test_or_fallback() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(OR_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let errors: Vec<String> = def.diagnostics.iter().map(|r| r.message.clone()).collect();
+    assert_eq!(errors, vec!["`or` is not supported for bool.".to_string()]);
+
+    let returned = |i: usize| -> Expression {
+        let Some(Statement::Block(block)) = def.functions[i].body.first() else {
+            panic!("Expected function body");
+        };
+        let Some(Statement::Return(r)) = block.statements.first() else {
+            panic!("Expected return statement");
+        };
+        r.expr.clone().expect("returns value")
+    };
+
+    let Expression::BuiltinCall(call) = returned(0) else {
+        panic!("Expected `or` call");
+    };
+    assert_eq!(call.func, BuiltinFunction::Or);
+    assert_eq!(call.returns, TypeVariant::Int);
+    assert!(matches!(&call.args[0], Expression::Multiply(_)));
+    assert!(matches!(&call.args[1], Expression::Int(_)));
+
+    // the value is resolved to the type of the call.
+    let Expression::BuiltinCall(call) = returned(1) else {
+        panic!("Expected `or` call");
+    };
+    assert_eq!(call.returns, TypeVariant::U8);
+    assert_eq!(call.args[0].ty(), &TypeVariant::U8);
+}
+
+const WARNING_SRC: &str = r#"
+@(any)
+fn int unreachable() {
+    return 1;
+    let a = 2;
+}
+
+fn int unreachable_err() {
+    return a;
+}
+"#;
+
+#[test]
+fn test_warnings_are_not_fatal() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(WARNING_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    assert_eq!(def.diagnostics.len(), 3, "{:#?}", def.diagnostics);
+    assert_eq!(def.diagnostics[0].level, Level::Warning);
+
+    let err = ContractDefinition::run(tree).unwrap_err();
+    assert_eq!(err.max_level(), Some(Level::Error));
+
+    let valid = parse(&WARNING_SRC[..WARNING_SRC.find("fn int unreachable_err").unwrap()]).unwrap();
+    let def = ContractDefinition::run(&valid).expect("warnings should not fail the stage");
+    assert_eq!(def.diagnostics.len(), 1);
+    assert_eq!(def.diagnostics[0].message, "Unreachable statement.");
+}
+
+const LAMBDA_SRC: &str = r#"
+fn list<bool> scale(l: list<int>, k: int) {
+    return l :> filter(|x| x > k) :> map(|x| x * k == 0);
+}
+
+fn int fail_standalone() {
+    let f = |x| x + 1;
+    return 0;
+}
+
+fn int fail_params(l: list<int>) {
+    let l2 = map(l, |a, b| a + b);
+    return 0;
+}
+
+fn int fail_predicate(l: list<int>) {
+    let l2 = filter(l, |x| x + 1);
+    return 0;
+}
+"#;
+
+#[test]
+fn test_lambda() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(LAMBDA_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let errors: Vec<String> = def.diagnostics.iter().map(|r| r.message.clone()).collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Type of the lambda can not be inferred, it can only be passed as a function."
+                .to_string(),
+            "Lambda is expected to have 1 parameters, but 2 were declared.".to_string(),
+            "Mismatched types: expected to resolve to bool, but expression can only resolve to int, uint, u8, u64, i64, u256, float, string"
+                .to_string(),
+        ]
+    );
+
+    let scale = &def.functions[0];
+    let Some(Statement::Block(block)) = scale.body.first() else {
+        panic!("Expected function body");
+    };
+    let Statement::Return(ret) = &block.statements[0] else {
+        panic!("Expected return statement");
+    };
+    let Some(Expression::BuiltinCall(map)) = &ret.expr else {
+        panic!("Expected built-in call");
+    };
+    assert_eq!(map.returns, TypeVariant::List(Box::new(TypeVariant::Bool)));
+    let Expression::Lambda(lambda) = &map.args[1] else {
+        panic!("Expected lambda");
+    };
+    let TypeVariant::Function(f_ty) = &lambda.ty else {
+        panic!("Expected function type");
+    };
+    assert_eq!(f_ty.params, vec![TypeVariant::Int]);
+    assert_eq!(f_ty.returns.as_ref(), &TypeVariant::Bool);
+
+    // `k` is captured, `x` is the parameter.
+    let (k, _) = scale.scope.find_var_index("k").unwrap();
+    assert_eq!(lambda.captures, vec![k]);
+    assert_eq!(lambda.params.len(), 1);
+    assert_ne!(lambda.params[0], k);
+}
+
+const FOLD_SRC: &str = r#"
+fn int add(acc: int, x: int) {
+    return acc + x;
+}
+
+fn int total(l: list<int>) {
+    let a = l :> fold(0, add);
+    let b = l :> fold(a, |acc, x| acc * x);
+    return a + b + sum(l);
+}
+
+fn bool any_true(l: list<bool>) {
+    return l :> fold(false, |acc, x| acc || x);
+}
+
+fn int fail_sum(l: list<bool>) {
+    let s = sum(l);
+    return 0;
+}
+
+fn int fail_fold(l: list<int>) {
+    let s = fold(l, true, add);
+    return 0;
+}
+"#;
+
+#[test]
+fn test_fold_sum() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(FOLD_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let errors: Vec<String> = def.diagnostics.iter().map(|r| r.message.clone()).collect();
+    assert_eq!(
+        errors,
+        vec![
+            "`sum` is not supported for list<bool>.".to_string(),
+            "Function's parameter types mismatched the expected ones.".to_string(),
+            "Function's return type mismatched.".to_string(),
+        ]
+    );
+
+    let any_true = def
+        .functions
+        .iter()
+        .find(|f| f.name.name == "any_true")
+        .unwrap();
+    let Some(Statement::Block(block)) = any_true.body.first() else {
+        panic!("Expected function body");
+    };
+    let Statement::Return(ret) = &block.statements[0] else {
+        panic!("Expected return statement");
+    };
+    let Some(Expression::BuiltinCall(call)) = &ret.expr else {
+        panic!("Expected built-in call");
+    };
+    assert_eq!(call.func, BuiltinFunction::Fold);
+    assert_eq!(call.returns, TypeVariant::Bool);
+    let Expression::Lambda(lambda) = &call.args[2] else {
+        panic!("Expected lambda");
+    };
+    let TypeVariant::Function(f_ty) = &lambda.ty else {
+        panic!("Expected function type");
+    };
+    assert_eq!(f_ty.params, vec![TypeVariant::Bool, TypeVariant::Bool]);
+}
+
+#[test]
+fn test_imports() {
+    let dir = std::env::temp_dir().join(format!("folidity_imports_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    std::fs::write(
+        dir.join("lib/model.fol"),
+        "import \"../contract.fol\"\n\nmodel Counter {\n    value: int,\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("contract.fol"),
+        "import \"lib/model.fol\"\nimport \"missing.fol\"\n\nstate CounterState(Counter)\n",
+    )
+    .unwrap();
+
+    let project = Project::load(&dir.join("contract.fol")).unwrap();
+    assert_eq!(project.modules.len(), 2);
+    let errors: Vec<String> = project
+        .diagnostics
+        .iter()
+        .map(|r| r.message.clone())
+        .collect();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("Cannot import `missing.fol`"));
+    let (module, loc) = project.locate(&project.diagnostics[0].loc).unwrap();
+    assert_eq!(module.path, dir.join("contract.fol"));
+    assert_eq!(&module.contents[loc], "import \"missing.fol\"");
+    let report = project.locate_report(&project.diagnostics[0]);
+    assert_eq!(
+        report.file,
+        Some(dir.join("contract.fol").to_string_lossy().to_string())
+    );
+    assert_eq!(
+        &module.contents[report.loc.clone()],
+        "import \"missing.fol\""
+    );
+    assert_eq!(project.locate_report(&report), report);
+
+    std::fs::write(
+        dir.join("contract.fol"),
+        "import \"lib/model.fol\"\n\nstate CounterState(Counter)\n",
+    )
+    .unwrap();
+    let project = Project::load(&dir.join("contract.fol")).unwrap();
+    let tree = project.parse().unwrap();
+    let def = resolve_contract(&tree);
+    assert!(def.diagnostics.is_empty(), "{:#?}", def.diagnostics);
+    assert_eq!(def.models[0].name.name, "Counter");
+    assert_eq!(def.states[0].name.name, "CounterState");
+    let (module, _) = project.locate(&def.models[0].loc).unwrap();
+    assert_eq!(module.path, dir.join("lib/model.fol"));
+    let report = project.locate_report(&Report::semantic_error(
+        def.models[0].loc.clone(),
+        String::new(),
+    ));
+    assert_eq!(
+        report.file,
+        Some(dir.join("lib/model.fol").to_string_lossy().to_string())
+    );
+    assert!(module.contents[report.loc].starts_with("model Counter"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+const OPT_SRC: &str = r#"
+fn int compute() {
+    let a = 2;
+    let b = a * 3;
+    if b > 5 {
+        return b + 1;
+    } else {
+        return 0;
+    }
+}
+
+fn int counter() {
+    let mut c = 1;
+    if false {
+        c = 10;
+    }
+    c = c + 1;
+    return c;
+}
+"#;
+
+#[test]
+fn test_optimizer() {
+    let result = parse(OPT_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let mut def = ContractDefinition::run(tree).expect("valid contract");
+    let bodies = |def: &ContractDefinition| -> Vec<Vec<Statement>> {
+        def.functions.iter().map(|f| f.body.clone()).collect()
+    };
+    let unoptimized = bodies(&def);
+    let lints = def.diagnostics.len();
+    optimize(&mut def, OptLevel::O0);
+    assert_eq!(bodies(&def), unoptimized);
+
+    optimize(&mut def, OptLevel::O1);
+    assert_eq!(def.diagnostics.len(), lints, "{:#?}", def.diagnostics);
+
+    let block = |i: usize| -> Vec<Statement> {
+        let [Statement::Block(body)] = def.functions[i].body.as_slice() else {
+            panic!("Expected function body");
+        };
+        body.statements.clone()
+    };
+
+    let compute = block(0);
+    assert_eq!(compute.len(), 3);
+    let Statement::Variable(b) = &compute[1] else {
+        panic!("Expected variable declaration");
+    };
+    assert!(matches!(&b.value, Some(Expression::Int(v)) if v.element == 6.into()));
+    let Statement::Block(branch) = &compute[2] else {
+        panic!("Expected the taken branch");
+    };
+    let [Statement::Return(ret)] = branch.statements.as_slice() else {
+        panic!("Expected return statement");
+    };
+    assert!(matches!(&ret.expr, Some(Expression::Int(v)) if v.element == 7.into()));
+
+    let counter = block(1);
+    assert_eq!(counter.len(), 3);
+    let Statement::Return(ret) = &counter[2] else {
+        panic!("Expected return statement");
+    };
+    assert!(matches!(&ret.expr, Some(Expression::Variable(_))));
+}
+
+const LABELLED_SRC: &str = r#"
+model Auction {
+    start_block: int,
+    end_block: int,
+} st [
+    "positive_start": start_block > 0,
+    end_block > start_block,
+]
+
+model Duplicate {
+    a: int,
+} st [
+    "positive": a > 0,
+    "positive": a < 10,
+]
+
+fn bool labelled(a: int) {
+    return ["positive": a > 0] == [true];
+}
+"#;
+
+#[test]
+fn test_labelled_constraints() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(LABELLED_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let contract = resolve_contract(tree);
+    let messages: Vec<&str> = contract
+        .diagnostics
+        .iter()
+        .filter(|e| e.level == Level::Error)
+        .map(|e| e.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Constraint label `positive` is already used.",
+            "Labels can only be given to the constraints of `st` blocks."
+        ]
+    );
+
+    let bounds = contract.models[0]
+        .bounds
+        .as_ref()
+        .expect("bounds are resolved");
+    assert_eq!(bounds.exprs.len(), 2);
+    assert_eq!(
+        bounds.labels,
+        vec![Some(String::from("positive_start")), None]
+    );
+    assert!(contract.models[1].bounds.is_none());
+}
+
+const LINTS_SRC: &str = r#"
+state Idle
+state Running {
+    ticks: int,
+}
+
+@init
+@(any)
+fn () start(unused: int, _ignored: int) when () -> Running {
+    let ticks = helper(1);
+    let spare = 2;
+    move Running : { ticks };
+}
+
+fn int helper(a: int) {
+    return a;
+}
+
+fn int orphan() {
+    return 1;
+}
+"#;
+
+#[test]
+fn test_lints() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(LINTS_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = ContractDefinition::run(tree).expect("lints should not fail the stage");
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Parameter `unused` is never used.",
+            "Variable `spare` is never used.",
+            "State `Idle` is unreachable, no function transitions to it.",
+            "Function `orphan` can never be called, it has no access attributes and is not called by the accessible functions.",
+        ]
+    );
+    assert!(def.diagnostics.iter().all(|r| r.level == Level::Warning));
+}
+
+const VIEW_SRC: &str = r#"
+state CounterState {
+    counter: int,
+}
+
+@(any)
+view(CounterState s) fn int reset() {
+    s = CounterState : { 0 };
+    return 0;
+}
+
+@(any)
+view(CounterState s) fn int bump() {
+    move CounterState : { s.counter + 1 };
+    return 0;
+}
+
+@(any)
+view(CounterState s) fn int transition() when (CounterState s) -> CounterState {
+    return 0;
+}
+"#;
+
+#[test]
+fn test_view_enforcement() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(VIEW_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let errors: Vec<&str> = def
+        .diagnostics
+        .iter()
+        .filter(|r| r.level == Level::Error)
+        .map(|r| r.message.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "View functions can not transition the contract to other states.",
+            "View functions can not modify the state `s`.",
+            "View functions can not perform state transitions.",
+        ]
+    );
+}
+
+const INVARIANT_SRC: &str = r#"
+@(any)
+fn int sum(n: int) {
+    let mut total = 0;
+    for (let mut i = 0; i < n; i + 1) invariant [i <= n, "positive": total >= 0] {
+        total = total + i;
+    }
+    return total;
+}
+
+@(any)
+fn () scoped(l: list<int>) {
+    for (item in l) invariant item > last {
+        let last = item;
+    }
+}
+"#;
+
+#[test]
+fn test_loop_invariants() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(INVARIANT_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let Some(Statement::Block(body)) = def.functions[0].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::ForLoop(l)) = body.statements.get(1) else {
+        panic!("Expected the loop");
+    };
+    let invariant = l.invariant.as_ref().expect("invariant is resolved");
+    assert_eq!(invariant.exprs.len(), 2);
+    assert_eq!(invariant.labels, vec![None, Some("positive".to_string())]);
+
+    // variables of the body are not visible to the invariant.
+    let errors: Vec<&str> = def
+        .diagnostics
+        .iter()
+        .filter(|r| r.level == Level::Error)
+        .map(|r| r.message.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Not declared.",
+            "`last`: Variable is not declared or inaccessible."
+        ]
+    );
+}
+
+const LOOP_CONTROL_SRC: &str = r#"
+fn int first_over(limit: int) {
+    let mut i = 0;
+    while true {
+        i = i + 1;
+        if i * i <= limit {
+            continue;
+        }
+        break;
+    }
+    return i;
+}
+
+fn () outside() {
+    break;
+}
+"#;
+
+#[test]
+fn test_loop_control() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(LOOP_CONTROL_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let Some(Statement::Block(body)) = def.functions[0].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::While(l)) = body.statements.get(1) else {
+        panic!("Expected the loop");
+    };
+    assert_eq!(l.condition.ty(), &TypeVariant::Bool);
+    assert!(matches!(l.body.last(), Some(Statement::Break(_))));
+
+    let errors: Vec<&str> = def
+        .diagnostics
+        .iter()
+        .filter(|r| r.level == Level::Error)
+        .map(|r| r.message.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        vec!["`break` can only be used inside loops and iterators"]
+    );
+}
+
+const MUTABILITY_SRC: &str = r#"
+struct Inner {
+    a: int,
+}
+
+struct Outer {
+    b: int,
+    inner: Inner,
+}
+
+fn () update(mut o: Outer) {
+    o.inner.a = 5;
+}
+
+@(any)
+fn () reassign() {
+    let fixed = 1;
+    fixed = 2;
+}
+
+@(any)
+fn () pass() {
+    let fixed = Outer : { 1, Inner : { 2 } };
+    update(fixed);
+}
+"#;
+
+const UNNEEDED_MUT_SRC: &str = r#"
+struct Outer {
+    b: int,
+}
+
+fn int update(mut o: Outer) {
+    o.b = 5;
+    return o.b;
+}
+
+@(any)
+fn int caller(mut count: int) {
+    let mut spare = count + 1;
+    let mut passed = Outer : { spare };
+    return update(passed) + spare;
+}
+"#;
+
+#[test]
+fn test_mutability() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(MUTABILITY_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let Some(Statement::Block(body)) = def.functions[0].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Assign(a)) = body.statements.first() else {
+        panic!("Expected the assignment");
+    };
+    assert_eq!(a.name.name, "o");
+    assert_eq!(a.fields, vec![1, 0]);
+
+    let errors: Vec<&str> = def
+        .diagnostics
+        .iter()
+        .filter(|r| r.level == Level::Error)
+        .map(|r| r.message.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Variable is immutable. Annotate with `mut` keyword to allow mutation.",
+            "Variable `fixed` is immutable, but is passed to the mutable parameter `o`.",
+        ]
+    );
+
+    let result = parse(UNNEEDED_MUT_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = ContractDefinition::run(tree).expect("lints should not fail the stage");
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Parameter `count` is declared mutable, but is never mutated.",
+            "Variable `spare` is declared mutable, but is never mutated.",
+        ]
+    );
+}
+
+const MATCH_SRC: &str = r#"
+enum Shape {
+    Circle(radius: int),
+    Rect(w: int, h: int),
+    Empty,
+}
+
+fn int area(s: Shape) {
+    match s {
+        Circle(r) => {
+            return r * r;
+        }
+        Rect(w, h) => {
+            return w * h;
+        }
+        _ => {
+            return 0;
+        }
+    }
+}
+
+fn Shape circle() {
+    return Shape.Circle(1);
+}
+
+fn Shape empty() {
+    return Shape.Empty;
+}
+"#;
+
+const INVALID_MATCH_SRC: &str = r#"
+enum Shape {
+    Circle(radius: int),
+    Rect(w: int, h: int),
+    Empty,
+}
+
+fn int missing(s: Shape) {
+    match s {
+        Circle(r) => {
+            return r;
+        }
+    }
+    return 0;
+}
+
+fn int fields(s: Shape) {
+    match s {
+        Rect(w) => {
+            return w;
+        }
+        _ => {
+            return 0;
+        }
+    }
+}
+
+fn int unreachable(s: Shape) {
+    match s {
+        _ => {
+            return 0;
+        }
+        Empty => {
+            return 1;
+        }
+    }
+}
+
+fn Shape payload() {
+    return Shape.Circle;
+}
+"#;
+
+#[test]
+fn test_match() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(MATCH_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let res = ContractDefinition::run(tree);
+    let Ok(def) = res else {
+        panic!("{:#?}", res.err().unwrap())
+    };
+    assert_eq!(def.enums[0].variants["Rect"].fields.len(), 2);
+
+    let Some(Statement::Block(body)) = def.functions[0].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Match(m)) = body.statements.first() else {
+        panic!("Expected the match statement");
+    };
+    assert_eq!(m.arms.len(), 3);
+    assert_eq!(m.arms[0].variant, Some(0));
+    assert_eq!(m.arms[1].bindings.len(), 2);
+    assert_eq!(m.arms[2].variant, None);
+
+    let return_expr = |i: usize| {
+        let Some(Statement::Block(body)) = def.functions[i].body.first() else {
+            panic!("Expected the function body");
+        };
+        let Some(Statement::Return(r)) = body.statements.first() else {
+            panic!("Expected the return statement");
+        };
+        r.expr.clone().expect("returns a value")
+    };
+    let Expression::VariantInit(v) = return_expr(1) else {
+        panic!("Expected the variant initialisation");
+    };
+    assert_eq!(v.variant, 0);
+    assert_eq!(v.args.len(), 1);
+    let Expression::Enum(e) = return_expr(2) else {
+        panic!("Expected the enum variant");
+    };
+    assert_eq!(e.element, 2);
+
+    let result = parse(INVALID_MATCH_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Match is not exhaustive, the variants `Rect`, `Empty` are not matched.",
+            "Variant `Rect` has 2 fields, but 1 are bound.",
+            "Arm is unreachable, its variants are matched by the previous arms.",
+            "Variant `Circle` carries a payload, it must be initialised with its fields.",
+        ]
+    );
+}
+
+const OPTION_SRC: &str = r#"
+fn option<int> find(flag: bool) {
+    if flag {
+        return some(1);
+    }
+    return none;
+}
+
+fn int unwrap(value: option<int>) {
+    match value {
+        some(v) => {
+            return v;
+        }
+        none => {
+            return 0;
+        }
+    }
+}
+
+fn int fallback(value: option<int>) {
+    return value :> or(0);
+}
+"#;
+
+const INVALID_OPTION_SRC: &str = r#"
+fn int unhandled(value: option<int>) {
+    return value;
+}
+
+fn () untyped() {
+    let missing = none;
+}
+
+fn int partial(value: option<int>) {
+    match value {
+        some(v) => {
+            return v;
+        }
+    }
+    return 0;
+}
+"#;
+
+#[test]
+fn test_option() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(OPTION_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let res = ContractDefinition::run(tree);
+    let Ok(def) = res else {
+        panic!("{:#?}", res.err().unwrap())
+    };
+    assert_eq!(
+        def.functions[0].return_ty.ty(),
+        &TypeVariant::Option(Box::new(TypeVariant::Int))
+    );
+
+    let Some(Statement::Block(body)) = def.functions[1].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Match(m)) = body.statements.first() else {
+        panic!("Expected the match statement");
+    };
+    let variants: Vec<Option<usize>> = m.arms.iter().map(|a| a.variant).collect();
+    assert_eq!(variants, vec![Some(1), Some(0)]);
+
+    let Some(Statement::Block(body)) = def.functions[2].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Return(r)) = body.statements.first() else {
+        panic!("Expected the return statement");
+    };
+    let Some(Expression::BuiltinCall(call)) = &r.expr else {
+        panic!("Expected `or` call");
+    };
+    assert_eq!(call.func, BuiltinFunction::Or);
+    assert_eq!(call.returns, TypeVariant::Int);
+    assert_eq!(
+        call.args[0].ty(),
+        &TypeVariant::Option(Box::new(TypeVariant::Int))
+    );
+
+    let result = parse(INVALID_OPTION_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Mismatched types: expected to resolve to int, but expression can only resolve to option<int>",
+            "Cannot derive type of `none` without the type annotation.",
+            "Match is not exhaustive, the variants `none` are not matched.",
+        ]
+    );
+}
+
+const TUPLE_SRC: &str = r#"
+fn (int, string) pair() {
+    return (1, s"one");
+}
+
+fn int first() {
+    let (a, _) = pair();
+    return a;
+}
+"#;
+
+const INVALID_TUPLE_SRC: &str = r#"
+fn (int, string) triple() {
+    return (1, s"one", 2);
+}
+
+fn int unpack(p: (int, int)) {
+    let (a, b, c) = p;
+    return a;
+}
+"#;
+
+#[test]
+fn test_tuple() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(TUPLE_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let res = ContractDefinition::run(tree);
+    let Ok(def) = res else {
+        panic!("{:#?}", res.err().unwrap())
+    };
+    assert_eq!(
+        def.functions[0].return_ty.ty(),
+        &TypeVariant::Tuple(vec![TypeVariant::Int, TypeVariant::String])
+    );
+
+    // the value is bound to the hidden variable, the elements are accessed by their position.
+    let Some(Statement::Block(body)) = def.functions[1].body.first() else {
+        panic!("Expected the function body");
+    };
+    let [Statement::Variable(hidden), Statement::Variable(a), Statement::Return(_)] =
+        body.statements.as_slice()
+    else {
+        panic!("Expected the destructured bindings: {:#?}", body.statements);
+    };
+    assert!(matches!(hidden.value, Some(Expression::FunctionCall(_))));
+    let Some(Expression::MemberAccess(m)) = &a.value else {
+        panic!("Expected the element access");
+    };
+    assert_eq!(m.member.0, 0);
+    assert_eq!(a.ty, TypeVariant::Int);
+
+    let result = parse(INVALID_TUPLE_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Mismatched types: expected to resolve to (int, string), but found the tuple of 3 elements.",
+            "Tuple has 2 elements, but 3 are bound.",
+        ]
+    );
+}
+
+const CONST_SRC: &str = r#"
+const MAX_SIZE: u8 = 10 * 10;
+const LIMIT = MAX_SIZE + 1;
+
+model Storage {
+    size: int
+} st [
+    size < MAX_SIZE
+]
+
+fn int limit() {
+    return LIMIT;
+}
+"#;
+
+const INVALID_CONST_SRC: &str = r#"
+const FIRST = SECOND;
+const SECOND: int = 1;
+const THIRD: int = limit();
+
+fn int limit() {
+    return 1;
+}
+"#;
+
+#[test]
+fn test_const() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(CONST_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let res = ContractDefinition::run(tree);
+    let Ok(def) = res else {
+        panic!("{:#?}", res.err().unwrap())
+    };
+    let constants: Vec<(&str, &TypeVariant)> = def
+        .constants
+        .iter()
+        .map(|c| (c.name.name.as_str(), &c.ty))
+        .collect();
+    assert_eq!(
+        constants,
+        vec![("MAX_SIZE", &TypeVariant::U8), ("LIMIT", &TypeVariant::U8)]
+    );
+    assert!(matches!(&def.constants[1].value, Expression::UInt(u) if u.element == 101u32.into()));
+
+    // the value is inlined, widened to the type of the field.
+    let Some(bounds) = &def.models[0].bounds else {
+        panic!("Expected the model bounds");
+    };
+    let Expression::Less(b) = &bounds.exprs[0] else {
+        panic!("Expected the comparison");
+    };
+    let Expression::BuiltinCall(cast) = b.right.as_ref() else {
+        panic!("Expected the widened constant: {:#?}", b.right);
+    };
+    assert!(matches!(&cast.args[0], Expression::UInt(u) if u.element == 100u32.into()));
+
+    let result = parse(INVALID_CONST_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Not declared.",
+            "`SECOND`: Variable is not declared or inaccessible.",
+            "The value of the constant must be known at compile time.",
+        ]
+    );
+}
+
+const ALIAS_SRC: &str = r#"
+enum Choice {
+    Yes,
+    No
+}
+
+type Votes = mapping<address -> Choice>
+type Ballots = list<Votes>
+
+model Election {
+    votes: Votes
+}
+
+fn int count(ballots: Ballots) {
+    return 0;
+}
+"#;
+
+const INVALID_ALIAS_SRC: &str = r#"
+type First = Second
+type Second = int
+type Choice = int
+
+struct Choice {
+    a: int
+}
+"#;
+
+#[test]
+fn test_type_alias() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(ALIAS_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let res = ContractDefinition::run(tree);
+    let Ok(def) = res else {
+        panic!("{:#?}", res.err().unwrap())
+    };
+    // the aliases are resolved to the aliased types.
+    let votes = &def.aliases[0].ty;
+    assert!(matches!(votes, TypeVariant::Mapping(_)));
+    assert_eq!(&def.models[0].fields[0].ty.ty, votes);
+    assert_eq!(
+        def.functions[0].params["ballots"].ty.ty,
+        TypeVariant::List(Box::new(votes.clone()))
+    );
+
+    let result = parse(INVALID_ALIAS_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Not declared.",
+            "The struct `Choice` has already been defined earlier.",
+        ]
+    );
+}
+
+const INTERFACE_SRC: &str = r#"
+interface Token {
+    fn int balance(owner: address);
+    fn () transfer(receiver: address, amount: int);
+}
+
+fn int balance_of(app: unit, owner: address) {
+    return Token(app).balance(owner);
+}
+"#;
+
+const INVALID_INTERFACE_SRC: &str = r#"
+interface Registry {
+    fn () register(names: list<string>);
+}
+
+fn string balance_of(app: unit, owner: address) {
+    Registry(app).unregister(owner);
+    return Registry(app).register(owner);
+}
+"#;
+
+#[test]
+fn test_interface() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(INTERFACE_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let res = ContractDefinition::run(tree);
+    let Ok(def) = res else {
+        panic!("{:#?}", res.err().unwrap())
+    };
+    let token = &def.interfaces[0];
+    assert_eq!(token.functions["transfer"].params.len(), 2);
+    assert_eq!(token.functions["balance"].return_ty.ty(), &TypeVariant::Int);
+
+    let Some(Statement::Block(block)) = def.functions[0].body.first() else {
+        panic!("Expected block statement");
+    };
+    let Statement::Return(ret) = &block.statements[0] else {
+        panic!("Expected return statement");
+    };
+    let Some(Expression::InterfaceCall(call)) = &ret.expr else {
+        panic!("Expected interface call");
+    };
+    assert_eq!(call.func, 0);
+    assert_eq!(call.args.len(), 1);
+    assert_eq!(call.returns, TypeVariant::Int);
+
+    let result = parse(INVALID_INTERFACE_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Only primitive types can be passed to the interface functions.",
+            "Function `unregister` is not declared in the interface `Registry`.",
+        ]
+    );
+}
+
+const EVENT_SRC: &str = r#"
+event Transfer {
+    receiver: address,
+    amount: int
+}
+
+fn () transfer(receiver: address) {
+    emit Transfer : { receiver, 10 };
+}
+"#;
+
+const INVALID_EVENT_SRC: &str = r#"
+event Registered {
+    names: list<string>,
+    names: int
+}
+
+event Checked {
+    ok: bool
+}
+
+state CheckState {
+    ok: bool,
+}
+
+@(any)
+view(CheckState s) fn bool check() {
+    emit Checked : { true };
+    return true;
+}
+"#;
+
+#[test]
+fn test_event() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(EVENT_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let res = ContractDefinition::run(tree);
+    let Ok(def) = res else {
+        panic!("{:#?}", res.err().unwrap())
+    };
+    assert_eq!(def.events[0].fields.len(), 2);
+
+    let Some(Statement::Block(block)) = def.functions[0].body.first() else {
+        panic!("Expected block statement");
+    };
+    let Statement::Emit(emit) = &block.statements[0] else {
+        panic!("Expected emit statement");
+    };
+    assert_eq!(emit.sym.i, 0);
+    assert_eq!(emit.args[1].ty(), &TypeVariant::Int);
+
+    let result = parse(INVALID_EVENT_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Only primitive types can be logged in the events.",
+            "Field with this name exist.",
+            "View functions can not emit events.",
+        ]
+    );
+}
+
+const TRANSACTION_SRC: &str = r#"
+state VaultState {
+    asset: unit,
+}
+
+@(any)
+fn () withdraw(receiver: address, amount: unit) {
+    pay(receiver, amount);
+    asset_transfer(10, receiver, amount);
+}
+
+@(any)
+view(VaultState s) fn bool refund(receiver: address) {
+    pay(receiver, 10);
+    return true;
+}
+
+@(any)
+fn () send(receiver: address) {
+    asset_transfer(10, receiver);
+}
+"#;
+
+#[test]
+fn test_transaction() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(TRANSACTION_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let Some(Statement::Block(block)) = def.functions[0].body.first() else {
+        panic!("Expected block statement");
+    };
+    let Statement::Expression(Expression::BuiltinCall(pay)) = &block.statements[0] else {
+        panic!("Expected builtin call");
+    };
+    assert_eq!(pay.func, BuiltinFunction::Pay);
+    assert_eq!(pay.returns, TypeVariant::Unit);
+
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "View functions can not send transactions.",
+            "`asset_transfer` expects 3 arguments, but 2 were provided.",
+        ]
+    );
+}
+
+const CONTEXT_SRC: &str = r#"
+fn bool can_withdraw(owner: address, deadline: unit) {
+    let caller = caller();
+    return caller == owner && current_block > deadline && caller.balance > 1000;
+}
+
+fn unit fail_balance(amount: int) {
+    return amount.balance;
+}
+"#;
+
+#[test]
+fn test_context_values() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(CONTEXT_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(messages, vec!["This type does not support member access."]);
+
+    let Some(Statement::Block(block)) = def.functions[0].body.first() else {
+        panic!("Expected block statement");
+    };
+    let Statement::Return(ret) = &block.statements[1] else {
+        panic!("Expected return statement");
+    };
+    let Some(Expression::And(and)) = &ret.expr else {
+        panic!("Expected conjunction");
+    };
+    let Expression::Greater(balance) = and.right.as_ref() else {
+        panic!("Expected comparison");
+    };
+    let Expression::BuiltinCall(call) = balance.left.as_ref() else {
+        panic!("Expected builtin call");
+    };
+    assert_eq!(call.func, BuiltinFunction::Balance);
+    assert!(matches!(call.args[0], Expression::Variable(_)));
+}
+
+const CRYPTO_SRC: &str = r#"
+fn hex commitment(vote: bool, salt: unit) {
+    return hash(caller(), vote, salt);
+}
+
+fn bool signed(data: hex, signature: hex, key: address) {
+    return ed25519_verify(data, signature, key);
+}
+
+fn hex fail_list(l: list<int>) {
+    return keccak256(s"votes", l);
+}
+
+fn hex fail_empty() {
+    return sha256();
+}
+"#;
+
+#[test]
+fn test_crypto_builtins() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(CRYPTO_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Only primitive types can be hashed, got list<int>.",
+            "`sha256` expects at least one argument.",
+        ]
+    );
+
+    let Some(Statement::Block(block)) = def.functions[0].body.first() else {
+        panic!("Expected block statement");
+    };
+    let Statement::Return(ret) = &block.statements[0] else {
+        panic!("Expected return statement");
+    };
+    let Some(Expression::BuiltinCall(call)) = &ret.expr else {
+        panic!("Expected builtin call");
+    };
+    assert_eq!(call.func, BuiltinFunction::Hash);
+    let tys: Vec<&TypeVariant> = call.args.iter().map(|a| a.ty()).collect();
+    assert_eq!(
+        tys,
+        vec![
+            &TypeVariant::Address,
+            &TypeVariant::Bool,
+            &TypeVariant::Uint
+        ]
+    );
+}
+
+const EQUALITY_SRC: &str = r#"
+struct Pair {
+    a: int,
+    b: list<int>,
+}
+
+struct Ledger {
+    balances: mapping<address -> int>,
+}
+
+fn bool same(p: Pair, q: Pair) {
+    return p == q;
+}
+
+fn bool same_items(l: set<int>, r: set<int>) {
+    return l != r;
+}
+
+fn bool fail_ledger(l: Ledger, r: Ledger) {
+    return l == r;
+}
+"#;
+
+#[test]
+fn test_structural_equality() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(EQUALITY_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec!["Values of type `struct Ledger` cannot be compared for equality."]
+    );
+
+    let Some(Statement::Block(block)) = def.functions[0].body.first() else {
+        panic!("Expected block statement");
+    };
+    let Statement::Return(ret) = &block.statements[0] else {
+        panic!("Expected return statement");
+    };
+    let Some(Expression::Equal(b)) = &ret.expr else {
+        panic!("Expected equality");
+    };
+    assert!(matches!(b.left.ty(), TypeVariant::Struct(_)));
+}
+
+const AS_SRC: &str = r#"
+fn unit to_uint(a: int) {
+    return a as unit;
+}
+
+fn address to_address(data: hex) {
+    return data as address;
+}
+
+fn u8 small() {
+    return 200 as u8;
+}
+
+fn hex fail_bool(flag: bool) {
+    return flag as hex;
+}
+
+fn u8 fail_range() {
+    return 300 as u8;
+}
+"#;
+
+#[test]
+fn test_as_conversions() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(AS_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let def = resolve_contract(tree);
+    let messages: Vec<&str> = def.diagnostics.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "bool cannot be converted to hex.",
+            "Value 300 is out of range of u8.",
+        ]
+    );
+
+    let returned = |i: usize| -> Expression {
+        let Some(Statement::Block(block)) = def.functions[i].body.first() else {
+            panic!("Expected function body");
+        };
+        let Some(Statement::Return(r)) = block.statements.first() else {
+            panic!("Expected return statement");
+        };
+        r.expr.clone().expect("returns value")
+    };
+    let Expression::BuiltinCall(to_uint) = returned(0) else {
+        panic!("Expected cast");
+    };
+    assert_eq!(to_uint.func, BuiltinFunction::Cast);
+    assert_eq!(to_uint.returns, TypeVariant::Uint);
+    let Expression::BuiltinCall(to_address) = returned(1) else {
+        panic!("Expected cast");
+    };
+    assert_eq!(to_address.returns, TypeVariant::Address);
+    assert_eq!(to_address.args[0].ty(), &TypeVariant::Hex);
+    // literals are converted at compile time.
+    assert!(matches!(returned(2), Expression::UInt(u) if u.ty == TypeVariant::U8));
+}
+
+const CONST_EVAL_SRC: &str = r#"
+enum Mode {
+    On,
+    Off
+}
+
+const PRIMES: list<int> = [2, 3, 5, 7];
+const COUNT = size(PRIMES);
+const DEFAULT_MODE = Mode.On;
+const IS_ON = DEFAULT_MODE == Mode.On;
+const CHECKED = !IS_ON || COUNT > 10;
+const SMALL: u8 = 3;
+const WIDE: int = (SMALL + 1) * 2;
+const HAS_FIVE = 5 in PRIMES;
+"#;
+
+#[test]
+fn test_const_eval() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(CONST_EVAL_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+    let res = ContractDefinition::run(tree);
+    let Ok(def) = res else {
+        panic!("{:#?}", res.err().unwrap())
+    };
+    let value = |name: &str| -> &Expression {
+        &def.constants
+            .iter()
+            .find(|c| c.name.name == name)
+            .expect("constant is declared")
+            .value
+    };
+    let Expression::List(primes) = value("PRIMES") else {
+        panic!("Expected the list literal");
+    };
+    assert!(primes.element.iter().all(|e| e.is_literal()));
+    assert!(matches!(value("COUNT"), Expression::UInt(u) if u.element == 4u32.into()));
+    assert!(matches!(value("DEFAULT_MODE"), Expression::Enum(u) if u.element == 0));
+    assert!(matches!(value("IS_ON"), Expression::Boolean(u) if u.element));
+    assert!(matches!(value("CHECKED"), Expression::Boolean(u) if !u.element));
+    assert!(matches!(value("WIDE"), Expression::Int(u) if u.element == 8.into()));
+    assert!(matches!(value("HAS_FIVE"), Expression::Boolean(u) if u.element));
+}
+
+#[test]
+fn test_parse_and_check_malformed() {
+    folidity_diagnostics::disable_pretty_print();
+    // inputs found by the fuzzer.
+    let valid = [
+        "fn float half() { return .5; }",
+        "fn () spin(n: int) { for (let mut i = 0; i < n; i + 1) {} }",
+    ];
+    for src in valid {
+        let res = parse_and_check(src);
+        assert!(res.is_ok(), "{:#?}", res.err());
+    }
+
+    let def = parse_and_check(valid[0]).unwrap();
+    let Some(Statement::Block(block)) = def.functions[0].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Statement::Return(ret) = &block.statements[0] else {
+        panic!("Expected the return statement");
+    };
+    assert!(
+        matches!(&ret.expr, Some(Expression::Float(f)) if f.element == (1.into(), 2.into()).into())
+    );
+
+    let invalid = [
+        "fn () f() { for (let mut i = 0; i < ; i + 1) {} }",
+        "state S { a: int } fn () f() { move S : { .5 }; }",
+        "model M { x: int } state S(M) fn () f() { return S : { }; }",
+        "fn int f(",
+    ];
+    for src in invalid {
+        assert!(parse_and_check(src).is_err());
+    }
+}
+
+#[test]
+fn test_assertions() {
+    folidity_diagnostics::disable_pretty_print();
+    let src = r#"
+fn int withdraw(amount: int, balance: int) {
+    require(amount > 0);
+    assert(amount <= balance, s"insufficient balance");
+    return balance - amount;
+}
+"#;
+    let res = parse_and_check(src);
+    assert!(res.is_ok(), "{:#?}", res.err());
+
+    let invalid = [
+        "fn () f(a: int) { assert(a); }",
+        "fn () f(a: int) { assert(a > 0, 1); }",
+        "fn () f(a: int) { require(a > 0, s\"a\", s\"b\"); }",
+        "fn int f(a: int) { return assert(a > 0); }",
+    ];
+    for src in invalid {
+        assert!(parse_and_check(src).is_err(), "{}", src);
+    }
+}
+
+const COMPOUND_ASSIGN_SRC: &str = r#"
+fn int total(n: int) {
+    let mut acc = 1;
+    for (let mut i = 0; i < n; i += 2) {
+        acc *= i;
+        acc++;
+    }
+    return acc;
+}
+
+fn int fixed(n: int) {
+    let acc = 1;
+    acc -= n;
+    return acc;
+}
+
+fn () spin(n: int) {
+    for (let mut i = 0; i < n; n++) {}
+}
+"#;
+
+#[test]
+fn test_compound_assign() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(COMPOUND_ASSIGN_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let Some(Statement::Block(body)) = def.functions[0].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::ForLoop(l)) = body.statements.get(1) else {
+        panic!("Expected the loop");
+    };
+    assert!(matches!(&l.incrementer, Expression::Add(_)));
+    let Some(Statement::Assign(a)) = l.body.first() else {
+        panic!("Expected the assignment");
+    };
+    let Expression::Multiply(b) = &a.value else {
+        panic!("Expected the desugared multiplication");
+    };
+    assert!(matches!(&*b.left, Expression::Variable(v) if v.element == a.pos));
+
+    let errors: Vec<&str> = def
+        .diagnostics
+        .iter()
+        .filter(|r| r.level == Level::Error)
+        .map(|r| r.message.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Variable is immutable. Annotate with `mut` keyword to allow mutation.",
+            "Only the loop variable can be updated by the incrementer.",
+        ]
+    );
+}
+
+const CONDITIONAL_SRC: &str = r#"
+fn unit fee(amount: unit, premium: bool) {
+    let rate = if premium then 1 else amount / 100;
+    return rate;
+}
+
+fn int fixed() {
+    return if 2 > 1 then 10 else 20;
+}
+
+fn int mismatched(a: int) {
+    let x = if a > 0 then a else s"none";
+    return 0;
+}
+"#;
+
+#[test]
+fn test_conditional() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(CONDITIONAL_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let Some(Statement::Block(body)) = def.functions[0].body.first() else {
+        panic!("Expected the function body");
+    };
+    // the type of the literal branch follows the other one.
+    let Some(Statement::Variable(var)) = body.statements.first() else {
+        panic!("Expected the variable");
+    };
+    let Some(Expression::Conditional(c)) = &var.value else {
+        panic!("Expected the conditional expression");
+    };
+    assert_eq!(c.ty, TypeVariant::Uint);
+    assert_eq!(c.then_expr.ty(), &TypeVariant::Uint);
+
+    let errors: Vec<&str> = def
+        .diagnostics
+        .iter()
+        .filter(|r| r.level == Level::Error)
+        .map(|r| r.message.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        vec!["Branches of the conditional expression have different types: int and string."]
+    );
+
+    // the branch of the constant condition is selected.
+    let mut def = def;
+    optimize(&mut def, OptLevel::O1);
+    let Some(Statement::Block(body)) = def.functions[1].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Return(ret)) = body.statements.first() else {
+        panic!("Expected the return statement");
+    };
+    assert!(matches!(&ret.expr, Some(Expression::Int(u)) if u.element == 10.into()));
+}
+
+const MATH_SRC: &str = r#"
+fn unit compound(principal: unit, years: unit) {
+    return principal * pow(2, years);
+}
+
+fn float root(x: float) {
+    return sqrt(x);
+}
+
+fn int folded() {
+    let p: int = pow(-2, 3);
+    return p + sqrt(17);
+}
+
+fn int negative() {
+    let r: int = sqrt(-4);
+    return r;
+}
+
+fn bool unsupported() {
+    let b = sqrt(true);
+    return b;
+}
+"#;
+
+#[test]
+fn test_pow_and_sqrt() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(MATH_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let Some(Statement::Block(body)) = def.functions[0].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Return(ret)) = body.statements.first() else {
+        panic!("Expected the return statement");
+    };
+    let Some(Expression::Multiply(b)) = &ret.expr else {
+        panic!("Expected the multiplication");
+    };
+    // the base takes the type of the call.
+    let Expression::BuiltinCall(pow) = b.right.as_ref() else {
+        panic!("Expected the call of `pow`");
+    };
+    assert_eq!(pow.func, BuiltinFunction::Pow);
+    assert_eq!(pow.args[0].ty(), &TypeVariant::Uint);
+    assert_eq!(pow.returns, TypeVariant::Uint);
+
+    let Some(Statement::Block(body)) = def.functions[1].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Return(ret)) = body.statements.first() else {
+        panic!("Expected the return statement");
+    };
+    assert!(
+        matches!(&ret.expr, Some(Expression::BuiltinCall(c)) if c.returns == TypeVariant::Float)
+    );
+
+    // literals are folded, the roots are rounded down.
+    let Some(Statement::Block(body)) = def.functions[2].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Variable(var)) = body.statements.first() else {
+        panic!("Expected the variable");
+    };
+    assert!(matches!(&var.value, Some(Expression::Int(u)) if u.element == (-8).into()));
+    let Some(Statement::Return(ret)) = body.statements.get(1) else {
+        panic!("Expected the return statement");
+    };
+    let Some(Expression::Add(b)) = &ret.expr else {
+        panic!("Expected the addition");
+    };
+    assert!(matches!(b.right.as_ref(), Expression::Int(u) if u.element == 4.into()));
+
+    let errors: Vec<&str> = def
+        .diagnostics
+        .iter()
+        .filter(|r| r.level == Level::Error)
+        .map(|r| r.message.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Square root of the negative number is undefined.",
+            "`sqrt` is not supported for bool."
+        ]
+    );
+}
+
+const INDEXING_SRC: &str = r#"
+fn list<int> window(xs: list<int>, n: unit) {
+    let first = xs[0];
+    return xs[1..n];
+}
+
+fn int folded() {
+    let l: list<int> = [1, 2, 3][1..];
+    return [1, 2, 3][1];
+}
+
+fn int out_of_bounds() {
+    return [1, 2, 3][3];
+}
+
+fn list<int> reversed(xs: list<int>) {
+    return xs[2..1];
+}
+
+fn int not_a_list(a: int) {
+    let b = a[0];
+    return b;
+}
+"#;
+
+#[test]
+fn test_indexing() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(INDEXING_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let Some(Statement::Block(body)) = def.functions[0].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Variable(var)) = body.statements.first() else {
+        panic!("Expected the variable");
+    };
+    let Some(Expression::Index(i)) = &var.value else {
+        panic!("Expected the index");
+    };
+    assert_eq!(i.ty, TypeVariant::Int);
+    assert_eq!(i.index.ty(), &TypeVariant::Uint);
+    let Some(Statement::Return(ret)) = body.statements.get(1) else {
+        panic!("Expected the return statement");
+    };
+    assert!(matches!(
+        &ret.expr,
+        Some(Expression::Slice(s)) if s.ty == TypeVariant::List(Box::new(TypeVariant::Int))
+    ));
+
+    // indices of the constant lists are folded.
+    let Some(Statement::Block(body)) = def.functions[1].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Variable(var)) = body.statements.first() else {
+        panic!("Expected the variable");
+    };
+    assert!(matches!(&var.value, Some(Expression::List(u)) if u.element.len() == 2));
+    let Some(Statement::Return(ret)) = body.statements.get(1) else {
+        panic!("Expected the return statement");
+    };
+    assert!(matches!(&ret.expr, Some(Expression::Int(u)) if u.element == 2.into()));
+
+    let errors: Vec<&str> = def
+        .diagnostics
+        .iter()
+        .filter(|r| r.level == Level::Error)
+        .map(|r| r.message.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Index 3 is out of bounds of the list of 3 elements.",
+            "Slice starts at 2 after its end at 1.",
+            "Only lists can be indexed, got int."
+        ]
+    );
+}
+
+const UPDATES_SRC: &str = r#"
+fn list<int> enqueue(l: list<int>, x: int) {
+    let mut q = l :> push(x) :> insert(0, x);
+    q = q :> pop();
+    return q :> union(l) :> difference([x]);
+}
+
+fn set<address> allow(s: set<address>, a: address) {
+    return s :> push(a) :> union(s);
+}
+
+fn set<int> unordered(s: set<int>) {
+    return s :> pop();
+}
+
+fn list<int> mismatched(l: list<int>, s: set<int>) {
+    return l :> union(s);
+}
+"#;
+
+#[test]
+fn test_collection_updates() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = parse(UPDATES_SRC);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let def = resolve_contract(tree);
+    let Some(Statement::Block(body)) = def.functions[0].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Variable(var)) = body.statements.first() else {
+        panic!("Expected the variable");
+    };
+    let Some(Expression::BuiltinCall(insert)) = &var.value else {
+        panic!("Expected the call of `insert`");
+    };
+    assert_eq!(insert.func, BuiltinFunction::Insert);
+    assert_eq!(insert.args[1].ty(), &TypeVariant::Uint);
+    assert!(
+        matches!(&insert.args[0], Expression::BuiltinCall(c) if c.func == BuiltinFunction::Push)
+    );
+    assert_eq!(
+        insert.returns,
+        TypeVariant::List(Box::new(TypeVariant::Int))
+    );
+
+    let Some(Statement::Block(body)) = def.functions[1].body.first() else {
+        panic!("Expected the function body");
+    };
+    let Some(Statement::Return(ret)) = body.statements.first() else {
+        panic!("Expected the return statement");
+    };
+    assert!(matches!(
+        &ret.expr,
+        Some(Expression::BuiltinCall(c))
+            if c.func == BuiltinFunction::Union
+                && c.returns == TypeVariant::Set(Box::new(TypeVariant::Address))
+    ));
+
+    let errors: Vec<&str> = def
+        .diagnostics
+        .iter()
+        .filter(|r| r.level == Level::Error)
+        .map(|r| r.message.as_str())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "`pop` is not supported for set<int>.",
+            "Mismatched types: expected to resolve to list<int>, but expression can only resolve to set<int>"
+        ]
+    );
+}
@@ -44,6 +44,7 @@ pub struct DelayedDeclaration<T> {
 #[derive(Debug)]
 pub struct DelayedDeclarations {
     pub structs: Vec<DelayedDeclaration<parsed_ast::StructDeclaration>>,
+    pub enums: Vec<DelayedDeclaration<parsed_ast::EnumDeclaration>>,
     pub models: Vec<DelayedDeclaration<parsed_ast::ModelDeclaration>>,
     pub states: Vec<DelayedDeclaration<parsed_ast::StateDeclaration>>,
     pub functions: Vec<DelayedDeclaration<parsed_ast::FunctionDeclaration>>,
@@ -62,7 +63,7 @@ pub enum ExpectedType {
 }
 
 impl ExpectedType {
-    fn display(&self, contract: &ContractDefinition) -> String {
+    pub(crate) fn display(&self, contract: &ContractDefinition) -> String {
         match self {
             ExpectedType::Empty => "nothing".to_string(),
             ExpectedType::Concrete(ty) => ty.display(contract),
@@ -84,6 +85,10 @@ pub fn map_type(contract: &mut ContractDefinition, ty: &parsed_ast::Type) -> Res
     let variant = match &ty.ty {
         parsed_ast::TypeVariant::Int => TypeVariant::Int,
         parsed_ast::TypeVariant::Uint => TypeVariant::Uint,
+        parsed_ast::TypeVariant::U8 => TypeVariant::U8,
+        parsed_ast::TypeVariant::U64 => TypeVariant::U64,
+        parsed_ast::TypeVariant::I64 => TypeVariant::I64,
+        parsed_ast::TypeVariant::U256 => TypeVariant::U256,
         parsed_ast::TypeVariant::Float => TypeVariant::Float,
         parsed_ast::TypeVariant::Char => TypeVariant::Char,
         parsed_ast::TypeVariant::String => TypeVariant::String,
@@ -99,6 +104,17 @@ pub fn map_type(contract: &mut ContractDefinition, ty: &parsed_ast::Type) -> Res
             let list_ty = map_type(contract, &l.ty)?;
             TypeVariant::List(Box::new(list_ty.ty))
         }
+        parsed_ast::TypeVariant::Option(o) => {
+            let option_ty = map_type(contract, &o.ty)?;
+            TypeVariant::Option(Box::new(option_ty.ty))
+        }
+        parsed_ast::TypeVariant::Tuple(tys) => {
+            let mut elements = vec![];
+            for ty in tys {
+                elements.push(map_type(contract, ty)?.ty);
+            }
+            TypeVariant::Tuple(elements)
+        }
         parsed_ast::TypeVariant::Mapping(m) => {
             let m_from_ty = map_type(contract, &m.from_ty)?;
             let m_to_ty = map_type(contract, &m.to_ty)?;
@@ -126,6 +142,29 @@ pub fn map_type(contract: &mut ContractDefinition, ty: &parsed_ast::Type) -> Res
                             returns: Box::new(return_ty),
                         })
                     }
+                    GlobalSymbol::Alias(info) => contract.aliases[info.i].ty.clone(),
+                    GlobalSymbol::Constant(_) => {
+                        contract.diagnostics.push(Report::semantic_error(
+                            user_ty.loc.clone(),
+                            String::from("Constants can not be used as types."),
+                        ));
+                        return Err(());
+                    }
+                    GlobalSymbol::Interface(_) => {
+                        contract.diagnostics.push(Report::semantic_error(
+                            user_ty.loc.clone(),
+                            String::from("Interfaces can not be used as types."),
+                        ));
+                        return Err(());
+                    }
+                    GlobalSymbol::Event(_) => {
+                        contract.diagnostics.push(Report::semantic_error(
+                            user_ty.loc.clone(),
+                            String::from("Events can not be used as types."),
+                        ));
+                        return Err(());
+                    }
+                    GlobalSymbol::Test(_) => unreachable!("tests are not declared as symbols"),
                 }
             } else {
                 return Err(());
@@ -168,9 +207,18 @@ impl Expression {
             Expression::Or(e) => &e.ty,
             Expression::And(e) => &e.ty,
             Expression::FunctionCall(e) => &e.returns,
+            Expression::BuiltinCall(e) => &e.returns,
+            Expression::InterfaceCall(e) => &e.returns,
             Expression::MemberAccess(e) => &e.ty,
             Expression::StructInit(e) => &e.ty,
+            Expression::VariantInit(e) => &e.ty,
+            Expression::Lambda(e) => &e.ty,
+            Expression::Conditional(e) => &e.ty,
+            Expression::Index(e) => &e.ty,
+            Expression::Slice(e) => &e.ty,
             Expression::List(e) => &e.ty,
+            Expression::Optional(e) => &e.ty,
+            Expression::Tuple(e) => &e.ty,
             Expression::Enum(e) => &e.ty,
         }
     }
@@ -197,7 +245,12 @@ pub fn find_user_type_recursion(contract: &mut ContractDefinition) {
     let graph: FieldGraph = Graph::from_edges(edges);
     let tarjan = tarjan_scc(&graph);
     let mut nodes = HashSet::new();
-    for node in tarjan.iter().flatten() {
+    // only the components with cycles are recursive, the struct can refer to itself.
+    for node in tarjan
+        .iter()
+        .filter(|nodes| nodes.len() > 1 || graph.contains_edge(nodes[0], nodes[0]))
+        .flatten()
+    {
         nodes.insert(node);
     }
 
@@ -213,6 +266,18 @@ pub fn find_user_type_recursion(contract: &mut ContractDefinition) {
             ));
         }
     }
+
+    // payloads can not hold the enum they belong to.
+    for (n, e) in contract.enums.iter().enumerate() {
+        for field in e.variants.values().flat_map(|v| &v.fields) {
+            if matches!(&field.ty.ty, TypeVariant::Enum(s) if s.i == n) {
+                contract.diagnostics.push(Report::semantic_error(
+                    field.loc.clone(),
+                    String::from("Recursive field detected."),
+                ));
+            }
+        }
+    }
 }
 
 /// Collect field dependencies into the graph edges.
@@ -274,6 +339,12 @@ pub fn validate_fields(contract: &mut ContractDefinition) {
         validate(&s.fields);
     }
 
+    for e in &contract.enums {
+        for v in e.variants.values() {
+            validate(&v.fields);
+        }
+    }
+
     for s in &contract.states {
         if let Some(StateBody::Raw(fields)) = &s.body {
             validate(fields);
@@ -3,7 +3,9 @@ use folidity_semantics::{
     ast::{
         Expression,
         Function,
+        Mapping,
         StateDeclaration,
+        TypeVariant,
     },
     DelayedDeclaration,
     Span,
@@ -11,6 +13,8 @@ use folidity_semantics::{
 use indexmap::IndexMap;
 use z3::{
     ast::{
+        forall_const,
+        Array,
         Ast,
         Bool,
         Dynamic,
@@ -24,7 +28,9 @@ use crate::{
     executor::SymbolicExecutor,
     transformer::{
         create_constraint_const,
+        simplify,
         transform_expr,
+        type_to_sort,
         TransformParams,
     },
 };
@@ -53,6 +59,20 @@ impl Z3Scope {
         }
     }
 
+    /// Create the constant of the field, along with the domain of the mapping fields.
+    pub fn declare_field<'ctx>(
+        &mut self,
+        ident: &str,
+        ty: &TypeVariant,
+        ctx: &'ctx Context,
+        executor: &mut SymbolicExecutor<'ctx>,
+    ) {
+        let _ = self.create_or_get(ident, type_to_sort(ty, ctx), ctx, executor);
+        if let TypeVariant::Mapping(m) = ty {
+            let _ = self.create_or_get(&domain_name(ident), domain_sort(m, ctx), ctx, executor);
+        }
+    }
+
     /// Retrieve a constant with the given name.
     pub fn get<'ctx>(
         &self,
@@ -66,6 +86,19 @@ impl Z3Scope {
     }
 }
 
+/// Suffix of the constants holding the set of keys present in the mappings.
+pub const DOMAIN_SUFFIX: &str = ".dom";
+
+/// Name of the constant holding the set of keys present in the mapping.
+pub fn domain_name(ident: &str) -> String {
+    format!("{}{}", ident, DOMAIN_SUFFIX)
+}
+
+/// Sort of the domain of the mapping, the set of its keys.
+pub fn domain_sort<'ctx>(m: &Mapping, ctx: &'ctx Context) -> Sort<'ctx> {
+    Sort::set(ctx, &type_to_sort(&m.from_ty, ctx))
+}
+
 /// List of delayed declaration to be resolved in consequent passes.
 #[derive(Debug, Default)]
 pub struct Delays<'a> {
@@ -102,6 +135,7 @@ impl<'ctx> DeclarationBounds<'ctx> {
                 Constraint {
                     loc: c.loc.clone(),
                     binding_sym: *n,
+                    label: c.label.clone(),
                     expr: c.expr.translate(new_ctx).clone(),
                 }
             })
@@ -127,6 +161,8 @@ pub struct Constraint<'ctx> {
     /// e.g. `k!0 => a > 10`
     /// where `0` is the id of the symbol.
     pub binding_sym: u32,
+    /// Label of the constraint given in the source, e.g. `"positive": a > 0`.
+    pub label: Option<String>,
     /// Boolean expression.
     pub expr: Bool<'ctx>,
 }
@@ -137,11 +173,20 @@ impl<'ctx> Constraint<'ctx> {
         Bool::new_const(ctx, self.binding_sym)
     }
 
+    /// Name of the constraint in the reports, its label or the symbol id.
+    pub fn name(&self) -> String {
+        match &self.label {
+            Some(label) => format!("\"{}\"", label),
+            None => self.binding_sym.to_string(),
+        }
+    }
+
     pub fn from_expr(
         expr: &Expression,
+        label: Option<String>,
         params: &mut TransformParams<'ctx, '_>,
     ) -> Result<Constraint<'ctx>, ()> {
-        let resolve_e = transform_expr(expr, params)?;
+        let resolve_e = transform_expr(&simplify(expr), params)?;
         let Some(bool_expr) = resolve_e.element.as_bool() else {
             params.diagnostics.push(Report::ver_error(
                 resolve_e.loc.clone(),
@@ -154,12 +199,104 @@ impl<'ctx> Constraint<'ctx> {
         // create a binding boolean constant: `c => expr`, to track each constraint.
         let binding_expr = binding_const.implies(&bool_expr);
 
+        // the expression might be reduced, so we keep the location of the original one.
         Ok(Constraint {
-            loc: resolve_e.loc.clone(),
+            loc: expr.loc().clone(),
             binding_sym: n,
+            label,
             expr: binding_expr,
         })
     }
+
+    /// Constraints of the relation declared by the mapping, e.g. `mapping<address >-/>
+    /// hex>`.
+    ///
+    /// - Total mappings contain every key.
+    /// - Injective mappings have an inverse on their domain, so distinct keys have
+    ///   distinct values.
+    /// - Surjective mappings have a preimage in their domain for every value.
+    pub fn from_relation(
+        ident: &str,
+        m: &Mapping,
+        params: &mut TransformParams<'ctx, '_>,
+    ) -> Result<Vec<Constraint<'ctx>>, ()> {
+        let ctx = params.ctx;
+        let key_sort = type_to_sort(&m.from_ty, ctx);
+        let value_sort = type_to_sort(&m.to_ty, ctx);
+        let array = params
+            .z3_scope
+            .create_or_get(
+                ident,
+                Sort::array(ctx, &key_sort, &value_sort),
+                ctx,
+                params.executor,
+            )
+            .as_array();
+        let domain = params
+            .z3_scope
+            .create_or_get(
+                &domain_name(ident),
+                domain_sort(m, ctx),
+                ctx,
+                params.executor,
+            )
+            .as_set();
+        let (Some(array), Some(domain)) = (array, domain) else {
+            params.diagnostics.push(Report::internal_error(
+                m.relation.loc.clone(),
+                format!("Mapping `{}` is not declared as an array.", ident),
+            ));
+            return Err(());
+        };
+
+        let mut properties: Vec<(&str, Bool<'ctx>)> = vec![];
+        if !m.relation.partial {
+            let (k, _) = params.executor.create_constant(&key_sort);
+            let total = forall_const(ctx, &[&k], &[], &domain.member(&k));
+            properties.push(("total", total));
+        }
+        if m.relation.injective {
+            let inverse = inverse_const(&value_sort, &key_sort, params);
+            let (k, _) = params.executor.create_constant(&key_sort);
+            let body = domain
+                .member(&k)
+                .implies(&inverse.select(&array.select(&k))._eq(&k));
+            properties.push(("injective", forall_const(ctx, &[&k], &[], &body)));
+        }
+        if m.relation.surjective {
+            let preimage = inverse_const(&value_sort, &key_sort, params);
+            let (v, _) = params.executor.create_constant(&value_sort);
+            let k = preimage.select(&v);
+            let body = Bool::and(ctx, &[domain.member(&k), array.select(&k)._eq(&v)]);
+            properties.push(("surjective", forall_const(ctx, &[&v], &[], &body)));
+        }
+
+        let constraints = properties
+            .into_iter()
+            .map(|(property, expr)| {
+                let (binding_const, n) = create_constraint_const(ctx, params.executor);
+                Constraint {
+                    loc: m.relation.loc.clone(),
+                    binding_sym: n,
+                    label: Some(format!("{} is {}", ident, property)),
+                    expr: binding_const.implies(&expr),
+                }
+            })
+            .collect();
+        Ok(constraints)
+    }
+}
+
+/// Fresh array from the values of the mapping to its keys.
+fn inverse_const<'ctx>(
+    value_sort: &Sort<'ctx>,
+    key_sort: &Sort<'ctx>,
+    params: &mut TransformParams<'ctx, '_>,
+) -> Array<'ctx> {
+    let (_, i) = params
+        .executor
+        .create_constant(&Sort::array(params.ctx, value_sort, key_sort));
+    Array::new_const(params.ctx, i, value_sort, key_sort)
 }
 
 /// Represents unary style expression.
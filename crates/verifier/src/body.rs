@@ -0,0 +1,1002 @@
+//! Symbolic execution of the function bodies.
+//!
+//! Statements are executed along every path of the function, tracking the values of
+//! the variables as Z3 expressions over the parameters and the fields of the current
+//! state. At every `move`, the constraints of the target state and its models are checked
+//! to hold for the new fields under the path condition, assuming the constraints of
+//! the current state and of the function.
+//!
+//! Loops and the expressions the verifier can not reason about yield unknown values,
+//! so the violations found along such paths may be spurious.
+//! Loop invariants are checked to hold before the loop and to be preserved by every
+//! iteration, and the values after the loop are only known to satisfy them.
+//! Recursive calls are collected along the paths to check the termination.
+//! Optionally, the arithmetic is checked not to overflow the integers of the AVM,
+//! and the indices of the lists to be within their bounds.
+//!
+//! The constraints holding after the transition along every explored path are recorded,
+//! so that the emitter can skip asserting them at runtime.
+
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use folidity_diagnostics::{
+    Paint,
+    Report,
+};
+use folidity_semantics::{
+    ast::{
+        Bounds,
+        Expression,
+        Function,
+        StateBody,
+        Statement,
+        TypeVariant,
+        UnaryExpression,
+    },
+    builtin::BuiltinFunction,
+    ContractDefinition,
+    GlobalSymbol,
+    Span,
+    SymbolInfo,
+};
+use indexmap::IndexMap;
+use z3::{
+    ast::{
+        Ast,
+        Bool,
+        Dynamic,
+    },
+    SatResult,
+};
+
+use crate::{
+    ast::{
+        domain_name,
+        domain_sort,
+        Constraint,
+        Z3Scope,
+        DOMAIN_SUFFIX,
+    },
+    executor::SymbolicExecutor,
+    transformer::{
+        transform_expr,
+        type_to_sort,
+        TransformParams,
+    },
+};
+use termination::{
+    calls_in,
+    RecursiveCall,
+};
+
+mod bounds;
+mod overflow;
+mod termination;
+
+/// Max number of the paths explored per function.
+const MAX_PATHS: usize = 64;
+
+/// Execution path of the function body.
+#[derive(Debug, Clone, Default)]
+struct Path<'ctx> {
+    /// Constants of the variables and their current values by the variable name.
+    values: IndexMap<String, (Dynamic<'ctx>, Dynamic<'ctx>)>,
+    /// Conditions of the branches taken along the path.
+    conditions: Vec<Bool<'ctx>>,
+    /// Has any value along the path been approximated?
+    approximate: bool,
+    /// Has the rest of the loop iteration been skipped?
+    skipped: bool,
+    /// Has the loop been left with `break`?
+    broken: bool,
+}
+
+impl<'ctx> Path<'ctx> {
+    /// Replace the constants of the variables with their current values.
+    fn substitute<T: Ast<'ctx>>(&self, e: &T) -> T {
+        let pairs: Vec<(&Dynamic<'ctx>, &Dynamic<'ctx>)> =
+            self.values.values().map(|(c, v)| (c, v)).collect();
+        e.substitute(&pairs)
+    }
+}
+
+impl<'ctx> SymbolicExecutor<'ctx> {
+    /// Execute the bodies of the functions transitioning the contract to other states,
+    /// having loop invariants, assertions or recursive calls, and check that the
+    /// constraints of the states hold after every `move`, that the loop invariants
+    /// are inductive, that the assertions hold and that the recursion terminates.
+    /// All functions are executed if the overflow or the bounds are checked.
+    ///
+    /// Invariants that do not hold along the exact paths are added to the diagnostics
+    /// of the executor as errors, the ones that may only fail along the approximate
+    /// paths are returned as warnings.
+    ///
+    /// # Returns
+    /// - Warnings of the transitions violating the constraints, of the invariants and
+    ///   assertions that may not hold, of the recursion that may not terminate, of the
+    ///   overflowing arithmetic and of the indices out of bounds, along with their
+    ///   functions.
+    pub fn verify_function_bodies(
+        &mut self,
+        contract: &ContractDefinition,
+    ) -> Vec<(GlobalSymbol, Report)> {
+        let mut reports = vec![];
+        let mut cycles = contract.recursive_cycles();
+        for (i, f) in contract.functions.iter().enumerate() {
+            if f.state_bound.as_ref().is_none_or(|b| b.to.is_empty())
+                && !has_invariants(&f.body)
+                && !has_assertions(&f.body)
+                && cycles[i].is_empty()
+                && !self.config.check_overflow
+                && !self.config.check_bounds
+            {
+                continue;
+            }
+            let sym = GlobalSymbol::Function(SymbolInfo::new(f.loc.clone(), i));
+            if self
+                .declarations
+                .get_index_of(&sym)
+                .is_none_or(|id| self.verified.contains(&id))
+            {
+                continue;
+            }
+
+            let mut body = BodyExecutor::new(self, i, &sym, contract);
+            body.cycle = std::mem::take(&mut cycles[i]);
+            body.execute(&f.body, Path::default());
+            body.check_termination();
+            let BodyExecutor {
+                reports: warnings,
+                errors,
+                transitions,
+                truncated,
+                ..
+            } = body;
+            // the constraints may be violated along the paths that are not explored.
+            if !truncated {
+                self.proven.extend(
+                    transitions
+                        .into_iter()
+                        .filter_map(|(k, holds)| holds.then_some(k)),
+                );
+            }
+            reports.extend(warnings.into_iter().map(|r| (sym.clone(), r)));
+            self.diagnostics.extend(errors);
+        }
+        reports
+    }
+
+    /// Constraints of the state and of the models it is built on.
+    pub(crate) fn state_invariants(
+        &self,
+        i: usize,
+        contract: &ContractDefinition,
+    ) -> Vec<Constraint<'ctx>> {
+        let decl = &contract.states[i];
+        let mut syms = vec![GlobalSymbol::State(SymbolInfo::new(decl.loc.clone(), i))];
+        let mut model = match &decl.body {
+            Some(StateBody::Model(m)) => Some(m.clone()),
+            _ => None,
+        };
+        while let Some(m) = model {
+            model = contract.models[m.i].parent.clone();
+            syms.push(GlobalSymbol::Model(m));
+        }
+        syms.iter()
+            .filter_map(|s| self.declarations.get(s))
+            .flat_map(|d| d.constraints.values().cloned())
+            .collect()
+    }
+}
+
+/// Executor of the body of a single function.
+struct BodyExecutor<'a, 'ctx> {
+    executor: &'a mut SymbolicExecutor<'ctx>,
+    func: &'a Function,
+    /// Index of the function.
+    func_i: usize,
+    contract: &'a ContractDefinition,
+    /// Constants of the variables of the function.
+    z3_scope: Z3Scope,
+    /// Constraints holding at the entry of the function.
+    assumptions: Vec<Bool<'ctx>>,
+    reports: Vec<Report>,
+    errors: Vec<Report>,
+    /// Functions in the recursive cycle of the function.
+    cycle: HashSet<usize>,
+    /// Recursive calls made along the paths.
+    calls: Vec<RecursiveCall<'ctx>>,
+    /// Operations reported to overflow.
+    overflows: HashSet<Span>,
+    /// Indices and slices reported to be out of bounds.
+    out_of_bounds: HashSet<Span>,
+    /// Constraints checked after the transitions, by the locations of the instantiation
+    /// and the constraint, and whether they hold along every path.
+    transitions: HashMap<(Span, Span), bool>,
+    /// Whether some paths were not explored.
+    truncated: bool,
+}
+
+impl<'a, 'ctx> BodyExecutor<'a, 'ctx> {
+    fn new(
+        executor: &'a mut SymbolicExecutor<'ctx>,
+        func_i: usize,
+        sym: &GlobalSymbol,
+        contract: &'a ContractDefinition,
+    ) -> Self {
+        let func = &contract.functions[func_i];
+        let mut constraints = vec![];
+        // the constants of the function scope are shared with its constraints.
+        let mut z3_scope = Z3Scope::default();
+        if let Some(decl) = executor.declarations.get(sym) {
+            z3_scope = decl.scope.clone();
+            constraints.extend(decl.constraints.values().cloned());
+        }
+        if let Some(from) = func.state_bound.as_ref().and_then(|b| b.from.as_ref()) {
+            constraints.extend(executor.state_invariants(from.ty.i, contract));
+        }
+
+        let mut assumptions = vec![];
+        for c in &constraints {
+            assumptions.push(c.expr.clone());
+            assumptions.push(c.sym_to_const(executor.context));
+        }
+
+        Self {
+            executor,
+            func,
+            func_i,
+            contract,
+            z3_scope,
+            assumptions,
+            reports: vec![],
+            errors: vec![],
+            cycle: HashSet::new(),
+            calls: vec![],
+            overflows: HashSet::new(),
+            out_of_bounds: HashSet::new(),
+            transitions: HashMap::new(),
+            truncated: false,
+        }
+    }
+
+    /// Execute the statements along the path.
+    ///
+    /// # Returns
+    /// - Paths reaching the end of the statements.
+    fn execute(&mut self, stmts: &[Statement], path: Path<'ctx>) -> Vec<Path<'ctx>> {
+        let mut paths = vec![path];
+        for stmt in stmts {
+            let mut next = vec![];
+            for p in paths {
+                if p.skipped || p.broken {
+                    next.push(p);
+                } else {
+                    next.extend(self.statement(stmt, p));
+                }
+            }
+            // the remaining paths are not explored.
+            self.truncated |= next.len() > MAX_PATHS;
+            next.truncate(MAX_PATHS);
+            paths = next;
+        }
+        paths
+    }
+
+    fn statement(&mut self, stmt: &Statement, mut path: Path<'ctx>) -> Vec<Path<'ctx>> {
+        match stmt {
+            Statement::Variable(v) => {
+                if let ([name], Some(e)) = (v.names.as_slice(), &v.value) {
+                    let value = self.eval(e, &mut path);
+                    self.bind(&name.name, value, &mut path);
+                } else {
+                    let names: Vec<&str> = v.names.iter().map(|n| n.name.as_str()).collect();
+                    self.havoc(&names, &mut path);
+                }
+                vec![path]
+            }
+            Statement::Assign(a) => {
+                let value = self.eval(&a.value, &mut path);
+                let mut key = a.name.name.clone();
+                for i in &a.fields {
+                    key.push_str(&format!(".{}", i));
+                }
+                self.bind(&key, value, &mut path);
+                self.forget_fields(&key, &mut path);
+                vec![path]
+            }
+            Statement::IfElse(b) => {
+                let condition = self.eval(&b.condition, &mut path).and_then(|c| c.as_bool());
+                let mut then_path = path.clone();
+                let mut else_path = path;
+                if let Some(c) = condition {
+                    then_path.conditions.push(c.clone());
+                    else_path.conditions.push(c.not());
+                }
+                let mut paths = self.execute(&b.body, then_path);
+                paths.extend(self.execute(&b.else_part, else_path));
+                paths
+            }
+            Statement::ForLoop(l) => {
+                let mut names: Vec<&str> = l.var.names.iter().map(|n| n.name.as_str()).collect();
+                assigned_vars(&l.body, &mut names);
+                let Some(inv) = &l.invariant else {
+                    self.havoc(&names, &mut path);
+                    // the transitions in the body are checked for the arbitrary iteration.
+                    self.execute(&l.body, path.clone());
+                    return vec![path];
+                };
+
+                let mut entry = self.statement(&Statement::Variable(l.var.clone()), path);
+                let Some(mut path) = entry.pop() else {
+                    return vec![];
+                };
+                self.check_invariant(inv, &path, false);
+
+                // an arbitrary iteration starts in the state satisfying the invariant.
+                let mut iteration = path.clone();
+                self.havoc_exact(&names, &mut iteration);
+                self.assume_invariant(inv, &mut iteration);
+                if let Some(c) = self
+                    .eval(&l.condition, &mut iteration)
+                    .and_then(|c| c.as_bool())
+                {
+                    iteration.conditions.push(c);
+                }
+                for mut p in self.execute(&l.body, iteration) {
+                    // the loop is left before the incrementer.
+                    if !p.broken {
+                        p.skipped = false;
+                        let next = self.eval(&l.incrementer, &mut p);
+                        if let Some(var) = l.var.names.first() {
+                            self.bind(&var.name, next, &mut p);
+                        }
+                    }
+                    self.check_invariant(inv, &p, true);
+                }
+
+                self.havoc(&names, &mut path);
+                self.assume_invariant(inv, &mut path);
+                if !breaks(&l.body) {
+                    if let Some(c) = self.eval(&l.condition, &mut path).and_then(|c| c.as_bool()) {
+                        path.conditions.push(c.not());
+                    }
+                }
+                vec![path]
+            }
+            Statement::While(l) => {
+                let mut names = vec![];
+                assigned_vars(&l.body, &mut names);
+                let Some(inv) = &l.invariant else {
+                    self.havoc(&names, &mut path);
+                    self.execute(&l.body, path.clone());
+                    return vec![path];
+                };
+                self.check_invariant(inv, &path, false);
+
+                let mut iteration = path.clone();
+                self.havoc_exact(&names, &mut iteration);
+                self.assume_invariant(inv, &mut iteration);
+                if let Some(c) = self
+                    .eval(&l.condition, &mut iteration)
+                    .and_then(|c| c.as_bool())
+                {
+                    iteration.conditions.push(c);
+                }
+                for p in self.execute(&l.body, iteration) {
+                    self.check_invariant(inv, &p, true);
+                }
+
+                self.havoc(&names, &mut path);
+                self.assume_invariant(inv, &mut path);
+                // the loop left with `break` may still satisfy the condition.
+                if !breaks(&l.body) {
+                    if let Some(c) = self.eval(&l.condition, &mut path).and_then(|c| c.as_bool()) {
+                        path.conditions.push(c.not());
+                    }
+                }
+                vec![path]
+            }
+            Statement::Iterator(it) => {
+                let elements: Vec<&str> = it.names.iter().map(|n| n.name.as_str()).collect();
+                let mut names = elements.clone();
+                assigned_vars(&it.body, &mut names);
+                let Some(inv) = &it.invariant else {
+                    self.havoc(&names, &mut path);
+                    self.execute(&it.body, path.clone());
+                    return vec![path];
+                };
+
+                // the invariant holds for any element the iteration starts with.
+                let mut entry = path.clone();
+                self.havoc_exact(&elements, &mut entry);
+                self.check_invariant(inv, &entry, false);
+
+                let mut iteration = path.clone();
+                self.havoc_exact(&names, &mut iteration);
+                self.assume_invariant(inv, &mut iteration);
+                for mut p in self.execute(&it.body, iteration) {
+                    p.skipped = false;
+                    self.havoc_exact(&elements, &mut p);
+                    self.check_invariant(inv, &p, true);
+                }
+
+                self.havoc(&names, &mut path);
+                self.assume_invariant(inv, &mut path);
+                vec![path]
+            }
+            Statement::Match(m) => {
+                let value = self.eval(&m.expr, &mut path);
+                let mut paths = vec![];
+                // the wildcard arm is taken when none of the previous arms is.
+                let mut not_matched = vec![];
+                for arm in &m.arms {
+                    let mut arm_path = path.clone();
+                    match arm.variant {
+                        Some(v) if matches!(m.expr.ty(), TypeVariant::Enum(_)) => {
+                            let tag = Expression::Enum(UnaryExpression {
+                                loc: arm.loc.clone(),
+                                element: v,
+                                ty: m.expr.ty().clone(),
+                            });
+                            let tag = self.eval(&tag, &mut arm_path);
+                            if let (Some(value), Some(tag)) = (&value, tag) {
+                                let c = value._eq(&tag);
+                                not_matched.push(c.not());
+                                arm_path.conditions.push(c);
+                            }
+                        }
+                        // the presence of the option value is not modelled.
+                        Some(_) => {}
+                        None => arm_path.conditions.extend(not_matched.iter().cloned()),
+                    }
+                    let names: Vec<&str> = arm
+                        .bindings
+                        .iter()
+                        .filter_map(|(_, var)| self.func.scope.vars.get(var))
+                        .map(|v| v.ident.name.as_str())
+                        .collect();
+                    self.havoc(&names, &mut arm_path);
+                    paths.extend(self.execute(&arm.body, arm_path));
+                }
+                paths
+            }
+            Statement::Return(r) => {
+                if let Some(e) = &r.expr {
+                    self.eval(e, &mut path);
+                }
+                vec![]
+            }
+            Statement::StateTransition(e) => {
+                self.check_transition(e, stmt.loc(), &mut path);
+                vec![path]
+            }
+            Statement::Block(b) => self.execute(&b.statements, path),
+            Statement::Skip(_) => {
+                path.skipped = true;
+                vec![path]
+            }
+            Statement::Break(_) => {
+                path.broken = true;
+                vec![path]
+            }
+            Statement::Expression(Expression::BuiltinCall(call)) if call.func.is_assertion() => {
+                let condition = self
+                    .eval(&call.args[0], &mut path)
+                    .and_then(|c| c.as_bool());
+                if let Some(c) = condition {
+                    if call.func == BuiltinFunction::Assert {
+                        self.check_assertion(&c, &call.loc, &path);
+                    }
+                    // the call is reverted otherwise.
+                    path.conditions.push(c);
+                }
+                vec![path]
+            }
+            Statement::Expression(e) => {
+                self.eval(e, &mut path);
+                vec![path]
+            }
+            // the logs do not affect the state of the contract.
+            Statement::Emit(e) => {
+                for a in &e.args {
+                    self.eval(a, &mut path);
+                }
+                vec![path]
+            }
+            Statement::Error(_) => vec![path],
+        }
+    }
+
+    /// Check that the constraints of the state the contract is moved to hold for its new
+    /// fields.
+    fn check_transition(&mut self, e: &Expression, loc: &Span, path: &mut Path<'ctx>) {
+        // moving the existing state keeps its constraints.
+        let Expression::StructInit(init) = e else {
+            return;
+        };
+        let TypeVariant::State(sym) = &init.ty else {
+            return;
+        };
+        let invariants = self.executor.state_invariants(sym.i, self.contract);
+        if invariants.is_empty() {
+            return;
+        }
+
+        let ctx = self.executor.context;
+        let state_decl = &self.contract.states[sym.i];
+        let state_sym = GlobalSymbol::State(SymbolInfo::new(state_decl.loc.clone(), sym.i));
+        let Some(state_scope) = self
+            .executor
+            .declarations
+            .get(&state_sym)
+            .map(|d| d.scope.clone())
+        else {
+            return;
+        };
+
+        let mut fields = vec![];
+        for (f, arg) in state_decl.fields(self.contract).iter().zip(&init.args) {
+            let Some(value) = self.eval(arg, path) else {
+                continue;
+            };
+            if let Some(c) = state_scope.get(&f.name.name, type_to_sort(&f.ty.ty, ctx), ctx) {
+                fields.push((c, value));
+            }
+            // the domain of the mapping is known if it is taken from the variable.
+            let (TypeVariant::Mapping(m), Expression::Variable(var)) = (&f.ty.ty, arg) else {
+                continue;
+            };
+            let Some(name) = self
+                .func
+                .scope
+                .vars
+                .get(&var.element)
+                .map(|v| &v.ident.name)
+            else {
+                continue;
+            };
+            let domain = self.z3_scope.create_or_get(
+                &domain_name(name),
+                domain_sort(m, ctx),
+                ctx,
+                self.executor,
+            );
+            if let Some(c) = state_scope.get(&domain_name(&f.name.name), domain_sort(m, ctx), ctx) {
+                fields.push((c, path.substitute(&domain)));
+            }
+        }
+        let pairs: Vec<(&Dynamic<'ctx>, &Dynamic<'ctx>)> =
+            fields.iter().map(|(c, v)| (c, v)).collect();
+
+        let solver = self.executor.config.solver(ctx);
+        for a in self.assumptions.iter().chain(&path.conditions) {
+            solver.assert(a);
+        }
+        for c in &invariants {
+            solver.push();
+            solver.assert(&c.expr.substitute(&pairs).not());
+            let result = solver.check();
+            *self
+                .transitions
+                .entry((init.loc.clone(), c.loc.clone()))
+                .or_insert(true) &= matches!(result, SatResult::Unsat);
+            if matches!(result, SatResult::Sat) {
+                let message = if path.approximate {
+                    format!(
+                        "Constraint {} of state {} may be violated after the transition.",
+                        c.name().yellow(),
+                        state_decl.name.name.cyan()
+                    )
+                } else {
+                    format!(
+                        "Constraint {} of state {} is violated after the transition for some inputs.",
+                        c.name().yellow(),
+                        state_decl.name.name.cyan()
+                    )
+                };
+                let mut report = Report::ver_warning(loc.clone(), message);
+                report.note =
+                    String::from("The call will be reverted, consider constraining the inputs.");
+                report.additional_info.push(Report::ver_warning(
+                    c.loc.clone(),
+                    format!("This is a constraint {}.", c.name().yellow()),
+                ));
+                self.reports.push(report);
+            }
+            solver.pop(1);
+        }
+    }
+
+    /// Check that the asserted condition holds along the path.
+    fn check_assertion(&mut self, condition: &Bool<'ctx>, loc: &Span, path: &Path<'ctx>) {
+        let solver = self.executor.config.solver(self.executor.context);
+        for a in self.assumptions.iter().chain(&path.conditions) {
+            solver.assert(a);
+        }
+        solver.assert(&condition.not());
+        if !matches!(solver.check(), SatResult::Sat) {
+            return;
+        }
+
+        let message = if path.approximate {
+            "Assertion may fail."
+        } else {
+            "Assertion fails for some inputs."
+        };
+        let mut report = Report::ver_warning(loc.clone(), String::from(message));
+        report.note = String::from(
+            "The call will be reverted, consider constraining the inputs with `require`.",
+        );
+        self.reports.push(report);
+    }
+
+    /// Check that the constraints of the loop invariant hold along the path,
+    /// before the loop or after the iteration.
+    ///
+    /// Violations along the exact paths are errors, otherwise they may be spurious.
+    fn check_invariant(&mut self, inv: &Bounds, path: &Path<'ctx>, preserved: bool) {
+        let mut path = path.clone();
+        let constraints = self.invariant_constraints(inv, &mut path);
+        let ctx = self.executor.context;
+        let solver = self.executor.config.solver(ctx);
+        for a in self.assumptions.iter().chain(&path.conditions) {
+            solver.assert(a);
+        }
+        for (e, label, c) in constraints {
+            solver.push();
+            solver.assert(&c.not());
+            if matches!(solver.check(), SatResult::Sat) {
+                let name = label
+                    .map(|l| format!("\"{}\" ", l).yellow().to_string())
+                    .unwrap_or_default();
+                let fails = match (preserved, path.approximate) {
+                    (false, false) => "does not hold before the loop",
+                    (false, true) => "may not hold before the loop",
+                    (true, false) => "is not preserved by the iteration",
+                    (true, true) => "may not be preserved by the iteration",
+                };
+                let message = format!("Loop invariant {}{}.", name, fails);
+                if path.approximate {
+                    self.reports
+                        .push(Report::ver_warning(e.loc().clone(), message));
+                } else {
+                    let mut report = Report::ver_error(e.loc().clone(), message);
+                    report.note = String::from(
+                        "Consider weakening the invariant or constraining the inputs.",
+                    );
+                    self.errors.push(report);
+                }
+            }
+            solver.pop(1);
+        }
+    }
+
+    /// Assume the constraints of the loop invariant along the path.
+    fn assume_invariant(&mut self, inv: &Bounds, path: &mut Path<'ctx>) {
+        let constraints = self.invariant_constraints(inv, path);
+        path.conditions
+            .extend(constraints.into_iter().map(|(_, _, c)| c));
+    }
+
+    /// Constraints of the loop invariant evaluated along the path.
+    ///
+    /// The constraints the verifier can not reason about are skipped.
+    fn invariant_constraints<'b>(
+        &mut self,
+        inv: &'b Bounds,
+        path: &mut Path<'ctx>,
+    ) -> Vec<(&'b Expression, Option<&'b String>, Bool<'ctx>)> {
+        inv.exprs
+            .iter()
+            .zip(&inv.labels)
+            .filter(|(e, _)| is_supported(e))
+            .filter_map(|(e, label)| {
+                let c = self.eval(e, path)?.as_bool()?;
+                Some((e, label.as_ref(), c))
+            })
+            .collect()
+    }
+
+    /// Evaluate the expression along the path, collecting the recursive calls it makes.
+    ///
+    /// Expressions the verifier can not reason about are evaluated to the unknown values.
+    fn eval(&mut self, e: &Expression, path: &mut Path<'ctx>) -> Option<Dynamic<'ctx>> {
+        // the calls and the arithmetic of the branches are only evaluated under their conditions.
+        if let Expression::Conditional(c) = e {
+            let condition = self.eval(&c.condition, path).and_then(|v| v.as_bool());
+            for (branch, holds) in [(&c.then_expr, true), (&c.else_expr, false)] {
+                let mut branch_path = path.clone();
+                if let Some(cond) = &condition {
+                    branch_path
+                        .conditions
+                        .push(if holds { cond.clone() } else { cond.not() });
+                }
+                self.eval(branch, &mut branch_path);
+            }
+            return self.value(e, path);
+        }
+        if !self.cycle.is_empty() {
+            let mut calls = vec![];
+            calls_in(e, &mut calls);
+            for call in calls {
+                if !self.cycle.contains(&call.sym.i) {
+                    continue;
+                }
+                let args = call.args.iter().map(|a| self.value(a, path)).collect();
+                self.calls.push(RecursiveCall {
+                    loc: call.loc.clone(),
+                    callee: call.sym.i,
+                    args,
+                    conditions: path.conditions.clone(),
+                    approximate: path.approximate,
+                });
+            }
+        }
+        if self.executor.config.check_overflow {
+            self.check_overflow(e, path);
+        }
+        if self.executor.config.check_bounds {
+            self.check_bounds(e, path);
+        }
+        self.value(e, path)
+    }
+
+    /// Value of the expression along the path.
+    fn value(&mut self, e: &Expression, path: &mut Path<'ctx>) -> Option<Dynamic<'ctx>> {
+        if is_supported(e) {
+            let mut diagnostics = vec![];
+            let mut params = TransformParams {
+                ctx: self.executor.context,
+                z3_scope: &mut self.z3_scope,
+                scope: &self.func.scope,
+                contract: self.contract,
+                diagnostics: &mut diagnostics,
+                executor: self.executor,
+            };
+            if let Ok(value) = transform_expr(e, &mut params) {
+                return Some(path.substitute(&value.element));
+            }
+        }
+        self.unknown(e.ty(), path)
+    }
+
+    /// Fresh constant of the unknown value of the type.
+    fn unknown(&mut self, ty: &TypeVariant, path: &mut Path<'ctx>) -> Option<Dynamic<'ctx>> {
+        path.approximate = true;
+        if matches!(ty, TypeVariant::Function(_) | TypeVariant::Generic(_)) {
+            return None;
+        }
+        let (c, _) = self
+            .executor
+            .create_constant(&type_to_sort(ty, self.executor.context));
+        Some(c)
+    }
+
+    /// Assign the value to the variable along the path.
+    fn bind(&mut self, name: &str, value: Option<Dynamic<'ctx>>, path: &mut Path<'ctx>) {
+        let Some(value) = value else {
+            return;
+        };
+        let c = self.z3_scope.create_or_get(
+            name,
+            value.get_sort(),
+            self.executor.context,
+            self.executor,
+        );
+        path.values.insert(name.to_string(), (c, value));
+    }
+
+    /// Assign the unknown values to the variables constrained by the path,
+    /// so that the path stays exact.
+    fn havoc_exact(&mut self, names: &[&str], path: &mut Path<'ctx>) {
+        let approximate = path.approximate;
+        self.havoc(names, path);
+        path.approximate = approximate;
+    }
+
+    /// Assign the unknown values to the variables.
+    fn havoc(&mut self, names: &[&str], path: &mut Path<'ctx>) {
+        for name in names {
+            let Some(ty) = self.field_type(name) else {
+                continue;
+            };
+            let value = self.unknown(&ty, path);
+            self.bind(name, value, path);
+            self.forget_fields(name, path);
+        }
+    }
+
+    /// Assign the unknown values to the fields of the reassigned variable or field,
+    /// e.g. `p.0.1` once `p.0` is assigned.
+    fn forget_fields(&mut self, key: &str, path: &mut Path<'ctx>) {
+        let prefix = format!("{}.", key);
+        let fields: Vec<String> = self
+            .z3_scope
+            .consts
+            .keys()
+            .filter(|k| k.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for f in fields {
+            let Some(ty) = self.field_type(&f) else {
+                continue;
+            };
+            let value = self.unknown(&ty, path);
+            self.bind(&f, value, path);
+        }
+    }
+
+    /// Type of the variable or of its field given by the path, e.g. `p.0.1`,
+    /// or of the domain of the mapping, e.g. `m.dom`.
+    fn field_type(&self, key: &str) -> Option<TypeVariant> {
+        // domains of the mappings are the sets of their keys.
+        if let Some(mapping) = key.strip_suffix(DOMAIN_SUFFIX) {
+            let TypeVariant::Mapping(m) = self.field_type(mapping)? else {
+                return None;
+            };
+            return Some(TypeVariant::Set(m.from_ty));
+        }
+        let mut parts = key.split('.');
+        let name = parts.next()?;
+        let mut ty = self
+            .func
+            .scope
+            .vars
+            .values()
+            .find(|v| v.ident.name == name)
+            .map(|v| v.ty.clone())?;
+        for i in parts {
+            let i: usize = i.parse().ok()?;
+            ty = ty.fields(self.contract)?.get(i)?.ty.ty.clone();
+        }
+        Some(ty)
+    }
+}
+
+/// Do any loops in the statements have invariants?
+fn has_invariants(stmts: &[Statement]) -> bool {
+    stmts.iter().any(|stmt| {
+        match stmt {
+            Statement::ForLoop(l) => l.invariant.is_some() || has_invariants(&l.body),
+            Statement::While(l) => l.invariant.is_some() || has_invariants(&l.body),
+            Statement::Iterator(it) => it.invariant.is_some() || has_invariants(&it.body),
+            Statement::IfElse(b) => has_invariants(&b.body) || has_invariants(&b.else_part),
+            Statement::Match(m) => m.arms.iter().any(|a| has_invariants(&a.body)),
+            Statement::Block(b) => has_invariants(&b.statements),
+            _ => false,
+        }
+    })
+}
+
+/// Do the statements assert any conditions?
+fn has_assertions(stmts: &[Statement]) -> bool {
+    stmts.iter().any(|stmt| {
+        match stmt {
+            Statement::Expression(Expression::BuiltinCall(call)) => {
+                call.func == BuiltinFunction::Assert
+            }
+            Statement::ForLoop(l) => has_assertions(&l.body),
+            Statement::While(l) => has_assertions(&l.body),
+            Statement::Iterator(it) => has_assertions(&it.body),
+            Statement::IfElse(b) => has_assertions(&b.body) || has_assertions(&b.else_part),
+            Statement::Match(m) => m.arms.iter().any(|a| has_assertions(&a.body)),
+            Statement::Block(b) => has_assertions(&b.statements),
+            _ => false,
+        }
+    })
+}
+
+/// Do the statements leave the enclosing loop with `break`?
+fn breaks(stmts: &[Statement]) -> bool {
+    stmts.iter().any(|stmt| {
+        match stmt {
+            Statement::Break(_) => true,
+            Statement::IfElse(b) => breaks(&b.body) || breaks(&b.else_part),
+            Statement::Match(m) => m.arms.iter().any(|a| breaks(&a.body)),
+            Statement::Block(b) => breaks(&b.statements),
+            _ => false,
+        }
+    })
+}
+
+/// Names of the variables declared or assigned in the statements.
+fn assigned_vars<'a>(stmts: &'a [Statement], names: &mut Vec<&'a str>) {
+    for stmt in stmts {
+        match stmt {
+            Statement::Variable(v) => names.extend(v.names.iter().map(|n| n.name.as_str())),
+            Statement::Assign(a) => names.push(&a.name.name),
+            Statement::IfElse(b) => {
+                assigned_vars(&b.body, names);
+                assigned_vars(&b.else_part, names);
+            }
+            Statement::ForLoop(l) => {
+                names.extend(l.var.names.iter().map(|n| n.name.as_str()));
+                assigned_vars(&l.body, names);
+            }
+            Statement::While(l) => assigned_vars(&l.body, names),
+            Statement::Iterator(it) => {
+                names.extend(it.names.iter().map(|n| n.name.as_str()));
+                assigned_vars(&it.body, names);
+            }
+            Statement::Match(m) => m.arms.iter().for_each(|a| assigned_vars(&a.body, names)),
+            Statement::Block(b) => assigned_vars(&b.statements, names),
+            _ => {}
+        }
+    }
+}
+
+/// Can the expression be transformed into Z3 expression?
+fn is_supported(e: &Expression) -> bool {
+    match e {
+        Expression::Variable(u) => {
+            !matches!(u.ty, TypeVariant::Function(_) | TypeVariant::Generic(_))
+        }
+        Expression::Multiply(b)
+        | Expression::Divide(b)
+        | Expression::Modulo(b)
+        | Expression::Add(b)
+        | Expression::Subtract(b)
+        | Expression::Equal(b)
+        | Expression::NotEqual(b)
+        | Expression::Greater(b)
+        | Expression::Less(b)
+        | Expression::GreaterEq(b)
+        | Expression::LessEq(b)
+        | Expression::In(b)
+        | Expression::Or(b)
+        | Expression::And(b) => is_supported(&b.left) && is_supported(&b.right),
+        Expression::Not(u) => is_supported(&u.element),
+        Expression::Conditional(c) => {
+            is_supported(&c.condition) && is_supported(&c.then_expr) && is_supported(&c.else_expr)
+        }
+        Expression::MemberAccess(m) => is_supported(&m.expr),
+        Expression::List(u) => u.element.iter().all(is_supported),
+        Expression::BuiltinCall(c) => {
+            match c.func {
+                BuiltinFunction::Cast => c.returns.is_integer() && c.args.iter().all(is_supported),
+                BuiltinFunction::Pow | BuiltinFunction::Sqrt => c.args.iter().all(is_supported),
+                BuiltinFunction::Size => {
+                    matches!(c.args[0].ty(), TypeVariant::List(_) | TypeVariant::Set(_))
+                        && is_supported(&c.args[0])
+                }
+                BuiltinFunction::Contains
+                | BuiltinFunction::Add
+                | BuiltinFunction::Push
+                | BuiltinFunction::Insert
+                | BuiltinFunction::Remove
+                | BuiltinFunction::Union
+                | BuiltinFunction::Difference => {
+                    matches!(c.args[0].ty(), TypeVariant::List(_) | TypeVariant::Set(_))
+                        && c.args.iter().all(is_supported)
+                }
+                BuiltinFunction::All | BuiltinFunction::Exists => {
+                    is_supported(&c.args[0])
+                        && matches!(&c.args[1], Expression::Lambda(l) if is_supported(&l.body))
+                }
+                _ => false,
+            }
+        }
+        Expression::FunctionCall(_)
+        | Expression::InterfaceCall(_)
+        | Expression::Index(_)
+        | Expression::Slice(_)
+        | Expression::StructInit(_)
+        | Expression::VariantInit(_)
+        | Expression::Optional(_)
+        | Expression::Tuple(_)
+        | Expression::Lambda(_) => false,
+        Expression::Int(_)
+        | Expression::UInt(_)
+        | Expression::Float(_)
+        | Expression::Boolean(_)
+        | Expression::String(_)
+        | Expression::Char(_)
+        | Expression::Hex(_)
+        | Expression::Address(_)
+        | Expression::Enum(_) => true,
+    }
+}
@@ -0,0 +1,209 @@
+//! Bounds of the list indices in the function bodies.
+//!
+//! Lists are encoded as the sets of their elements, so their lengths are the values of
+//! the uninterpreted `size` function, which the bounds of the function can constrain,
+//! e.g. `st i < size(xs)`. The indices are checked to be within the lengths of their
+//! lists, and the slices to start before their ends, assuming the bounds of the function.
+//! Similarly, the items are inserted within the lists, and popped from non-empty ones.
+
+use folidity_diagnostics::Report;
+use folidity_semantics::{
+    ast::{
+        BuiltinCall,
+        Expression,
+        TypeVariant,
+    },
+    builtin::BuiltinFunction,
+};
+use z3::{
+    ast::{
+        Ast,
+        Bool,
+        Int,
+        Set,
+    },
+    SatResult,
+};
+
+use super::{
+    BodyExecutor,
+    Path,
+};
+use crate::transformer::type_to_sort;
+
+/// Indices, slices, insertions and pops in the expression, including the ones in the
+/// operands.
+///
+/// The bodies of lambdas are not evaluated with the expression.
+fn indexing_in<'a>(e: &'a Expression, ops: &mut Vec<&'a Expression>) {
+    match e {
+        Expression::Index(i) => {
+            indexing_in(&i.expr, ops);
+            indexing_in(&i.index, ops);
+            ops.push(e);
+        }
+        Expression::Slice(s) => {
+            indexing_in(&s.expr, ops);
+            s.from.iter().chain(&s.to).for_each(|a| indexing_in(a, ops));
+            ops.push(e);
+        }
+        Expression::Multiply(b)
+        | Expression::Divide(b)
+        | Expression::Modulo(b)
+        | Expression::Add(b)
+        | Expression::Subtract(b)
+        | Expression::Equal(b)
+        | Expression::NotEqual(b)
+        | Expression::Greater(b)
+        | Expression::Less(b)
+        | Expression::GreaterEq(b)
+        | Expression::LessEq(b)
+        | Expression::In(b)
+        | Expression::Or(b)
+        | Expression::And(b) => {
+            indexing_in(&b.left, ops);
+            indexing_in(&b.right, ops);
+        }
+        Expression::Not(u) => indexing_in(&u.element, ops),
+        Expression::FunctionCall(call) => call.args.iter().for_each(|a| indexing_in(a, ops)),
+        Expression::BuiltinCall(call) => {
+            call.args.iter().for_each(|a| indexing_in(a, ops));
+            let is_list = matches!(
+                call.args.first().map(|a| a.ty()),
+                Some(TypeVariant::List(_))
+            );
+            if is_list && matches!(call.func, BuiltinFunction::Insert | BuiltinFunction::Pop) {
+                ops.push(e);
+            }
+        }
+        Expression::InterfaceCall(call) => {
+            indexing_in(&call.app, ops);
+            call.args.iter().for_each(|a| indexing_in(a, ops));
+        }
+        Expression::MemberAccess(m) => indexing_in(&m.expr, ops),
+        Expression::StructInit(s) => s.args.iter().for_each(|a| indexing_in(a, ops)),
+        Expression::VariantInit(v) => v.args.iter().for_each(|a| indexing_in(a, ops)),
+        Expression::List(u) => u.element.iter().for_each(|a| indexing_in(a, ops)),
+        Expression::Optional(u) => u.element.iter().for_each(|a| indexing_in(a, ops)),
+        Expression::Tuple(u) => u.element.iter().for_each(|a| indexing_in(a, ops)),
+        Expression::Conditional(c) => {
+            indexing_in(&c.condition, ops);
+            indexing_in(&c.then_expr, ops);
+            indexing_in(&c.else_expr, ops);
+        }
+        Expression::Variable(_)
+        | Expression::Lambda(_)
+        | Expression::Int(_)
+        | Expression::UInt(_)
+        | Expression::Float(_)
+        | Expression::Boolean(_)
+        | Expression::String(_)
+        | Expression::Char(_)
+        | Expression::Hex(_)
+        | Expression::Address(_)
+        | Expression::Enum(_) => {}
+    }
+}
+
+impl<'a, 'ctx> BodyExecutor<'a, 'ctx> {
+    /// Check that the indices, slices, insertions and pops of the expression
+    /// are within the bounds of their lists along the path.
+    ///
+    /// Every operation is reported once, even if it is evaluated along several paths.
+    pub(super) fn check_bounds(&mut self, e: &Expression, path: &mut Path<'ctx>) {
+        let mut ops = vec![];
+        indexing_in(e, &mut ops);
+        for op in ops {
+            if self.out_of_bounds.contains(op.loc()) {
+                continue;
+            }
+            let Some(within) = self.within_bounds(op, path) else {
+                continue;
+            };
+
+            let solver = self.executor.config.solver(self.executor.context);
+            for a in self.assumptions.iter().chain(&path.conditions) {
+                solver.assert(a);
+            }
+            solver.assert(&within.not());
+            if !matches!(solver.check(), SatResult::Sat) {
+                continue;
+            }
+
+            let (what, bound) = match op {
+                Expression::Slice(_) => ("Slice", "out of bounds of the list"),
+                Expression::BuiltinCall(c) if c.func == BuiltinFunction::Pop => {
+                    ("Popped list", "empty")
+                }
+                _ => ("Index", "out of bounds of the list"),
+            };
+            let message = if path.approximate {
+                format!("{} may be {}.", what, bound)
+            } else {
+                format!("{} is {} for some inputs.", what, bound)
+            };
+            let mut report = Report::ver_warning(op.loc().clone(), message);
+            report.note = String::from(
+                "The call will be reverted, consider constraining the list with `st`, e.g. `st i < size(xs)`.",
+            );
+            self.reports.push(report);
+            self.out_of_bounds.insert(op.loc().clone());
+        }
+    }
+
+    /// Condition of the operation being within the bounds of its list:
+    /// `index < size(list)`, `from <= to <= size(list)`,
+    /// `index <= size(list)` for the insertion,
+    /// or `0 < size(list)` for the pop, unless the list is known to have elements.
+    fn within_bounds(&mut self, op: &Expression, path: &mut Path<'ctx>) -> Option<Bool<'ctx>> {
+        let ctx = self.executor.context;
+        let list = match op {
+            Expression::Index(i) => i.expr.as_ref(),
+            Expression::Slice(s) => s.expr.as_ref(),
+            Expression::BuiltinCall(c) => c.args.first()?,
+            _ => return None,
+        };
+        let size = Expression::BuiltinCall(BuiltinCall {
+            loc: list.loc().clone(),
+            func: BuiltinFunction::Size,
+            args: vec![list.clone()],
+            returns: TypeVariant::Uint,
+        });
+        let size = self.value(&size, path)?.as_int()?;
+
+        match op {
+            Expression::Index(i) => {
+                let index = self.value(&i.index, path)?.as_int()?;
+                Some(index.lt(&size))
+            }
+            Expression::Slice(s) => {
+                let from = match &s.from {
+                    Some(e) => self.value(e, path)?.as_int()?,
+                    None => Int::from_u64(ctx, 0),
+                };
+                let to = match &s.to {
+                    Some(e) => self.value(e, path)?.as_int()?,
+                    None => size.clone(),
+                };
+                Some(Bool::and(ctx, &[&from.le(&to), &to.le(&size)]))
+            }
+            Expression::BuiltinCall(c) if c.func == BuiltinFunction::Insert => {
+                let index = self.value(&c.args[1], path)?.as_int()?;
+                Some(index.le(&size))
+            }
+            // the list with any element is not empty, even if its size is unknown.
+            Expression::BuiltinCall(c) => {
+                let TypeVariant::List(item) = c.args[0].ty() else {
+                    return None;
+                };
+                let set = self.value(list, path)?.as_set()?;
+                let empty = Set::empty(ctx, &type_to_sort(item, ctx));
+                Some(Bool::or(
+                    ctx,
+                    &[&Int::from_u64(ctx, 0).lt(&size), &set._eq(&empty).not()],
+                ))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Overflow of the arithmetic in the function bodies.
+//!
+//! Integers are 64-bit on the AVM: `uint` is presented as `uint64`, and `int` as the sign
+//! and the `uint64` magnitude. The results of the additions, subtractions and
+//! multiplications are checked to fit in the width of their types, assuming the bounds of
+//! the function and that the parameters fit in their types.
+
+use folidity_diagnostics::Report;
+use folidity_semantics::ast::{
+    Expression,
+    TypeVariant,
+};
+use num_bigint::BigInt;
+use z3::{
+    ast::{
+        Bool,
+        Int,
+    },
+    SatResult,
+};
+
+use super::{
+    is_supported,
+    BodyExecutor,
+    Path,
+};
+use crate::transformer::type_to_sort;
+
+/// Range of the integer type on the AVM.
+fn avm_range(ty: &TypeVariant) -> Option<(BigInt, BigInt)> {
+    let max = BigInt::from(u64::MAX);
+    match ty {
+        TypeVariant::Uint => Some((BigInt::from(0), max)),
+        TypeVariant::Int => Some((-max.clone(), max)),
+        _ => {
+            let (min, max) = ty.int_range()?;
+            Some((min?, max?))
+        }
+    }
+}
+
+/// Additions, subtractions and multiplications in the expression,
+/// including the ones in the operands.
+///
+/// The bodies of lambdas are not evaluated with the expression.
+fn arithmetic_in<'a>(e: &'a Expression, ops: &mut Vec<&'a Expression>) {
+    match e {
+        Expression::Add(b) | Expression::Subtract(b) | Expression::Multiply(b) => {
+            arithmetic_in(&b.left, ops);
+            arithmetic_in(&b.right, ops);
+            if avm_range(&b.ty).is_some() {
+                ops.push(e);
+            }
+        }
+        Expression::Divide(b)
+        | Expression::Modulo(b)
+        | Expression::Equal(b)
+        | Expression::NotEqual(b)
+        | Expression::Greater(b)
+        | Expression::Less(b)
+        | Expression::GreaterEq(b)
+        | Expression::LessEq(b)
+        | Expression::In(b)
+        | Expression::Or(b)
+        | Expression::And(b) => {
+            arithmetic_in(&b.left, ops);
+            arithmetic_in(&b.right, ops);
+        }
+        Expression::Not(u) => arithmetic_in(&u.element, ops),
+        Expression::FunctionCall(call) => call.args.iter().for_each(|a| arithmetic_in(a, ops)),
+        Expression::BuiltinCall(call) => call.args.iter().for_each(|a| arithmetic_in(a, ops)),
+        Expression::InterfaceCall(call) => {
+            arithmetic_in(&call.app, ops);
+            call.args.iter().for_each(|a| arithmetic_in(a, ops));
+        }
+        Expression::MemberAccess(m) => arithmetic_in(&m.expr, ops),
+        Expression::StructInit(s) => s.args.iter().for_each(|a| arithmetic_in(a, ops)),
+        Expression::VariantInit(v) => v.args.iter().for_each(|a| arithmetic_in(a, ops)),
+        Expression::List(u) => u.element.iter().for_each(|a| arithmetic_in(a, ops)),
+        Expression::Optional(u) => u.element.iter().for_each(|a| arithmetic_in(a, ops)),
+        Expression::Tuple(u) => u.element.iter().for_each(|a| arithmetic_in(a, ops)),
+        Expression::Conditional(c) => {
+            arithmetic_in(&c.condition, ops);
+            arithmetic_in(&c.then_expr, ops);
+            arithmetic_in(&c.else_expr, ops);
+        }
+        Expression::Index(i) => {
+            arithmetic_in(&i.expr, ops);
+            arithmetic_in(&i.index, ops);
+        }
+        Expression::Slice(s) => {
+            arithmetic_in(&s.expr, ops);
+            s.from
+                .iter()
+                .chain(&s.to)
+                .for_each(|a| arithmetic_in(a, ops));
+        }
+        Expression::Variable(_)
+        | Expression::Lambda(_)
+        | Expression::Int(_)
+        | Expression::UInt(_)
+        | Expression::Float(_)
+        | Expression::Boolean(_)
+        | Expression::String(_)
+        | Expression::Char(_)
+        | Expression::Hex(_)
+        | Expression::Address(_)
+        | Expression::Enum(_) => {}
+    }
+}
+
+impl<'a, 'ctx> BodyExecutor<'a, 'ctx> {
+    /// Check that the arithmetic of the expression does not overflow along the path.
+    ///
+    /// Every operation is reported once, even if it is evaluated along several paths.
+    pub(super) fn check_overflow(&mut self, e: &Expression, path: &mut Path<'ctx>) {
+        let mut ops = vec![];
+        arithmetic_in(e, &mut ops);
+        for op in ops {
+            if self.overflows.contains(op.loc()) || !is_supported(op) {
+                continue;
+            }
+            let Some((min, max)) = avm_range(op.ty()) else {
+                continue;
+            };
+            let Some(value) = self.value(op, path).and_then(|v| v.as_int()) else {
+                continue;
+            };
+
+            let ranges = self.param_ranges();
+            let ctx = self.executor.context;
+            let solver = self.executor.config.solver(ctx);
+            for a in self
+                .assumptions
+                .iter()
+                .chain(&ranges)
+                .chain(&path.conditions)
+            {
+                solver.assert(a);
+            }
+            let outside = Bool::or(
+                ctx,
+                &[
+                    &value.lt(&Int::from_big_int(ctx, &min)),
+                    &value.gt(&Int::from_big_int(ctx, &max)),
+                ],
+            );
+            solver.assert(&outside);
+            if !matches!(solver.check(), SatResult::Sat) {
+                continue;
+            }
+
+            let ty = op.ty().display(self.contract);
+            let message = if path.approximate {
+                format!("Result of the operation may not fit in `{}`.", ty)
+            } else {
+                format!(
+                    "Result of the operation does not fit in `{}` for some inputs.",
+                    ty
+                )
+            };
+            let mut report = Report::ver_warning(op.loc().clone(), message);
+            report.note = String::from(
+                "The call will be reverted on the overflow, consider constraining the inputs with `st`.",
+            );
+            self.reports.push(report);
+            self.overflows.insert(op.loc().clone());
+        }
+    }
+
+    /// Ranges of the integer parameters of the function.
+    fn param_ranges(&mut self) -> Vec<Bool<'ctx>> {
+        let ctx = self.executor.context;
+        let func = self.func;
+        func.params
+            .values()
+            .filter_map(|p| {
+                let (min, max) = avm_range(&p.ty.ty)?;
+                let c = self
+                    .z3_scope
+                    .create_or_get(
+                        &p.name.name,
+                        type_to_sort(&p.ty.ty, ctx),
+                        ctx,
+                        self.executor,
+                    )
+                    .as_int()?;
+                Some(Bool::and(
+                    ctx,
+                    &[
+                        &c.ge(&Int::from_big_int(ctx, &min)),
+                        &c.le(&Int::from_big_int(ctx, &max)),
+                    ],
+                ))
+            })
+            .collect()
+    }
+}
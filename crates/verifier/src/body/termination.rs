@@ -0,0 +1,341 @@
+//! Termination of the recursive functions.
+//!
+//! A function calling itself, directly or through other functions, terminates if some
+//! measure over its parameters stays non-negative and decreases in every recursive call.
+//! The measure is given with `decreases`, or is inferred from the integer parameters for
+//! the functions calling only themselves.
+
+use std::collections::HashSet;
+
+use folidity_diagnostics::Report;
+use folidity_semantics::{
+    ast::{
+        Expression,
+        FunctionCall,
+    },
+    GlobalSymbol,
+    Span,
+    SymbolInfo,
+};
+use num_bigint::BigInt;
+use z3::{
+    ast::{
+        Ast,
+        Bool,
+        Dynamic,
+        Int,
+    },
+    SatResult,
+};
+
+use super::BodyExecutor;
+use crate::{
+    ast::Z3Scope,
+    transformer::{
+        transform_expr,
+        type_to_sort,
+        TransformParams,
+    },
+};
+
+/// Recursive call made along the execution path.
+#[derive(Debug, Clone)]
+pub(super) struct RecursiveCall<'ctx> {
+    pub loc: Span,
+    /// Index of the called function.
+    pub callee: usize,
+    /// Values of the arguments, `None` if they are unknown.
+    pub args: Vec<Option<Dynamic<'ctx>>>,
+    /// Conditions of the path the call is made along.
+    pub conditions: Vec<Bool<'ctx>>,
+    /// Has any value along the path been approximated?
+    pub approximate: bool,
+}
+
+/// Measure of the function over the constants of its parameters.
+struct Measure<'ctx> {
+    value: Int<'ctx>,
+    /// Constants of the parameters in the declaration order.
+    params: Vec<Dynamic<'ctx>>,
+    /// Least value of the measure.
+    lower: Int<'ctx>,
+}
+
+impl<'ctx> Measure<'ctx> {
+    /// Value of the measure for the arguments of the call.
+    fn at(&self, args: &[Option<Dynamic<'ctx>>]) -> Int<'ctx> {
+        let pairs: Vec<(&Dynamic<'ctx>, &Dynamic<'ctx>)> = self
+            .params
+            .iter()
+            .zip(args)
+            .filter_map(|(p, a)| Some((p, a.as_ref()?)))
+            .collect();
+        self.value.substitute(&pairs)
+    }
+}
+
+/// Function calls in the expression, including the ones in the arguments.
+///
+/// The calls in the bodies of lambdas are not made when the expression is evaluated.
+pub(super) fn calls_in<'a>(e: &'a Expression, calls: &mut Vec<&'a FunctionCall>) {
+    match e {
+        Expression::Multiply(b)
+        | Expression::Divide(b)
+        | Expression::Modulo(b)
+        | Expression::Add(b)
+        | Expression::Subtract(b)
+        | Expression::Equal(b)
+        | Expression::NotEqual(b)
+        | Expression::Greater(b)
+        | Expression::Less(b)
+        | Expression::GreaterEq(b)
+        | Expression::LessEq(b)
+        | Expression::In(b)
+        | Expression::Or(b)
+        | Expression::And(b) => {
+            calls_in(&b.left, calls);
+            calls_in(&b.right, calls);
+        }
+        Expression::Not(u) => calls_in(&u.element, calls),
+        Expression::FunctionCall(call) => {
+            calls.push(call);
+            call.args.iter().for_each(|a| calls_in(a, calls));
+        }
+        Expression::BuiltinCall(call) => call.args.iter().for_each(|a| calls_in(a, calls)),
+        Expression::InterfaceCall(call) => {
+            calls_in(&call.app, calls);
+            call.args.iter().for_each(|a| calls_in(a, calls));
+        }
+        Expression::MemberAccess(m) => calls_in(&m.expr, calls),
+        Expression::StructInit(s) => s.args.iter().for_each(|a| calls_in(a, calls)),
+        Expression::VariantInit(v) => v.args.iter().for_each(|a| calls_in(a, calls)),
+        Expression::List(u) => u.element.iter().for_each(|a| calls_in(a, calls)),
+        Expression::Optional(u) => u.element.iter().for_each(|a| calls_in(a, calls)),
+        Expression::Tuple(u) => u.element.iter().for_each(|a| calls_in(a, calls)),
+        Expression::Conditional(c) => {
+            calls_in(&c.condition, calls);
+            calls_in(&c.then_expr, calls);
+            calls_in(&c.else_expr, calls);
+        }
+        Expression::Index(i) => {
+            calls_in(&i.expr, calls);
+            calls_in(&i.index, calls);
+        }
+        Expression::Slice(s) => {
+            calls_in(&s.expr, calls);
+            s.from.iter().chain(&s.to).for_each(|a| calls_in(a, calls));
+        }
+        Expression::Variable(_)
+        | Expression::Lambda(_)
+        | Expression::Int(_)
+        | Expression::UInt(_)
+        | Expression::Float(_)
+        | Expression::Boolean(_)
+        | Expression::String(_)
+        | Expression::Char(_)
+        | Expression::Hex(_)
+        | Expression::Address(_)
+        | Expression::Enum(_) => {}
+    }
+}
+
+impl<'a, 'ctx> BodyExecutor<'a, 'ctx> {
+    /// Check that the measure of the function decreases in the recursive calls made along
+    /// the executed paths.
+    pub(super) fn check_termination(&mut self) {
+        if self.calls.is_empty() {
+            return;
+        }
+        let func = self.func;
+        if func.decreases.is_some() {
+            self.check_annotated();
+            return;
+        }
+
+        let mut others: Vec<usize> = self
+            .cycle
+            .iter()
+            .copied()
+            .filter(|i| *i != self.func_i)
+            .collect();
+        others.sort();
+        let others: Vec<String> = others
+            .iter()
+            .map(|i| format!("`{}`", self.contract.functions[*i].name.name))
+            .collect();
+        if !others.is_empty() {
+            let mut report = Report::ver_warning(
+                func.name.loc.clone(),
+                format!(
+                    "Function `{}` is mutually recursive with {}, its termination can not be checked.",
+                    func.name.name,
+                    others.join(", ")
+                ),
+            );
+            report.note = String::from(
+                "Mutual recursion terminates if every function of the cycle is annotated with a measure that stays non-negative and decreases in every call, e.g. `decreases n`.",
+            );
+            self.reports.push(report);
+            return;
+        }
+
+        // any integer parameter decreasing in all calls is a measure.
+        let candidates: Vec<Measure<'ctx>> = func
+            .params
+            .values()
+            .filter_map(|p| {
+                let (min, _) = p.ty.ty.int_range()?;
+                let ctx = self.executor.context;
+                let c = self.z3_scope.create_or_get(
+                    &p.name.name,
+                    type_to_sort(&p.ty.ty, ctx),
+                    ctx,
+                    self.executor,
+                );
+                let lower = Int::from_big_int(ctx, &min.unwrap_or_else(|| BigInt::from(0)));
+                Some(Measure {
+                    value: c.as_int()?,
+                    params: vec![],
+                    lower,
+                })
+            })
+            .collect();
+        let mut z3_scope = self.function_scope(self.func_i);
+        let params = self.param_consts(self.func_i, &mut z3_scope);
+        self.z3_scope = z3_scope;
+        let calls = self.calls.clone();
+        let terminates = candidates.into_iter().any(|mut m| {
+            m.params = params.clone();
+            calls.iter().all(|call| self.decreases(call, &m, &m))
+        });
+        if !terminates {
+            let mut report = Report::ver_warning(
+                func.name.loc.clone(),
+                format!(
+                    "Function `{}` may not terminate, none of its integer parameters decreases in every recursive call.",
+                    func.name.name
+                ),
+            );
+            report.note = String::from(
+                "Consider bounding the parameter from below with `st`, e.g. `st n >= 0`, or annotating the function with a measure that decreases in every recursive call, e.g. `decreases n - i`.",
+            );
+            self.reports.push(report);
+        }
+    }
+
+    /// Check the calls against the measures given with `decreases`.
+    ///
+    /// The calls of the functions without the measure are reported with their functions.
+    fn check_annotated(&mut self) {
+        let Some(measure) = self.measure(self.func_i) else {
+            return;
+        };
+        let mut reported: HashSet<Span> = HashSet::new();
+        for call in self.calls.clone() {
+            let Some(callee) = self.measure(call.callee) else {
+                continue;
+            };
+            if self.decreases(&call, &measure, &callee) || !reported.insert(call.loc.clone()) {
+                continue;
+            }
+            let name = &self.contract.functions[call.callee].name.name;
+            let message = if call.approximate {
+                format!(
+                    "Measure may not decrease in the recursive call of `{}`.",
+                    name
+                )
+            } else {
+                format!(
+                    "Measure does not decrease in the recursive call of `{}` for some inputs.",
+                    name
+                )
+            };
+            let mut report = Report::ver_warning(call.loc.clone(), message);
+            report.note = String::from(
+                "The measure must stay non-negative and decrease in every recursive call.",
+            );
+            self.reports.push(report);
+        }
+    }
+
+    /// Is the measure of the callee at the arguments non-negative and less than
+    /// the measure of the function at its entry?
+    fn decreases(
+        &mut self,
+        call: &RecursiveCall<'ctx>,
+        measure: &Measure<'ctx>,
+        callee: &Measure<'ctx>,
+    ) -> bool {
+        let ctx = self.executor.context;
+        let next = callee.at(&call.args);
+        let decreasing = Bool::and(ctx, &[&next.ge(&callee.lower), &next.lt(&measure.value)]);
+
+        let solver = self.executor.config.solver(ctx);
+        for a in self.assumptions.iter().chain(&call.conditions) {
+            solver.assert(a);
+        }
+        solver.assert(&decreasing.not());
+        matches!(solver.check(), SatResult::Unsat)
+    }
+
+    /// Measure of the function given with `decreases`.
+    fn measure(&mut self, i: usize) -> Option<Measure<'ctx>> {
+        let f = &self.contract.functions[i];
+        let e = f.decreases.as_ref()?;
+        let ctx = self.executor.context;
+        let mut z3_scope = self.function_scope(i);
+        let params = self.param_consts(i, &mut z3_scope);
+
+        let mut diagnostics = vec![];
+        let mut transform = TransformParams {
+            ctx,
+            z3_scope: &mut z3_scope,
+            scope: &f.scope,
+            contract: self.contract,
+            diagnostics: &mut diagnostics,
+            executor: self.executor,
+        };
+        let value = transform_expr(e, &mut transform);
+        if i == self.func_i {
+            self.z3_scope = z3_scope;
+        }
+        Some(Measure {
+            value: value.ok()?.element.as_int()?,
+            params,
+            lower: Int::from_i64(ctx, 0),
+        })
+    }
+
+    /// Scope of the constants of the function.
+    ///
+    /// The scope of the executed function is taken, and must be put back.
+    fn function_scope(&mut self, i: usize) -> Z3Scope {
+        if i == self.func_i {
+            return std::mem::take(&mut self.z3_scope);
+        }
+        let f = &self.contract.functions[i];
+        let sym = GlobalSymbol::Function(SymbolInfo::new(f.loc.clone(), i));
+        self.executor
+            .declarations
+            .get(&sym)
+            .map(|d| d.scope.clone())
+            .unwrap_or_default()
+    }
+
+    /// Constants of the parameters of the function in the scope.
+    fn param_consts(&mut self, i: usize, z3_scope: &mut Z3Scope) -> Vec<Dynamic<'ctx>> {
+        let ctx = self.executor.context;
+        self.contract.functions[i]
+            .params
+            .values()
+            .map(|p| {
+                z3_scope.create_or_get(
+                    &p.name.name,
+                    type_to_sort(&p.ty.ty, ctx),
+                    ctx,
+                    self.executor,
+                )
+            })
+            .collect()
+    }
+}
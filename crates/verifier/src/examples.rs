@@ -0,0 +1,312 @@
+//! Sampling of the concrete instances of models and states from their bounds.
+//!
+//! Valid instances satisfy all constraints of the declaration, invalid instances
+//! violate exactly one of them, so that the invariants can be sanity checked
+//! against the values they are expected to accept or reject.
+
+use folidity_semantics::{
+    ast::{
+        Param,
+        StateBody,
+        TypeVariant,
+    },
+    CompilationError,
+    ContractDefinition,
+    GlobalSymbol,
+    Span,
+};
+use num_bigint::BigInt;
+use z3::{
+    ast::{
+        Ast,
+        Bool,
+        Dynamic,
+        Int,
+    },
+    Context,
+    SatResult,
+};
+
+use crate::{
+    ast::{
+        Constraint,
+        DeclarationBounds,
+    },
+    executor::SymbolicExecutor,
+    transformer::type_to_sort,
+    SolverConfig,
+};
+
+/// Default number of valid instances sampled per declaration.
+pub const DEFAULT_EXAMPLES: usize = 3;
+
+/// Concrete instance sampled from the bounds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Example {
+    /// Values of the fields in the declaration order.
+    pub values: Vec<String>,
+    /// Location of the constraint violated by the instance, `None` for valid instances.
+    pub violates: Option<Span>,
+}
+
+/// Instances sampled from the bounds of the declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeclarationExamples {
+    /// Name of the declaration, e.g. `model User`.
+    pub name: String,
+    /// Names of the fields.
+    pub fields: Vec<String>,
+    /// Instances satisfying all constraints.
+    pub valid: Vec<Example>,
+    /// Instances violating one of the constraints, one per constraint.
+    pub invalid: Vec<Example>,
+}
+
+/// Sample up to `count` valid instances of every model and state with bounds,
+/// and an invalid instance per each of their constraints.
+///
+/// The solvers are configured by `config`.
+///
+/// # Errors
+/// - Bounds can not be transformed into constraints.
+pub fn generate_examples(
+    source: &ContractDefinition,
+    count: usize,
+    config: &SolverConfig,
+) -> Result<Vec<DeclarationExamples>, CompilationError> {
+    let context = Context::new(&config.z3_cfg());
+    config.validate(&context)?;
+    let mut executor = SymbolicExecutor::new(&context, config.clone());
+
+    let delays = executor.resolve_declarations(source);
+    let mut error = !executor.resolve_links(delays, source);
+    error |= !executor.resolve_bounds(source);
+    if error {
+        return Err(CompilationError::Formal(executor.diagnostics));
+    }
+
+    let mut examples = vec![];
+    for (sym, decl) in &executor.declarations {
+        let (name, fields, model) = match sym {
+            GlobalSymbol::Model(s) => {
+                let m = &source.models[s.i];
+                (format!("model {}", m.name.name), m.fields(source), None)
+            }
+            GlobalSymbol::State(s) => {
+                let st = &source.states[s.i];
+                // a state built on a model also carries the constraints of the model.
+                let model = match &st.body {
+                    Some(StateBody::Model(m)) => {
+                        executor.declarations.get(&GlobalSymbol::Model(m.clone()))
+                    }
+                    _ => None,
+                };
+                (format!("state {}", st.name.name), st.fields(source), model)
+            }
+            _ => continue,
+        };
+
+        let mut constraints: Vec<&Constraint> = decl.constraints.values().collect();
+        if let Some(m) = model {
+            constraints.extend(m.constraints.values());
+        }
+        if constraints.is_empty() {
+            continue;
+        }
+
+        let sampler = Sampler::new(decl, &fields, source, config, &context);
+        let valid = sampler
+            .sample(&constraints, None, count)
+            .into_iter()
+            .map(|values| {
+                Example {
+                    values,
+                    violates: None,
+                }
+            })
+            .collect();
+        let invalid = constraints
+            .iter()
+            .filter_map(|c| {
+                let values = sampler.sample(&constraints, Some(c.binding_sym), 1).pop()?;
+                Some(Example {
+                    values,
+                    violates: Some(c.loc.clone()),
+                })
+            })
+            .collect();
+
+        examples.push(DeclarationExamples {
+            name,
+            fields: fields.iter().map(|f| f.name.name.clone()).collect(),
+            valid,
+            invalid,
+        });
+    }
+
+    Ok(examples)
+}
+
+/// Sampler of the field values of the declaration.
+struct Sampler<'a, 'ctx> {
+    /// Z3 constants of the fields along with their types.
+    fields: Vec<(Dynamic<'ctx>, &'a TypeVariant)>,
+    contract: &'a ContractDefinition,
+    config: &'a SolverConfig,
+    context: &'ctx Context,
+}
+
+impl<'a, 'ctx> Sampler<'a, 'ctx> {
+    fn new(
+        decl: &DeclarationBounds<'ctx>,
+        fields: &'a [Param],
+        contract: &'a ContractDefinition,
+        config: &'a SolverConfig,
+        context: &'ctx Context,
+    ) -> Self {
+        let fields = fields
+            .iter()
+            .filter_map(|f| {
+                let sort = type_to_sort(&f.ty.ty, context);
+                let c = decl.scope.get(&f.name.name, sort, context)?;
+                Some((c, &f.ty.ty))
+            })
+            .collect();
+        Self {
+            fields,
+            contract,
+            config,
+            context,
+        }
+    }
+
+    /// Sample up to `count` distinct instances satisfying the constraints,
+    /// except the `violated` one which is negated.
+    fn sample(
+        &self,
+        constraints: &[&Constraint<'ctx>],
+        violated: Option<u32>,
+        count: usize,
+    ) -> Vec<Vec<String>> {
+        let solver = self.config.solver(self.context);
+        for c in constraints {
+            if Some(c.binding_sym) == violated {
+                // `k => e` is negated into `k && !e`.
+                solver.assert(&c.expr.not());
+            } else {
+                solver.assert(&c.expr);
+                solver.assert(&c.sym_to_const(self.context));
+            }
+        }
+        for (c, ty) in &self.fields {
+            if let Some(range) = self.type_range(c, ty) {
+                solver.assert(&range);
+            }
+        }
+
+        let mut samples = vec![];
+        while samples.len() < count {
+            if !matches!(solver.check(), SatResult::Sat) {
+                break;
+            }
+            let Some(model) = solver.get_model() else {
+                break;
+            };
+            let Some(values) = self
+                .fields
+                .iter()
+                .map(|(c, _)| model.eval(c, true))
+                .collect::<Option<Vec<Dynamic>>>()
+            else {
+                break;
+            };
+            samples.push(
+                values
+                    .iter()
+                    .zip(&self.fields)
+                    .map(|(v, (_, ty))| self.display(&v.to_string(), ty))
+                    .collect(),
+            );
+
+            // exclude the sampled instance to get a different one.
+            let distinct: Vec<Bool> = self
+                .fields
+                .iter()
+                .zip(&values)
+                .map(|((c, _), v)| c._eq(v).not())
+                .collect();
+            if distinct.is_empty() {
+                break;
+            }
+            solver.assert(&Bool::or(self.context, &distinct));
+        }
+        solver.reset();
+        samples
+    }
+
+    /// Constraint of the values representable by the type,
+    /// e.g. unsigned integers are non-negative.
+    fn type_range(&self, c: &Dynamic<'ctx>, ty: &TypeVariant) -> Option<Bool<'ctx>> {
+        let (min, max) = match ty {
+            TypeVariant::Enum(s) => {
+                let variants = self.contract.enums[s.i].variants.len();
+                (Some(BigInt::from(0)), Some(BigInt::from(variants) - 1))
+            }
+            TypeVariant::Char => (Some(BigInt::from(0)), Some(BigInt::from(0x10FFFF))),
+            _ => ty.int_range()?,
+        };
+        let c = c.as_int()?;
+        let mut bounds = vec![];
+        if let Some(min) = min {
+            bounds.push(c.ge(&Int::from_big_int(self.context, &min)));
+        }
+        if let Some(max) = max {
+            bounds.push(c.le(&Int::from_big_int(self.context, &max)));
+        }
+        Some(Bool::and(self.context, &bounds))
+    }
+
+    /// Render the value of Z3 model in the source syntax.
+    fn display(&self, value: &str, ty: &TypeVariant) -> String {
+        let value = z3_value(value);
+        let quoted = value.trim_matches('"');
+        match ty {
+            TypeVariant::Enum(s) => {
+                let decl = &self.contract.enums[s.i];
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| decl.variants.get_index(i))
+                    .map(|(v, _)| format!("{}.{}", decl.name.name, v))
+                    .unwrap_or(value)
+            }
+            TypeVariant::Char => {
+                value
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map(|c| format!("{:?}", c))
+                    .unwrap_or(value)
+            }
+            TypeVariant::String => format!("s\"{}\"", quoted),
+            TypeVariant::Address => format!("a\"{}\"", quoted),
+            TypeVariant::Hex => format!("hex\"{}\"", quoted),
+            _ => value,
+        }
+    }
+}
+
+/// Convert the numeric value of Z3 model from the S-expression, e.g. `(- (/ 1 2))` to
+/// `-1/2`.
+fn z3_value(value: &str) -> String {
+    let value = value.trim();
+    if let Some(inner) = value.strip_prefix("(- ").and_then(|v| v.strip_suffix(')')) {
+        return format!("-{}", z3_value(inner));
+    }
+    if let Some(inner) = value.strip_prefix("(/ ").and_then(|v| v.strip_suffix(')')) {
+        if let Some((n, d)) = inner.split_once(' ') {
+            return format!("{}/{}", z3_value(n), z3_value(d));
+        }
+    }
+    value.to_string()
+}
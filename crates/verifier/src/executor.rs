@@ -5,7 +5,14 @@ use folidity_diagnostics::{
     Report,
 };
 use folidity_semantics::{
-    ast::StateBody,
+    ast::{
+        Bounds,
+        Mapping,
+        Param,
+        StateBody,
+        TypeVariant,
+    },
+    symtable::Scope,
     ContractDefinition,
     DelayedDeclaration,
     GlobalSymbol,
@@ -30,34 +37,43 @@ use crate::{
     solver::{
         verify_constraint_blocks,
         verify_constraints,
+        Counterexample,
     },
-    transformer::{
-        type_to_sort,
-        TransformParams,
-    },
+    transformer::TransformParams,
     Diagnostics,
+    SolverConfig,
 };
 #[derive(Debug)]
 pub struct SymbolicExecutor<'ctx> {
     /// Global context of the executor.
     ///
     /// Sealed for save use.
-    context: &'ctx Context,
+    pub(crate) context: &'ctx Context,
+    /// Settings of the solvers.
+    pub(crate) config: SolverConfig,
     /// List of resolved declaration to verify.
     pub declarations: IndexMap<GlobalSymbol, DeclarationBounds<'ctx>>,
     /// Symbol counter to track boolean constants across the program.
     pub symbol_counter: u32,
     /// List of diagnostics messages associated with the verifier.
     pub diagnostics: Vec<Report>,
+    /// Indices of declarations that have been verified before and can be skipped.
+    pub verified: HashSet<usize>,
+    /// Constraints proven to hold after the transitions,
+    /// by the locations of the state instantiation and the constraint.
+    pub proven: HashSet<(Span, Span)>,
 }
 
 impl<'ctx> SymbolicExecutor<'ctx> {
-    pub fn new(context: &'ctx Context) -> Self {
+    pub fn new(context: &'ctx Context, config: SolverConfig) -> Self {
         Self {
             context,
+            config,
             declarations: IndexMap::new(),
             diagnostics: vec![],
             symbol_counter: 0,
+            verified: HashSet::new(),
+            proven: HashSet::new(),
         }
     }
 
@@ -83,12 +99,7 @@ impl<'ctx> SymbolicExecutor<'ctx> {
             }
             let fields = m.fields(contract);
             for var in &fields {
-                let _ = scope.create_or_get(
-                    &var.name.name,
-                    type_to_sort(&var.ty.ty, self.context),
-                    self.context,
-                    self,
-                );
+                scope.declare_field(&var.name.name, &var.ty.ty, self.context, self);
             }
 
             let decl = DeclarationBounds {
@@ -119,12 +130,7 @@ impl<'ctx> SymbolicExecutor<'ctx> {
             let mut add_delay = match &s.body {
                 Some(StateBody::Raw(fields)) => {
                     for f in fields {
-                        let _ = scope.create_or_get(
-                            &f.name.name,
-                            type_to_sort(&f.ty.ty, self.context),
-                            self.context,
-                            self,
-                        );
+                        scope.declare_field(&f.name.name, &f.ty.ty, self.context, self);
                     }
                     false
                 }
@@ -166,12 +172,7 @@ impl<'ctx> SymbolicExecutor<'ctx> {
 
             let mut scope = Z3Scope::default();
             for (_, p) in &f.params {
-                let _ = scope.create_or_get(
-                    &f.name.name,
-                    type_to_sort(&p.ty.ty, self.context),
-                    self.context,
-                    self,
-                );
+                scope.declare_field(&p.name.name, &p.ty.ty, self.context, self);
             }
 
             if f.state_bound.is_some() {
@@ -199,14 +200,26 @@ impl<'ctx> SymbolicExecutor<'ctx> {
     }
 
     /// Resolve State and Function delay to finalise its scopes.
-    pub fn resolve_links(&mut self, delays: Delays<'_>, contract: &ContractDefinition) {
+    ///
+    /// # Return
+    /// - true if execution did not have any errors.
+    pub fn resolve_links(&mut self, delays: Delays<'_>, contract: &ContractDefinition) -> bool {
+        let mut error = false;
         for s in &delays.state_delay {
             if let Some(StateBody::Model(model_sym)) = &s.decl.body {
                 let mut links: Vec<usize> = vec![];
-                let (m_sym, model_bound) = self
+                let Some((m_sym, model_bound)) = self
                     .declarations
                     .get_key_value(&GlobalSymbol::Model(model_sym.clone()))
-                    .expect("Model should exist.");
+                else {
+                    self.diagnostics.push(missing_declaration(
+                        &GlobalSymbol::Model(model_sym.clone()),
+                        &s.decl.loc,
+                        contract,
+                    ));
+                    error = true;
+                    continue;
+                };
 
                 let m_scope = model_bound.scope.consts.clone();
                 let mut m_sym = Some(m_sym.clone());
@@ -263,9 +276,12 @@ impl<'ctx> SymbolicExecutor<'ctx> {
             }
             self.declarations[m.i].links = links;
         }
+
+        !error
     }
 
-    /// Resolve expression in `st` blocks into concrete Z3 constraints.
+    /// Resolve expression in `st` blocks and the relations of the mapping fields
+    /// into concrete Z3 constraints.
     ///
     /// # Return
     /// - true if execution did not have any errors.
@@ -274,120 +290,46 @@ impl<'ctx> SymbolicExecutor<'ctx> {
         let mut diagnostics: Diagnostics = vec![];
 
         for (i, m) in contract.models.iter().enumerate() {
-            let Some(bounds) = &m.bounds else {
-                continue;
-            };
-            let mut constraints: IndexMap<u32, Constraint> = IndexMap::new();
-            let scope = &m.scope;
             let sym = GlobalSymbol::Model(SymbolInfo::new(m.loc.clone(), i));
-            let mut z3_scope = Z3Scope::default();
-            std::mem::swap(
-                &mut z3_scope,
-                &mut self.declarations.get_mut(&sym).expect("should exist").scope,
-            );
-            let mut params = TransformParams {
-                ctx: self.context,
-                z3_scope: &mut z3_scope,
-                scope,
+            let fields = m.fields(contract);
+            error |= !self.resolve_block(
+                &sym,
+                m.bounds.as_ref(),
+                &fields,
+                &m.scope,
                 contract,
-                diagnostics: &mut diagnostics,
-                executor: self,
-            };
-            for e in &bounds.exprs {
-                match Constraint::from_expr(e, &mut params) {
-                    Ok(c) => constraints.insert(c.binding_sym, c),
-                    Err(_) => {
-                        error = true;
-                        continue;
-                    }
-                };
-            }
-            std::mem::swap(
-                &mut z3_scope,
-                &mut self.declarations.get_mut(&sym).expect("should exist").scope,
+                &mut diagnostics,
             );
-            self.declarations
-                .get_mut(&sym)
-                .expect("should exist")
-                .constraints = constraints;
         }
 
         for (i, s) in contract.states.iter().enumerate() {
-            let Some(bounds) = &s.bounds else {
-                continue;
-            };
-            let mut constraints: IndexMap<u32, Constraint> = IndexMap::new();
-            let scope = &s.scope;
             let sym = GlobalSymbol::State(SymbolInfo::new(s.loc.clone(), i));
-            let mut z3_scope = Z3Scope::default();
-            std::mem::swap(
-                &mut z3_scope,
-                &mut self.declarations.get_mut(&sym).expect("should exist").scope,
-            );
-            let mut params = TransformParams {
-                ctx: self.context,
-                z3_scope: &mut z3_scope,
-                scope,
-                contract,
-                diagnostics: &mut diagnostics,
-                executor: self,
+            // relations of the model fields are constrained by the linked model.
+            let fields = match &s.body {
+                Some(StateBody::Raw(fields)) => fields.as_slice(),
+                _ => &[],
             };
-            for e in &bounds.exprs {
-                match Constraint::from_expr(e, &mut params) {
-                    Ok(c) => constraints.insert(c.binding_sym, c),
-                    Err(_) => {
-                        error = true;
-                        continue;
-                    }
-                };
-            }
-            std::mem::swap(
-                &mut z3_scope,
-                &mut self.declarations.get_mut(&sym).expect("should exist").scope,
+            error |= !self.resolve_block(
+                &sym,
+                s.bounds.as_ref(),
+                fields,
+                &s.scope,
+                contract,
+                &mut diagnostics,
             );
-            self.declarations
-                .get_mut(&sym)
-                .expect("should exist")
-                .constraints = constraints;
         }
 
         for (i, f) in contract.functions.iter().enumerate() {
-            let Some(bounds) = &f.bounds else {
-                continue;
-            };
-            let mut constraints: IndexMap<u32, Constraint> = IndexMap::new();
-            let scope = &f.scope;
             let sym = GlobalSymbol::Function(SymbolInfo::new(f.loc.clone(), i));
-            let mut z3_scope = Z3Scope::default();
-            std::mem::swap(
-                &mut z3_scope,
-                &mut self.declarations.get_mut(&sym).expect("should exist").scope,
-            );
-            let mut params = TransformParams {
-                ctx: self.context,
-                z3_scope: &mut z3_scope,
-                scope,
+            let params: Vec<Param> = f.params.values().cloned().collect();
+            error |= !self.resolve_block(
+                &sym,
+                f.bounds.as_ref(),
+                &params,
+                &f.scope,
                 contract,
-                diagnostics: &mut diagnostics,
-                executor: self,
-            };
-            for e in &bounds.exprs {
-                match Constraint::from_expr(e, &mut params) {
-                    Ok(c) => constraints.insert(c.binding_sym, c),
-                    Err(_) => {
-                        error = true;
-                        continue;
-                    }
-                };
-            }
-            std::mem::swap(
-                &mut z3_scope,
-                &mut self.declarations.get_mut(&sym).expect("should exist").scope,
+                &mut diagnostics,
             );
-            self.declarations
-                .get_mut(&sym)
-                .expect("should exist")
-                .constraints = constraints;
         }
 
         if error {
@@ -397,6 +339,73 @@ impl<'ctx> SymbolicExecutor<'ctx> {
         !error
     }
 
+    /// Resolve the constraints of a single declaration,
+    /// its `st` block followed by the relations of its mapping fields.
+    ///
+    /// # Return
+    /// - true if execution did not have any errors.
+    fn resolve_block(
+        &mut self,
+        sym: &GlobalSymbol,
+        bounds: Option<&Bounds>,
+        fields: &[Param],
+        scope: &Scope,
+        contract: &ContractDefinition,
+        diagnostics: &mut Diagnostics,
+    ) -> bool {
+        let mappings: Vec<(&str, &Mapping)> = fields
+            .iter()
+            .filter_map(|f| {
+                match &f.ty.ty {
+                    TypeVariant::Mapping(m) => Some((f.name.name.as_str(), m)),
+                    _ => None,
+                }
+            })
+            .collect();
+        if bounds.is_none() && mappings.is_empty() {
+            return true;
+        }
+
+        let mut error = false;
+        let mut constraints: IndexMap<u32, Constraint> = IndexMap::new();
+        let Some(decl) = self.declarations.get_mut(sym) else {
+            diagnostics.push(missing_declaration(sym, sym.loc(), contract));
+            return false;
+        };
+        let mut z3_scope = std::mem::take(&mut decl.scope);
+        let mut params = TransformParams {
+            ctx: self.context,
+            z3_scope: &mut z3_scope,
+            scope,
+            contract,
+            diagnostics,
+            executor: self,
+        };
+        if let Some(bounds) = bounds {
+            for (i, e) in bounds.exprs.iter().enumerate() {
+                let label = bounds.labels.get(i).cloned().flatten();
+                match Constraint::from_expr(e, label, &mut params) {
+                    Ok(c) => {
+                        constraints.insert(c.binding_sym, c);
+                    }
+                    Err(_) => error = true,
+                }
+            }
+        }
+        for (ident, m) in mappings {
+            match Constraint::from_relation(ident, m, &mut params) {
+                Ok(cs) => constraints.extend(cs.into_iter().map(|c| (c.binding_sym, c))),
+                Err(_) => error = true,
+            }
+        }
+        if let Some(decl) = self.declarations.get_mut(sym) {
+            decl.scope = z3_scope;
+            decl.constraints = constraints;
+        }
+
+        !error
+    }
+
     /// Verify individual blocks of constraints for satisfiability.
     ///
     /// # Return
@@ -405,26 +414,38 @@ impl<'ctx> SymbolicExecutor<'ctx> {
         let mut diagnostics: Diagnostics = vec![];
         let mut error = false;
 
-        for (sym, d) in &self.declarations {
-            if let Err(errs) = verify_constraints(
+        for (i, (sym, d)) in self.declarations.iter().enumerate() {
+            if self.verified.contains(&i) {
+                continue;
+            }
+            if let Err((errs, model)) = verify_constraints(
                 d.constraints
                     .values()
                     .collect::<Vec<&Constraint>>()
                     .as_slice(),
+                &self.config,
                 self.context,
             ) {
                 let mut notes: Diagnostics = vec![];
-                for (i, e) in errs.iter().enumerate() {
-                    let c = d.constraints.get(e).expect("constraints exists");
+                let constraints: Vec<&Constraint> =
+                    errs.iter().filter_map(|e| d.constraints.get(e)).collect();
+                let names: Vec<String> = constraints.iter().map(|c| c.name()).collect();
+                for (i, c) in constraints.iter().enumerate() {
                     notes.push(Report::ver_error(
                         c.loc.clone(),
                         format!(
-                            "This is a constraint {}. It contradicts {:?}",
-                            e.yellow(),
-                            &remove_element(&errs, i).red()
+                            "This is a constraint {}. It contradicts [{}]",
+                            names[i].yellow(),
+                            remove_element(&names, i).join(", ").red()
                         ),
                     ))
                 }
+                if let Some(note) = model.and_then(|m| {
+                    let c = d.constraints.get(&m.violated)?;
+                    counterexample_note(&m, c, &[&d.scope])
+                }) {
+                    notes.push(note);
+                }
 
                 diagnostics.push(Report::ver_error_with_extra(
                     d.loc.clone(),
@@ -454,7 +475,9 @@ impl<'ctx> SymbolicExecutor<'ctx> {
 
         let blocks = build_constraint_blocks(self);
         for b in &blocks {
-            if let Err(errs) = verify_constraint_blocks(b.as_slice(), self.context) {
+            if let Err((errs, model)) =
+                verify_constraint_blocks(b.as_slice(), &self.config, self.context)
+            {
                 error = true;
                 let mut notes: Diagnostics = vec![];
 
@@ -462,22 +485,35 @@ impl<'ctx> SymbolicExecutor<'ctx> {
                 let mut syms: Vec<GlobalSymbol> = syms.into_iter().collect();
                 syms.sort_by(|x, y| x.loc().start.cmp(&y.loc().start));
 
-                let consts: Vec<u32> = errs.iter().map(|x| x.0).collect();
-                for (i, (cid, g)) in errs.iter().enumerate() {
-                    let decl = &self.declarations.get(g).expect("should exist");
-                    let c = decl.constraints.get(cid).expect("constraints exists");
-                    let other_consts = remove_element(&consts, i);
-
+                let constraints: Vec<(&GlobalSymbol, &Constraint)> = errs
+                    .iter()
+                    .filter_map(|(cid, g)| {
+                        let c = self.declarations.get(g)?.constraints.get(cid)?;
+                        Some((g, c))
+                    })
+                    .collect();
+                let names: Vec<String> = constraints.iter().map(|(_, c)| c.name()).collect();
+                for (i, (g, c)) in constraints.iter().enumerate() {
                     notes.push(Report::ver_error(
                         c.loc.clone(),
                         format!(
-                            "This is a constraint {} in {}. It contradicts {:?}",
-                            cid.yellow().bold(),
+                            "This is a constraint {} in {}. It contradicts [{}]",
+                            names[i].yellow().bold(),
                             &symbol_name(g, contract).bold(),
-                            &other_consts.red(),
+                            remove_element(&names, i).join(", ").red(),
                         ),
                     ))
                 }
+                if let Some(note) = model.and_then(|m| {
+                    let (c, _) = b.iter().find(|(c, _)| c.binding_sym == m.violated)?;
+                    let scopes: Vec<&Z3Scope> = syms
+                        .iter()
+                        .filter_map(|g| self.declarations.get(g).map(|d| &d.scope))
+                        .collect();
+                    counterexample_note(&m, c, &scopes)
+                }) {
+                    notes.push(note);
+                }
 
                 let sym_strs: String = syms
                     .iter()
@@ -529,6 +565,57 @@ fn remove_element<T: Clone>(arr: &[T], i: usize) -> Vec<T> {
     result
 }
 
+/// Render the valuations of the named constants of the counterexample
+/// as a note at the violated constraint.
+fn counterexample_note(
+    model: &Counterexample,
+    violated: &Constraint,
+    scopes: &[&Z3Scope],
+) -> Option<Report> {
+    let mut values: Vec<String> = vec![];
+    for (id, value) in &model.values {
+        let Some(name) = scopes.iter().find_map(|s| {
+            s.consts
+                .iter()
+                .find(|(_, i)| *i == id)
+                .map(|(name, _)| name)
+        }) else {
+            continue;
+        };
+        let value = format!("{} = {}", name, value);
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    if values.is_empty() {
+        return None;
+    }
+
+    Some(Report::ver_error(
+        violated.loc.clone(),
+        format!(
+            "Counterexample: {} satisfies other constraints, but violates constraint {}.",
+            values.join(", ").yellow(),
+            violated.name().red()
+        ),
+    ))
+}
+
+/// Report the declaration missing from the executor.
+pub(crate) fn missing_declaration(
+    sym: &GlobalSymbol,
+    loc: &Span,
+    contract: &ContractDefinition,
+) -> Report {
+    Report::internal_error(
+        loc.clone(),
+        format!(
+            "{} is missing from the declarations of the verifier.",
+            symbol_name(sym, contract)
+        ),
+    )
+}
+
 /// Display a declaration name with the given symbol name.
 fn symbol_name(sym: &GlobalSymbol, contract: &ContractDefinition) -> String {
     match sym {
@@ -539,5 +626,14 @@ fn symbol_name(sym: &GlobalSymbol, contract: &ContractDefinition) -> String {
         GlobalSymbol::Function(s) => {
             format!("function {}", contract.functions[s.i].name.name.cyan())
         }
+        GlobalSymbol::Constant(s) => {
+            format!("constant {}", contract.constants[s.i].name.name.cyan())
+        }
+        GlobalSymbol::Alias(s) => format!("type {}", contract.aliases[s.i].name.name.cyan()),
+        GlobalSymbol::Interface(s) => {
+            format!("interface {}", contract.interfaces[s.i].name.name.cyan())
+        }
+        GlobalSymbol::Event(s) => format!("event {}", contract.events[s.i].name.name.cyan()),
+        GlobalSymbol::Test(s) => format!("test {}", contract.tests[s.i].name.cyan()),
     }
 }
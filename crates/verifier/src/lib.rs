@@ -1,18 +1,36 @@
+pub use examples::{
+    generate_examples,
+    DeclarationExamples,
+    Example,
+    DEFAULT_EXAMPLES,
+};
 pub use executor::SymbolicExecutor;
 use folidity_diagnostics::Report;
 use folidity_semantics::{
     CompilationError,
     ContractDefinition,
+    GlobalSymbol,
     Runner,
+    Span,
 };
+use links::declaration_components;
+use rayon::prelude::*;
+use std::collections::HashSet;
 use z3::{
+    set_global_param,
     Config,
     Context,
+    Solver,
+    Tactic,
 };
 
 mod ast;
+mod body;
+mod examples;
 mod executor;
 mod links;
+mod reachability;
+mod refinement;
 mod solver;
 mod transformer;
 
@@ -21,13 +39,93 @@ mod tests;
 
 type Diagnostics = Vec<Report>;
 
+/// Default timeout for constraint solving in milliseconds.
+pub const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+/// Settings of the Z3 solver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolverConfig {
+    /// Timeout for constraint solving in milliseconds.
+    pub timeout_ms: u64,
+    /// Seed of the random choices made by the solver, for reproducible results.
+    pub seed: Option<u32>,
+    /// Tactic the solvers are built from, e.g. `qfnia`, the default solver otherwise.
+    pub tactic: Option<String>,
+    /// Solve the constraints in parallel.
+    pub parallel: bool,
+    /// Check that the arithmetic of the function bodies does not overflow.
+    pub check_overflow: bool,
+    /// Check that the indices of the lists are within their bounds,
+    /// and that the popped lists are not empty.
+    pub check_bounds: bool,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            seed: None,
+            tactic: None,
+            parallel: false,
+            check_overflow: false,
+            check_bounds: false,
+        }
+    }
+}
+
+impl SolverConfig {
+    /// Create config for the Z3 context.
+    ///
+    /// The seed and the parallel mode are global parameters of Z3,
+    /// so they are applied to all contexts created afterwards.
+    pub fn z3_cfg(&self) -> Config {
+        if let Some(seed) = self.seed {
+            set_global_param("smt.random_seed", &seed.to_string());
+            set_global_param("sat.random_seed", &seed.to_string());
+        }
+        if self.parallel {
+            set_global_param("parallel.enable", "true");
+        }
+        self.context_cfg()
+    }
+
+    /// Create config for the Z3 context without touching the global parameters.
+    fn context_cfg(&self) -> Config {
+        let mut cfg = Config::new();
+        cfg.set_model_generation(true);
+        cfg.set_timeout_msec(self.timeout_ms);
+        cfg
+    }
+
+    /// Create the solver in the context.
+    pub fn solver<'ctx>(&self, context: &'ctx Context) -> Solver<'ctx> {
+        match &self.tactic {
+            Some(t) => Tactic::new(context, t).solver(),
+            None => Solver::new(context),
+        }
+    }
+
+    /// Check that the tactic is known to Z3.
+    ///
+    /// # Errors
+    /// - The tactic is not known.
+    fn validate(&self, context: &Context) -> Result<(), CompilationError> {
+        let Some(tactic) = &self.tactic else {
+            return Ok(());
+        };
+        if Tactic::list_all(context).any(|t| t.is_ok_and(|t| t == tactic)) {
+            return Ok(());
+        }
+        Err(CompilationError::Formal(vec![Report::ver_error(
+            0..0,
+            format!("Tactic `{}` is not known to the solver.", tactic),
+        )]))
+    }
+}
+
 /// Create config for the Z3 context.
 pub fn z3_cfg() -> Config {
-    let mut cfg = Config::new();
-    cfg.set_model_generation(true);
-    // 10s timeout for constraint solving.
-    cfg.set_timeout_msec(10_000);
-    cfg
+    SolverConfig::default().z3_cfg()
 }
 
 impl<'ctx> Runner<ContractDefinition, ()> for SymbolicExecutor<'ctx> {
@@ -35,28 +133,140 @@ impl<'ctx> Runner<ContractDefinition, ()> for SymbolicExecutor<'ctx> {
     where
         Self: std::marker::Sized,
     {
-        let context = Context::new(&z3_cfg());
+        verify_incremental(source, &SolverConfig::default(), |_| false).map(|_| ())
+    }
+}
 
-        let mut executor = SymbolicExecutor::new(&context);
+/// Outcome of the successful verification.
+#[derive(Debug, Default)]
+pub struct Verification {
+    /// Components of linked declarations without warnings, so that they can be cached.
+    pub components: Vec<Vec<GlobalSymbol>>,
+    /// Transitions of the functions that may violate the constraints of the states,
+    /// models not refining their parents, unreachable states and dead ends.
+    pub warnings: Diagnostics,
+    /// Constraints proven to hold after the transitions,
+    /// by the locations of the state instantiation and the constraint.
+    ///
+    /// The transitions of the skipped components are not included.
+    pub proven: HashSet<(Span, Span)>,
+}
 
-        let mut err = false;
-        let delays = executor.resolve_declarations(source);
-        executor.resolve_links(delays, source);
+/// Verify the contract skipping the components of linked declarations
+/// for which `is_verified` returns `true`.
+///
+/// The solvers are configured by `config`.
+pub fn verify_incremental<F>(
+    source: &ContractDefinition,
+    config: &SolverConfig,
+    is_verified: F,
+) -> Result<Verification, CompilationError>
+where
+    F: Fn(&[GlobalSymbol]) -> bool,
+{
+    let context = Context::new(&config.z3_cfg());
+    config.validate(&context)?;
 
-        err |= !executor.resolve_bounds(source);
+    let mut executor = SymbolicExecutor::new(&context, config.clone());
 
-        err |= !executor.verify_individual_blocks(source);
+    let mut err = false;
+    let delays = executor.resolve_declarations(source);
+    err |= !executor.resolve_links(delays, source);
 
-        // report errors in individual blocks earlier to avoid catching them in linked blocks.
-        if err {
-            return Err(CompilationError::Formal(executor.diagnostics));
-        }
+    err |= !executor.resolve_bounds(source);
 
-        err = !executor.verify_linked_blocks(source);
-        if err {
-            return Err(CompilationError::Formal(executor.diagnostics));
+    let component_ids = declaration_components(&executor);
+    let components: Vec<Vec<GlobalSymbol>> = component_ids
+        .iter()
+        .map(|c| {
+            c.iter()
+                .filter_map(|i| executor.declarations.get_index(*i))
+                .map(|(sym, _)| sym.clone())
+                .collect()
+        })
+        .collect();
+    for (ids, syms) in component_ids.iter().zip(&components) {
+        if is_verified(syms) {
+            executor.verified.extend(ids);
         }
+    }
+
+    let diagnostics = verify_blocks_in_parallel(
+        source,
+        config,
+        &executor.verified,
+        executor.declarations.len(),
+    );
+    err |= !diagnostics.is_empty();
+    executor.diagnostics.extend(diagnostics);
+
+    // report errors in individual blocks earlier to avoid catching them in linked blocks.
+    if err {
+        return Err(CompilationError::Formal(executor.diagnostics));
+    }
+
+    err = !executor.verify_linked_blocks(source);
+    if err {
+        return Err(CompilationError::Formal(executor.diagnostics));
+    }
 
-        Ok(())
+    // components with warnings are verified again, so that the warnings are not lost.
+    let (warned, mut warnings): (Vec<GlobalSymbol>, Diagnostics) =
+        executor.verify_function_bodies(source).into_iter().unzip();
+    if !executor.diagnostics.is_empty() {
+        return Err(CompilationError::Formal(executor.diagnostics));
     }
+    warnings.extend(executor.verify_refinements(source));
+    warnings.extend(executor.verify_reachability(source));
+    let components = components
+        .into_iter()
+        .filter(|c| !c.iter().any(|s| warned.contains(s)))
+        .collect();
+
+    Ok(Verification {
+        components,
+        warnings,
+        proven: executor.proven,
+    })
+}
+
+/// Verify the individual blocks of `count` declarations in parallel,
+/// skipping the `verified` ones.
+///
+/// Z3 contexts can not be shared between threads, so every thread constructs the
+/// constraints in its own context and verifies its chunk of the declarations.
+/// Constants are numbered in the same order in every context,
+/// hence the reports are the same as of the sequential verification.
+///
+/// # Returns
+/// - Reports of the declarations with unsatisfiable constraints in the declaration order.
+fn verify_blocks_in_parallel(
+    source: &ContractDefinition,
+    config: &SolverConfig,
+    verified: &HashSet<usize>,
+    count: usize,
+) -> Diagnostics {
+    let pending: Vec<usize> = (0..count).filter(|i| !verified.contains(i)).collect();
+    if pending.is_empty() {
+        return vec![];
+    }
+    let chunk_size = pending.len().div_ceil(rayon::current_num_threads());
+
+    let reports: Vec<Diagnostics> = pending
+        .par_chunks(chunk_size)
+        .map(|chunk| {
+            let context = Context::new(&config.context_cfg());
+            let mut executor = SymbolicExecutor::new(&context, config.clone());
+            let delays = executor.resolve_declarations(source);
+            executor.resolve_links(delays, source);
+            executor.resolve_bounds(source);
+            // errors of the construction are reported by the calling thread.
+            executor.diagnostics.clear();
+
+            executor.verified = (0..count).filter(|i| !chunk.contains(i)).collect();
+            executor.verify_individual_blocks(source);
+            executor.diagnostics
+        })
+        .collect();
+    reports.into_iter().flatten().collect()
 }
@@ -16,15 +16,7 @@ type LinkGraph = Graph<usize, usize, Undirected, usize>;
 fn find_link_components(executor: &SymbolicExecutor) -> Vec<Vec<usize>> {
     let mut edges: HashSet<(usize, usize)> = HashSet::new();
 
-    for (sym, d) in &executor.declarations {
-        if d.links.is_empty() {
-            continue;
-        }
-        let origin = executor
-            .declarations
-            .get_index_of(sym)
-            .expect("should exist");
-
+    for (origin, d) in executor.declarations.values().enumerate() {
         for l in &d.links {
             edges.insert((origin, *l));
         }
@@ -41,15 +33,34 @@ fn find_link_components(executor: &SymbolicExecutor) -> Vec<Vec<usize>> {
     components
 }
 
+/// Split all declarations into the linked components,
+/// declarations without any links form their own components.
+pub fn declaration_components(executor: &SymbolicExecutor) -> Vec<Vec<usize>> {
+    let mut components = find_link_components(executor);
+    let linked: HashSet<usize> = components.iter().flatten().copied().collect();
+    for i in 0..executor.declarations.len() {
+        if !linked.contains(&i) {
+            components.push(vec![i]);
+        }
+    }
+    components
+}
+
 pub fn build_constraint_blocks<'ctx>(
     executor: &mut SymbolicExecutor<'ctx>,
 ) -> Vec<Vec<(Constraint<'ctx>, GlobalSymbol)>> {
     let components = find_link_components(executor);
     let mut blocks = vec![];
     for decls in &components {
+        // skip the components that have been verified before.
+        if decls.iter().all(|i| executor.verified.contains(i)) {
+            continue;
+        }
         let mut constraints: Vec<(Constraint, GlobalSymbol)> = vec![];
         for i in decls {
-            let (sym, decl) = executor.declarations.get_index(*i).expect("should exist");
+            let Some((sym, decl)) = executor.declarations.get_index(*i) else {
+                continue;
+            };
 
             for (_, c) in decl.constraints.clone() {
                 constraints.push((c, sym.clone()));
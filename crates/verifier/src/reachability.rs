@@ -0,0 +1,155 @@
+//! Reachability of the states.
+//!
+//! The transitions of the contract are built from the state bounds of the functions,
+//! a transition is feasible if the constraints of the function and of the states
+//! it transitions between are satisfiable together.
+//! The states are reached from the ones the `@init` function transitions to.
+
+use std::collections::HashSet;
+
+use folidity_diagnostics::{
+    Paint,
+    Report,
+};
+use folidity_semantics::{
+    ContractDefinition,
+    GlobalSymbol,
+    SymbolInfo,
+};
+use z3::SatResult;
+
+use crate::executor::SymbolicExecutor;
+
+/// Feasible transition between the states.
+#[derive(Debug, Clone, PartialEq)]
+struct Transition {
+    /// State the transition is made from, any state if `None`.
+    from: Option<usize>,
+    /// State the transition is made to.
+    to: usize,
+    /// Is it made by the `@init` function?
+    init: bool,
+}
+
+impl<'ctx> SymbolicExecutor<'ctx> {
+    /// Check that every state is reachable from the ones the `@init` function transitions
+    /// to, and that the contract can transition out of every reachable state unless it
+    /// is marked with `@final`.
+    ///
+    /// Contracts without the `@init` function are not checked.
+    ///
+    /// # Returns
+    /// - Warnings of the unreachable states and of the dead ends.
+    pub fn verify_reachability(&self, contract: &ContractDefinition) -> Vec<Report> {
+        if contract.states.is_empty() || !contract.functions.iter().any(|f| f.is_init) {
+            return vec![];
+        }
+
+        let mut targeted = HashSet::new();
+        let mut transitions = vec![];
+        for (i, f) in contract.functions.iter().enumerate() {
+            let Some(bound) = &f.state_bound else {
+                continue;
+            };
+            // the constructor is called before the contract is in any state.
+            let from = bound.from.as_ref().filter(|_| !f.is_init).map(|s| s.ty.i);
+            for to in &bound.to {
+                targeted.insert(to.ty.i);
+                if self.is_feasible(i, from, to.ty.i, contract) {
+                    transitions.push(Transition {
+                        from,
+                        to: to.ty.i,
+                        init: f.is_init,
+                    });
+                }
+            }
+        }
+
+        let mut reachable = HashSet::new();
+        let mut pending: Vec<usize> = transitions
+            .iter()
+            .filter(|t| t.init)
+            .map(|t| t.to)
+            .collect();
+        while let Some(s) = pending.pop() {
+            if !reachable.insert(s) {
+                continue;
+            }
+            pending.extend(
+                transitions
+                    .iter()
+                    .filter(|t| !t.init && t.from.is_none_or(|from| from == s))
+                    .map(|t| t.to),
+            );
+        }
+
+        let mut reports = vec![];
+        for (i, s) in contract.states.iter().enumerate() {
+            // states without any transitions to them are reported by the semantic analysis.
+            if targeted.contains(&i) && !reachable.contains(&i) {
+                let mut report = Report::ver_warning(
+                    s.name.loc.clone(),
+                    format!(
+                        "State {} is unreachable from the initial state.",
+                        s.name.name.cyan()
+                    ),
+                );
+                report.note = String::from(
+                    "The constraints of the transitions to it can not be satisfied, or they start in the unreachable states.",
+                );
+                reports.push(report);
+            } else if reachable.contains(&i) && !s.is_final {
+                let is_sink = !transitions
+                    .iter()
+                    .any(|t| !t.init && t.from.is_none_or(|from| from == i));
+                if is_sink {
+                    let mut report = Report::ver_warning(
+                        s.name.loc.clone(),
+                        format!(
+                            "State {} is a dead end, the contract can not transition out of it.",
+                            s.name.name.cyan()
+                        ),
+                    );
+                    report.note = String::from(
+                        "Consider marking the state with `@final` if the contract is not expected to leave it.",
+                    );
+                    reports.push(report);
+                }
+            }
+        }
+        reports
+    }
+
+    /// Can the constraints of the function be satisfied along with the constraints of the
+    /// states it transitions between?
+    ///
+    /// The transition is assumed to be feasible if the solver can not decide.
+    fn is_feasible(
+        &self,
+        func: usize,
+        from: Option<usize>,
+        to: usize,
+        contract: &ContractDefinition,
+    ) -> bool {
+        let f = &contract.functions[func];
+        let sym = GlobalSymbol::Function(SymbolInfo::new(f.loc.clone(), func));
+        let mut constraints = vec![];
+        if let Some(decl) = self.declarations.get(&sym) {
+            constraints.extend(decl.constraints.values().cloned());
+        }
+        if let Some(from) = from {
+            constraints.extend(self.state_invariants(from, contract));
+        }
+        // the fields of the same state share the constants.
+        if from != Some(to) {
+            constraints.extend(self.state_invariants(to, contract));
+        }
+
+        let solver = self.config.solver(self.context);
+        for c in &constraints {
+            solver.assert(&c.expr);
+            solver.assert(&c.sym_to_const(self.context));
+        }
+        !matches!(solver.check(), SatResult::Unsat)
+    }
+}
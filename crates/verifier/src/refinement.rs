@@ -0,0 +1,136 @@
+//! Refinement of the models.
+//!
+//! The model inheriting from the parent one refines its constraints,
+//! so every instance of the child model is expected to satisfy the constraints
+//! of the parent model as well.
+
+use folidity_diagnostics::{
+    Paint,
+    Report,
+};
+use folidity_semantics::{
+    ast::TypeVariant,
+    ContractDefinition,
+    GlobalSymbol,
+    SymbolInfo,
+};
+use z3::{
+    ast::{
+        Ast,
+        Dynamic,
+    },
+    SatResult,
+};
+
+use crate::{
+    ast::{
+        domain_name,
+        domain_sort,
+        DeclarationBounds,
+    },
+    executor::SymbolicExecutor,
+    transformer::type_to_sort,
+};
+
+impl<'ctx> SymbolicExecutor<'ctx> {
+    /// Check that the constraints of every model with its own `st` block
+    /// imply the constraints of its parent.
+    ///
+    /// The parent constraints are checked one by one, and the constraint is assumed
+    /// to be implied if the solver can not decide.
+    ///
+    /// # Returns
+    /// - Warnings of the parent constraints the child model may violate.
+    pub fn verify_refinements(&self, contract: &ContractDefinition) -> Vec<Report> {
+        let mut reports = vec![];
+        for (i, m) in contract.models.iter().enumerate() {
+            let (Some(parent), Some(bounds)) = (&m.parent, &m.bounds) else {
+                continue;
+            };
+            let child_sym = GlobalSymbol::Model(SymbolInfo::new(m.loc.clone(), i));
+            let parent_sym = GlobalSymbol::Model(parent.clone());
+            let (Some(child_decl), Some(parent_decl)) = (
+                self.declarations.get(&child_sym),
+                self.declarations.get(&parent_sym),
+            ) else {
+                continue;
+            };
+            let parent_model = &contract.models[parent.i];
+
+            // the fields of the parent have their own constants in its declaration.
+            let fields = self.shared_fields(parent.i, parent_decl, child_decl, contract);
+            let pairs: Vec<(&Dynamic<'ctx>, &Dynamic<'ctx>)> =
+                fields.iter().map(|(p, c)| (p, c)).collect();
+
+            let solver = self.config.solver(self.context);
+            for c in child_decl.constraints.values() {
+                solver.assert(&c.expr);
+                solver.assert(&c.sym_to_const(self.context));
+            }
+            for c in parent_decl.constraints.values() {
+                solver.push();
+                solver.assert(&c.expr.substitute(&pairs).not());
+                let result = solver.check();
+                solver.pop(1);
+                if !matches!(result, SatResult::Sat) {
+                    continue;
+                }
+
+                let mut report = Report::ver_warning(
+                    bounds.loc.clone(),
+                    format!(
+                        "Model {} does not refine constraint {} of model {}.",
+                        m.name.name.cyan(),
+                        c.name().yellow(),
+                        parent_model.name.name.cyan()
+                    ),
+                );
+                let mut parent_constraint = Report::ver_warning(
+                    c.loc.clone(),
+                    String::from("This constraint may be violated by the instances of the model."),
+                );
+                parent_constraint.note = format!(
+                    "The constraint is not implied by the constraints of model {}.",
+                    m.name.name.cyan()
+                );
+                report.additional_info.push(parent_constraint);
+                report.note = format!(
+                    "Constraint {} of model {} is not implied by the `st` block of model {}, consider adding it there, or strengthening the constraints that imply it.",
+                    c.name().yellow(),
+                    parent_model.name.name.cyan(),
+                    m.name.name.cyan()
+                );
+                reports.push(report);
+            }
+        }
+        reports
+    }
+
+    /// Constants of the parent model's fields paired with the ones of the child model,
+    /// including the domains of the mappings.
+    fn shared_fields(
+        &self,
+        parent: usize,
+        parent_decl: &DeclarationBounds<'ctx>,
+        child_decl: &DeclarationBounds<'ctx>,
+        contract: &ContractDefinition,
+    ) -> Vec<(Dynamic<'ctx>, Dynamic<'ctx>)> {
+        let ctx = self.context;
+        let mut names = vec![];
+        for f in contract.models[parent].fields(contract) {
+            if let TypeVariant::Mapping(m) = &f.ty.ty {
+                names.push((domain_name(&f.name.name), domain_sort(m, ctx)));
+            }
+            names.push((f.name.name.clone(), type_to_sort(&f.ty.ty, ctx)));
+        }
+
+        names
+            .into_iter()
+            .filter_map(|(name, sort)| {
+                let p = parent_decl.scope.get(&name, sort.clone(), ctx)?;
+                let c = child_decl.scope.get(&name, sort, ctx)?;
+                Some((p, c))
+            })
+            .collect()
+    }
+}
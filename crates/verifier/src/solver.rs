@@ -6,22 +6,38 @@ use z3::{
     Solver,
 };
 
-use crate::ast::Constraint;
+use crate::{
+    ast::Constraint,
+    SolverConfig,
+};
+
+/// Concrete witness of the contradiction.
+///
+/// The model satisfies all constraints except the violated one.
+#[derive(Debug, Clone, Default)]
+pub struct Counterexample {
+    /// Id of the constraint violated by the model.
+    pub violated: u32,
+    /// Valuations of the constants in the model, by their ids.
+    pub values: Vec<(u32, String)>,
+}
 
 /// Verify the slice of constraints for satisfiability.
 ///
 /// # Errors
-/// - List of ids of constraints that contradict each other.
+/// - List of ids of constraints that contradict each other in the minimal unsat core, and
+///   the counterexample if one can be found.
 pub fn verify_constraints<'ctx>(
     constraints: &[&Constraint],
+    config: &SolverConfig,
     context: &'ctx Context,
-) -> Result<(), Vec<u32>> {
+) -> Result<(), (Vec<u32>, Option<Counterexample>)> {
     let binding_consts: Vec<Bool<'ctx>> = constraints
         .iter()
         .map(|c| c.sym_to_const(context))
         .collect();
 
-    let solver = Solver::new(context);
+    let solver = config.solver(context);
     for c in constraints {
         solver.assert(&c.expr);
     }
@@ -29,12 +45,12 @@ pub fn verify_constraints<'ctx>(
     let res = match solver.check_assumptions(&binding_consts) {
         SatResult::Sat => Ok(()),
         SatResult::Unsat | SatResult::Unknown => {
-            let consts = solver
-                .get_unsat_core()
+            let consts: Vec<u32> = minimize_core(&solver, solver.get_unsat_core())
                 .iter()
                 .filter_map(|b| bool_const_to_id(b))
                 .collect();
-            Err(consts)
+            let model = counterexample(constraints, &consts, config, context);
+            Err((consts, model))
         }
     };
     solver.reset();
@@ -45,17 +61,19 @@ pub fn verify_constraints<'ctx>(
 ///
 /// # Errors
 /// - List of mapping from symbol of declaration to the vector of contradicting constant
-///   ids.
+///   ids, and the counterexample if one can be found.
+#[allow(clippy::type_complexity)]
 pub fn verify_constraint_blocks<'ctx>(
     constraints: &[(Constraint<'ctx>, GlobalSymbol)],
+    config: &SolverConfig,
     context: &'ctx Context,
-) -> Result<(), Vec<(u32, GlobalSymbol)>> {
+) -> Result<(), (Vec<(u32, GlobalSymbol)>, Option<Counterexample>)> {
     let binding_consts: Vec<Bool<'ctx>> = constraints
         .iter()
         .map(|c| c.0.sym_to_const(context))
         .collect();
 
-    let solver = Solver::new(context);
+    let solver = config.solver(context);
     for c in constraints {
         solver.assert(&c.0.expr);
     }
@@ -63,8 +81,7 @@ pub fn verify_constraint_blocks<'ctx>(
     let res = match solver.check_assumptions(&binding_consts) {
         SatResult::Sat => Ok(()),
         SatResult::Unsat | SatResult::Unknown => {
-            let consts: Vec<u32> = solver
-                .get_unsat_core()
+            let consts: Vec<u32> = minimize_core(&solver, solver.get_unsat_core())
                 .iter()
                 .filter_map(|b| bool_const_to_id(b))
                 .collect();
@@ -80,14 +97,87 @@ pub fn verify_constraint_blocks<'ctx>(
                 })
                 .collect();
             consts_syms.sort_by_key(|x| x.0);
-            Err(consts_syms)
+
+            let refs: Vec<&Constraint> = constraints.iter().map(|(c, _)| c).collect();
+            let model = counterexample(&refs, &consts, config, context);
+            Err((consts_syms, model))
         }
     };
     solver.reset();
     res
 }
 
+/// Shrink the unsat core, so that every constraint in it is necessary for the
+/// contradiction.
+///
+/// Z3 does not guarantee the minimal core, so the constraints are dropped one by one
+/// while the rest are still unsatisfiable.
+fn minimize_core<'ctx>(solver: &Solver<'ctx>, mut core: Vec<Bool<'ctx>>) -> Vec<Bool<'ctx>> {
+    let mut i = 0;
+    while i < core.len() {
+        let mut rest = core.clone();
+        rest.remove(i);
+        if matches!(solver.check_assumptions(&rest), SatResult::Unsat) {
+            core = rest;
+        } else {
+            i += 1;
+        }
+    }
+    core
+}
+
+/// Find the model that satisfies all constraints but the last one in the unsat core.
+///
+/// It shows the concrete values under which the rest of the constraints hold
+/// while the violated one does not.
+fn counterexample<'ctx>(
+    constraints: &[&Constraint],
+    core: &[u32],
+    config: &SolverConfig,
+    context: &'ctx Context,
+) -> Option<Counterexample> {
+    let violated = *core.iter().max()?;
+    let binding_syms: Vec<u32> = constraints.iter().map(|c| c.binding_sym).collect();
+    let binding_consts: Vec<Bool<'ctx>> = constraints
+        .iter()
+        .filter(|c| c.binding_sym != violated)
+        .map(|c| c.sym_to_const(context))
+        .collect();
+
+    let solver = config.solver(context);
+    for c in constraints {
+        solver.assert(&c.expr);
+    }
+
+    let mut res = None;
+    if let SatResult::Sat = solver.check_assumptions(&binding_consts) {
+        if let Some(model) = solver.get_model() {
+            let mut values: Vec<(u32, String)> = model
+                .iter()
+                .filter(|d| d.arity() == 0)
+                .filter_map(|d| {
+                    let id = name_to_id(&d.name())?;
+                    // binding constants are not a part of the user's code.
+                    if binding_syms.contains(&id) {
+                        return None;
+                    }
+                    let value = model.eval(&d.apply(&[]), true)?;
+                    Some((id, value.to_string()))
+                })
+                .collect();
+            values.sort_by_key(|x| x.0);
+            res = Some(Counterexample { violated, values });
+        }
+    }
+    solver.reset();
+    res
+}
+
 /// Z3 converts integer names to `k!_` format, we need to parse it back to integers.
 fn bool_const_to_id(c: &Bool) -> Option<u32> {
-    c.to_string().replace("k!", "").parse().ok()
+    name_to_id(&c.to_string())
+}
+
+fn name_to_id(name: &str) -> Option<u32> {
+    name.replace("k!", "").parse().ok()
 }
@@ -2,16 +2,19 @@ use folidity_parser::ast::Identifier;
 use folidity_semantics::{
     ast::{
         BinaryExpression,
+        BuiltinCall,
         Expression,
         TypeVariant,
         UnaryExpression,
     },
+    builtin::BuiltinFunction,
     symtable::{
         Scope,
         VariableKind,
     },
     CompilationError,
     ContractDefinition,
+    GlobalSymbol,
     Runner,
     Span,
 };
@@ -36,11 +39,15 @@ use z3::{
 use crate::{
     ast::Z3Scope,
     executor::SymbolicExecutor,
+    generate_examples,
     transformer::{
+        simplify,
         transform_expr,
         TransformParams,
     },
+    verify_incremental,
     z3_cfg,
+    SolverConfig,
 };
 
 #[test]
@@ -64,7 +71,7 @@ fn mul_transform() {
     });
 
     let context = Context::new(&z3_cfg());
-    let mut executor = SymbolicExecutor::new(&context);
+    let mut executor = SymbolicExecutor::new(&context, SolverConfig::default());
     let mut diagnostics = vec![];
     let scope = Scope::default();
     let mut z3_scope = Z3Scope::default();
@@ -87,6 +94,75 @@ fn mul_transform() {
     );
 }
 
+#[test]
+fn bool_simplify() {
+    let loc = Span { start: 0, end: 0 };
+    let boolean = |element: bool| {
+        Expression::Boolean(UnaryExpression {
+            loc: loc.clone(),
+            element,
+            ty: TypeVariant::Bool,
+        })
+    };
+    let binary = |left: Expression, right: Expression| {
+        BinaryExpression {
+            loc: loc.clone(),
+            left: Box::new(left),
+            right: Box::new(right),
+            ty: TypeVariant::Bool,
+        }
+    };
+    let var = Expression::Variable(UnaryExpression {
+        loc: loc.clone(),
+        element: 0,
+        ty: TypeVariant::Bool,
+    });
+
+    // `true && a` => `a`
+    let and = Expression::And(binary(boolean(true), var.clone()));
+    assert_eq!(simplify(&and), var);
+
+    // `a || !true` => `a`
+    let not = Expression::Not(UnaryExpression {
+        loc: loc.clone(),
+        element: Box::new(boolean(true)),
+        ty: TypeVariant::Bool,
+    });
+    let or = Expression::Or(binary(var.clone(), not));
+    assert_eq!(simplify(&or), var);
+
+    // `a && (false == true)` => `false`
+    let eq = Expression::Equal(binary(boolean(false), boolean(true)));
+    let and = Expression::And(binary(var.clone(), eq));
+    assert_eq!(simplify(&and), boolean(false));
+
+    // `a != b` is left untouched.
+    let neq = Expression::NotEqual(binary(var.clone(), var.clone()));
+    assert_eq!(simplify(&neq), neq);
+
+    // `a || size([1]) > 0` => `true`
+    let uint = |element: u64| {
+        Expression::UInt(UnaryExpression {
+            loc: loc.clone(),
+            element: BigUint::from(element),
+            ty: TypeVariant::Uint,
+        })
+    };
+    let size = Expression::BuiltinCall(BuiltinCall {
+        loc: loc.clone(),
+        func: BuiltinFunction::Size,
+        args: vec![Expression::List(UnaryExpression {
+            loc: loc.clone(),
+            element: vec![uint(1)],
+            ty: TypeVariant::List(Box::new(TypeVariant::Uint)),
+        })],
+        returns: TypeVariant::Uint,
+    });
+    let gt = Expression::Greater(binary(size, uint(0)));
+    let or = Expression::Or(binary(var.clone(), gt));
+    assert_eq!(simplify(&or), boolean(true));
+}
+
 #[test]
 fn var_transform() {
     let loc = Span { start: 0, end: 0 };
@@ -97,7 +173,7 @@ fn var_transform() {
     });
 
     let context = Context::new(&z3_cfg());
-    let mut executor = SymbolicExecutor::new(&context);
+    let mut executor = SymbolicExecutor::new(&context, SolverConfig::default());
     let mut diagnostics = vec![];
     let mut scope = Scope::default();
     let mut contract = ContractDefinition::default();
@@ -136,7 +212,7 @@ fn string_hex_transform() {
     });
 
     let context = Context::new(&z3_cfg());
-    let mut executor = SymbolicExecutor::new(&context);
+    let mut executor = SymbolicExecutor::new(&context, SolverConfig::default());
     let mut diagnostics = vec![];
     let scope = Scope::default();
     let mut z3_scope = Z3Scope::default();
@@ -199,7 +275,7 @@ fn list_transform() {
     });
 
     let context = Context::new(&z3_cfg());
-    let mut executor = SymbolicExecutor::new(&context);
+    let mut executor = SymbolicExecutor::new(&context, SolverConfig::default());
     let mut diagnostics = vec![];
     let scope = Scope::default();
     let mut z3_scope = Z3Scope::default();
@@ -299,7 +375,7 @@ fn in_transform() {
     });
 
     let context = Context::new(&z3_cfg());
-    let mut executor = SymbolicExecutor::new(&context);
+    let mut executor = SymbolicExecutor::new(&context, SolverConfig::default());
     let mut diagnostics = vec![];
     let scope = Scope::default();
     let mut z3_scope = Z3Scope::default();
@@ -465,7 +541,7 @@ fn test_incorrect_bounds() {
         &error.message,
         "model MyModel has unsatisfiable constraints."
     );
-    assert_eq!(error.additional_info.len(), 3);
+    assert_eq!(error.additional_info.len(), 4);
     let mut errs = error.additional_info.iter();
     let e = errs.next().unwrap();
     assert!(
@@ -488,6 +564,14 @@ fn test_incorrect_bounds() {
         "{}",
         e.message
     );
+    let e = errs.next().unwrap();
+    assert!(
+        e.message.starts_with("Counterexample:")
+            && e.message.contains("d = ")
+            && e.message.ends_with("violates constraint 13."),
+        "{}",
+        e.message
+    );
 }
 
 const NOT_WORKING_LINKED: &str = r#"
@@ -539,7 +623,7 @@ fn test_incorrect_linked_bounds() {
         &error.message,
         "Detected conflicting constraints in linked blocks. These are the linked blocks: state StartState, function start"
     );
-    assert_eq!(error.additional_info.len(), 2);
+    assert_eq!(error.additional_info.len(), 3);
     let mut errs = error.additional_info.iter();
     let e = errs.next().unwrap();
     assert!(
@@ -555,4 +639,769 @@ fn test_incorrect_linked_bounds() {
         "{}",
         e.message
     );
+    let e = errs.next().unwrap();
+    assert!(
+        e.message.starts_with("Counterexample:") && e.message.ends_with("violates constraint 6."),
+        "{}",
+        e.message
+    );
+}
+
+const EXAMPLES: &str = r#"
+model Account {
+    balance: int,
+    limit: u64,
+} st [
+    balance >= 0,
+    balance <= limit,
+]
+"#;
+
+#[test]
+fn test_examples() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(EXAMPLES);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let examples = generate_examples(&contract, 2, &SolverConfig::default()).unwrap();
+    let account = &examples[0];
+    assert_eq!(account.name, "model Account");
+    assert_eq!(account.fields, vec!["balance", "limit"]);
+
+    let values = |e: &crate::Example| -> (i64, i64) {
+        (e.values[0].parse().unwrap(), e.values[1].parse().unwrap())
+    };
+    assert_eq!(account.valid.len(), 2);
+    assert_ne!(account.valid[0], account.valid[1]);
+    for e in &account.valid {
+        let (balance, limit) = values(e);
+        assert!(balance >= 0 && balance <= limit, "{:?}", e);
+    }
+
+    // each instance violates a single constraint, and fits in the range of the types.
+    assert_eq!(account.invalid.len(), 2);
+    let (balance, limit) = values(&account.invalid[0]);
+    assert!(balance < 0 && limit >= 0);
+    let (balance, limit) = values(&account.invalid[1]);
+    assert!(balance >= 0 && balance > limit && limit >= 0);
+}
+
+const LABELLED: &str = r#"
+model Range {
+    a: int,
+    b: int,
+} st [
+    "positive": a > 0,
+    b > 5,
+    b < 100,
+    "negative": a < 0,
+]
+"#;
+
+#[test]
+fn test_labelled_bounds() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(LABELLED);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    let runner = SymbolicExecutor::run(&contract);
+    let Err(CompilationError::Formal(reports)) = runner else {
+        panic!("Expected error");
+    };
+
+    // the core is minimal, so the constraints of `b` are not reported.
+    let error = reports.first().expect("contain error");
+    assert_eq!(error.additional_info.len(), 3);
+    let mut errs = error.additional_info.iter();
+    let e = errs.next().unwrap();
+    assert!(
+        e.message
+            .contains(r#"This is a constraint "positive". It contradicts ["negative"]"#),
+        "{}",
+        e.message
+    );
+    let e = errs.next().unwrap();
+    assert!(
+        e.message
+            .contains(r#"This is a constraint "negative". It contradicts ["positive"]"#),
+        "{}",
+        e.message
+    );
+    let e = errs.next().unwrap();
+    assert!(
+        e.message.ends_with(r#"violates constraint "negative"."#),
+        "{}",
+        e.message
+    );
+}
+
+const INDEPENDENT_BLOCKS: &str = r#"
+model First {
+    a: int,
+} st [
+    a > 10,
+    a < 5,
+]
+
+model Second {
+    b: int,
+} st [
+    b > 0,
+]
+
+model Third {
+    c: int,
+} st [
+    c == 1,
+    c == 2,
+]
+"#;
+
+#[test]
+fn test_independent_blocks() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(INDEPENDENT_BLOCKS);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    // blocks are verified in parallel, but reported in the declaration order.
+    let runner = SymbolicExecutor::run(&contract);
+    let Err(CompilationError::Formal(reports)) = runner else {
+        panic!("Expected error");
+    };
+    let messages: Vec<&str> = reports.iter().map(|r| r.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "model First has unsatisfiable constraints.",
+            "model Third has unsatisfiable constraints."
+        ]
+    );
+}
+
+const QUANTIFIED: &str = r#"
+model Ballot {
+    choices: set<int>,
+    votes: list<int>,
+} st [
+    all(choices, |c| c > 0),
+    all(votes, |v| contains(choices, v)),
+    exists(votes, |v| v < 0),
+]
+"#;
+
+#[test]
+fn test_quantified_bounds() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(QUANTIFIED);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    // every vote is a positive choice, so none of them is negative.
+    let runner = SymbolicExecutor::run(&contract);
+    let Err(CompilationError::Formal(reports)) = runner else {
+        panic!("Expected error");
+    };
+    assert_eq!(reports.len(), 1);
+    assert_eq!(
+        reports[0].message,
+        "model Ballot has unsatisfiable constraints."
+    );
+    assert_eq!(reports[0].additional_info.len(), 3);
+}
+
+const MAPPINGS: &str = r#"
+model Registry {
+    ids: mapping<int >-> int>,
+    a: int,
+    b: int,
+} st [
+    a != b,
+    get(ids, a) == get(ids, b),
+]
+
+model Ledger {
+    balances: mapping<int -/> int>,
+    owner: int,
+} st [
+    contains(balances, owner),
+    get(balances, owner) > 0,
+]
+"#;
+
+#[test]
+fn test_mapping_relations() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(MAPPINGS);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    // distinct keys of the injective mapping have distinct values,
+    // and the keys of the partial mapping are constrained by `contains`.
+    let runner = SymbolicExecutor::run(&contract);
+    let Err(CompilationError::Formal(reports)) = runner else {
+        panic!("Expected error");
+    };
+    assert_eq!(reports.len(), 1);
+    assert_eq!(
+        reports[0].message,
+        "model Registry has unsatisfiable constraints."
+    );
+    assert!(reports[0]
+        .additional_info
+        .iter()
+        .any(|r| r.message.contains(r#""ids is injective""#)));
+}
+
+const TRANSITIONS: &str = r#"
+state Counter {
+    count: int,
+} st [
+    "bounded": count < 1000,
+]
+
+@init
+@(any)
+fn () init() when () -> Counter {
+    move Counter : { 0 };
+}
+
+@(any)
+fn () incr(value: int) when (Counter s) -> Counter
+st [
+    value > 0,
+] {
+    let count = s.count + value;
+    move Counter : { count };
+}
+
+@(any)
+fn () reset(value: int) when (Counter s) -> Counter {
+    if value < 1000 {
+        move Counter : { value };
+    } else {
+        move Counter : { 0 };
+    }
+}
+"#;
+
+#[test]
+fn test_function_bodies() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(TRANSITIONS);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    // only the increment can overflow the bound, the reset is guarded by the condition.
+    let res = verify_incremental(&contract, &SolverConfig::default(), |_| false);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let verification = res.unwrap();
+    assert_eq!(verification.warnings.len(), 1);
+    let warning = &verification.warnings[0];
+    assert_eq!(
+        warning.message,
+        r#"Constraint "bounded" of state Counter is violated after the transition for some inputs."#
+    );
+    assert!(TRANSITIONS[warning.loc.clone()].contains("{ count }"));
+    // the bound of the other transitions does not need to be asserted at runtime.
+    assert_eq!(verification.proven.len(), 3);
+    assert!(!verification
+        .proven
+        .iter()
+        .any(|(init, _)| TRANSITIONS[init.clone()].contains("{ count }")));
+    // the component of the function is not cached.
+    assert!(!verification.components.iter().any(|c| {
+        c.iter()
+            .any(|s| matches!(s, GlobalSymbol::Function(f) if f.i == 1))
+    }));
+}
+
+const REACHABILITY: &str = r#"
+state Open
+
+state Frozen
+
+@final
+state Closed
+
+state Paused
+
+state Locked
+
+@init
+@(any)
+fn () init() when () -> Open {
+    move Open : {};
+}
+
+@(any)
+fn () freeze() when (Open s) -> Frozen {
+    move Frozen : {};
+}
+
+@(any)
+fn () close() when (Open s) -> Closed {
+    move Closed : {};
+}
+
+@(any)
+fn () lock() when (Paused s) -> Locked {
+    move Locked : {};
+}
+
+@(any)
+fn () pause() when (Locked s) -> Paused {
+    move Paused : {};
+}
+"#;
+
+#[test]
+fn test_state_reachability() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(REACHABILITY);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+    assert!(contract.states[2].is_final);
+
+    // the closed state is final, the paused and locked states only transition to each other.
+    let res = verify_incremental(&contract, &SolverConfig::default(), |_| false);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let warnings = res.unwrap().warnings;
+    let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "State Frozen is a dead end, the contract can not transition out of it.",
+            "State Paused is unreachable from the initial state.",
+            "State Locked is unreachable from the initial state.",
+        ]
+    );
+}
+
+const REFINEMENT: &str = r#"
+model Account {
+    balance: int,
+    limit: int,
+} st [
+    "solvent": balance >= 0,
+    limit < 1000,
+]
+
+model Savings: Account {
+    rate: int,
+} st [
+    balance > 10,
+    limit < 100,
+    rate > 0,
+]
+
+model Credit: Account {
+    debt: int,
+} st [
+    balance > debt,
+    limit < 500,
+]
+"#;
+
+#[test]
+fn test_model_refinement() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(REFINEMENT);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    // the balance of `Credit` is not bounded below, as the debt can be negative.
+    let res = verify_incremental(&contract, &SolverConfig::default(), |_| false);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let warnings = res.unwrap().warnings;
+    let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![r#"Model Credit does not refine constraint "solvent" of model Account."#]
+    );
+    assert_eq!(warnings[0].additional_info.len(), 1);
+}
+
+const LOOP_INVARIANTS: &str = r#"
+@(any)
+fn int sum(n: int)
+st [
+    n > 0,
+] {
+    let mut total = 0;
+    for (let mut i = 0; i < n; i + 1) invariant [
+        "bounded": i <= n,
+        "positive": total >= 0,
+    ] {
+        total = total + i;
+    }
+    return total;
+}
+
+@(any)
+fn int overflow(n: int)
+st [
+    n > 0,
+] {
+    let mut total = 0;
+    for (let mut i = 0; i < n; i + 1) invariant [
+        "small": total < 10,
+    ] {
+        total = total + i;
+    }
+    return total;
+}
+"#;
+
+#[test]
+fn test_loop_invariants() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(LOOP_INVARIANTS);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    // the total of the second loop grows past the bound of the invariant.
+    let res = verify_incremental(&contract, &SolverConfig::default(), |_| false);
+    let Err(CompilationError::Formal(errors)) = res else {
+        panic!("Expected the verification to fail.");
+    };
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![r#"Loop invariant "small" is not preserved by the iteration."#]
+    );
+    assert_eq!(&LOOP_INVARIANTS[errors[0].loc.clone()], "total < 10");
+}
+
+const RECURSION: &str = r#"
+@(any)
+fn int countdown(n: int)
+st [
+    n >= 0,
+] {
+    if n == 0 {
+        return 0;
+    } else {
+        return countdown(n - 1);
+    }
+}
+
+@(any)
+fn int halve(n: int)
+st [
+    n > 0,
+]
+decreases n {
+    if n == 1 {
+        return 1;
+    }
+    return halve(n / 2) + 1;
+}
+
+@(any)
+fn int spin(n: int) {
+    return spin(n + 1);
+}
+
+@(any)
+fn int ping(n: int) {
+    return pong(n);
+}
+
+@(any)
+fn int pong(n: int) {
+    return ping(n);
+}
+"#;
+
+#[test]
+fn test_termination() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(RECURSION);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+    assert!(contract.functions[1].decreases.is_some());
+
+    // the countdown decreases its parameter, and the halving decreases the given measure.
+    let res = verify_incremental(&contract, &SolverConfig::default(), |_| false);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let warnings = res.unwrap().warnings;
+    let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Function `spin` may not terminate, none of its integer parameters decreases in every recursive call.",
+            "Function `ping` is mutually recursive with `pong`, its termination can not be checked.",
+            "Function `pong` is mutually recursive with `ping`, its termination can not be checked.",
+        ]
+    );
+}
+
+const OVERFLOW: &str = r#"
+@(any)
+fn unit total(price: unit, count: unit)
+st [
+    price < 1000,
+    count < 1000,
+] {
+    return price * count;
+}
+
+@(any)
+fn unit product(a: unit, b: unit) {
+    return a * b;
+}
+
+@(any)
+fn unit distance(a: unit, b: unit) {
+    if a > b {
+        return a - b;
+    }
+    return b - a - 1;
+}
+"#;
+
+#[test]
+fn test_overflow() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(OVERFLOW);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    // the overflow is not checked by default.
+    let res = verify_incremental(&contract, &SolverConfig::default(), |_| false);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    assert!(res.unwrap().warnings.is_empty());
+
+    // the bounds of the total rule out the overflow.
+    let config = SolverConfig {
+        check_overflow: true,
+        ..Default::default()
+    };
+    let res = verify_incremental(&contract, &config, |_| false);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let warnings = res.unwrap().warnings;
+    let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Result of the operation does not fit in `uint` for some inputs.",
+            "Result of the operation does not fit in `uint` for some inputs.",
+        ]
+    );
+    assert_eq!(&OVERFLOW[warnings[0].loc.clone()], "a * b");
+    assert_eq!(&OVERFLOW[warnings[1].loc.clone()], "b - a - 1");
+}
+
+const ASSERTIONS: &str = r#"
+@(any)
+fn int withdraw(amount: int, balance: int) st [balance >= 0] {
+    require(amount > 0);
+    assert(balance - amount < balance);
+    assert(amount <= balance, s"insufficient balance");
+    return balance - amount;
+}
+"#;
+
+#[test]
+fn test_assertions() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(ASSERTIONS);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    // the first assertion follows from the required condition.
+    let res = verify_incremental(&contract, &SolverConfig::default(), |_| false);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let warnings = res.unwrap().warnings;
+    let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+    assert_eq!(messages, vec!["Assertion fails for some inputs."]);
+    assert_eq!(
+        &ASSERTIONS[warnings[0].loc.clone()],
+        "assert(amount <= balance, s\"insufficient balance\")"
+    );
+}
+
+const BOUNDS: &str = r#"
+@(any)
+fn int pick(xs: list<int>, i: unit) st [i < size(xs)] {
+    return xs[i];
+}
+
+@(any)
+fn list<int> window(xs: list<int>, lo: unit) {
+    return xs[lo..];
+}
+"#;
+
+#[test]
+fn test_bounds() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(BOUNDS);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    // the bounds are not checked by default.
+    let res = verify_incremental(&contract, &SolverConfig::default(), |_| false);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    assert!(res.unwrap().warnings.is_empty());
+
+    // the index is constrained by the bounds of the function.
+    let config = SolverConfig {
+        check_bounds: true,
+        ..Default::default()
+    };
+    let res = verify_incremental(&contract, &config, |_| false);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let warnings = res.unwrap().warnings;
+    let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec!["Slice is out of bounds of the list for some inputs."]
+    );
+    assert_eq!(&BOUNDS[warnings[0].loc.clone()], "xs[lo..]");
+}
+
+const UPDATES: &str = r#"
+@(any)
+fn list<int> enqueue(xs: list<int>, x: int) {
+    let q = xs :> push(x);
+    assert(contains(q, x));
+    assert(contains(q :> remove(x), x));
+    return q :> pop();
+}
+
+@(any)
+fn set<int> merge(a: set<int>, b: set<int>, x: int) st [x in b] {
+    let s = a :> union(b) :> difference([0]);
+    assert(x == 0 || x in s);
+    return s;
+}
+"#;
+
+#[test]
+fn test_collection_updates() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(UPDATES);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    // the list with the pushed item can be popped, while the removed item is missing from it.
+    let config = SolverConfig {
+        check_bounds: true,
+        ..Default::default()
+    };
+    let res = verify_incremental(&contract, &config, |_| false);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let warnings = res.unwrap().warnings;
+    let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+    assert_eq!(messages, vec!["Assertion fails for some inputs."]);
+    assert_eq!(
+        &UPDATES[warnings[0].loc.clone()],
+        "assert(contains(q :> remove(x), x))"
+    );
+}
+
+const UNSUPPORTED_BOUNDS: &str = r#"
+model Queue {
+    items: list<int>,
+} st [
+    items[0] > 1,
+]
+"#;
+
+#[test]
+fn test_unsupported_bounds() {
+    folidity_diagnostics::disable_pretty_print();
+    let result = folidity_parser::parse(UNSUPPORTED_BOUNDS);
+    let Ok(tree) = &result else {
+        panic!("{:#?}", &result.err().unwrap());
+    };
+
+    let res = ContractDefinition::run(tree);
+    assert!(res.is_ok(), "{:#?}", res.err().unwrap());
+    let contract = res.unwrap();
+
+    // the constraint is reported rather than crashing the verifier.
+    let runner = SymbolicExecutor::run(&contract);
+    let Err(CompilationError::Formal(reports)) = runner else {
+        panic!("Expected error");
+    };
+    assert_eq!(
+        reports[0].message,
+        "Verification of indexing is unsupported in constraints."
+    );
+    assert_eq!(&UNSUPPORTED_BOUNDS[reports[0].loc.clone()], "items[0]");
 }
@@ -6,11 +6,15 @@ use folidity_diagnostics::{
 use folidity_semantics::{
     ast::{
         BinaryExpression,
+        BuiltinCall,
+        Conditional,
         Expression,
         MemberAccess,
         TypeVariant,
         UnaryExpression,
     },
+    builtin::BuiltinFunction,
+    fold_constant,
     symtable::Scope,
     ContractDefinition,
     GlobalSymbol,
@@ -19,6 +23,8 @@ use num_bigint::BigInt;
 use num_rational::BigRational;
 use z3::{
     ast::{
+        exists_const,
+        forall_const,
         Ast,
         Bool,
         Dynamic,
@@ -28,15 +34,21 @@ use z3::{
         String as Z3String,
     },
     Context,
+    FuncDecl,
     Sort,
 };
 
 use crate::{
     ast::{
+        domain_name,
+        domain_sort,
         Z3Expression,
         Z3Scope,
     },
-    executor::SymbolicExecutor,
+    executor::{
+        missing_declaration,
+        SymbolicExecutor,
+    },
     Diagnostics,
 };
 
@@ -85,18 +97,157 @@ pub fn transform_expr<'ctx>(
 
         Expression::Or(b) => or(b, params),
         Expression::And(b) => and(b, params),
+        Expression::Conditional(c) => conditional(c, params),
 
         Expression::Variable(u) => variable(u, params),
         Expression::MemberAccess(m) => member_access(m, params),
         Expression::List(u) => list(u, params),
         Expression::In(b) => in_(b, params),
 
-        Expression::FunctionCall(_) => {
-            todo!("Verification of function calls is currently unsupported.")
+        Expression::FunctionCall(f) => unsupported(&f.loc, "function calls", params),
+        // integers of all widths share the same sort.
+        Expression::BuiltinCall(c) if c.func == BuiltinFunction::Cast && c.returns.is_integer() => {
+            transform_expr(&c.args[0], params)
+        }
+        Expression::BuiltinCall(c)
+            if c.func == BuiltinFunction::Contains
+                && matches!(c.args[0].ty(), TypeVariant::List(_) | TypeVariant::Set(_)) =>
+        {
+            contains(c, params)
+        }
+        Expression::BuiltinCall(c)
+            if matches!(c.func, BuiltinFunction::Get | BuiltinFunction::Contains)
+                && matches!(c.args[0].ty(), TypeVariant::Mapping(_)) =>
+        {
+            mapping_call(c, params)
+        }
+        Expression::BuiltinCall(c)
+            if matches!(c.func, BuiltinFunction::All | BuiltinFunction::Exists) =>
+        {
+            quantifier(c, params)
+        }
+        Expression::BuiltinCall(c)
+            if matches!(c.func, BuiltinFunction::Pow | BuiltinFunction::Sqrt) =>
+        {
+            math(c, params)
         }
-        Expression::StructInit(_) => {
-            todo!("Verification of struct initialisation is currently unsupported.")
+        Expression::BuiltinCall(c) if c.func == BuiltinFunction::Size => size(c, params),
+        Expression::BuiltinCall(c)
+            if matches!(
+                c.func,
+                BuiltinFunction::Add
+                    | BuiltinFunction::Push
+                    | BuiltinFunction::Insert
+                    | BuiltinFunction::Remove
+                    | BuiltinFunction::Union
+                    | BuiltinFunction::Difference
+            ) && matches!(c.args[0].ty(), TypeVariant::List(_) | TypeVariant::Set(_)) =>
+        {
+            collection_update(c, params)
         }
+        Expression::BuiltinCall(c) => {
+            unsupported(&c.loc, &format!("calls of `{}`", c.func), params)
+        }
+        Expression::InterfaceCall(c) => unsupported(&c.loc, "interface calls", params),
+        Expression::StructInit(s) => unsupported(&s.loc, "struct initialisation", params),
+        Expression::VariantInit(v) => unsupported(&v.loc, "enum payloads", params),
+        Expression::Optional(u) => unsupported(&u.loc, "options", params),
+        Expression::Tuple(u) => unsupported(&u.loc, "tuples", params),
+        Expression::Index(i) => unsupported(&i.loc, "indexing", params),
+        Expression::Slice(s) => unsupported(&s.loc, "slicing", params),
+        Expression::Lambda(l) => unsupported(&l.loc, "lambdas", params),
+    }
+}
+
+/// Report the expression the solver can not reason about, e.g. in the constraints.
+fn unsupported<'ctx>(
+    loc: &Span,
+    what: &str,
+    params: &mut TransformParams<'ctx, '_>,
+) -> Result<Z3Expression<'ctx>, ()> {
+    params.diagnostics.push(Report::ver_error(
+        loc.clone(),
+        format!("Verification of {} is unsupported in constraints.", what),
+    ));
+    Err(())
+}
+
+/// Pre-evaluate constant boolean sub-expressions.
+///
+/// Literal operands of logical operators are short-circuited,
+/// so that only the parts depending on variables are encoded for the solver.
+/// The rest of the constant sub-expressions, e.g. sizes of the list literals,
+/// are evaluated with the constant evaluator of the semantic stage.
+pub fn simplify(expr: &Expression) -> Expression {
+    match expr {
+        Expression::And(b) => {
+            let left = simplify(&b.left);
+            let right = simplify(&b.right);
+            match (bool_literal(&left), bool_literal(&right)) {
+                (Some(false), _) | (_, Some(false)) => bool_expr(false, &b.loc),
+                (Some(true), _) => right,
+                (_, Some(true)) => left,
+                _ => Expression::And(rebuild(b, left, right)),
+            }
+        }
+        Expression::Or(b) => {
+            let left = simplify(&b.left);
+            let right = simplify(&b.right);
+            match (bool_literal(&left), bool_literal(&right)) {
+                (Some(true), _) | (_, Some(true)) => bool_expr(true, &b.loc),
+                (Some(false), _) => right,
+                (_, Some(false)) => left,
+                _ => Expression::Or(rebuild(b, left, right)),
+            }
+        }
+        Expression::Not(u) => {
+            let element = simplify(&u.element);
+            match bool_literal(&element) {
+                Some(v) => bool_expr(!v, &u.loc),
+                None => {
+                    Expression::Not(UnaryExpression {
+                        loc: u.loc.clone(),
+                        element: Box::new(element),
+                        ty: u.ty.clone(),
+                    })
+                }
+            }
+        }
+        Expression::Equal(b) | Expression::NotEqual(b) => {
+            let left = simplify(&b.left);
+            let right = simplify(&b.right);
+            let is_eq = matches!(expr, Expression::Equal(_));
+            match (bool_literal(&left), bool_literal(&right)) {
+                (Some(l), Some(r)) => bool_expr((l == r) == is_eq, &b.loc),
+                _ if is_eq => Expression::Equal(rebuild(b, left, right)),
+                _ => Expression::NotEqual(rebuild(b, left, right)),
+            }
+        }
+        _ => fold_constant(expr).unwrap_or_else(|| expr.clone()),
+    }
+}
+
+fn bool_literal(expr: &Expression) -> Option<bool> {
+    match expr {
+        Expression::Boolean(u) => Some(u.element),
+        _ => None,
+    }
+}
+
+fn bool_expr(value: bool, loc: &Span) -> Expression {
+    Expression::Boolean(UnaryExpression {
+        loc: loc.clone(),
+        element: value,
+        ty: TypeVariant::Bool,
+    })
+}
+
+fn rebuild(b: &BinaryExpression, left: Expression, right: Expression) -> BinaryExpression {
+    BinaryExpression {
+        loc: b.loc.clone(),
+        left: Box::new(left),
+        right: Box::new(right),
+        ty: b.ty.clone(),
     }
 }
 
@@ -118,11 +269,273 @@ fn in_<'ctx>(
     Ok(Z3Expression::new(&b.loc, &assertion))
 }
 
+/// `contains(c, x)` on lists and sets is the same as `x in c`.
+fn contains<'ctx>(
+    c: &BuiltinCall,
+    params: &mut TransformParams<'ctx, '_>,
+) -> Result<Z3Expression<'ctx>, ()> {
+    let set = transform_expr(&c.args[0], params)?;
+    let set = to_z3_set(&set, params.diagnostics)?;
+    let item = transform_expr(&c.args[1], params)?;
+
+    Ok(Z3Expression::new(&c.loc, &set.member(&item.element)))
+}
+
+/// `get(m, k)` selects the value of the key in the array of the mapping,
+/// `contains(m, k)` checks the key is a member of the mapping's domain.
+fn mapping_call<'ctx>(
+    c: &BuiltinCall,
+    params: &mut TransformParams<'ctx, '_>,
+) -> Result<Z3Expression<'ctx>, ()> {
+    let key = transform_expr(&c.args[1], params)?;
+    if c.func == BuiltinFunction::Contains {
+        let domain = mapping_domain(&c.args[0], params)?;
+        return Ok(Z3Expression::new(&c.loc, &domain.member(&key.element)));
+    }
+
+    let mapping = transform_expr(&c.args[0], params)?;
+    let array = mapping.element.as_array().ok_or_else(|| {
+        params.diagnostics.push(Report::ver_error(
+            mapping.loc.clone(),
+            String::from("Value can not be converted to array."),
+        ));
+    })?;
+    Ok(Z3Expression::new(&c.loc, &array.select(&key.element)))
+}
+
+/// Set of the keys present in the mapping stored in the variable or the field.
+///
+/// The domain is named after the constant of the mapping, see [`member_access`].
+fn mapping_domain<'ctx>(
+    e: &Expression,
+    params: &mut TransformParams<'ctx, '_>,
+) -> Result<Set<'ctx>, ()> {
+    let TypeVariant::Mapping(m) = e.ty() else {
+        params.diagnostics.push(Report::internal_error(
+            e.loc().clone(),
+            String::from("Expression is not a mapping."),
+        ));
+        return Err(());
+    };
+    let sort = domain_sort(m, params.ctx);
+
+    // fields of the state are declared in the scope of its declaration.
+    let state_field = match e {
+        Expression::MemberAccess(access) => {
+            match access.expr.as_ref() {
+                Expression::Variable(UnaryExpression {
+                    ty: TypeVariant::State(s),
+                    ..
+                }) => Some((s, access.member.0)),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    let domain = if let Some((s, member)) = state_field {
+        let sym = GlobalSymbol::State(s.clone());
+        let Some(decl) = params.executor.declarations.get(&sym) else {
+            params
+                .diagnostics
+                .push(missing_declaration(&sym, e.loc(), params.contract));
+            return Err(());
+        };
+        let members = params.contract.states[s.i].fields(params.contract);
+        decl.scope
+            .get(&domain_name(&members[member].name.name), sort, params.ctx)
+    } else {
+        access_path(e, params).map(|name| {
+            params
+                .z3_scope
+                .create_or_get(&domain_name(&name), sort, params.ctx, params.executor)
+        })
+    };
+
+    domain.and_then(|d| d.as_set()).ok_or_else(|| {
+        params.diagnostics.push(Report::ver_error(
+            e.loc().clone(),
+            String::from("Keys can only be checked in mappings of variables and fields."),
+        ));
+    })
+}
+
+/// `size(l)` of the list literal is the number of its elements.
+///
+/// Lists are encoded as the sets of their elements, so the sizes of other lists
+/// are the values of the uninterpreted `size` function of their sets.
+fn size<'ctx>(
+    c: &BuiltinCall,
+    params: &mut TransformParams<'ctx, '_>,
+) -> Result<Z3Expression<'ctx>, ()> {
+    if let Expression::List(u) = &c.args[0] {
+        let len = Int::from_u64(params.ctx, u.element.len() as u64);
+        return Ok(Z3Expression::new(&c.loc, &len));
+    }
+    let list = transform_expr(&c.args[0], params)?;
+    let sort = type_to_sort(c.args[0].ty(), params.ctx);
+    let size = FuncDecl::new(params.ctx, "size", &[&sort], &Sort::int(params.ctx));
+    Ok(Z3Expression::new(&c.loc, &size.apply(&[&list.element])))
+}
+
+/// Updates of the lists and sets are the operations on their sets of elements,
+/// e.g. `push(l, x)` adds `x` to the set of `l` wherever it is inserted,
+/// and `remove(l, x)` removes every occurrence of `x`.
+fn collection_update<'ctx>(
+    c: &BuiltinCall,
+    params: &mut TransformParams<'ctx, '_>,
+) -> Result<Z3Expression<'ctx>, ()> {
+    let set = transform_expr(&c.args[0], params)?;
+    let set = to_z3_set(&set, params.diagnostics)?;
+    let arg = transform_expr(c.args.last().expect("updates have arguments"), params)?;
+    let updated = match c.func {
+        BuiltinFunction::Add | BuiltinFunction::Push | BuiltinFunction::Insert => {
+            set.add(&arg.element)
+        }
+        BuiltinFunction::Remove => set.del(&arg.element),
+        BuiltinFunction::Union | BuiltinFunction::Difference => {
+            let other = to_z3_set(&arg, params.diagnostics)?;
+            if c.func == BuiltinFunction::Union {
+                Set::set_union(params.ctx, &[&set, &other])
+            } else {
+                set.difference(&other)
+            }
+        }
+        _ => unreachable!(),
+    };
+    Ok(Z3Expression::new(&c.loc, &updated))
+}
+
+/// Largest literal exponent of `pow` expanded into the product.
+const MAX_EXPANDED_EXPONENT: u32 = 8;
+
+/// `pow(x, n)` with a small literal exponent is expanded into the product of `x`,
+/// other powers and the square roots are encoded with the exponentiation of the solver.
+///
+/// The roots of the integers are rounded down to integers.
+fn math<'ctx>(
+    c: &BuiltinCall,
+    params: &mut TransformParams<'ctx, '_>,
+) -> Result<Z3Expression<'ctx>, ()> {
+    let ctx = params.ctx;
+    let value = transform_expr(&c.args[0], params)?;
+    let is_float = c.returns == TypeVariant::Float;
+    let res = match (c.func, c.args.get(1)) {
+        (BuiltinFunction::Pow, Some(Expression::UInt(u)))
+            if u.element <= MAX_EXPANDED_EXPONENT.into() =>
+        {
+            let exp = u32::try_from(&u.element).expect("exponent is small");
+            if is_float {
+                let base = to_z3_real(&value, params.diagnostics)?;
+                let factors = vec![base; exp as usize];
+                match exp {
+                    0 => Dynamic::from_ast(&Real::from_real(ctx, 1, 1)),
+                    _ => Dynamic::from_ast(&Real::mul(ctx, &factors)),
+                }
+            } else {
+                let base = to_z3_int(&value, params.diagnostics)?;
+                let factors = vec![base; exp as usize];
+                match exp {
+                    0 => Dynamic::from_ast(&Int::from_u64(ctx, 1)),
+                    _ => Dynamic::from_ast(&Int::mul(ctx, &factors)),
+                }
+            }
+        }
+        (BuiltinFunction::Pow, Some(exp)) => {
+            let exp = transform_expr(exp, params)?;
+            let exp = to_z3_int(&exp, params.diagnostics)?;
+            if is_float {
+                let base = to_z3_real(&value, params.diagnostics)?;
+                Dynamic::from_ast(&base.power(&exp.to_real()))
+            } else {
+                let base = to_z3_int(&value, params.diagnostics)?;
+                Dynamic::from_ast(&base.power(&exp))
+            }
+        }
+        _ => {
+            let half = Real::from_real(ctx, 1, 2);
+            if is_float {
+                let base = to_z3_real(&value, params.diagnostics)?;
+                Dynamic::from_ast(&base.power(&half))
+            } else {
+                let base = to_z3_int(&value, params.diagnostics)?;
+                Dynamic::from_ast(&base.to_real().power(&half).to_int())
+            }
+        }
+    };
+
+    Ok(Z3Expression::new(&c.loc, &res))
+}
+
+/// Quantify the predicate over the elements of the list or set.
+///
+/// The parameter of the lambda is bound to a fresh constant `x`,
+/// so `all(c, |x| p)` becomes `forall x. x in c => p`
+/// and `exists(c, |x| p)` becomes `exists x. x in c && p`.
+fn quantifier<'ctx>(
+    c: &BuiltinCall,
+    params: &mut TransformParams<'ctx, '_>,
+) -> Result<Z3Expression<'ctx>, ()> {
+    let set = transform_expr(&c.args[0], params)?;
+    let set = to_z3_set(&set, params.diagnostics)?;
+    let Expression::Lambda(l) = &c.args[1] else {
+        params.diagnostics.push(Report::ver_error(
+            c.args[1].loc().clone(),
+            String::from("Only lambdas can be quantified in verifier."),
+        ));
+        return Err(());
+    };
+    let (TypeVariant::List(item) | TypeVariant::Set(item)) = c.args[0].ty() else {
+        params.diagnostics.push(Report::internal_error(
+            c.args[0].loc().clone(),
+            String::from("Quantified expression is not a collection."),
+        ));
+        return Err(());
+    };
+    let Some(var) = l.params.first().and_then(|p| params.scope.vars.get(p)) else {
+        params.diagnostics.push(Report::internal_error(
+            l.loc.clone(),
+            String::from("Parameter of the lambda is not declared in the scope."),
+        ));
+        return Err(());
+    };
+    let name = var.ident.name.clone();
+
+    // the parameter shadows the constant of the same name while the body is transformed.
+    let (bound, id) = params
+        .executor
+        .create_constant(&type_to_sort(item, params.ctx));
+    let shadowed = params.z3_scope.consts.insert(name.clone(), id);
+    let body = transform_expr(&l.body, params);
+    match shadowed {
+        Some(i) => params.z3_scope.consts.insert(name, i),
+        None => params.z3_scope.consts.shift_remove(&name),
+    };
+    let body = to_z3_bool(&body?, params.diagnostics)?;
+
+    let member = set.member(&bound);
+    let assertion = if c.func == BuiltinFunction::All {
+        forall_const(params.ctx, &[&bound], &[], &member.implies(&body))
+    } else {
+        exists_const(
+            params.ctx,
+            &[&bound],
+            &[],
+            &Bool::and(params.ctx, &[&member, &body]),
+        )
+    };
+
+    Ok(Z3Expression::new(&c.loc, &assertion))
+}
+
 fn list<'ctx>(
     u: &UnaryExpression<Vec<Expression>>,
     params: &mut TransformParams<'ctx, '_>,
 ) -> Result<Z3Expression<'ctx>, ()> {
-    let mut set = Set::empty(params.ctx, &type_to_sort(&u.ty, params.ctx));
+    let (TypeVariant::List(item) | TypeVariant::Set(item)) = &u.ty else {
+        unreachable!("list literals are collections")
+    };
+    let mut set = Set::empty(params.ctx, &type_to_sort(item, params.ctx));
     for e in &u.element {
         let z3_e = transform_expr(e, params)?;
         set = set.add(&z3_e.element);
@@ -135,7 +548,8 @@ fn list<'ctx>(
 /// - If the access is for the state's member, then we lookup the constraint id in the its
 ///   local table.
 /// - Otherwise, we just construct a variable name `{name}.{member_id}` and store in the
-///   scope in case we refer to it later.
+///   scope in case we refer to it later. Chained accesses extend the name, e.g.
+///   `{name}.{member_id}.{member_id}`.
 ///
 /// # Errors
 /// - the member is access from non-variable expression.
@@ -143,40 +557,47 @@ fn member_access<'ctx>(
     e: &MemberAccess,
     params: &mut TransformParams<'ctx, '_>,
 ) -> Result<Z3Expression<'ctx>, ()> {
-    let Expression::Variable(var) = &e.expr.as_ref() else {
+    if let Expression::Variable(var) = &e.expr.as_ref() {
+        if let TypeVariant::State(s) = &var.ty {
+            let sym = GlobalSymbol::State(s.clone());
+            let Some(decl) = params.executor.declarations.get_mut(&sym) else {
+                params
+                    .diagnostics
+                    .push(missing_declaration(&sym, &e.loc, params.contract));
+                return Err(());
+            };
+
+            let state_decl = &params.contract.states[s.i];
+            let members = state_decl.fields(params.contract);
+            let member = &members[e.member.0];
+            let Some(c) = decl.scope.get(
+                &member.name.name,
+                type_to_sort(&member.ty.ty, params.ctx),
+                params.ctx,
+            ) else {
+                params.diagnostics.push(Report::internal_error(
+                    e.loc.clone(),
+                    format!(
+                        "Field `{}` of the state is not declared in the solver.",
+                        member.name.name
+                    ),
+                ));
+                return Err(());
+            };
+
+            return Ok(Z3Expression::new(&e.loc, &c));
+        }
+    }
+
+    let Some(name) = access_path(&e.expr, params) else {
         params.diagnostics.push(Report::ver_error(
             e.expr.loc().clone(),
             String::from("Non-variable access is unsupported in verifier."),
         ));
         return Err(());
     };
-
-    if let TypeVariant::State(s) = &var.ty {
-        let local_scope = &mut params
-            .executor
-            .declarations
-            .get_mut(&GlobalSymbol::State(s.clone()))
-            .expect("Should exist")
-            .scope;
-
-        let state_decl = &params.contract.states[s.i];
-        let members = state_decl.fields(params.contract);
-        let member = &members[e.member.0];
-        let c = local_scope
-            .get(
-                &member.name.name,
-                type_to_sort(&member.ty.ty, params.ctx),
-                params.ctx,
-            )
-            .expect("const should exist");
-
-        return Ok(Z3Expression::new(&e.loc, &c));
-    }
-
-    let name = &params.scope.vars[var.element].ident.name;
-    let variant = e.member.0.to_string();
     let c = params.z3_scope.create_or_get(
-        &format!("{}.{}", name, variant),
+        &format!("{}.{}", name, e.member.0),
         type_to_sort(&e.ty, params.ctx),
         params.ctx,
         params.executor,
@@ -185,11 +606,28 @@ fn member_access<'ctx>(
     Ok(Z3Expression::new(&e.loc, &c))
 }
 
+/// Name of the accessed variable followed by the ids of the accessed members.
+fn access_path(e: &Expression, params: &TransformParams) -> Option<String> {
+    match e {
+        Expression::Variable(var) => Some(params.scope.vars[var.element].ident.name.clone()),
+        Expression::MemberAccess(m) => {
+            Some(format!("{}.{}", access_path(&m.expr, params)?, m.member.0))
+        }
+        _ => None,
+    }
+}
+
 fn variable<'ctx>(
     e: &UnaryExpression<usize>,
     params: &mut TransformParams<'ctx, '_>,
 ) -> Result<Z3Expression<'ctx>, ()> {
-    let var = params.scope.vars.get(&e.element).expect("should exist");
+    let Some(var) = params.scope.vars.get(&e.element) else {
+        params.diagnostics.push(Report::internal_error(
+            e.loc.clone(),
+            String::from("Variable is not declared in the scope."),
+        ));
+        return Err(());
+    };
     let z3_const = params.z3_scope.create_or_get(
         &var.ident.name,
         type_to_sort(&e.ty, params.ctx),
@@ -201,9 +639,14 @@ fn variable<'ctx>(
 
 pub fn type_to_sort<'ctx>(ty: &TypeVariant, ctx: &'ctx Context) -> Sort<'ctx> {
     match ty {
-        TypeVariant::Int | TypeVariant::Uint | TypeVariant::Char | TypeVariant::Enum(_) => {
-            Sort::int(ctx)
-        }
+        TypeVariant::Int
+        | TypeVariant::Uint
+        | TypeVariant::U8
+        | TypeVariant::U64
+        | TypeVariant::I64
+        | TypeVariant::U256
+        | TypeVariant::Char
+        | TypeVariant::Enum(_) => Sort::int(ctx),
         TypeVariant::Float => Sort::real(ctx),
         TypeVariant::Address | TypeVariant::Hex | TypeVariant::String => Sort::string(ctx),
         TypeVariant::Bool => Sort::bool(ctx),
@@ -213,6 +656,16 @@ pub fn type_to_sort<'ctx>(ty: &TypeVariant, ctx: &'ctx Context) -> Sort<'ctx> {
         TypeVariant::Struct(s) => Sort::uninterpreted(ctx, format!("SS!{}", s.i).into()),
         TypeVariant::Set(s_ty) => Sort::set(ctx, &type_to_sort(s_ty, ctx)),
         TypeVariant::List(l_ty) => Sort::set(ctx, &type_to_sort(l_ty, ctx)),
+        TypeVariant::Option(o_ty) => {
+            Sort::uninterpreted(ctx, format!("O!{}", type_to_sort(o_ty, ctx)).into())
+        }
+        TypeVariant::Tuple(tys) => {
+            let sorts: Vec<String> = tys
+                .iter()
+                .map(|ty| type_to_sort(ty, ctx).to_string())
+                .collect();
+            Sort::uninterpreted(ctx, format!("T!({})", sorts.join(",")).into())
+        }
         TypeVariant::Mapping(m) => {
             Sort::array(
                 ctx,
@@ -377,6 +830,19 @@ fn not<'ctx>(
     Ok(Z3Expression::new(&u.loc, &bool_v))
 }
 
+fn conditional<'ctx>(
+    c: &Conditional,
+    params: &mut TransformParams<'ctx, '_>,
+) -> Result<Z3Expression<'ctx>, ()> {
+    let cond = transform_expr(&c.condition, params)?;
+    let then_v = transform_expr(&c.then_expr, params)?;
+    let else_v = transform_expr(&c.else_expr, params)?;
+
+    let cond = to_z3_bool(&cond, params.diagnostics)?;
+    let res = cond.ite(&then_v.element, &else_v.element);
+    Ok(Z3Expression::new(&c.loc, &res))
+}
+
 fn or<'ctx>(
     b: &BinaryExpression,
     params: &mut TransformParams<'ctx, '_>,
@@ -534,6 +1000,18 @@ fn to_z3_string<'ctx>(
     })
 }
 
+fn to_z3_set<'ctx>(
+    expr: &Z3Expression<'ctx>,
+    diagnostics: &mut Diagnostics,
+) -> Result<Set<'ctx>, ()> {
+    expr.element.as_set().ok_or_else(|| {
+        diagnostics.push(Report::ver_error(
+            expr.loc.clone(),
+            String::from("Value can not be converted to set."),
+        ));
+    })
+}
+
 /// Create a boolean constant and returns its id as `u32`
 pub fn create_constraint_const<'ctx>(
     ctx: &'ctx Context,
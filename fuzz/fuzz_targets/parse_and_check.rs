@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// malformed contracts are expected to be reported in the diagnostics.
+fuzz_target!(|src: &str| {
+    let _ = folidity_semantics::parse_and_check(src);
+});